@@ -0,0 +1,63 @@
+// Writes src-tauri/resources/cache_fallback.json, the cache used when neither Warframe Market
+// nor cache.json can provide one. Only what the app needs to resolve items offline is kept.
+// Runs before every tauri build, or by hand with: pnpm cache:fallback
+import { readFileSync, writeFileSync } from "fs";
+import { dirname, join } from "path";
+import { fileURLToPath } from "url";
+
+const FALLBACK_PATH = join(dirname(fileURLToPath(import.meta.url)), "..", "src-tauri", "resources", "cache_fallback.json");
+const WFM_API = "https://api.warframe.market/v1";
+const ITEM_INFO = "http://relics.run/history/item_data/item_info.json";
+
+const getJson = async (url) => {
+  const response = await fetch(url, { headers: { Language: "en", Platform: "pc" } });
+  if (!response.ok) throw new Error(`${url} returned ${response.status}`);
+  return response.json();
+};
+
+const main = async () => {
+  const fallback = JSON.parse(readFileSync(FALLBACK_PATH, "utf8"));
+  const wfmItems = (await getJson(`${WFM_API}/items`)).payload.items;
+  const itemInfo = await getJson(ITEM_INFO);
+  const rivenItems = (await getJson(`${WFM_API}/riven/items`)).payload.items;
+
+  // Items without relics.run data are skipped by the cache refresh as well
+  fallback.item.items = wfmItems
+    .filter((item) => itemInfo[item.id])
+    .map((item) => {
+      const info = itemInfo[item.id];
+      return {
+        item_name: item.item_name,
+        id: item.id,
+        url_name: item.url_name,
+        thumb: "",
+        set_items: info.set_items ?? null,
+        tags: info.tags ?? [],
+        mod_max_rank: info.mod_max_rank ?? null,
+        subtypes: info.subtypes ?? null,
+      };
+    })
+    .sort((a, b) => a.url_name.localeCompare(b.url_name));
+
+  fallback.riven.items = rivenItems
+    .map((weapon) => ({
+      thumb: "",
+      url_name: weapon.url_name,
+      mastery_level: weapon.mastery_level ?? 0,
+      group: weapon.group ?? "",
+      id: weapon.id,
+      icon: "",
+      item_name: weapon.item_name,
+      riven_type: weapon.riven_type ?? null,
+    }))
+    .sort((a, b) => a.url_name.localeCompare(b.url_name));
+
+  fallback.last_refresh = null;
+  writeFileSync(FALLBACK_PATH, JSON.stringify(fallback, null, 2) + "\n");
+  console.log(`Wrote ${fallback.item.items.length} items and ${fallback.riven.items.length} riven weapons to ${FALLBACK_PATH}`);
+};
+
+main().catch((error) => {
+  console.error(error);
+  process.exit(1);
+});
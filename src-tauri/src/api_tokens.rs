@@ -0,0 +1,245 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use aes_gcm::aead::{rand_core::RngCore, OsRng};
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    enums::{ErrorKind, LogLevel},
+    error::AppError,
+    helper, logger,
+    rate_limiter::RateLimiter,
+};
+
+// What a token can be used for, only scopes an API surface checks are listed
+pub const SCOPE_READ_ANALYTICS: &str = "read-analytics";
+const SCOPES: [&str; 1] = [SCOPE_READ_ANALYTICS];
+const TOKEN_PREFIX: &str = "qf_";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ApiTokenStruct {
+    pub id: String,
+    pub name: String,
+    // Only the hash is stored, the token is shown once when it is created
+    pub token_hash: String,
+    pub scopes: Vec<String>,
+    // Requests per minute, -1 for no limit
+    pub rate_limit: i64,
+    pub created: String,
+    pub last_used: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CreatedApiTokenStruct {
+    pub token: String,
+    pub info: ApiTokenStruct,
+}
+
+// Tokens for the external integrations, each limited to its scopes and request rate
+#[derive(Clone, Debug)]
+pub struct ApiTokenClient {
+    log_file: String,
+    tokens: Arc<Mutex<Vec<ApiTokenStruct>>>,
+    // Keyed by token id, rebuilt when the rate limit of a token changes
+    limiters: Arc<Mutex<HashMap<String, RateLimiter>>>,
+}
+
+impl ApiTokenClient {
+    pub fn setup() -> Result<Self, AppError> {
+        let path = Self::get_file_path();
+        let mut tokens: Vec<ApiTokenStruct> = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .map_err(|e| AppError::new("ApiTokens", eyre!(e.to_string())))?;
+            serde_json::from_str(&content)
+                .map_err(|e| AppError::new("ApiTokens", eyre!(e.to_string())))?
+        } else {
+            vec![]
+        };
+        // Scopes that are no longer offered are dropped from older tokens
+        for token in tokens.iter_mut() {
+            token
+                .scopes
+                .retain(|scope| SCOPES.contains(&scope.as_str()));
+        }
+        Ok(ApiTokenClient {
+            log_file: "api_tokens.log".to_string(),
+            tokens: Arc::new(Mutex::new(tokens)),
+            limiters: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    fn get_file_path() -> PathBuf {
+        helper::get_app_roaming_path().join("api_tokens.json")
+    }
+
+    fn save(&self, tokens: &Vec<ApiTokenStruct>) -> Result<(), AppError> {
+        let json = serde_json::to_string_pretty(tokens)
+            .map_err(|e| AppError::new("ApiTokens", eyre!(e.to_string())))?;
+        fs::write(Self::get_file_path(), json)
+            .map_err(|e| AppError::new("ApiTokens", eyre!(e.to_string())))
+    }
+
+    fn hash(token: &str) -> String {
+        Sha256::digest(token.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    // Compares every byte so the time taken does not tell how much of a hash matched
+    fn hash_eq(a: &str, b: &str) -> bool {
+        a.len() == b.len()
+            && a.bytes()
+                .zip(b.bytes())
+                .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+                == 0
+    }
+
+    fn random_hex(bytes: usize) -> String {
+        let mut buffer = vec![0u8; bytes];
+        OsRng.fill_bytes(&mut buffer);
+        buffer.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn validate_scopes(scopes: &Vec<String>) -> Result<(), AppError> {
+        match scopes
+            .iter()
+            .find(|scope| !SCOPES.contains(&scope.as_str()))
+        {
+            Some(scope) => Err(AppError::new_with_level(
+                "ApiTokens",
+                eyre!("Unknown scope {}", scope),
+                LogLevel::Warning,
+            )
+            .with_kind(ErrorKind::Validation)),
+            None => Ok(()),
+        }
+    }
+
+    fn not_found(id: &str) -> AppError {
+        AppError::new_with_level(
+            "ApiTokens",
+            eyre!("API token {} not found", id),
+            LogLevel::Warning,
+        )
+        .with_kind(ErrorKind::NotFound)
+    }
+
+    pub fn get_tokens(&self) -> Result<Vec<ApiTokenStruct>, AppError> {
+        Ok(self.tokens.lock()?.clone())
+    }
+
+    pub fn create(
+        &self,
+        name: &str,
+        scopes: Vec<String>,
+        rate_limit: i64,
+    ) -> Result<CreatedApiTokenStruct, AppError> {
+        Self::validate_scopes(&scopes)?;
+        let token = format!("{}{}", TOKEN_PREFIX, Self::random_hex(24));
+        let info = ApiTokenStruct {
+            id: Self::random_hex(4),
+            name: name.to_string(),
+            token_hash: Self::hash(&token),
+            scopes,
+            rate_limit,
+            created: helper::get_utc_timestamp(),
+            last_used: None,
+        };
+        let mut tokens = self.tokens.lock()?;
+        tokens.push(info.clone());
+        self.save(&tokens)?;
+        logger::info_file(
+            "ApiTokens",
+            format!(
+                "Created API token {} with scopes {:?}",
+                info.id, info.scopes
+            )
+            .as_str(),
+            Some(self.log_file.as_str()),
+        );
+        Ok(CreatedApiTokenStruct { token, info })
+    }
+
+    pub fn update(
+        &self,
+        id: &str,
+        scopes: Vec<String>,
+        rate_limit: i64,
+    ) -> Result<ApiTokenStruct, AppError> {
+        Self::validate_scopes(&scopes)?;
+        let mut tokens = self.tokens.lock()?;
+        let info = tokens
+            .iter_mut()
+            .find(|t| t.id == id)
+            .ok_or_else(|| Self::not_found(id))?;
+        info.scopes = scopes;
+        info.rate_limit = rate_limit;
+        let info = info.clone();
+        self.save(&tokens)?;
+        self.limiters.lock()?.remove(id);
+        Ok(info)
+    }
+
+    pub fn revoke(&self, id: &str) -> Result<(), AppError> {
+        let mut tokens = self.tokens.lock()?;
+        if !tokens.iter().any(|t| t.id == id) {
+            return Err(Self::not_found(id));
+        }
+        tokens.retain(|t| t.id != id);
+        self.save(&tokens)?;
+        self.limiters.lock()?.remove(id);
+        logger::info_file(
+            "ApiTokens",
+            format!("Revoked API token {}", id).as_str(),
+            Some(self.log_file.as_str()),
+        );
+        Ok(())
+    }
+
+    // Checks a request of an external integration, every API surface calls this first
+    pub fn authorize(&self, token: &str, scope: &str) -> Result<ApiTokenStruct, AppError> {
+        let hash = Self::hash(token.trim());
+        let mut tokens = self.tokens.lock()?;
+        let info = tokens
+            .iter_mut()
+            .find(|t| Self::hash_eq(&t.token_hash, &hash))
+            .ok_or_else(|| {
+                AppError::new_with_level("ApiTokens", eyre!("Invalid API token"), LogLevel::Warning)
+                    .with_kind(ErrorKind::AuthExpired)
+            })?;
+        if !info.scopes.iter().any(|s| s == scope) {
+            return Err(AppError::new_with_level(
+                "ApiTokens",
+                eyre!("API token {} does not have the {} scope", info.id, scope),
+                LogLevel::Warning,
+            )
+            .with_kind(ErrorKind::Forbidden));
+        }
+        if info.rate_limit >= 0 {
+            let mut limiters = self.limiters.lock()?;
+            let limiter = limiters.entry(info.id.clone()).or_insert_with(|| {
+                RateLimiter::new(info.rate_limit as f64, Duration::from_secs(60))
+            });
+            if !limiter.can_make_request() {
+                return Err(AppError::new_with_level(
+                    "ApiTokens",
+                    eyre!("API token {} is over its rate limit", info.id),
+                    LogLevel::Warning,
+                )
+                .with_kind(ErrorKind::RateLimited));
+            }
+        }
+        info.last_used = Some(helper::get_utc_timestamp());
+        let info = info.clone();
+        self.save(&tokens)?;
+        Ok(info)
+    }
+}
@@ -0,0 +1,419 @@
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use eyre::eyre;
+use hmac::Hmac;
+use reqwest::{Client, Method, Url};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    database::client::DBClient,
+    enums::LogLevel,
+    error::{self, AppError},
+    helper, logger,
+    settings::{BackupSettings, SettingsState},
+};
+
+// Header written in front of every encrypted backup, followed by the salt and nonce
+const BACKUP_MAGIC: &[u8; 4] = b"QFB2";
+// Backups made before the key was salted, the key is a plain hash of the passphrase
+const BACKUP_MAGIC_LEGACY: &[u8; 4] = b"QFB1";
+const BACKUP_PREFIX: &str = "quantframe_backup_";
+const BACKUP_EXTENSION: &str = ".qfb";
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+const PBKDF2_ROUNDS: u32 = 600_000;
+const DATABASE_FILE: &str = "quantframe.sqlite";
+
+#[derive(Clone, Debug)]
+pub struct BackupClient {
+    log_file: String,
+    is_running: Arc<AtomicBool>,
+    settings: Arc<Mutex<SettingsState>>,
+    db: Arc<Mutex<DBClient>>,
+    last_backup: Arc<Mutex<Option<chrono::DateTime<chrono::Utc>>>>,
+}
+
+impl BackupClient {
+    pub fn new(settings: Arc<Mutex<SettingsState>>, db: Arc<Mutex<DBClient>>) -> Self {
+        BackupClient {
+            log_file: "backup.log".to_string(),
+            is_running: Arc::new(AtomicBool::new(false)),
+            settings,
+            db,
+            last_backup: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    fn get_restore_path() -> PathBuf {
+        helper::get_app_roaming_path().join("restore")
+    }
+
+    // quantframe_backup_<%Y%m%d%H%M%S>.qfb, anything else is not one of our backups
+    fn is_backup_name(name: &str) -> bool {
+        match name
+            .strip_prefix(BACKUP_PREFIX)
+            .and_then(|rest| rest.strip_suffix(BACKUP_EXTENSION))
+        {
+            Some(timestamp) => {
+                timestamp.len() == 14 && timestamp.chars().all(|c| c.is_ascii_digit())
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.is_running.load(Ordering::SeqCst)
+    }
+
+    pub fn start_loop(&self) {
+        if self.is_running() {
+            return;
+        }
+        self.is_running.store(true, Ordering::SeqCst);
+        let client = self.clone();
+        tauri::async_runtime::spawn(async move {
+            logger::info_con("Backup", "Backup scheduler is started");
+            while client.is_running() {
+                let settings = client.settings.lock().unwrap().clone().backup;
+                let last_backup = client.last_backup.lock().unwrap().clone();
+                let is_due = match last_backup {
+                    Some(last_backup) => {
+                        chrono::Utc::now() - last_backup
+                            >= chrono::Duration::hours(settings.interval_hours.max(1))
+                    }
+                    None => true,
+                };
+                if settings.enabled && settings.url != "" && is_due {
+                    match client.backup_to_remote().await {
+                        Ok(_) => {}
+                        Err(e) => error::create_log_file(client.log_file.clone(), &e),
+                    }
+                    // Wait a full interval before trying again, even when the backup failed
+                    *client.last_backup.lock().unwrap() = Some(chrono::Utc::now());
+                }
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            }
+        });
+    }
+
+    pub fn stop_loop(&self) {
+        self.is_running.store(false, Ordering::SeqCst);
+    }
+
+    // Copies the database into a single consistent file, it is open while the app runs
+    async fn snapshot_database(&self, snapshot_path: &PathBuf) -> Result<(), AppError> {
+        if snapshot_path.exists() {
+            fs::remove_file(snapshot_path)
+                .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+        }
+        let connection = self.db.lock()?.connection.lock()?.clone();
+        sqlx::query("VACUUM INTO ?")
+            .bind(snapshot_path.to_str().unwrap_or_default())
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+        Ok(())
+    }
+
+    // Zips the app data folder and returns the bytes of the archive
+    async fn create_archive(&self) -> Result<Vec<u8>, AppError> {
+        let mut files_to_compress: Vec<helper::ZipEntry> = vec![];
+        let app_path = helper::get_app_roaming_path();
+
+        // The snapshot goes in its own folder so it keeps the database file name in the zip
+        let snapshot_dir = std::env::temp_dir().join("quantframe_backup_db");
+        fs::create_dir_all(&snapshot_dir)
+            .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+        let snapshot_path = snapshot_dir.join(DATABASE_FILE);
+        self.snapshot_database(&snapshot_path).await?;
+        files_to_compress.push(helper::ZipEntry {
+            file_path: snapshot_path.to_owned(),
+            sub_path: None,
+            include_dir: false,
+        });
+
+        for path in
+            fs::read_dir(app_path).map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?
+        {
+            let path = path
+                .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?
+                .path();
+            // The live database and its journal files are replaced by the snapshot
+            let is_database = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.starts_with(DATABASE_FILE))
+                .unwrap_or(false);
+            if path.is_file() && !is_database {
+                files_to_compress.push(helper::ZipEntry {
+                    file_path: path.to_owned(),
+                    sub_path: None,
+                    include_dir: false,
+                });
+            }
+        }
+        let zip_path = std::env::temp_dir().join("quantframe_backup.zip");
+        helper::create_zip_file(files_to_compress, zip_path.to_str().unwrap_or_default())?;
+        let data =
+            fs::read(&zip_path).map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+        fs::remove_file(&zip_path).ok();
+        fs::remove_dir_all(&snapshot_dir).ok();
+        Ok(data)
+    }
+
+    // The key is derived with PBKDF2 from the passphrase and the salt stored in the backup,
+    // without a salt the old backups use a plain hash of the passphrase
+    fn get_cipher(passphrase: &str, salt: Option<&[u8]>) -> Result<Aes256Gcm, AppError> {
+        if passphrase == "" {
+            return Err(AppError::new(
+                "Backup",
+                eyre!("A passphrase is required to encrypt backups"),
+            ));
+        }
+        let mut key = [0u8; 32];
+        match salt {
+            Some(salt) => {
+                pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut key)
+            }
+            None => key.copy_from_slice(&Sha256::digest(passphrase.as_bytes())),
+        }
+        Aes256Gcm::new_from_slice(&key).map_err(|e| AppError::new("Backup", eyre!(e.to_string())))
+    }
+
+    fn encrypt(&self, data: &[u8], passphrase: &str) -> Result<Vec<u8>, AppError> {
+        let mut salt = [0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        let cipher = Self::get_cipher(passphrase, Some(&salt))?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let encrypted = cipher
+            .encrypt(&nonce, data)
+            .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+        let mut output = BACKUP_MAGIC.to_vec();
+        output.extend_from_slice(&salt);
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&encrypted);
+        Ok(output)
+    }
+
+    fn decrypt(&self, data: &[u8], passphrase: &str) -> Result<Vec<u8>, AppError> {
+        let header = match data.get(0..4) {
+            Some(magic) if magic == BACKUP_MAGIC => 4 + SALT_LENGTH,
+            Some(magic) if magic == BACKUP_MAGIC_LEGACY => 4,
+            _ => return Err(AppError::new("Backup", eyre!("Not a valid backup file"))),
+        };
+        if data.len() < header + NONCE_LENGTH {
+            return Err(AppError::new("Backup", eyre!("Not a valid backup file")));
+        }
+        let salt = if header > 4 {
+            Some(&data[4..header])
+        } else {
+            None
+        };
+        let cipher = Self::get_cipher(passphrase, salt)?;
+        let nonce = Nonce::from_slice(&data[header..header + NONCE_LENGTH]);
+        cipher
+            .decrypt(nonce, &data[header + NONCE_LENGTH..])
+            .map_err(|_| {
+                AppError::new(
+                    "Backup",
+                    eyre!("Could not decrypt backup, is the passphrase correct?"),
+                )
+            })
+    }
+
+    fn get_remote_url(settings: &BackupSettings, name: &str) -> Result<Url, AppError> {
+        let mut base = settings.url.clone();
+        if !base.ends_with("/") {
+            base.push('/');
+        }
+        Url::parse(&base)
+            .and_then(|url| url.join(name))
+            .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        name: &str,
+        body: Option<Vec<u8>>,
+    ) -> Result<reqwest::Response, AppError> {
+        let settings = self.settings.lock()?.clone().backup;
+        let url = Self::get_remote_url(&settings, name)?;
+        let mut request = Client::new()
+            .request(method.clone(), url)
+            .basic_auth(settings.username, Some(settings.password));
+        if method.as_str() == "PROPFIND" {
+            request = request.header("Depth", "1");
+        }
+        if let Some(body) = body {
+            request = request.body(body);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+        if !response.status().is_success() {
+            return Err(AppError::new(
+                "Backup",
+                eyre!(
+                    "Remote returned {} for {} {}",
+                    response.status(),
+                    method,
+                    name
+                ),
+            ));
+        }
+        Ok(response)
+    }
+
+    pub async fn backup_to_remote(&self) -> Result<String, AppError> {
+        let settings = self.settings.lock()?.clone().backup;
+        let name = format!(
+            "{}{}{}",
+            BACKUP_PREFIX,
+            chrono::Utc::now().format("%Y%m%d%H%M%S"),
+            BACKUP_EXTENSION
+        );
+        logger::info_con("Backup", format!("Uploading backup {}", name).as_str());
+        let archive = self.create_archive().await?;
+        let encrypted = self.encrypt(&archive, &settings.passphrase)?;
+        self.send(Method::PUT, &name, Some(encrypted)).await?;
+        *self.last_backup.lock()? = Some(chrono::Utc::now());
+        self.rotate().await?;
+        helper::send_message_to_window(
+            "Backup:Completed",
+            Some(serde_json::json!({ "name": name })),
+        );
+        Ok(name)
+    }
+
+    // Names of all backups on the remote, oldest first
+    pub async fn list_remote(&self) -> Result<Vec<String>, AppError> {
+        let response = self
+            .send(Method::from_bytes(b"PROPFIND").unwrap(), "", None)
+            .await?;
+        let body = response
+            .text()
+            .await
+            .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+        let re = regex::Regex::new(r"<(?:\w+:)?href>([^<]+)</(?:\w+:)?href>").unwrap();
+        let mut names: Vec<String> = re
+            .captures_iter(&body)
+            .filter_map(|c| c.get(1))
+            .filter_map(|href| href.as_str().trim_end_matches('/').rsplit('/').next())
+            .filter(|name| Self::is_backup_name(name))
+            .map(|name| name.to_string())
+            .collect();
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+
+    // Delete the oldest backups so only the configured amount is kept
+    async fn rotate(&self) -> Result<(), AppError> {
+        let retention = self.settings.lock()?.clone().backup.retention;
+        if retention <= 0 {
+            return Ok(());
+        }
+        let names = self.list_remote().await?;
+        let to_delete = names.len().saturating_sub(retention as usize);
+        for name in names.iter().take(to_delete) {
+            logger::info_con("Backup", format!("Deleting old backup {}", name).as_str());
+            self.send(Method::DELETE, name, None).await?;
+        }
+        Ok(())
+    }
+
+    // Downloads a backup and stages it, it is applied the next time the app starts
+    pub async fn restore_from_remote(&self, name: &str) -> Result<(), AppError> {
+        if !Self::is_backup_name(name) {
+            return Err(AppError::new_with_level(
+                "Backup",
+                eyre!("{} is not a backup name", name),
+                LogLevel::Warning,
+            ));
+        }
+        let settings = self.settings.lock()?.clone().backup;
+        let response = self.send(Method::GET, name, None).await?;
+        let data = response
+            .bytes()
+            .await
+            .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+        let archive = self.decrypt(&data, &settings.passphrase)?;
+
+        let zip_path = std::env::temp_dir().join("quantframe_restore.zip");
+        let mut file =
+            File::create(&zip_path).map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+        file.write_all(&archive)
+            .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+
+        let restore_path = Self::get_restore_path();
+        if restore_path.exists() {
+            fs::remove_dir_all(&restore_path)
+                .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+        }
+        let file =
+            File::open(&zip_path).map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+        let mut zip = zip::ZipArchive::new(file)
+            .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+        zip.extract(&restore_path)
+            .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+        fs::remove_file(&zip_path).ok();
+        logger::info_con(
+            "Backup",
+            format!("Backup {} is staged and will be restored on restart", name).as_str(),
+        );
+        Ok(())
+    }
+
+    // Moves a staged restore into place, must run before the database and settings are loaded
+    pub fn apply_pending_restore() -> Result<(), AppError> {
+        let restore_path = Self::get_restore_path();
+        if !restore_path.exists() {
+            return Ok(());
+        }
+        let app_path = helper::get_app_roaming_path();
+        for entry in fs::read_dir(&restore_path)
+            .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?
+        {
+            let path = entry
+                .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?
+                .path();
+            if let Some(file_name) = path.file_name() {
+                // The old database's WAL would be replayed over the restored one
+                for suffix in ["-wal", "-shm"] {
+                    let mut stale = file_name.to_os_string();
+                    stale.push(suffix);
+                    let stale = app_path.join(stale);
+                    if stale.exists() {
+                        fs::remove_file(&stale)
+                            .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+                    }
+                }
+                let mut content = vec![];
+                File::open(&path)
+                    .and_then(|mut f| f.read_to_end(&mut content))
+                    .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+                fs::write(app_path.join(file_name), content)
+                    .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+            }
+        }
+        fs::remove_dir_all(&restore_path)
+            .map_err(|e| AppError::new("Backup", eyre!(e.to_string())))?;
+        logger::info_con("Backup", "Restored backup");
+        Ok(())
+    }
+}
@@ -1,8 +1,12 @@
 use std::{
+    collections::HashMap,
     fs::File,
     io::{Read, Write},
     path::PathBuf,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
     time::Duration,
 };
 
@@ -17,6 +21,7 @@ use serde_json::{json, Value};
 
 use crate::{
     auth::AuthState,
+    enums::LogLevel,
     error::AppError,
     helper,
     logger::{self},
@@ -25,7 +30,15 @@ use crate::{
     wfm_client::client::WFMClient,
 };
 
-use super::modules::{item::ItemModule, riven::RivenModule};
+use super::modules::{
+    alias::AliasModule,
+    feature_flag::{FeatureFlagModule, FeatureFlagStruct},
+    item::ItemModule,
+    relic::{RelicInfo, RelicModule},
+    resolver::{ItemIndex, ResolverModule},
+    riven::{RivenModule, RivenSoldStatsStruct},
+    source::SourceModule,
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[allow(dead_code)]
@@ -33,38 +46,71 @@ pub struct CacheDataStruct {
     pub last_refresh: Option<String>,
     pub item: CacheDataItemStruct,
     pub riven: CacheDataRivenStruct,
+    pub relic: CacheDataRelicStruct,
+    pub feature_flags: HashMap<String, FeatureFlagStruct>,
 }
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[allow(dead_code)]
 pub struct CacheDataItemStruct {
     pub items: Vec<Item>,
+    // Item names keyed by language and url name, kept for every region that has been used
+    pub localized_names: HashMap<String, HashMap<String, String>>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CacheDataRelicStruct {
+    pub items: Vec<RelicInfo>,
 }
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CacheDataRivenStruct {
     pub items: Vec<RivenTypeInfo>,
     pub attributes: Vec<RivenAttributeInfo>,
+    pub sold_stats: Vec<RivenSoldStatsStruct>,
+    // Weapon names keyed by language and url name
+    pub localized_names: HashMap<String, HashMap<String, String>>,
 }
 
+// Shipped with the app, used when neither Warframe Market nor cache.json can provide the cache
+const FALLBACK_CACHE: &str = include_str!("../../resources/cache_fallback.json");
+
+// Features that need fresh cache data and are limited while the cache is degraded
+const DEGRADED_FEATURES: [&str; 2] = ["riven_sold_stats", "price_providers"];
+
 #[derive(Clone, Debug)]
 pub struct CacheClient {
     pub log_file: PathBuf,
     pub wfm: Arc<Mutex<WFMClient>>,
     pub cache_data: Arc<Mutex<CacheDataStruct>>,
+    pub item_index: Arc<Mutex<Option<Arc<ItemIndex>>>>,
+    // Contents of item_aliases.json, read with the cache and written through on every change
+    pub item_aliases: Arc<Mutex<Option<HashMap<String, String>>>>,
+    // Set while the app runs on an old or the bundled cache because the download failed
+    pub degraded: Arc<AtomicBool>,
 }
 
 impl CacheClient {
     pub fn new(wfm: Arc<Mutex<WFMClient>>) -> Self {
+        let item_index = wfm.lock().unwrap().item_index.clone();
         CacheClient {
             log_file: PathBuf::from("cache"),
             wfm,
             cache_data: Arc::new(Mutex::new(CacheDataStruct {
                 last_refresh: None,
-                item: CacheDataItemStruct { items: vec![] },
+                item: CacheDataItemStruct {
+                    items: vec![],
+                    localized_names: HashMap::new(),
+                },
                 riven: CacheDataRivenStruct {
                     items: vec![],
                     attributes: vec![],
+                    sold_stats: vec![],
+                    localized_names: HashMap::new(),
                 },
+                relic: CacheDataRelicStruct { items: vec![] },
+                feature_flags: HashMap::new(),
             })),
+            item_index,
+            item_aliases: Arc::new(Mutex::new(None)),
+            degraded: Arc::new(AtomicBool::new(false)),
         }
     }
     fn get_file_path() -> PathBuf {
@@ -74,52 +120,116 @@ impl CacheClient {
     }
 
     pub async fn load(&self) -> Result<CacheDataStruct, AppError> {
+        self.alias().load()?;
         let path_ref = Self::get_file_path();
 
         if path_ref.exists() {
             let (se, vaild) = Self::read_from_file()?;
-            if vaild {
-                let last_refresh = se.last_refresh.clone();
-                match last_refresh {
-                    Some(last_refresh) => {
-                        let last_refresh = chrono::DateTime::parse_from_rfc3339(&last_refresh)
-                            .map_err(|e| AppError::new("Cache", eyre!(e.to_string())))?;
-                        let now = chrono::Utc::now();
-                        let diff = now.signed_duration_since(last_refresh);
-                        if diff.num_hours() < 24 {
-                            let arced_mutex = Arc::clone(&self.cache_data);
-                            let mut my_lock = arced_mutex.lock()?;
-                            my_lock.last_refresh = Some(last_refresh.to_string());
-                            my_lock.item = se.item;
-                            my_lock.riven = se.riven;
-                            return Ok(my_lock.clone());
-                        } else {
-                            let data = self.refresh().await?;
-                            self.save_to_file()?;
-                            return Ok(data);
-                        }
-                    }
-                    None => {
-                        let data = self.refresh().await?;
-                        self.save_to_file()?;
-                        return Ok(data);
-                    }
-                }
-            } else {
-                let data = self.refresh().await?;
-                self.save_to_file()?;
+            // Old data is used as is, the scheduler refreshes it in the background
+            if vaild && se.last_refresh.is_some() {
+                let arced_mutex = Arc::clone(&self.cache_data);
+                let mut my_lock = arced_mutex.lock()?;
+                my_lock.last_refresh = se.last_refresh;
+                my_lock.item = se.item;
+                my_lock.riven = se.riven;
+                my_lock.relic = se.relic;
+                my_lock.feature_flags = se.feature_flags;
+                let data = my_lock.clone();
+                drop(my_lock);
+                self.resolver().rebuild()?;
                 return Ok(data);
             }
-        } else {
-            let data = self.refresh().await?;
-            self.save_to_file()?;
-            return Ok(data);
         }
+        match self.refresh().await {
+            Ok(data) => {
+                self.save_to_file()?;
+                Ok(data)
+            }
+            Err(e) => self.load_fallback(e),
+        }
+    }
+
+    // Runs on cache.json even when it is outdated, otherwise on the cache bundled with the app
+    fn load_fallback(&self, error: AppError) -> Result<CacheDataStruct, AppError> {
+        let (data, source) = match Self::read_from_file() {
+            Ok((data, _)) => (data, "local"),
+            Err(_) => (Self::validate_json(FALLBACK_CACHE)?.0, "bundled"),
+        };
+        logger::warning_con(
+            "Cache",
+            format!(
+                "Could not download the cache, running on the {} cache: {}",
+                source,
+                error.cause()
+            )
+            .as_str(),
+        );
+        let arced_mutex = Arc::clone(&self.cache_data);
+        let mut my_lock = arced_mutex.lock()?;
+        my_lock.last_refresh = data.last_refresh.clone();
+        my_lock.item = data.item.clone();
+        my_lock.riven = data.riven.clone();
+        my_lock.relic = data.relic.clone();
+        my_lock.feature_flags = data.feature_flags.clone();
+        drop(my_lock);
+        self.resolver().rebuild()?;
+        self.set_degraded(Some(source));
+        Ok(data)
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::SeqCst)
+    }
+
+    // Source is local or bundled, None once a download succeeded again
+    fn set_degraded(&self, source: Option<&str>) {
+        self.degraded.store(source.is_some(), Ordering::SeqCst);
+        let features: Vec<&str> = match source {
+            Some(_) => DEGRADED_FEATURES.to_vec(),
+            None => vec![],
+        };
+        helper::send_message_to_window(
+            "Cache:Degraded",
+            Some(json!({
+                "active": source.is_some(),
+                "source": source,
+                "features": features,
+            })),
+        );
+    }
+
+    // Run by the scheduler, downloads the cache again once it is older than a day
+    pub async fn refresh_if_stale(&self) -> Result<(), AppError> {
+        // A degraded cache is downloaded again on every run until it works
+        if !self.is_degraded() {
+            let last_refresh = self.cache_data.lock()?.last_refresh.clone();
+            // Nothing is loaded yet, the init command loads or downloads the cache
+            let last_refresh = match last_refresh.and_then(|l| helper::parse_timestamp(&l)) {
+                Some(last_refresh) => last_refresh,
+                None => return Ok(()),
+            };
+            if chrono::Utc::now() - last_refresh < chrono::Duration::hours(24) {
+                return Ok(());
+            }
+            logger::info_con("Cache", "Cache is older than a day, refreshing");
+        }
+        self.refresh().await?;
+        self.save_to_file()?;
+        if self.is_degraded() {
+            logger::info_con("Cache", "Cache downloaded, leaving the degraded mode");
+            self.set_degraded(None);
+        }
+        self.items().emit();
+        self.riven().emit();
+        Ok(())
     }
 
     pub async fn refresh(&self) -> Result<CacheDataStruct, AppError> {
         self.items().refresh().await?;
         self.riven().refresh().await?;
+        self.relics().refresh().await?;
+        self.feature_flags().refresh().await?;
+        self.resolver().rebuild()?;
         self.set_last_refresh(chrono::Utc::now().to_rfc3339())?;
         let cache_data = self.cache_data.lock()?.clone();
         Ok(cache_data)
@@ -139,6 +249,26 @@ impl CacheClient {
         RivenModule { client: self }
     }
 
+    pub fn relics(&self) -> RelicModule {
+        RelicModule { client: self }
+    }
+
+    pub fn alias(&self) -> AliasModule {
+        AliasModule { client: self }
+    }
+
+    pub fn feature_flags(&self) -> FeatureFlagModule {
+        FeatureFlagModule { client: self }
+    }
+
+    pub fn resolver(&self) -> ResolverModule {
+        ResolverModule { client: self }
+    }
+
+    pub fn sources(&self) -> SourceModule {
+        SourceModule { client: self }
+    }
+
     pub fn save_to_file(&self) -> Result<(), AppError> {
         let chache_data = self.cache_data.clone();
         let json = serde_json::to_string_pretty(&chache_data)
@@ -163,6 +293,74 @@ impl CacheClient {
         Ok(Self::validate_json(&content)?)
     }
 
+    // Re-reads one category of cache.json after it was edited by hand and swaps it in memory
+    pub fn reload_category(&self, category: &str) -> Result<(), AppError> {
+        let mut file = File::open(Self::get_file_path())
+            .map_err(|e| AppError::new("Cache", eyre!(e.to_string())))?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)
+            .map_err(|e| AppError::new("Cache", eyre!(e.to_string())))?;
+        let json_value: Value = serde_json::from_str(&content).map_err(|e| {
+            AppError::new_with_level(
+                "Cache",
+                eyre!("cache.json is not valid JSON: {}", e),
+                LogLevel::Warning,
+            )
+        })?;
+        let data = match json_value.get(category) {
+            Some(data) => data.clone(),
+            None => {
+                return Err(AppError::new_with_level(
+                    "Cache",
+                    eyre!("Category {} not found in cache.json", category),
+                    LogLevel::Warning,
+                ))
+            }
+        };
+        let invalid = |e: serde_json::Error| {
+            AppError::new_with_level(
+                "Cache",
+                eyre!("Category {} is invalid: {}", category, e),
+                LogLevel::Warning,
+            )
+        };
+
+        match category {
+            "item" => {
+                let item: CacheDataItemStruct = serde_json::from_value(data).map_err(invalid)?;
+                self.cache_data.lock()?.item = item;
+                self.resolver().rebuild()?;
+                self.items().emit();
+            }
+            "riven" => {
+                let riven: CacheDataRivenStruct = serde_json::from_value(data).map_err(invalid)?;
+                self.cache_data.lock()?.riven = riven;
+                self.riven().emit();
+            }
+            "relic" => {
+                let relic: CacheDataRelicStruct = serde_json::from_value(data).map_err(invalid)?;
+                self.cache_data.lock()?.relic = relic;
+            }
+            "feature_flags" => {
+                let feature_flags: HashMap<String, FeatureFlagStruct> =
+                    serde_json::from_value(data).map_err(invalid)?;
+                self.cache_data.lock()?.feature_flags = feature_flags;
+            }
+            _ => {
+                return Err(AppError::new_with_level(
+                    "Cache",
+                    eyre!("Unknown cache category: {}", category),
+                    LogLevel::Warning,
+                ))
+            }
+        }
+        logger::info_con(
+            "Cache",
+            format!("Reloaded category {} from cache.json", category).as_str(),
+        );
+        Ok(())
+    }
+
     fn validate_json(json_str: &str) -> Result<(CacheDataStruct, bool), AppError> {
         let mut is_valid = true;
         // Parse the JSON string into a Value object
@@ -182,6 +380,10 @@ impl CacheClient {
                 item_data["items"] = json!([]);
                 is_valid = false;
             }
+            if item_data.get("localized_names").is_none() {
+                item_data["localized_names"] = json!({});
+                is_valid = false;
+            }
         }
 
         // Check for nested properties within 'riven'
@@ -194,6 +396,33 @@ impl CacheClient {
                 riven_data["attributes"] = json!([]);
                 is_valid = false;
             }
+            if riven_data.get("sold_stats").is_none() {
+                riven_data["sold_stats"] = json!([]);
+                is_valid = false;
+            }
+            if riven_data.get("localized_names").is_none() {
+                riven_data["localized_names"] = json!({});
+                is_valid = false;
+            }
+        }
+
+        // Check for nested properties within 'relic'
+        match json_value.get_mut("relic") {
+            Some(relic_data) => {
+                if relic_data.get("items").is_none() {
+                    relic_data["items"] = json!([]);
+                    is_valid = false;
+                }
+            }
+            None => {
+                json_value["relic"] = json!({ "items": [] });
+                is_valid = false;
+            }
+        }
+
+        if json_value.get("feature_flags").is_none() {
+            json_value["feature_flags"] = json!({});
+            is_valid = false;
         }
 
         // Deserialize the updated JSON object into a SettingsState struct
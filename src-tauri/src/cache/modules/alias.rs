@@ -0,0 +1,150 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Read, Write},
+    path::PathBuf,
+};
+
+use eyre::eyre;
+use serde_json::json;
+
+use crate::{
+    cache::client::CacheClient,
+    enums::{ErrorKind, LogLevel},
+    error::AppError,
+    helper, logger,
+};
+
+pub struct AliasModule<'a> {
+    pub client: &'a CacheClient,
+}
+
+impl<'a> AliasModule<'a> {
+    fn get_file_path() -> PathBuf {
+        let app_path = helper::get_app_roaming_path();
+        let alias_path = app_path.join("item_aliases.json");
+        alias_path
+    }
+
+    // Shipped abbreviations used in trade chat and EE.log, expanded word by word
+    fn get_default_abbreviations() -> HashMap<&'static str, &'static str> {
+        HashMap::from([
+            ("p", "prime"),
+            ("bp", "blueprint"),
+            ("nc", "neuroptics"),
+            ("neuro", "neuroptics"),
+            ("chas", "chassis"),
+            ("sys", "systems"),
+            ("rec", "receiver"),
+            ("stk", "stock"),
+            ("brl", "barrel"),
+        ])
+    }
+
+    // Reads item_aliases.json, done once when the cache is loaded
+    pub fn load(&self) -> Result<HashMap<String, String>, AppError> {
+        let path_ref = Self::get_file_path();
+        let mut aliases = HashMap::new();
+        if path_ref.exists() {
+            let mut file = File::open(path_ref)
+                .map_err(|e| AppError::new("CacheAlias", eyre!(e.to_string())))?;
+            let mut content = String::new();
+            file.read_to_string(&mut content)
+                .map_err(|e| AppError::new("CacheAlias", eyre!(e.to_string())))?;
+            aliases = serde_json::from_str(&content)
+                .map_err(|e| AppError::new("CacheAlias", eyre!(e.to_string())))?;
+        }
+        *self.client.item_aliases.lock()? = Some(aliases.clone());
+        Ok(aliases)
+    }
+
+    pub fn get_aliases(&self) -> Result<HashMap<String, String>, AppError> {
+        let aliases = self.client.item_aliases.lock()?.clone();
+        match aliases {
+            Some(aliases) => Ok(aliases),
+            None => self.load(),
+        }
+    }
+
+    fn save_aliases(&self, aliases: &HashMap<String, String>) -> Result<(), AppError> {
+        let json = serde_json::to_string_pretty(aliases)
+            .map_err(|e| AppError::new("CacheAlias", eyre!(e.to_string())))?;
+        let mut file = File::create(Self::get_file_path())
+            .map_err(|e| AppError::new("CacheAlias", eyre!(e.to_string())))?;
+        file.write_all(json.as_bytes())
+            .map_err(|e| AppError::new("CacheAlias", eyre!(e.to_string())))?;
+        *self.client.item_aliases.lock()? = Some(aliases.clone());
+        Ok(())
+    }
+
+    pub fn add(&self, alias: &str, url_name: &str) -> Result<HashMap<String, String>, AppError> {
+        let alias = Self::normalize(alias);
+        if alias == "" {
+            return Err(AppError::new_with_level(
+                "CacheAlias",
+                eyre!("Alias can not be empty"),
+                LogLevel::Warning,
+            ));
+        }
+        if self.client.items().find_type(url_name)?.is_none() {
+            return Err(AppError::new_with_level(
+                "CacheAlias",
+                eyre!("Item {} not found in cache", url_name),
+                LogLevel::Warning,
+            )
+            .with_kind(ErrorKind::NotFound));
+        }
+        let mut aliases = self.get_aliases()?;
+        aliases.insert(alias.clone(), url_name.to_string());
+        self.save_aliases(&aliases)?;
+        logger::info_con(
+            "CacheAlias",
+            format!("Added alias: {} -> {}", alias, url_name).as_str(),
+        );
+        self.emit(&aliases);
+        Ok(aliases)
+    }
+
+    // Used when a synced profile replaces every alias at once
+    pub fn replace(&self, aliases: HashMap<String, String>) -> Result<(), AppError> {
+        self.save_aliases(&aliases)?;
+        self.emit(&aliases);
+        Ok(())
+    }
+
+    pub fn remove(&self, alias: &str) -> Result<HashMap<String, String>, AppError> {
+        let mut aliases = self.get_aliases()?;
+        aliases.remove(&Self::normalize(alias));
+        self.save_aliases(&aliases)?;
+        self.emit(&aliases);
+        Ok(aliases)
+    }
+
+    /// Returns the url_name a user alias points to, or the url_name produced by
+    /// expanding the shipped abbreviations (e.g. "wisp p set" -> "wisp_prime_set").
+    pub fn resolve(&self, name: &str) -> Result<(Option<String>, String), AppError> {
+        let normalized = Self::normalize(name);
+        let aliases = self.get_aliases()?;
+        let user_alias = aliases.get(&normalized).cloned();
+
+        let abbreviations = Self::get_default_abbreviations();
+        let expanded = normalized
+            .split(' ')
+            .map(|word| abbreviations.get(word).cloned().unwrap_or(word))
+            .collect::<Vec<_>>()
+            .join("_");
+        Ok((user_alias, expanded))
+    }
+
+    pub fn normalize(name: &str) -> String {
+        name.to_lowercase()
+            .replace("\u{e000}", "")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn emit(&self, aliases: &HashMap<String, String>) {
+        helper::send_message_to_window("Cache:Update:ItemAliases", Some(json!(aliases)));
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+use crate::{cache::client::CacheClient, error::AppError, logger, settings::SettingsState};
+
+// Experimental subsystems that can be turned on per user or turned off remotely
+pub const WEBSOCKET_EVENTS: &str = "websocket_events";
+pub const MARKET_MAKER: &str = "market_maker";
+pub const TRADE_OCR: &str = "trade_ocr";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FeatureFlagStruct {
+    // Used when the user has not opted in or out
+    pub enabled: bool,
+    // Remotely turned off, overrides the user's choice
+    pub killed: bool,
+    pub description: String,
+}
+
+pub struct FeatureFlagModule<'a> {
+    pub client: &'a CacheClient,
+}
+
+impl<'a> FeatureFlagModule<'a> {
+    fn get_url() -> &'static str {
+        "https://raw.githubusercontent.com/Kenya-DK/quantframe-react/main/feature_flags.json"
+    }
+
+    // Defaults shipped with the app, used until the cache feed has been downloaded
+    fn get_builtin_flags() -> HashMap<String, FeatureFlagStruct> {
+        HashMap::from([
+            (
+                WEBSOCKET_EVENTS.to_string(),
+                FeatureFlagStruct {
+                    enabled: true,
+                    killed: false,
+                    description: "Handle Warframe Market websocket events".to_string(),
+                },
+            ),
+            (
+                MARKET_MAKER.to_string(),
+                FeatureFlagStruct {
+                    enabled: false,
+                    killed: false,
+                    description: "Keep buy and sell orders around the mid price".to_string(),
+                },
+            ),
+            (
+                TRADE_OCR.to_string(),
+                FeatureFlagStruct {
+                    enabled: false,
+                    killed: false,
+                    description: "Check trades from EE.log against a screenshot of the trade"
+                        .to_string(),
+                },
+            ),
+        ])
+    }
+
+    pub async fn refresh(&self) -> Result<(), AppError> {
+        let flags = match self.download().await {
+            Ok(flags) => flags,
+            Err(e) => {
+                // The feed is optional, keep the last known flags
+                logger::warning_con(
+                    "CacheFeatureFlags",
+                    format!("Could not download feature flags: {}", e.cause()).as_str(),
+                );
+                return Ok(());
+            }
+        };
+        let mut cache_data = self.client.cache_data.lock()?;
+        cache_data.feature_flags = flags;
+        Ok(())
+    }
+
+    async fn download(&self) -> Result<HashMap<String, FeatureFlagStruct>, AppError> {
+        let response = reqwest::get(Self::get_url())
+            .await
+            .map_err(|e| AppError::new("CacheFeatureFlags", eyre!(e.to_string())))?;
+        if response.status() != 200 {
+            return Err(AppError::new(
+                "CacheFeatureFlags",
+                eyre!("Could not get feature flags. Status: {}", response.status()),
+            ));
+        }
+        response
+            .json::<HashMap<String, FeatureFlagStruct>>()
+            .await
+            .map_err(|e| AppError::new("CacheFeatureFlags", eyre!(e.to_string())))
+    }
+
+    // Built-in flags overridden by the ones from the cache feed
+    pub fn get_flags(&self) -> Result<HashMap<String, FeatureFlagStruct>, AppError> {
+        let mut flags = Self::get_builtin_flags();
+        for (name, flag) in self.client.cache_data.lock()?.feature_flags.iter() {
+            flags.insert(name.clone(), flag.clone());
+        }
+        Ok(flags)
+    }
+
+    pub fn is_enabled(&self, name: &str, settings: &SettingsState) -> Result<bool, AppError> {
+        let flag = match self.get_flags()?.get(name) {
+            Some(flag) => flag.clone(),
+            None => return Ok(false),
+        };
+        if flag.killed {
+            return Ok(false);
+        }
+        Ok(*settings.feature_flags.get(name).unwrap_or(&flag.enabled))
+    }
+
+    // The state of every flag for the current settings
+    pub fn get_resolved(
+        &self,
+        settings: &SettingsState,
+    ) -> Result<HashMap<String, bool>, AppError> {
+        let mut resolved = HashMap::new();
+        for name in self.get_flags()?.keys() {
+            resolved.insert(name.clone(), self.is_enabled(name, settings)?);
+        }
+        Ok(resolved)
+    }
+}
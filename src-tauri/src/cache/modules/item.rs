@@ -4,19 +4,68 @@ use std::{
 };
 
 use eyre::eyre;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
 use crate::{
     cache::client::CacheClient,
+    enums::{ErrorKind, LogLevel},
     error::AppError,
     helper, logger,
     structs::{Item, ItemDetails},
 };
 
+// Relics trade by refinement, used when relics.run does not list the subtypes of a relic
+const RELIC_REFINEMENTS: [&str; 4] = ["intact", "exceptional", "flawless", "radiant"];
+
 pub struct ItemModule<'a> {
     pub client: &'a CacheClient,
 }
 
+// Languages the names are cached in besides the region, English and the language of the game
+// client so the trade log can be read whatever language Warframe runs in
+pub fn get_name_languages(region: &str) -> Vec<String> {
+    let mut languages = vec!["en".to_string()];
+    if let Some(code) = helper::get_warframe_language().as_code() {
+        languages.push(code.to_string());
+    }
+    languages.dedup();
+    languages.retain(|language| language != region);
+    languages
+}
+
+// Ducats keyed by the full part name, from the components of the WFCD items
+fn get_ducat_values(wfcd_items: &[Value]) -> HashMap<String, i64> {
+    let mut values = HashMap::new();
+    for item in wfcd_items {
+        let name = match item["name"].as_str() {
+            Some(name) => name,
+            None => continue,
+        };
+        for component in item["components"].as_array().into_iter().flatten() {
+            if let (Some(part), Some(ducats)) =
+                (component["name"].as_str(), component["ducats"].as_i64())
+            {
+                values.insert(format!("{} {}", name, part), ducats);
+            }
+        }
+    }
+    values
+}
+
+// What a stock item of this type can be, used by the stock dialogs to pick the right controls
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ItemConstraintsStruct {
+    pub url_name: String,
+    // None when the item has no ranks
+    pub max_rank: Option<i64>,
+    // Empty when the item has no sub types, the first one is the default
+    pub sub_types: Vec<String>,
+    pub default_sub_type: Option<String>,
+    // Ayatan sculptures are traded with their amber and cyan stars
+    pub has_stars: bool,
+}
+
 impl<'a> ItemModule<'a> {
     // Refrece
     pub async fn refresh(&self) -> Result<(), AppError> {
@@ -33,22 +82,33 @@ impl<'a> ItemModule<'a> {
             "set_initializstatus",
             Some(json!({"status": "Downloading Item Data from Github..."})),
         );
-        let response2: Vec<Value> =
-            reqwest::get("https://github.com/WFCD/warframe-items/raw/master/data/json/All.json")
-                .await
-                .map_err(|e| AppError::new("CacheItems", eyre!(e.to_string())))?
-                .json()
-                .await
-                .map_err(|e| AppError::new("CacheItems", eyre!(e.to_string())))?;
+        let response2: Vec<Value> = serde_json::from_str(
+            &self
+                .client
+                .sources()
+                .fetch(
+                    "warframe_items",
+                    "https://github.com/WFCD/warframe-items/raw/master/data/json/All.json",
+                )
+                .await?,
+        )
+        .map_err(|e| AppError::new("CacheItems", eyre!(e.to_string())))?;
 
         helper::emit_undate_initializ_status("Downloading Item Data from Relics.Run...", None);
-        let response: HashMap<String, Value> =
-            reqwest::get("http://relics.run/history/item_data/item_info.json")
-                .await
-                .map_err(|e| AppError::new("CacheItems", eyre!(e.to_string())))?
-                .json()
-                .await
-                .map_err(|e| AppError::new("CacheItems", eyre!(e.to_string())))?;
+        let response: HashMap<String, Value> = serde_json::from_str(
+            &self
+                .client
+                .sources()
+                .fetch(
+                    "relics_run_item_info",
+                    "http://relics.run/history/item_data/item_info.json",
+                )
+                .await?,
+        )
+        .map_err(|e| AppError::new("CacheItems", eyre!(e.to_string())))?;
+
+        // Prime parts are components of their item in the WFCD data, e.g. "Ash Prime" and "Chassis"
+        let ducat_values = get_ducat_values(&response2);
 
         helper::emit_undate_initializ_status("Storing Looping through Item Data...", None);
         let mut items: Vec<Item> = Vec::new();
@@ -58,7 +118,11 @@ impl<'a> ItemModule<'a> {
                 let mut new = item.clone();
                 let set_items = self.get_string_arry_from_json(relic_data.unwrap(), "set_items");
                 let tags = self.get_string_arry_from_json(relic_data.unwrap(), "tags");
-                let subtypes = self.get_string_arry_from_json(relic_data.unwrap(), "subtypes");
+                let mut subtypes =
+                    self.get_string_arry_from_json(relic_data.unwrap(), "subtypes");
+                if subtypes.is_empty() && tags.contains(&"relic".to_string()) {
+                    subtypes = RELIC_REFINEMENTS.iter().map(|s| s.to_string()).collect();
+                }
                 let mod_max_rank = relic_data.unwrap().get("mod_max_rank").unwrap().as_i64();
                 let mut mr_requirement: Option<i64> = Some(0);
                 let mut wikia_url: Option<String> = Some("".to_string());
@@ -85,6 +149,11 @@ impl<'a> ItemModule<'a> {
                 new.trade_tax = Some(helper::calculate_trade_tax(tags, mod_max_rank));
                 new.mr_requirement = mr_requirement;
                 new.wikia_url = wikia_url;
+                new.ducats = ducat_values.get(&item.item_name).cloned().or_else(|| {
+                    item.item_name
+                        .strip_suffix(" Blueprint")
+                        .and_then(|name| ducat_values.get(name).cloned())
+                });
                 // Only send for every 10th item
                 if items.len() % 100 == 0 {
                     helper::emit_undate_initializ_status(
@@ -103,9 +172,47 @@ impl<'a> ItemModule<'a> {
             }
         }
 
+        // WFM returns the names in the language of the current region
+        let mut language = wfm.auth.lock()?.region.clone();
+        if language == "" {
+            language = "en".to_string();
+        }
+        let mut localized_names = HashMap::new();
+        localized_names.insert(
+            language.clone(),
+            items
+                .iter()
+                .map(|item| (item.url_name.clone(), item.item_name.clone()))
+                .collect::<HashMap<String, String>>(),
+        );
+        for extra in get_name_languages(&language) {
+            helper::emit_undate_initializ_status(
+                format!("Downloading Item Names in {}...", extra).as_str(),
+                None,
+            );
+            match wfm.with_language(&extra).items().get_all_items().await {
+                Ok(localized) => {
+                    localized_names.insert(
+                        extra,
+                        localized
+                            .into_iter()
+                            .map(|item| (item.url_name, item.item_name))
+                            .collect(),
+                    );
+                }
+                Err(e) => logger::warning_con(
+                    "CacheItems",
+                    format!("No item names in {}: {}", extra, e.cause()).as_str(),
+                ),
+            }
+        }
+
         let arced_mutex = Arc::clone(&self.client.cache_data);
         let mut my_lock = arced_mutex.lock()?;
         my_lock.item.items = items.clone();
+        my_lock.item.localized_names.extend(localized_names);
+        drop(my_lock);
+        self.client.resolver().rebuild()?;
         Ok(items)
     }
 
@@ -115,8 +222,7 @@ impl<'a> ItemModule<'a> {
     }
 
     pub fn find_type(&self, url_name: &str) -> Result<Option<Item>, AppError> {
-        let types = self.client.cache_data.lock()?.clone().item.items;
-        let item_type = types.iter().find(|&x| x.url_name == url_name).cloned();
+        let item_type = self.client.resolver().by_url_name(url_name)?;
         if !item_type.is_some() {
             logger::warning_con(
                 "CacheItems",
@@ -126,6 +232,72 @@ impl<'a> ItemModule<'a> {
         Ok(item_type)
     }
 
+    // Name as shown in the game or on Warframe Market in the given language, e.g. "de"
+    pub fn find_by_name(&self, name: &str, language: &str) -> Result<Option<Item>, AppError> {
+        let item_type = self.client.resolver().by_name_in(name, language)?;
+        if !item_type.is_some() {
+            logger::warning_con(
+                "CacheItems",
+                format!("Item Name: {} not found", name).as_str(),
+            );
+        }
+        Ok(item_type)
+    }
+
+    // Valid sub types of the item, e.g. the refinements of a relic. Empty when the item has none
+    pub fn get_subtypes(&self, url_name: &str) -> Result<Vec<String>, AppError> {
+        let item = self.client.resolver().by_url_name(url_name)?;
+        Ok(item.and_then(|item| item.subtypes).unwrap_or_default())
+    }
+
+    pub fn get_constraints(
+        &self,
+        url_name: &str,
+    ) -> Result<Option<ItemConstraintsStruct>, AppError> {
+        let item = match self.client.resolver().by_url_name(url_name)? {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+        let sub_types = item.subtypes.unwrap_or_default();
+        Ok(Some(ItemConstraintsStruct {
+            url_name: item.url_name,
+            max_rank: item.mod_max_rank,
+            default_sub_type: sub_types.first().cloned(),
+            sub_types,
+            has_stars: item
+                .tags
+                .unwrap_or_default()
+                .contains(&"ayatan_sculpture".to_string()),
+        }))
+    }
+
+    // Checks the sub type against the cached subtypes of the item.
+    // Items with subtypes default to the first one, which is what Warframe Market lists as the base variant.
+    pub fn validate_sub_type(
+        &self,
+        url_name: &str,
+        sub_type: Option<&str>,
+    ) -> Result<Option<String>, AppError> {
+        let subtypes = self.get_subtypes(url_name)?;
+        match sub_type {
+            Some(sub_type) if subtypes.contains(&sub_type.to_string()) => {
+                Ok(Some(sub_type.to_string()))
+            }
+            Some(sub_type) => Err(AppError::new_with_level(
+                "CacheItems",
+                eyre!(
+                    "Sub type {} is not valid for {}, expected one of: {}",
+                    sub_type,
+                    url_name,
+                    subtypes.join(", ")
+                ),
+                LogLevel::Warning,
+            )
+            .with_kind(ErrorKind::Validation)),
+            None => Ok(subtypes.first().cloned()),
+        }
+    }
+
     pub fn emit(&self) {
         let types = self.client.cache_data.lock().unwrap().clone().item.items;
         helper::send_message_to_window(
@@ -1,2 +1,8 @@
+pub mod alias;
+pub mod feature_flag;
 pub mod item;
+pub mod price_provider;
+pub mod relic;
+pub mod resolver;
 pub mod riven;
+pub mod source;
@@ -0,0 +1,262 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use eyre::eyre;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+
+use crate::{
+    enums::{ErrorKind, LogLevel},
+    error::AppError,
+    logger,
+    price_scraper::PriceHistory,
+    settings::StockItemSettings,
+    wfm_client::client::WFMClient,
+};
+
+pub const QF_DUMP: &str = "qf_dump";
+pub const WFM_STATISTICS: &str = "wfm_statistics";
+pub const CSV: &str = "csv";
+
+// Closed days a provider averages over
+const AVERAGE_DAYS: usize = 7;
+// Warframe Market statistics only change once a day, so they are not fetched every pass
+const STATISTICS_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+static STATISTICS_CACHE: Lazy<Mutex<HashMap<String, (Instant, Option<f64>)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub type PriceFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<HashMap<String, f64>, AppError>> + Send + 'a>>;
+
+// A source of average item prices, keyed by url name. Items the source knows nothing about are left out.
+pub trait PriceProvider: Send + Sync {
+    fn name(&self) -> &str;
+    fn get_prices<'a>(&'a self, url_names: &'a [String]) -> PriceFuture<'a>;
+}
+
+// The daily price dump downloaded by the price scraper
+pub struct QfDumpProvider {
+    pub history: Arc<PriceHistory>,
+}
+
+impl PriceProvider for QfDumpProvider {
+    fn name(&self) -> &str {
+        QF_DUMP
+    }
+
+    fn get_prices<'a>(&'a self, url_names: &'a [String]) -> PriceFuture<'a> {
+        Box::pin(async move {
+            let mut prices = HashMap::new();
+            for url_name in url_names {
+                let days = self.history.get_closed_days(url_name);
+                if days.is_empty() {
+                    continue;
+                }
+                let total: f64 = days.iter().map(|day| day.avg_price).sum();
+                prices.insert(url_name.clone(), total / days.len() as f64);
+            }
+            Ok(prices)
+        })
+    }
+}
+
+// The statistics endpoint of Warframe Market, one request per item that is not cached yet
+pub struct WfmStatisticsProvider {
+    pub wfm: WFMClient,
+}
+
+impl WfmStatisticsProvider {
+    // Volume weighted average price of the last closed days, unranked only for mods
+    fn get_average(statistics: &Value) -> Option<f64> {
+        let days = statistics["90days"].as_array()?;
+        let (total, volume) = days
+            .iter()
+            .rev()
+            .filter(|day| day["mod_rank"].as_i64().unwrap_or(0) == 0)
+            .take(AVERAGE_DAYS)
+            .fold((0.0, 0.0), |(total, volume), day| {
+                let day_volume = day["volume"].as_f64().unwrap_or(0.0);
+                let avg_price = day["avg_price"].as_f64().unwrap_or(0.0);
+                (total + avg_price * day_volume, volume + day_volume)
+            });
+        if volume > 0.0 {
+            Some(total / volume)
+        } else {
+            None
+        }
+    }
+}
+
+impl PriceProvider for WfmStatisticsProvider {
+    fn name(&self) -> &str {
+        WFM_STATISTICS
+    }
+
+    fn get_prices<'a>(&'a self, url_names: &'a [String]) -> PriceFuture<'a> {
+        Box::pin(async move {
+            let mut prices = HashMap::new();
+            for url_name in url_names {
+                let cached = STATISTICS_CACHE.lock()?.get(url_name).cloned();
+                let price = match cached {
+                    Some((fetched, price)) if fetched.elapsed() < STATISTICS_TTL => price,
+                    _ => match self.wfm.items().get_statistics(url_name).await {
+                        Ok(statistics) => {
+                            let price = Self::get_average(&statistics);
+                            STATISTICS_CACHE
+                                .lock()?
+                                .insert(url_name.clone(), (Instant::now(), price));
+                            price
+                        }
+                        // The rest of the items keep their other prices
+                        Err(e) if e.kind() == ErrorKind::BudgetExhausted => break,
+                        Err(e) => {
+                            logger::warning_con(
+                                "PriceProvider",
+                                format!("No statistics for {}: {}", url_name, e.cause()).as_str(),
+                            );
+                            None
+                        }
+                    },
+                };
+                if let Some(price) = price {
+                    prices.insert(url_name.clone(), price);
+                }
+            }
+            Ok(prices)
+        })
+    }
+}
+
+// Prices supplied by the user in a CSV file with url_name and price columns
+pub struct CsvProvider {
+    pub path: String,
+}
+
+impl PriceProvider for CsvProvider {
+    fn name(&self) -> &str {
+        CSV
+    }
+
+    fn get_prices<'a>(&'a self, url_names: &'a [String]) -> PriceFuture<'a> {
+        Box::pin(async move {
+            let mut reader = csv::ReaderBuilder::new()
+                .flexible(true)
+                .trim(csv::Trim::All)
+                .from_path(&self.path)
+                .map_err(|e| {
+                    AppError::new_with_level(
+                        "PriceProvider",
+                        eyre!("Could not read {}: {}", self.path, e),
+                        LogLevel::Warning,
+                    )
+                    .with_kind(ErrorKind::NotFound)
+                })?;
+            let headers = reader
+                .headers()
+                .map_err(|e| AppError::new("PriceProvider", eyre!(e.to_string())))?
+                .clone();
+            let get_index = |column: &str| -> Result<usize, AppError> {
+                headers
+                    .iter()
+                    .position(|h| h.eq_ignore_ascii_case(column))
+                    .ok_or_else(|| {
+                        AppError::new_with_level(
+                            "PriceProvider",
+                            eyre!("Column {} not found in {}", column, self.path),
+                            LogLevel::Warning,
+                        )
+                        .with_kind(ErrorKind::Validation)
+                    })
+            };
+            let name_index = get_index("url_name")?;
+            let price_index = get_index("price")?;
+
+            let mut prices = HashMap::new();
+            for record in reader.records().flatten() {
+                let url_name = record.get(name_index).unwrap_or_default();
+                if !url_names.iter().any(|name| name == url_name) {
+                    continue;
+                }
+                match record.get(price_index).map(|p| p.parse::<f64>()) {
+                    Some(Ok(price)) if price > 0.0 => {
+                        prices.insert(url_name.to_string(), price);
+                    }
+                    _ => {}
+                }
+            }
+            Ok(prices)
+        })
+    }
+}
+
+// The enabled providers and their weights, unknown names in the settings are skipped
+pub fn get_providers(
+    settings: &StockItemSettings,
+    history: Arc<PriceHistory>,
+    wfm: WFMClient,
+) -> Vec<(Box<dyn PriceProvider>, f64)> {
+    let mut providers: Vec<(Box<dyn PriceProvider>, f64)> = vec![];
+    let mut names: Vec<&String> = settings.price_providers.keys().collect();
+    names.sort();
+    for name in names {
+        let rule = &settings.price_providers[name];
+        if !rule.enabled || rule.weight <= 0 {
+            continue;
+        }
+        let provider: Box<dyn PriceProvider> = match name.as_str() {
+            QF_DUMP => Box::new(QfDumpProvider {
+                history: history.clone(),
+            }),
+            WFM_STATISTICS => Box::new(WfmStatisticsProvider { wfm: wfm.clone() }),
+            CSV => Box::new(CsvProvider {
+                path: settings.price_provider_csv_path.clone(),
+            }),
+            _ => {
+                logger::warning_con(
+                    "PriceProvider",
+                    format!("Unknown price provider {}", name).as_str(),
+                );
+                continue;
+            }
+        };
+        providers.push((provider, rule.weight as f64));
+    }
+    providers
+}
+
+// Weighted average of the providers that know the item. A provider that fails is left out
+// so one broken source does not stop the pass.
+pub async fn get_blended_prices(
+    providers: &[(Box<dyn PriceProvider>, f64)],
+    url_names: &[String],
+) -> HashMap<String, f64> {
+    let mut totals: HashMap<String, (f64, f64)> = HashMap::new();
+    for (provider, weight) in providers {
+        let prices = match provider.get_prices(url_names).await {
+            Ok(prices) => prices,
+            Err(e) => {
+                logger::warning_con(
+                    "PriceProvider",
+                    format!("Provider {} failed: {}", provider.name(), e.cause()).as_str(),
+                );
+                continue;
+            }
+        };
+        for (url_name, price) in prices {
+            let (total, weights) = totals.entry(url_name).or_insert((0.0, 0.0));
+            *total += price * weight;
+            *weights += weight;
+        }
+    }
+    totals
+        .into_iter()
+        .filter(|(_, (_, weights))| *weights > 0.0)
+        .map(|(url_name, (total, weights))| (url_name, total / weights))
+        .collect()
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{cache::client::CacheClient, error::AppError, helper, logger};
+
+// Chance in percent of each common, uncommon and rare reward by refinement
+const REFINEMENT_CHANCES: [(&str, [f64; 3]); 4] = [
+    ("intact", [25.33, 11.0, 2.0]),
+    ("exceptional", [23.33, 13.0, 4.0]),
+    ("flawless", [20.0, 17.0, 6.0]),
+    ("radiant", [16.67, 20.0, 10.0]),
+];
+
+pub struct RelicModule<'a> {
+    pub client: &'a CacheClient,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RelicReward {
+    // None when the reward is not traded on Warframe Market, e.g. Forma
+    pub url_name: Option<String>,
+    pub name: String,
+    // common, uncommon or rare
+    pub rarity: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RelicInfo {
+    // Without the refinement, e.g. "Axi A1"
+    pub name: String,
+    pub url_name: String,
+    // lith, meso, neo, axi or requiem
+    pub tier: String,
+    pub vaulted: bool,
+    pub rewards: Vec<RelicReward>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RelicRewardValue {
+    pub url_name: Option<String>,
+    pub name: String,
+    pub rarity: String,
+    pub price: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RelicRefinementValue {
+    pub refinement: String,
+    // Plat a crack is worth on average, rewards without a price count as 0
+    pub expected_value: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RelicValueStruct {
+    pub name: String,
+    pub url_name: String,
+    pub tier: String,
+    pub vaulted: bool,
+    pub rewards: Vec<RelicRewardValue>,
+    pub values: Vec<RelicRefinementValue>,
+}
+
+fn get_rarity_index(rarity: &str) -> Option<usize> {
+    match rarity {
+        "common" => Some(0),
+        "uncommon" => Some(1),
+        "rare" => Some(2),
+        _ => None,
+    }
+}
+
+impl<'a> RelicModule<'a> {
+    pub async fn refresh(&self) -> Result<(), AppError> {
+        helper::send_message_to_window(
+            "set_initializstatus",
+            Some(json!({"status": "Downloading Relic Data from Github..."})),
+        );
+        let content = match self
+            .client
+            .sources()
+            .fetch(
+                "warframe_relics",
+                "https://github.com/WFCD/warframe-items/raw/master/data/json/Relics.json",
+            )
+            .await
+        {
+            Ok(content) => content,
+            Err(e) => {
+                // Only the relic values need it, keep the last known relics
+                logger::warning_con(
+                    "CacheRelics",
+                    format!("Could not download relic data: {}", e.cause()).as_str(),
+                );
+                return Ok(());
+            }
+        };
+        let entries: Vec<Value> = serde_json::from_str(&content)
+            .map_err(|e| AppError::new("CacheRelics", eyre!(e.to_string())))?;
+        let relics = self.parse_relics(&entries)?;
+        self.client.cache_data.lock()?.relic.items = relics;
+        Ok(())
+    }
+
+    // Every refinement of a relic holds the same rewards, only the intact entries are read
+    fn parse_relics(&self, entries: &[Value]) -> Result<Vec<RelicInfo>, AppError> {
+        let resolver = self.client.resolver();
+        let mut relics = vec![];
+        for entry in entries {
+            let name = match entry["name"]
+                .as_str()
+                .and_then(|n| n.strip_suffix(" Intact"))
+            {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            let mut rewards = vec![];
+            for reward in entry["rewards"].as_array().into_iter().flatten() {
+                let reward_name = match reward["item"]["name"].as_str() {
+                    Some(reward_name) => reward_name.to_string(),
+                    None => continue,
+                };
+                let url_name = match reward["item"]["warframeMarket"]["urlName"].as_str() {
+                    Some(url_name) => Some(url_name.to_string()),
+                    None => resolver
+                        .by_name_in(&reward_name, "en")?
+                        .map(|item| item.url_name),
+                };
+                rewards.push(RelicReward {
+                    url_name,
+                    name: reward_name,
+                    rarity: reward["rarity"].as_str().unwrap_or("").to_lowercase(),
+                });
+            }
+            let url_name = match entry["marketInfo"]["urlName"].as_str() {
+                Some(url_name) => url_name.to_string(),
+                None => format!("{}_relic", name.to_lowercase().replace(' ', "_")),
+            };
+            relics.push(RelicInfo {
+                tier: name.split(' ').next().unwrap_or_default().to_lowercase(),
+                url_name,
+                name,
+                vaulted: entry["vaulted"].as_bool().unwrap_or(false),
+                rewards,
+            });
+        }
+        Ok(relics)
+    }
+
+    pub fn get_relics(&self) -> Result<Vec<RelicInfo>, AppError> {
+        let relics = self.client.cache_data.lock()?.relic.items.clone();
+        Ok(relics)
+    }
+
+    // Expected plat per crack of every refinement, prices are keyed by url name
+    pub fn get_values(
+        &self,
+        prices: &HashMap<String, f64>,
+    ) -> Result<Vec<RelicValueStruct>, AppError> {
+        let mut values: Vec<RelicValueStruct> = self
+            .get_relics()?
+            .into_iter()
+            .map(|relic| {
+                let rewards: Vec<RelicRewardValue> = relic
+                    .rewards
+                    .into_iter()
+                    .map(|reward| RelicRewardValue {
+                        price: reward
+                            .url_name
+                            .as_ref()
+                            .and_then(|url_name| prices.get(url_name).cloned()),
+                        url_name: reward.url_name,
+                        name: reward.name,
+                        rarity: reward.rarity,
+                    })
+                    .collect();
+                let refinements = REFINEMENT_CHANCES
+                    .iter()
+                    .map(|(refinement, chances)| RelicRefinementValue {
+                        refinement: refinement.to_string(),
+                        expected_value: rewards
+                            .iter()
+                            .filter_map(|reward| {
+                                let chance = chances[get_rarity_index(&reward.rarity)?];
+                                Some(chance / 100.0 * reward.price.unwrap_or(0.0))
+                            })
+                            .sum(),
+                    })
+                    .collect();
+                RelicValueStruct {
+                    name: relic.name,
+                    url_name: relic.url_name,
+                    tier: relic.tier,
+                    vaulted: relic.vaulted,
+                    rewards,
+                    values: refinements,
+                }
+            })
+            .collect();
+        // The radiant crack is worth the most, the best relics come first
+        let get_radiant = |relic: &RelicValueStruct| {
+            relic
+                .values
+                .last()
+                .map(|value| value.expected_value)
+                .unwrap_or(0.0)
+        };
+        values.sort_by(|a, b| get_radiant(b).total_cmp(&get_radiant(a)));
+        Ok(values)
+    }
+}
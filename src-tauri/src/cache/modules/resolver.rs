@@ -0,0 +1,220 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{cache::client::CacheClient, error::AppError, helper, logger, structs::Item};
+
+// Lookup tables built from the cached items, rebuilt whenever the items change
+#[derive(Clone, Debug, Default)]
+pub struct ItemIndex {
+    items: Vec<Item>,
+    by_id: HashMap<String, usize>,
+    by_url_name: HashMap<String, usize>,
+    // Lowercase names in every language that has been cached
+    by_name: HashMap<String, usize>,
+    // Lowercase names keyed by language, for names that mean different items in two languages
+    by_language: HashMap<String, HashMap<String, usize>>,
+}
+
+impl ItemIndex {
+    // Item by id, url name or name in any cached language
+    pub fn find(&self, value: &str) -> Option<&Item> {
+        self.by_id
+            .get(value)
+            .or_else(|| self.by_url_name.get(value))
+            .or_else(|| self.by_name.get(&ResolverModule::normalize(value)))
+            .map(|&i| &self.items[i])
+    }
+}
+
+pub struct ResolverModule<'a> {
+    pub client: &'a CacheClient,
+}
+
+impl<'a> ResolverModule<'a> {
+    fn normalize(name: &str) -> String {
+        name.trim().to_lowercase()
+    }
+
+    pub fn rebuild(&self) -> Result<(), AppError> {
+        let cache_data = self.client.cache_data.lock()?.clone();
+        let mut index = ItemIndex::default();
+        for (i, item) in cache_data.item.items.iter().enumerate() {
+            index.by_id.insert(item.id.clone(), i);
+            index.by_url_name.insert(item.url_name.clone(), i);
+            index.by_name.insert(Self::normalize(&item.item_name), i);
+        }
+        for (language, names) in cache_data.item.localized_names.iter() {
+            let by_language = index.by_language.entry(language.clone()).or_default();
+            for (url_name, name) in names {
+                if let Some(i) = index.by_url_name.get(url_name).cloned() {
+                    index.by_name.entry(Self::normalize(name)).or_insert(i);
+                    by_language.insert(Self::normalize(name), i);
+                }
+            }
+        }
+        index.items = cache_data.item.items;
+        *self.client.item_index.lock()? = Some(Arc::new(index));
+        Ok(())
+    }
+
+    // Shared with the cache, a lookup only copies the item it returns
+    fn get_index(&self) -> Result<Arc<ItemIndex>, AppError> {
+        if self.client.item_index.lock()?.is_none() {
+            self.rebuild()?;
+        }
+        Ok(self.client.item_index.lock()?.clone().unwrap_or_default())
+    }
+
+    pub fn by_id(&self, id: &str) -> Result<Option<Item>, AppError> {
+        let index = self.get_index()?;
+        Ok(index.by_id.get(id).map(|&i| index.items[i].clone()))
+    }
+
+    pub fn by_url_name(&self, url_name: &str) -> Result<Option<Item>, AppError> {
+        let index = self.get_index()?;
+        Ok(index
+            .by_url_name
+            .get(url_name)
+            .map(|&i| index.items[i].clone()))
+    }
+
+    // Localized or english name, falls back to the user aliases and the shipped abbreviations
+    pub fn by_name(&self, name: &str) -> Result<Option<Item>, AppError> {
+        let index = self.get_index()?;
+        if let Some(&i) = index.by_name.get(&Self::normalize(name)) {
+            return Ok(Some(index.items[i].clone()));
+        }
+        let (user_alias, expanded) = self.client.alias().resolve(name)?;
+        if let Some(url_name) = user_alias {
+            return Ok(index
+                .by_url_name
+                .get(&url_name)
+                .map(|&i| index.items[i].clone()));
+        }
+        let i = index
+            .by_url_name
+            .get(&expanded)
+            .or_else(|| index.by_name.get(&expanded.replace("_", " ")));
+        Ok(i.map(|&i| index.items[i].clone()))
+    }
+
+    // Name in the given language, falls back to the names of the other languages
+    pub fn by_name_in(&self, name: &str, language: &str) -> Result<Option<Item>, AppError> {
+        let index = self.get_index()?;
+        let i = index
+            .by_language
+            .get(language)
+            .and_then(|names| names.get(&Self::normalize(name)));
+        if let Some(&i) = i {
+            return Ok(Some(index.items[i].clone()));
+        }
+        self.by_name(name)
+    }
+
+    // Accepts an id, url name or name, in that order
+    pub fn resolve(&self, value: &str) -> Result<Option<Item>, AppError> {
+        if let Some(item) = self.by_id(value)? {
+            return Ok(Some(item));
+        }
+        if let Some(item) = self.by_url_name(value)? {
+            return Ok(Some(item));
+        }
+        let item = self.by_name(value)?;
+        if item.is_none() {
+            logger::warning_con(
+                "CacheResolver",
+                format!("Item: {} could not be resolved", value).as_str(),
+            );
+        }
+        Ok(item)
+    }
+
+    // Every item with its edit distance to the value, closest first.
+    // The value with its abbreviations expanded and the user aliases are scored as well.
+    fn get_scored(&self, value: &str) -> Result<Vec<(usize, Item)>, AppError> {
+        let index = self.get_index()?;
+        let (_, expanded) = self.client.alias().resolve(value)?;
+        let mut aliases: HashMap<String, Vec<String>> = HashMap::new();
+        for (alias, url_name) in self.client.alias().get_aliases()? {
+            aliases
+                .entry(url_name)
+                .or_default()
+                .push(alias.replace(" ", "_"));
+        }
+        let value = Self::normalize(value).replace(" ", "_");
+        let mut scored: Vec<(usize, Item)> = index
+            .items
+            .iter()
+            .map(|item| {
+                let name = Self::normalize(&item.item_name).replace(" ", "_");
+                let mut distance = helper::levenshtein(&value, &item.url_name)
+                    .min(helper::levenshtein(&value, &name))
+                    .min(helper::levenshtein(&expanded, &item.url_name));
+                for alias in aliases.get(&item.url_name).unwrap_or(&vec![]) {
+                    distance = distance.min(helper::levenshtein(&value, alias));
+                }
+                (distance, item.clone())
+            })
+            .collect();
+        scored.sort_by_key(|(distance, _)| *distance);
+        Ok(scored)
+    }
+
+    // Closest items by url name or name, used when a stored url no longer exists
+    pub fn suggest(&self, value: &str, limit: usize) -> Result<Vec<Item>, AppError> {
+        Ok(self
+            .get_scored(value)?
+            .into_iter()
+            .take(limit)
+            .map(|(_, item)| item)
+            .collect())
+    }
+
+    // Exact match first, otherwise the closest item when it is clearly the one meant.
+    // Ties are left unresolved so a typo never picks one of two similar items.
+    pub fn resolve_fuzzy(
+        &self,
+        value: &str,
+        max_distance: usize,
+    ) -> Result<Option<Item>, AppError> {
+        if let Some(item) = self.resolve(value)? {
+            return Ok(Some(item));
+        }
+        let scored = self.get_scored(value)?;
+        match scored.as_slice() {
+            [(best, item), (second, _), ..] if *best <= max_distance && best < second => {
+                Ok(Some(item.clone()))
+            }
+            [(best, item)] if *best <= max_distance => Ok(Some(item.clone())),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn get_url_name(&self, value: &str) -> Result<Option<String>, AppError> {
+        Ok(self.resolve(value)?.map(|item| item.url_name))
+    }
+
+    pub fn get_id(&self, value: &str) -> Result<Option<String>, AppError> {
+        Ok(self.resolve(value)?.map(|item| item.id))
+    }
+
+    // Name of the item in the given language, falls back to the cached name
+    pub fn get_localized_name(
+        &self,
+        value: &str,
+        language: &str,
+    ) -> Result<Option<String>, AppError> {
+        let item = match self.resolve(value)? {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+        let cache_data = self.client.cache_data.lock()?;
+        let name = cache_data
+            .item
+            .localized_names
+            .get(language)
+            .and_then(|names| names.get(&item.url_name))
+            .cloned()
+            .unwrap_or(item.item_name);
+        Ok(Some(name))
+    }
+}
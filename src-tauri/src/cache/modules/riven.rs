@@ -1,9 +1,14 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 use crate::{
-    cache::client::CacheClient,
+    cache::{client::CacheClient, modules::item::get_name_languages},
     error::AppError,
     logger,
     structs::{RivenAttributeInfo, RivenTypeInfo}, helper,
@@ -12,13 +17,147 @@ pub struct RivenModule<'a> {
     pub client: &'a CacheClient,
 }
 
+// Weekly sold prices of a weapon's rivens, from the trade statistics published by DE
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RivenSoldStatsStruct {
+    pub weapon_url: String,
+    pub rerolled: bool,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    pub median: f64,
+    pub stddev: f64,
+    // Number of trades
+    pub pop: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RivenPriceBands {
+    pub p10: i64,
+    pub p25: i64,
+    pub p50: i64,
+    pub p75: i64,
+    pub p90: i64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct WeeklyRivenEntry {
+    compatibility: Option<String>,
+    rerolled: bool,
+    min: f64,
+    max: f64,
+    avg: f64,
+    median: f64,
+    stddev: f64,
+    pop: f64,
+}
+
+impl RivenSoldStatsStruct {
+    // Only min, max, median and stddev are published, the bands assume a normal spread around the median
+    pub fn get_bands(&self) -> RivenPriceBands {
+        let band = |z: f64| {
+            (self.median + z * self.stddev)
+                .max(self.min)
+                .min(self.max)
+                .round() as i64
+        };
+        RivenPriceBands {
+            p10: band(-1.2816),
+            p25: band(-0.6745),
+            p50: band(0.0),
+            p75: band(0.6745),
+            p90: band(1.2816),
+        }
+    }
+
+    pub fn get_percentile(&self, percentile: i64) -> i64 {
+        let bands = self.get_bands();
+        match percentile {
+            p if p <= 10 => bands.p10,
+            p if p <= 25 => bands.p25,
+            p if p <= 50 => bands.p50,
+            p if p <= 75 => bands.p75,
+            _ => bands.p90,
+        }
+    }
+}
+
 impl<'a> RivenModule<'a> {
     // Refrece
     pub async fn refresh(&self) -> Result<(), AppError> {
         self.refresh_types().await?;
         self.refresh_attributes().await?;
+        self.refresh_sold_stats().await?;
+        Ok(())
+    }
+    fn get_sold_stats_url() -> &'static str {
+        "https://www-static.warframe.com/repos/weeklyRivensPC.json"
+    }
+    pub async fn refresh_sold_stats(&self) -> Result<(), AppError> {
+        helper::send_message_to_window(
+            "set_initializstatus",
+            Some(json!({"status": "Downloading Riven Sold Prices..."})),
+        );
+        let entries = match self.download_sold_stats().await {
+            Ok(entries) => entries,
+            Err(e) => {
+                // The feed is optional, keep the last known prices
+                logger::warning_con(
+                    "CacheRivens",
+                    format!("Could not download riven sold prices: {}", e.cause()).as_str(),
+                );
+                return Ok(());
+            }
+        };
+        // The feed uses the in-game weapon name, veiled rivens have no weapon and are skipped
+        let types = self.get_types()?;
+        let mut sold_stats = vec![];
+        for entry in entries {
+            let compatibility = match entry.compatibility {
+                Some(compatibility) => compatibility.to_lowercase(),
+                None => continue,
+            };
+            let riven_type = types
+                .iter()
+                .find(|t| t.item_name.to_lowercase() == compatibility);
+            if let Some(riven_type) = riven_type {
+                sold_stats.push(RivenSoldStatsStruct {
+                    weapon_url: riven_type.url_name.clone(),
+                    rerolled: entry.rerolled,
+                    min: entry.min,
+                    max: entry.max,
+                    avg: entry.avg,
+                    median: entry.median,
+                    stddev: entry.stddev,
+                    pop: entry.pop,
+                });
+            }
+        }
+        self.client.cache_data.lock()?.riven.sold_stats = sold_stats;
         Ok(())
     }
+    async fn download_sold_stats(&self) -> Result<Vec<WeeklyRivenEntry>, AppError> {
+        let content = self
+            .client
+            .sources()
+            .fetch("weekly_riven_sold_stats", Self::get_sold_stats_url())
+            .await?;
+        serde_json::from_str::<Vec<WeeklyRivenEntry>>(&content)
+            .map_err(|e| AppError::new("CacheRivens", eyre!(e.to_string())))
+    }
+    // Sold prices of the weapon's rivens, rerolled and unrolled rivens are tracked apart
+    pub fn get_sold_stats(
+        &self,
+        weapon_url: &str,
+        rerolled: Option<bool>,
+    ) -> Result<Vec<RivenSoldStatsStruct>, AppError> {
+        let sold_stats = self.client.cache_data.lock()?.riven.sold_stats.clone();
+        Ok(sold_stats
+            .into_iter()
+            .filter(|s| s.weapon_url == weapon_url)
+            .filter(|s| rerolled.map_or(true, |rerolled| s.rerolled == rerolled))
+            .collect())
+    }
     pub async fn refresh_types(&self) -> Result<Vec<RivenTypeInfo>, AppError> {
         let wfm = self.client.wfm.lock()?.clone();
         helper::send_message_to_window(
@@ -27,9 +166,41 @@ impl<'a> RivenModule<'a> {
         );
         let riven_types = wfm.auction().get_all_riven_types().await?;
 
+        // Names of the weapons in the region language and the ones the trade log may use
+        let mut language = wfm.auth.lock()?.region.clone();
+        if language == "" {
+            language = "en".to_string();
+        }
+        let mut localized_names = HashMap::new();
+        localized_names.insert(
+            language.clone(),
+            riven_types
+                .iter()
+                .map(|t| (t.url_name.clone(), t.item_name.clone()))
+                .collect::<HashMap<String, String>>(),
+        );
+        for extra in get_name_languages(&language) {
+            match wfm.with_language(&extra).auction().get_all_riven_types().await {
+                Ok(localized) => {
+                    localized_names.insert(
+                        extra,
+                        localized
+                            .into_iter()
+                            .map(|t| (t.url_name, t.item_name))
+                            .collect(),
+                    );
+                }
+                Err(e) => logger::warning_con(
+                    "CacheRivens",
+                    format!("No riven names in {}: {}", extra, e.cause()).as_str(),
+                ),
+            }
+        }
+
         let arced_mutex = Arc::clone(&self.client.cache_data);
         let mut my_lock = arced_mutex.lock()?;
         my_lock.riven.items = riven_types.clone();
+        my_lock.riven.localized_names.extend(localized_names);
         Ok(riven_types)
     }
     pub async fn refresh_attributes(&self) -> Result<Vec<RivenAttributeInfo>, AppError> {
@@ -66,6 +237,47 @@ impl<'a> RivenModule<'a> {
         Ok(riven_type)
     }
 
+    // Weapon name in the given language, e.g. "de", falls back to the other cached languages
+    pub fn find_by_name(
+        &self,
+        name: &str,
+        language: &str,
+    ) -> Result<Option<RivenTypeInfo>, AppError> {
+        let cache_data = self.client.cache_data.lock()?.clone();
+        let name = name.trim().to_lowercase();
+        let matches = |names: &HashMap<String, String>| {
+            names
+                .iter()
+                .find(|(_, n)| n.to_lowercase() == name)
+                .map(|(url_name, _)| url_name.clone())
+        };
+        let url_name = cache_data
+            .riven
+            .localized_names
+            .get(language)
+            .and_then(matches)
+            .or_else(|| cache_data.riven.localized_names.values().find_map(matches));
+        let riven_type = match url_name {
+            Some(url_name) => cache_data
+                .riven
+                .items
+                .into_iter()
+                .find(|t| t.url_name == url_name),
+            None => cache_data
+                .riven
+                .items
+                .into_iter()
+                .find(|t| t.item_name.to_lowercase() == name),
+        };
+        if riven_type.is_none() {
+            logger::warning_con(
+                "CacheRivens",
+                format!("Riven Name: {} not found", name).as_str(),
+            );
+        }
+        Ok(riven_type)
+    }
+
     pub fn find_attribute(
         &self,
         url_name: &str,
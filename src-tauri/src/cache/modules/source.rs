@@ -0,0 +1,133 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use eyre::eyre;
+use reqwest::{
+    header::{ETAG, IF_NONE_MATCH},
+    Client, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{cache::client::CacheClient, error::AppError, helper, logger};
+
+// A source file the cache is built from, as it was last downloaded
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SourceManifestEntry {
+    pub url: String,
+    pub etag: Option<String>,
+    pub hash: String,
+    pub updated: String,
+}
+
+pub struct SourceModule<'a> {
+    pub client: &'a CacheClient,
+}
+
+impl<'a> SourceModule<'a> {
+    // Stored next to cache.json, keyed by the source name
+    fn get_manifest_path() -> PathBuf {
+        helper::get_app_roaming_path().join("cache_manifest.json")
+    }
+
+    fn get_source_path(name: &str) -> PathBuf {
+        let dir = helper::get_app_roaming_path().join("cache_sources");
+        if !dir.exists() {
+            let _ = fs::create_dir_all(&dir);
+        }
+        dir.join(format!("{}.json", name))
+    }
+
+    pub fn get_manifest() -> HashMap<String, SourceManifestEntry> {
+        fs::read_to_string(Self::get_manifest_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_manifest(manifest: &HashMap<String, SourceManifestEntry>) -> Result<(), AppError> {
+        let json = serde_json::to_string_pretty(manifest)
+            .map_err(|e| AppError::new("CacheSources", eyre!(e.to_string())))?;
+        fs::write(Self::get_manifest_path(), json)
+            .map_err(|e| AppError::new("CacheSources", eyre!(e.to_string())))?;
+        Ok(())
+    }
+
+    fn hash(content: &str) -> String {
+        Sha256::digest(content.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    // The local copy when it still matches the hash in the manifest
+    fn read_local(name: &str, entry: &SourceManifestEntry) -> Option<String> {
+        let content = fs::read_to_string(Self::get_source_path(name)).ok()?;
+        if Self::hash(&content) != entry.hash {
+            return None;
+        }
+        Some(content)
+    }
+
+    // Downloads the source only when it changed since the last time, otherwise the local copy
+    // is used. Sends the stored ETag and compares the hash for servers that ignore it.
+    pub async fn fetch(&self, name: &str, url: &str) -> Result<String, AppError> {
+        let mut manifest = Self::get_manifest();
+        let cached = manifest
+            .get(name)
+            .filter(|entry| entry.url == url)
+            .and_then(|entry| Some((entry.clone(), Self::read_local(name, entry)?)));
+
+        let mut request = Client::new().get(url);
+        if let Some((entry, _)) = &cached {
+            if let Some(etag) = &entry.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| AppError::new("CacheSources", eyre!(e.to_string())))?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some((_, content)) = cached {
+                logger::info_con("CacheSources", format!("{} is unchanged", name).as_str());
+                return Ok(content);
+            }
+        }
+        if !response.status().is_success() {
+            return Err(AppError::new(
+                "CacheSources",
+                eyre!("Could not download {}. Status: {}", name, response.status()),
+            ));
+        }
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(|etag| etag.to_string());
+        let content = response
+            .text()
+            .await
+            .map_err(|e| AppError::new("CacheSources", eyre!(e.to_string())))?;
+        let hash = Self::hash(&content);
+        let unchanged = matches!(&cached, Some((entry, _)) if entry.hash == hash);
+        if !unchanged {
+            fs::write(Self::get_source_path(name), &content)
+                .map_err(|e| AppError::new("CacheSources", eyre!(e.to_string())))?;
+        }
+        manifest.insert(
+            name.to_string(),
+            SourceManifestEntry {
+                url: url.to_string(),
+                etag,
+                hash,
+                updated: helper::get_utc_timestamp(),
+            },
+        );
+        Self::save_manifest(&manifest)?;
+        if !unchanged {
+            logger::info_con("CacheSources", format!("Downloaded {}", name).as_str());
+        }
+        Ok(content)
+    }
+}
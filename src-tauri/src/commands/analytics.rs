@@ -0,0 +1,66 @@
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    database::{
+        client::DBClient,
+        modules::analytics::{DucatAnalysisStruct, ItemTurnoverStruct, TradeAnalyticsStruct},
+    },
+    error::{self, AppError},
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> =
+    Lazy::new(|| Mutex::new("command_analytics.log".to_string()));
+
+// Group is hour, day, week or month, in the given timezone or the local one
+#[tauri::command]
+pub async fn get_trade_analytics(
+    group: String,
+    utc_offset_minutes: Option<i32>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<TradeAnalyticsStruct, AppError> {
+    let db = db.lock()?.clone();
+    match db
+        .analytics()
+        .get_trade_analytics(&group, utc_offset_minutes)
+        .await
+    {
+        Ok(analytics) => Ok(analytics),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_item_turnover(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<ItemTurnoverStruct>, AppError> {
+    let db = db.lock()?.clone();
+    match db.analytics().get_item_turnover().await {
+        Ok(items) => Ok(items),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Stock and market items ranked by ducats per plat, the market list holds 50 items unless a limit is given
+#[tauri::command]
+pub async fn get_ducat_analysis(
+    limit: Option<usize>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<DucatAnalysisStruct, AppError> {
+    let db = db.lock()?.clone();
+    match db.analytics().get_ducat_analysis(limit.unwrap_or(50)).await {
+        Ok(analysis) => Ok(analysis),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    api_tokens::{ApiTokenClient, ApiTokenStruct, CreatedApiTokenStruct},
+    error::{self, AppError},
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> =
+    Lazy::new(|| Mutex::new("command_api_tokens.log".to_string()));
+
+#[tauri::command]
+pub fn get_api_tokens(
+    api_tokens: tauri::State<'_, Arc<Mutex<ApiTokenClient>>>,
+) -> Result<Vec<ApiTokenStruct>, AppError> {
+    let api_tokens = api_tokens.lock()?.clone();
+    api_tokens.get_tokens()
+}
+
+// The returned token is not stored and can't be shown again
+#[tauri::command]
+pub fn create_api_token(
+    name: String,
+    scopes: Vec<String>,
+    rate_limit: i64,
+    api_tokens: tauri::State<'_, Arc<Mutex<ApiTokenClient>>>,
+) -> Result<CreatedApiTokenStruct, AppError> {
+    let api_tokens = api_tokens.lock()?.clone();
+    match api_tokens.create(&name, scopes, rate_limit) {
+        Ok(token) => Ok(token),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn update_api_token(
+    id: String,
+    scopes: Vec<String>,
+    rate_limit: i64,
+    api_tokens: tauri::State<'_, Arc<Mutex<ApiTokenClient>>>,
+) -> Result<ApiTokenStruct, AppError> {
+    let api_tokens = api_tokens.lock()?.clone();
+    match api_tokens.update(&id, scopes, rate_limit) {
+        Ok(token) => Ok(token),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn revoke_api_token(
+    id: String,
+    api_tokens: tauri::State<'_, Arc<Mutex<ApiTokenClient>>>,
+) -> Result<(), AppError> {
+    let api_tokens = api_tokens.lock()?.clone();
+    match api_tokens.revoke(&id) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
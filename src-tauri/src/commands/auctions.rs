@@ -1,6 +1,8 @@
+use eyre::eyre;
 use once_cell::sync::Lazy;
 
 use crate::{
+    enums::ErrorKind,
     error::{self, AppError},
     helper,
     wfm_client::client::WFMClient,
@@ -27,3 +29,72 @@ pub async fn refresh_auctions(
         }
     }
 }
+
+#[tauri::command]
+pub async fn update_auction_price(
+    auction_id: String,
+    price: i64,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+) -> Result<serde_json::Value, AppError> {
+    let wfm = wfm.lock()?.clone();
+    let auction = match wfm.auction().get_my_auctions().await {
+        Ok(auctions) => auctions.into_iter().find(|a| a.id == auction_id),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    };
+    let auction = match auction {
+        Some(auction) => auction,
+        None => {
+            let e = AppError::new(
+                "Command:UpdateAuctionPrice",
+                eyre!("Auction {} was not found", auction_id),
+            )
+            .with_kind(ErrorKind::NotFound);
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    };
+    match wfm
+        .auction()
+        .update_price(&auction, price, auction.visible)
+        .await
+    {
+        Ok(auction) => Ok(serde_json::to_value(auction).unwrap()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn close_auction(
+    auction_id: String,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+) -> Result<(), AppError> {
+    let wfm = wfm.lock()?.clone();
+    match wfm.auction().close(&auction_id).await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn delete_auction(
+    auction_id: String,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+) -> Result<(), AppError> {
+    let wfm = wfm.lock()?.clone();
+    match wfm.auction().delete(&auction_id).await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    backup::BackupClient,
+    error::{self, AppError},
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_backup.log".to_string()));
+
+#[tauri::command]
+pub async fn backup_now(
+    backup: tauri::State<'_, Arc<Mutex<BackupClient>>>,
+) -> Result<String, AppError> {
+    let backup = backup.lock()?.clone();
+    match backup.backup_to_remote().await {
+        Ok(name) => Ok(name),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn list_remote_backups(
+    backup: tauri::State<'_, Arc<Mutex<BackupClient>>>,
+) -> Result<Vec<String>, AppError> {
+    let backup = backup.lock()?.clone();
+    match backup.list_remote().await {
+        Ok(names) => Ok(names),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn restore_remote_backup(
+    name: String,
+    backup: tauri::State<'_, Arc<Mutex<BackupClient>>>,
+) -> Result<(), AppError> {
+    let backup = backup.lock()?.clone();
+    match backup.restore_from_remote(&name).await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
@@ -17,7 +17,7 @@ static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_base.log
 
 use crate::{
     auth::AuthState,
-    cache::client::CacheClient,
+    cache::{client::CacheClient, modules::feature_flag::WEBSOCKET_EVENTS},
     database::client::DBClient,
     enums::LogLevel,
     error::{self, AppError},
@@ -25,6 +25,7 @@ use crate::{
     helper, logger,
     price_scraper::PriceScraper,
     settings::SettingsState,
+    support_bundle::{SupportBundle, SupportBundleManifest},
     wf_ee_log_parser::client::EELogParser,
     wfm_client::client::WFMClient,
     PACKAGEINFO,
@@ -41,6 +42,7 @@ pub async fn init(
     price_scraper: tauri::State<'_, Arc<Mutex<PriceScraper>>>,
     ee_log: tauri::State<'_, Arc<std::sync::Mutex<EELogParser>>>,
     db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    mh: tauri::State<'_, Arc<Mutex<MonitorHandler>>>,
 ) -> Result<Value, AppError> {
     let db = db.lock()?.clone();
     let mut ee_log = ee_log.lock()?.clone();
@@ -71,6 +73,9 @@ pub async fn init(
             response["items"] = json!(cache.items().get_types()?);
             response["riven_items"] = json!(cache.riven().get_types()?);
             response["riven_attributes"] = json!(cache.riven().get_attributes()?);
+            response["item_aliases"] = json!(cache.alias().get_aliases()?);
+            response["feature_flags"] = json!(cache.feature_flags().get_resolved(&settings)?);
+            response["cache_degraded"] = json!(cache.is_degraded());
         }
         Err(e) => {
             error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
@@ -103,6 +108,16 @@ pub async fn init(
             return Err(e);
         }
     };
+    // Stock items the cache no longer knows about, the user picks a replacement or archives them
+    match db.stock_item().get_cache_conflicts().await {
+        Ok(conflicts) => {
+            response["stock_item_conflicts"] = json!(conflicts);
+        }
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    };
     // Load Stock Rivens
     match db.stock_riven().get_rivens().await {
         Ok(items) => {
@@ -126,6 +141,17 @@ pub async fn init(
         }
     };
 
+    // Bulk order operations the last session did not finish
+    match db.order_journal().get_entries(None).await {
+        Ok(entries) => {
+            response["order_journal"] = json!(entries);
+        }
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    };
+
     if is_validate {
         helper::emit_undate_initializ_status("Loading Your Orders...", None);
         let mut ordres_vec = match wfm.orders().get_my_orders().await {
@@ -137,6 +163,30 @@ pub async fn init(
         };
         let mut ordres = ordres_vec.buy_orders;
         ordres.append(&mut ordres_vec.sell_orders);
+
+        // Duplicates left behind by a crash would be undercutting each other
+        helper::emit_undate_initializ_status("Checking For Duplicate Orders...", None);
+        match super::orders::cleanup_duplicate_orders(&wfm, &db, &ordres).await {
+            Ok(deleted) => {
+                if !deleted.is_empty() {
+                    ordres.retain(|order| !deleted.iter().any(|d| d.id == order.id));
+                    mh.lock()?.show_notification(
+                        "Duplicate Orders Removed",
+                        format!(
+                            "Deleted {} duplicate orders, the better priced one of each was kept",
+                            deleted.len()
+                        )
+                        .as_str(),
+                        None,
+                        None,
+                    );
+                }
+            }
+            Err(e) => {
+                error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+                return Err(e);
+            }
+        }
         response["orders"] = json!(ordres);
 
         helper::emit_undate_initializ_status("Loading Your Auctions...", None);
@@ -189,10 +239,75 @@ pub async fn update_settings(
     // Set Whisper Scraper Settings
     my_lock.notifications = settings.notifications;
 
+    // Set EE Log Settings
+    my_lock.ee_log_path = settings.ee_log_path;
+    my_lock.ee_log_paths = settings.ee_log_paths;
+
+    // Set Backup Settings
+    my_lock.backup = settings.backup;
+
+    // Set Feature Flags
+    my_lock.feature_flags = settings.feature_flags;
+
+    // Set Warframe Market Cache
+    my_lock.wfm_cache_ttl = settings.wfm_cache_ttl;
+
+    // Set Warframe Market Retry Policy
+    my_lock.wfm_retry = settings.wfm_retry;
+
+    // Set Friends
+    my_lock.friends = settings.friends;
+
+    // Set Dashboard Settings
+    my_lock.dashboard = settings.dashboard;
+
+    // Set Whisper Templates
+    my_lock.whisper_templates = settings.whisper_templates;
+
+    // Set Profile Sync Settings, the sync state is only changed by the sync itself
+    let last_synced_hash = my_lock.profile_sync.last_synced_hash.clone();
+    my_lock.profile_sync = settings.profile_sync;
+    my_lock.profile_sync.last_synced_hash = last_synced_hash;
+
+    // Set Deal Hunter Settings
+    my_lock.deal_hunter = settings.deal_hunter;
+
+    // Set Price History Retention
+    my_lock.price_history = settings.price_history;
+
+    // Set Price Backfill Window
+    my_lock.price_backfill_days = settings.price_backfill_days;
+
+    // Set Trade Watcher Settings
+    my_lock.trade_watcher = settings.trade_watcher;
+
+    // Set Logging Settings
+    logger::configure(&settings.logging);
+    my_lock.logging = settings.logging;
+
     my_lock.save_to_file().expect("Could not save settings");
     Ok(())
 }
 
+#[tauri::command]
+pub fn flush_event_buffer() -> Value {
+    helper::flush_event_buffer()
+}
+
+#[tauri::command]
+pub fn get_event_buffer_metrics() -> Value {
+    helper::EVENT_BUFFER.lock().unwrap().get_metrics()
+}
+
+// Places EE.log was looked for, so the settings can offer the ones that exist
+#[tauri::command]
+pub fn get_ee_log_candidates() -> Vec<Value> {
+    crate::wf_ee_log_parser::paths::get_candidates()
+        .into_iter()
+        .map(|path| json!({ "path": path.to_str().unwrap_or(""), "exists": path.exists() }))
+        .collect()
+}
+
 #[tauri::command]
 pub async fn open_logs_folder() {
     Command::new("explorer")
@@ -224,10 +339,22 @@ pub fn on_new_wfm_message(
     auth: tauri::State<'_, Arc<Mutex<AuthState>>>,  
     settings: tauri::State<'_, Arc<std::sync::Mutex<SettingsState>>>,
     mh: tauri::State<'_, Arc<std::sync::Mutex<MonitorHandler>>>,
+    cache: tauri::State<'_, Arc<std::sync::Mutex<CacheClient>>>,
 ) {
     let mh = mh.lock().unwrap();
     let auth = auth.lock().unwrap().clone();
-    let settings = settings.lock().unwrap().clone().notifications.on_wfm_chat_message;
+    let settings = settings.lock().unwrap().clone();
+    let cache = cache.lock().unwrap().clone();
+
+    // Websocket events can be turned off remotely if they misbehave
+    if !cache
+        .feature_flags()
+        .is_enabled(WEBSOCKET_EVENTS, &settings)
+        .unwrap_or(true)
+    {
+        return;
+    }
+    let settings = settings.notifications.on_wfm_chat_message;
 
     if auth.id == message.message_from {
         return;
@@ -270,6 +397,73 @@ pub fn log(
     logger::dolog(level, &component, &msg, console, file);
 }
 
+async fn collect_support_bundle(
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+    auth: tauri::State<'_, Arc<Mutex<AuthState>>>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    cache: tauri::State<'_, Arc<Mutex<CacheClient>>>,
+) -> Result<SupportBundle, AppError> {
+    let settings = settings.lock()?.clone();
+    let auth = auth.lock()?.clone();
+    let db = db.lock()?.clone();
+    let cache = cache.lock()?.clone();
+    SupportBundle::collect(&settings, &auth, &db, &cache).await
+}
+
+// Shows what the support bundle will contain before the user agrees to create it
+#[tauri::command]
+pub async fn preview_support_bundle(
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+    auth: tauri::State<'_, Arc<Mutex<AuthState>>>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    cache: tauri::State<'_, Arc<Mutex<CacheClient>>>,
+) -> Result<SupportBundleManifest, AppError> {
+    match collect_support_bundle(settings, auth, db, cache).await {
+        Ok(bundle) => Ok(bundle.get_manifest()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Returns the path of the zip on the desktop
+#[tauri::command]
+pub async fn generate_support_bundle(
+    consent: bool,
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+    auth: tauri::State<'_, Arc<Mutex<AuthState>>>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    cache: tauri::State<'_, Arc<Mutex<CacheClient>>>,
+) -> Result<String, AppError> {
+    let bundle = match collect_support_bundle(settings, auth, db, cache).await {
+        Ok(bundle) => bundle,
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    };
+    match bundle.write(consent) {
+        Ok(path) => Ok(path),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Newest app log entries for the log viewer
+#[tauri::command]
+pub fn get_logs(filter: Option<logger::LogFilter>) -> Vec<logger::LogEntry> {
+    logger::query(&filter.unwrap_or_default())
+}
+
+// Sends new entries matching the filter as they are logged, None stops the stream
+#[tauri::command]
+pub fn stream_logs(filter: Option<logger::LogFilter>) {
+    logger::set_stream_filter(filter);
+}
+
 #[tauri::command]
 pub fn export_logs(mh: tauri::State<'_, Arc<std::sync::Mutex<MonitorHandler>>>) {
     logger::export_logs();
@@ -0,0 +1,226 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use once_cell::sync::Lazy;
+use serde_json::json;
+
+use crate::{
+    cache::{
+        client::CacheClient,
+        modules::{item::ItemConstraintsStruct, relic::RelicValueStruct},
+    },
+    database::client::DBClient,
+    error::{self, AppError},
+    settings::SettingsState,
+    structs::Item,
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_cache.log".to_string()));
+
+#[tauri::command]
+pub fn get_item_aliases(
+    cache: tauri::State<'_, Arc<Mutex<CacheClient>>>,
+) -> Result<HashMap<String, String>, AppError> {
+    let cache = cache.lock()?.clone();
+    match cache.alias().get_aliases() {
+        Ok(aliases) => Ok(aliases),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn add_item_alias(
+    alias: String,
+    url_name: String,
+    cache: tauri::State<'_, Arc<Mutex<CacheClient>>>,
+) -> Result<HashMap<String, String>, AppError> {
+    let cache = cache.lock()?.clone();
+    match cache.alias().add(&alias, &url_name) {
+        Ok(aliases) => Ok(aliases),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn remove_item_alias(
+    alias: String,
+    cache: tauri::State<'_, Arc<Mutex<CacheClient>>>,
+) -> Result<HashMap<String, String>, AppError> {
+    let cache = cache.lock()?.clone();
+    match cache.alias().remove(&alias) {
+        Ok(aliases) => Ok(aliases),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Weekly sold prices of the weapon's rivens with percentile bands, one entry for rerolled and one for unrolled
+#[tauri::command]
+pub fn get_riven_sold_stats(
+    weapon: String,
+    cache: tauri::State<'_, Arc<Mutex<CacheClient>>>,
+) -> Result<serde_json::Value, AppError> {
+    let cache = cache.lock()?.clone();
+    match cache.riven().get_sold_stats(&weapon, None) {
+        Ok(sold_stats) => Ok(json!(sold_stats
+            .iter()
+            .map(|s| json!({ "stats": s, "bands": s.get_bands() }))
+            .collect::<Vec<_>>())),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_feature_flags(
+    cache: tauri::State<'_, Arc<Mutex<CacheClient>>>,
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+) -> Result<HashMap<String, bool>, AppError> {
+    let cache = cache.lock()?.clone();
+    let settings = settings.lock()?.clone();
+    match cache.feature_flags().get_resolved(&settings) {
+        Ok(flags) => Ok(flags),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Opt in or out of a feature, None goes back to the default
+#[tauri::command]
+pub fn set_feature_flag(
+    name: String,
+    enabled: Option<bool>,
+    cache: tauri::State<'_, Arc<Mutex<CacheClient>>>,
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+) -> Result<HashMap<String, bool>, AppError> {
+    let cache = cache.lock()?.clone();
+    let mut settings = settings.lock()?;
+    match enabled {
+        Some(enabled) => settings.feature_flags.insert(name, enabled),
+        None => settings.feature_flags.remove(&name),
+    };
+    match settings.save_to_file() {
+        Ok(_) => {}
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+    match cache.feature_flags().get_resolved(&settings) {
+        Ok(flags) => Ok(flags),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Same lookup the log parser and the live scraper use, accepts an id, url name or name
+#[tauri::command]
+pub fn resolve_item(
+    value: String,
+    cache: tauri::State<'_, Arc<Mutex<CacheClient>>>,
+) -> Result<Option<Item>, AppError> {
+    let cache = cache.lock()?.clone();
+    match cache.resolver().resolve(&value) {
+        Ok(item) => Ok(item),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Max rank and valid variants of an item, None when the cache does not know it
+#[tauri::command]
+pub fn get_item_constraints(
+    url_name: String,
+    cache: tauri::State<'_, Arc<Mutex<CacheClient>>>,
+) -> Result<Option<ItemConstraintsStruct>, AppError> {
+    let cache = cache.lock()?.clone();
+    match cache.items().get_constraints(&url_name) {
+        Ok(constraints) => Ok(constraints),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Picks up manual edits to cache.json, category is item, riven, relic or feature_flags
+#[tauri::command]
+pub fn reload_cache_file(
+    category: String,
+    cache: tauri::State<'_, Arc<Mutex<CacheClient>>>,
+) -> Result<(), AppError> {
+    let cache = cache.lock()?.clone();
+    match cache.reload_category(&category) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Expected plat per crack of the relics by refinement, priced at the weekly closed median of the rewards.
+// All relics when no url names are given.
+#[tauri::command]
+pub async fn get_relic_values(
+    relics: Option<Vec<String>>,
+    cache: tauri::State<'_, Arc<Mutex<CacheClient>>>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<RelicValueStruct>, AppError> {
+    let cache = cache.lock()?.clone();
+    let db = db.lock()?.clone();
+    let result = async {
+        let mut rewards: Vec<String> = cache
+            .relics()
+            .get_relics()?
+            .into_iter()
+            .flat_map(|relic| relic.rewards)
+            .filter_map(|reward| reward.url_name)
+            .collect();
+        rewards.sort();
+        rewards.dedup();
+        let prices: HashMap<String, f64> = db
+            .item_price()
+            .get_averages(&rewards)
+            .await?
+            .into_iter()
+            .filter(|average| average.order_type == "closed" && average.median > 0.0)
+            .map(|average| (average.name, average.median))
+            .collect();
+        let values = cache.relics().get_values(&prices)?;
+        Ok(match relics {
+            Some(relics) => values
+                .into_iter()
+                .filter(|value| relics.contains(&value.url_name))
+                .collect(),
+            None => values,
+        })
+    }
+    .await;
+    match result {
+        Ok(values) => Ok(values),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
@@ -4,6 +4,7 @@ use once_cell::sync::Lazy;
 
 use crate::{
     debug::DebugClient,
+    wfm_client::cache::ResponseCacheStats,
     error::{self, AppError},
 };
 
@@ -38,3 +39,18 @@ pub async fn reset_data(
     debug.reset_data(reset_type).await?;
     Ok(())
 }
+
+#[tauri::command]
+pub fn get_wfm_cache_stats(
+    reset: Option<bool>,
+    debug: tauri::State<'_, Arc<Mutex<DebugClient>>>,
+) -> Result<ResponseCacheStats, AppError> {
+    let debug = debug.lock()?.clone();
+    match debug.get_wfm_cache_stats(reset.unwrap_or(false)) {
+        Ok(stats) => Ok(stats),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
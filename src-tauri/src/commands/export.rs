@@ -0,0 +1,30 @@
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    database::client::DBClient,
+    error::{self, AppError},
+    export,
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_export.log".to_string()));
+
+// Table is stock_items, stock_rivens or transactions, the path comes from the save dialog.
+// Returns the path of the written file.
+#[tauri::command]
+pub async fn export_table(
+    table: String,
+    path: String,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<String, AppError> {
+    let db = db.lock()?.clone();
+    match export::export(&db, &table, &path).await {
+        Ok(path) => Ok(path),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::{Arc, Mutex};
+
+use eyre::eyre;
+use once_cell::sync::Lazy;
+
+use crate::{
+    enums::{ErrorKind, LogLevel},
+    error::{self, AppError},
+    settings::SettingsState,
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_friends.log".to_string()));
+
+fn add_friends(
+    names: Vec<String>,
+    settings: &Arc<Mutex<SettingsState>>,
+) -> Result<Vec<String>, AppError> {
+    let mut settings = settings.lock()?;
+    settings.friends.add(names);
+    settings.save_to_file()?;
+    Ok(settings.friends.names.clone())
+}
+
+#[tauri::command]
+pub fn add_friend(
+    name: String,
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+) -> Result<Vec<String>, AppError> {
+    match add_friends(vec![name], &settings) {
+        Ok(names) => Ok(names),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn remove_friend(
+    name: String,
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+) -> Result<Vec<String>, AppError> {
+    let mut settings = settings.lock()?;
+    settings
+        .friends
+        .names
+        .retain(|friend| !friend.eq_ignore_ascii_case(name.trim()));
+    match settings.save_to_file() {
+        Ok(_) => Ok(settings.friends.names.clone()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Imports names from a text file, one per line or comma separated, or a JSON list of names
+#[tauri::command]
+pub fn import_friends(
+    path: String,
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+) -> Result<Vec<String>, AppError> {
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => {
+            let e = AppError::new_with_level(
+                "Friends",
+                eyre!("Could not read {}: {}", path, e),
+                LogLevel::Warning,
+            )
+            .with_kind(ErrorKind::NotFound);
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    };
+    let names: Vec<String> = match serde_json::from_str::<Vec<String>>(&content) {
+        Ok(names) => names,
+        Err(_) => content
+            .split(|c| c == '\n' || c == ',')
+            .map(|name| name.trim().to_string())
+            .collect(),
+    };
+    match add_friends(names, &settings) {
+        Ok(names) => Ok(names),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::sync::{Arc, Mutex};
+
+use eyre::eyre;
+use once_cell::sync::Lazy;
+
+use crate::{
+    cache::client::CacheClient,
+    enums::{ErrorKind, LogLevel},
+    error::{self, AppError},
+    helper,
+    settings::{ItemListEntry, SettingsState},
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> =
+    Lazy::new(|| Mutex::new("command_item_lists.log".to_string()));
+
+fn add_entry(
+    entry: ItemListEntry,
+    settings: &Arc<Mutex<SettingsState>>,
+    cache: &Arc<Mutex<CacheClient>>,
+) -> Result<Vec<ItemListEntry>, AppError> {
+    let cache = cache.lock()?.clone();
+    if cache.items().find_type(&entry.url_name)?.is_none() {
+        return Err(AppError::new_with_level(
+            "ItemLists",
+            eyre!("{} is not a tradable item", entry.url_name),
+            LogLevel::Warning,
+        )
+        .with_kind(ErrorKind::Validation));
+    }
+    if let Some(expires_at) = &entry.expires_at {
+        match helper::parse_timestamp(expires_at) {
+            Some(expires_at) if expires_at > chrono::Utc::now() => {}
+            _ => {
+                return Err(AppError::new_with_level(
+                    "ItemLists",
+                    eyre!("The expiry {} has to be a date in the future", expires_at),
+                    LogLevel::Warning,
+                )
+                .with_kind(ErrorKind::Validation))
+            }
+        }
+    }
+    let mut settings = settings.lock()?;
+    settings
+        .live_scraper
+        .stock_item
+        .add_list_entry(entry)
+        .map_err(|e| e.with_kind(ErrorKind::Validation))?;
+    settings.save_to_file()?;
+    Ok(settings.live_scraper.stock_item.list_entries.clone())
+}
+
+// Entries of both lists, items added without metadata have none
+#[tauri::command]
+pub fn get_item_list_entries(
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+) -> Result<Vec<ItemListEntry>, AppError> {
+    Ok(settings
+        .lock()?
+        .live_scraper
+        .stock_item
+        .list_entries
+        .clone())
+}
+
+// list is blacklist or whitelist, expires_at is an RFC3339 date
+#[tauri::command]
+pub fn add_item_list_entry(
+    list: String,
+    url_name: String,
+    reason: Option<String>,
+    category: Option<String>,
+    expires_at: Option<String>,
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+    cache: tauri::State<'_, Arc<Mutex<CacheClient>>>,
+) -> Result<Vec<ItemListEntry>, AppError> {
+    let entry = ItemListEntry {
+        url_name: url_name.trim().to_string(),
+        list,
+        reason: reason.unwrap_or_default(),
+        category: category.unwrap_or_default(),
+        expires_at,
+        created: helper::get_utc_timestamp(),
+    };
+    match add_entry(entry, &settings, &cache) {
+        Ok(entries) => Ok(entries),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn remove_item_list_entry(
+    list: String,
+    url_name: String,
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+) -> Result<Vec<ItemListEntry>, AppError> {
+    let mut settings = settings.lock()?;
+    settings
+        .live_scraper
+        .stock_item
+        .remove_list_entry(&list, &url_name);
+    match settings.save_to_file() {
+        Ok(_) => Ok(settings.live_scraper.stock_item.list_entries.clone()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
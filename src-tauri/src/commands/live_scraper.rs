@@ -1,8 +1,22 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 use once_cell::sync::Lazy;
 
-use crate::{error, live_scraper::client::LiveScraperClient, logger::error};
+use crate::{
+    database::{
+        client::DBClient,
+        modules::shadow_decision::{ShadowDecisionStruct, ShadowSummaryStruct},
+    },
+    error::{self, AppError},
+    live_scraper::{
+        client::{BuyAction, LiveScraperClient, PartialSale, PriceOverride},
+        modules::{liquidation::LiquidationPlanStruct, supervisor::RestartRecord},
+    },
+    logger::error,
+};
 
 // Create a static variable to store the log file name
 static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_live_scraper.log".to_string()));
@@ -23,3 +37,214 @@ pub fn toggle_live_scraper(
         }
     }
 }
+
+#[tauri::command]
+pub async fn set_price_override(
+    stock_id: i64,
+    price: i64,
+    hours: i64,
+    live_scraper: tauri::State<'_, Arc<std::sync::Mutex<LiveScraperClient>>>,
+) -> Result<PriceOverride, AppError> {
+    let live_scraper = live_scraper.lock()?.clone();
+    match live_scraper.item().set_price_override(stock_id, price, hours).await {
+        Ok(price_override) => Ok(price_override),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn clear_price_override(
+    stock_id: i64,
+    live_scraper: tauri::State<'_, Arc<std::sync::Mutex<LiveScraperClient>>>,
+) -> Result<(), AppError> {
+    let live_scraper = live_scraper.lock()?.clone();
+    match live_scraper.item().clear_price_override(stock_id) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_price_overrides(
+    live_scraper: tauri::State<'_, Arc<std::sync::Mutex<LiveScraperClient>>>,
+) -> Result<HashMap<i64, PriceOverride>, AppError> {
+    let live_scraper = live_scraper.lock()?.clone();
+    let price_overrides = live_scraper.price_overrides.lock()?.clone();
+    Ok(price_overrides)
+}
+
+#[tauri::command]
+pub fn get_partial_sales(
+    live_scraper: tauri::State<'_, Arc<std::sync::Mutex<LiveScraperClient>>>,
+) -> Result<Vec<PartialSale>, AppError> {
+    let live_scraper = live_scraper.lock()?.clone();
+    match live_scraper.partial_sale().get_pending() {
+        Ok(partial_sales) => Ok(partial_sales),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn confirm_partial_sale(
+    order_id: String,
+    live_scraper: tauri::State<'_, Arc<std::sync::Mutex<LiveScraperClient>>>,
+) -> Result<(), AppError> {
+    let live_scraper = live_scraper.lock()?.clone();
+    match live_scraper.partial_sale().confirm(&order_id).await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn dismiss_partial_sale(
+    order_id: String,
+    live_scraper: tauri::State<'_, Arc<std::sync::Mutex<LiveScraperClient>>>,
+) -> Result<(), AppError> {
+    let live_scraper = live_scraper.lock()?.clone();
+    match live_scraper.partial_sale().dismiss(&order_id) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_buy_actions(
+    live_scraper: tauri::State<'_, Arc<std::sync::Mutex<LiveScraperClient>>>,
+) -> Result<Vec<BuyAction>, AppError> {
+    let live_scraper = live_scraper.lock()?.clone();
+    match live_scraper.action_queue().get_pending() {
+        Ok(actions) => Ok(actions),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Returns false when the seller has to be whispered in game
+#[tauri::command]
+pub async fn approve_buy_action(
+    id: String,
+    live_scraper: tauri::State<'_, Arc<std::sync::Mutex<LiveScraperClient>>>,
+) -> Result<bool, AppError> {
+    let live_scraper = live_scraper.lock()?.clone();
+    match live_scraper.action_queue().approve(&id).await {
+        Ok(whispered) => Ok(whispered),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn dismiss_buy_action(
+    id: String,
+    live_scraper: tauri::State<'_, Arc<std::sync::Mutex<LiveScraperClient>>>,
+) -> Result<(), AppError> {
+    let live_scraper = live_scraper.lock()?.clone();
+    match live_scraper.action_queue().dismiss(&id) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Buyers to whisper for selling the selected stock items right away
+#[tauri::command]
+pub async fn get_liquidation_suggestions(
+    stock_ids: Vec<i64>,
+    min_price: Option<i64>,
+    live_scraper: tauri::State<'_, Arc<std::sync::Mutex<LiveScraperClient>>>,
+) -> Result<LiquidationPlanStruct, AppError> {
+    let live_scraper = live_scraper.lock()?.clone();
+    match live_scraper
+        .liquidation()
+        .get_suggestions(stock_ids, min_price)
+        .await
+    {
+        Ok(plan) => Ok(plan),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Prices where the shadow strategy disagreed, newest first
+#[tauri::command]
+pub async fn get_shadow_decisions(
+    strategy: Option<String>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<ShadowDecisionStruct>, AppError> {
+    let db = db.lock()?.clone();
+    match db.shadow_decision().get_items(strategy).await {
+        Ok(decisions) => Ok(decisions),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_shadow_summary(
+    strategy: Option<String>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<ShadowSummaryStruct>, AppError> {
+    let db = db.lock()?.clone();
+    match db.shadow_decision().get_summary(strategy).await {
+        Ok(summary) => Ok(summary),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn clear_shadow_decisions(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<(), AppError> {
+    let db = db.lock()?.clone();
+    match db.shadow_decision().clear().await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Errors that ended a pass this session and whether the scraper retried or stopped
+#[tauri::command]
+pub fn get_scraper_restarts(
+    live_scraper: tauri::State<'_, Arc<std::sync::Mutex<LiveScraperClient>>>,
+) -> Result<Vec<RestartRecord>, AppError> {
+    let live_scraper = live_scraper.lock()?.clone();
+    match live_scraper.supervisor().get_history() {
+        Ok(history) => Ok(history),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
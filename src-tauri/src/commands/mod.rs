@@ -1,10 +1,24 @@
+pub mod analytics;
+pub mod api_tokens;
 pub mod auctions;
 pub mod auth;
+pub mod backup;
 pub mod base;
+pub mod cache;
 pub mod debug;
+pub mod export;
+pub mod friends;
+pub mod item_lists;
 pub mod live_scraper;
+pub mod onboarding;
 pub mod orders;
 pub mod price_scraper;
+pub mod profile_sync;
+pub mod scheduler;
 pub mod stock;
 pub mod chat;
+pub mod tag;
 pub mod transaction;
+pub mod undo;
+pub mod whisper;
+pub mod wish_list;
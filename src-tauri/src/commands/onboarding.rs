@@ -0,0 +1,37 @@
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    error::{self, AppError},
+    onboarding::{LegacyInstallStruct, OnboardingClient},
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> =
+    Lazy::new(|| Mutex::new("command_onboarding.log".to_string()));
+
+// Old QuantframeV1 data folders that can be imported
+#[tauri::command]
+pub fn detect_legacy_installs(
+    onboarding: tauri::State<'_, Arc<Mutex<OnboardingClient>>>,
+) -> Result<Vec<LegacyInstallStruct>, AppError> {
+    let onboarding = onboarding.lock()?.clone();
+    Ok(onboarding.detect())
+}
+
+// Returns the path the old folder was archived to
+#[tauri::command]
+pub async fn import_legacy_install(
+    path: String,
+    onboarding: tauri::State<'_, Arc<Mutex<OnboardingClient>>>,
+) -> Result<String, AppError> {
+    let onboarding = onboarding.lock()?.clone();
+    match onboarding.import(&path).await {
+        Ok(archived) => Ok(archived),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
@@ -1,15 +1,25 @@
+use eyre::eyre;
 use once_cell::sync::Lazy;
 use serde_json::{json, Value};
 
 use crate::{
-    database::client::DBClient,
+    database::{
+        client::DBClient,
+        modules::order_journal::{self, OrderJournalStruct},
+    },
+    enums::{ErrorKind, LogLevel, OrderType},
     error::{self, AppError},
     helper,
+    live_scraper::client::LiveScraperClient,
+    projection::{self, OrderListItem},
     settings::SettingsState,
     structs::Order,
     wfm_client::client::WFMClient,
 };
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 // Create a static variable to store the log file name
 static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_orders.log".to_string()));
@@ -18,28 +28,220 @@ static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_orders.l
 pub async fn get_orders(_wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>) -> Result<(), AppError> {
     Ok(())
 }
+// Orders changed from the app go through the same paths as the live scraper, so the order caps
+// and the listed prices of the stock stay in line with what is on Warframe Market
+async fn get_my_order(wfm: &WFMClient, id: &str) -> Result<Order, AppError> {
+    let orders = wfm.orders().get_my_orders().await?;
+    orders
+        .buy_orders
+        .into_iter()
+        .chain(orders.sell_orders.into_iter())
+        .find(|order| order.id == id)
+        .ok_or_else(|| {
+            AppError::new("Command:Orders", eyre!("Order {} not found", id))
+                .with_kind(ErrorKind::NotFound)
+        })
+}
+
+// Keeps the listed price of the stock item of a sell order up to date, -1 when it is no longer listed
+async fn update_listed_price(db: &DBClient, order: &Order, listed_price: i32) -> Result<(), AppError> {
+    if order.order_type != OrderType::Sell {
+        return Ok(());
+    }
+    let url_name = match &order.item {
+        Some(item) => item.url_name.clone(),
+        None => return Ok(()),
+    };
+    let stock_item = db.stock_item().get_items().await?.into_iter().find(|item| {
+        item.url == url_name
+            && item.sub_type == order.subtype
+            && (order.mod_rank.is_none() || order.mod_rank == Some(item.rank as i64))
+    });
+    let stock_item = match stock_item {
+        Some(stock_item) => stock_item,
+        None => return Ok(()),
+    };
+    let status = match listed_price {
+        -1 => "pending",
+        _ => "live",
+    };
+    db.stock_item()
+        .update_by_id(
+            stock_item.id,
+            None,
+            None,
+            None,
+            Some(listed_price),
+            Some(status.to_string()),
+            None,
+        )
+        .await?;
+    Ok(())
+}
+
+async fn update_my_order(
+    wfm: &WFMClient,
+    db: &DBClient,
+    id: &str,
+    platinum: Option<i64>,
+    visible: Option<bool>,
+) -> Result<Order, AppError> {
+    let order = get_my_order(wfm, id).await?;
+    let order = wfm
+        .orders()
+        .update(
+            id,
+            platinum.unwrap_or(order.platinum) as i32,
+            order.quantity as i32,
+            visible.unwrap_or(order.visible),
+        )
+        .await?;
+    let listed_price = match order.visible {
+        true => order.platinum as i32,
+        false => -1,
+    };
+    update_listed_price(db, &order, listed_price).await?;
+    Ok(order)
+}
+
+async fn delete_my_order(
+    wfm: &WFMClient,
+    db: &DBClient,
+    live_scraper: &LiveScraperClient,
+    id: &str,
+) -> Result<Order, AppError> {
+    let order = get_my_order(wfm, id).await?;
+    wfm.orders().delete(id).await?;
+    live_scraper.order_budget().release_order(&order)?;
+    update_listed_price(db, &order, -1).await?;
+    Ok(order)
+}
+
 #[tauri::command]
-pub async fn delete_order(
+pub async fn create_order(
     id: String,
+    order_type: String,
+    quantity: i64,
+    price: i64,
+    rank: Option<f64>,
+    sub_type: Option<String>,
+    visible: Option<bool>,
     wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
-) -> Result<(), AppError> {
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    live_scraper: tauri::State<'_, Arc<Mutex<LiveScraperClient>>>,
+) -> Result<Order, AppError> {
     let wfm = wfm.lock()?.clone();
-    match wfm.orders().delete(id.as_str()).await {
-        Ok(_) => {}
+    let db = db.lock()?.clone();
+    let live_scraper = live_scraper.lock()?.clone();
+    let result = async {
+        let cache = db.cache.lock()?.clone();
+        let url_name = cache
+            .items()
+            .get_types()?
+            .into_iter()
+            .find(|item| item.id == id)
+            .map(|item| item.url_name)
+            .ok_or_else(|| {
+                AppError::new("Command:Orders", eyre!("Item {} not found", id))
+                    .with_kind(ErrorKind::NotFound)
+            })?;
+        if !live_scraper.order_budget().reserve(&url_name)? {
+            return Err(AppError::new_with_level(
+                "Command:Orders",
+                eyre!("The order cap for {} is reached", url_name),
+                LogLevel::Warning,
+            ));
+        }
+        let order = match wfm
+            .orders()
+            .create(
+                &id,
+                &order_type,
+                price,
+                quantity,
+                visible.unwrap_or(true),
+                rank,
+                sub_type.as_deref(),
+            )
+            .await
+        {
+            Ok(order) => order,
+            Err(e) => {
+                live_scraper.order_budget().release(&url_name)?;
+                return Err(e);
+            }
+        };
+        if order.visible {
+            update_listed_price(&db, &order, order.platinum as i32).await?;
+        }
+        Ok(order)
+    }
+    .await;
+    match result {
+        Ok(order) => Ok(order),
         Err(e) => {
             error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
             return Err(e);
         }
     }
-    Ok(())
 }
+
 #[tauri::command]
-pub async fn create_order(_wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>) -> Result<(), AppError> {
-    Ok(())
+pub async fn update_order_price(
+    id: String,
+    price: i64,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Order, AppError> {
+    let wfm = wfm.lock()?.clone();
+    let db = db.lock()?.clone();
+    match update_my_order(&wfm, &db, &id, Some(price), None).await {
+        Ok(order) => Ok(order),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
 }
+
 #[tauri::command]
-pub async fn update_order(_wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>) -> Result<(), AppError> {
-    Ok(())
+pub async fn toggle_order_visibility(
+    id: String,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Order, AppError> {
+    let wfm = wfm.lock()?.clone();
+    let db = db.lock()?.clone();
+    let result = match get_my_order(&wfm, &id).await {
+        Ok(order) => update_my_order(&wfm, &db, &id, None, Some(!order.visible)).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(order) => Ok(order),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn delete_order(
+    id: String,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    live_scraper: tauri::State<'_, Arc<Mutex<LiveScraperClient>>>,
+) -> Result<Order, AppError> {
+    let wfm = wfm.lock()?.clone();
+    let db = db.lock()?.clone();
+    let live_scraper = live_scraper.lock()?.clone();
+    match delete_my_order(&wfm, &db, &live_scraper, &id).await {
+        Ok(order) => Ok(order),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
 }
 
 #[tauri::command]
@@ -63,8 +265,153 @@ pub async fn refresh_orders(wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>) -> Res
     );
     Ok(())
 }
+#[tauri::command]
+pub async fn get_orders_list(
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+) -> Result<Vec<OrderListItem>, AppError> {
+    let wfm = wfm.lock()?.clone();
+    match wfm.orders().get_my_orders().await {
+        Ok(mut orders) => {
+            orders.buy_orders.append(&mut orders.sell_orders);
+            Ok(projection::project(&orders.buy_orders))
+        }
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_order_detail(
+    id: String,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+) -> Result<Order, AppError> {
+    let wfm = wfm.lock()?.clone();
+    let orders = match wfm.orders().get_my_orders().await {
+        Ok(orders) => orders,
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    };
+    orders
+        .buy_orders
+        .into_iter()
+        .chain(orders.sell_orders.into_iter())
+        .find(|order| order.id == id)
+        .ok_or_else(|| {
+            AppError::new("Command:Orders", eyre!("Order {} not found", id))
+                .with_kind(ErrorKind::NotFound)
+        })
+}
+
+async fn get_orders_to_delete(wfm: &WFMClient, db: &DBClient, blacklist: &Vec<String>) -> Result<Vec<Order>, AppError> {
+    let mut auctions = wfm.orders().get_my_orders().await?;
+    let mut orders = auctions.buy_orders;
+    orders.append(&mut auctions.sell_orders);
+    // Wish list orders are kept, the wish list would take a missing order as filled
+    let wished = db.wish_list().get_order_ids().await?;
+    Ok(orders
+        .into_iter()
+        .filter(|order| !blacklist.contains(&order.item.clone().unwrap().url_name))
+        .filter(|order| !wished.contains(&order.id))
+        .collect())
+}
+
+// Deletes my orders left behind twice for the same item, side and variant, e.g. after a crash.
+// The better priced order is kept: the highest sell or the lowest buy order, then the most recently updated.
+pub async fn cleanup_duplicate_orders(
+    wfm: &WFMClient,
+    db: &DBClient,
+    orders: &Vec<Order>,
+) -> Result<Vec<Order>, AppError> {
+    let mut groups: HashMap<(String, String, Option<String>, Option<i64>), Vec<Order>> =
+        HashMap::new();
+    for order in orders.iter() {
+        let url_name = match &order.item {
+            Some(item) => item.url_name.clone(),
+            None => continue,
+        };
+        groups
+            .entry((
+                url_name,
+                order.order_type.as_str().to_string(),
+                order.subtype.clone(),
+                order.mod_rank,
+            ))
+            .or_default()
+            .push(order.clone());
+    }
+
+    let mut deleted: Vec<Order> = vec![];
+    for (_, mut group) in groups.into_iter().filter(|(_, group)| group.len() > 1) {
+        group.sort_by(|a, b| {
+            let by_price = match a.order_type {
+                OrderType::Buy => a.platinum.cmp(&b.platinum),
+                _ => b.platinum.cmp(&a.platinum),
+            };
+            by_price.then_with(|| b.last_update.cmp(&a.last_update))
+        });
+        for order in group.into_iter().skip(1) {
+            // A failed delete is logged and the rest are still cleaned up
+            match wfm.orders().delete(&order.id).await {
+                Ok(_) => deleted.push(order),
+                Err(e) => error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e),
+            }
+        }
+    }
+    if deleted.is_empty() {
+        return Ok(deleted);
+    }
+
+    let summary: Vec<Value> = deleted
+        .iter()
+        .map(|order| {
+            json!({
+                "id": order.id,
+                "url_name": order.item.clone().unwrap().url_name,
+                "order_type": order.order_type,
+                "sub_type": order.subtype,
+                "mod_rank": order.mod_rank,
+                "platinum": order.platinum,
+                "quantity": order.quantity,
+            })
+        })
+        .collect();
+    db.audit_log()
+        .create(
+            "cleanup_duplicate_orders",
+            "Orders",
+            format!("Deleted {} duplicate orders", deleted.len()).as_str(),
+            Some(json!({ "orders": summary })),
+        )
+        .await?;
+    Ok(deleted)
+}
+
+#[tauri::command]
+pub async fn preview_delete_all_orders(
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+) -> Result<serde_json::Value, AppError> {
+    let wfm = wfm.lock()?.clone();
+    let db = db.lock()?.clone();
+    let settings = settings.lock()?.clone();
+    let blacklist = settings.live_scraper.stock_item.blacklist.clone();
+    match get_orders_to_delete(&wfm, &db, &blacklist).await {
+        Ok(orders) => Ok(json!({"count": orders.len(), "orders": orders})),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
 #[tauri::command]
 pub async fn delete_all_orders(
+    ids: Option<Vec<String>>,
     wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
     db: tauri::State<'_, Arc<Mutex<DBClient>>>,
     settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
@@ -82,15 +429,21 @@ pub async fn delete_all_orders(
         }
     };
     helper::emit_progress("Orders:Delete:All:Progress", "delete_all_orders.loading", None, false);
-    let current_orders: Vec<Order> = match wfm.orders().get_my_orders().await {
-        Ok(mut auctions) => {
-            let mut orders = auctions.buy_orders;
-            orders.append(&mut auctions.sell_orders);
-            orders
-                .into_iter()
-                .filter(|order| !blacklist.contains(&order.item.clone().unwrap().url_name))
-                .collect()
+    let current_orders: Vec<Order> = match get_orders_to_delete(&wfm, &db, &blacklist).await {
+        // Only delete the orders the user confirmed in the preview, if any were given
+        Ok(orders) => match &ids {
+            Some(ids) => orders.into_iter().filter(|order| ids.contains(&order.id)).collect(),
+            None => orders,
+        },
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
         }
+    };
+
+    // Left behind when the app closes halfway, to be resumed or rolled back on the next start
+    let batch = match db.order_journal().begin("delete_all_orders", &current_orders).await {
+        Ok(batch) => batch,
         Err(e) => {
             error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
             return Err(e);
@@ -99,6 +452,9 @@ pub async fn delete_all_orders(
 
     let count = current_orders.len();
     let mut current_count = 0;
+    let mut deleted: Vec<Value> = vec![];
+    // A failed delete is logged and the rest are still deleted, the failed ones stay listed
+    let mut failed: Vec<Value> = vec![];
     for order in current_orders {
         current_count += 1;
         helper::emit_progress("Orders:Delete:All:Progress", "delete_all_orders.progress",Some(json!({
@@ -106,15 +462,213 @@ pub async fn delete_all_orders(
             "total": count
         })), false);
         match wfm.orders().delete(&order.id).await {
-            Ok(_) => {}
+            Ok(_) => {
+                db.order_journal().set_status(&batch, &order.id, order_journal::DELETED).await?;
+                deleted.push(json!({
+                    "id": order.id,
+                    "url_name": order.item.clone().unwrap().url_name,
+                    "order_type": order.order_type,
+                    "platinum": order.platinum,
+                    "quantity": order.quantity,
+                }));
+            }
             Err(e) => {
                 error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
-                return Err(e);
+                failed.push(json!({
+                    "id": order.id,
+                    "url_name": order.item.clone().unwrap().url_name,
+                    "error": e.cause(),
+                }));
             }
         };
     }
+    match db
+        .audit_log()
+        .create(
+            "delete_all_orders",
+            "Orders",
+            format!("Deleted {} orders, {} failed", deleted.len(), failed.len()).as_str(),
+            Some(json!({ "orders": deleted, "failed": failed })),
+        )
+        .await
+    {
+        Ok(_) => {}
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    };
+    db.order_journal().complete(&batch).await?;
     helper::emit_progress("Orders:Delete:All:Progress", "delete_all_orders.completed", Some(json!({
-        "total": count
+        "total": deleted.len(),
+        "failed": failed.len()
     })), true);
-    Ok(json!({"count": count}))
+    Ok(json!({"count": deleted.len(), "failed": failed.len()}))
+}
+
+// Bulk order operations that were interrupted, grouped by batch on the UI
+#[tauri::command]
+pub async fn get_order_journal(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<OrderJournalStruct>, AppError> {
+    let db = db.lock()?.clone();
+    match db.order_journal().get_entries(None).await {
+        Ok(entries) => Ok(entries),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Deletes the orders the interrupted operation had not reached yet
+async fn resume_journal(wfm: &WFMClient, db: &DBClient, batch: &str) -> Result<i64, AppError> {
+    let mut count = 0;
+    for entry in db.order_journal().get_entries(Some(batch)).await? {
+        if entry.status != order_journal::PENDING {
+            continue;
+        }
+        match wfm.orders().delete(&entry.order_id).await {
+            Ok(_) => count += 1,
+            // Already gone, e.g. sold or removed on the site
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        db.order_journal().set_status(batch, &entry.order_id, order_journal::DELETED).await?;
+    }
+    db.order_journal().complete(batch).await?;
+    Ok(count)
+}
+
+// Creates the orders the interrupted operation had already deleted again
+async fn rollback_journal(wfm: &WFMClient, db: &DBClient, batch: &str) -> Result<i64, AppError> {
+    let mut count = 0;
+    for entry in db.order_journal().get_entries(Some(batch)).await? {
+        if entry.status != order_journal::DELETED {
+            continue;
+        }
+        let order = entry.order_data.0;
+        let item_id = match &order.item {
+            Some(item) => item.id.clone(),
+            None => continue,
+        };
+        wfm.orders()
+            .create(
+                &item_id,
+                order.order_type.as_str(),
+                order.platinum,
+                order.quantity,
+                order.visible,
+                order.mod_rank.map(|rank| rank as f64),
+                order.subtype.as_deref(),
+            )
+            .await?;
+        db.order_journal().set_status(batch, &entry.order_id, order_journal::PENDING).await?;
+        count += 1;
+    }
+    db.order_journal().complete(batch).await?;
+    db.audit_log()
+        .create(
+            "rollback_order_journal",
+            "Orders",
+            format!("Created {} orders again", count).as_str(),
+            Some(json!({ "batch": batch })),
+        )
+        .await?;
+    Ok(count)
+}
+
+#[tauri::command]
+pub async fn resume_order_journal(
+    batch: String,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<i64, AppError> {
+    let wfm = wfm.lock()?.clone();
+    let db = db.lock()?.clone();
+    match resume_journal(&wfm, &db, &batch).await {
+        Ok(count) => Ok(count),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn rollback_order_journal(
+    batch: String,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<i64, AppError> {
+    let wfm = wfm.lock()?.clone();
+    let db = db.lock()?.clone();
+    match rollback_journal(&wfm, &db, &batch).await {
+        Ok(count) => Ok(count),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Compare the orders of another player with mine, matched on item and order type
+#[tauri::command]
+pub async fn compare_player_orders(
+    ingame_name: String,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+) -> Result<serde_json::Value, AppError> {
+    let wfm = wfm.lock()?.clone();
+    let (my_orders, their_orders) = match get_orders_to_compare(&wfm, &ingame_name).await {
+        Ok(orders) => orders,
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    };
+
+    let mut overlapping: Vec<Value> = vec![];
+    for their_order in their_orders.iter() {
+        let url_name = match &their_order.item {
+            Some(item) => item.url_name.clone(),
+            None => continue,
+        };
+        let my_order = my_orders.iter().find(|order| {
+            order.order_type == their_order.order_type
+                && order.mod_rank == their_order.mod_rank
+                && order.item.as_ref().map(|item| item.url_name.clone()) == Some(url_name.clone())
+        });
+        if let Some(my_order) = my_order {
+            overlapping.push(json!({
+                "url_name": url_name,
+                "order_type": their_order.order_type,
+                "mod_rank": their_order.mod_rank,
+                "my_price": my_order.platinum,
+                "their_price": their_order.platinum,
+                "difference": my_order.platinum - their_order.platinum,
+            }));
+        }
+    }
+    Ok(json!({
+        "ingame_name": ingame_name,
+        "orders": their_orders,
+        "overlapping": overlapping,
+    }))
+}
+async fn get_orders_to_compare(
+    wfm: &WFMClient,
+    ingame_name: &str,
+) -> Result<(Vec<Order>, Vec<Order>), AppError> {
+    let mut mine = wfm.orders().get_my_orders().await?;
+    let mut my_orders = mine.buy_orders;
+    my_orders.append(&mut mine.sell_orders);
+
+    // Other players' orders are cached for a minute so browsing a shop does not hit the rate limit
+    let mut theirs = wfm
+        .orders()
+        .get_user_orders_cached(ingame_name, std::time::Duration::from_secs(60))
+        .await?;
+    let mut their_orders = theirs.buy_orders;
+    their_orders.append(&mut theirs.sell_orders);
+    Ok((my_orders, their_orders))
 }
@@ -3,8 +3,11 @@ use std::sync::{Arc, Mutex};
 use once_cell::sync::Lazy;
 
 use crate::{
+    database::{client::DBClient, modules::price_snapshot::PriceSnapshotStruct},
     error::{self, AppError},
+    market_report::{self, MarketReportStruct},
     price_scraper::PriceScraper,
+    settings::SettingsState,
 };
 
 // Create a static variable to store the log file name
@@ -14,9 +17,11 @@ static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_price_hi
 pub async fn generate_price_history(
     days: i64,
     price_scraper: tauri::State<'_, Arc<std::sync::Mutex<PriceScraper>>>,
+    db: tauri::State<'_, Arc<std::sync::Mutex<DBClient>>>,
 ) -> Result<i64, AppError> {
     let price_scraper = price_scraper.lock()?.clone();
-    match price_scraper.generate(days).await {
+    let db = db.lock()?.clone();
+    match price_scraper.generate(&db, days).await {
         Ok(_) => Ok(0),
         Err(e) => {
             error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
@@ -24,3 +29,47 @@ pub async fn generate_price_history(
         }
     }
 }
+
+#[tauri::command(async)]
+pub async fn get_price_snapshots(
+    url: String,
+    db: tauri::State<'_, Arc<std::sync::Mutex<DBClient>>>,
+) -> Result<Vec<PriceSnapshotStruct>, AppError> {
+    let db = db.lock()?.clone();
+    match db.price_snapshot().get_by_url(&url).await {
+        Ok(snapshots) => Ok(snapshots),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Report over the last week, month or quarter, also written as an HTML file when asked
+#[tauri::command(async)]
+pub async fn generate_market_report(
+    range: String,
+    render_html: Option<bool>,
+    db: tauri::State<'_, Arc<std::sync::Mutex<DBClient>>>,
+    settings: tauri::State<'_, Arc<std::sync::Mutex<SettingsState>>>,
+) -> Result<MarketReportStruct, AppError> {
+    let db = db.lock()?.clone();
+    let settings = settings.lock()?.clone();
+    let mut report = match market_report::generate(&db, &settings, &range).await {
+        Ok(report) => report,
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    };
+    if render_html.unwrap_or(false) {
+        match market_report::write_html(&report) {
+            Ok(path) => report.html_path = Some(path),
+            Err(e) => {
+                error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+                return Err(e);
+            }
+        }
+    }
+    Ok(report)
+}
@@ -0,0 +1,72 @@
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    error::{self, AppError},
+    profile_sync::{ProfileBundle, ProfileSyncClient, ProfileSyncResult},
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> =
+    Lazy::new(|| Mutex::new("command_profile_sync.log".to_string()));
+
+#[tauri::command]
+pub fn export_profile(
+    path: String,
+    profile_sync: tauri::State<'_, Arc<Mutex<ProfileSyncClient>>>,
+) -> Result<(), AppError> {
+    let profile_sync = profile_sync.lock()?.clone();
+    match profile_sync.export_to(&path) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn import_profile(
+    path: String,
+    profile_sync: tauri::State<'_, Arc<Mutex<ProfileSyncClient>>>,
+) -> Result<ProfileBundle, AppError> {
+    let profile_sync = profile_sync.lock()?.clone();
+    match profile_sync.import_from(&path) {
+        Ok(bundle) => Ok(bundle),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn sync_profile(
+    profile_sync: tauri::State<'_, Arc<Mutex<ProfileSyncClient>>>,
+) -> Result<ProfileSyncResult, AppError> {
+    let profile_sync = profile_sync.lock()?.clone();
+    match profile_sync.sync() {
+        Ok(result) => Ok(result),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Settles a conflict by keeping the "local" or the "remote" profile
+#[tauri::command]
+pub fn resolve_profile_conflict(
+    keep: String,
+    profile_sync: tauri::State<'_, Arc<Mutex<ProfileSyncClient>>>,
+) -> Result<ProfileSyncResult, AppError> {
+    let profile_sync = profile_sync.lock()?.clone();
+    match profile_sync.resolve_conflict(&keep) {
+        Ok(result) => Ok(result),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
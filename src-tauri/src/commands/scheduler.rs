@@ -0,0 +1,72 @@
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    error::{self, AppError},
+    scheduler::{ScheduledTaskInfo, Scheduler},
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> =
+    Lazy::new(|| Mutex::new("command_scheduler.log".to_string()));
+
+#[tauri::command]
+pub fn get_scheduled_tasks(
+    scheduler: tauri::State<'_, Arc<Mutex<Scheduler>>>,
+) -> Result<Vec<ScheduledTaskInfo>, AppError> {
+    let scheduler = scheduler.lock()?.clone();
+    match scheduler.get_tasks() {
+        Ok(tasks) => Ok(tasks),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn pause_scheduled_task(
+    name: String,
+    scheduler: tauri::State<'_, Arc<Mutex<Scheduler>>>,
+) -> Result<Vec<ScheduledTaskInfo>, AppError> {
+    let scheduler = scheduler.lock()?.clone();
+    match scheduler.pause(&name) {
+        Ok(_) => scheduler.get_tasks(),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn resume_scheduled_task(
+    name: String,
+    scheduler: tauri::State<'_, Arc<Mutex<Scheduler>>>,
+) -> Result<Vec<ScheduledTaskInfo>, AppError> {
+    let scheduler = scheduler.lock()?.clone();
+    match scheduler.resume(&name) {
+        Ok(_) => scheduler.get_tasks(),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Runs the task on the next tick of the scheduler
+#[tauri::command]
+pub fn run_scheduled_task(
+    name: String,
+    scheduler: tauri::State<'_, Arc<Mutex<Scheduler>>>,
+) -> Result<Vec<ScheduledTaskInfo>, AppError> {
+    let scheduler = scheduler.lock()?.clone();
+    match scheduler.trigger(&name) {
+        Ok(_) => scheduler.get_tasks(),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
@@ -8,11 +8,24 @@ static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_stock.lo
 use crate::{
     database::{
         client::DBClient,
-        modules::{stock_item, stock_riven::MatchRivenStruct},
+        modules::{
+            stock_item::{
+                self, BulkPricing, CostBasisDeltaStruct, ResupplyRiskStruct, StockImportReportStruct,
+                StockItemFilter,
+            },
+            stock_riven::{MatchRivenStruct, StockRivenQuery, StockRivenStruct},
+            stock_set::StockSetStruct,
+            stock_price_history::PricePointStruct,
+            undo::UndoChange,
+        },
     },
-    enums::{LogLevel, OrderType},
+    enums::{ErrorKind, LogLevel, OrderType},
     error::{self, AppError},
+    live_scraper::client::LiveScraperClient,
     logger,
+    price_scraper::PriceScraper,
+    price_validation::{self, PriceValidationStruct},
+    projection::{self, StockItemListItem},
     structs::{Order, RivenAttribute},
     wfm_client::client::WFMClient,
 };
@@ -20,7 +33,123 @@ use eyre::eyre;
 use once_cell::sync::Lazy;
 use serde_json::json;
 
+// Runs the single item pricing in the background, the command does not wait for Warframe Market
+fn auto_price_item(live_scraper: LiveScraperClient, url_name: &str) {
+    let url_name = url_name.to_string();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = live_scraper.item().price_item(&url_name).await {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+        }
+    });
+}
+
+// Validates the price entered for an item, logging the rejection like the other command errors
+fn validate_price(
+    price_scraper: &PriceScraper,
+    url_name: &str,
+    price: f64,
+) -> Result<PriceValidationStruct, AppError> {
+    match price_validation::validate_price(price_scraper, url_name, price) {
+        Ok(validation) => Ok(validation),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Merges the warnings of every price checked by a command
+fn merge_validations(validations: Vec<PriceValidationStruct>) -> Option<PriceValidationStruct> {
+    let mut validations = validations.into_iter();
+    let mut merged = validations.next()?;
+    for validation in validations {
+        merged.warnings.extend(validation.warnings);
+    }
+    Some(merged)
+}
+
 // Item Stock Commands
+#[tauri::command]
+pub async fn get_stock_items_list(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    live_scraper: tauri::State<'_, Arc<Mutex<LiveScraperClient>>>,
+) -> Result<Vec<StockItemListItem>, AppError> {
+    let db = db.lock()?.clone();
+    let time_to_sell = live_scraper.lock()?.time_to_sell.lock()?.clone();
+    let mut items = match db.stock_item().get_items().await {
+        Ok(items) => items,
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    };
+    for item in items.iter_mut() {
+        item.time_to_sell = time_to_sell.get(&item.id).cloned();
+    }
+    Ok(projection::project(&items))
+}
+
+// Sets the stock parts make up, as last priced by the live scraper
+#[tauri::command]
+pub async fn get_stock_sets(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<StockSetStruct>, AppError> {
+    let db = db.lock()?.clone();
+    match db.stock_set().get_sets().await {
+        Ok(sets) => Ok(sets),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Listed prices of a stock item over the range, e.g. "24h", "7d" or "all", downsampled for a chart
+#[tauri::command]
+pub async fn get_price_history(
+    id: i64,
+    range: String,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<PricePointStruct>, AppError> {
+    let db = db.lock()?.clone();
+    match db.stock_price_history().get_series(id, &range).await {
+        Ok(points) => Ok(points),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Lets the UI check a price while it is typed, before anything is saved or listed
+#[tauri::command]
+pub async fn validate_item_price(
+    url_name: String,
+    price: f64,
+    price_scraper: tauri::State<'_, Arc<Mutex<PriceScraper>>>,
+) -> Result<PriceValidationStruct, AppError> {
+    let price_scraper = price_scraper.lock()?.clone();
+    validate_price(&price_scraper, &url_name, price)
+}
+
+#[tauri::command]
+pub async fn get_stock_item_detail(
+    id: i64,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<stock_item::StockItemStruct, AppError> {
+    let db = db.lock()?.clone();
+    match db.stock_item().get_by_id(id).await {
+        Ok(Some(item)) => Ok(item),
+        Ok(None) => Err(
+            AppError::new("Command", eyre!("Item not found")).with_kind(ErrorKind::NotFound),
+        ),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
 #[tauri::command]
 pub async fn create_item_stock(
     url_name: String,
@@ -32,10 +161,35 @@ pub async fn create_item_stock(
     db: tauri::State<'_, Arc<Mutex<DBClient>>>,
     wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
     settings: tauri::State<'_, Arc<Mutex<crate::settings::SettingsState>>>,
+    live_scraper: tauri::State<'_, Arc<Mutex<LiveScraperClient>>>,
+    price_scraper: tauri::State<'_, Arc<Mutex<PriceScraper>>>,
 ) -> Result<serde_json::Value, AppError> {
     let db = db.lock()?.clone();
     let wfm = wfm.lock()?.clone();
     let settings = settings.lock()?.clone();
+    let price_scraper = price_scraper.lock()?.clone();
+
+    // A price of 0 adds the item without a purchase, a minimum price of -1 clears it
+    let mut validations = vec![];
+    let mut price = price;
+    if price != 0.0 {
+        let validation = validate_price(&price_scraper, &url_name, price)?;
+        price = validation.price as f64;
+        validations.push(validation);
+    }
+    let mut minium_price = minium_price;
+    if let Some(minimum) = minium_price.filter(|p| *p != -1) {
+        let validation = validate_price(&price_scraper, &url_name, minimum as f64)?;
+        minium_price = Some(validation.price as i32);
+        validations.push(validation);
+    }
+    let validation = merge_validations(validations);
+    let with_warnings = |value: serde_json::Value| match &validation {
+        Some(validation) => validation.attach_to(value),
+        None => value,
+    };
+    // Adding to an owned variant updates it instead of creating a new item
+    let stock_before = db.stock_item().get_items().await?;
 
     // Create Item in Stock DB
     let stockitem = match db
@@ -50,19 +204,32 @@ pub async fn create_item_stock(
         }
     };
 
+    if settings.live_scraper.stock_item.auto_price_new_items {
+        auto_price_item(live_scraper.lock()?.clone(), &url_name);
+    }
+    let undo_description = format!("Add {} x{}", stockitem.name, quantity);
+    let mut undo_changes = vec![UndoChange::stock_item(
+        stockitem.id,
+        stock_before.iter().find(|t| t.id == stockitem.id),
+        Some(&stockitem),
+    )];
+
     // Create transaction if price is greater than 0
     if price <= 0.0 {
-        return Ok(json!(stockitem));
+        db.undo().push(&undo_description, undo_changes)?;
+        return Ok(with_warnings(json!(stockitem)));
     }
     match db
         .transaction()
         .create(&url_name, "item", "buy", quantity, price as i32, rank, None)
         .await
     {
-        Ok(_) => {
+        Ok(transaction) => {
+            undo_changes.push(UndoChange::transaction(transaction.id, None, Some(&transaction)));
+            db.undo().push(&undo_description, undo_changes)?;
             // Send Close Event to Warframe Market API if enabled
             if !settings.live_scraper.stock_item.report_to_wfm {
-                return Ok(serde_json::to_value(stockitem).unwrap());
+                return Ok(with_warnings(json!(stockitem)));
             }
         }
         Err(e) => {
@@ -72,7 +239,7 @@ pub async fn create_item_stock(
     };
     match wfm.orders().close(&url_name, OrderType::Buy).await {
         Ok(_) => {
-            return Ok(json!(stockitem));
+            return Ok(with_warnings(json!(stockitem)));
         }
         Err(e) => {
             error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
@@ -81,6 +248,23 @@ pub async fn create_item_stock(
     }
 }
 
+// Stock from a spreadsheet export, with dry_run the rows are only matched and checked
+#[tauri::command]
+pub async fn import_stock_items(
+    path: String,
+    dry_run: Option<bool>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<StockImportReportStruct, AppError> {
+    let db = db.lock()?.clone();
+    match db.stock_item().import(&path, dry_run.unwrap_or(false)).await {
+        Ok(report) => Ok(report),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
 #[tauri::command]
 pub async fn update_item_stock(
     id: i64,
@@ -88,22 +272,48 @@ pub async fn update_item_stock(
     minium_price: Option<i32>,
     hidden: Option<bool>,
     db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    price_scraper: tauri::State<'_, Arc<Mutex<PriceScraper>>>,
 ) -> Result<serde_json::Value, AppError> {
     let db = db.lock()?.clone();
+    let price_scraper = price_scraper.lock()?.clone();
     // Find Riven in Stock
     let stock = db.stock_item().get_by_id(id).await?;
     if stock.is_none() {
-        return Err(AppError::new("Command", eyre!("Item not found")));
+        return Err(
+            AppError::new("Command", eyre!("Item not found")).with_kind(ErrorKind::NotFound),
+        );
     }
 
+    let stock = stock.unwrap();
+    let mut minium_price = minium_price;
+    let mut validation = None;
+    if let Some(minimum) = minium_price.filter(|p| *p != -1) {
+        let result = validate_price(&price_scraper, &stock.url, minimum as f64)?;
+        minium_price = Some(result.price as i32);
+        validation = Some(result);
+    }
+    // Editing an item counts as reviewing it
+    let status = if stock.status == "needs_review" {
+        Some("pending".to_string())
+    } else {
+        None
+    };
+
     // Update Riven in Stock
     match db
         .stock_item()
-        .update_by_id(id, owned, None, minium_price, None,None, hidden)
+        .update_by_id(id, owned, None, minium_price, None, status, hidden)
         .await
     {
-        Ok(stock) => {
-            return Ok(json!(stock.clone()));
+        Ok(updated) => {
+            db.undo().push(
+                &format!("Edit {}", updated.name),
+                vec![UndoChange::stock_item(id, Some(&stock), Some(&updated))],
+            )?;
+            return Ok(match validation {
+                Some(validation) => validation.attach_to(json!(updated.clone())),
+                None => json!(updated.clone()),
+            });
         }
         Err(e) => {
             error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
@@ -124,6 +334,10 @@ pub async fn delete_item_stock(
         Ok(stockitem) => {
             // Send Delete Event to Frontend
             db.stock_item().emit("DELETE", json!(stockitem.clone()));
+            db.undo().push(
+                &format!("Delete {}", stockitem.name),
+                vec![UndoChange::stock_item(id, Some(&stockitem), None)],
+            )?;
             stockitem
         }
         Err(e) => {
@@ -160,6 +374,219 @@ pub async fn delete_item_stock(
     }
 }
 
+// Stock items that are no longer in the item cache, with suggested replacements
+#[tauri::command]
+pub async fn get_stock_item_conflicts(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<serde_json::Value, AppError> {
+    let db = db.lock()?.clone();
+    match db.stock_item().get_cache_conflicts().await {
+        Ok(conflicts) => {
+            return Ok(json!(conflicts));
+        }
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn remap_item_stock(
+    id: i64,
+    url_name: String,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<serde_json::Value, AppError> {
+    let db = db.lock()?.clone();
+    match db.stock_item().remap(id, &url_name).await {
+        Ok(stock) => {
+            return Ok(json!(stock.clone()));
+        }
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn archive_item_stock(
+    id: i64,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<serde_json::Value, AppError> {
+    let db = db.lock()?.clone();
+    match db.stock_item().archive(id).await {
+        Ok(stock) => {
+            return Ok(json!(stock.clone()));
+        }
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Hides or shows the matching items and their sell orders, e.g. before going on vacation
+#[tauri::command]
+pub async fn set_hidden_by_filter(
+    filter: StockItemFilter,
+    hidden: bool,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+) -> Result<serde_json::Value, AppError> {
+    let db = db.lock()?.clone();
+    let wfm = wfm.lock()?.clone();
+    let items = match db.stock_item().set_hidden_by_filter(filter, hidden).await {
+        Ok(items) => {
+            let changes = items
+                .iter()
+                .map(|t| {
+                    let mut before = t.clone();
+                    before.hidden = !hidden;
+                    UndoChange::stock_item(t.id, Some(&before), Some(t))
+                })
+                .collect();
+            let action = if hidden { "Hide" } else { "Show" };
+            db.undo()
+                .push(&format!("{} {} items", action, items.len()), changes)?;
+            items
+        }
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    };
+    if items.is_empty() {
+        return Ok(json!(items));
+    }
+
+    // Match the visibility of the sell orders, fetched once for all items
+    let orders: Vec<Order> = wfm.orders().get_my_orders().await?.sell_orders;
+    for order in orders.iter().filter(|order| order.visible == hidden) {
+        let url_name = match order.item.as_ref() {
+            Some(item) => item.url_name.clone(),
+            None => continue,
+        };
+        let is_match = items.iter().any(|t| {
+            t.url == url_name
+                && t.sub_type == order.subtype
+                && order.mod_rank.map_or(true, |rank| rank == t.rank as i64)
+        });
+        if !is_match {
+            continue;
+        }
+        match wfm
+            .orders()
+            .update(
+                &order.id,
+                order.platinum as i32,
+                order.quantity as i32,
+                !hidden,
+            )
+            .await
+        {
+            Ok(_) => {}
+            Err(e) => {
+                error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+                logger::warning_con(
+                    "CommandStock",
+                    format!("Error updating the visibility of order {}", order.id).as_str(),
+                );
+            }
+        }
+    }
+    Ok(json!(items))
+}
+
+// Corrects the stock prices from the purchase transactions, e.g. after manual edits
+#[tauri::command]
+pub async fn rebuild_cost_basis(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    settings: tauri::State<'_, Arc<Mutex<crate::settings::SettingsState>>>,
+) -> Result<Vec<CostBasisDeltaStruct>, AppError> {
+    let db = db.lock()?.clone();
+    let method = settings.lock()?.live_scraper.stock_item.cost_basis.clone();
+    match db.stock_item().rebuild_cost_basis(method).await {
+        Ok(deltas) => Ok(deltas),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn update_item_stock_bulk(
+    id: i64,
+    bulk: Option<BulkPricing>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<serde_json::Value, AppError> {
+    let db = db.lock()?.clone();
+    match db.stock_item().update_bulk(id, bulk).await {
+        Ok(stock) => {
+            return Ok(json!(stock.clone()));
+        }
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn update_item_stock_reserved(
+    id: i64,
+    reserved: i32,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<serde_json::Value, AppError> {
+    let db = db.lock()?.clone();
+    match db.stock_item().update_reserved(id, reserved).await {
+        Ok(stock) => {
+            return Ok(json!(stock.clone()));
+        }
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn update_item_stock_resupply(
+    id: i64,
+    resupply_at: Option<String>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<serde_json::Value, AppError> {
+    let db = db.lock()?.clone();
+    match db.stock_item().update_resupply(id, resupply_at).await {
+        Ok(stock) => {
+            return Ok(json!(stock.clone()));
+        }
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Stock at risk of losing value to an upcoming resupply, within the discount window when no days are given
+#[tauri::command]
+pub async fn get_resupply_risk(
+    days: Option<i64>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    settings: tauri::State<'_, Arc<Mutex<crate::settings::SettingsState>>>,
+) -> Result<Vec<ResupplyRiskStruct>, AppError> {
+    let db = db.lock()?.clone();
+    let days = days.unwrap_or(settings.lock()?.live_scraper.stock_item.resupply_discount.days);
+    match db.stock_item().get_resupply_risk(days).await {
+        Ok(risks) => Ok(risks),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
 #[tauri::command]
 pub async fn sell_item_stock(
     id: i64,
@@ -168,10 +595,52 @@ pub async fn sell_item_stock(
     db: tauri::State<'_, Arc<Mutex<DBClient>>>,
     wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
     settings: tauri::State<'_, Arc<Mutex<crate::settings::SettingsState>>>,
+    price_scraper: tauri::State<'_, Arc<Mutex<PriceScraper>>>,
 ) -> Result<serde_json::Value, AppError> {
     let db = db.lock()?.clone();
     let wfm = wfm.lock()?.clone();
     let settings = settings.lock()?.clone();
+    let price_scraper = price_scraper.lock()?.clone();
+    let mut price = price;
+    let mut properties = None;
+
+    // Items sold in bundles are priced by their tiers
+    let stock_before = db.stock_item().get_by_id(id).await?;
+    if let Some(bulk) = stock_before.clone().and_then(|s| s.bulk).map(|b| b.0) {
+        if quantity < bulk.min_quantity {
+            return Err(AppError::new_with_level(
+                "Command",
+                eyre!("Item is sold in bundles of at least {}", bulk.min_quantity),
+                LogLevel::Warning,
+            ));
+        }
+        let unit_price = bulk.get_unit_price(quantity);
+        if price <= 0 && unit_price.is_some() {
+            price = unit_price.unwrap() * quantity;
+        }
+        properties = Some(json!({
+            "bundle": {
+                "quantity": quantity,
+                "unit_price": unit_price,
+                "note": bulk.get_note(),
+            }
+        }));
+    }
+
+    // The sale price is per trade, compared with the market per unit
+    let validation = match stock_before.as_ref() {
+        Some(stock) => {
+            let unit_price = price as f64 / quantity.max(1) as f64;
+            let mut validation = validate_price(&price_scraper, &stock.url, unit_price)?;
+            validation.price = price as i64;
+            Some(validation)
+        }
+        None => None,
+    };
+    let with_warnings = |value: serde_json::Value| match &validation {
+        Some(validation) => validation.attach_to(value),
+        None => value,
+    };
 
     // Sell Item in Stock DB
     let invantory = match db.stock_item().sell_item(id, quantity).await {
@@ -200,11 +669,20 @@ pub async fn sell_item_stock(
             quantity,
             price,
             invantory.rank,
-            None,
+            properties,
         )
         .await
     {
-        Ok(_) => {}
+        Ok(transaction) => {
+            let stock_after = db.stock_item().get_by_id(id).await?;
+            db.undo().push(
+                &format!("Sell {} x{}", invantory.name, quantity),
+                vec![
+                    UndoChange::stock_item(id, stock_before.as_ref(), stock_after.as_ref()),
+                    UndoChange::transaction(transaction.id, None, Some(&transaction)),
+                ],
+            )?;
+        }
         Err(e) => {
             error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
             return Err(e);
@@ -220,7 +698,7 @@ pub async fn sell_item_stock(
                 return Err(e);
             }
         }
-        return Ok(json!(invantory.clone()));
+        return Ok(with_warnings(json!(invantory.clone())));
     }
     let ordres: Vec<Order> = wfm.orders().get_my_orders().await?.sell_orders;
     let order = ordres
@@ -230,7 +708,7 @@ pub async fn sell_item_stock(
 
     // Check if order is found
     if order.is_none() {
-        return Ok(json!(invantory.clone()));
+        return Ok(with_warnings(json!(invantory.clone())));
     }
 
     // Delete the order from Warframe Market API OR Update the order Warframe Market API
@@ -244,14 +722,14 @@ pub async fn sell_item_stock(
             .await
         {
             Ok(_) => {
-                return Ok(json!(invantory.clone()));
+                return Ok(with_warnings(json!(invantory.clone())));
             }
             Err(e) => {
                 error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
                 if e.log_level() != LogLevel::Error {
                     return Err(e);
                 } else {
-                    return Ok(json!(invantory.clone()));
+                    return Ok(with_warnings(json!(invantory.clone())));
                 }
             }
         }
@@ -268,7 +746,7 @@ pub async fn sell_item_stock(
             .await
         {
             Ok(_) => {
-                return Ok(json!(invantory.clone()));
+                return Ok(with_warnings(json!(invantory.clone())));
             }
             Err(e) => {
                 error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
@@ -277,26 +755,122 @@ pub async fn sell_item_stock(
         }
     }
 }
+// Gives stock away, recorded as a zero plat gift to the recipient that is left out of the profit
+#[tauri::command]
+pub async fn gift_item_stock(
+    id: i64,
+    quantity: i32,
+    recipient: String,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+    settings: tauri::State<'_, Arc<Mutex<crate::settings::SettingsState>>>,
+) -> Result<serde_json::Value, AppError> {
+    let db = db.lock()?.clone();
+    let wfm = wfm.lock()?.clone();
+    let settings = settings.lock()?.clone();
+    let recipient = recipient.trim().to_string();
+    if recipient.is_empty() {
+        let e = AppError::new_with_level("Command", eyre!("A gift needs a recipient"), LogLevel::Warning)
+            .with_kind(ErrorKind::Validation);
+        error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+        return Err(e);
+    }
+
+    let invantory = match db.stock_item().sell_item(id, quantity).await {
+        Ok(invantory) => invantory,
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    };
+    if invantory.owned == 0 {
+        db.stock_item().emit("DELETE", json!(invantory.clone()));
+    } else {
+        db.stock_item()
+            .emit("CREATE_OR_UPDATE", json!(invantory.clone()));
+    }
+
+    let properties = json!({
+        "recipient": recipient,
+        "friend": settings.friends.is_friend(&recipient),
+    });
+    match db
+        .transaction()
+        .create(
+            &invantory.url,
+            "item",
+            "gift",
+            quantity,
+            0,
+            invantory.rank,
+            Some(properties),
+        )
+        .await
+    {
+        Ok(_) => {}
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+
+    // Keep the sell order in line with what is left
+    let ordres: Vec<Order> = wfm.orders().get_my_orders().await?.sell_orders;
+    let order = match ordres
+        .iter()
+        .find(|order| order.item.as_ref().unwrap().url_name == invantory.url)
+    {
+        Some(order) => order,
+        None => return Ok(json!(invantory.clone())),
+    };
+    let result = if invantory.owned <= 0 {
+        wfm.orders().delete(&order.id).await.map(|_| ())
+    } else {
+        wfm.orders()
+            .update(&order.id, order.platinum as i32, invantory.owned, order.visible)
+            .await
+            .map(|_| ())
+    };
+    match result {
+        Ok(_) => Ok(json!(invantory.clone())),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
 #[tauri::command]
 pub async fn sell_item_stock_by_url(
     name: String,
     quantity: i32,
     price: i32,
+    rank: Option<i32>,
+    sub_type: Option<String>,
     db: tauri::State<'_, Arc<Mutex<DBClient>>>,
     wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
     settings: tauri::State<'_, Arc<Mutex<crate::settings::SettingsState>>>,
+    price_scraper: tauri::State<'_, Arc<Mutex<PriceScraper>>>,
 ) -> Result<serde_json::Value, AppError> {
     let db_state = db.lock()?.clone();
 
-    // Find Item in Stock DB by url_name
-    let stock_item = match db_state
-        .stock_item()
-        .get_item_by_url_name(name.as_str())
-        .await
-    {
+    // Find Item in Stock DB by url_name, and by rank and sub type when given
+    let stock_item = match rank {
+        Some(rank) => {
+            db_state
+                .stock_item()
+                .get_item_by_variant(name.as_str(), rank, sub_type.as_deref())
+                .await
+        }
+        None => db_state.stock_item().get_item_by_url_name(name.as_str()).await,
+    };
+    let stock_item = match stock_item {
         Ok(strock_item) => {
             if strock_item.is_none() {
-                return Err(AppError::new("Command", eyre!("Item not found: {}", name)));
+                return Err(
+                    AppError::new("Command", eyre!("Item not found: {}", name))
+                        .with_kind(ErrorKind::NotFound),
+                );
             }
             strock_item.unwrap()
         }
@@ -306,7 +880,17 @@ pub async fn sell_item_stock_by_url(
         }
     };
 
-    match sell_item_stock(stock_item.id.clone(), quantity, price, db, wfm, settings).await {
+    match sell_item_stock(
+        stock_item.id.clone(),
+        quantity,
+        price,
+        db,
+        wfm,
+        settings,
+        price_scraper,
+    )
+    .await
+    {
         Ok(invantory) => {
             return Ok(invantory);
         }
@@ -404,7 +988,8 @@ pub async fn import_auction(
         return Err(AppError::new(
             "Command",
             eyre!("Auction {} not found", id.clone()),
-        ));
+        )
+        .with_kind(ErrorKind::NotFound));
     }
     let auction = auction.unwrap().clone();
 
@@ -448,6 +1033,21 @@ pub async fn import_auction(
     Ok(json!(riven_item.clone()))
 }
 
+#[tauri::command]
+pub async fn query_riven_stock(
+    query: StockRivenQuery,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<StockRivenStruct>, AppError> {
+    let db = db.lock()?.clone();
+    match db.stock_riven().query(query).await {
+        Ok(rivens) => Ok(rivens),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
 #[tauri::command]
 pub async fn delete_riven_stock(
     id: i64,
@@ -463,7 +1063,8 @@ pub async fn delete_riven_stock(
         return Err(AppError::new(
             "Command",
             eyre!("Riven {} not found", id.clone()),
-        ));
+        )
+        .with_kind(ErrorKind::NotFound));
     }
 
     let stock = stock.unwrap().clone();
@@ -503,7 +1104,10 @@ pub async fn update_riven_stock(
     // Find Riven in Stock
     let stock = db.stock_riven().get_by_id(id).await?;
     if stock.is_none() {
-        return Err(AppError::new("Riven not found", eyre!("Riven not found")));
+        return Err(
+            AppError::new("Riven not found", eyre!("Riven not found"))
+                .with_kind(ErrorKind::NotFound),
+        );
     }
 
     // Update Riven in Stock
@@ -537,7 +1141,10 @@ pub async fn sell_riven_stock(
     // Find Riven in Stock
     let stock = db.stock_riven().get_by_id(id).await?;
     if stock.is_none() {
-        return Err(AppError::new("Riven not found", eyre!("Riven not found")));
+        return Err(
+            AppError::new("Riven not found", eyre!("Riven not found"))
+                .with_kind(ErrorKind::NotFound),
+        );
     }
     let stock = stock.unwrap().clone();
 
@@ -0,0 +1,127 @@
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    database::{
+        client::DBClient,
+        modules::tag::{TagStruct, TaggedEntitiesStruct},
+    },
+    error::{self, AppError},
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_tag.log".to_string()));
+
+#[tauri::command]
+pub async fn get_tags(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<TagStruct>, AppError> {
+    let db = db.lock()?.clone();
+    match db.tag().get_tags().await {
+        Ok(tags) => Ok(tags),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// entity_type is stock_item, stock_riven or transaction
+#[tauri::command]
+pub async fn add_tag(
+    entity_type: String,
+    entity_id: i64,
+    name: String,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<String>, AppError> {
+    let db = db.lock()?.clone();
+    match db.tag().add(&entity_type, entity_id, &name).await {
+        Ok(_) => {}
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+    db.tag().get_entity_tags(&entity_type, entity_id).await
+}
+
+#[tauri::command]
+pub async fn remove_tag(
+    entity_type: String,
+    entity_id: i64,
+    name: String,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<String>, AppError> {
+    let db = db.lock()?.clone();
+    match db.tag().remove(&entity_type, entity_id, &name).await {
+        Ok(_) => {}
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+    db.tag().get_entity_tags(&entity_type, entity_id).await
+}
+
+#[tauri::command]
+pub async fn delete_tag(
+    name: String,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<(), AppError> {
+    let db = db.lock()?.clone();
+    match db.tag().delete(&name).await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_entity_tags(
+    entity_type: String,
+    entity_id: i64,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<String>, AppError> {
+    let db = db.lock()?.clone();
+    match db.tag().get_entity_tags(&entity_type, entity_id).await {
+        Ok(tags) => Ok(tags),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_tagged(
+    name: String,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<TaggedEntitiesStruct, AppError> {
+    let db = db.lock()?.clone();
+    match db.tag().get_tagged(&name).await {
+        Ok(tagged) => Ok(tagged),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Returns the path of the exported file
+#[tauri::command]
+pub async fn export_tag(
+    name: String,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<String, AppError> {
+    let db = db.lock()?.clone();
+    match db.tag().export(&name).await {
+        Ok(path) => Ok(path),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
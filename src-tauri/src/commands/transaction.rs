@@ -1,6 +1,18 @@
 use crate::{
-    database::{client::DBClient, modules::transaction::TransactionStruct},
+    database::{
+        client::DBClient,
+        modules::{
+            search::SearchResultStruct,
+            transaction::{
+                TransactionBucketStruct, TransactionImportMapping, TransactionImportReportStruct,
+                TransactionStruct,
+            },
+            undo::UndoChange,
+        },
+    },
+    enums::ErrorKind,
     error::{self, AppError},
+    projection::{self, TransactionListItem},
 };
 use eyre::eyre;
 use once_cell::sync::Lazy;
@@ -28,6 +40,10 @@ pub async fn create_transaction_entry(
         "CREATE_OR_UPDATE",
         serde_json::to_value(transaction.clone()).unwrap(),
     );
+    db.undo().push(
+        &format!("Add transaction {}", transaction.name),
+        vec![UndoChange::transaction(transaction.id, None, Some(&transaction))],
+    )?;
     Ok(transaction)
 }
 #[tauri::command]
@@ -55,8 +71,12 @@ pub async fn update_transaction_entry(
         .update_by_id(id, price, transaction_type, quantity, rank)
         .await
     {
-        Ok(transaction) => {
-            return Ok(transaction);
+        Ok(updated) => {
+            db.undo().push(
+                &format!("Edit transaction {}", updated.name),
+                vec![UndoChange::transaction(id, transaction.as_ref(), Some(&updated))],
+            )?;
+            return Ok(updated);
         }
         Err(e) => {
             error::create_log_file(db.log_file.clone(), &e);
@@ -86,5 +106,103 @@ pub async fn delete_transaction_entry(
     db.transaction().delete(id).await?;
     db.transaction()
         .emit("DELETE", serde_json::to_value(transaction.clone()).unwrap());
+    db.undo().push(
+        &format!("Delete transaction {}", transaction.name),
+        vec![UndoChange::transaction(id, Some(&transaction), None)],
+    )?;
     Ok(transaction)
 }
+
+#[tauri::command]
+pub async fn search_history(
+    query: String,
+    limit: Option<i64>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<SearchResultStruct>, AppError> {
+    let db = db.lock()?.clone();
+    match db.search().search(&query, limit.unwrap_or(100)).await {
+        Ok(results) => Ok(results),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_transaction_buckets(
+    group: String,
+    utc_offset_minutes: Option<i32>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<TransactionBucketStruct>, AppError> {
+    let db = db.lock()?.clone();
+    match db
+        .transaction()
+        .get_buckets(&group, utc_offset_minutes)
+        .await
+    {
+        Ok(buckets) => Ok(buckets),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_transactions_list(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<TransactionListItem>, AppError> {
+    let db = db.lock()?.clone();
+    match db.transaction().get_items().await {
+        Ok(transactions) => Ok(projection::project(&transactions)),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_transaction_detail(
+    id: i64,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<TransactionStruct, AppError> {
+    let db = db.lock()?.clone();
+    match db.transaction().get_by_id(id).await {
+        Ok(Some(transaction)) => Ok(transaction),
+        Ok(None) => Err(AppError::new(
+            "Command:Transaction",
+            eyre!("Transaction not found"),
+        )
+        .with_kind(ErrorKind::NotFound)),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Historical trades from a CSV, with dry_run the rows are only validated
+#[tauri::command]
+pub async fn import_transactions(
+    path: String,
+    mapping: TransactionImportMapping,
+    dry_run: Option<bool>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<TransactionImportReportStruct, AppError> {
+    let db = db.lock()?.clone();
+    let dry_run = dry_run.unwrap_or(false);
+    match db.transaction().import_csv(&path, &mapping, dry_run).await {
+        Ok(report) => {
+            if !dry_run && report.imported > 0 {
+                db.transaction().emit("SET", serde_json::to_value(db.transaction().get_items().await?).unwrap());
+            }
+            Ok(report)
+        }
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    database::{
+        client::DBClient,
+        modules::undo::{UndoCommand, UndoStatusStruct},
+    },
+    error::{self, AppError},
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_undo.log".to_string()));
+
+// Descriptions of the next undo and redo, for the tooltips of the buttons
+#[tauri::command]
+pub fn get_undo_status(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<UndoStatusStruct, AppError> {
+    let db = db.lock()?.clone();
+    db.undo().get_status()
+}
+
+#[tauri::command]
+pub async fn undo_last(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<UndoCommand, AppError> {
+    let db = db.lock()?.clone();
+    match db.undo().undo_last().await {
+        Ok(command) => Ok(command),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn redo_last(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<UndoCommand, AppError> {
+    let db = db.lock()?.clone();
+    match db.undo().redo_last().await {
+        Ok(command) => Ok(command),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use std::sync::{Arc, Mutex};
+
+use eyre::eyre;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::{
+    cache::client::CacheClient,
+    database::client::DBClient,
+    enums::{ErrorKind, LogLevel, OrderType},
+    error::{self, AppError},
+    handler::MonitorHandler,
+    settings::{SettingsState, WhisperTemplateSettings},
+    structs::Order,
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_whisper.log".to_string()));
+
+fn validation_error(message: String) -> AppError {
+    AppError::new_with_level("Whisper", eyre!(message), LogLevel::Warning)
+        .with_kind(ErrorKind::Validation)
+}
+
+fn update_templates(
+    settings: &Arc<Mutex<SettingsState>>,
+    update: impl FnOnce(&mut WhisperTemplateSettings) -> Result<(), AppError>,
+) -> Result<WhisperTemplateSettings, AppError> {
+    let mut settings = settings.lock()?;
+    update(&mut settings.whisper_templates)?;
+    settings.save_to_file()?;
+    Ok(settings.whisper_templates.clone())
+}
+
+#[tauri::command]
+pub fn get_whisper_templates(
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+) -> Result<WhisperTemplateSettings, AppError> {
+    Ok(settings.lock()?.whisper_templates.clone())
+}
+
+// Adds or replaces the template of a language, e.g. "de"
+#[tauri::command]
+pub fn set_whisper_template(
+    language: String,
+    template: String,
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+) -> Result<WhisperTemplateSettings, AppError> {
+    let language = language.trim().to_lowercase();
+    match update_templates(&settings, |templates| {
+        if language.is_empty() {
+            return Err(validation_error("A template needs a language".to_string()));
+        }
+        if !template.contains("<PLAYER_NAME>") {
+            return Err(validation_error(format!(
+                "The {} template has to whisper <PLAYER_NAME>",
+                language
+            )));
+        }
+        templates.templates.insert(language.clone(), template);
+        Ok(())
+    }) {
+        Ok(templates) => Ok(templates),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn remove_whisper_template(
+    language: String,
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+) -> Result<WhisperTemplateSettings, AppError> {
+    let language = language.trim().to_lowercase();
+    match update_templates(&settings, |templates| {
+        if templates.fallback == language {
+            return Err(validation_error(format!(
+                "{} is the fallback language, pick another fallback first",
+                language
+            )));
+        }
+        templates.templates.remove(&language);
+        Ok(())
+    }) {
+        Ok(templates) => Ok(templates),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Language used when there is no template for the buyer's language
+#[tauri::command]
+pub fn set_whisper_fallback(
+    language: String,
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+) -> Result<WhisperTemplateSettings, AppError> {
+    let language = language.trim().to_lowercase();
+    match update_templates(&settings, |templates| {
+        if !templates.templates.contains_key(&language) {
+            return Err(validation_error(format!(
+                "There is no template for {}",
+                language
+            )));
+        }
+        templates.fallback = language.clone();
+        Ok(())
+    }) {
+        Ok(templates) => Ok(templates),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Adds or replaces a trade message template, e.g. "buy" or "sell"
+#[tauri::command]
+pub fn set_trade_template(
+    name: String,
+    template: String,
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+) -> Result<WhisperTemplateSettings, AppError> {
+    let name = name.trim().to_lowercase();
+    match update_templates(&settings, |templates| {
+        if name.is_empty() {
+            return Err(validation_error(
+                "A trade template needs a name".to_string(),
+            ));
+        }
+        if !template.contains("<ITEM_NAME>") {
+            return Err(validation_error(format!(
+                "The {} trade template has to mention <ITEM_NAME>",
+                name
+            )));
+        }
+        templates.trade_templates.insert(name.clone(), template);
+        Ok(())
+    }) {
+        Ok(templates) => Ok(templates),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn remove_trade_template(
+    name: String,
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+) -> Result<WhisperTemplateSettings, AppError> {
+    let name = name.trim().to_lowercase();
+    match update_templates(&settings, |templates| {
+        templates.trade_templates.remove(&name);
+        Ok(())
+    }) {
+        Ok(templates) => Ok(templates),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// What the trade message is about, an order or a stock item
+#[derive(Deserialize, Debug)]
+pub struct TradeMessageRequest {
+    pub order: Option<Order>,
+    pub stock_item_id: Option<i64>,
+    // Item of the order, orders listed by item do not carry it
+    pub url_name: Option<String>,
+    // Replaces the owner of the order
+    pub player_name: Option<String>,
+    // Trade template name, "buy" or "sell" by default
+    pub template: Option<String>,
+}
+
+// Template, player, item name, quantity, price and rank of the trade.
+// Answering a sell order means buying from its owner and the other way around.
+async fn get_trade_details(
+    request: TradeMessageRequest,
+    cache: &Arc<Mutex<CacheClient>>,
+    db: &Arc<Mutex<DBClient>>,
+) -> Result<(&'static str, String, String, i64, i64, Option<i64>), AppError> {
+    let url_name = request.url_name;
+    let (template, player, url_name, quantity, price, rank) =
+        match (request.order, request.stock_item_id) {
+            (Some(order), _) => (
+                match order.order_type {
+                    OrderType::Buy => "sell",
+                    _ => "buy",
+                },
+                order.user.map(|user| user.ingame_name).unwrap_or_default(),
+                url_name
+                    .or(order.item.map(|item| item.url_name))
+                    .unwrap_or_default(),
+                order.quantity,
+                order.platinum,
+                order.mod_rank,
+            ),
+            (None, Some(id)) => {
+                let db = db.lock()?.clone();
+                let stock_item = match db.stock_item().get_by_id(id).await? {
+                    Some(stock_item) => stock_item,
+                    None => {
+                        return Err(AppError::new_with_level(
+                            "Whisper",
+                            eyre!("Stock item {} not found", id),
+                            LogLevel::Warning,
+                        )
+                        .with_kind(ErrorKind::NotFound))
+                    }
+                };
+                (
+                    "sell",
+                    "".to_string(),
+                    stock_item.url,
+                    stock_item.owned as i64,
+                    stock_item.listed_price.unwrap_or(0) as i64,
+                    Some(stock_item.rank as i64).filter(|rank| *rank > 0),
+                )
+            }
+            (None, None) => {
+                return Err(validation_error(
+                    "A trade message needs an order or a stock item".to_string(),
+                ))
+            }
+        };
+    let cache = cache.lock()?.clone();
+    match cache.items().find_type(&url_name)? {
+        Some(item) => Ok((template, player, item.item_name, quantity, price, rank)),
+        None => Err(validation_error(format!(
+            "{} is not a tradable item",
+            url_name
+        ))),
+    }
+}
+
+// The whisper for an order or a stock item, copied to the clipboard so it can be pasted in game
+#[tauri::command]
+pub async fn generate_trade_message(
+    request: TradeMessageRequest,
+    settings: tauri::State<'_, Arc<Mutex<SettingsState>>>,
+    cache: tauri::State<'_, Arc<Mutex<CacheClient>>>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    mh: tauri::State<'_, Arc<Mutex<MonitorHandler>>>,
+) -> Result<String, AppError> {
+    let player_name = request.player_name.clone();
+    let template = request.template.clone();
+    let (default_template, player, item_name, quantity, price, rank) =
+        match get_trade_details(request, &cache, &db).await {
+            Ok(details) => details,
+            Err(e) => {
+                error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+                return Err(e);
+            }
+        };
+    let template = template.unwrap_or(default_template.to_string());
+    let message = match settings.lock()?.whisper_templates.render_trade(
+        &template,
+        &player_name.unwrap_or(player),
+        quantity,
+        &item_name,
+        price,
+        rank,
+    ) {
+        Some(message) => message,
+        None => {
+            let e = validation_error(format!("There is no trade template named {}", template));
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    };
+    match mh.lock()?.copy_to_clipboard(&message) {
+        Ok(_) => Ok(message),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
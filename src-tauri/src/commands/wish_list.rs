@@ -0,0 +1,118 @@
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    database::{client::DBClient, modules::wish_list::WishListStruct},
+    error::{self, AppError},
+    live_scraper::client::LiveScraperClient,
+    wfm_client::client::WFMClient,
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> =
+    Lazy::new(|| Mutex::new("command_wish_list.log".to_string()));
+
+#[tauri::command]
+pub async fn get_wish_list(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<WishListStruct>, AppError> {
+    let db = db.lock()?.clone();
+    match db.wish_list().get_items().await {
+        Ok(items) => Ok(items),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// The live scraper places the buy order on its next pass
+#[tauri::command]
+pub async fn create_wish_list_item(
+    url_name: String,
+    max_price: i64,
+    quantity: i64,
+    rank: Option<i32>,
+    sub_type: Option<String>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<WishListStruct, AppError> {
+    let db = db.lock()?.clone();
+    match db
+        .wish_list()
+        .create(
+            &url_name,
+            max_price,
+            quantity,
+            rank.unwrap_or(0),
+            sub_type.as_deref(),
+        )
+        .await
+    {
+        Ok(item) => Ok(item),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn update_wish_list_item(
+    id: i64,
+    max_price: Option<i64>,
+    quantity: Option<i64>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<WishListStruct, AppError> {
+    let db = db.lock()?.clone();
+    match db.wish_list().update(id, max_price, quantity, None).await {
+        Ok(item) => Ok(item),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+// Removes the item and the buy order the live scraper placed for it
+#[tauri::command]
+pub async fn delete_wish_list_item(
+    id: i64,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+    live_scraper: tauri::State<'_, Arc<Mutex<LiveScraperClient>>>,
+) -> Result<WishListStruct, AppError> {
+    let db = db.lock()?.clone();
+    let wfm = wfm.lock()?.clone();
+    let live_scraper = live_scraper.lock()?.clone();
+    let item = match db.wish_list().delete(id).await {
+        Ok(item) => item,
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    };
+
+    let orders = match wfm.orders().get_my_orders().await {
+        Ok(orders) => orders,
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    };
+    let order = orders.buy_orders.into_iter().find(|o| match &o.item {
+        Some(order_item) => order_item.url_name == item.url,
+        None => false,
+    });
+    if let Some(order) = order {
+        match wfm.orders().delete(&order.id).await {
+            Ok(_) => {}
+            Err(e) => {
+                error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+                return Err(e);
+            }
+        }
+        live_scraper.order_budget().release(&item.url)?;
+    }
+    Ok(item)
+}
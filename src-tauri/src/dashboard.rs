@@ -0,0 +1,173 @@
+use std::{net::SocketAddr, sync::Mutex};
+
+use futures_util::{SinkExt, StreamExt};
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::broadcast,
+};
+use tokio_tungstenite::tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    http::StatusCode,
+    Message,
+};
+
+use crate::{
+    api_tokens::{ApiTokenClient, SCOPE_READ_ANALYTICS},
+    logger,
+    settings::DashboardSettings,
+};
+
+const LOG_FILE: &str = "dashboard.log";
+// Messages a slow client can fall behind before it skips ahead
+const CHANNEL_SIZE: usize = 256;
+
+// Set once the server is started, events are dropped until then
+static CHANNEL: Lazy<Mutex<Option<broadcast::Sender<String>>>> = Lazy::new(|| Mutex::new(None));
+
+// Scraper progress, run reports and sales, the rest of the window events stay local
+fn is_dashboard_event(event: &str, data: &Option<Value>) -> bool {
+    match event {
+        "Client:Update:Progress" | "LiveScraper:UpdateMessage" => true,
+        "Client:Update" => data
+            .as_ref()
+            .and_then(|data| data["type"].as_str())
+            .map(|update_type| update_type == "transactions")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+// Called for every message sent to the window
+pub fn publish(event: &str, data: &Option<Value>) {
+    let channel = CHANNEL.lock().unwrap();
+    let sender = match &*channel {
+        Some(sender) => sender,
+        None => return,
+    };
+    if !is_dashboard_event(event, data) {
+        return;
+    }
+    // Fails only when no dashboard is connected
+    let _ = sender.send(json!({ "event": event, "data": data }).to_string());
+}
+
+// Clients connect with ws://<host>:<port>/?token=<api token>, the token needs the read-analytics scope
+pub fn start(settings: &DashboardSettings, api_tokens: ApiTokenClient) {
+    if !settings.enabled {
+        return;
+    }
+    let (sender, _) = broadcast::channel(CHANNEL_SIZE);
+    *CHANNEL.lock().unwrap() = Some(sender.clone());
+    let host = if settings.allow_lan {
+        "0.0.0.0"
+    } else {
+        "127.0.0.1"
+    };
+    let address = format!("{}:{}", host, settings.port);
+
+    tauri::async_runtime::spawn(async move {
+        let listener = match TcpListener::bind(&address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                logger::error_file(
+                    "Dashboard",
+                    format!("Could not listen on {}: {}", address, e).as_str(),
+                    Some(LOG_FILE),
+                );
+                return;
+            }
+        };
+        logger::info_file(
+            "Dashboard",
+            format!("Listening on {}", address).as_str(),
+            Some(LOG_FILE),
+        );
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer)) => {
+                    tauri::async_runtime::spawn(handle_connection(
+                        stream,
+                        peer,
+                        sender.subscribe(),
+                        api_tokens.clone(),
+                    ));
+                }
+                Err(e) => logger::warning_file(
+                    "Dashboard",
+                    format!("Could not accept a connection: {}", e).as_str(),
+                    Some(LOG_FILE),
+                ),
+            }
+        }
+    });
+}
+
+fn get_token(request: &Request) -> String {
+    request
+        .uri()
+        .query()
+        .unwrap_or("")
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+        .unwrap_or("")
+        .to_string()
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    peer: SocketAddr,
+    mut receiver: broadcast::Receiver<String>,
+    api_tokens: ApiTokenClient,
+) {
+    let authorize = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        match api_tokens.authorize(&get_token(request), SCOPE_READ_ANALYTICS) {
+            Ok(_) => Ok(response),
+            Err(e) => {
+                logger::warning_file(
+                    "Dashboard",
+                    format!("Rejected {}: {}", peer, e.cause()).as_str(),
+                    Some(LOG_FILE),
+                );
+                let mut error = ErrorResponse::new(Some("Unauthorized".to_string()));
+                *error.status_mut() = StatusCode::UNAUTHORIZED;
+                Err(error)
+            }
+        }
+    };
+    let socket = match tokio_tungstenite::accept_hdr_async(stream, authorize).await {
+        Ok(socket) => socket,
+        Err(_) => return,
+    };
+    logger::info_file(
+        "Dashboard",
+        format!("Dashboard connected from {}", peer).as_str(),
+        Some(LOG_FILE),
+    );
+
+    let (mut write, mut read) = socket.split();
+    loop {
+        tokio::select! {
+            message = receiver.recv() => match message {
+                Ok(text) => {
+                    if write.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = read.next() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // The stream is one way, anything the dashboard sends is ignored
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    logger::info_file(
+        "Dashboard",
+        format!("Dashboard disconnected from {}", peer).as_str(),
+        Some(LOG_FILE),
+    );
+}
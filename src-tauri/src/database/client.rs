@@ -19,13 +19,32 @@ use crate::{
     wfm_client::client::WFMClient,
 };
 
-use super::modules::{ transaction::TransactionModule, stock_item::StockItemModule, stock_riven::StockRivenModule};
+// Every column holding a timestamp, stored timestamps used to be naive local time
+const TIMESTAMP_COLUMNS: [(&str, &[&str]); 13] = [
+    ("stock_item", &["created", "listed_at", "resupply_at"]),
+    ("stock_riven", &["created"]),
+    ("stock_set", &["updated"]),
+    ("transaction", &["created"]),
+    ("audit_log", &["created"]),
+    ("buy_book_snapshot", &["created"]),
+    ("price_backfill_day", &["created"]),
+    ("shadow_decision", &["created"]),
+    ("tag", &["created"]),
+    ("wish_list", &["created"]),
+    ("order_journal", &["created"]),
+    ("stock_price_history", &["created"]),
+    ("order_reprice", &["last_reprice", "pending_since"]),
+];
+
+use super::modules::{ analytics::AnalyticsModule, audit_log::AuditLogModule, buy_book::BuyBookModule, item_price::ItemPriceModule, price_snapshot::PriceSnapshotModule, search::SearchModule, shadow_decision::ShadowDecisionModule, transaction::TransactionModule, stock_item::StockItemModule, stock_riven::StockRivenModule, stock_set::StockSetModule, tag::TagModule, undo::{UndoModule, UndoStack}, wish_list::WishListModule, order_journal::OrderJournalModule, stock_price_history::StockPriceHistoryModule, order_reprice::OrderRepriceModule};
 #[derive(Clone, Debug)]
 pub struct DBClient {
     pub log_file: String,
     pub connection: Arc<Mutex<Pool<Sqlite>>>,
     pub cache: Arc<Mutex<CacheClient>>,
     pub wfm: Arc<Mutex<WFMClient>>,
+    // Stock and transaction changes made by the user, only kept for the current session
+    pub undo_stack: Arc<Mutex<UndoStack>>,
 }
 
 impl DBClient {
@@ -56,22 +75,70 @@ impl DBClient {
             connection: Arc::new(Mutex::new(SqlitePool::connect(db_url).await.unwrap())),
             cache,
             wfm,
+            undo_stack: Arc::new(Mutex::new(UndoStack::default())),
         })
     }
     pub async fn initialize(&self) -> Result<bool, AppError> {
         self.stock_item().initialize().await?;
         self.stock_riven().initialize().await?;
+        self.stock_set().initialize().await?;
         self.transaction().initialize().await?;
+        self.audit_log().initialize().await?;
+        self.search().initialize().await?;
+        self.buy_book().initialize().await?;
+        self.price_snapshot().initialize().await?;
+        self.item_price().initialize().await?;
+        self.shadow_decision().initialize().await?;
+        self.tag().initialize().await?;
+        self.wish_list().initialize().await?;
+        self.order_journal().initialize().await?;
+        self.stock_price_history().initialize().await?;
+        self.order_reprice().initialize().await?;
+        let connection = self.connection.lock()?.clone();
+        // Every timestamp is written as UTC now, the old ones only need rewriting once
+        if !helper::is_migrated(connection.clone(), "timestamps_to_utc").await? {
+            for (table, columns) in TIMESTAMP_COLUMNS {
+                helper::migrate_timestamps_to_utc(connection.clone(), table, columns.to_vec()).await?;
+            }
+            helper::mark_migrated(connection.clone(), "timestamps_to_utc").await?;
+        }
         Ok(true)
     }
     pub fn get_connection(&self) -> Arc<Mutex<Pool<Sqlite>>> {
         self.connection.clone()
     }
 
+    pub fn analytics(&self) -> AnalyticsModule {
+        AnalyticsModule { client: self }
+    }
+
     pub fn transaction(&self) -> TransactionModule {
         TransactionModule { client: self }
     }
 
+    pub fn audit_log(&self) -> AuditLogModule {
+        AuditLogModule { client: self }
+    }
+
+    pub fn buy_book(&self) -> BuyBookModule {
+        BuyBookModule { client: self }
+    }
+
+    pub fn price_snapshot(&self) -> PriceSnapshotModule {
+        PriceSnapshotModule { client: self }
+    }
+
+    pub fn item_price(&self) -> ItemPriceModule {
+        ItemPriceModule { client: self }
+    }
+    pub fn shadow_decision(&self) -> ShadowDecisionModule {
+        ShadowDecisionModule { client: self }
+    }
+
+    pub fn search(&self) -> SearchModule {
+        SearchModule { client: self }
+    }
+
     pub fn stock_item(&self) -> StockItemModule {
         StockItemModule { client: self }
     }
@@ -79,4 +146,32 @@ impl DBClient {
     pub fn stock_riven(&self) -> StockRivenModule {
         StockRivenModule { client: self }
     }
+
+    pub fn stock_set(&self) -> StockSetModule {
+        StockSetModule { client: self }
+    }
+
+    pub fn tag(&self) -> TagModule {
+        TagModule { client: self }
+    }
+
+    pub fn undo(&self) -> UndoModule {
+        UndoModule { client: self }
+    }
+
+    pub fn wish_list(&self) -> WishListModule {
+        WishListModule { client: self }
+    }
+
+    pub fn order_journal(&self) -> OrderJournalModule {
+        OrderJournalModule { client: self }
+    }
+
+    pub fn stock_price_history(&self) -> StockPriceHistoryModule {
+        StockPriceHistoryModule { client: self }
+    }
+
+    pub fn order_reprice(&self) -> OrderRepriceModule {
+        OrderRepriceModule { client: self }
+    }
 }
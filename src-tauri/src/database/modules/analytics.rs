@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::{
+        client::DBClient,
+        modules::transaction::{TransactionBucketStruct, TransactionStruct},
+    },
+    error::AppError,
+    helper,
+};
+
+pub struct AnalyticsModule<'a> {
+    pub client: &'a DBClient,
+}
+
+// Trading results of one item, sales are matched with the oldest purchases still held
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ItemTurnoverStruct {
+    pub url: String,
+    pub name: String,
+    pub item_type: String,
+    pub bought: i64,
+    pub sold: i64,
+    pub buy_total: i64,
+    pub sell_total: i64,
+    // Sales minus what the sold units cost, sales without a matching purchase count at no cost
+    pub realized_profit: f64,
+    // Realized profit over the cost of the sold units, None when nothing bought was sold
+    pub roi: Option<f64>,
+    // Days between buying and selling a unit, None when nothing bought was sold
+    pub avg_hold_days: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AnalyticsSummaryStruct {
+    pub trades: i64,
+    pub buy_total: i64,
+    pub sell_total: i64,
+    pub realized_profit: f64,
+    pub roi: Option<f64>,
+    pub avg_hold_days: Option<f64>,
+}
+
+// An item Baro Ki'Teer takes and how many ducats a plat of it is worth
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DucatItemStruct {
+    pub url: String,
+    pub name: String,
+    pub ducats: i64,
+    // Cheapest weekly sell price, or the closed median when nobody lists it. Stock without
+    // price data uses the bought price
+    pub price: f64,
+    pub ducats_per_plat: f64,
+    // Stock copies that can be traded for ducats, 0 for the market items
+    pub owned: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DucatAnalysisStruct {
+    pub stock: Vec<DucatItemStruct>,
+    // Ducat items not in the stock, cheapest to collect first
+    pub market: Vec<DucatItemStruct>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TradeAnalyticsStruct {
+    pub summary: AnalyticsSummaryStruct,
+    pub periods: Vec<TransactionBucketStruct>,
+    pub items: Vec<ItemTurnoverStruct>,
+}
+
+// What the units of one sale cost and how long they were held, units without a purchase are left out
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SaleMatchStruct {
+    pub cost: f64,
+    pub matched: i64,
+    pub avg_hold_days: Option<f64>,
+}
+
+// A purchased unit price and when it was bought, sold oldest first
+struct Lot {
+    quantity: i64,
+    unit_price: f64,
+    bought_at: DateTime<Utc>,
+}
+
+// Running totals of the matched units, turned into averages at the end
+#[derive(Default)]
+struct Matched {
+    cost: f64,
+    revenue: f64,
+    units: i64,
+    hold_seconds: f64,
+}
+
+impl Matched {
+    fn add(&mut self, other: &Matched) {
+        self.cost += other.cost;
+        self.revenue += other.revenue;
+        self.units += other.units;
+        self.hold_seconds += other.hold_seconds;
+    }
+
+    fn get_roi(&self) -> Option<f64> {
+        if self.units == 0 || self.cost <= 0.0 {
+            return None;
+        }
+        Some((self.revenue - self.cost) / self.cost)
+    }
+
+    fn get_avg_hold_days(&self) -> Option<f64> {
+        if self.units == 0 {
+            return None;
+        }
+        Some(self.hold_seconds / self.units as f64 / 86400.0)
+    }
+}
+
+impl<'a> AnalyticsModule<'a> {
+    // Per item totals, lots are matched per url and rank so ranked copies are not mixed
+    pub async fn get_item_turnover(&self) -> Result<Vec<ItemTurnoverStruct>, AppError> {
+        Ok(self
+            .get_turnover()
+            .await?
+            .0
+            .into_iter()
+            .map(|(item, _)| item)
+            .collect())
+    }
+
+    // My stock and the market's ducat items, best ducats per plat first. Market items are capped at limit.
+    pub async fn get_ducat_analysis(&self, limit: usize) -> Result<DucatAnalysisStruct, AppError> {
+        let cache = self.client.cache.lock()?.clone();
+        let ducats: HashMap<String, (String, i64)> = cache
+            .items()
+            .get_types()?
+            .into_iter()
+            .filter_map(|item| Some((item.url_name, (item.item_name, item.ducats?))))
+            .filter(|(_, (_, ducats))| *ducats > 0)
+            .collect();
+        let names: Vec<String> = ducats.keys().cloned().collect();
+
+        let mut prices: HashMap<String, f64> = HashMap::new();
+        for average in self.client.item_price().get_averages(&names).await? {
+            match average.order_type.as_str() {
+                "sell" if average.min_price > 0.0 => {
+                    prices.insert(average.name, average.min_price);
+                }
+                "closed" if average.median > 0.0 => {
+                    prices.entry(average.name).or_insert(average.median);
+                }
+                _ => {}
+            }
+        }
+        let get_item =
+            |url: &str, name: &str, ducats: i64, price: f64, owned: i64| DucatItemStruct {
+                url: url.to_string(),
+                name: name.to_string(),
+                ducats,
+                price,
+                ducats_per_plat: ducats as f64 / price.max(1.0),
+                owned,
+            };
+
+        let mut stock = vec![];
+        for stock_item in self.client.stock_item().get_items().await? {
+            let owned = stock_item.get_sellable() as i64;
+            let value = match ducats.get(&stock_item.url) {
+                Some((_, value)) if owned > 0 => *value,
+                _ => continue,
+            };
+            let price = prices
+                .get(&stock_item.url)
+                .cloned()
+                .unwrap_or(stock_item.price);
+            stock.push(get_item(
+                &stock_item.url,
+                &stock_item.name,
+                value,
+                price,
+                owned,
+            ));
+        }
+        stock.sort_by(|a, b| b.ducats_per_plat.total_cmp(&a.ducats_per_plat));
+
+        let mut market: Vec<DucatItemStruct> = ducats
+            .iter()
+            .filter(|(url, _)| !stock.iter().any(|item| &item.url == *url))
+            .filter_map(|(url, (name, value))| {
+                Some(get_item(url, name, *value, *prices.get(url)?, 0))
+            })
+            .collect();
+        market.sort_by(|a, b| b.ducats_per_plat.total_cmp(&a.ducats_per_plat));
+        market.truncate(limit);
+        Ok(DucatAnalysisStruct { stock, market })
+    }
+
+    // Cost and holding time of every sale keyed by transaction id, used by the exports
+    pub async fn get_sale_matches(&self) -> Result<HashMap<i64, SaleMatchStruct>, AppError> {
+        Ok(self.get_turnover().await?.1)
+    }
+
+    async fn get_turnover(
+        &self,
+    ) -> Result<
+        (
+            Vec<(ItemTurnoverStruct, Matched)>,
+            HashMap<i64, SaleMatchStruct>,
+        ),
+        AppError,
+    > {
+        let mut transactions: Vec<(DateTime<Utc>, TransactionStruct)> = self
+            .client
+            .transaction()
+            .get_items()
+            .await?
+            .into_iter()
+            .filter_map(|t| Some((helper::parse_timestamp(&t.created)?, t)))
+            .collect();
+        transactions.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.id.cmp(&b.1.id)));
+
+        let mut lots: HashMap<(String, i32), Vec<Lot>> = HashMap::new();
+        let mut items: HashMap<String, (ItemTurnoverStruct, Matched)> = HashMap::new();
+        let mut sales: HashMap<i64, SaleMatchStruct> = HashMap::new();
+        for (created, transaction) in transactions {
+            let quantity = (transaction.quantity as i64).max(1);
+            let (item, matched) = items.entry(transaction.url.clone()).or_insert_with(|| {
+                (
+                    ItemTurnoverStruct {
+                        url: transaction.url.clone(),
+                        name: transaction.name.clone(),
+                        item_type: transaction.item_type.clone(),
+                        ..Default::default()
+                    },
+                    Matched::default(),
+                )
+            });
+            let item_lots = lots
+                .entry((transaction.url.clone(), transaction.rank))
+                .or_default();
+            match transaction.transaction_type.as_str() {
+                "buy" => {
+                    item.bought += quantity;
+                    item.buy_total += transaction.price as i64;
+                    item_lots.push(Lot {
+                        quantity,
+                        unit_price: transaction.price as f64 / quantity as f64,
+                        bought_at: created,
+                    });
+                }
+                "sell" | "gift" => {
+                    let is_sale = transaction.transaction_type == "sell";
+                    let unit_revenue = transaction.price as f64 / quantity as f64;
+                    if is_sale {
+                        item.sold += quantity;
+                        item.sell_total += transaction.price as i64;
+                        item.realized_profit += transaction.price as f64;
+                    }
+                    let mut sale = Matched::default();
+                    let mut left = quantity;
+                    while left > 0 && !item_lots.is_empty() {
+                        let lot = &mut item_lots[0];
+                        let taken = lot.quantity.min(left);
+                        lot.quantity -= taken;
+                        left -= taken;
+                        // Gifts use up stock but are left out of the profit
+                        if is_sale {
+                            let cost = lot.unit_price * taken as f64;
+                            item.realized_profit -= cost;
+                            sale.cost += cost;
+                            sale.revenue += unit_revenue * taken as f64;
+                            sale.units += taken;
+                            let held = (created - lot.bought_at).num_seconds().max(0);
+                            sale.hold_seconds += held as f64 * taken as f64;
+                        }
+                        if lot.quantity == 0 {
+                            item_lots.remove(0);
+                        }
+                    }
+                    if is_sale {
+                        matched.add(&sale);
+                        sales.insert(
+                            transaction.id,
+                            SaleMatchStruct {
+                                cost: sale.cost,
+                                matched: sale.units,
+                                avg_hold_days: sale.get_avg_hold_days(),
+                            },
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut items: Vec<(ItemTurnoverStruct, Matched)> = items
+            .into_values()
+            .map(|(mut item, matched)| {
+                item.roi = matched.get_roi();
+                item.avg_hold_days = matched.get_avg_hold_days();
+                (item, matched)
+            })
+            .collect();
+        items.sort_by(|a, b| b.0.realized_profit.total_cmp(&a.0.realized_profit));
+        Ok((items, sales))
+    }
+
+    // Everything the profit dashboard shows, periods are grouped like the transaction buckets
+    pub async fn get_trade_analytics(
+        &self,
+        group: &str,
+        utc_offset_minutes: Option<i32>,
+    ) -> Result<TradeAnalyticsStruct, AppError> {
+        let periods = self
+            .client
+            .transaction()
+            .get_buckets(group, utc_offset_minutes)
+            .await?;
+        let (turnover, _) = self.get_turnover().await?;
+
+        let mut summary = AnalyticsSummaryStruct::default();
+        let mut matched = Matched::default();
+        for (item, item_matched) in turnover.iter() {
+            summary.buy_total += item.buy_total;
+            summary.sell_total += item.sell_total;
+            summary.realized_profit += item.realized_profit;
+            matched.add(item_matched);
+        }
+        summary.trades = periods.iter().map(|p| p.buy_count + p.sell_count).sum();
+        summary.roi = matched.get_roi();
+        summary.avg_hold_days = matched.get_avg_hold_days();
+
+        Ok(TradeAnalyticsStruct {
+            summary,
+            periods,
+            items: turnover.into_iter().map(|(item, _)| item).collect(),
+        })
+    }
+}
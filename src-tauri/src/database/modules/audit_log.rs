@@ -0,0 +1,138 @@
+use crate::{
+    database::client::DBClient,
+    error::AppError,
+    helper,
+    logger::{self},
+};
+use eyre::eyre;
+use sea_query::{ColumnDef, Iden, InsertStatement, Order, Query, SqliteQueryBuilder, Table};
+use serde::{Deserialize, Serialize};
+
+pub struct AuditLogModule<'a> {
+    pub client: &'a DBClient,
+}
+#[derive(Iden)]
+pub enum AuditLog {
+    Table,
+    Id,
+    Action,
+    Component,
+    Message,
+    Properties,
+    Created,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+#[allow(dead_code)]
+pub struct AuditLogStruct {
+    pub id: i64,
+    pub action: String,
+    pub component: String,
+    pub message: String,
+    pub properties: Option<sqlx::types::Json<Option<serde_json::Value>>>,
+    pub created: String,
+}
+impl<'a> AuditLogModule<'a> {
+    pub async fn initialize(&self) -> Result<bool, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Table::create()
+            .table(AuditLog::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(AuditLog::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(AuditLog::Action).string().not_null())
+            .col(ColumnDef::new(AuditLog::Component).string().not_null())
+            .col(ColumnDef::new(AuditLog::Message).string().not_null())
+            .col(ColumnDef::new(AuditLog::Properties).json())
+            .col(ColumnDef::new(AuditLog::Created).date_time().not_null())
+            .build(SqliteQueryBuilder);
+
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(true)
+    }
+
+    pub async fn get_items(&self) -> Result<Vec<AuditLogStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([
+                AuditLog::Id,
+                AuditLog::Action,
+                AuditLog::Component,
+                AuditLog::Message,
+                AuditLog::Properties,
+                AuditLog::Created,
+            ])
+            .from(AuditLog::Table)
+            .order_by(AuditLog::Id, Order::Desc)
+            .to_string(SqliteQueryBuilder);
+
+        let rows = sqlx::query_as::<_, AuditLogStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+
+    pub async fn create(
+        &self,
+        action: &str,
+        component: &str,
+        message: &str,
+        properties: Option<serde_json::Value>,
+    ) -> Result<AuditLogStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut entry = AuditLogStruct {
+            id: 0,
+            action: action.to_string(),
+            component: component.to_string(),
+            message: message.to_string(),
+            properties: Some(sqlx::types::Json(properties.clone())),
+            created: chrono::Utc::now().to_rfc3339(),
+        };
+
+        logger::info_con(
+            "Database",
+            format!("Creating Audit Log: {} {} {}", action, component, message).as_str(),
+        );
+
+        let sql = InsertStatement::default()
+            .into_table(AuditLog::Table)
+            .columns([
+                AuditLog::Action,
+                AuditLog::Component,
+                AuditLog::Message,
+                AuditLog::Properties,
+                AuditLog::Created,
+            ])
+            .values_panic([
+                entry.action.clone().into(),
+                entry.component.clone().into(),
+                entry.message.clone().into(),
+                properties.into(),
+                entry.created.clone().into(),
+            ])
+            .to_string(SqliteQueryBuilder);
+        let row = sqlx::query(&sql.replace("\\", ""))
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        entry.id = row.last_insert_rowid();
+        self.emit(
+            "CREATE_OR_UPDATE",
+            serde_json::to_value(entry.clone()).unwrap(),
+        );
+        Ok(entry)
+    }
+
+    pub fn emit(&self, operation: &str, data: serde_json::Value) {
+        helper::emit_update("audit_logs", operation, Some(data));
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{database::client::DBClient, error::AppError, helper};
+use eyre::eyre;
+use sea_query::{ColumnDef, Expr, Iden, InsertStatement, Order, Query, SqliteQueryBuilder, Table};
+use serde::{Deserialize, Serialize};
+
+// Days the snapshots are kept
+const RETENTION_DAYS: i64 = 60;
+
+pub struct BuyBookModule<'a> {
+    pub client: &'a DBClient,
+}
+#[derive(Iden)]
+pub enum BuyBookSnapshot {
+    Table,
+    Id,
+    Url,
+    TopPrice,
+    Buyers,
+    Created,
+}
+
+// Highest buy order of an item at the time the live scraper checked it
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct BuyBookSnapshotStruct {
+    pub id: i64,
+    pub url: String,
+    pub top_price: i64,
+    pub buyers: i64,
+    pub created: String,
+}
+
+impl<'a> BuyBookModule<'a> {
+    pub async fn initialize(&self) -> Result<bool, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Table::create()
+            .table(BuyBookSnapshot::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(BuyBookSnapshot::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(BuyBookSnapshot::Url).string().not_null())
+            .col(
+                ColumnDef::new(BuyBookSnapshot::TopPrice)
+                    .integer()
+                    .not_null(),
+            )
+            .col(ColumnDef::new(BuyBookSnapshot::Buyers).integer().not_null())
+            .col(
+                ColumnDef::new(BuyBookSnapshot::Created)
+                    .date_time()
+                    .not_null(),
+            )
+            .build(SqliteQueryBuilder);
+
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        self.prune().await?;
+        Ok(true)
+    }
+
+    // Only the recent market is useful for estimating fills
+    pub async fn prune(&self) -> Result<u64, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let cutoff = (chrono::Utc::now() - chrono::Duration::days(RETENTION_DAYS)).to_rfc3339();
+        let sql = Query::delete()
+            .from_table(BuyBookSnapshot::Table)
+            .and_where(Expr::col(BuyBookSnapshot::Created).lt(cutoff))
+            .to_string(SqliteQueryBuilder);
+        let result = sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_by_url(&self, url: &str) -> Result<Vec<BuyBookSnapshotStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([
+                BuyBookSnapshot::Id,
+                BuyBookSnapshot::Url,
+                BuyBookSnapshot::TopPrice,
+                BuyBookSnapshot::Buyers,
+                BuyBookSnapshot::Created,
+            ])
+            .from(BuyBookSnapshot::Table)
+            .and_where(Expr::col(BuyBookSnapshot::Url).eq(url))
+            .order_by(BuyBookSnapshot::Created, Order::Asc)
+            .to_string(SqliteQueryBuilder);
+
+        let rows = sqlx::query_as::<_, BuyBookSnapshotStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+
+    pub async fn create(
+        &self,
+        url: &str,
+        top_price: i64,
+        buyers: i64,
+    ) -> Result<BuyBookSnapshotStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut snapshot = BuyBookSnapshotStruct {
+            id: 0,
+            url: url.to_string(),
+            top_price,
+            buyers,
+            created: helper::get_utc_timestamp(),
+        };
+        let sql = InsertStatement::default()
+            .into_table(BuyBookSnapshot::Table)
+            .columns([
+                BuyBookSnapshot::Url,
+                BuyBookSnapshot::TopPrice,
+                BuyBookSnapshot::Buyers,
+                BuyBookSnapshot::Created,
+            ])
+            .values_panic([
+                snapshot.url.clone().into(),
+                snapshot.top_price.into(),
+                snapshot.buyers.into(),
+                snapshot.created.clone().into(),
+            ])
+            .to_string(SqliteQueryBuilder);
+        let row = sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        snapshot.id = row.last_insert_rowid();
+        Ok(snapshot)
+    }
+}
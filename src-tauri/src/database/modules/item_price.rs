@@ -0,0 +1,326 @@
+use crate::{database::client::DBClient, error::AppError};
+use eyre::eyre;
+use polars::prelude::*;
+use sea_query::{
+    Alias, ColumnDef, Expr, Func, Iden, InsertStatement, Order, Query, SelectStatement,
+    SqliteQueryBuilder, Table,
+};
+use serde::{Deserialize, Serialize};
+
+// Rows per insert statement when the price data is replaced
+const INSERT_CHUNK_SIZE: usize = 500;
+
+pub struct ItemPriceModule<'a> {
+    pub client: &'a DBClient,
+}
+
+// The price data of the last week written by the price scraper, one row per item, day and order type
+#[derive(Iden)]
+pub enum ItemPrice {
+    Table,
+    Id,
+    Name,
+    ItemId,
+    Day,
+    OrderType,
+    Volume,
+    MinPrice,
+    MaxPrice,
+    AvgPrice,
+    Range,
+    Median,
+    ModRank,
+}
+
+// The averages of an item and order type over every stored day
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct ItemPriceAverageStruct {
+    pub name: String,
+    pub order_type: String,
+    pub volume: f64,
+    pub min_price: f64,
+    pub max_price: f64,
+    pub range: f64,
+    pub median: f64,
+    pub avg_price: f64,
+    pub mod_rank: Option<f64>,
+    pub item_id: String,
+}
+
+impl<'a> ItemPriceModule<'a> {
+    pub async fn initialize(&self) -> Result<bool, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Table::create()
+            .table(ItemPrice::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(ItemPrice::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(ItemPrice::Name).string().not_null())
+            .col(ColumnDef::new(ItemPrice::ItemId).string().not_null())
+            .col(ColumnDef::new(ItemPrice::Day).string().not_null())
+            .col(ColumnDef::new(ItemPrice::OrderType).string().not_null())
+            .col(ColumnDef::new(ItemPrice::Volume).float().not_null())
+            .col(ColumnDef::new(ItemPrice::MinPrice).float().not_null())
+            .col(ColumnDef::new(ItemPrice::MaxPrice).float().not_null())
+            .col(ColumnDef::new(ItemPrice::AvgPrice).float().not_null())
+            .col(ColumnDef::new(ItemPrice::Range).float().not_null())
+            .col(ColumnDef::new(ItemPrice::Median).float().not_null())
+            .col(ColumnDef::new(ItemPrice::ModRank).float())
+            .build(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        // Every lookup is by item and order type, the day keeps the rows of an item in order
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_item_price_name_order_type_day ON item_price (name, order_type, day)",
+        )
+        .execute(&connection)
+        .await
+        .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(true)
+    }
+
+    pub async fn is_empty(&self) -> Result<bool, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .expr(Func::count(Expr::col(ItemPrice::Id)))
+            .from(ItemPrice::Table)
+            .to_string(SqliteQueryBuilder);
+        let count = sqlx::query_scalar::<_, i64>(&sql)
+            .fetch_one(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(count == 0)
+    }
+
+    // Replaces the stored price data with the rows of a price scraper DataFrame
+    pub async fn import_dataframe(&self, df: &DataFrame) -> Result<usize, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let df = df
+            .clone()
+            .lazy()
+            .select([
+                col("name").cast(DataType::Utf8),
+                col("item_id").cast(DataType::Utf8),
+                col("datetime").cast(DataType::Utf8),
+                col("order_type").cast(DataType::Utf8),
+                col("volume").cast(DataType::Float64),
+                col("min_price").cast(DataType::Float64),
+                col("max_price").cast(DataType::Float64),
+                col("avg_price").cast(DataType::Float64),
+                col("range").cast(DataType::Float64),
+                col("median").cast(DataType::Float64),
+                col("mod_rank").cast(DataType::Float64),
+            ])
+            .collect()
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        let get_utf8 = |name: &str| -> Result<Vec<Option<String>>, AppError> {
+            df.column(name)
+                .and_then(|s| {
+                    s.utf8()
+                        .map(|s| s.into_iter().map(|v| v.map(String::from)).collect())
+                })
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))
+        };
+        let get_f64 = |name: &str| -> Result<Vec<Option<f64>>, AppError> {
+            df.column(name)
+                .and_then(|s| s.f64().map(|s| s.into_iter().collect()))
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))
+        };
+        let names = get_utf8("name")?;
+        let item_ids = get_utf8("item_id")?;
+        let days = get_utf8("datetime")?;
+        let order_types = get_utf8("order_type")?;
+        let volumes = get_f64("volume")?;
+        let min_prices = get_f64("min_price")?;
+        let max_prices = get_f64("max_price")?;
+        let avg_prices = get_f64("avg_price")?;
+        let ranges = get_f64("range")?;
+        let medians = get_f64("median")?;
+        let mod_ranks = get_f64("mod_rank")?;
+
+        // Readers keep seeing the old prices until every row of the new ones is in
+        let mut tx = connection
+            .begin()
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        let sql = Query::delete()
+            .from_table(ItemPrice::Table)
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        let rows: Vec<usize> = (0..df.height())
+            .filter(|&i| names[i].is_some() && order_types[i].is_some())
+            .collect();
+        for chunk in rows.chunks(INSERT_CHUNK_SIZE) {
+            let mut insert = InsertStatement::default();
+            insert.into_table(ItemPrice::Table).columns([
+                ItemPrice::Name,
+                ItemPrice::ItemId,
+                ItemPrice::Day,
+                ItemPrice::OrderType,
+                ItemPrice::Volume,
+                ItemPrice::MinPrice,
+                ItemPrice::MaxPrice,
+                ItemPrice::AvgPrice,
+                ItemPrice::Range,
+                ItemPrice::Median,
+                ItemPrice::ModRank,
+            ]);
+            for &i in chunk {
+                insert.values_panic([
+                    names[i].clone().into(),
+                    item_ids[i].clone().unwrap_or_default().into(),
+                    days[i].clone().unwrap_or_default().into(),
+                    order_types[i].clone().into(),
+                    volumes[i].unwrap_or(0.0).into(),
+                    min_prices[i].unwrap_or(0.0).into(),
+                    max_prices[i].unwrap_or(0.0).into(),
+                    avg_prices[i].unwrap_or(0.0).into(),
+                    ranges[i].unwrap_or(0.0).into(),
+                    medians[i].unwrap_or(0.0).into(),
+                    mod_ranks[i].into(),
+                ]);
+            }
+            sqlx::query(&insert.to_string(SqliteQueryBuilder))
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        }
+        tx.commit()
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows.len())
+    }
+
+    fn select_averages() -> SelectStatement {
+        let mut select = Query::select();
+        select
+            .columns([ItemPrice::Name, ItemPrice::OrderType])
+            .from(ItemPrice::Table);
+        for (column, alias) in [
+            (ItemPrice::Volume, "volume"),
+            (ItemPrice::MinPrice, "min_price"),
+            (ItemPrice::MaxPrice, "max_price"),
+            (ItemPrice::Range, "range"),
+            (ItemPrice::Median, "median"),
+            (ItemPrice::AvgPrice, "avg_price"),
+            (ItemPrice::ModRank, "mod_rank"),
+        ] {
+            select.expr_as(Func::avg(Expr::col(column)), Alias::new(alias));
+        }
+        select
+            .expr_as(
+                Func::max(Expr::col(ItemPrice::ItemId)),
+                Alias::new("item_id"),
+            )
+            .group_by_columns([ItemPrice::Name, ItemPrice::OrderType]);
+        select
+    }
+
+    // Averages of every order type of the given items
+    pub async fn get_averages(
+        &self,
+        names: &[String],
+    ) -> Result<Vec<ItemPriceAverageStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Self::select_averages()
+            .and_where(Expr::col(ItemPrice::Name).is_in(names.to_vec()))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query_as::<_, ItemPriceAverageStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))
+    }
+
+    // Closed averages of the items traded and spread enough to flip, and of the inventory items.
+    // The widest range comes first.
+    pub async fn get_interesting_items(
+        &self,
+        volume_threshold: i64,
+        range_threshold: i64,
+        inventory: &[String],
+    ) -> Result<Vec<ItemPriceAverageStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Self::select_averages()
+            .and_where(Expr::col(ItemPrice::OrderType).eq("closed"))
+            .and_having(
+                Expr::expr(Func::avg(Expr::col(ItemPrice::Volume)))
+                    .gt(volume_threshold)
+                    .and(Expr::expr(Func::avg(Expr::col(ItemPrice::Range))).gt(range_threshold))
+                    .or(Expr::col(ItemPrice::Name).is_in(inventory.to_vec())),
+            )
+            .order_by(Alias::new("range"), Order::Desc)
+            .to_string(SqliteQueryBuilder);
+        sqlx::query_as::<_, ItemPriceAverageStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))
+    }
+}
+
+// Same columns as the averaged price DataFrame the live scraper works with
+pub fn convert_averages_to_dataframe(
+    averages: Vec<ItemPriceAverageStruct>,
+) -> Result<DataFrame, AppError> {
+    DataFrame::new(vec![
+        Series::new(
+            "name",
+            averages.iter().map(|a| a.name.clone()).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "order_type",
+            averages
+                .iter()
+                .map(|a| a.order_type.clone())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "volume",
+            averages.iter().map(|a| a.volume).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "min_price",
+            averages.iter().map(|a| a.min_price).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "max_price",
+            averages.iter().map(|a| a.max_price).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "range",
+            averages.iter().map(|a| a.range).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "median",
+            averages.iter().map(|a| a.median).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "avg_price",
+            averages.iter().map(|a| a.avg_price).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "mod_rank",
+            averages.iter().map(|a| a.mod_rank).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "item_id",
+            averages
+                .iter()
+                .map(|a| a.item_id.clone())
+                .collect::<Vec<_>>(),
+        ),
+    ])
+    .map_err(|e| AppError::new("Database", eyre!(e.to_string())))
+}
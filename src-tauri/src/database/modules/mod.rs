@@ -1,3 +1,17 @@
+pub mod analytics;
+pub mod audit_log;
+pub mod buy_book;
+pub mod item_price;
+pub mod order_journal;
+pub mod order_reprice;
+pub mod price_snapshot;
+pub mod search;
+pub mod shadow_decision;
 pub mod stock_item;
+pub mod stock_price_history;
 pub mod stock_riven;
+pub mod stock_set;
+pub mod tag;
 pub mod transaction;
+pub mod undo;
+pub mod wish_list;
@@ -0,0 +1,170 @@
+use crate::{database::client::DBClient, error::AppError, helper, structs::Order};
+use eyre::eyre;
+use sea_query::{ColumnDef, Expr, Iden, InsertStatement, Query, SqliteQueryBuilder, Table};
+use serde::{Deserialize, Serialize};
+
+pub struct OrderJournalModule<'a> {
+    pub client: &'a DBClient,
+}
+#[derive(Iden)]
+pub enum OrderJournal {
+    Table,
+    Id,
+    Batch,
+    Operation,
+    OrderId,
+    OrderData,
+    Status,
+    Created,
+}
+
+pub const PENDING: &str = "pending";
+pub const DELETED: &str = "deleted";
+
+// An order touched by a bulk operation. The rows of a batch are removed once the operation
+// finishes, whatever is left was interrupted and can be resumed or rolled back.
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct OrderJournalStruct {
+    pub id: i64,
+    pub batch: String,
+    pub operation: String,
+    pub order_id: String,
+    // The order as it was before the operation, enough to create it again
+    pub order_data: sqlx::types::Json<Order>,
+    pub status: String,
+    pub created: String,
+}
+
+impl<'a> OrderJournalModule<'a> {
+    pub async fn initialize(&self) -> Result<bool, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Table::create()
+            .table(OrderJournal::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(OrderJournal::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(OrderJournal::Batch).string().not_null())
+            .col(ColumnDef::new(OrderJournal::Operation).string().not_null())
+            .col(ColumnDef::new(OrderJournal::OrderId).string().not_null())
+            .col(ColumnDef::new(OrderJournal::OrderData).json().not_null())
+            .col(ColumnDef::new(OrderJournal::Status).string().not_null())
+            .col(ColumnDef::new(OrderJournal::Created).date_time().not_null())
+            .build(SqliteQueryBuilder);
+
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(true)
+    }
+
+    pub async fn get_entries(
+        &self,
+        batch: Option<&str>,
+    ) -> Result<Vec<OrderJournalStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut query = Query::select();
+        query
+            .columns([
+                OrderJournal::Id,
+                OrderJournal::Batch,
+                OrderJournal::Operation,
+                OrderJournal::OrderId,
+                OrderJournal::OrderData,
+                OrderJournal::Status,
+                OrderJournal::Created,
+            ])
+            .from(OrderJournal::Table);
+        if let Some(batch) = batch {
+            query.and_where(Expr::col(OrderJournal::Batch).eq(batch));
+        }
+        let sql = query.to_string(SqliteQueryBuilder);
+
+        let rows = sqlx::query_as::<_, OrderJournalStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+
+    // Journals the orders before the operation touches them, returns the batch they belong to
+    pub async fn begin(&self, operation: &str, orders: &Vec<Order>) -> Result<String, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let created = helper::get_utc_timestamp();
+        let batch = format!("{}-{}", operation, chrono::Utc::now().timestamp_millis());
+        if orders.is_empty() {
+            return Ok(batch);
+        }
+        let mut insert = InsertStatement::default();
+        insert.into_table(OrderJournal::Table).columns([
+            OrderJournal::Batch,
+            OrderJournal::Operation,
+            OrderJournal::OrderId,
+            OrderJournal::OrderData,
+            OrderJournal::Status,
+            OrderJournal::Created,
+        ]);
+        for order in orders {
+            let data = serde_json::to_value(order)
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+            insert.values_panic([
+                batch.clone().into(),
+                operation.into(),
+                order.id.clone().into(),
+                data.into(),
+                PENDING.into(),
+                created.clone().into(),
+            ]);
+        }
+        let sql = insert.to_string(SqliteQueryBuilder);
+        sqlx::query(&sql.replace("\\", ""))
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(batch)
+    }
+
+    pub async fn set_status(
+        &self,
+        batch: &str,
+        order_id: &str,
+        status: &str,
+    ) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::update()
+            .table(OrderJournal::Table)
+            .values([(OrderJournal::Status, status.into())])
+            .and_where(Expr::col(OrderJournal::Batch).eq(batch))
+            .and_where(Expr::col(OrderJournal::OrderId).eq(order_id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(())
+    }
+
+    // The operation finished, nothing is left to resume or roll back
+    pub async fn complete(&self, batch: &str) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::delete()
+            .from_table(OrderJournal::Table)
+            .and_where(Expr::col(OrderJournal::Batch).eq(batch))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        helper::emit_update(
+            "OrderJournal",
+            "DELETE",
+            Some(serde_json::json!({ "batch": batch })),
+        );
+        Ok(())
+    }
+}
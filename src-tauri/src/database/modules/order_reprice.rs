@@ -0,0 +1,150 @@
+use crate::{database::client::DBClient, error::AppError, helper};
+use eyre::eyre;
+use sea_query::{ColumnDef, Expr, Iden, InsertStatement, Query, SqliteQueryBuilder, Table};
+use serde::{Deserialize, Serialize};
+
+pub struct OrderRepriceModule<'a> {
+    pub client: &'a DBClient,
+}
+#[derive(Iden)]
+pub enum OrderReprice {
+    Table,
+    OrderId,
+    ItemName,
+    LastReprice,
+    PendingPrice,
+    PendingSince,
+}
+
+// When the live scraper last changed the price of an order, and the price it holds back until the
+// cooldown is over. Kept in the database so the cooldown survives restarts.
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct OrderRepriceStruct {
+    pub order_id: String,
+    pub item_name: String,
+    pub last_reprice: String,
+    pub pending_price: Option<i64>,
+    pub pending_since: Option<String>,
+}
+
+impl<'a> OrderRepriceModule<'a> {
+    pub async fn initialize(&self) -> Result<bool, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Table::create()
+            .table(OrderReprice::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(OrderReprice::OrderId)
+                    .string()
+                    .not_null()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(OrderReprice::ItemName).string().not_null())
+            .col(
+                ColumnDef::new(OrderReprice::LastReprice)
+                    .date_time()
+                    .not_null(),
+            )
+            .col(ColumnDef::new(OrderReprice::PendingPrice).integer())
+            .col(ColumnDef::new(OrderReprice::PendingSince).date_time())
+            .build(SqliteQueryBuilder);
+
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(true)
+    }
+
+    pub async fn get_entries(&self) -> Result<Vec<OrderRepriceStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([
+                OrderReprice::OrderId,
+                OrderReprice::ItemName,
+                OrderReprice::LastReprice,
+                OrderReprice::PendingPrice,
+                OrderReprice::PendingSince,
+            ])
+            .from(OrderReprice::Table)
+            .to_string(SqliteQueryBuilder);
+
+        let rows = sqlx::query_as::<_, OrderRepriceStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+
+    pub async fn get_by_order(
+        &self,
+        order_id: &str,
+    ) -> Result<Option<OrderRepriceStruct>, AppError> {
+        Ok(self
+            .get_entries()
+            .await?
+            .into_iter()
+            .find(|entry| entry.order_id == order_id))
+    }
+
+    // The order was repriced now, whatever was pending is replaced by it
+    pub async fn record(&self, order_id: &str, item_name: &str) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        self.delete(order_id).await?;
+        let sql = InsertStatement::default()
+            .into_table(OrderReprice::Table)
+            .columns([
+                OrderReprice::OrderId,
+                OrderReprice::ItemName,
+                OrderReprice::LastReprice,
+            ])
+            .values_panic([
+                order_id.into(),
+                item_name.into(),
+                helper::get_utc_timestamp().into(),
+            ])
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(())
+    }
+
+    // Keeps the latest price the order should move to, the time it was first held back stays
+    pub async fn set_pending(&self, order_id: &str, price: i64) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let pending_since = match self.get_by_order(order_id).await? {
+            Some(entry) => entry
+                .pending_since
+                .unwrap_or_else(helper::get_utc_timestamp),
+            None => return Ok(()),
+        };
+        let sql = Query::update()
+            .table(OrderReprice::Table)
+            .values([
+                (OrderReprice::PendingPrice, price.into()),
+                (OrderReprice::PendingSince, pending_since.into()),
+            ])
+            .and_where(Expr::col(OrderReprice::OrderId).eq(order_id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(())
+    }
+
+    pub async fn delete(&self, order_id: &str) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::delete()
+            .from_table(OrderReprice::Table)
+            .and_where(Expr::col(OrderReprice::OrderId).eq(order_id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::HashSet;
+
+use crate::{database::client::DBClient, error::AppError, helper};
+use eyre::eyre;
+use sea_query::{ColumnDef, Expr, Iden, InsertStatement, Order, Query, SqliteQueryBuilder, Table};
+use serde::{Deserialize, Serialize};
+
+pub struct PriceSnapshotModule<'a> {
+    pub client: &'a DBClient,
+}
+#[derive(Iden)]
+pub enum PriceSnapshot {
+    Table,
+    Id,
+    Url,
+    Day,
+    OrderType,
+    ModRank,
+    Volume,
+    MinPrice,
+    MaxPrice,
+    AvgPrice,
+    Median,
+}
+
+// Days the backfill has downloaded and the items it kept from them
+#[derive(Iden)]
+pub enum PriceBackfillDay {
+    Table,
+    Day,
+    Items,
+    Created,
+}
+
+// Daily price statistics of an item, kept for analytics and backtesting
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct PriceSnapshotStruct {
+    pub id: i64,
+    pub url: String,
+    pub day: String,
+    pub order_type: String,
+    pub mod_rank: Option<i64>,
+    pub volume: i64,
+    pub min_price: f64,
+    pub max_price: f64,
+    pub avg_price: f64,
+    pub median: f64,
+}
+
+impl<'a> PriceSnapshotModule<'a> {
+    pub async fn initialize(&self) -> Result<bool, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Table::create()
+            .table(PriceSnapshot::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(PriceSnapshot::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(PriceSnapshot::Url).string().not_null())
+            .col(ColumnDef::new(PriceSnapshot::Day).string().not_null())
+            .col(ColumnDef::new(PriceSnapshot::OrderType).string().not_null())
+            .col(ColumnDef::new(PriceSnapshot::ModRank).integer())
+            .col(ColumnDef::new(PriceSnapshot::Volume).integer().not_null())
+            .col(ColumnDef::new(PriceSnapshot::MinPrice).float().not_null())
+            .col(ColumnDef::new(PriceSnapshot::MaxPrice).float().not_null())
+            .col(ColumnDef::new(PriceSnapshot::AvgPrice).float().not_null())
+            .col(ColumnDef::new(PriceSnapshot::Median).float().not_null())
+            .build(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        let sql = Table::create()
+            .table(PriceBackfillDay::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(PriceBackfillDay::Day)
+                    .string()
+                    .not_null()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(PriceBackfillDay::Items).string().not_null())
+            .col(
+                ColumnDef::new(PriceBackfillDay::Created)
+                    .date_time()
+                    .not_null(),
+            )
+            .build(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(true)
+    }
+
+    pub async fn get_by_url(&self, url: &str) -> Result<Vec<PriceSnapshotStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([
+                PriceSnapshot::Id,
+                PriceSnapshot::Url,
+                PriceSnapshot::Day,
+                PriceSnapshot::OrderType,
+                PriceSnapshot::ModRank,
+                PriceSnapshot::Volume,
+                PriceSnapshot::MinPrice,
+                PriceSnapshot::MaxPrice,
+                PriceSnapshot::AvgPrice,
+                PriceSnapshot::Median,
+            ])
+            .from(PriceSnapshot::Table)
+            .and_where(Expr::col(PriceSnapshot::Url).eq(url))
+            .order_by(PriceSnapshot::Day, Order::Asc)
+            .to_string(SqliteQueryBuilder);
+        sqlx::query_as::<_, PriceSnapshotStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))
+    }
+
+    // Closed statistics of every item for the days from and to, both included
+    pub async fn get_closed_between(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Result<Vec<PriceSnapshotStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([
+                PriceSnapshot::Id,
+                PriceSnapshot::Url,
+                PriceSnapshot::Day,
+                PriceSnapshot::OrderType,
+                PriceSnapshot::ModRank,
+                PriceSnapshot::Volume,
+                PriceSnapshot::MinPrice,
+                PriceSnapshot::MaxPrice,
+                PriceSnapshot::AvgPrice,
+                PriceSnapshot::Median,
+            ])
+            .from(PriceSnapshot::Table)
+            .and_where(Expr::col(PriceSnapshot::OrderType).eq("closed"))
+            .and_where(Expr::col(PriceSnapshot::Day).gte(from))
+            .and_where(Expr::col(PriceSnapshot::Day).lte(to))
+            .order_by(PriceSnapshot::Day, Order::Asc)
+            .to_string(SqliteQueryBuilder);
+        sqlx::query_as::<_, PriceSnapshotStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))
+    }
+
+    // Items that were already looked for in the day's history
+    pub async fn get_backfilled_items(&self, day: &str) -> Result<HashSet<String>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .column(PriceBackfillDay::Items)
+            .from(PriceBackfillDay::Table)
+            .and_where(Expr::col(PriceBackfillDay::Day).eq(day))
+            .to_string(SqliteQueryBuilder);
+        let items = sqlx::query_scalar::<_, String>(&sql)
+            .fetch_optional(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(items
+            .map(|items| {
+                items
+                    .split(',')
+                    .filter(|item| !item.is_empty())
+                    .map(|item| item.to_string())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    // Adds the day's snapshots that are not stored yet and marks the items as looked for
+    pub async fn add_day(
+        &self,
+        day: &str,
+        items: &HashSet<String>,
+        snapshots: Vec<PriceSnapshotStruct>,
+    ) -> Result<usize, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([
+                PriceSnapshot::Id,
+                PriceSnapshot::Url,
+                PriceSnapshot::Day,
+                PriceSnapshot::OrderType,
+                PriceSnapshot::ModRank,
+                PriceSnapshot::Volume,
+                PriceSnapshot::MinPrice,
+                PriceSnapshot::MaxPrice,
+                PriceSnapshot::AvgPrice,
+                PriceSnapshot::Median,
+            ])
+            .from(PriceSnapshot::Table)
+            .and_where(Expr::col(PriceSnapshot::Day).eq(day))
+            .to_string(SqliteQueryBuilder);
+        let existing: HashSet<(String, String, Option<i64>)> =
+            sqlx::query_as::<_, PriceSnapshotStruct>(&sql)
+                .fetch_all(&connection)
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?
+                .into_iter()
+                .map(|s| (s.url, s.order_type, s.mod_rank))
+                .collect();
+        let snapshots: Vec<PriceSnapshotStruct> = snapshots
+            .into_iter()
+            .filter(|s| !existing.contains(&(s.url.clone(), s.order_type.clone(), s.mod_rank)))
+            .collect();
+
+        if !snapshots.is_empty() {
+            let mut insert = InsertStatement::default();
+            insert.into_table(PriceSnapshot::Table).columns([
+                PriceSnapshot::Url,
+                PriceSnapshot::Day,
+                PriceSnapshot::OrderType,
+                PriceSnapshot::ModRank,
+                PriceSnapshot::Volume,
+                PriceSnapshot::MinPrice,
+                PriceSnapshot::MaxPrice,
+                PriceSnapshot::AvgPrice,
+                PriceSnapshot::Median,
+            ]);
+            for snapshot in snapshots.iter() {
+                insert.values_panic([
+                    snapshot.url.clone().into(),
+                    snapshot.day.clone().into(),
+                    snapshot.order_type.clone().into(),
+                    snapshot.mod_rank.into(),
+                    snapshot.volume.into(),
+                    snapshot.min_price.into(),
+                    snapshot.max_price.into(),
+                    snapshot.avg_price.into(),
+                    snapshot.median.into(),
+                ]);
+            }
+            sqlx::query(&insert.to_string(SqliteQueryBuilder))
+                .execute(&connection)
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        }
+
+        let mut backfilled = self.get_backfilled_items(day).await?;
+        backfilled.extend(items.iter().cloned());
+        let mut backfilled: Vec<String> = backfilled.into_iter().collect();
+        backfilled.sort();
+        let sql = Query::delete()
+            .from_table(PriceBackfillDay::Table)
+            .and_where(Expr::col(PriceBackfillDay::Day).eq(day))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        let sql = InsertStatement::default()
+            .into_table(PriceBackfillDay::Table)
+            .columns([
+                PriceBackfillDay::Day,
+                PriceBackfillDay::Items,
+                PriceBackfillDay::Created,
+            ])
+            .values_panic([
+                day.into(),
+                backfilled.join(",").into(),
+                helper::get_utc_timestamp().into(),
+            ])
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(snapshots.len())
+    }
+}
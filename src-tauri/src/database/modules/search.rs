@@ -0,0 +1,143 @@
+use crate::{database::client::DBClient, error::AppError};
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+pub struct SearchModule<'a> {
+    pub client: &'a DBClient,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct SearchResultStruct {
+    pub source: String,
+    pub source_id: i64,
+    pub snippet: String,
+    pub created: String,
+}
+
+// The text that is indexed for each source table
+const TRANSACTION_CONTENT: &str = "new.name || ' ' || new.url || ' ' || new.item_type || ' ' || new.transaction_type || ' ' || new.tags || ' ' || new.price || ' ' || COALESCE(new.properties, '')";
+const AUDIT_LOG_CONTENT: &str = "new.action || ' ' || new.component || ' ' || new.message || ' ' || COALESCE(new.properties, '')";
+// Bump when the indexed text or the triggers change, the index is then rebuilt on the next start
+const SEARCH_INDEX_VERSION: i64 = 1;
+const SEARCH_TRIGGERS: [&str; 5] = [
+    "history_search_transaction_insert",
+    "history_search_transaction_update",
+    "history_search_transaction_delete",
+    "history_search_audit_log_insert",
+    "history_search_audit_log_delete",
+];
+
+impl<'a> SearchModule<'a> {
+    pub async fn initialize(&self) -> Result<bool, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let map_err = |e: sqlx::Error| AppError::new("Database", eyre!(e.to_string()));
+        let exists: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'history_search'",
+        )
+        .fetch_one(&connection)
+        .await
+        .map_err(map_err)?;
+        sqlx::query("CREATE TABLE IF NOT EXISTS history_search_version (version INTEGER NOT NULL)")
+            .execute(&connection)
+            .await
+            .map_err(map_err)?;
+        let version: Option<i64> =
+            sqlx::query_scalar("SELECT MAX(version) FROM history_search_version")
+                .fetch_one(&connection)
+                .await
+                .map_err(map_err)?;
+        let outdated = exists == 0 || version != Some(SEARCH_INDEX_VERSION);
+
+        // Triggers of an older version index other text
+        if outdated {
+            for trigger in SEARCH_TRIGGERS {
+                sqlx::query(&format!("DROP TRIGGER IF EXISTS {}", trigger))
+                    .execute(&connection)
+                    .await
+                    .map_err(map_err)?;
+            }
+        }
+
+        let transaction_content = TRANSACTION_CONTENT;
+        let audit_log_content = AUDIT_LOG_CONTENT;
+        let statements = vec![
+            "CREATE VIRTUAL TABLE IF NOT EXISTS history_search USING fts5(source UNINDEXED, source_id UNINDEXED, content, created UNINDEXED)".to_string(),
+            // Keep the index in sync with the transaction table
+            format!("CREATE TRIGGER IF NOT EXISTS history_search_transaction_insert AFTER INSERT ON \"transaction\" BEGIN INSERT INTO history_search(source, source_id, content, created) VALUES ('transaction', new.id, {transaction_content}, new.created); END"),
+            format!("CREATE TRIGGER IF NOT EXISTS history_search_transaction_update AFTER UPDATE ON \"transaction\" BEGIN DELETE FROM history_search WHERE source = 'transaction' AND source_id = old.id; INSERT INTO history_search(source, source_id, content, created) VALUES ('transaction', new.id, {transaction_content}, new.created); END"),
+            "CREATE TRIGGER IF NOT EXISTS history_search_transaction_delete AFTER DELETE ON \"transaction\" BEGIN DELETE FROM history_search WHERE source = 'transaction' AND source_id = old.id; END".to_string(),
+            // Keep the index in sync with the audit log table
+            format!("CREATE TRIGGER IF NOT EXISTS history_search_audit_log_insert AFTER INSERT ON \"audit_log\" BEGIN INSERT INTO history_search(source, source_id, content, created) VALUES ('audit_log', new.id, {audit_log_content}, new.created); END"),
+            "CREATE TRIGGER IF NOT EXISTS history_search_audit_log_delete AFTER DELETE ON \"audit_log\" BEGIN DELETE FROM history_search WHERE source = 'audit_log' AND source_id = old.id; END".to_string(),
+        ];
+        for sql in statements {
+            sqlx::query(&sql)
+                .execute(&connection)
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        }
+        if !outdated {
+            return Ok(true);
+        }
+        self.rebuild().await?;
+        sqlx::query("DELETE FROM history_search_version")
+            .execute(&connection)
+            .await
+            .map_err(map_err)?;
+        sqlx::query("INSERT INTO history_search_version (version) VALUES (?)")
+            .bind(SEARCH_INDEX_VERSION)
+            .execute(&connection)
+            .await
+            .map_err(map_err)?;
+        Ok(true)
+    }
+
+    // Rebuild the whole index, used when the index is new or outdated so older rows are searchable
+    pub async fn rebuild(&self) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let statements = vec![
+            "DELETE FROM history_search".to_string(),
+            format!(
+                "INSERT INTO history_search(source, source_id, content, created) SELECT 'transaction', new.id, {}, new.created FROM \"transaction\" AS new",
+                TRANSACTION_CONTENT
+            ),
+            format!(
+                "INSERT INTO history_search(source, source_id, content, created) SELECT 'audit_log', new.id, {}, new.created FROM \"audit_log\" AS new",
+                AUDIT_LOG_CONTENT
+            ),
+        ];
+        for sql in statements {
+            sqlx::query(&sql)
+                .execute(&connection)
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        }
+        Ok(())
+    }
+
+    pub async fn search(
+        &self,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<SearchResultStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        // Quote every word so user input can not break the FTS syntax, and match on prefixes
+        let match_query = query
+            .split_whitespace()
+            .map(|word| format!("\"{}\"*", word.replace("\"", "")))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if match_query.is_empty() {
+            return Ok(vec![]);
+        }
+        let rows = sqlx::query_as::<_, SearchResultStruct>(
+            "SELECT source, CAST(source_id AS INTEGER) AS source_id, snippet(history_search, 2, '[', ']', '...', 12) AS snippet, created FROM history_search WHERE history_search MATCH ? ORDER BY rank LIMIT ?",
+        )
+        .bind(match_query)
+        .bind(limit)
+        .fetch_all(&connection)
+        .await
+        .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{database::client::DBClient, error::AppError, helper};
+use eyre::eyre;
+use sea_query::{ColumnDef, Expr, Iden, InsertStatement, Order, Query, SqliteQueryBuilder, Table};
+use serde::{Deserialize, Serialize};
+
+pub struct ShadowDecisionModule<'a> {
+    pub client: &'a DBClient,
+}
+#[derive(Iden)]
+pub enum ShadowDecision {
+    Table,
+    Id,
+    Url,
+    OrderType,
+    Strategy,
+    CurrentPrice,
+    CandidatePrice,
+    Created,
+}
+
+// A price where the shadow strategy disagreed with the live one
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct ShadowDecisionStruct {
+    pub id: i64,
+    pub url: String,
+    pub order_type: String,
+    pub strategy: String,
+    pub current_price: i64,
+    pub candidate_price: i64,
+    pub created: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShadowSummaryStruct {
+    pub url: String,
+    pub order_type: String,
+    pub divergences: i64,
+    // Candidate minus current price, averaged over the divergences
+    pub avg_difference: f64,
+    pub last_seen: String,
+}
+
+impl<'a> ShadowDecisionModule<'a> {
+    pub async fn initialize(&self) -> Result<bool, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Table::create()
+            .table(ShadowDecision::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(ShadowDecision::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(ShadowDecision::Url).string().not_null())
+            .col(
+                ColumnDef::new(ShadowDecision::OrderType)
+                    .string()
+                    .not_null(),
+            )
+            .col(ColumnDef::new(ShadowDecision::Strategy).string().not_null())
+            .col(
+                ColumnDef::new(ShadowDecision::CurrentPrice)
+                    .integer()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(ShadowDecision::CandidatePrice)
+                    .integer()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(ShadowDecision::Created)
+                    .date_time()
+                    .not_null(),
+            )
+            .build(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(true)
+    }
+
+    // Newest first, only the given strategy when set
+    pub async fn get_items(
+        &self,
+        strategy: Option<String>,
+    ) -> Result<Vec<ShadowDecisionStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut query = Query::select();
+        query
+            .columns([
+                ShadowDecision::Id,
+                ShadowDecision::Url,
+                ShadowDecision::OrderType,
+                ShadowDecision::Strategy,
+                ShadowDecision::CurrentPrice,
+                ShadowDecision::CandidatePrice,
+                ShadowDecision::Created,
+            ])
+            .from(ShadowDecision::Table)
+            .order_by(ShadowDecision::Id, Order::Desc);
+        if let Some(strategy) = strategy {
+            query.and_where(Expr::col(ShadowDecision::Strategy).eq(strategy));
+        }
+        sqlx::query_as::<_, ShadowDecisionStruct>(&query.to_string(SqliteQueryBuilder))
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))
+    }
+
+    // Divergences per item and side, the items that diverge most often first
+    pub async fn get_summary(
+        &self,
+        strategy: Option<String>,
+    ) -> Result<Vec<ShadowSummaryStruct>, AppError> {
+        let mut summary: Vec<ShadowSummaryStruct> = vec![];
+        for decision in self.get_items(strategy).await? {
+            let difference = (decision.candidate_price - decision.current_price) as f64;
+            match summary
+                .iter_mut()
+                .find(|s| s.url == decision.url && s.order_type == decision.order_type)
+            {
+                Some(entry) => {
+                    entry.avg_difference = (entry.avg_difference * entry.divergences as f64
+                        + difference)
+                        / (entry.divergences + 1) as f64;
+                    entry.divergences += 1;
+                }
+                None => summary.push(ShadowSummaryStruct {
+                    url: decision.url,
+                    order_type: decision.order_type,
+                    divergences: 1,
+                    avg_difference: difference,
+                    last_seen: decision.created,
+                }),
+            }
+        }
+        summary.sort_by(|a, b| b.divergences.cmp(&a.divergences));
+        Ok(summary)
+    }
+
+    pub async fn create(
+        &self,
+        url: &str,
+        order_type: &str,
+        strategy: &str,
+        current_price: i64,
+        candidate_price: i64,
+    ) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = InsertStatement::default()
+            .into_table(ShadowDecision::Table)
+            .columns([
+                ShadowDecision::Url,
+                ShadowDecision::OrderType,
+                ShadowDecision::Strategy,
+                ShadowDecision::CurrentPrice,
+                ShadowDecision::CandidatePrice,
+                ShadowDecision::Created,
+            ])
+            .values_panic([
+                url.into(),
+                order_type.into(),
+                strategy.into(),
+                current_price.into(),
+                candidate_price.into(),
+                helper::get_utc_timestamp().into(),
+            ])
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(())
+    }
+
+    // Started over when the candidate changes
+    pub async fn clear(&self) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::delete()
+            .from_table(ShadowDecision::Table)
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(())
+    }
+}
@@ -1,11 +1,14 @@
 use crate::{
     auth::AuthState,
-    database::client::DBClient,
-    enums::LogLevel,
+    database::{
+        client::DBClient,
+        modules::{tag::ENTITY_STOCK_ITEM, transaction::TransactionStruct, undo::UndoChange},
+    },
+    enums::{CostBasisMethod, ErrorKind, LogLevel},
     error::AppError,
     helper,
     logger::{self},
-    structs::RivenAttribute,
+    structs::{Item, RivenAttribute},
 };
 use eyre::eyre;
 use polars::{
@@ -34,9 +37,18 @@ pub enum StockItem {
     Owned,
     Hidden,
     Status,
+    ListedAt,
+    Bulk,
+    ResupplyAt,
+    Thumb,
+    WikiUrl,
+    Reserved,
     Created,
 }
 
+// Thumbnails from the item cache and Warframe Market are relative to this
+const WFM_ASSETS_URL: &str = "https://warframe.market/static/assets/";
+
 #[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
 #[allow(dead_code)]
 pub struct StockItemStruct {
@@ -53,7 +65,152 @@ pub struct StockItemStruct {
     pub owned: i32,
     pub hidden: bool,
     pub status: String,
+    pub listed_at: Option<String>,
+    pub bulk: Option<sqlx::types::Json<BulkPricing>>,
+    // Expected date of the next supply wave, e.g. the return of an event
+    pub resupply_at: Option<String>,
+    // Full image URL and wiki link, filled in by the enrichment job
+    pub thumb: Option<String>,
+    pub wiki_url: Option<String>,
+    // Copies kept for personal use, never listed or sold through the trade log
+    #[serde(default)]
+    pub reserved: i32,
     pub created: String,
+    // Expected days until the listing sells, estimated by the live scraper and not stored
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub time_to_sell: Option<f64>,
+}
+
+// Price change of a stock item after its cost basis was rebuilt from the transactions
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CostBasisDeltaStruct {
+    pub stock_id: i64,
+    pub url: String,
+    pub name: String,
+    pub rank: i32,
+    pub old_price: f64,
+    pub new_price: f64,
+    pub delta: f64,
+    // Set when the item was left as it is
+    pub skipped: Option<String>,
+}
+
+// Stock whose value is expected to drop with an upcoming supply wave
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ResupplyRiskStruct {
+    pub stock_id: i64,
+    pub url: String,
+    pub name: String,
+    pub resupply_at: String,
+    pub days_left: f64,
+    pub owned: i32,
+    // Listed price, or the bought price when not listed, times the owned quantity
+    pub value: f64,
+}
+
+// A stock item missing from the item cache with the closest matches
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StockItemConflictStruct {
+    pub stock_item: StockItemStruct,
+    pub suggestions: Vec<Item>,
+}
+
+// Misspelled names within this many edits of an item are still matched on import
+const IMPORT_MAX_DISTANCE: usize = 3;
+
+// A row of a stock import, the CSV columns use the same names
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct StockImportRow {
+    // Item name, url name or id
+    pub name: String,
+    pub quantity: Option<i32>,
+    // Bought price per unit, 0 or missing adds the item without a cost
+    pub price: Option<f64>,
+    pub rank: Option<i32>,
+    pub sub_type: Option<String>,
+    pub minimum_price: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StockImportRowStruct {
+    // Line in a CSV file with the header as line 1, position in the list for JSON
+    pub line: usize,
+    // imported or invalid
+    pub status: String,
+    pub message: Option<String>,
+    // Cache item the name was matched to
+    pub url_name: Option<String>,
+    pub stock_id: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct StockImportReportStruct {
+    pub imported: i64,
+    pub invalid: i64,
+    pub rows: Vec<StockImportRowStruct>,
+}
+
+// Filters for bulk edits of the item stock, unset fields match everything
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct StockItemFilter {
+    // User tag, see tag.rs
+    pub tag: Option<String>,
+    // Item tag from the cache, e.g. mod or prime
+    pub category: Option<String>,
+    // Compared with the listed price, or the bought price when the item is not listed
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub status: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BulkPriceTier {
+    pub min_quantity: i32,
+    // Price per unit once the bundle reaches min_quantity
+    pub price: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BulkPricing {
+    // Smallest bundle the item is sold in
+    pub min_quantity: i32,
+    pub tiers: Vec<BulkPriceTier>,
+}
+
+impl BulkPricing {
+    // Per unit price of the highest tier the quantity reaches
+    pub fn get_unit_price(&self, quantity: i32) -> Option<i32> {
+        self.tiers
+            .iter()
+            .filter(|t| t.min_quantity <= quantity)
+            .max_by_key(|t| t.min_quantity)
+            .map(|t| t.price)
+    }
+
+    pub fn get_note(&self) -> String {
+        let mut tiers = self.tiers.clone();
+        tiers.sort_by_key(|t| t.min_quantity);
+        let tiers = tiers
+            .iter()
+            .map(|t| format!("{}+ for {}p each", t.min_quantity, t.price))
+            .collect::<Vec<_>>();
+        if tiers.is_empty() {
+            return format!("Sold in bundles of {}", self.min_quantity);
+        }
+        format!(
+            "Sold in bundles of {}: {}",
+            self.min_quantity,
+            tiers.join(", ")
+        )
+    }
+}
+
+impl StockItemStruct {
+    // Copies that can be listed and sold
+    pub fn get_sellable(&self) -> i32 {
+        (self.owned - self.reserved).max(0)
+    }
 }
 
 pub struct StockItemModule<'a> {
@@ -156,6 +313,47 @@ impl<'a> StockItemModule<'a> {
             .to_string(SqliteQueryBuilder);
         helper::alter_table(connection.clone(), &table).await?;
 
+        table = Table::alter()
+            .table(StockItem::Table)
+            .add_column(ColumnDef::new(StockItem::ListedAt).date_time())
+            .to_string(SqliteQueryBuilder);
+        helper::alter_table(connection.clone(), &table).await?;
+
+        table = Table::alter()
+            .table(StockItem::Table)
+            .add_column(ColumnDef::new(StockItem::Bulk).json())
+            .to_string(SqliteQueryBuilder);
+        helper::alter_table(connection.clone(), &table).await?;
+
+        table = Table::alter()
+            .table(StockItem::Table)
+            .add_column(ColumnDef::new(StockItem::ResupplyAt).date_time())
+            .to_string(SqliteQueryBuilder);
+        helper::alter_table(connection.clone(), &table).await?;
+
+        table = Table::alter()
+            .table(StockItem::Table)
+            .add_column(ColumnDef::new(StockItem::Thumb).string())
+            .to_string(SqliteQueryBuilder);
+        helper::alter_table(connection.clone(), &table).await?;
+
+        table = Table::alter()
+            .table(StockItem::Table)
+            .add_column(ColumnDef::new(StockItem::WikiUrl).string())
+            .to_string(SqliteQueryBuilder);
+        helper::alter_table(connection.clone(), &table).await?;
+
+        table = Table::alter()
+            .table(StockItem::Table)
+            .add_column(
+                ColumnDef::new(StockItem::Reserved)
+                    .integer()
+                    .not_null()
+                    .default(Value::Int(Some(0))),
+            )
+            .to_string(SqliteQueryBuilder);
+        helper::alter_table(connection.clone(), &table).await?;
+
         Ok(true)
     }
 
@@ -177,6 +375,12 @@ impl<'a> StockItemModule<'a> {
                 StockItem::Owned,
                 StockItem::Hidden,
                 StockItem::Status,
+                StockItem::ListedAt,
+                StockItem::Bulk,
+                StockItem::ResupplyAt,
+                StockItem::Thumb,
+                StockItem::WikiUrl,
+                StockItem::Reserved,
                 StockItem::Created,
             ])
             .from(StockItem::Table)
@@ -197,6 +401,26 @@ impl<'a> StockItemModule<'a> {
         let item = items.iter().find(|t| t.url == url_name);
         Ok(item.cloned())
     }
+    // Every rank and sub type of the item, each one is kept as its own stock entry
+    pub async fn get_items_by_url_name(
+        &self,
+        url_name: &str,
+    ) -> Result<Vec<StockItemStruct>, AppError> {
+        let items = self.get_items().await?;
+        Ok(items.into_iter().filter(|t| t.url == url_name).collect())
+    }
+    pub async fn get_item_by_variant(
+        &self,
+        url_name: &str,
+        rank: i32,
+        sub_type: Option<&str>,
+    ) -> Result<Option<StockItemStruct>, AppError> {
+        let items = self.get_items_by_url_name(url_name).await?;
+        let item = items
+            .into_iter()
+            .find(|t| t.rank == rank && t.sub_type.as_deref() == sub_type);
+        Ok(item)
+    }
     pub async fn get_by_id(&self, id: i64) -> Result<Option<StockItemStruct>, AppError> {
         let stock = self.get_items().await?;
         let stock_item = stock.iter().find(|t| t.id == id);
@@ -211,7 +435,16 @@ impl<'a> StockItemModule<'a> {
         rank: i32,
         sub_type: Option<&str>,
     ) -> Result<StockItemStruct, AppError> {
-        let inventorys = self.get_item_by_url_name(url_name).await?;
+        // Relics and other items with subtypes must be stocked as one of their variants
+        let sub_type = self
+            .client
+            .cache
+            .lock()?
+            .items()
+            .validate_sub_type(url_name, sub_type)?;
+        let sub_type = sub_type.as_deref();
+        // Copies with a different rank or sub type need their own order, so they are not merged
+        let inventorys = self.get_item_by_variant(url_name, rank, sub_type).await?;
         let connection = self.client.connection.lock().unwrap().clone();
 
         if quantity <= 0 {
@@ -227,7 +460,8 @@ impl<'a> StockItemModule<'a> {
                     "Database",
                     eyre!("Item {} not found in cache", url_name),
                     LogLevel::Critical,
-                ));
+                )
+                .with_kind(ErrorKind::NotFound));
             }
         };
 
@@ -270,7 +504,14 @@ impl<'a> StockItemModule<'a> {
                     owned: quantity as i32,
                     hidden: false,
                     status: "pending".to_string(),
-                    created: chrono::Local::now().naive_local().to_string(),
+                    listed_at: None,
+                    bulk: None,
+                    resupply_at: None,
+                    thumb: None,
+                    wiki_url: None,
+                    reserved: 0,
+                    created: helper::get_utc_timestamp(),
+                    time_to_sell: None,
                 };
 
                 let sql = InsertStatement::default()
@@ -339,10 +580,12 @@ impl<'a> StockItemModule<'a> {
                 "Database",
                 eyre!("Item not found in database"),
                 LogLevel::Error,
-            ));
+            )
+            .with_kind(ErrorKind::NotFound));
         }
         let mut inventory = inventory.unwrap().clone();
         let mut values = vec![];
+        let mut price_changed = false;
 
         if owned.is_some() {
             inventory.owned = owned.unwrap();
@@ -371,6 +614,13 @@ impl<'a> StockItemModule<'a> {
             } else {
                 listed_price
             };
+            // Keep track of when the listed price last changed
+            price_changed = inventory.listed_price != listed_price;
+            if price_changed {
+                inventory.listed_at =
+                    listed_price.map(|_| helper::get_utc_timestamp());
+                values.push((StockItem::ListedAt, inventory.listed_at.clone().into()));
+            }
             inventory.listed_price = listed_price;
             values.push((StockItem::ListedPrice, listed_price.into()));
         }
@@ -394,6 +644,14 @@ impl<'a> StockItemModule<'a> {
             .execute(&connection)
             .await
             .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        if price_changed {
+            if let Some(listed_price) = inventory.listed_price {
+                self.client
+                    .stock_price_history()
+                    .add(id, listed_price as i64)
+                    .await?;
+            }
+        }
 
         self.emit(
             "CREATE_OR_UPDATE",
@@ -401,12 +659,259 @@ impl<'a> StockItemModule<'a> {
         );
         Ok(inventory.clone())
     }
+    pub async fn update_bulk(
+        &self,
+        id: i64,
+        bulk: Option<BulkPricing>,
+    ) -> Result<StockItemStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let stock_item = self.get_by_id(id).await?;
+        if stock_item.is_none() {
+            return Err(AppError::new_with_level(
+                "Database",
+                eyre!("Item not found in database"),
+                LogLevel::Error,
+            )
+            .with_kind(ErrorKind::NotFound));
+        }
+        let mut stock_item = stock_item.unwrap();
+        stock_item.bulk = bulk.clone().map(|b| sqlx::types::Json(b));
+
+        let bulk_value = bulk.map(|b| serde_json::to_value(b).unwrap());
+        let sql = Query::update()
+            .table(StockItem::Table)
+            .values([(StockItem::Bulk, bulk_value.into())])
+            .and_where(Expr::col(StockItem::Id).eq(id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql.replace("\\", ""))
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        self.emit(
+            "CREATE_OR_UPDATE",
+            serde_json::to_value(stock_item.clone()).unwrap(),
+        );
+        Ok(stock_item)
+    }
+    // Reserved copies are kept out of the sell orders, at most the owned quantity
+    pub async fn update_reserved(
+        &self,
+        id: i64,
+        reserved: i32,
+    ) -> Result<StockItemStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut stock_item = self.get_by_id(id).await?.ok_or_else(|| {
+            AppError::new_with_level(
+                "Database",
+                eyre!("Item not found in database"),
+                LogLevel::Error,
+            )
+            .with_kind(ErrorKind::NotFound)
+        })?;
+        stock_item.reserved = reserved.clamp(0, stock_item.owned.max(0));
+
+        let sql = Query::update()
+            .table(StockItem::Table)
+            .values([(StockItem::Reserved, stock_item.reserved.into())])
+            .and_where(Expr::col(StockItem::Id).eq(id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        self.emit(
+            "CREATE_OR_UPDATE",
+            serde_json::to_value(stock_item.clone()).unwrap(),
+        );
+        Ok(stock_item)
+    }
+    // resupply_at is an RFC3339 date, None clears it
+    pub async fn update_resupply(
+        &self,
+        id: i64,
+        resupply_at: Option<String>,
+    ) -> Result<StockItemStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut stock_item = self.get_by_id(id).await?.ok_or_else(|| {
+            AppError::new_with_level(
+                "Database",
+                eyre!("Item not found in database"),
+                LogLevel::Error,
+            )
+            .with_kind(ErrorKind::NotFound)
+        })?;
+        let resupply_at = match resupply_at {
+            Some(value) => match helper::parse_timestamp(&value) {
+                Some(date) => Some(date.to_rfc3339()),
+                None => {
+                    return Err(AppError::new_with_level(
+                        "Database",
+                        eyre!("Invalid resupply date {}", value),
+                        LogLevel::Warning,
+                    )
+                    .with_kind(ErrorKind::Validation))
+                }
+            },
+            None => None,
+        };
+        stock_item.resupply_at = resupply_at.clone();
+
+        let sql = Query::update()
+            .table(StockItem::Table)
+            .values([(StockItem::ResupplyAt, resupply_at.into())])
+            .and_where(Expr::col(StockItem::Id).eq(id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        self.emit(
+            "CREATE_OR_UPDATE",
+            serde_json::to_value(stock_item.clone()).unwrap(),
+        );
+        Ok(stock_item)
+    }
+
+    // Owned stock with a resupply date within the next days, soonest first
+    pub async fn update_media(
+        &self,
+        id: i64,
+        thumb: Option<String>,
+        wiki_url: Option<String>,
+    ) -> Result<StockItemStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut stock_item = self.get_by_id(id).await?.ok_or_else(|| {
+            AppError::new_with_level(
+                "Database",
+                eyre!("Item not found in database"),
+                LogLevel::Error,
+            )
+            .with_kind(ErrorKind::NotFound)
+        })?;
+        stock_item.thumb = thumb.clone();
+        stock_item.wiki_url = wiki_url.clone();
+
+        let sql = Query::update()
+            .table(StockItem::Table)
+            .values([
+                (StockItem::Thumb, thumb.into()),
+                (StockItem::WikiUrl, wiki_url.into()),
+            ])
+            .and_where(Expr::col(StockItem::Id).eq(id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        self.emit(
+            "CREATE_OR_UPDATE",
+            serde_json::to_value(stock_item.clone()).unwrap(),
+        );
+        Ok(stock_item)
+    }
+
+    // Fills in the image and wiki link of stock items missing them. The item cache is used first,
+    // Warframe Market is only asked when the cache has no wiki link, at most max_requests times a run.
+    pub async fn enrich_media(&self, max_requests: i64) -> Result<i64, AppError> {
+        let cache = self.client.cache.lock()?.clone();
+        let wfm = self.client.wfm.lock()?.clone();
+        let items: Vec<StockItemStruct> = self
+            .get_items()
+            .await?
+            .into_iter()
+            .filter(|t| t.thumb.is_none() || t.wiki_url.is_none())
+            .collect();
+        let mut requests = 0;
+        let mut enriched = 0;
+        for item in items {
+            let cached = match cache.items().find_type(&item.url)? {
+                Some(cached) => cached,
+                None => continue,
+            };
+            let mut thumb = match cached.thumb.as_str() {
+                "" => None,
+                path => Some(format!("{}{}", WFM_ASSETS_URL, path)),
+            };
+            let mut wiki_url = cached.wikia_url.clone().filter(|url| url != "");
+            if wiki_url.is_none() {
+                if requests >= max_requests {
+                    continue;
+                }
+                requests += 1;
+                match wfm.items().get_item(item.url.clone()).await {
+                    Ok(details) => {
+                        let info = details.items_in_set.iter().find(|i| i.id == item.wfm_id);
+                        if let Some(info) = info {
+                            wiki_url = info.en.as_ref().and_then(|en| en.wiki_link.clone());
+                            if thumb.is_none() {
+                                thumb = info
+                                    .thumb
+                                    .as_ref()
+                                    .map(|t| format!("{}{}", WFM_ASSETS_URL, t));
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        logger::warning_con(
+                            "Database",
+                            format!("Could not get the details of {}: {}", item.url, e.cause())
+                                .as_str(),
+                        );
+                        continue;
+                    }
+                }
+            }
+            // Stored empty when there is none, so the item is not looked up again
+            self.update_media(
+                item.id,
+                Some(thumb.unwrap_or_default()),
+                Some(wiki_url.unwrap_or_default()),
+            )
+            .await?;
+            enriched += 1;
+        }
+        Ok(enriched)
+    }
+
+    pub async fn get_resupply_risk(&self, days: i64) -> Result<Vec<ResupplyRiskStruct>, AppError> {
+        let now = chrono::Utc::now();
+        let mut risks: Vec<ResupplyRiskStruct> = self
+            .get_items()
+            .await?
+            .into_iter()
+            .filter(|t| t.owned > 0 && !t.hidden)
+            .filter_map(|t| {
+                let resupply_at = helper::parse_timestamp(t.resupply_at.as_deref()?)?;
+                let days_left = (resupply_at - now).num_seconds() as f64 / 86400.0;
+                if days_left > days as f64 {
+                    return None;
+                }
+                let price = t.listed_price.map(|p| p as f64).unwrap_or(t.price);
+                Some(ResupplyRiskStruct {
+                    stock_id: t.id,
+                    url: t.url,
+                    name: t.name,
+                    resupply_at: resupply_at.to_rfc3339(),
+                    days_left,
+                    owned: t.owned,
+                    value: price * t.owned as f64,
+                })
+            })
+            .collect();
+        risks.sort_by(|a, b| a.days_left.total_cmp(&b.days_left));
+        Ok(risks)
+    }
     pub async fn reset_listed_price(&self) -> Result<(), AppError> {
         let connection = self.client.connection.lock().unwrap().clone();
         let sql = Query::update()
             .table(StockItem::Table)
             .values([
                 (StockItem::ListedPrice, Value::Int(None)),
+                (StockItem::ListedAt, Value::String(None)),
                 (StockItem::Status, "pending".into()),
             ])
             .to_string(SqliteQueryBuilder);
@@ -434,7 +939,8 @@ impl<'a> StockItemModule<'a> {
                 "Database",
                 eyre!("Item not found in database: {}", id),
                 LogLevel::Warning,
-            ));
+            )
+            .with_kind(ErrorKind::NotFound));
         }
         let item = item.unwrap();
         self.update_by_id(
@@ -452,6 +958,77 @@ impl<'a> StockItemModule<'a> {
             .await?)
     }
 
+    // Writes the stock item back as it was, used by undo and redo
+    pub async fn restore(&self, stock_item: &StockItemStruct) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::delete()
+            .from_table(StockItem::Table)
+            .and_where(Expr::col(StockItem::Id).eq(stock_item.id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        let bulk_value = stock_item
+            .bulk
+            .clone()
+            .map(|b| serde_json::to_value(b.0).unwrap());
+        let sql = InsertStatement::default()
+            .into_table(StockItem::Table)
+            .columns([
+                StockItem::Id,
+                StockItem::WFMId,
+                StockItem::Url,
+                StockItem::Name,
+                StockItem::Tags,
+                StockItem::Rank,
+                StockItem::SubType,
+                StockItem::Price,
+                StockItem::MiniumPrice,
+                StockItem::ListedPrice,
+                StockItem::Owned,
+                StockItem::Hidden,
+                StockItem::Status,
+                StockItem::ListedAt,
+                StockItem::Bulk,
+                StockItem::ResupplyAt,
+                StockItem::Thumb,
+                StockItem::WikiUrl,
+                StockItem::Reserved,
+                StockItem::Created,
+            ])
+            .values_panic([
+                stock_item.id.into(),
+                stock_item.wfm_id.clone().into(),
+                stock_item.url.clone().into(),
+                stock_item.name.clone().replace("\'", "").into(),
+                stock_item.tags.clone().into(),
+                stock_item.rank.into(),
+                stock_item.sub_type.clone().into(),
+                stock_item.price.into(),
+                stock_item.minium_price.into(),
+                stock_item.listed_price.into(),
+                stock_item.owned.into(),
+                stock_item.hidden.into(),
+                stock_item.status.clone().into(),
+                stock_item.listed_at.clone().into(),
+                bulk_value.into(),
+                stock_item.resupply_at.clone().into(),
+                stock_item.thumb.clone().into(),
+                stock_item.wiki_url.clone().into(),
+                stock_item.reserved.into(),
+                stock_item.created.clone().into(),
+            ])
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.emit("CREATE_OR_UPDATE", json!(stock_item));
+        Ok(())
+    }
+
     pub async fn delete(&self, id: i64) -> Result<StockItemStruct, AppError> {
         let connection = self.client.connection.lock().unwrap().clone();
         let items = self.get_items().await?;
@@ -462,7 +1039,8 @@ impl<'a> StockItemModule<'a> {
                 "Database",
                 eyre!("Stock Item not found in database"),
                 LogLevel::Error,
-            ));
+            )
+            .with_kind(ErrorKind::NotFound));
         }
         let sql = Query::delete()
             .from_table(StockItem::Table)
@@ -472,6 +1050,11 @@ impl<'a> StockItemModule<'a> {
             .execute(&connection)
             .await
             .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.client.tag().remove_entity(ENTITY_STOCK_ITEM, id).await?;
+        self.client
+            .stock_price_history()
+            .delete_by_stock_item(id)
+            .await?;
         self.emit(
             "DELETE",
             serde_json::to_value(stock_item.unwrap().clone()).unwrap(),
@@ -488,7 +1071,8 @@ impl<'a> StockItemModule<'a> {
                 "Database",
                 eyre!("Item not found in database"),
                 LogLevel::Error,
-            ));
+            )
+            .with_kind(ErrorKind::NotFound));
         }
 
         let mut inventory = stock_item.unwrap().clone();
@@ -516,15 +1100,499 @@ impl<'a> StockItemModule<'a> {
 
     pub async fn get_items_names(&self) -> Result<Vec<String>, AppError> {
         let inventorys = self.get_items().await?;
+        let cache = self.client.cache.lock()?.clone();
         // Return all hidden items and where owned is under 1
+        // Items missing from the cache wait for the user to resolve the conflict
         let inventorys = inventorys
             .iter()
             .filter(|t| t.hidden == false && t.owned > 0)
+            .filter(|t| matches!(cache.resolver().by_url_name(&t.url), Ok(Some(_))))
             .collect::<Vec<_>>();
         let names = inventorys.iter().map(|t| t.url.clone()).collect::<Vec<_>>();
         Ok(names)
     }
 
+    // Rows of a .json file holding a list of rows, or of a CSV file with a header line
+    fn read_import_rows(path: &str) -> Result<Vec<(usize, Result<StockImportRow, String>)>, AppError> {
+        let not_found = |e: String| {
+            AppError::new_with_level("Database", eyre!("Could not read {}: {}", path, e), LogLevel::Warning)
+                .with_kind(ErrorKind::NotFound)
+        };
+        if path.to_lowercase().ends_with(".json") {
+            let content = std::fs::read_to_string(path).map_err(|e| not_found(e.to_string()))?;
+            let rows: Vec<serde_json::Value> = serde_json::from_str(&content).map_err(|e| {
+                AppError::new_with_level("Database", eyre!("{} is not a list of rows: {}", path, e), LogLevel::Warning)
+                    .with_kind(ErrorKind::Validation)
+            })?;
+            return Ok(rows
+                .into_iter()
+                .enumerate()
+                .map(|(index, row)| (index + 1, serde_json::from_value(row).map_err(|e| e.to_string())))
+                .collect());
+        }
+
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_path(path)
+            .map_err(|e| not_found(e.to_string()))?;
+        let headers = reader
+            .headers()
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?
+            .clone();
+        let get_index = |column: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(column));
+        let name_index = get_index("name").or_else(|| get_index("url_name")).ok_or_else(|| {
+            AppError::new_with_level("Database", eyre!("Column name not found in {}", path), LogLevel::Warning)
+                .with_kind(ErrorKind::Validation)
+        })?;
+        let quantity_index = get_index("quantity");
+        let price_index = get_index("price");
+        let rank_index = get_index("rank");
+        let sub_type_index = get_index("sub_type");
+        let minimum_index = get_index("minimum_price");
+
+        let mut rows = vec![];
+        for (index, record) in reader.records().enumerate() {
+            let line = index + 2;
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    rows.push((line, Err(e.to_string())));
+                    continue;
+                }
+            };
+            let get = |index: Option<usize>| {
+                index
+                    .and_then(|i| record.get(i))
+                    .map(|v| v.to_string())
+                    .filter(|v| !v.is_empty())
+            };
+            let parse = |index: Option<usize>, field: &str| -> Result<Option<f64>, String> {
+                get(index)
+                    .map(|v| v.replace(',', "").parse::<f64>().map_err(|_| format!("Invalid {}: {}", field, v)))
+                    .transpose()
+            };
+            let row = (|| -> Result<StockImportRow, String> {
+                Ok(StockImportRow {
+                    name: get(Some(name_index)).unwrap_or_default(),
+                    quantity: parse(quantity_index, "quantity")?.map(|v| v.round() as i32),
+                    price: parse(price_index, "price")?,
+                    rank: parse(rank_index, "rank")?.map(|v| v.round() as i32),
+                    sub_type: get(sub_type_index),
+                    minimum_price: parse(minimum_index, "minimum_price")?.map(|v| v.round() as i32),
+                })
+            })();
+            rows.push((line, row));
+        }
+        Ok(rows)
+    }
+
+    // Adds stock from a CSV or JSON file. Every row is matched against the item cache and checked
+    // on its own, nothing is written when dry_run is set so the file can be previewed.
+    // The rows that were written are one undo step, also when later rows failed.
+    pub async fn import(&self, path: &str, dry_run: bool) -> Result<StockImportReportStruct, AppError> {
+        let rows = Self::read_import_rows(path)?;
+        let cache = self.client.cache.lock()?.clone();
+        let stock_before = self.get_items().await?;
+
+        let mut report = StockImportReportStruct::default();
+        for (line, row) in rows {
+            let mut result = StockImportRowStruct {
+                line,
+                status: "invalid".to_string(),
+                message: None,
+                url_name: None,
+                stock_id: None,
+            };
+            let validated = row.and_then(|row| -> Result<(StockImportRow, Item, Option<String>), String> {
+                if row.name.trim().is_empty() {
+                    return Err("Missing item name".to_string());
+                }
+                let item = cache
+                    .resolver()
+                    .resolve_fuzzy(&row.name, IMPORT_MAX_DISTANCE)
+                    .map_err(|e| e.cause())?
+                    .ok_or_else(|| format!("Item {} not found in the cache", row.name))?;
+                if row.quantity.unwrap_or(1) < 1 {
+                    return Err(format!("Invalid quantity: {}", row.quantity.unwrap_or(0)));
+                }
+                if row.price.unwrap_or(0.0) < 0.0 {
+                    return Err(format!("Invalid price: {}", row.price.unwrap_or(0.0)));
+                }
+                let rank = row.rank.unwrap_or(0);
+                if let Some(max_rank) = item.mod_max_rank {
+                    if rank as i64 > max_rank || rank < 0 {
+                        return Err(format!("Rank {} is not between 0 and {}", rank, max_rank));
+                    }
+                }
+                let sub_type = cache
+                    .items()
+                    .validate_sub_type(&item.url_name, row.sub_type.as_deref())
+                    .map_err(|e| e.cause())?;
+                Ok((row, item, sub_type))
+            });
+            let (row, item, sub_type) = match validated {
+                Ok(values) => values,
+                Err(message) => {
+                    result.message = Some(message);
+                    report.invalid += 1;
+                    report.rows.push(result);
+                    continue;
+                }
+            };
+            result.url_name = Some(item.url_name.clone());
+
+            if !dry_run {
+                let quantity = row.quantity.unwrap_or(1);
+                let created = self
+                    .create(
+                        &item.url_name,
+                        quantity,
+                        row.price.unwrap_or(0.0) * quantity as f64,
+                        row.minimum_price,
+                        row.rank.unwrap_or(0),
+                        sub_type.as_deref(),
+                    )
+                    .await;
+                match created {
+                    Ok(stock_item) => result.stock_id = Some(stock_item.id),
+                    Err(e) => {
+                        result.message = Some(e.cause());
+                        report.invalid += 1;
+                        report.rows.push(result);
+                        continue;
+                    }
+                }
+            }
+            result.status = "imported".to_string();
+            report.imported += 1;
+            report.rows.push(result);
+        }
+        if dry_run || report.imported == 0 {
+            return Ok(report);
+        }
+
+        // Rows merged into the same item are listed once
+        let mut stock_ids: Vec<i64> = report.rows.iter().filter_map(|r| r.stock_id).collect();
+        stock_ids.sort();
+        stock_ids.dedup();
+        let mut undo_changes = vec![];
+        for id in stock_ids {
+            let after = self.get_by_id(id).await?;
+            undo_changes.push(UndoChange::stock_item(
+                id,
+                stock_before.iter().find(|t| t.id == id),
+                after.as_ref(),
+            ));
+        }
+        self.client.undo().push(
+            &format!("Import {} stock items", report.imported),
+            undo_changes,
+        )?;
+        Ok(report)
+    }
+
+    // Stock items whose url is no longer in the item cache, e.g. after an item was renamed upstream
+    pub async fn get_cache_conflicts(&self) -> Result<Vec<StockItemConflictStruct>, AppError> {
+        let cache = self.client.cache.lock()?.clone();
+        let mut conflicts = vec![];
+        for stock_item in self.get_items().await? {
+            if stock_item.status == "archived" {
+                continue;
+            }
+            if cache.resolver().by_url_name(&stock_item.url)?.is_some() {
+                continue;
+            }
+            let suggestions = cache.resolver().suggest(&stock_item.url, 5)?;
+            conflicts.push(StockItemConflictStruct {
+                stock_item,
+                suggestions,
+            });
+        }
+        Ok(conflicts)
+    }
+
+    // Points a conflicting stock item at another item, merging it into an owned entry of that variant
+    pub async fn remap(&self, id: i64, url_name: &str) -> Result<StockItemStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let stock_item = match self.get_by_id(id).await? {
+            Some(stock_item) => stock_item,
+            None => {
+                return Err(AppError::new_with_level(
+                    "Database",
+                    eyre!("Item not found in database"),
+                    LogLevel::Error,
+                )
+                .with_kind(ErrorKind::NotFound))
+            }
+        };
+        let item = match self.client.cache.lock()?.resolver().by_url_name(url_name)? {
+            Some(item) => item,
+            None => {
+                return Err(AppError::new_with_level(
+                    "Database",
+                    eyre!("Item {} not found in cache", url_name),
+                    LogLevel::Warning,
+                )
+                .with_kind(ErrorKind::NotFound))
+            }
+        };
+
+        let existing = self
+            .get_item_by_variant(
+                &item.url_name,
+                stock_item.rank,
+                stock_item.sub_type.as_deref(),
+            )
+            .await?;
+        if let Some(existing) = existing {
+            let total_owned = existing.owned + stock_item.owned;
+            let total_price =
+                existing.price * existing.owned as f64 + stock_item.price * stock_item.owned as f64;
+            let merged = self
+                .update_by_id(
+                    existing.id,
+                    Some(total_owned),
+                    Some(total_price / total_owned.max(1) as f64),
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .await?;
+            self.delete(stock_item.id).await?;
+            return Ok(merged);
+        }
+
+        let mut stock_item = stock_item;
+        stock_item.wfm_id = item.id.clone();
+        stock_item.url = item.url_name.clone();
+        stock_item.name = item.item_name.clone();
+        stock_item.tags = item.tags.clone().map(|t| t.join(",")).unwrap_or_default();
+        let sql = Query::update()
+            .table(StockItem::Table)
+            .values(vec![
+                (StockItem::WFMId, stock_item.wfm_id.clone().into()),
+                (StockItem::Url, stock_item.url.clone().into()),
+                (StockItem::Name, stock_item.name.clone().into()),
+                (StockItem::Tags, stock_item.tags.clone().into()),
+            ])
+            .and_where(Expr::col(StockItem::Id).eq(id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.emit(
+            "CREATE_OR_UPDATE",
+            serde_json::to_value(stock_item.clone()).unwrap(),
+        );
+        Ok(stock_item)
+    }
+
+    // Keeps the stock item for the records but takes it out of the live scraper
+    pub async fn archive(&self, id: i64) -> Result<StockItemStruct, AppError> {
+        self.update_by_id(
+            id,
+            None,
+            None,
+            None,
+            None,
+            Some("archived".to_string()),
+            Some(true),
+        )
+        .await
+    }
+
+    // Hides or shows every item matching the filter in one update, returns the changed items
+    pub async fn set_hidden_by_filter(
+        &self,
+        filter: StockItemFilter,
+        hidden: bool,
+    ) -> Result<Vec<StockItemStruct>, AppError> {
+        let tagged_ids = match filter.tag.as_ref() {
+            Some(tag) => Some(
+                self.client
+                    .tag()
+                    .get_entity_ids(ENTITY_STOCK_ITEM, tag)
+                    .await?,
+            ),
+            None => None,
+        };
+        let items: Vec<StockItemStruct> = self
+            .get_items()
+            .await?
+            .into_iter()
+            .filter(|t| t.hidden != hidden)
+            .filter(|t| tagged_ids.as_ref().map_or(true, |ids| ids.contains(&t.id)))
+            .filter(|t| {
+                filter
+                    .category
+                    .as_ref()
+                    .map_or(true, |category| t.tags.split(',').any(|tag| tag == category))
+            })
+            .filter(|t| filter.status.as_ref().map_or(true, |status| &t.status == status))
+            .filter(|t| {
+                let price = t.listed_price.map(|p| p as f64).unwrap_or(t.price);
+                filter.min_price.map_or(true, |min| price >= min)
+                    && filter.max_price.map_or(true, |max| price <= max)
+            })
+            .map(|mut t| {
+                t.hidden = hidden;
+                t
+            })
+            .collect();
+        if items.is_empty() {
+            return Ok(items);
+        }
+
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::update()
+            .table(StockItem::Table)
+            .values([(StockItem::Hidden, hidden.into())])
+            .and_where(Expr::col(StockItem::Id).is_in(items.iter().map(|t| t.id)))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        for item in items.iter() {
+            self.emit("CREATE_OR_UPDATE", json!(item));
+        }
+        Ok(items)
+    }
+
+    // Replays the item transactions of every stock item and corrects its price, returns the changes.
+    // Transactions have no sub type, so variants sharing a url and rank are skipped.
+    pub async fn rebuild_cost_basis(
+        &self,
+        method: CostBasisMethod,
+    ) -> Result<Vec<CostBasisDeltaStruct>, AppError> {
+        let mut transactions: Vec<TransactionStruct> = self
+            .client
+            .transaction()
+            .get_items()
+            .await?
+            .into_iter()
+            .filter(|t| t.item_type == "item")
+            .collect();
+        transactions.sort_by(|a, b| a.created.cmp(&b.created).then(a.id.cmp(&b.id)));
+        let stock = self.get_items().await?;
+
+        let mut deltas = vec![];
+        for stock_item in stock.iter() {
+            let mut delta = CostBasisDeltaStruct {
+                stock_id: stock_item.id,
+                url: stock_item.url.clone(),
+                name: stock_item.name.clone(),
+                rank: stock_item.rank,
+                old_price: stock_item.price,
+                new_price: stock_item.price,
+                delta: 0.0,
+                skipped: None,
+            };
+            let variants = stock
+                .iter()
+                .filter(|t| t.url == stock_item.url && t.rank == stock_item.rank)
+                .count();
+            if variants > 1 {
+                delta.skipped = Some("variants".to_string());
+                deltas.push(delta);
+                continue;
+            }
+            let item_transactions: Vec<&TransactionStruct> = transactions
+                .iter()
+                .filter(|t| t.url == stock_item.url && t.rank == stock_item.rank)
+                .collect();
+            let new_price = match Self::replay_cost_basis(
+                &item_transactions,
+                stock_item.owned as i64,
+                &method,
+            ) {
+                Some(price) => (price * 100.0).round() / 100.0,
+                None => {
+                    delta.skipped = Some("no_purchases".to_string());
+                    deltas.push(delta);
+                    continue;
+                }
+            };
+            delta.new_price = new_price;
+            delta.delta = new_price - stock_item.price;
+            if delta.delta.abs() >= 0.01 {
+                self.update_by_id(stock_item.id, None, Some(new_price), None, None, None, None)
+                    .await?;
+            }
+            deltas.push(delta);
+        }
+        logger::info_con(
+            "StockItem",
+            format!(
+                "Rebuilt the cost basis of {} items with {}",
+                deltas.iter().filter(|d| d.delta.abs() >= 0.01).count(),
+                method.as_str()
+            )
+            .as_str(),
+        );
+        Ok(deltas)
+    }
+
+    // Unit cost of the owned quantity, None without purchases
+    fn replay_cost_basis(
+        transactions: &Vec<&TransactionStruct>,
+        owned: i64,
+        method: &CostBasisMethod,
+    ) -> Option<f64> {
+        // Lots of (quantity, unit price), oldest first
+        let mut lots: Vec<(i64, f64)> = vec![];
+        let remove = |lots: &mut Vec<(i64, f64)>, mut quantity: i64| {
+            while quantity > 0 && !lots.is_empty() {
+                let index = if *method == CostBasisMethod::Lifo {
+                    lots.len() - 1
+                } else {
+                    0
+                };
+                let taken = lots[index].0.min(quantity);
+                lots[index].0 -= taken;
+                quantity -= taken;
+                if lots[index].0 == 0 {
+                    lots.remove(index);
+                }
+            }
+        };
+        for transaction in transactions {
+            let quantity = (transaction.quantity as i64).max(1);
+            match transaction.transaction_type.as_str() {
+                "buy" => {
+                    let unit_price = transaction.price as f64 / quantity as f64;
+                    match (method, lots.first_mut()) {
+                        // A single lot at the running average
+                        (CostBasisMethod::WeightedAverage, Some(lot)) => {
+                            let total = lot.0 as f64 * lot.1 + transaction.price as f64;
+                            lot.0 += quantity;
+                            lot.1 = total / lot.0 as f64;
+                        }
+                        _ => lots.push((quantity, unit_price)),
+                    }
+                }
+                "sell" | "gift" => remove(&mut lots, quantity),
+                _ => {}
+            }
+        }
+        // Stock removed by hand is taken out the same way as a sale
+        let remaining: i64 = lots.iter().map(|lot| lot.0).sum();
+        if remaining > owned && owned > 0 {
+            remove(&mut lots, remaining - owned);
+        }
+        let remaining: i64 = lots.iter().map(|lot| lot.0).sum();
+        if remaining == 0 {
+            return None;
+        }
+        // Stock added by hand has no transaction, it is valued like the rest
+        let total: f64 = lots.iter().map(|lot| lot.0 as f64 * lot.1).sum();
+        Some(total / remaining as f64)
+    }
+
     pub fn emit(&self, operation: &str, data: serde_json::Value) {
         helper::emit_update("StockItems", operation, Some(data));
     }
@@ -562,3 +1630,158 @@ impl<'a> StockItemModule<'a> {
         Ok(df.unwrap())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use crate::{
+        auth::AuthState, cache::client::CacheClient, database::client::DBClient,
+        settings::SettingsState, structs::Item, wfm_client::client::WFMClient,
+    };
+
+    fn mod_item(url_name: &str, name: &str) -> Item {
+        Item {
+            item_name: name.to_string(),
+            id: format!("{}_id", url_name),
+            url_name: url_name.to_string(),
+            thumb: "".to_string(),
+            wikia_url: None,
+            trade_tax: None,
+            mr_requirement: None,
+            set_items: None,
+            tags: Some(vec!["mod".to_string()]),
+            mod_max_rank: Some(10),
+            subtypes: None,
+            ducats: None,
+        }
+    }
+
+    // A database in memory with a cache holding two mods
+    async fn get_db() -> DBClient {
+        let wfm = Arc::new(Mutex::new(WFMClient::new(
+            Arc::new(Mutex::new(AuthState::default())),
+            Arc::new(Mutex::new(SettingsState::default())),
+        )));
+        let cache = CacheClient::new(Arc::clone(&wfm));
+        cache.cache_data.lock().unwrap().item.items = vec![
+            mod_item("primed_flow", "Primed Flow"),
+            mod_item("primed_continuity", "Primed Continuity"),
+        ];
+        // A single connection, every new connection would open its own empty database
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let db = DBClient {
+            log_file: "db.log".to_string(),
+            connection: Arc::new(Mutex::new(pool)),
+            cache: Arc::new(Mutex::new(cache)),
+            wfm,
+            undo_stack: Arc::new(Mutex::new(Default::default())),
+        };
+        db.stock_item().initialize().await.unwrap();
+        db.tag().initialize().await.unwrap();
+        db.stock_price_history().initialize().await.unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn create_keeps_ranks_apart_and_merges_the_same_rank() {
+        let db = get_db().await;
+        let rank_0 = db
+            .stock_item()
+            .create("primed_flow", 1, 10.0, None, 0, None)
+            .await
+            .unwrap();
+        let rank_10 = db
+            .stock_item()
+            .create("primed_flow", 1, 200.0, None, 10, None)
+            .await
+            .unwrap();
+        assert_ne!(rank_0.id, rank_10.id);
+
+        let merged = db
+            .stock_item()
+            .create("primed_flow", 1, 20.0, None, 0, None)
+            .await
+            .unwrap();
+        assert_eq!(merged.id, rank_0.id);
+        assert_eq!(merged.owned, 2);
+        assert_eq!(merged.price, 15.0);
+
+        let items = db.stock_item().get_items_by_url_name("primed_flow").await.unwrap();
+        assert_eq!(items.len(), 2);
+        let max_rank = items.iter().find(|t| t.rank == 10).unwrap();
+        assert_eq!(max_rank.owned, 1);
+        assert_eq!(max_rank.price, 200.0);
+    }
+
+    #[tokio::test]
+    async fn sell_only_takes_from_the_sold_rank() {
+        let db = get_db().await;
+        let rank_0 = db
+            .stock_item()
+            .create("primed_flow", 2, 20.0, None, 0, None)
+            .await
+            .unwrap();
+        let rank_10 = db
+            .stock_item()
+            .create("primed_flow", 1, 200.0, None, 10, None)
+            .await
+            .unwrap();
+
+        db.stock_item().sell_item(rank_10.id, 1).await.unwrap();
+        let items = db.stock_item().get_items_by_url_name("primed_flow").await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, rank_0.id);
+        assert_eq!(items[0].owned, 2);
+
+        db.stock_item().sell_item(rank_0.id, 1).await.unwrap();
+        let rank_0 = db
+            .stock_item()
+            .get_item_by_variant("primed_flow", 0, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(rank_0.owned, 1);
+    }
+
+    #[tokio::test]
+    async fn remap_merges_into_the_entry_of_the_same_rank() {
+        let db = get_db().await;
+        let target_0 = db
+            .stock_item()
+            .create("primed_continuity", 1, 10.0, None, 0, None)
+            .await
+            .unwrap();
+        let target_10 = db
+            .stock_item()
+            .create("primed_continuity", 1, 100.0, None, 10, None)
+            .await
+            .unwrap();
+        let moved = db
+            .stock_item()
+            .create("primed_flow", 1, 200.0, None, 10, None)
+            .await
+            .unwrap();
+
+        let merged = db.stock_item().remap(moved.id, "primed_continuity").await.unwrap();
+        assert_eq!(merged.id, target_10.id);
+        assert_eq!(merged.owned, 2);
+        assert_eq!(merged.price, 150.0);
+
+        let items = db
+            .stock_item()
+            .get_items_by_url_name("primed_continuity")
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 2);
+        let rank_0 = items.iter().find(|t| t.id == target_0.id).unwrap();
+        assert_eq!(rank_0.owned, 1);
+        assert!(db.stock_item().get_by_id(moved.id).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{
+    database::client::DBClient,
+    enums::{ErrorKind, LogLevel},
+    error::AppError,
+    helper,
+    settings::PriceHistoryRetention,
+};
+use eyre::eyre;
+use sea_query::{ColumnDef, Expr, Iden, InsertStatement, Order, Query, SqliteQueryBuilder, Table};
+use serde::{Deserialize, Serialize};
+
+// Points a series is downsampled to, enough for a chart
+const MAX_POINTS: usize = 100;
+
+pub struct StockPriceHistoryModule<'a> {
+    pub client: &'a DBClient,
+}
+#[derive(Iden)]
+pub enum StockPriceHistory {
+    Table,
+    Id,
+    StockItemId,
+    Price,
+    Created,
+}
+
+// A listed price of a stock item, recorded whenever it changes
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct StockPriceHistoryStruct {
+    pub id: i64,
+    pub stock_item_id: i64,
+    pub price: i64,
+    pub created: String,
+}
+
+// A point of the chart, the last price of its time bucket and the range it moved in
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PricePointStruct {
+    pub time: String,
+    pub price: i64,
+    pub min: i64,
+    pub max: i64,
+}
+
+impl<'a> StockPriceHistoryModule<'a> {
+    pub async fn initialize(&self) -> Result<bool, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Table::create()
+            .table(StockPriceHistory::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(StockPriceHistory::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(
+                ColumnDef::new(StockPriceHistory::StockItemId)
+                    .integer()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(StockPriceHistory::Price)
+                    .integer()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(StockPriceHistory::Created)
+                    .date_time()
+                    .not_null(),
+            )
+            .build(SqliteQueryBuilder);
+
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(true)
+    }
+
+    pub async fn get_by_stock_item(
+        &self,
+        stock_item_id: i64,
+    ) -> Result<Vec<StockPriceHistoryStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([
+                StockPriceHistory::Id,
+                StockPriceHistory::StockItemId,
+                StockPriceHistory::Price,
+                StockPriceHistory::Created,
+            ])
+            .from(StockPriceHistory::Table)
+            .and_where(Expr::col(StockPriceHistory::StockItemId).eq(stock_item_id))
+            .order_by(StockPriceHistory::Id, Order::Asc)
+            .to_string(SqliteQueryBuilder);
+
+        let rows = sqlx::query_as::<_, StockPriceHistoryStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+
+    pub async fn add(&self, stock_item_id: i64, price: i64) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = InsertStatement::default()
+            .into_table(StockPriceHistory::Table)
+            .columns([
+                StockPriceHistory::StockItemId,
+                StockPriceHistory::Price,
+                StockPriceHistory::Created,
+            ])
+            .values_panic([
+                stock_item_id.into(),
+                price.into(),
+                helper::get_utc_timestamp().into(),
+            ])
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(())
+    }
+
+    pub async fn delete_by_stock_item(&self, stock_item_id: i64) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::delete()
+            .from_table(StockPriceHistory::Table)
+            .and_where(Expr::col(StockPriceHistory::StockItemId).eq(stock_item_id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(())
+    }
+
+    // Drops the prices older than the retention and the oldest ones above the cap of each item
+    pub async fn prune(&self, retention: &PriceHistoryRetention) -> Result<u64, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut removed = 0;
+        if retention.max_days >= 0 {
+            let cutoff =
+                (chrono::Utc::now() - chrono::Duration::days(retention.max_days)).to_rfc3339();
+            let sql = Query::delete()
+                .from_table(StockPriceHistory::Table)
+                .and_where(Expr::col(StockPriceHistory::Created).lt(cutoff))
+                .to_string(SqliteQueryBuilder);
+            removed += sqlx::query(&sql)
+                .execute(&connection)
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?
+                .rows_affected();
+        }
+        if retention.max_entries >= 0 {
+            let sql = format!(
+                "DELETE FROM stock_price_history WHERE id IN (SELECT id FROM (SELECT id, ROW_NUMBER() OVER (PARTITION BY stock_item_id ORDER BY id DESC) AS position FROM stock_price_history) WHERE position > {})",
+                retention.max_entries
+            );
+            removed += sqlx::query(&sql)
+                .execute(&connection)
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?
+                .rows_affected();
+        }
+        Ok(removed)
+    }
+
+    // The prices within the range, e.g. "24h", "7d" or "all", downsampled for a chart
+    pub async fn get_series(
+        &self,
+        stock_item_id: i64,
+        range: &str,
+    ) -> Result<Vec<PricePointStruct>, AppError> {
+        let from = match Self::parse_range(range) {
+            Some(duration) => Some(chrono::Utc::now() - duration),
+            None if range == "all" => None,
+            None => {
+                return Err(AppError::new_with_level(
+                    "Database",
+                    eyre!("Invalid price history range {}", range),
+                    LogLevel::Warning,
+                )
+                .with_kind(ErrorKind::Validation))
+            }
+        };
+        let prices: Vec<(chrono::DateTime<chrono::Utc>, i64)> = self
+            .get_by_stock_item(stock_item_id)
+            .await?
+            .into_iter()
+            .filter_map(|p| Some((helper::parse_timestamp(&p.created)?, p.price)))
+            .filter(|(time, _)| match from {
+                Some(from) => *time >= from,
+                None => true,
+            })
+            .collect();
+        let (first, last) = match (prices.first(), prices.last()) {
+            (Some(first), Some(last)) => (first.0, last.0),
+            _ => return Ok(vec![]),
+        };
+
+        let span = (last - first).num_seconds().max(1);
+        let bucket_size = (span / MAX_POINTS as i64).max(1);
+        let mut points: Vec<PricePointStruct> = vec![];
+        let mut current_bucket = -1;
+        for (time, price) in prices {
+            let bucket = ((time - first).num_seconds() / bucket_size).min(MAX_POINTS as i64 - 1);
+            match points.last_mut() {
+                Some(point) if bucket == current_bucket => {
+                    point.price = price;
+                    point.min = point.min.min(price);
+                    point.max = point.max.max(price);
+                }
+                _ => {
+                    current_bucket = bucket;
+                    points.push(PricePointStruct {
+                        time: time.to_rfc3339(),
+                        price,
+                        min: price,
+                        max: price,
+                    });
+                }
+            }
+        }
+        Ok(points)
+    }
+
+    // e.g. "24h" or "30d", anything else is not a range
+    fn parse_range(range: &str) -> Option<chrono::Duration> {
+        if let Some(value) = range.strip_suffix('h') {
+            return Some(chrono::Duration::hours(value.parse().ok()?));
+        }
+        if let Some(value) = range.strip_suffix('d') {
+            return Some(chrono::Duration::days(value.parse().ok()?));
+        }
+        None
+    }
+}
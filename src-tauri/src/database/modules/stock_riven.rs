@@ -1,7 +1,7 @@
 use crate::{
     auth::AuthState,
-    database::client::DBClient,
-    enums::LogLevel,
+    database::{client::DBClient, modules::tag::ENTITY_STOCK_RIVEN},
+    enums::{ErrorKind, LogLevel},
     error::AppError,
     helper,
     logger::{self},
@@ -85,6 +85,24 @@ pub struct MinMaxStruct {
     pub max: i64,
 }
 
+// Filters for searching the riven stock, unset fields match everything.
+// Attributes are given by url_name, e.g. critical_chance.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct StockRivenQuery {
+    pub weapon_url: Option<String>,
+    pub status: Option<String>,
+    pub min_re_rolls: Option<i32>,
+    pub max_re_rolls: Option<i32>,
+    // Attributes that must be rolled as positives
+    pub has_positive: Option<Vec<String>>,
+    // Attributes that must be rolled as the negative
+    pub has_negative: Option<Vec<String>>,
+    // Attributes that must not be rolled as the negative
+    pub without_negative: Option<Vec<String>>,
+    // Attributes that must not be rolled at all
+    pub without: Option<Vec<String>>,
+}
+
 pub struct StockRivenModule<'a> {
     pub client: &'a DBClient,
 }
@@ -246,6 +264,82 @@ impl<'a> StockRivenModule<'a> {
             .unwrap();
         Ok(rows)
     }
+    // Filters in SQL, attributes are matched with json_each over the stored attribute list
+    pub async fn query(&self, query: StockRivenQuery) -> Result<Vec<StockRivenStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut select = Query::select();
+        select
+            .columns([
+                StockRiven::Id,
+                StockRiven::OrderId,
+                StockRiven::WeaponId,
+                StockRiven::WeaponUrl,
+                StockRiven::WeaponName,
+                StockRiven::WeaponType,
+                StockRiven::ModName,
+                StockRiven::Rank,
+                StockRiven::Attributes,
+                StockRiven::MatchRiven,
+                StockRiven::MasteryRank,
+                StockRiven::ReRolls,
+                StockRiven::Polarity,
+                StockRiven::Price,
+                StockRiven::MiniumPrice,
+                StockRiven::ListedPrice,
+                StockRiven::Private,
+                StockRiven::Status,
+                StockRiven::Created,
+            ])
+            .from(StockRiven::Table);
+
+        if let Some(weapon_url) = query.weapon_url {
+            select.and_where(Expr::col(StockRiven::WeaponUrl).eq(weapon_url));
+        }
+        if let Some(status) = query.status {
+            select.and_where(Expr::col(StockRiven::Status).eq(status));
+        }
+        if let Some(min_re_rolls) = query.min_re_rolls {
+            select.and_where(Expr::col(StockRiven::ReRolls).gte(min_re_rolls));
+        }
+        if let Some(max_re_rolls) = query.max_re_rolls {
+            select.and_where(Expr::col(StockRiven::ReRolls).lte(max_re_rolls));
+        }
+
+        // None matches the attribute with either sign
+        let attribute_filters = [
+            (query.has_positive, Some(true), true),
+            (query.has_negative, Some(false), true),
+            (query.without_negative, Some(false), false),
+            (query.without, None, false),
+        ];
+        for (attributes, positive, present) in attribute_filters {
+            for attribute in attributes.unwrap_or_default() {
+                let mut condition = "SELECT 1 FROM json_each(stock_riven.attributes) \
+                    WHERE json_extract(value, '$.url_name') = ?"
+                    .to_string();
+                if let Some(positive) = positive {
+                    condition.push_str(&format!(
+                        " AND json_extract(value, '$.positive') = {}",
+                        positive as i32
+                    ));
+                }
+                let condition = if present {
+                    format!("EXISTS ({})", condition)
+                } else {
+                    format!("NOT EXISTS ({})", condition)
+                };
+                select.and_where(Expr::cust_with_values(&condition, vec![attribute]));
+            }
+        }
+
+        let sql = select.to_string(SqliteQueryBuilder);
+        let rows = sqlx::query_as::<_, StockRivenStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+
     pub async fn get_by_id(&self, id: i64) -> Result<Option<StockRivenStruct>, AppError> {
         let stock = self.get_rivens().await?;
         let stock_riven = stock.iter().find(|t| t.id == id);
@@ -308,7 +402,7 @@ impl<'a> StockRivenModule<'a> {
             listed_price: None,
             private: false,
             status: "pending".to_string(),
-            created: chrono::Local::now().naive_local().to_string(),
+            created: helper::get_utc_timestamp(),
         };
 
         let sql = InsertStatement::default()
@@ -431,7 +525,8 @@ impl<'a> StockRivenModule<'a> {
                 "Database",
                 eyre!("Riven not found in database"),
                 LogLevel::Error,
-            ));
+            )
+            .with_kind(ErrorKind::NotFound));
         }
         let mut stock_riven = stock_riven.unwrap().clone();
         let mut values = vec![(StockRiven::ListedPrice, listed_price.into())];
@@ -528,7 +623,8 @@ impl<'a> StockRivenModule<'a> {
                 "Database",
                 eyre!("Stock Riven not found in database"),
                 LogLevel::Error,
-            ));
+            )
+            .with_kind(ErrorKind::NotFound));
         }
         let sql = Query::delete()
             .from_table(StockRiven::Table)
@@ -538,6 +634,7 @@ impl<'a> StockRivenModule<'a> {
             .execute(&connection)
             .await
             .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.client.tag().remove_entity(ENTITY_STOCK_RIVEN, id).await?;
         self.emit(
             "DELETE",
             serde_json::to_value(stock_item.unwrap().clone()).unwrap(),
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use crate::{database::client::DBClient, error::AppError, helper};
+use eyre::eyre;
+use sea_query::{ColumnDef, Expr, Iden, InsertStatement, Query, SqliteQueryBuilder, Table};
+use serde::{Deserialize, Serialize};
+
+pub struct StockSetModule<'a> {
+    pub client: &'a DBClient,
+}
+#[derive(Iden)]
+pub enum StockSet {
+    Table,
+    Id,
+    WFMId,
+    Url,
+    Name,
+    Parts,
+    Complete,
+    SetPrice,
+    PartsPrice,
+    Listed,
+    Updated,
+}
+
+// A part of a set and how many of it the set needs, e.g. two blades
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StockSetPart {
+    pub url: String,
+    pub quantity: i64,
+}
+
+// A set whose parts are in the stock, kept up to date by the live scraper
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct StockSetStruct {
+    pub id: i64,
+    pub wfm_id: String,
+    pub url: String,
+    pub name: String,
+    pub parts: sqlx::types::Json<Vec<StockSetPart>>,
+    // Full sets the owned parts make up
+    pub complete: i64,
+    // What the set is listed for and what the parts of one set are worth on their own
+    pub set_price: Option<i64>,
+    pub parts_price: Option<i64>,
+    // Listed as a set instead of the parts
+    pub listed: bool,
+    pub updated: String,
+}
+
+impl<'a> StockSetModule<'a> {
+    pub async fn initialize(&self) -> Result<bool, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Table::create()
+            .table(StockSet::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(StockSet::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(StockSet::WFMId).string().not_null())
+            .col(
+                ColumnDef::new(StockSet::Url)
+                    .string()
+                    .not_null()
+                    .unique_key(),
+            )
+            .col(ColumnDef::new(StockSet::Name).string().not_null())
+            .col(ColumnDef::new(StockSet::Parts).json().not_null())
+            .col(ColumnDef::new(StockSet::Complete).integer().not_null())
+            .col(ColumnDef::new(StockSet::SetPrice).integer())
+            .col(ColumnDef::new(StockSet::PartsPrice).integer())
+            .col(ColumnDef::new(StockSet::Listed).boolean().not_null())
+            .col(ColumnDef::new(StockSet::Updated).date_time().not_null())
+            .build(SqliteQueryBuilder);
+
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(true)
+    }
+
+    pub async fn get_sets(&self) -> Result<Vec<StockSetStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([
+                StockSet::Id,
+                StockSet::WFMId,
+                StockSet::Url,
+                StockSet::Name,
+                StockSet::Parts,
+                StockSet::Complete,
+                StockSet::SetPrice,
+                StockSet::PartsPrice,
+                StockSet::Listed,
+                StockSet::Updated,
+            ])
+            .from(StockSet::Table)
+            .to_string(SqliteQueryBuilder);
+
+        let rows = sqlx::query_as::<_, StockSetStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+
+    pub async fn get_by_url(&self, url: &str) -> Result<Option<StockSetStruct>, AppError> {
+        Ok(self.get_sets().await?.into_iter().find(|s| s.url == url))
+    }
+
+    // Copies of a part kept for the listed sets, they are not sold on their own
+    pub async fn get_reserved_parts(&self) -> Result<HashMap<String, i64>, AppError> {
+        let mut reserved: HashMap<String, i64> = HashMap::new();
+        for set in self.get_sets().await?.into_iter().filter(|s| s.listed) {
+            for part in set.parts.0.iter() {
+                *reserved.entry(part.url.clone()).or_default() += set.complete * part.quantity;
+            }
+        }
+        Ok(reserved)
+    }
+
+    // Inserts the set or replaces the stored one with the same url
+    pub async fn save(&self, mut set: StockSetStruct) -> Result<StockSetStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        set.updated = helper::get_utc_timestamp();
+        let parts = serde_json::to_value(&set.parts)
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        let sql = match self.get_by_url(&set.url).await? {
+            Some(stored) => {
+                set.id = stored.id;
+                Query::update()
+                    .table(StockSet::Table)
+                    .values([
+                        (StockSet::WFMId, set.wfm_id.clone().into()),
+                        (StockSet::Name, set.name.clone().into()),
+                        (StockSet::Parts, parts.into()),
+                        (StockSet::Complete, set.complete.into()),
+                        (StockSet::SetPrice, set.set_price.into()),
+                        (StockSet::PartsPrice, set.parts_price.into()),
+                        (StockSet::Listed, set.listed.into()),
+                        (StockSet::Updated, set.updated.clone().into()),
+                    ])
+                    .and_where(Expr::col(StockSet::Id).eq(set.id))
+                    .to_string(SqliteQueryBuilder)
+            }
+            None => InsertStatement::default()
+                .into_table(StockSet::Table)
+                .columns([
+                    StockSet::WFMId,
+                    StockSet::Url,
+                    StockSet::Name,
+                    StockSet::Parts,
+                    StockSet::Complete,
+                    StockSet::SetPrice,
+                    StockSet::PartsPrice,
+                    StockSet::Listed,
+                    StockSet::Updated,
+                ])
+                .values_panic([
+                    set.wfm_id.clone().into(),
+                    set.url.clone().into(),
+                    set.name.clone().into(),
+                    parts.into(),
+                    set.complete.into(),
+                    set.set_price.into(),
+                    set.parts_price.into(),
+                    set.listed.into(),
+                    set.updated.clone().into(),
+                ])
+                .to_string(SqliteQueryBuilder),
+        };
+        let row = sqlx::query(&sql.replace("\\", ""))
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        if set.id == 0 {
+            set.id = row.last_insert_rowid();
+        }
+        helper::emit_update(
+            "StockSets",
+            "CREATE_OR_UPDATE",
+            Some(serde_json::to_value(&set).unwrap()),
+        );
+        Ok(set)
+    }
+}
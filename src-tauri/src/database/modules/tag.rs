@@ -0,0 +1,370 @@
+use crate::{
+    database::client::DBClient,
+    database::modules::{
+        stock_item::StockItemStruct, stock_riven::StockRivenStruct, transaction::TransactionStruct,
+    },
+    enums::LogLevel,
+    error::AppError,
+    helper,
+    logger::{self},
+};
+use eyre::eyre;
+use sea_query::{ColumnDef, Expr, Iden, InsertStatement, Order, Query, SqliteQueryBuilder, Table};
+use serde::{Deserialize, Serialize};
+
+pub struct TagModule<'a> {
+    pub client: &'a DBClient,
+}
+#[derive(Iden)]
+pub enum Tag {
+    Table,
+    Id,
+    Name,
+    Created,
+}
+#[derive(Iden)]
+pub enum TagLink {
+    Table,
+    Id,
+    TagId,
+    EntityType,
+    EntityId,
+}
+
+// Entities that can carry user tags
+pub const ENTITY_STOCK_ITEM: &str = "stock_item";
+pub const ENTITY_STOCK_RIVEN: &str = "stock_riven";
+pub const ENTITY_TRANSACTION: &str = "transaction";
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct TagStruct {
+    pub id: i64,
+    pub name: String,
+    pub created: String,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct TagLinkStruct {
+    pub id: i64,
+    pub tag_id: i64,
+    pub entity_type: String,
+    pub entity_id: i64,
+}
+
+// Everything carrying a tag, used for filtering and exports
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TaggedEntitiesStruct {
+    pub tag: String,
+    pub stock_items: Vec<StockItemStruct>,
+    pub stock_rivens: Vec<StockRivenStruct>,
+    pub transactions: Vec<TransactionStruct>,
+}
+
+impl<'a> TagModule<'a> {
+    pub async fn initialize(&self) -> Result<bool, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Table::create()
+            .table(Tag::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(Tag::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(Tag::Name).string().not_null().unique_key())
+            .col(ColumnDef::new(Tag::Created).date_time().not_null())
+            .build(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        let sql = Table::create()
+            .table(TagLink::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(TagLink::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(TagLink::TagId).integer().not_null())
+            .col(ColumnDef::new(TagLink::EntityType).string().not_null())
+            .col(ColumnDef::new(TagLink::EntityId).integer().not_null())
+            .build(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        Ok(true)
+    }
+
+    fn normalize(name: &str) -> String {
+        name.trim().to_string()
+    }
+
+    fn validate_entity_type(entity_type: &str) -> Result<(), AppError> {
+        match entity_type {
+            ENTITY_STOCK_ITEM | ENTITY_STOCK_RIVEN | ENTITY_TRANSACTION => Ok(()),
+            _ => Err(AppError::new_with_level(
+                "Database",
+                eyre!("Unknown entity type: {}", entity_type),
+                LogLevel::Warning,
+            )),
+        }
+    }
+
+    pub async fn get_tags(&self) -> Result<Vec<TagStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([Tag::Id, Tag::Name, Tag::Created])
+            .from(Tag::Table)
+            .order_by(Tag::Name, Order::Asc)
+            .to_string(SqliteQueryBuilder);
+        let rows = sqlx::query_as::<_, TagStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+
+    pub async fn get_by_name(&self, name: &str) -> Result<Option<TagStruct>, AppError> {
+        let tags = self.get_tags().await?;
+        let name = Self::normalize(name);
+        Ok(tags.into_iter().find(|t| t.name == name))
+    }
+
+    async fn get_or_create(&self, name: &str) -> Result<TagStruct, AppError> {
+        let name = Self::normalize(name);
+        if name == "" {
+            return Err(AppError::new_with_level(
+                "Database",
+                eyre!("Tag can not be empty"),
+                LogLevel::Warning,
+            ));
+        }
+        if let Some(tag) = self.get_by_name(&name).await? {
+            return Ok(tag);
+        }
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut tag = TagStruct {
+            id: 0,
+            name,
+            created: helper::get_utc_timestamp(),
+        };
+        let sql = InsertStatement::default()
+            .into_table(Tag::Table)
+            .columns([Tag::Name, Tag::Created])
+            .values_panic([tag.name.clone().into(), tag.created.clone().into()])
+            .to_string(SqliteQueryBuilder);
+        let row = sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        tag.id = row.last_insert_rowid();
+        self.emit(
+            "CREATE_OR_UPDATE",
+            serde_json::to_value(tag.clone()).unwrap(),
+        );
+        Ok(tag)
+    }
+
+    async fn get_links(&self) -> Result<Vec<TagLinkStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([
+                TagLink::Id,
+                TagLink::TagId,
+                TagLink::EntityType,
+                TagLink::EntityId,
+            ])
+            .from(TagLink::Table)
+            .to_string(SqliteQueryBuilder);
+        let rows = sqlx::query_as::<_, TagLinkStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+
+    pub async fn add(&self, entity_type: &str, entity_id: i64, name: &str) -> Result<(), AppError> {
+        Self::validate_entity_type(entity_type)?;
+        let tag = self.get_or_create(name).await?;
+        let links = self.get_links().await?;
+        if links
+            .iter()
+            .any(|l| l.tag_id == tag.id && l.entity_type == entity_type && l.entity_id == entity_id)
+        {
+            return Ok(());
+        }
+        let connection = self.client.connection.lock().unwrap().clone();
+        logger::info_con(
+            "Database",
+            format!("Tagging {} {} with {}", entity_type, entity_id, tag.name).as_str(),
+        );
+        let sql = InsertStatement::default()
+            .into_table(TagLink::Table)
+            .columns([TagLink::TagId, TagLink::EntityType, TagLink::EntityId])
+            .values_panic([tag.id.into(), entity_type.into(), entity_id.into()])
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(())
+    }
+
+    pub async fn remove(
+        &self,
+        entity_type: &str,
+        entity_id: i64,
+        name: &str,
+    ) -> Result<(), AppError> {
+        let tag = match self.get_by_name(name).await? {
+            Some(tag) => tag,
+            None => return Ok(()),
+        };
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::delete()
+            .from_table(TagLink::Table)
+            .and_where(Expr::col(TagLink::TagId).eq(tag.id))
+            .and_where(Expr::col(TagLink::EntityType).eq(entity_type))
+            .and_where(Expr::col(TagLink::EntityId).eq(entity_id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(())
+    }
+
+    // Drops the links of a deleted entity
+    pub async fn remove_entity(&self, entity_type: &str, entity_id: i64) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::delete()
+            .from_table(TagLink::Table)
+            .and_where(Expr::col(TagLink::EntityType).eq(entity_type))
+            .and_where(Expr::col(TagLink::EntityId).eq(entity_id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(())
+    }
+
+    // Removes a tag from every entity
+    pub async fn delete(&self, name: &str) -> Result<(), AppError> {
+        let tag = match self.get_by_name(name).await? {
+            Some(tag) => tag,
+            None => return Ok(()),
+        };
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::delete()
+            .from_table(TagLink::Table)
+            .and_where(Expr::col(TagLink::TagId).eq(tag.id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        let sql = Query::delete()
+            .from_table(Tag::Table)
+            .and_where(Expr::col(Tag::Id).eq(tag.id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.emit("DELETE", serde_json::to_value(tag).unwrap());
+        Ok(())
+    }
+
+    pub async fn get_entity_tags(
+        &self,
+        entity_type: &str,
+        entity_id: i64,
+    ) -> Result<Vec<String>, AppError> {
+        let tags = self.get_tags().await?;
+        let links = self.get_links().await?;
+        Ok(links
+            .iter()
+            .filter(|l| l.entity_type == entity_type && l.entity_id == entity_id)
+            .filter_map(|l| tags.iter().find(|t| t.id == l.tag_id))
+            .map(|t| t.name.clone())
+            .collect())
+    }
+
+    pub async fn get_entity_ids(
+        &self,
+        entity_type: &str,
+        name: &str,
+    ) -> Result<Vec<i64>, AppError> {
+        let tag = match self.get_by_name(name).await? {
+            Some(tag) => tag,
+            None => return Ok(vec![]),
+        };
+        let links = self.get_links().await?;
+        Ok(links
+            .iter()
+            .filter(|l| l.tag_id == tag.id && l.entity_type == entity_type)
+            .map(|l| l.entity_id)
+            .collect())
+    }
+
+    pub async fn get_tagged(&self, name: &str) -> Result<TaggedEntitiesStruct, AppError> {
+        let stock_item_ids = self.get_entity_ids(ENTITY_STOCK_ITEM, name).await?;
+        let stock_riven_ids = self.get_entity_ids(ENTITY_STOCK_RIVEN, name).await?;
+        let transaction_ids = self.get_entity_ids(ENTITY_TRANSACTION, name).await?;
+        Ok(TaggedEntitiesStruct {
+            tag: Self::normalize(name),
+            stock_items: self
+                .client
+                .stock_item()
+                .get_items()
+                .await?
+                .into_iter()
+                .filter(|i| stock_item_ids.contains(&i.id))
+                .collect(),
+            stock_rivens: self
+                .client
+                .stock_riven()
+                .get_rivens()
+                .await?
+                .into_iter()
+                .filter(|r| stock_riven_ids.contains(&r.id))
+                .collect(),
+            transactions: self
+                .client
+                .transaction()
+                .get_items()
+                .await?
+                .into_iter()
+                .filter(|t| transaction_ids.contains(&t.id))
+                .collect(),
+        })
+    }
+
+    // Writes everything carrying the tag to a JSON file on the desktop
+    pub async fn export(&self, name: &str) -> Result<String, AppError> {
+        let tagged = self.get_tagged(name).await?;
+        let file_name: String = tagged
+            .tag
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        let path = helper::get_desktop_path().join(format!("tag_{}.json", file_name));
+        let json = serde_json::to_string_pretty(&tagged)
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        std::fs::write(&path, json).map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(path.to_str().unwrap_or_default().to_string())
+    }
+
+    pub fn emit(&self, operation: &str, data: serde_json::Value) {
+        helper::emit_update("tags", operation, Some(data));
+    }
+}
@@ -1,6 +1,8 @@
-use crate::{database::client::DBClient, error::AppError, helper, structs::RivenAttribute, logger::{self}, enums::LogLevel};
+use crate::{database::{client::DBClient, modules::tag::ENTITY_TRANSACTION}, error::AppError, helper, structs::RivenAttribute, logger::{self}, enums::{ErrorKind, LogLevel}};
 use eyre::eyre;
-use sea_query::{ColumnDef, Expr, Iden, InsertStatement, Query, SqliteQueryBuilder, Table, Value};
+use sea_query::{
+    ColumnDef, Expr, Iden, InsertStatement, Order, Query, SqliteQueryBuilder, Table, Value,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
@@ -40,6 +42,49 @@ pub struct TransactionStruct {
     pub created: String,
     pub properties: Option<sqlx::types::Json<Option<serde_json::Value>>>,
 }
+
+// CSV column names for each transaction field, the optional ones fall back to their defaults
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TransactionImportMapping {
+    // Item name, url name or id
+    pub name: String,
+    // buy, sell or gift
+    pub transaction_type: String,
+    // Price of the whole trade
+    pub price: String,
+    pub quantity: Option<String>,
+    pub rank: Option<String>,
+    // RFC3339, "YYYY-MM-DD HH:MM:SS" or "YYYY-MM-DD", the import time when missing
+    pub created: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TransactionImportRowStruct {
+    // Line in the file, the header is line 1
+    pub line: usize,
+    // imported, duplicate or invalid
+    pub status: String,
+    pub message: Option<String>,
+    pub transaction_id: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TransactionImportReportStruct {
+    pub imported: i64,
+    pub duplicates: i64,
+    pub invalid: i64,
+    pub rows: Vec<TransactionImportRowStruct>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TransactionBucketStruct {
+    pub bucket: String,
+    pub sell_count: i64,
+    pub sell_total: i64,
+    pub buy_count: i64,
+    pub buy_total: i64,
+    pub profit: i64,
+}
 impl<'a> TransactionModule<'a> {
     pub async fn initialize(&self) -> Result<bool, AppError> {
         let connection = self.client.connection.lock().unwrap().clone();
@@ -119,6 +164,39 @@ impl<'a> TransactionModule<'a> {
             .unwrap();
         Ok(rows)
     }
+    // Transactions of one item and type, oldest first
+    pub async fn get_by_url(
+        &self,
+        url: &str,
+        transaction_type: &str,
+    ) -> Result<Vec<TransactionStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([
+                Transaction::Id,
+                Transaction::TransactionType,
+                Transaction::WFMId,
+                Transaction::Url,
+                Transaction::Name,
+                Transaction::ItemType,
+                Transaction::Tags,
+                Transaction::Rank,
+                Transaction::Price,
+                Transaction::Quantity,
+                Transaction::Properties,
+                Transaction::Created,
+            ])
+            .from(Transaction::Table)
+            .and_where(Expr::col(Transaction::Url).eq(url))
+            .and_where(Expr::col(Transaction::TransactionType).eq(transaction_type))
+            .order_by(Transaction::Created, Order::Asc)
+            .to_string(SqliteQueryBuilder);
+
+        sqlx::query_as::<_, TransactionStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))
+    }
     pub async fn get_by_id(&self, id: i64) -> Result<Option<TransactionStruct>, AppError> {
         let transactions = self.get_items().await?;
         let transaction = transactions.iter().find(|t| t.id == id);
@@ -133,6 +211,30 @@ impl<'a> TransactionModule<'a> {
         price: i32,
         rank: i32,
         properties: Option<serde_json::Value>,
+    ) -> Result<TransactionStruct, AppError> {
+        self.create_at(
+            url_name,
+            item_type,
+            transaction_type,
+            quantity,
+            price,
+            rank,
+            properties,
+            chrono::Utc::now().to_rfc3339(),
+        )
+        .await
+    }
+    // Same as create with the date of the trade, used for imported history
+    pub async fn create_at(
+        &self,
+        url_name: &str,
+        item_type: &str,
+        transaction_type: &str,
+        quantity: i32,
+        price: i32,
+        rank: i32,
+        properties: Option<serde_json::Value>,
+        created: String,
     ) -> Result<TransactionStruct, AppError> {
         let connection = self.client.connection.lock().unwrap().clone();
         let mut transaction = TransactionStruct {
@@ -147,7 +249,7 @@ impl<'a> TransactionModule<'a> {
             price,
             transaction_type: transaction_type.to_string(),
             quantity,
-            created: chrono::Utc::now().to_rfc3339(),
+            created,
         };
         if item_type == "riven" {
             let item = self
@@ -245,7 +347,8 @@ impl<'a> TransactionModule<'a> {
                 "Database",
                 eyre!("Transaction not found in database"),
                 LogLevel::Error,
-            ));
+            )
+            .with_kind(ErrorKind::NotFound));
         }
         let mut transaction = transaction.unwrap().clone();
         let mut values = vec![];
@@ -287,6 +390,61 @@ impl<'a> TransactionModule<'a> {
         Ok(transaction.clone())
     }
     
+    // Writes the transaction back as it was, used by undo and redo
+    pub async fn restore(&self, transaction: &TransactionStruct) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::delete()
+            .from_table(Transaction::Table)
+            .and_where(Expr::col(Transaction::Id).eq(transaction.id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        let properties = transaction.properties.clone().and_then(|p| p.0);
+        let sql = InsertStatement::default()
+            .into_table(Transaction::Table)
+            .columns([
+                Transaction::Id,
+                Transaction::WFMId,
+                Transaction::Url,
+                Transaction::Name,
+                Transaction::ItemType,
+                Transaction::Tags,
+                Transaction::Rank,
+                Transaction::Properties,
+                Transaction::Price,
+                Transaction::TransactionType,
+                Transaction::Quantity,
+                Transaction::Created,
+            ])
+            .values_panic([
+                transaction.id.into(),
+                transaction.wfm_id.clone().into(),
+                transaction.url.clone().into(),
+                transaction.name.clone().into(),
+                transaction.item_type.clone().into(),
+                transaction.tags.clone().into(),
+                transaction.rank.into(),
+                properties.into(),
+                transaction.price.into(),
+                transaction.transaction_type.clone().into(),
+                transaction.quantity.into(),
+                transaction.created.clone().into(),
+            ])
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql.replace("\\", ""))
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.emit(
+            "CREATE_OR_UPDATE",
+            serde_json::to_value(transaction.clone()).unwrap(),
+        );
+        Ok(())
+    }
+
     pub async fn delete(&self, id: i64) -> Result<(), AppError> {
         let connection = self.client.connection.lock().unwrap().clone();
 
@@ -299,10 +457,251 @@ impl<'a> TransactionModule<'a> {
             .execute(&connection)
             .await
             .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.client.tag().remove_entity(ENTITY_TRANSACTION, id).await?;
         Ok(())
     }
 
+    // Group transactions by hour, day, week or month in the given timezone, defaults to the local timezone
+    pub async fn get_buckets(
+        &self,
+        group: &str,
+        utc_offset_minutes: Option<i32>,
+    ) -> Result<Vec<TransactionBucketStruct>, AppError> {
+        let format = match group {
+            "hour" => "%Y-%m-%d %H:00",
+            "day" => "%Y-%m-%d",
+            "week" => "%G-W%V",
+            "month" => "%Y-%m",
+            _ => {
+                return Err(AppError::new(
+                    "Database",
+                    eyre!("Unknown bucket group: {}", group),
+                ))
+            }
+        };
+        let offset = match utc_offset_minutes {
+            Some(minutes) => Some(chrono::FixedOffset::east_opt(minutes * 60).ok_or_else(|| {
+                AppError::new("Database", eyre!("Invalid utc offset: {}", minutes))
+            })?),
+            None => None,
+        };
+
+        let mut buckets: std::collections::BTreeMap<String, TransactionBucketStruct> =
+            std::collections::BTreeMap::new();
+        for transaction in self.get_items().await? {
+            let created = match helper::parse_timestamp(&transaction.created) {
+                Some(created) => created,
+                None => continue,
+            };
+            let key = match offset {
+                Some(offset) => created.with_timezone(&offset).format(format).to_string(),
+                None => created
+                    .with_timezone(&chrono::Local)
+                    .format(format)
+                    .to_string(),
+            };
+            let bucket = buckets
+                .entry(key.clone())
+                .or_insert_with(|| TransactionBucketStruct {
+                    bucket: key,
+                    ..Default::default()
+                });
+            let total = transaction.price as i64;
+            match transaction.transaction_type.as_str() {
+                "sell" => {
+                    bucket.sell_count += 1;
+                    bucket.sell_total += total;
+                }
+                "buy" => {
+                    bucket.buy_count += 1;
+                    bucket.buy_total += total;
+                }
+                // Gifts move stock but are left out of the profit
+                _ => {}
+            }
+            bucket.profit = bucket.sell_total - bucket.buy_total;
+        }
+        Ok(buckets.into_values().collect())
+    }
+
     pub fn emit(&self, operation: &str, data: serde_json::Value) {
         helper::emit_update("transactions", operation, Some(data));
     }
+
+    fn parse_import_date(value: &str) -> Option<String> {
+        use chrono::TimeZone;
+        if let Some(date) = helper::parse_timestamp(value) {
+            return Some(date.to_rfc3339());
+        }
+        let day = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+        chrono::Local
+            .from_local_datetime(&day.and_hms_opt(0, 0, 0)?)
+            .earliest()
+            .map(|date| date.with_timezone(&chrono::Utc).to_rfc3339())
+    }
+
+    // Same trade on the same day
+    fn get_import_key(
+        url: &str,
+        transaction_type: &str,
+        price: i32,
+        quantity: i32,
+        rank: i32,
+        created: &str,
+    ) -> String {
+        format!(
+            "{}|{}|{}|{}|{}|{}",
+            url,
+            transaction_type,
+            price,
+            quantity,
+            rank,
+            created.get(0..10).unwrap_or(created)
+        )
+    }
+
+    // Imports item trades from a CSV file. Every row is checked against the cache and the existing
+    // transactions, nothing is written when dry_run is set so the mapping can be previewed.
+    pub async fn import_csv(
+        &self,
+        path: &str,
+        mapping: &TransactionImportMapping,
+        dry_run: bool,
+    ) -> Result<TransactionImportReportStruct, AppError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_path(path)
+            .map_err(|e| {
+                AppError::new_with_level("Database", eyre!("Could not read {}: {}", path, e), LogLevel::Warning)
+                    .with_kind(ErrorKind::NotFound)
+            })?;
+        let headers = reader
+            .headers()
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?
+            .clone();
+        let get_index = |column: &str| -> Result<usize, AppError> {
+            headers.iter().position(|h| h.eq_ignore_ascii_case(column)).ok_or_else(|| {
+                AppError::new_with_level("Database", eyre!("Column {} not found in {}", column, path), LogLevel::Warning)
+                    .with_kind(ErrorKind::Validation)
+            })
+        };
+        let name_index = get_index(&mapping.name)?;
+        let type_index = get_index(&mapping.transaction_type)?;
+        let price_index = get_index(&mapping.price)?;
+        let quantity_index = mapping.quantity.as_deref().map(|c| get_index(c)).transpose()?;
+        let rank_index = mapping.rank.as_deref().map(|c| get_index(c)).transpose()?;
+        let created_index = mapping.created.as_deref().map(|c| get_index(c)).transpose()?;
+
+        let cache = self.client.cache.lock()?.clone();
+        let mut existing: std::collections::HashSet<String> = self
+            .get_items()
+            .await?
+            .iter()
+            .map(|t| Self::get_import_key(&t.url, &t.transaction_type, t.price, t.quantity, t.rank, &t.created))
+            .collect();
+
+        let mut report = TransactionImportReportStruct::default();
+        for (index, record) in reader.records().enumerate() {
+            let line = index + 2;
+            let mut row = TransactionImportRowStruct {
+                line,
+                status: "invalid".to_string(),
+                message: None,
+                transaction_id: None,
+            };
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    row.message = Some(e.to_string());
+                    report.invalid += 1;
+                    report.rows.push(row);
+                    continue;
+                }
+            };
+            let get = |index: usize| record.get(index).unwrap_or("").to_string();
+            let parse_int = |index: Option<usize>, default: i32, field: &str| -> Result<i32, String> {
+                match index.map(|i| get(i)).filter(|v| !v.is_empty()) {
+                    Some(value) => value
+                        .replace(',', "")
+                        .parse::<f64>()
+                        .map(|v| v.round() as i32)
+                        .map_err(|_| format!("Invalid {}: {}", field, value)),
+                    None => Ok(default),
+                }
+            };
+
+            let validated = (|| -> Result<(String, String, i32, i32, i32, String), String> {
+                let name = get(name_index);
+                let item = cache
+                    .resolver()
+                    .resolve(&name)
+                    .map_err(|e| e.cause())?
+                    .ok_or_else(|| format!("Item {} not found in the cache", name))?;
+                let transaction_type = match get(type_index).to_lowercase().as_str() {
+                    "buy" | "bought" | "purchase" => "buy",
+                    "sell" | "sold" | "sale" => "sell",
+                    "gift" => "gift",
+                    other => return Err(format!("Unknown transaction type: {}", other)),
+                }
+                .to_string();
+                let price = parse_int(Some(price_index), 0, "price")?;
+                if price < 0 {
+                    return Err(format!("Invalid price: {}", price));
+                }
+                let quantity = parse_int(quantity_index, 1, "quantity")?;
+                if quantity < 1 {
+                    return Err(format!("Invalid quantity: {}", quantity));
+                }
+                let rank = parse_int(rank_index, 0, "rank")?;
+                if let Some(max_rank) = item.mod_max_rank {
+                    if rank as i64 > max_rank || rank < 0 {
+                        return Err(format!("Rank {} is not between 0 and {}", rank, max_rank));
+                    }
+                }
+                let created = match created_index.map(|i| get(i)).filter(|v| !v.is_empty()) {
+                    Some(value) => Self::parse_import_date(&value)
+                        .ok_or_else(|| format!("Invalid date: {}", value))?,
+                    None => chrono::Utc::now().to_rfc3339(),
+                };
+                Ok((item.url_name, transaction_type, price, quantity, rank, created))
+            })();
+            let (url, transaction_type, price, quantity, rank, created) = match validated {
+                Ok(values) => values,
+                Err(message) => {
+                    row.message = Some(message);
+                    report.invalid += 1;
+                    report.rows.push(row);
+                    continue;
+                }
+            };
+
+            // Also catches rows repeated in the file
+            if !existing.insert(Self::get_import_key(&url, &transaction_type, price, quantity, rank, &created)) {
+                row.status = "duplicate".to_string();
+                report.duplicates += 1;
+                report.rows.push(row);
+                continue;
+            }
+            if !dry_run {
+                let transaction = self
+                    .create_at(
+                        &url,
+                        "item",
+                        &transaction_type,
+                        quantity,
+                        price,
+                        rank,
+                        Some(json!({ "imported_from": path })),
+                        created,
+                    )
+                    .await?;
+                row.transaction_id = Some(transaction.id);
+            }
+            row.status = "imported".to_string();
+            report.imported += 1;
+            report.rows.push(row);
+        }
+        Ok(report)
+    }
 }
@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    database::{
+        client::DBClient,
+        modules::{stock_item::StockItemStruct, transaction::TransactionStruct},
+    },
+    enums::{ErrorKind, LogLevel},
+    error::AppError,
+};
+
+pub const ENTITY_STOCK_ITEM: &str = "stock_item";
+pub const ENTITY_TRANSACTION: &str = "transaction";
+
+// Oldest commands are dropped past this depth
+const UNDO_DEPTH: usize = 50;
+
+// One row before and after the change, None when it did not exist
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UndoChange {
+    pub entity: String,
+    pub id: i64,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl UndoChange {
+    pub fn stock_item(
+        id: i64,
+        before: Option<&StockItemStruct>,
+        after: Option<&StockItemStruct>,
+    ) -> Self {
+        UndoChange {
+            entity: ENTITY_STOCK_ITEM.to_string(),
+            id,
+            before: before.map(|t| json!(t)),
+            after: after.map(|t| json!(t)),
+        }
+    }
+    pub fn transaction(
+        id: i64,
+        before: Option<&TransactionStruct>,
+        after: Option<&TransactionStruct>,
+    ) -> Self {
+        UndoChange {
+            entity: ENTITY_TRANSACTION.to_string(),
+            id,
+            before: before.map(|t| json!(t)),
+            after: after.map(|t| json!(t)),
+        }
+    }
+}
+
+// A user action, undone and redone as a whole, e.g. every item of a bulk edit
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UndoCommand {
+    pub description: String,
+    pub changes: Vec<UndoChange>,
+    pub created: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct UndoStack {
+    undo: VecDeque<UndoCommand>,
+    redo: Vec<UndoCommand>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UndoStatusStruct {
+    pub undo: Option<String>,
+    pub redo: Option<String>,
+}
+
+pub struct UndoModule<'a> {
+    pub client: &'a DBClient,
+}
+
+impl<'a> UndoModule<'a> {
+    // Only the database rows are put back, orders on Warframe Market are left to the live scraper
+    pub fn push(&self, description: &str, changes: Vec<UndoChange>) -> Result<(), AppError> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let mut stack = self.client.undo_stack.lock()?;
+        stack.undo.push_back(UndoCommand {
+            description: description.to_string(),
+            changes,
+            created: chrono::Utc::now().to_rfc3339(),
+        });
+        while stack.undo.len() > UNDO_DEPTH {
+            stack.undo.pop_front();
+        }
+        stack.redo.clear();
+        Ok(())
+    }
+
+    pub fn get_status(&self) -> Result<UndoStatusStruct, AppError> {
+        let stack = self.client.undo_stack.lock()?;
+        Ok(UndoStatusStruct {
+            undo: stack.undo.back().map(|c| c.description.clone()),
+            redo: stack.redo.last().map(|c| c.description.clone()),
+        })
+    }
+
+    fn nothing_to(action: &str) -> AppError {
+        AppError::new_with_level("Undo", eyre!("Nothing to {}", action), LogLevel::Warning)
+            .with_kind(ErrorKind::NotFound)
+    }
+
+    fn parse<T: serde::de::DeserializeOwned>(value: &Value) -> Result<T, AppError> {
+        serde_json::from_value(value.clone())
+            .map_err(|e| AppError::new("Undo", eyre!(e.to_string())))
+    }
+
+    // Puts the row in the given state, deleting it when it should not exist
+    async fn apply(&self, change: &UndoChange, state: &Option<Value>) -> Result<(), AppError> {
+        match (change.entity.as_str(), state) {
+            (ENTITY_STOCK_ITEM, Some(state)) => {
+                self.client
+                    .stock_item()
+                    .restore(&Self::parse::<StockItemStruct>(state)?)
+                    .await
+            }
+            (ENTITY_STOCK_ITEM, None) => {
+                if self
+                    .client
+                    .stock_item()
+                    .get_by_id(change.id)
+                    .await?
+                    .is_some()
+                {
+                    self.client.stock_item().delete(change.id).await?;
+                }
+                Ok(())
+            }
+            (ENTITY_TRANSACTION, Some(state)) => {
+                self.client
+                    .transaction()
+                    .restore(&Self::parse::<TransactionStruct>(state)?)
+                    .await
+            }
+            (ENTITY_TRANSACTION, None) => {
+                if let Some(transaction) = self.client.transaction().get_by_id(change.id).await? {
+                    self.client.transaction().delete(change.id).await?;
+                    self.client.transaction().emit("DELETE", json!(transaction));
+                }
+                Ok(())
+            }
+            (entity, _) => Err(AppError::new("Undo", eyre!("Unknown entity {}", entity))),
+        }
+    }
+
+    async fn audit(&self, action: &str, command: &UndoCommand) -> Result<(), AppError> {
+        self.client
+            .audit_log()
+            .create(
+                action,
+                "Undo",
+                &command.description,
+                Some(json!({ "changes": command.changes })),
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn undo_last(&self) -> Result<UndoCommand, AppError> {
+        let command = match self.client.undo_stack.lock()?.undo.pop_back() {
+            Some(command) => command,
+            None => return Err(Self::nothing_to("undo")),
+        };
+        for change in command.changes.iter().rev() {
+            if let Err(e) = self.apply(change, &change.before).await {
+                // Kept so the user can try again
+                self.client.undo_stack.lock()?.undo.push_back(command);
+                return Err(e);
+            }
+        }
+        self.client.undo_stack.lock()?.redo.push(command.clone());
+        self.audit("undo", &command).await?;
+        Ok(command)
+    }
+
+    pub async fn redo_last(&self) -> Result<UndoCommand, AppError> {
+        let command = match self.client.undo_stack.lock()?.redo.pop() {
+            Some(command) => command,
+            None => return Err(Self::nothing_to("redo")),
+        };
+        for change in command.changes.iter() {
+            if let Err(e) = self.apply(change, &change.after).await {
+                self.client.undo_stack.lock()?.redo.push(command);
+                return Err(e);
+            }
+        }
+        self.client
+            .undo_stack
+            .lock()?
+            .undo
+            .push_back(command.clone());
+        self.audit("redo", &command).await?;
+        Ok(command)
+    }
+}
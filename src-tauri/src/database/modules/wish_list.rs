@@ -0,0 +1,326 @@
+use crate::{
+    database::client::DBClient,
+    enums::{ErrorKind, LogLevel},
+    error::AppError,
+    helper,
+};
+use eyre::eyre;
+use std::collections::HashSet;
+use sea_query::{ColumnDef, Expr, Iden, InsertStatement, Query, SqliteQueryBuilder, Table};
+use serde::{Deserialize, Serialize};
+
+pub struct WishListModule<'a> {
+    pub client: &'a DBClient,
+}
+#[derive(Iden)]
+pub enum WishList {
+    Table,
+    Id,
+    WFMId,
+    Url,
+    Name,
+    Rank,
+    SubType,
+    MaxPrice,
+    Quantity,
+    Status,
+    Created,
+    OrderId,
+}
+
+// An item wanted for my own account, bought at the target price and never resold by the scraper
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct WishListStruct {
+    pub id: i64,
+    pub wfm_id: String,
+    pub url: String,
+    pub name: String,
+    pub rank: i32,
+    pub sub_type: Option<String>,
+    // The buy order is kept at this price
+    pub max_price: i64,
+    pub quantity: i64,
+    pub status: String,
+    pub created: String,
+    // The buy order placed for the wish, gone from Warframe Market once it is filled
+    #[serde(default)]
+    pub order_id: Option<String>,
+}
+
+impl<'a> WishListModule<'a> {
+    pub async fn initialize(&self) -> Result<bool, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Table::create()
+            .table(WishList::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(WishList::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(WishList::WFMId).string().not_null())
+            .col(ColumnDef::new(WishList::Url).string().not_null())
+            .col(ColumnDef::new(WishList::Name).string().not_null())
+            .col(ColumnDef::new(WishList::Rank).integer().not_null())
+            .col(ColumnDef::new(WishList::SubType).string())
+            .col(ColumnDef::new(WishList::MaxPrice).integer().not_null())
+            .col(ColumnDef::new(WishList::Quantity).integer().not_null())
+            .col(
+                ColumnDef::new(WishList::Status)
+                    .string()
+                    .not_null()
+                    .default("pending"),
+            )
+            .col(ColumnDef::new(WishList::Created).date_time().not_null())
+            .build(SqliteQueryBuilder);
+
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        let table = Table::alter()
+            .table(WishList::Table)
+            .add_column(ColumnDef::new(WishList::OrderId).string())
+            .to_string(SqliteQueryBuilder);
+        helper::alter_table(connection.clone(), &table).await?;
+        Ok(true)
+    }
+
+    pub async fn get_items(&self) -> Result<Vec<WishListStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([
+                WishList::Id,
+                WishList::WFMId,
+                WishList::Url,
+                WishList::Name,
+                WishList::Rank,
+                WishList::SubType,
+                WishList::MaxPrice,
+                WishList::Quantity,
+                WishList::Status,
+                WishList::Created,
+                WishList::OrderId,
+            ])
+            .from(WishList::Table)
+            .to_string(SqliteQueryBuilder);
+
+        let rows = sqlx::query_as::<_, WishListStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+
+    pub async fn get_by_id(&self, id: i64) -> Result<WishListStruct, AppError> {
+        match self.get_items().await?.into_iter().find(|t| t.id == id) {
+            Some(item) => Ok(item),
+            None => Err(AppError::new_with_level(
+                "Database",
+                eyre!("Wish list item not found in database"),
+                LogLevel::Error,
+            )
+            .with_kind(ErrorKind::NotFound)),
+        }
+    }
+
+    pub async fn create(
+        &self,
+        url_name: &str,
+        max_price: i64,
+        quantity: i64,
+        rank: i32,
+        sub_type: Option<&str>,
+    ) -> Result<WishListStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let cache = self.client.cache.lock()?.clone();
+        let sub_type = cache.items().validate_sub_type(url_name, sub_type)?;
+        let item = match cache.items().find_type(url_name)? {
+            Some(item) => item,
+            None => {
+                return Err(AppError::new_with_level(
+                    "Database",
+                    eyre!("Item {} not found in cache", url_name),
+                    LogLevel::Warning,
+                )
+                .with_kind(ErrorKind::NotFound))
+            }
+        };
+        if max_price <= 0 {
+            return Err(AppError::new_with_level(
+                "Database",
+                eyre!("The max price of {} has to be above 0", url_name),
+                LogLevel::Warning,
+            )
+            .with_kind(ErrorKind::Validation));
+        }
+
+        let mut wish = WishListStruct {
+            id: 0,
+            wfm_id: item.id,
+            url: url_name.to_string(),
+            name: item.item_name,
+            rank,
+            sub_type,
+            max_price,
+            quantity: quantity.max(1),
+            status: "pending".to_string(),
+            created: helper::get_utc_timestamp(),
+            order_id: None,
+        };
+        let sql = InsertStatement::default()
+            .into_table(WishList::Table)
+            .columns([
+                WishList::WFMId,
+                WishList::Url,
+                WishList::Name,
+                WishList::Rank,
+                WishList::SubType,
+                WishList::MaxPrice,
+                WishList::Quantity,
+                WishList::Status,
+                WishList::Created,
+            ])
+            .values_panic([
+                wish.wfm_id.clone().into(),
+                wish.url.clone().into(),
+                wish.name.clone().into(),
+                wish.rank.into(),
+                wish.sub_type.clone().into(),
+                wish.max_price.into(),
+                wish.quantity.into(),
+                wish.status.clone().into(),
+                wish.created.clone().into(),
+            ])
+            .to_string(SqliteQueryBuilder);
+        let row = sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        wish.id = row.last_insert_rowid();
+        self.emit("CREATE_OR_UPDATE", &wish);
+        Ok(wish)
+    }
+
+    pub async fn update(
+        &self,
+        id: i64,
+        max_price: Option<i64>,
+        quantity: Option<i64>,
+        status: Option<String>,
+    ) -> Result<WishListStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut wish = self.get_by_id(id).await?;
+        let mut values = vec![];
+        if let Some(max_price) = max_price {
+            wish.max_price = max_price;
+            values.push((WishList::MaxPrice, max_price.into()));
+        }
+        if let Some(quantity) = quantity {
+            wish.quantity = quantity.max(0);
+            values.push((WishList::Quantity, wish.quantity.into()));
+        }
+        if let Some(status) = status {
+            wish.status = status;
+            values.push((WishList::Status, wish.status.clone().into()));
+        }
+        if values.is_empty() {
+            return Ok(wish);
+        }
+        let sql = Query::update()
+            .table(WishList::Table)
+            .values(values)
+            .and_where(Expr::col(WishList::Id).eq(id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.emit("CREATE_OR_UPDATE", &wish);
+        Ok(wish)
+    }
+
+    // Buy orders placed for the wish list, deleting every order leaves them alone
+    pub async fn get_order_ids(&self) -> Result<HashSet<String>, AppError> {
+        Ok(self
+            .get_items()
+            .await?
+            .into_iter()
+            .filter_map(|t| t.order_id)
+            .collect())
+    }
+
+    pub async fn update_order_id(
+        &self,
+        id: i64,
+        order_id: Option<String>,
+    ) -> Result<WishListStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut wish = self.get_by_id(id).await?;
+        wish.order_id = order_id;
+        let sql = Query::update()
+            .table(WishList::Table)
+            .values([(WishList::OrderId, wish.order_id.clone().into())])
+            .and_where(Expr::col(WishList::Id).eq(id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.emit("CREATE_OR_UPDATE", &wish);
+        Ok(wish)
+    }
+
+    // Takes a traded purchase off the open wish for the item, None when nothing was wished for
+    pub async fn record_purchase(
+        &self,
+        url_name: &str,
+        quantity: i64,
+    ) -> Result<Option<WishListStruct>, AppError> {
+        let wish = match self
+            .get_items()
+            .await?
+            .into_iter()
+            .find(|wish| wish.url == url_name && wish.status != "fulfilled")
+        {
+            Some(wish) => wish,
+            None => return Ok(None),
+        };
+        let remaining = (wish.quantity - quantity).max(0);
+        let status = if remaining == 0 {
+            "fulfilled".to_string()
+        } else {
+            wish.status.clone()
+        };
+        let wish = self
+            .update(wish.id, None, Some(remaining), Some(status))
+            .await?;
+        Ok(Some(wish))
+    }
+
+    pub async fn delete(&self, id: i64) -> Result<WishListStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let wish = self.get_by_id(id).await?;
+        let sql = Query::delete()
+            .from_table(WishList::Table)
+            .and_where(Expr::col(WishList::Id).eq(id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.emit("DELETE", &wish);
+        Ok(wish)
+    }
+
+    pub fn emit(&self, operation: &str, wish: &WishListStruct) {
+        helper::emit_update(
+            "WishList",
+            operation,
+            Some(serde_json::to_value(wish).unwrap()),
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    auth::AuthState,
+    cache::client::CacheClient,
+    enums::{ErrorKind, OrderType},
+    error::AppError,
+    handler::MonitorHandler,
+    helper, logger,
+    price_scraper::{PriceHistory, PriceScraper},
+    settings::{DealHunterSettings, SettingsState},
+    wfm_client::client::WFMClient,
+};
+
+// Closed days the average price is taken over
+const AVERAGE_DAYS: usize = 7;
+
+// Orders are forgotten this long after their alert, a deal still listed by then alerts again
+const ALERT_EXPIRY: Duration = Duration::from_secs(24 * 60 * 60);
+
+// Orders already alerted keyed by order id, with the price they had and when the alert was sent.
+// A seller lowering the price alerts again.
+static ALERTED: Lazy<Mutex<HashMap<String, (i64, Instant)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DealStruct {
+    pub order_id: String,
+    pub url: String,
+    pub name: String,
+    pub seller: String,
+    pub price: i64,
+    pub quantity: i64,
+    pub average_price: f64,
+    pub discount_percent: f64,
+    pub whisper: String,
+}
+
+// Looks for sell orders well below the closed average, on any item and not only the stock
+#[derive(Clone)]
+pub struct DealHunter {
+    settings: Arc<Mutex<SettingsState>>,
+    price_scraper: Arc<Mutex<PriceScraper>>,
+    wfm: Arc<Mutex<WFMClient>>,
+    auth: Arc<Mutex<AuthState>>,
+    cache: Arc<Mutex<CacheClient>>,
+    mh: Arc<Mutex<MonitorHandler>>,
+}
+
+impl DealHunter {
+    pub fn new(
+        settings: Arc<Mutex<SettingsState>>,
+        price_scraper: Arc<Mutex<PriceScraper>>,
+        wfm: Arc<Mutex<WFMClient>>,
+        auth: Arc<Mutex<AuthState>>,
+        cache: Arc<Mutex<CacheClient>>,
+        mh: Arc<Mutex<MonitorHandler>>,
+    ) -> Self {
+        DealHunter {
+            settings,
+            price_scraper,
+            wfm,
+            auth,
+            cache,
+            mh,
+        }
+    }
+
+    // The configured items, otherwise the most traded items of the price history
+    fn get_items(settings: &DealHunterSettings, history: &PriceHistory) -> Vec<String> {
+        if !settings.items.is_empty() {
+            return settings
+                .items
+                .iter()
+                .take(settings.max_items.max(0) as usize)
+                .cloned()
+                .collect();
+        }
+        let mut items: Vec<(String, f64)> = history
+            .iter_closed_days()
+            .map(|(url_name, days)| {
+                let volume: f64 = days.iter().take(AVERAGE_DAYS).map(|d| d.volume).sum();
+                (url_name.clone(), volume / AVERAGE_DAYS as f64)
+            })
+            .filter(|(_, volume)| *volume >= settings.min_volume as f64)
+            .collect();
+        items.sort_by(|a, b| b.1.total_cmp(&a.1));
+        items
+            .into_iter()
+            .take(settings.max_items.max(0) as usize)
+            .map(|(url_name, _)| url_name)
+            .collect()
+    }
+
+    // One pass over the items, returns the new deals. Does nothing while disabled.
+    pub async fn scan(&self) -> Result<Vec<DealStruct>, AppError> {
+        let settings = self.settings.lock()?.deal_hunter.clone();
+        if !settings.enabled {
+            return Ok(vec![]);
+        }
+        let history = self.price_scraper.lock()?.get_price_history()?;
+        let wfm = self.wfm.lock()?.clone();
+        let cache = self.cache.lock()?.clone();
+        let my_name = self.auth.lock()?.ingame_name.clone();
+
+        ALERTED
+            .lock()?
+            .retain(|_, (_, alerted_at)| alerted_at.elapsed() < ALERT_EXPIRY);
+
+        let mut deals = vec![];
+        for url_name in Self::get_items(&settings, &history) {
+            let average_price = match history.get_average_price(&url_name, AVERAGE_DAYS) {
+                Some(price) => price,
+                None => continue,
+            };
+            let orders = match wfm.orders().get_ingame_orders_by_item(&url_name).await {
+                Ok(orders) => orders,
+                Err(e) if e.kind() == ErrorKind::BudgetExhausted => break,
+                Err(e) => {
+                    logger::warning_con(
+                        "DealHunter",
+                        format!("No orders for {}: {}", url_name, e.cause()).as_str(),
+                    );
+                    continue;
+                }
+            };
+            // Ranked mods are left out, the average is mostly made up of unranked sales
+            let lowest = orders
+                .into_iter()
+                .filter(|o| o.order_type == OrderType::Sell && o.mod_rank.unwrap_or(0) == 0)
+                .filter(|o| match &o.user {
+                    Some(user) => !user.ingame_name.eq_ignore_ascii_case(&my_name),
+                    None => false,
+                })
+                .min_by_key(|o| o.platinum);
+            let order = match lowest {
+                Some(order) => order,
+                None => continue,
+            };
+            let threshold = average_price * (1.0 - settings.discount_percent as f64 / 100.0);
+            if order.platinum as f64 > threshold {
+                continue;
+            }
+            let mut alerted = ALERTED.lock()?;
+            if let Some((price, _)) = alerted.get(&order.id) {
+                if *price <= order.platinum {
+                    continue;
+                }
+            }
+            alerted.insert(order.id.clone(), (order.platinum, Instant::now()));
+            drop(alerted);
+
+            let seller = order.user.map(|u| u.ingame_name).unwrap_or_default();
+            let name = match cache.items().find_type(&url_name)? {
+                Some(item) => item.item_name,
+                None => url_name.clone(),
+            };
+            let whisper = settings
+                .whisper_template
+                .replace("<PLAYER_NAME>", &seller)
+                .replace("<QUANTITY>", "")
+                .replace("<ITEM_NAME>", &name)
+                .replace("<PRICE>", &order.platinum.to_string());
+            let deal = DealStruct {
+                order_id: order.id,
+                url: url_name,
+                name,
+                seller,
+                price: order.platinum,
+                quantity: order.quantity,
+                average_price,
+                discount_percent: (1.0 - order.platinum as f64 / average_price) * 100.0,
+                whisper,
+            };
+
+            helper::emit_update("DealHunter", "CREATE", Some(json!(deal)));
+            self.mh.lock()?.show_notification(
+                "Deal Found",
+                format!(
+                    "{} sells {} for {}p, {:.0}% below the average of {:.0}p",
+                    deal.seller, deal.name, deal.price, deal.discount_percent, deal.average_price
+                )
+                .as_str(),
+                None,
+                None,
+            );
+            deals.push(deal);
+        }
+        Ok(deals)
+    }
+}
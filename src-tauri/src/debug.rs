@@ -8,7 +8,7 @@ use crate::{
     logger,
     settings::SettingsState,
     structs::Ordres,
-    wfm_client::client::WFMClient, cache::client::CacheClient,
+    wfm_client::{cache::ResponseCacheStats, client::WFMClient}, cache::client::CacheClient,
 };
 use eyre::eyre;
 use sea_query::{InsertStatement, SqliteQueryBuilder};
@@ -183,4 +183,14 @@ impl DebugClient {
         }
         Ok(true)
     }
+
+    // Hits and misses of the Warframe Market response cache, cleared when reset is true
+    pub fn get_wfm_cache_stats(&self, reset: bool) -> Result<ResponseCacheStats, AppError> {
+        let wfm = self.wfm.lock()?.clone();
+        let stats = wfm.get_cache_stats()?;
+        if reset {
+            wfm.clear_cache()?;
+        }
+        Ok(stats)
+    }
 }
@@ -201,6 +201,18 @@ impl LogLevel {
             LogLevel::Unknown(ref i) => i,
         }
     }
+    // Higher is more severe, unknown levels are always logged
+    pub fn severity(&self) -> u8 {
+        match *self {
+            LogLevel::Trace => 0,
+            LogLevel::Debug => 1,
+            LogLevel::Info => 2,
+            LogLevel::Warning => 3,
+            LogLevel::Error => 4,
+            LogLevel::Critical => 5,
+            LogLevel::Unknown(_) => 6,
+        }
+    }
 }
 impl Serialize for LogLevel {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -249,3 +261,461 @@ impl<'de> Deserialize<'de> for LogLevel {
         })
     }
 }
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ListingAgeAction {
+    Notify,
+    Drop,
+    Unknown(String),
+}
+impl ListingAgeAction {
+    // Create method to convert `ListingAgeAction` to a `&str`
+    pub fn as_str(&self) -> &str {
+        match *self {
+            ListingAgeAction::Notify => "notify",
+            ListingAgeAction::Drop => "drop",
+            ListingAgeAction::Unknown(ref i) => i,
+        }
+    }
+}
+impl Serialize for ListingAgeAction {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let value = match self {
+            ListingAgeAction::Notify => "notify",
+            ListingAgeAction::Drop => "drop",
+            ListingAgeAction::Unknown(i) => {
+                logger::critical_file(
+                    "ListingAgeAction",
+                    format!("Unknown ListingAgeAction: {}", i).as_str(),
+                    Some("enums.log"),
+                );
+                "unknown"
+            }
+        };
+        serializer.serialize_str(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for ListingAgeAction {
+    fn deserialize<D>(deserializer: D) -> Result<ListingAgeAction, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s: String = String::deserialize(deserializer)?;
+        Ok(match s.as_str() {
+            "notify" => ListingAgeAction::Notify,
+            "drop" => ListingAgeAction::Drop,
+            s => ListingAgeAction::Unknown(s.parse().map_err(|_| {
+                serde::de::Error::custom(format!(
+                    "invalid value for ListingAgeAction, must be an string: {}",
+                    s
+                ))
+            })?),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PartialSaleMode {
+    Auto,
+    Confirm,
+    Off,
+    Unknown(String),
+}
+impl PartialSaleMode {
+    // Create method to convert `PartialSaleMode` to a `&str`
+    pub fn as_str(&self) -> &str {
+        match *self {
+            PartialSaleMode::Auto => "auto",
+            PartialSaleMode::Confirm => "confirm",
+            PartialSaleMode::Off => "off",
+            PartialSaleMode::Unknown(ref i) => i,
+        }
+    }
+}
+impl Serialize for PartialSaleMode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let value = match self {
+            PartialSaleMode::Auto => "auto",
+            PartialSaleMode::Confirm => "confirm",
+            PartialSaleMode::Off => "off",
+            PartialSaleMode::Unknown(i) => {
+                logger::critical_file(
+                    "PartialSaleMode",
+                    format!("Unknown PartialSaleMode: {}", i).as_str(),
+                    Some("enums.log"),
+                );
+                "unknown"
+            }
+        };
+        serializer.serialize_str(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for PartialSaleMode {
+    fn deserialize<D>(deserializer: D) -> Result<PartialSaleMode, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s: String = String::deserialize(deserializer)?;
+        Ok(match s.as_str() {
+            "auto" => PartialSaleMode::Auto,
+            "confirm" => PartialSaleMode::Confirm,
+            "off" => PartialSaleMode::Off,
+            s => PartialSaleMode::Unknown(s.parse().map_err(|_| {
+                serde::de::Error::custom(format!(
+                    "invalid value for PartialSaleMode, must be an string: {}",
+                    s
+                ))
+            })?),
+        })
+    }
+}
+
+// How the cost of owned stock is worked out from the purchases
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CostBasisMethod {
+    WeightedAverage,
+    Fifo,
+    Lifo,
+    Unknown(String),
+}
+impl CostBasisMethod {
+    // Create method to convert `CostBasisMethod` to a `&str`
+    pub fn as_str(&self) -> &str {
+        match *self {
+            CostBasisMethod::WeightedAverage => "weighted_average",
+            CostBasisMethod::Fifo => "fifo",
+            CostBasisMethod::Lifo => "lifo",
+            CostBasisMethod::Unknown(ref i) => i,
+        }
+    }
+}
+impl Serialize for CostBasisMethod {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let value = match self {
+            CostBasisMethod::WeightedAverage => "weighted_average",
+            CostBasisMethod::Fifo => "fifo",
+            CostBasisMethod::Lifo => "lifo",
+            CostBasisMethod::Unknown(i) => {
+                logger::critical_file(
+                    "CostBasisMethod",
+                    format!("Unknown CostBasisMethod: {}", i).as_str(),
+                    Some("enums.log"),
+                );
+                "unknown"
+            }
+        };
+        serializer.serialize_str(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for CostBasisMethod {
+    fn deserialize<D>(deserializer: D) -> Result<CostBasisMethod, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s: String = String::deserialize(deserializer)?;
+        Ok(match s.as_str() {
+            "weighted_average" => CostBasisMethod::WeightedAverage,
+            "fifo" => CostBasisMethod::Fifo,
+            "lifo" => CostBasisMethod::Lifo,
+            s => CostBasisMethod::Unknown(s.parse().map_err(|_| {
+                serde::de::Error::custom(format!(
+                    "invalid value for CostBasisMethod, must be an string: {}",
+                    s
+                ))
+            })?),
+        })
+    }
+}
+
+// What went wrong in a command, the UI branches on this instead of the message text
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    AuthExpired,
+    NotFound,
+    Validation,
+    RateLimited,
+    Unavailable,
+    Maintenance,
+    BudgetExhausted,
+    Network,
+    Database,
+    Internal,
+    // The caller is known but not allowed to do this, e.g. an API token without the scope
+    Forbidden,
+    Unknown(String),
+}
+impl ErrorKind {
+    // Create method to convert `ErrorKind` to a `&str`
+    pub fn as_str(&self) -> &str {
+        match *self {
+            ErrorKind::AuthExpired => "auth_expired",
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::Validation => "validation",
+            ErrorKind::RateLimited => "rate_limited",
+            ErrorKind::Unavailable => "unavailable",
+            ErrorKind::Maintenance => "maintenance",
+            ErrorKind::BudgetExhausted => "budget_exhausted",
+            ErrorKind::Network => "network",
+            ErrorKind::Database => "database",
+            ErrorKind::Internal => "internal",
+            ErrorKind::Forbidden => "forbidden",
+            ErrorKind::Unknown(ref i) => i,
+        }
+    }
+}
+impl Serialize for ErrorKind {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let value = match self {
+            ErrorKind::Unknown(i) => {
+                logger::critical_file(
+                    "ErrorKind",
+                    format!("Unknown ErrorKind: {}", i).as_str(),
+                    Some("enums.log"),
+                );
+                "unknown"
+            }
+            kind => kind.as_str(),
+        };
+        serializer.serialize_str(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorKind {
+    fn deserialize<D>(deserializer: D) -> Result<ErrorKind, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s: String = String::deserialize(deserializer)?;
+        Ok(match s.as_str() {
+            "auth_expired" => ErrorKind::AuthExpired,
+            "not_found" => ErrorKind::NotFound,
+            "validation" => ErrorKind::Validation,
+            "rate_limited" => ErrorKind::RateLimited,
+            "unavailable" => ErrorKind::Unavailable,
+            "maintenance" => ErrorKind::Maintenance,
+            "budget_exhausted" => ErrorKind::BudgetExhausted,
+            "network" => ErrorKind::Network,
+            "database" => ErrorKind::Database,
+            "internal" => ErrorKind::Internal,
+            "forbidden" => ErrorKind::Forbidden,
+            s => ErrorKind::Unknown(s.parse().map_err(|_| {
+                serde::de::Error::custom(format!(
+                    "invalid value for ErrorKind, must be an string: {}",
+                    s
+                ))
+            })?),
+        })
+    }
+}
+
+// Candidate pricing run next to the live one, only logged and never acted on
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShadowStrategy {
+    Disabled,
+    // Median of the five best competitor prices
+    MedianTop,
+    // Price of the second best competitor, ignores a single outlier
+    SecondBest,
+    Unknown(String),
+}
+impl ShadowStrategy {
+    // Create method to convert `ShadowStrategy` to a `&str`
+    pub fn as_str(&self) -> &str {
+        match *self {
+            ShadowStrategy::Disabled => "disabled",
+            ShadowStrategy::MedianTop => "median_top",
+            ShadowStrategy::SecondBest => "second_best",
+            ShadowStrategy::Unknown(ref i) => i,
+        }
+    }
+}
+impl Serialize for ShadowStrategy {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let value = match self {
+            ShadowStrategy::Disabled => "disabled",
+            ShadowStrategy::MedianTop => "median_top",
+            ShadowStrategy::SecondBest => "second_best",
+            ShadowStrategy::Unknown(i) => {
+                logger::critical_file(
+                    "ShadowStrategy",
+                    format!("Unknown ShadowStrategy: {}", i).as_str(),
+                    Some("enums.log"),
+                );
+                "unknown"
+            }
+        };
+        serializer.serialize_str(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for ShadowStrategy {
+    fn deserialize<D>(deserializer: D) -> Result<ShadowStrategy, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s: String = String::deserialize(deserializer)?;
+        Ok(match s.as_str() {
+            "disabled" => ShadowStrategy::Disabled,
+            "median_top" => ShadowStrategy::MedianTop,
+            "second_best" => ShadowStrategy::SecondBest,
+            s => ShadowStrategy::Unknown(s.parse().map_err(|_| {
+                serde::de::Error::custom(format!(
+                    "invalid value for ShadowStrategy, must be an string: {}",
+                    s
+                ))
+            })?),
+        })
+    }
+}
+
+// How far a price is moved past the best competing order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PriceStepMode {
+    // A fixed amount of plat
+    Fixed,
+    // A percent of the competing price
+    Percent,
+    // The same price as the competing order
+    Match,
+    Unknown(String),
+}
+impl PriceStepMode {
+    // Create method to convert `PriceStepMode` to a `&str`
+    pub fn as_str(&self) -> &str {
+        match *self {
+            PriceStepMode::Fixed => "fixed",
+            PriceStepMode::Percent => "percent",
+            PriceStepMode::Match => "match",
+            PriceStepMode::Unknown(ref i) => i,
+        }
+    }
+}
+impl Serialize for PriceStepMode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let value = match self {
+            PriceStepMode::Fixed => "fixed",
+            PriceStepMode::Percent => "percent",
+            PriceStepMode::Match => "match",
+            PriceStepMode::Unknown(i) => {
+                logger::critical_file(
+                    "PriceStepMode",
+                    format!("Unknown PriceStepMode: {}", i).as_str(),
+                    Some("enums.log"),
+                );
+                "unknown"
+            }
+        };
+        serializer.serialize_str(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for PriceStepMode {
+    fn deserialize<D>(deserializer: D) -> Result<PriceStepMode, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s: String = String::deserialize(deserializer)?;
+        Ok(match s.as_str() {
+            "fixed" => PriceStepMode::Fixed,
+            "percent" => PriceStepMode::Percent,
+            "match" => PriceStepMode::Match,
+            s => PriceStepMode::Unknown(s.parse().map_err(|_| {
+                serde::de::Error::custom(format!(
+                    "invalid value for PriceStepMode, must be an string: {}",
+                    s
+                ))
+            })?),
+        })
+    }
+}
+
+// Availability of a player on Warframe Market, from most to least available
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OnlineStatus {
+    InGame,
+    Online,
+    Offline,
+    Unknown(String),
+}
+impl OnlineStatus {
+    // Create method to convert `OnlineStatus` to a `&str`
+    pub fn as_str(&self) -> &str {
+        match *self {
+            OnlineStatus::InGame => "ingame",
+            OnlineStatus::Online => "online",
+            OnlineStatus::Offline => "offline",
+            OnlineStatus::Unknown(ref i) => i,
+        }
+    }
+    // Statuses at least as available as this one
+    pub fn accepted(&self) -> Vec<&str> {
+        match *self {
+            OnlineStatus::InGame => vec!["ingame"],
+            OnlineStatus::Online => vec!["ingame", "online"],
+            OnlineStatus::Offline => vec!["ingame", "online", "offline"],
+            OnlineStatus::Unknown(ref i) => vec![i],
+        }
+    }
+}
+impl Serialize for OnlineStatus {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let value = match self {
+            OnlineStatus::InGame => "ingame",
+            OnlineStatus::Online => "online",
+            OnlineStatus::Offline => "offline",
+            OnlineStatus::Unknown(i) => {
+                logger::critical_file(
+                    "OnlineStatus",
+                    format!("Unknown OnlineStatus: {}", i).as_str(),
+                    Some("enums.log"),
+                );
+                "unknown"
+            }
+        };
+        serializer.serialize_str(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for OnlineStatus {
+    fn deserialize<D>(deserializer: D) -> Result<OnlineStatus, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s: String = String::deserialize(deserializer)?;
+        Ok(match s.as_str() {
+            "ingame" => OnlineStatus::InGame,
+            "online" => OnlineStatus::Online,
+            "offline" => OnlineStatus::Offline,
+            s => OnlineStatus::Unknown(s.parse().map_err(|_| {
+                serde::de::Error::custom(format!(
+                    "invalid value for OnlineStatus, must be an string: {}",
+                    s
+                ))
+            })?),
+        })
+    }
+}
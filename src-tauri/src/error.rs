@@ -3,7 +3,10 @@ use regex::Regex;
 use reqwest::header::HeaderMap;
 use serde_json::{json, Value};
 
-use crate::{enums::LogLevel, logger};
+use crate::{
+    enums::{ErrorKind, LogLevel},
+    logger,
+};
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct ErrorApiResponse {
@@ -40,6 +43,17 @@ pub struct AppError {
     component: String,
     eyre_report: String,
     log_level: LogLevel,
+    kind: Option<ErrorKind>,
+}
+
+// Typed error returned to the frontend by every command
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct CommandError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub context: Value,
+    // The user can fix it or retry, e.g. log in again or wait for Warframe Market
+    pub recoverable: bool,
 }
 impl AppError {
     // Custom constructor
@@ -48,6 +62,7 @@ impl AppError {
             component: component.to_string(),
             eyre_report: format!("{:?}", eyre_report),
             log_level: LogLevel::Critical,
+            kind: None,
         }
     }
     // Custom constructor
@@ -104,6 +119,7 @@ impl AppError {
             component: component.to_string(),
             eyre_report: format!("{:?}", eyre_report),
             log_level,
+            kind: None,
         }
     }
     pub fn get_info(&self) -> (String, String, Value) {
@@ -182,14 +198,64 @@ impl AppError {
         json
     }
 
+    pub fn with_kind(mut self, kind: ErrorKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    // The kind set where the error was created, otherwise taken from the status code of a failed request
+    pub fn kind(&self) -> ErrorKind {
+        if let Some(kind) = self.kind.clone() {
+            return kind;
+        }
+        let extra = self.extra_data();
+        let api_error = &extra["ApiError"];
+        match api_error["statusCode"].as_i64() {
+            Some(401) | Some(403) => ErrorKind::AuthExpired,
+            Some(404) => ErrorKind::NotFound,
+            Some(400) | Some(422) => ErrorKind::Validation,
+            Some(429) => ErrorKind::RateLimited,
+            Some(503) => ErrorKind::Maintenance,
+            // Requests that never got a response keep the default status
+            Some(500) if api_error["error"] == "UnknownError" => ErrorKind::Network,
+            Some(status) if status >= 500 => ErrorKind::Unavailable,
+            _ if self.component.starts_with("Database") => ErrorKind::Database,
+            _ => ErrorKind::Internal,
+        }
+    }
+
+    pub fn to_command_error(&self) -> CommandError {
+        let kind = self.kind();
+        let recoverable = match kind {
+            ErrorKind::Internal | ErrorKind::Database | ErrorKind::Unknown(_) => {
+                self.log_level == LogLevel::Warning || self.log_level == LogLevel::Info
+            }
+            _ => true,
+        };
+        CommandError {
+            kind,
+            message: self.cause().trim().to_string(),
+            context: json!({
+                "component": self.component(),
+                "extra_data": self.extra_data(),
+            }),
+            recoverable,
+        }
+    }
+
     // Getter for component
     pub fn to_json(&self) -> Value {
+        let command_error = self.to_command_error();
         json!({
             "component": self.component(),
             "cause": self.cause(),
             "backtrace": self.backtrace(),
             "log_level": self.log_level(),
             "extra_data": self.extra_data(),
+            "kind": command_error.kind,
+            "message": command_error.message,
+            "context": command_error.context,
+            "recoverable": command_error.recoverable,
         })
     }
 }
@@ -0,0 +1,393 @@
+use std::{fs::File, io::Write};
+
+use eyre::eyre;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    database::client::DBClient,
+    enums::{ErrorKind, LogLevel},
+    error::AppError,
+    helper, logger,
+};
+
+pub enum ExportCell {
+    Text(String),
+    Number(f64),
+    Empty,
+}
+
+impl From<String> for ExportCell {
+    fn from(value: String) -> Self {
+        ExportCell::Text(value)
+    }
+}
+
+impl From<&str> for ExportCell {
+    fn from(value: &str) -> Self {
+        ExportCell::Text(value.to_string())
+    }
+}
+
+impl From<f64> for ExportCell {
+    fn from(value: f64) -> Self {
+        ExportCell::Number(value)
+    }
+}
+
+impl From<i32> for ExportCell {
+    fn from(value: i32) -> Self {
+        ExportCell::Number(value as f64)
+    }
+}
+
+impl From<i64> for ExportCell {
+    fn from(value: i64) -> Self {
+        ExportCell::Number(value as f64)
+    }
+}
+
+impl<T: Into<ExportCell>> From<Option<T>> for ExportCell {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => ExportCell::Empty,
+        }
+    }
+}
+
+impl ExportCell {
+    fn to_text(&self) -> String {
+        match self {
+            ExportCell::Text(value) => value.clone(),
+            ExportCell::Number(value) => value.to_string(),
+            ExportCell::Empty => "".to_string(),
+        }
+    }
+}
+
+pub struct ExportTable {
+    // Also the sheet name in XLSX files
+    pub name: String,
+    pub headers: Vec<&'static str>,
+    pub rows: Vec<Vec<ExportCell>>,
+}
+
+// Days between a stored timestamp and now, None when it can not be parsed
+fn get_days_since(created: &str) -> Option<f64> {
+    let created = helper::parse_timestamp(created)?;
+    Some((chrono::Utc::now() - created).num_seconds().max(0) as f64 / 86400.0)
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+pub async fn get_stock_items(db: &DBClient) -> Result<ExportTable, AppError> {
+    let rows: Vec<Vec<ExportCell>> = db
+        .stock_item()
+        .get_items()
+        .await?
+        .into_iter()
+        .map(|item| {
+            // What the stock would make at the listed price over what it cost
+            let profit = item
+                .listed_price
+                .map(|listed| round((listed as f64 - item.price) * item.owned as f64));
+            vec![
+                item.id.into(),
+                item.name.into(),
+                item.url.into(),
+                item.tags.into(),
+                item.rank.into(),
+                item.sub_type.into(),
+                item.owned.into(),
+                round(item.price).into(),
+                round(item.price * item.owned as f64).into(),
+                item.minium_price.into(),
+                item.listed_price.into(),
+                profit.into(),
+                get_days_since(&item.created).map(round).into(),
+                item.status.into(),
+                item.created.into(),
+            ]
+        })
+        .collect();
+    Ok(ExportTable {
+        name: "stock_items".to_string(),
+        headers: vec![
+            "id",
+            "name",
+            "url_name",
+            "tags",
+            "rank",
+            "sub_type",
+            "owned",
+            "unit_price",
+            "total_cost",
+            "minimum_price",
+            "listed_price",
+            "potential_profit",
+            "days_held",
+            "status",
+            "created",
+        ],
+        rows,
+    })
+}
+
+pub async fn get_stock_rivens(db: &DBClient) -> Result<ExportTable, AppError> {
+    let rows: Vec<Vec<ExportCell>> = db
+        .stock_riven()
+        .get_items()
+        .await?
+        .into_iter()
+        .map(|riven| {
+            let attributes = riven
+                .attributes
+                .0
+                .iter()
+                .map(|a| format!("{} {}", a.url_name, a.value))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let profit = riven
+                .listed_price
+                .map(|listed| round(listed as f64 - riven.price));
+            vec![
+                riven.id.into(),
+                riven.weapon_name.into(),
+                riven.mod_name.into(),
+                riven.weapon_type.into(),
+                riven.rank.into(),
+                riven.mastery_rank.into(),
+                riven.re_rolls.into(),
+                riven.polarity.into(),
+                attributes.into(),
+                round(riven.price).into(),
+                riven.minium_price.into(),
+                riven.listed_price.into(),
+                profit.into(),
+                get_days_since(&riven.created).map(round).into(),
+                riven.status.into(),
+                riven.created.into(),
+            ]
+        })
+        .collect();
+    Ok(ExportTable {
+        name: "stock_rivens".to_string(),
+        headers: vec![
+            "id",
+            "weapon",
+            "mod_name",
+            "weapon_type",
+            "rank",
+            "mastery_rank",
+            "re_rolls",
+            "polarity",
+            "attributes",
+            "price",
+            "minimum_price",
+            "listed_price",
+            "potential_profit",
+            "days_held",
+            "status",
+            "created",
+        ],
+        rows,
+    })
+}
+
+// Sales carry their cost and profit, matched with the oldest purchases like the trade analytics
+pub async fn get_transactions(db: &DBClient) -> Result<ExportTable, AppError> {
+    let sales = db.analytics().get_sale_matches().await?;
+    let rows: Vec<Vec<ExportCell>> = db
+        .transaction()
+        .get_items()
+        .await?
+        .into_iter()
+        .map(|transaction| {
+            let sale = sales.get(&transaction.id);
+            let unit_price = transaction.price as f64 / (transaction.quantity.max(1) as f64);
+            vec![
+                transaction.id.into(),
+                transaction.created.into(),
+                transaction.transaction_type.into(),
+                transaction.item_type.into(),
+                transaction.name.into(),
+                transaction.url.into(),
+                transaction.rank.into(),
+                transaction.quantity.into(),
+                transaction.price.into(),
+                round(unit_price).into(),
+                sale.map(|s| round(s.cost)).into(),
+                sale.map(|s| round(transaction.price as f64 - s.cost))
+                    .into(),
+                sale.and_then(|s| s.avg_hold_days).map(round).into(),
+            ]
+        })
+        .collect();
+    Ok(ExportTable {
+        name: "transactions".to_string(),
+        headers: vec![
+            "id",
+            "created",
+            "type",
+            "item_type",
+            "name",
+            "url_name",
+            "rank",
+            "quantity",
+            "price",
+            "unit_price",
+            "cost",
+            "profit",
+            "days_held",
+        ],
+        rows,
+    })
+}
+
+pub async fn get_table(db: &DBClient, table: &str) -> Result<ExportTable, AppError> {
+    match table {
+        "stock_items" => get_stock_items(db).await,
+        "stock_rivens" => get_stock_rivens(db).await,
+        "transactions" => get_transactions(db).await,
+        _ => Err(AppError::new_with_level(
+            "Export",
+            eyre!("Unknown table: {}", table),
+            LogLevel::Warning,
+        )
+        .with_kind(ErrorKind::Validation)),
+    }
+}
+
+fn write_csv(path: &str, table: &ExportTable) -> Result<(), AppError> {
+    let mut writer = csv::Writer::from_path(path)
+        .map_err(|e| AppError::new("Export", eyre!("Could not create {}: {}", path, e)))?;
+    writer
+        .write_record(&table.headers)
+        .map_err(|e| AppError::new("Export", eyre!(e.to_string())))?;
+    for row in table.rows.iter() {
+        writer
+            .write_record(row.iter().map(|cell| cell.to_text()))
+            .map_err(|e| AppError::new("Export", eyre!(e.to_string())))?;
+    }
+    writer
+        .flush()
+        .map_err(|e| AppError::new("Export", eyre!(e.to_string())))?;
+    Ok(())
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn get_xlsx_row(cells: Vec<String>) -> String {
+    format!("<row>{}</row>", cells.join(""))
+}
+
+fn get_xlsx_cell(cell: &ExportCell) -> String {
+    match cell {
+        ExportCell::Number(value) if value.is_finite() => format!("<c><v>{}</v></c>", value),
+        ExportCell::Empty => "<c/>".to_string(),
+        cell => format!(
+            "<c t=\"inlineStr\"><is><t>{}</t></is></c>",
+            escape_xml(&cell.to_text())
+        ),
+    }
+}
+
+// A single sheet workbook with inline strings, enough for Excel and LibreOffice to open
+fn write_xlsx(path: &str, table: &ExportTable) -> Result<(), AppError> {
+    let file = File::create(path)
+        .map_err(|e| AppError::new("Export", eyre!("Could not create {}: {}", path, e)))?;
+    let mut zip = ZipWriter::new(file);
+    let options = FileOptions::default().compression_method(CompressionMethod::DEFLATE);
+    let zip_error = |e: String| AppError::new("Export", eyre!(e));
+    let files = [
+        (
+            "[Content_Types].xml",
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/></Types>"#.to_string(),
+        ),
+        (
+            "_rels/.rels",
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#.to_string(),
+        ),
+        (
+            "xl/workbook.xml",
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="{}" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+                escape_xml(&table.name)
+            ),
+        ),
+        (
+            "xl/_rels/workbook.xml.rels",
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#.to_string(),
+        ),
+    ];
+    for (name, content) in files.iter() {
+        zip.start_file(*name, options)
+            .map_err(|e| zip_error(e.to_string()))?;
+        zip.write_all(content.as_bytes())
+            .map_err(|e| zip_error(e.to_string()))?;
+    }
+
+    // The sheet is written row by row so big tables are not built up as one string
+    zip.start_file("xl/worksheets/sheet1.xml", options)
+        .map_err(|e| zip_error(e.to_string()))?;
+    zip.write_all(
+        concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            "\n",
+            r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#
+        )
+        .as_bytes(),
+    )
+    .map_err(|e| zip_error(e.to_string()))?;
+    let headers = get_xlsx_row(
+        table
+            .headers
+            .iter()
+            .map(|h| get_xlsx_cell(&ExportCell::from(*h)))
+            .collect(),
+    );
+    zip.write_all(headers.as_bytes())
+        .map_err(|e| zip_error(e.to_string()))?;
+    for row in table.rows.iter() {
+        let row = get_xlsx_row(row.iter().map(get_xlsx_cell).collect());
+        zip.write_all(row.as_bytes())
+            .map_err(|e| zip_error(e.to_string()))?;
+    }
+    zip.write_all(b"</sheetData></worksheet>")
+        .map_err(|e| zip_error(e.to_string()))?;
+    zip.finish().map_err(|e| zip_error(e.to_string()))?;
+    Ok(())
+}
+
+// Writes the table to the path picked in the save dialog, XLSX for .xlsx files and CSV otherwise
+pub async fn export(db: &DBClient, table: &str, path: &str) -> Result<String, AppError> {
+    let table = get_table(db, table).await?;
+    if path.to_lowercase().ends_with(".xlsx") {
+        write_xlsx(path, &table)?;
+    } else {
+        write_csv(path, &table)?;
+    }
+    logger::info_con(
+        "Export",
+        format!(
+            "Exported {} rows of {} to {}",
+            table.rows.len(),
+            table.name,
+            path
+        )
+        .as_str(),
+    );
+    Ok(path.to_string())
+}
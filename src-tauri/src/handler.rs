@@ -1,6 +1,9 @@
 use std::{env, fs};
 
-use tauri::{api::notification::Notification, AppHandle, Window};
+use eyre::eyre;
+use tauri::{api::notification::Notification, AppHandle, ClipboardManager, Window};
+
+use crate::error::AppError;
 
 #[derive(Clone,Debug)]
 pub struct MonitorHandler {
@@ -27,4 +30,10 @@ impl MonitorHandler {
             .sound(sound);
         notification.show().unwrap();
     }
+    pub fn copy_to_clipboard(&self, text: &str) -> Result<(), AppError> {
+        self.app_handler
+            .clipboard_manager()
+            .write_text(text)
+            .map_err(|e| AppError::new("Clipboard", eyre!(e.to_string())))
+    }
 }
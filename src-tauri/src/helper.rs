@@ -48,17 +48,120 @@ pub enum ColumnValue {
     String(Option<String>),
 }
 
+// Max number of events kept while the frontend is not connected
+const EVENT_BUFFER_LIMIT: usize = 500;
+
+// Holds events while the frontend is disconnected, only the latest event per entity is kept
+#[derive(Debug, Default)]
+pub struct EventBuffer {
+    pub connected: bool,
+    events: Vec<(String, Value)>,
+    pub received: u64,
+    pub compacted: u64,
+    pub dropped: u64,
+    pub flushed: u64,
+}
+
+impl EventBuffer {
+    // Key used to compact the buffer, e.g. "Client:Update:StockItems:12"
+    fn get_key(event: &str, data: &Option<Value>) -> String {
+        let data = data.clone().unwrap_or(Value::Null);
+        match event {
+            "Client:Update" => {
+                let update_type = data["type"].as_str().unwrap_or("").to_string();
+                // A SET replaces everything that came before it for that type
+                if data["operation"] == "SET" {
+                    return format!("{}:{}", event, update_type);
+                }
+                format!("{}:{}:{}", event, update_type, data["data"]["id"])
+            }
+            "Client:Update:Progress" => format!("{}:{}", event, data["id"]),
+            _ => match data.get("id") {
+                Some(id) => format!("{}:{}", event, id),
+                None => event.to_string(),
+            },
+        }
+    }
+
+    pub fn push(&mut self, event: &str, data: Option<Value>) {
+        let key = Self::get_key(event, &data);
+        self.received += 1;
+        let before = self.events.len();
+        if event == "Client:Update" && data.clone().unwrap_or(Value::Null)["operation"] == "SET" {
+            self.events
+                .retain(|(k, _)| !k.starts_with(&format!("{}:", key)) && k != &key);
+        } else {
+            self.events.retain(|(k, _)| k != &key);
+        }
+        self.compacted += (before - self.events.len()) as u64;
+        self.events
+            .push((key, json!({ "event": event, "data": data })));
+        if self.events.len() > EVENT_BUFFER_LIMIT {
+            self.events.remove(0);
+            self.dropped += 1;
+        }
+    }
+
+    pub fn get_metrics(&self) -> Value {
+        json!({
+            "connected": self.connected,
+            "size": self.events.len(),
+            "limit": EVENT_BUFFER_LIMIT,
+            "received": self.received,
+            "compacted": self.compacted,
+            "dropped": self.dropped,
+            "flushed": self.flushed,
+        })
+    }
+}
+
+pub static EVENT_BUFFER: Lazy<Mutex<EventBuffer>> =
+    Lazy::new(|| Mutex::new(EventBuffer::default()));
+
 pub fn send_message_to_window(event: &str, data: Option<Value>) {
+    crate::dashboard::publish(event, &data);
+    let mut buffer = EVENT_BUFFER.lock().unwrap();
     let window = WINDOW.lock().unwrap();
     if let Some(window) = &*window {
-        let rep = window.emit("message", json!({ "event": event, "data": data }));
-        match rep {
-            Ok(_) => {}
-            Err(e) => {
-                println!("Error while sending message to window {:?}", e);
+        if buffer.connected {
+            let rep = window.emit("message", json!({ "event": event, "data": data }));
+            match rep {
+                Ok(_) => return,
+                Err(e) => {
+                    println!("Error while sending message to window {:?}", e);
+                    buffer.connected = false;
+                }
             }
         }
     }
+    buffer.push(event, data);
+}
+
+// Called by the frontend once it is listening, sends everything that was buffered while it was away
+pub fn flush_event_buffer() -> Value {
+    let mut buffer = EVENT_BUFFER.lock().unwrap();
+    let window = WINDOW.lock().unwrap();
+    if let Some(window) = &*window {
+        let events = std::mem::take(&mut buffer.events);
+        // Everything from the first failed event on stays buffered in order for the next flush
+        let mut unsent = vec![];
+        for (key, message) in events {
+            if !unsent.is_empty() {
+                unsent.push((key, message));
+                continue;
+            }
+            match window.emit("message", message.clone()) {
+                Ok(_) => buffer.flushed += 1,
+                Err(e) => {
+                    println!("Error while sending message to window {:?}", e);
+                    unsent.push((key, message));
+                }
+            }
+        }
+        buffer.connected = unsent.is_empty();
+        buffer.events = unsent;
+    }
+    buffer.get_metrics()
 }
 
 pub async fn get_app_info() -> Result<serde_json::Value, AppError> {
@@ -516,6 +619,136 @@ pub fn merge_dataframes(frames: Vec<DataFrame>) -> Result<DataFrame, AppError> {
     Ok(DataFrame::new(combined_series)
         .map_err(|e| AppError::new("Helper", eyre!(e.to_string())))?)
 }
+/// Returns the current time as an RFC3339 UTC string, every timestamp stored by the app uses this format.
+pub fn get_utc_timestamp() -> String {
+    chrono::Utc::now().to_rfc3339()
+}
+
+/// Parses a stored timestamp into UTC.
+/// Rows created before timestamps were standardized hold naive local times, those are read as local time.
+pub fn parse_timestamp(value: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    use chrono::TimeZone;
+    if let Ok(date) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&chrono::Utc));
+    }
+    let naive = chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f").ok()?;
+    chrono::Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|date| date.with_timezone(&chrono::Utc))
+}
+
+/// Whether a one time data migration already ran, they are recorded in the "migration" table.
+pub async fn is_migrated(
+    connection: sqlx::Pool<sqlx::Sqlite>,
+    name: &str,
+) -> Result<bool, AppError> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS \"migration\" (\"name\" TEXT PRIMARY KEY NOT NULL, \"applied\" TEXT NOT NULL)",
+    )
+    .execute(&connection)
+    .await
+    .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+    let row = sqlx::query("SELECT \"name\" FROM \"migration\" WHERE \"name\" = ?")
+        .bind(name)
+        .fetch_optional(&connection)
+        .await
+        .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+    Ok(row.is_some())
+}
+
+/// Records a one time data migration, call once it finished.
+pub async fn mark_migrated(
+    connection: sqlx::Pool<sqlx::Sqlite>,
+    name: &str,
+) -> Result<(), AppError> {
+    sqlx::query("INSERT OR IGNORE INTO \"migration\" (\"name\", \"applied\") VALUES (?, ?)")
+        .bind(name)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&connection)
+        .await
+        .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+    Ok(())
+}
+
+/// Rewrites naive local timestamps in the given columns as RFC3339 UTC.
+/// Rows are matched by rowid so tables without an id column can be migrated as well.
+pub async fn migrate_timestamps_to_utc(
+    connection: sqlx::Pool<sqlx::Sqlite>,
+    table: &str,
+    columns: Vec<&str>,
+) -> Result<(), AppError> {
+    for column in columns {
+        let rows = sqlx::query(
+            format!(
+                "SELECT rowid AS id, \"{}\" AS value FROM \"{}\"",
+                column, table
+            )
+            .as_str(),
+        )
+        .fetch_all(&connection)
+        .await
+        .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        for row in rows {
+            let id: i64 = sqlx::Row::get(&row, "id");
+            let value: Option<String> = sqlx::Row::get(&row, "value");
+            let value = match value {
+                Some(value) => value,
+                None => continue,
+            };
+            if chrono::DateTime::parse_from_rfc3339(&value).is_ok() {
+                continue;
+            }
+            if let Some(date) = parse_timestamp(&value) {
+                sqlx::query(
+                    format!(
+                        "UPDATE \"{}\" SET \"{}\" = ? WHERE rowid = ?",
+                        table, column
+                    )
+                    .as_str(),
+                )
+                .bind(date.to_rfc3339())
+                .bind(id)
+                .execute(&connection)
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Number of single character edits needed to turn `a` into `b`.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current.push(
+                (previous[j] + cost)
+                    .min(previous[j + 1] + 1)
+                    .min(current[j] + 1),
+            );
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Returns a random number of milliseconds between 0 and `max`, used to spread out retries and scheduled work.
+pub fn get_jitter(max: u64) -> u64 {
+    if max == 0 {
+        return 0;
+    }
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or_default();
+    nanos % (max + 1)
+}
+
 /// Returns a vector of strings representing the dates of the last `x` days, including today.
 /// The dates are formatted as "YYYY-MM-DD".
 pub fn last_x_days(x: i64) -> Vec<String> {
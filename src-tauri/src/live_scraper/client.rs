@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc, Mutex,
@@ -6,6 +7,8 @@ use std::{
     time::Duration,
 };
 
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 use crate::{
@@ -18,21 +21,143 @@ use crate::{
     logger::{self},
     price_scraper::PriceScraper,
     settings::SettingsState,
+    structs::Order,
+    system_tray,
     wfm_client::client::WFMClient,
 };
 
-use super::modules::{item::ItemModule, riven::RivenModule};
+use super::modules::order_budget::RIVEN_CATEGORY;
+use super::modules::{
+    action_queue::ActionQueueModule,
+    baro::BaroModule,
+    fill_probability::FillProbabilityModule,
+    item::ItemModule,
+    liquidation::LiquidationModule,
+    market_maker::MarketMakerModule,
+    order_budget::OrderBudgetModule,
+    partial_sale::PartialSaleModule,
+    ranking::RankingModule,
+    reprice_throttle::RepriceThrottleModule,
+    riven::RivenModule,
+    schedule::ScheduleModule,
+    set::SetModule,
+    shadow::ShadowModule,
+    supervisor::{SupervisorModule, SupervisorState},
+    time_to_sell::TimeToSellModule,
+    volatility::{ItemVolatility, VolatilityModule},
+    wish_list::WishListModule,
+};
+
+// Seconds between requests checking if a maintenance is over
+const MAINTENANCE_PROBE_INTERVAL: u64 = 180;
+
+// Temporary price for a stock item, used instead of the computed price until it expires
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PriceOverride {
+    pub price: i64,
+    pub expires_at: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PassType {
+    // First pass after starting, only syncs my orders and the stock listings
+    WarmUp,
+    Full,
+}
+
+// Units sold on Warframe Market that have not been recorded locally yet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartialSale {
+    pub order_id: String,
+    pub stock_id: i64,
+    pub url_name: String,
+    pub rank: i32,
+    pub sub_type: Option<String>,
+    pub quantity: i64,
+    // Listed price per unit
+    pub price: i64,
+    pub detected_at: String,
+    // The whole order disappeared instead of its quantity dropping
+    pub order_closed: bool,
+}
+
+// A sell order worth buying from, waiting for the user to approve messaging the seller
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BuyAction {
+    pub id: String,
+    pub url_name: String,
+    pub name: String,
+    pub rank: i32,
+    pub sub_type: Option<String>,
+    pub seller: String,
+    pub price: i64,
+    // Closed average minus the price
+    pub profit: i64,
+    pub whisper: String,
+    pub detected_at: String,
+    // Set once the seller was messaged, the action then waits for the trade in EE.log
+    #[serde(default)]
+    pub approved_at: Option<String>,
+}
+
+// Active orders of the current pass, used to keep the order caps
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OrderBudget {
+    pub total_orders: i64,
+    // Item orders keyed by item tag
+    pub orders: HashMap<String, i64>,
+    pub auctions: i64,
+    // Orders not created because a cap was reached, keyed by category
+    pub skipped: HashMap<String, i64>,
+}
+
+impl OrderBudget {
+    pub fn get_count(&self, category: &str) -> i64 {
+        let mut count = *self.orders.get(category).unwrap_or(&0);
+        if category == RIVEN_CATEGORY {
+            count += self.auctions;
+        }
+        count
+    }
+}
 
 #[derive(Clone)]
 pub struct LiveScraperClient {
     pub log_file: String,
     pub is_running: Arc<AtomicBool>,
+    // Paused passes keep the orders up but don't check the stock
+    pub is_paused: Arc<AtomicBool>,
+    // Cause of the last error that stopped the loop, shown in the tray
+    pub last_error: Arc<Mutex<Option<String>>>,
     pub settings: Arc<Mutex<SettingsState>>,
     pub price_scraper: Arc<Mutex<PriceScraper>>,
     pub wfm: Arc<Mutex<WFMClient>>,
     pub auth: Arc<Mutex<AuthState>>,
     pub db: Arc<Mutex<DBClient>>,
     pub mh: Arc<Mutex<MonitorHandler>>,
+    // Keyed by stock item id, only kept for the current session
+    pub price_overrides: Arc<Mutex<HashMap<i64, PriceOverride>>>,
+    // Requests left in the current pass, shared by every module
+    pub request_budget: Arc<Mutex<i64>>,
+    // Items not checked when the budget ran out, checked first in the next pass
+    pub pending_items: Arc<Mutex<Vec<String>>>,
+    // My sell orders from the last pass, keyed by order id
+    pub sell_orders: Arc<Mutex<HashMap<String, Order>>>,
+    // Partial sales waiting for the user to confirm, keyed by order id
+    pub partial_sales: Arc<Mutex<HashMap<String, PartialSale>>>,
+    // Buy opportunities waiting for approval, keyed by the seller's order id
+    pub buy_actions: Arc<Mutex<HashMap<String, BuyAction>>>,
+    // Average units sold per day, keyed by url name
+    pub daily_volumes: Arc<Mutex<HashMap<String, f64>>>,
+    // Expected days until the listing sells, keyed by stock item id
+    pub time_to_sell: Arc<Mutex<HashMap<i64, f64>>>,
+    pub order_budget: Arc<Mutex<OrderBudget>>,
+    // Passes since the app started, used to space out the checks of stable items
+    pub pass_count: Arc<Mutex<u64>>,
+    // Recent price changes keyed by url name
+    pub item_volatility: Arc<Mutex<HashMap<String, ItemVolatility>>>,
+    // Failed passes and the restarts of this session
+    pub supervisor_state: Arc<Mutex<SupervisorState>>,
 }
 
 impl LiveScraperClient {
@@ -49,20 +174,42 @@ impl LiveScraperClient {
             price_scraper,
             settings,
             is_running: Arc::new(AtomicBool::new(false)),
+            is_paused: Arc::new(AtomicBool::new(false)),
+            last_error: Arc::new(Mutex::new(None)),
             wfm,
             auth,
             db,
             mh,
+            price_overrides: Arc::new(Mutex::new(HashMap::new())),
+            request_budget: Arc::new(Mutex::new(-1)),
+            pending_items: Arc::new(Mutex::new(vec![])),
+            sell_orders: Arc::new(Mutex::new(HashMap::new())),
+            partial_sales: Arc::new(Mutex::new(HashMap::new())),
+            buy_actions: Arc::new(Mutex::new(HashMap::new())),
+            daily_volumes: Arc::new(Mutex::new(HashMap::new())),
+            time_to_sell: Arc::new(Mutex::new(HashMap::new())),
+            order_budget: Arc::new(Mutex::new(OrderBudget::default())),
+            pass_count: Arc::new(Mutex::new(0)),
+            item_volatility: Arc::new(Mutex::new(HashMap::new())),
+            supervisor_state: Arc::new(Mutex::new(SupervisorState::default())),
         }
     }
     fn report_error(&self, error: AppError) {
+        // Every request fails during maintenance, the loop waits it out instead of stopping
+        if self.is_maintenance() {
+            logger::info_file(
+                "LiveScraper",
+                format!("Skipped error during maintenance: {}", error.cause()).as_str(),
+                Some(self.log_file.as_str()),
+            );
+            return;
+        }
         let component = error.component();
         let cause = error.cause();
         let backtrace = error.backtrace();
         let log_level = error.log_level();
         let extra = error.extra_data();
         if log_level == LogLevel::Critical || log_level == LogLevel::Error {
-            self.is_running.store(false, Ordering::SeqCst);
             crate::logger::dolog(
                 log_level.clone(),
                 component.as_str(),
@@ -70,6 +217,12 @@ impl LiveScraperClient {
                 true,
                 Some(self.log_file.as_str()),
             );
+            // Network errors are retried once the pass is over
+            if self.supervisor().handle(&error).unwrap_or(false) {
+                return;
+            }
+            self.is_running.store(false, Ordering::SeqCst);
+            *self.last_error.lock().unwrap() = Some(format!("{}: {}", component, cause));
             helper::send_message_to_window("LiveScraper:Error", Some(error.to_json()));
         } else {
             crate::logger::dolog(
@@ -89,8 +242,142 @@ impl LiveScraperClient {
         self.is_running.load(Ordering::SeqCst)
     }
 
+    // Takes effect once the current pass is done
+    pub fn pause(&self) {
+        self.is_paused.store(true, Ordering::SeqCst);
+        self.send_message("paused", None);
+    }
+
+    pub fn resume(&self) {
+        self.is_paused.store(false, Ordering::SeqCst);
+        self.send_message("resumed", None);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_paused.load(Ordering::SeqCst)
+    }
+
+    // Warframe Market client that counts its requests against the budget of the pass
+    pub fn get_wfm(&self) -> Result<WFMClient, AppError> {
+        Ok(self.wfm.lock()?.with_budget(self.request_budget.clone()))
+    }
+
+    pub fn is_maintenance(&self) -> bool {
+        self.wfm
+            .lock()
+            .map(|wfm| wfm.is_maintenance())
+            .unwrap_or(false)
+    }
+
+    // Suspends the scraper until Warframe Market is back, probing it every few minutes
+    async fn wait_for_maintenance(&self) {
+        logger::warning_con(
+            "LiveScraper",
+            "Warframe Market is in maintenance, suspending until it is back",
+        );
+        helper::send_message_to_window("LiveScraper:Maintenance", Some(json!({ "active": true })));
+        self.send_message("maintenance", None);
+        let mut elapsed = 0;
+        while self.is_running() && self.is_maintenance() {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            elapsed += 1;
+            if elapsed < MAINTENANCE_PROBE_INTERVAL {
+                continue;
+            }
+            elapsed = 0;
+            // Any successful request clears the maintenance state
+            let wfm = self.wfm.lock().unwrap().clone();
+            let _ = wfm.orders().get_my_orders().await;
+        }
+        helper::send_message_to_window("LiveScraper:Maintenance", Some(json!({ "active": false })));
+        logger::info_con("LiveScraper", "Warframe Market is back, resuming");
+    }
+
+    // Holds the next cycle back until the schedule allows it, true when it had to wait
+    async fn wait_for_schedule(&self, last_cycle: Option<DateTime<Utc>>) -> bool {
+        let mut waited = false;
+        let mut announced = None;
+        while self.is_running() {
+            let (next_run, reason) = match self.schedule().next_run(last_cycle) {
+                Ok(next) => next,
+                Err(e) => {
+                    self.report_error(e);
+                    break;
+                }
+            };
+            if next_run <= Local::now() {
+                break;
+            }
+            if announced != Some(next_run) {
+                announced = Some(next_run);
+                helper::send_message_to_window(
+                    "LiveScraper:Schedule",
+                    Some(json!({ "next_run": next_run.to_rfc3339(), "reason": reason })),
+                );
+                self.send_message(
+                    "schedule.waiting",
+                    Some(json!({ "next_run": next_run.to_rfc3339(), "reason": reason })),
+                );
+            }
+            waited = true;
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+        if waited {
+            helper::send_message_to_window(
+                "LiveScraper:Schedule",
+                Some(json!({ "next_run": null, "reason": null })),
+            );
+        }
+        waited
+    }
+
+    // Shows me as in game when enabled, returns the status to restore when the scraper stops
+    async fn set_ingame_status(&self) -> Option<String> {
+        if !self.settings.lock().unwrap().live_scraper.sync_status {
+            return None;
+        }
+        let wfm = self.wfm.lock().unwrap().clone();
+        let status = match wfm.auth().get_status().await {
+            Ok(status) => status,
+            Err(e) => {
+                self.report_error(e);
+                return None;
+            }
+        };
+        if status == "ingame" {
+            return None;
+        }
+        match wfm.auth().set_status("ingame").await {
+            Ok(_) => Some(status),
+            Err(e) => {
+                self.report_error(e);
+                None
+            }
+        }
+    }
+
+    // Offline can not be set, invisible is the closest
+    async fn restore_status(&self, status: &str) {
+        let status = if status == "offline" {
+            "invisible"
+        } else {
+            status
+        };
+        let wfm = self.wfm.lock().unwrap().clone();
+        if let Err(e) = wfm.auth().set_status(status).await {
+            self.report_error(e);
+        }
+    }
+
+    pub fn is_budget_exhausted(&self) -> Result<bool, AppError> {
+        Ok(*self.request_budget.lock()? == 0)
+    }
+
     pub fn start_loop(&mut self) -> Result<(), AppError> {
         self.is_running.store(true, Ordering::SeqCst);
+        self.is_paused.store(false, Ordering::SeqCst);
+        *self.last_error.lock()? = None;
+        self.supervisor_state.lock()?.failed_passes = 0;
         let is_running = Arc::clone(&self.is_running);
         let forced_stop = Arc::clone(&self.is_running);
         let scraper = self.clone();
@@ -108,8 +395,28 @@ impl LiveScraperClient {
                 .delete_all_orders(OrderMode::Both)
                 .await
                 .unwrap();
+            let previous_status = scraper.set_ingame_status().await;
+            let mut pass = PassType::WarmUp;
+            let mut last_cycle: Option<DateTime<Utc>> = None;
             while is_running.load(Ordering::SeqCst) && forced_stop.load(Ordering::SeqCst) {
+                if scraper.is_maintenance() {
+                    scraper.wait_for_maintenance().await;
+                    continue;
+                }
+                if scraper.is_paused() {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+                if scraper.wait_for_schedule(last_cycle).await {
+                    continue;
+                }
+                last_cycle = Some(Utc::now());
                 let settings = scraper.settings.lock().unwrap().clone();
+                *scraper.request_budget.lock().unwrap() = settings.live_scraper.api_budget;
+                *scraper.order_budget.lock().unwrap() = OrderBudget::default();
+                if let Err(e) = scraper.volatility().next_pass() {
+                    scraper.report_error(e);
+                }
                 if settings.live_scraper.stock_mode == StockMode::Riven
                     || settings.live_scraper.stock_mode == StockMode::All
                 {
@@ -126,13 +433,44 @@ impl LiveScraperClient {
                 {
                     logger::info_con("LiveScraper", "Checking item stock");
                     scraper.send_message("riven.starting", None);
-                    match scraper.item().check_stock().await {
+                    match scraper.item().check_stock(pass.clone()).await {
                         Ok(_) => {}
                         Err(e) => scraper.report_error(e),
                     }
+                    let pending_items = scraper.pending_items.lock().unwrap().clone();
+                    if !pending_items.is_empty() {
+                        logger::warning_con(
+                            "LiveScraper",
+                            format!(
+                                "Request budget exhausted, not checked: {}",
+                                pending_items.join(", ")
+                            )
+                            .as_str(),
+                        );
+                        scraper.send_message(
+                            "item.budget_exhausted",
+                            Some(json!({ "count": pending_items.len() })),
+                        );
+                    }
+                }
+                if let Err(e) = scraper.order_budget().report() {
+                    scraper.report_error(e);
+                }
+                if let Err(e) = scraper.volatility().report() {
+                    scraper.report_error(e);
+                }
+                if scraper.supervisor().wait_before_retry().await {
+                    continue;
+                }
+                if pass == PassType::WarmUp {
+                    logger::info_con("LiveScraper", "Warm-up pass done");
+                    pass = PassType::Full;
                 }
                 tokio::time::sleep(Duration::from_secs(1)).await;
             }
+            if let Some(status) = previous_status {
+                scraper.restore_status(&status).await;
+            }
             scraper.send_message("", None);
             logger::info_con("LiveScraper", "Loop live scraper is stopped");
         });
@@ -144,8 +482,57 @@ impl LiveScraperClient {
     pub fn riven(&self) -> RivenModule {
         RivenModule { client: self }
     }
+    pub fn market_maker(&self) -> MarketMakerModule {
+        MarketMakerModule { client: self }
+    }
+    pub fn action_queue(&self) -> ActionQueueModule {
+        ActionQueueModule { client: self }
+    }
+    pub fn set(&self) -> SetModule {
+        SetModule { client: self }
+    }
+    pub fn wish_list(&self) -> WishListModule {
+        WishListModule { client: self }
+    }
+    pub fn baro(&self) -> BaroModule {
+        BaroModule { client: self }
+    }
+    pub fn partial_sale(&self) -> PartialSaleModule {
+        PartialSaleModule { client: self }
+    }
+    pub fn fill_probability(&self) -> FillProbabilityModule {
+        FillProbabilityModule { client: self }
+    }
+    pub fn time_to_sell(&self) -> TimeToSellModule {
+        TimeToSellModule { client: self }
+    }
+    pub fn liquidation(&self) -> LiquidationModule {
+        LiquidationModule { client: self }
+    }
+    pub fn order_budget(&self) -> OrderBudgetModule {
+        OrderBudgetModule { client: self }
+    }
+    pub fn volatility(&self) -> VolatilityModule {
+        VolatilityModule { client: self }
+    }
+    pub fn shadow(&self) -> ShadowModule {
+        ShadowModule { client: self }
+    }
+    pub fn reprice_throttle(&self) -> RepriceThrottleModule {
+        RepriceThrottleModule { client: self }
+    }
+    pub fn schedule(&self) -> ScheduleModule {
+        ScheduleModule { client: self }
+    }
+    pub fn supervisor(&self) -> SupervisorModule {
+        SupervisorModule { client: self }
+    }
+    pub fn ranking(&self) -> RankingModule {
+        RankingModule { client: self }
+    }
 
     pub fn send_message(&self, i18n_key: &str, data: Option<serde_json::Value>) {
+        system_tray::client::update_status(self, Some((i18n_key, &data)));
         helper::send_message_to_window(
             "LiveScraper:UpdateMessage",
             Some(json!({
@@ -0,0 +1,237 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use eyre::eyre;
+use once_cell::sync::Lazy;
+use polars::prelude::DataFrame;
+use serde_json::json;
+
+use crate::{
+    enums::{ErrorKind, LogLevel},
+    error::AppError,
+    helper::{self, ColumnType, ColumnValue},
+    live_scraper::client::{BuyAction, LiveScraperClient},
+    logger,
+};
+
+// Queued actions older than this are dropped, the order has likely been sold or changed
+const PENDING_EXPIRY_MINUTES: i64 = 30;
+// Approved actions without a trade after this long are dropped, the whisper went unanswered
+const APPROVED_EXPIRY_MINUTES: i64 = 120;
+
+// Approved actions waiting for their trade in EE.log, keyed by order id
+static APPROVED: Lazy<Mutex<HashMap<String, BuyAction>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn is_expired(timestamp: &str, minutes: i64) -> bool {
+    match helper::parse_timestamp(timestamp) {
+        Some(date) => chrono::Utc::now() - date > chrono::Duration::minutes(minutes),
+        None => true,
+    }
+}
+
+// The approved action the trade with the seller was for, removed so it is only matched once
+pub fn take_approved(seller: &str, url_name: &str) -> Result<Option<BuyAction>, AppError> {
+    let mut approved = APPROVED.lock()?;
+    let id = approved
+        .values()
+        .find(|a| a.seller.eq_ignore_ascii_case(seller) && a.url_name == url_name)
+        .map(|a| a.id.clone());
+    Ok(id.and_then(|id| approved.remove(&id)))
+}
+
+pub struct ActionQueueModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> ActionQueueModule<'a> {
+    // Queues the cheapest sell order of an item worth buying, nothing is sent until it is approved
+    pub fn add_opportunity(
+        &self,
+        item_name: &str,
+        item_rank: Option<f64>,
+        sub_type: Option<String>,
+        live_sell_orders_df: &DataFrame,
+        closed_avg: f64,
+    ) -> Result<(), AppError> {
+        let settings = self
+            .client
+            .settings
+            .lock()?
+            .live_scraper
+            .action_queue
+            .clone();
+        if !settings.enabled || live_sell_orders_df.height() == 0 {
+            return Ok(());
+        }
+        self.expire()?;
+        let get_string = |column: &str| -> Result<String, AppError> {
+            match helper::get_column_value(
+                live_sell_orders_df.clone(),
+                None,
+                column,
+                ColumnType::String,
+            )? {
+                ColumnValue::String(value) => Ok(value.unwrap_or_default()),
+                _ => Err(AppError::new(
+                    "ActionQueue",
+                    eyre!("Expected string values"),
+                )),
+            }
+        };
+        let price = match helper::get_column_value(
+            live_sell_orders_df.clone(),
+            None,
+            "platinum",
+            ColumnType::I64,
+        )? {
+            ColumnValue::I64(value) => value.unwrap_or(0),
+            _ => return Err(AppError::new("ActionQueue", eyre!("Expected i64 values"))),
+        };
+        let profit = (closed_avg - price as f64).round() as i64;
+        if price <= 0 || profit < settings.min_profit {
+            return Ok(());
+        }
+        let id = get_string("id")?;
+        let seller = get_string("username")?;
+        if self.client.buy_actions.lock()?.contains_key(&id) {
+            return Ok(());
+        }
+
+        let name = match self
+            .client
+            .db
+            .lock()?
+            .cache
+            .lock()?
+            .items()
+            .find_type(item_name)?
+        {
+            Some(item) => item.item_name,
+            None => item_name.to_string(),
+        };
+        let action = BuyAction {
+            id: id.clone(),
+            url_name: item_name.to_string(),
+            name: name.clone(),
+            rank: item_rank.unwrap_or(0.0) as i32,
+            sub_type,
+            seller: seller.clone(),
+            price,
+            profit,
+            whisper: settings
+                .whisper_template
+                .replace("<PLAYER_NAME>", &seller)
+                .replace("<QUANTITY>", "")
+                .replace("<ITEM_NAME>", &name)
+                .replace("<PRICE>", &price.to_string()),
+            detected_at: helper::get_utc_timestamp(),
+            approved_at: None,
+        };
+        self.client.buy_actions.lock()?.insert(id, action.clone());
+        helper::emit_update("BuyActions", "CREATE_OR_UPDATE", Some(json!(action)));
+        logger::info_con(
+            "ActionQueue",
+            format!(
+                "Queued {} from {} for {}p, {}p below the average",
+                item_name, seller, price, profit
+            )
+            .as_str(),
+        );
+        Ok(())
+    }
+
+    // Drops queued actions that were left too long and approved ones that never led to a trade
+    pub fn expire(&self) -> Result<(), AppError> {
+        let mut expired = vec![];
+        self.client.buy_actions.lock()?.retain(|_, action| {
+            let keep = !is_expired(&action.detected_at, PENDING_EXPIRY_MINUTES);
+            if !keep {
+                expired.push(action.clone());
+            }
+            keep
+        });
+        for action in expired {
+            helper::emit_update("BuyActions", "DELETE", Some(json!(action)));
+        }
+        APPROVED.lock()?.retain(|_, action| {
+            let approved_at = action.approved_at.as_deref().unwrap_or(&action.detected_at);
+            let keep = !is_expired(approved_at, APPROVED_EXPIRY_MINUTES);
+            if !keep {
+                logger::info_con(
+                    "ActionQueue",
+                    format!(
+                        "No trade with {} for {}, dropping the approved buy",
+                        action.seller, action.url_name
+                    )
+                    .as_str(),
+                );
+            }
+            keep
+        });
+        Ok(())
+    }
+
+    pub fn get_pending(&self) -> Result<Vec<BuyAction>, AppError> {
+        self.expire()?;
+        let mut actions: Vec<BuyAction> =
+            self.client.buy_actions.lock()?.values().cloned().collect();
+        actions.sort_by(|a, b| b.profit.cmp(&a.profit));
+        Ok(actions)
+    }
+
+    // Messages the seller and keeps the action until the trade shows up in EE.log, which adds the stock.
+    // Without a conversation on Warframe Market the whisper has to be sent in game and false is returned.
+    pub async fn approve(&self, id: &str) -> Result<bool, AppError> {
+        let mut action = match self.client.buy_actions.lock()?.remove(id) {
+            Some(action) => action,
+            None => {
+                return Err(AppError::new_with_level(
+                    "ActionQueue",
+                    eyre!("No buy action found for order {}", id),
+                    LogLevel::Warning,
+                )
+                .with_kind(ErrorKind::NotFound))
+            }
+        };
+        helper::emit_update("BuyActions", "DELETE", Some(json!(action)));
+
+        let wfm = self.client.wfm.lock()?.clone();
+        let message = action.whisper.trim_start_matches("/w").trim_start();
+        let message = message
+            .strip_prefix(action.seller.as_str())
+            .unwrap_or(message)
+            .trim_start();
+        let whispered = match wfm.chat().send_message(&action.seller, message).await {
+            Ok(_) => true,
+            Err(e) if e.kind() == ErrorKind::NotFound => false,
+            Err(e) => {
+                // Left in the queue so it can be approved again
+                self.client
+                    .buy_actions
+                    .lock()?
+                    .insert(action.id.clone(), action.clone());
+                helper::emit_update("BuyActions", "CREATE_OR_UPDATE", Some(json!(action)));
+                return Err(e);
+            }
+        };
+
+        action.approved_at = Some(helper::get_utc_timestamp());
+        APPROVED.lock()?.insert(action.id.clone(), action.clone());
+        logger::info_con(
+            "ActionQueue",
+            format!(
+                "Approved buying {} from {} for {}p",
+                action.url_name, action.seller, action.price
+            )
+            .as_str(),
+        );
+        Ok(whispered)
+    }
+
+    pub fn dismiss(&self, id: &str) -> Result<(), AppError> {
+        if let Some(action) = self.client.buy_actions.lock()?.remove(id) {
+            helper::emit_update("BuyActions", "DELETE", Some(json!(action)));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+use std::{collections::HashSet, sync::Mutex};
+
+use eyre::eyre;
+use once_cell::sync::Lazy;
+use polars::prelude::*;
+use serde_json::json;
+
+use crate::{
+    database::modules::analytics::DucatItemStruct,
+    error::AppError,
+    helper::{self, ColumnType, ColumnValue},
+    live_scraper::client::LiveScraperClient,
+    logger,
+};
+
+// Tag put on the buy transactions of parts bought for Baro
+pub const BARO_TAG: &str = "baro";
+
+// Parts with a Baro buy order up, set on every pass
+static BARO_ITEMS: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+pub fn is_baro_item(url_name: &str) -> Result<bool, AppError> {
+    Ok(BARO_ITEMS.lock()?.contains(url_name))
+}
+
+pub struct BaroModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> BaroModule<'a> {
+    // Keeps buy orders up for the best ducats per plat parts until the budget is used.
+    // Returns the parts bought for Baro, the profit driven buying leaves their orders alone.
+    pub async fn check_items(
+        &self,
+        current_buy_orders: &DataFrame,
+    ) -> Result<HashSet<String>, AppError> {
+        let mut handled = HashSet::new();
+        let rule = self
+            .client
+            .settings
+            .lock()?
+            .live_scraper
+            .stock_item
+            .baro_prep
+            .clone();
+        if !rule.is_active() {
+            BARO_ITEMS.lock()?.clear();
+            return Ok(handled);
+        }
+        let db = self.client.db.lock()?.clone();
+        let analysis = db.analytics().get_ducat_analysis(usize::MAX).await?;
+
+        // What the ducat parts in stock cost counts against the budget
+        let stock_urls: HashSet<&String> = analysis.stock.iter().map(|item| &item.url).collect();
+        let mut spent: i64 = db
+            .stock_item()
+            .get_items()
+            .await?
+            .iter()
+            .filter(|stock_item| stock_urls.contains(&stock_item.url))
+            .map(|stock_item| (stock_item.price * stock_item.owned as f64) as i64)
+            .sum();
+
+        let mut candidates: Vec<&DucatItemStruct> = analysis
+            .stock
+            .iter()
+            .chain(analysis.market.iter())
+            .collect();
+        candidates.sort_by(|a, b| b.ducats_per_plat.total_cmp(&a.ducats_per_plat));
+        for item in candidates {
+            // Never bid more than keeps the part at the ducats per plat limit
+            let price = (item.price.floor() as i64).min(rule.get_max_price(item.ducats));
+            let quantity = rule.quantity - item.owned;
+            if item.ducats_per_plat < rule.min_ducats_per_plat
+                || price <= 0
+                || quantity <= 0
+                || spent + price * quantity > rule.budget
+            {
+                continue;
+            }
+            spent += price * quantity;
+            handled.insert(item.url.clone());
+            self.sync_order(item, price, quantity, current_buy_orders)
+                .await?;
+        }
+        logger::info_con(
+            "Baro",
+            format!(
+                "Buying {} parts for Baro, {}/{} plat of the budget used",
+                handled.len(),
+                spent,
+                rule.budget
+            )
+            .as_str(),
+        );
+        *BARO_ITEMS.lock()? = handled.clone();
+        Ok(handled)
+    }
+
+    async fn sync_order(
+        &self,
+        item: &DucatItemStruct,
+        price: i64,
+        quantity: i64,
+        current_buy_orders: &DataFrame,
+    ) -> Result<(), AppError> {
+        let wfm = self.client.get_wfm()?;
+        let url_name = item.url.as_str();
+        let (order_id, visibility, current_price, active) = self
+            .client
+            .item()
+            .get_my_order_information(url_name, current_buy_orders)
+            .await?;
+        if !active {
+            if !self.client.order_budget().reserve(url_name)? {
+                return Ok(());
+            }
+            let cache = self.client.db.lock()?.cache.lock()?.clone();
+            let item_id = match cache.items().find_type(url_name)? {
+                Some(item) => item.id,
+                None => return Ok(()),
+            };
+            self.client.send_message(
+                "item.buy.creating",
+                Some(json!({ "name": url_name, "price": price})),
+            );
+            wfm.orders()
+                .create(&item_id, "buy", price, quantity, true, None, None)
+                .await?;
+            logger::info_con(
+                "Baro",
+                format!(
+                    "Buying {} x{} for {}p, {} ducats each",
+                    url_name, quantity, price, item.ducats
+                )
+                .as_str(),
+            );
+        } else if current_price != price
+            || self.get_order_quantity(url_name, current_buy_orders)? != quantity
+        {
+            self.client.send_message(
+                "item.buy.updating",
+                Some(json!({ "name": url_name, "price": price})),
+            );
+            wfm.orders()
+                .update(
+                    &order_id.unwrap_or_default(),
+                    price as i32,
+                    quantity as i32,
+                    visibility,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    fn get_order_quantity(&self, url_name: &str, df: &DataFrame) -> Result<i64, AppError> {
+        match helper::get_column_value(
+            df.clone(),
+            Some(col("url_name").eq(lit(url_name))),
+            "quantity",
+            ColumnType::I64,
+        )? {
+            ColumnValue::I64(values) => Ok(values.unwrap_or(0)),
+            _ => Err(AppError::new("Baro", eyre!("Expected i64 values"))),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{error::AppError, helper, live_scraper::client::LiveScraperClient, logger};
+
+// Fewer fills than this are not enough to trust the estimate
+const MIN_FILLS: usize = 5;
+
+pub struct FillProbabilityModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> FillProbabilityModule<'a> {
+    // Only kept while a fill probability target is set, nothing else reads the snapshots
+    pub async fn record_snapshot(
+        &self,
+        item_name: &str,
+        top_price: i64,
+        buyers: i64,
+        target: i64,
+    ) -> Result<(), AppError> {
+        if target < 0 {
+            return Ok(());
+        }
+        let db = self.client.db.lock()?.clone();
+        db.buy_book().create(item_name, top_price, buyers).await?;
+        Ok(())
+    }
+
+    // How far from the top bid each of my purchases of the item was filled, sorted ascending
+    async fn get_fill_offsets(&self, item_name: &str) -> Result<Vec<i64>, AppError> {
+        let db = self.client.db.lock()?.clone();
+        let snapshots = db.buy_book().get_by_url(item_name).await?;
+        let purchases = db.transaction().get_by_url(item_name, "buy").await?;
+
+        let mut offsets = vec![];
+        for purchase in purchases {
+            let bought_at = match helper::parse_timestamp(&purchase.created) {
+                Some(bought_at) => bought_at,
+                None => continue,
+            };
+            // The book as it was last seen before the purchase, ignored when it is too old
+            let snapshot = snapshots
+                .iter()
+                .filter_map(|s| helper::parse_timestamp(&s.created).map(|created| (created, s)))
+                .filter(|(created, _)| {
+                    *created <= bought_at && bought_at - *created <= chrono::Duration::hours(24)
+                })
+                .last();
+            if let Some((_, snapshot)) = snapshot {
+                let unit_price = purchase.price as i64 / purchase.quantity.max(1) as i64;
+                offsets.push(unit_price - snapshot.top_price);
+            }
+        }
+        offsets.sort();
+        Ok(offsets)
+    }
+
+    // Share of past fills that a bid this far from the top bid would have matched
+    fn estimate(offsets: &Vec<i64>, offset: i64) -> f64 {
+        if offsets.is_empty() {
+            return 0.0;
+        }
+        let filled = offsets.iter().filter(|&&o| o <= offset).count();
+        filled as f64 / offsets.len() as f64
+    }
+
+    // Cheapest bid whose fill probability reaches the target in percent.
+    // None when there is not enough history, the caller keeps its own price.
+    pub async fn get_bid(
+        &self,
+        item_name: &str,
+        top_price: i64,
+        target: i64,
+    ) -> Result<Option<i64>, AppError> {
+        if target < 0 {
+            return Ok(None);
+        }
+        let offsets = self.get_fill_offsets(item_name).await?;
+        if offsets.len() < MIN_FILLS {
+            return Ok(None);
+        }
+        let target = target as f64 / 100.0;
+        for offset in offsets.iter() {
+            let probability = Self::estimate(&offsets, *offset);
+            if probability >= target {
+                let bid = (top_price + offset).max(1);
+                logger::info_con(
+                    "FillProbability",
+                    format!(
+                        "Item {item_name} top bid: {top_price}, bid: {bid}, fill probability: {:.0}%",
+                        probability * 100.0
+                    )
+                    .as_str(),
+                );
+                return Ok(Some(bid));
+            }
+        }
+        Ok(None)
+    }
+}
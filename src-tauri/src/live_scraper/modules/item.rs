@@ -1,6 +1,14 @@
-use crate::enums::OrderMode;
+use crate::cache::modules::feature_flag::MARKET_MAKER;
+use crate::cache::modules::price_provider;
+use crate::database::modules::item_price;
+use crate::database::modules::order_journal;
+use crate::database::modules::stock_item::StockItemStruct;
+use crate::enums::{ErrorKind, ListingAgeAction, OrderMode};
 use crate::error;
-use crate::live_scraper::client::LiveScraperClient;
+use crate::enums::LogLevel;
+use crate::live_scraper::client::{LiveScraperClient, PassType, PriceOverride};
+use crate::live_scraper::modules::ranking::CheckPhase;
+use crate::settings::{MarketMakerRule, PricingProfile};
 use crate::structs::Order;
 use crate::{
     error::AppError,
@@ -8,24 +16,32 @@ use crate::{
     logger,
 };
 use eyre::eyre;
+use futures_util::{stream, StreamExt};
 use polars::prelude::*;
 use serde_json::json;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::vec;
 
+// Upper bound of the item order requests in flight, the rate limiter still spaces them out
+const MAX_CONCURRENT_CHECKS: i64 = 5;
+
 pub struct ItemModule<'a> {
     pub client: &'a LiveScraperClient,
 }
 
 impl<'a> ItemModule<'a> {
-    pub async fn check_stock(&self) -> Result<(), AppError> {
+    pub async fn check_stock(&self, pass: PassType) -> Result<(), AppError> {
         logger::info_con("ItemModule", "Run item module");
         let db = self.client.db.lock()?.clone();
+        self.prune_item_lists()?;
+        self.client.reprice_throttle().apply_pending().await?;
 
         let settings = self.client.settings.lock()?.clone().live_scraper;
         let order_mode = settings.stock_item.order_mode.clone();
+        // The warm-up pass only keeps the stock listings up to date, new buys are found later
+        let discover = pass == PassType::Full;
 
-        let wfm = self.client.wfm.lock()?.clone();
+        let wfm = self.client.get_wfm()?;
 
         // List of strings that will be checked
         let mut stock_items: Vec<String> = vec![];
@@ -36,7 +52,12 @@ impl<'a> ItemModule<'a> {
         .unwrap();
         let mut popular_items: Vec<String> = vec![];
         let popular_items_df = self.get_buy_sell_overlap().await?;
-        let whitelist_items: Vec<String> = settings.stock_item.whitelist.clone();
+        self.client.time_to_sell().load_daily_volumes()?;
+        let whitelist_items: Vec<String> = if discover {
+            settings.stock_item.whitelist.clone()
+        } else {
+            vec![]
+        };
 
         // Get current orders from Warframe Market Sell and Buy orders.
         let (mut current_buy_orders_df, current_sell_orders_df) =
@@ -44,36 +65,78 @@ impl<'a> ItemModule<'a> {
 
         // Delete orders base on order_mode
         let orders = wfm.orders().get_my_orders().await?;
-        if order_mode == OrderMode::Buy {
-            let mut current_index = 0;
-            let total = orders.sell_orders.len();
-            self.client.send_message(
-                "item.deleting_orders",
-                Some(json!({ "count": 0, "total": total})),
-            );
-            for order in orders.sell_orders {
-                current_index += 1;
-                self.client.send_message(
-                    "item.deleting_orders",
-                    Some(json!({ "count": current_index, "total": total})),
-                );
-                wfm.orders().delete(&order.id).await?;
-            }
-        } else if order_mode == OrderMode::Sell {
-            let mut current_index = 0;
-            let total = orders.buy_orders.len();
+        // Visible orders by id with their url name, checked first so my listings do not go stale
+        let visible_orders: Vec<(String, String)> = orders
+            .sell_orders
+            .iter()
+            .chain(orders.buy_orders.iter())
+            .filter(|order| order.visible)
+            .filter_map(|order| Some((order.id.clone(), order.item.as_ref()?.url_name.clone())))
+            .collect();
+        self.client.order_budget().load_orders(
+            &orders
+                .sell_orders
+                .iter()
+                .chain(orders.buy_orders.iter())
+                .cloned()
+                .collect(),
+        )?;
+        self.client
+            .partial_sale()
+            .check_orders(&orders.sell_orders)
+            .await?;
+        // Wish list items keep their own buy orders at the target price
+        let mut wish_items = self
+            .client
+            .wish_list()
+            .check_items(&current_buy_orders_df)
+            .await?;
+        // So do the parts bought for Baro Ki'Teer
+        wish_items.extend(
+            self.client
+                .baro()
+                .check_items(&current_buy_orders_df)
+                .await?,
+        );
+        let to_delete: Vec<Order> = match order_mode {
+            OrderMode::Buy => orders.sell_orders,
+            OrderMode::Sell => orders.buy_orders,
+            _ => vec![],
+        }
+        .into_iter()
+        .filter(|order| {
+            !self.is_market_maker_order(order) && !self.is_wish_list_order(order, &wish_items)
+        })
+        .collect();
+        let deleted_ids: HashSet<&String> = to_delete.iter().map(|order| &order.id).collect();
+        let active_items: HashSet<String> = visible_orders
+            .into_iter()
+            .filter(|(id, _)| !deleted_ids.contains(id))
+            .map(|(_, url_name)| url_name)
+            .collect();
+        if !to_delete.is_empty() {
+            // Journaled so a pass stopped halfway can be resumed or rolled back
+            let batch = db
+                .order_journal()
+                .begin("live_scraper.order_mode", &to_delete)
+                .await?;
+            let total = to_delete.len();
             self.client.send_message(
                 "item.deleting_orders",
                 Some(json!({ "count": 0, "total": total})),
             );
-            for order in orders.buy_orders {
-                current_index += 1;
+            for (index, order) in to_delete.iter().enumerate() {
                 self.client.send_message(
                     "item.deleting_orders",
-                    Some(json!({ "count": current_index, "total": total})),
+                    Some(json!({ "count": index + 1, "total": total})),
                 );
                 wfm.orders().delete(&order.id).await?;
+                db.order_journal()
+                    .set_status(&batch, &order.id, order_journal::DELETED)
+                    .await?;
+                self.client.order_budget().release_order(order)?;
             }
+            db.order_journal().complete(&batch).await?;
         }
 
         // Get the items names from the database based on order_mode
@@ -85,8 +148,16 @@ impl<'a> ItemModule<'a> {
             stock_items.append(&mut db.stock_item().get_items_names().await?.clone());
         }
 
+        // Sets are left to the set module, the parts they use are reserved when selling
+        let set_listings = if order_mode == OrderMode::Sell || order_mode == OrderMode::Both {
+            self.client.set().check_sets(&current_sell_orders_df).await?
+        } else {
+            HashSet::new()
+        };
+        stock_items.retain(|item| !set_listings.contains(item));
+
         // Get the items names from the database based on order_mode
-        if order_mode == OrderMode::Buy || order_mode == OrderMode::Both {
+        if discover && (order_mode == OrderMode::Buy || order_mode == OrderMode::Both) {
             let mut items: Vec<String> = match helper::get_column_values(
                 popular_items_df.clone(),
                 None,
@@ -98,6 +169,7 @@ impl<'a> ItemModule<'a> {
             };
 
             popular_items.append(&mut items);
+            popular_items.retain(|item| !wish_items.contains(item));
 
             if current_buy_orders_df.shape().0 != 0 {
                 current_buy_orders_df = current_buy_orders_df
@@ -136,6 +208,15 @@ impl<'a> ItemModule<'a> {
             .into_iter()
             .chain(popular_items.clone().into_iter())
             .chain(whitelist_items.clone().into_iter())
+            .chain(
+                settings
+                    .stock_item
+                    .market_maker
+                    .keys()
+                    .filter(|_| discover)
+                    .filter(|item| matches!(self.get_market_maker_rule(item), Ok(Some(_))))
+                    .cloned(),
+            )
             .collect::<HashSet<_>>()
             .into_iter()
             .collect::<Vec<_>>();
@@ -148,13 +229,63 @@ impl<'a> ItemModule<'a> {
             Some(self.client.log_file.as_str()),
         );
 
-        let mut current_index = all_interesting_items.len();
-        // Loop through all interesting items
-        for item in all_interesting_items.clone() {
-            if self.client.is_running() == false || item == "" {
+        // Items with my orders go first, then the stock, then new opportunities. Within each the best
+        // scored items and the items left over from a pass that ran out of requests go first.
+        let pending_items = self.client.pending_items.lock()?.clone();
+        let stock_item_set: HashSet<String> = stock_items.iter().cloned().collect();
+        let all_interesting_items = self.client.ranking().get_check_order(
+            all_interesting_items.into_iter().collect(),
+            &popular_items_df,
+            &pending_items,
+            &active_items,
+            &stock_item_set,
+        )?;
+
+        // Stable items are checked every few passes, the best scored items and the items left over
+        // from the last pass always
+        let top_items = settings.stock_item.ranking.top_items.max(0) as usize;
+        let mut due_items = vec![];
+        for (index, item) in all_interesting_items.iter().enumerate() {
+            if item == "" {
                 continue;
             }
-            current_index -= 1;
+            if index < top_items
+                || pending_items.contains(item)
+                || self.client.volatility().is_due(item)?
+            {
+                due_items.push((index, item.clone()));
+            }
+        }
+
+        // The orders of the next items are fetched while the current one is compared,
+        // orders are only changed one item at a time so my orders stay consistent
+        let concurrency = settings.concurrent_item_checks.clamp(1, MAX_CONCURRENT_CHECKS) as usize;
+        let filter = settings.stock_item.live_order_filter.clone();
+        let mut fetched_items = stream::iter(due_items.into_iter().map(|(index, item)| {
+            let wfm = wfm.clone();
+            let filter = filter.clone();
+            async move {
+                let orders = wfm
+                    .orders()
+                    .get_order_book_by_item(&item, &filter.status, filter.min_reputation)
+                    .await;
+                (index, item, orders)
+            }
+        }))
+        .buffered(concurrency);
+
+        // Loop through all interesting items
+        while let Some((index, item, item_order_book)) = fetched_items.next().await {
+            if self.client.is_running() == false {
+                break;
+            }
+            // Stop the pass once the budget is used up, the rest is kept for the next pass
+            *self.client.pending_items.lock()? = all_interesting_items[index..].to_vec();
+            if self.client.is_budget_exhausted()? {
+                return Ok(());
+            }
+            // Counts up like the other progress messages
+            let current_index = index + 1;
 
             logger::info_con(
                 "LiveScraper",
@@ -166,11 +297,41 @@ impl<'a> ItemModule<'a> {
                 )
                 .as_str(),
             );
-            self.client.send_message("item.checking", Some(json!({ "name": item, "count": current_index, "total": all_interesting_items.len()})));
-
-            let item_live_orders_df = wfm.orders().get_ordres_by_item(&item).await?;
+            let phase = CheckPhase::get(&item, &active_items, &stock_item_set);
+            self.client.send_message("item.checking", Some(json!({ "name": item, "count": current_index, "total": all_interesting_items.len(), "phase": phase.as_str()})));
+
+            // Orders for every rank, selling needs one order per owned rank and buying only the highest rank
+            let (item_all_orders_df, item_sell_book) = match item_order_book {
+                Ok(item_order_book) => item_order_book,
+                // Fetched ahead of the budget check, the item waits for the next pass
+                Err(e) if e.kind() == ErrorKind::BudgetExhausted => return Ok(()),
+                Err(e) => return Err(e),
+            };
             // Check if item_orders_df is empty and skip if it is
-            if item_live_orders_df.height() == 0 {
+            if item_all_orders_df.height() == 0 {
+                continue;
+            }
+            let item_live_orders_df = self.get_highest_rank_orders(&item_all_orders_df)?;
+            self.client.volatility().record(&item, &item_live_orders_df)?;
+            let item_live_orders_df = self.get_base_variant_orders(&item, &item_live_orders_df)?;
+            // Market maker items have their own decision path
+            if let Some(rule) = self.get_market_maker_rule(&item)?.filter(|_| discover) {
+                let (item_id, item_rank) = match self.resolve_item(&item)? {
+                    Some(item_info) => item_info,
+                    None => continue,
+                };
+                self.client
+                    .market_maker()
+                    .check_item(
+                        &item,
+                        &item_id,
+                        item_rank,
+                        &rule,
+                        &current_buy_orders_df,
+                        &current_sell_orders_df,
+                        &item_live_orders_df,
+                    )
+                    .await?;
                 continue;
             }
             let item_stats = popular_items_df
@@ -181,21 +342,25 @@ impl<'a> ItemModule<'a> {
                 .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
             // Check if item is in all_interesting_items
             if !popular_items.contains(&item) {
+                if set_listings.contains(&item) {
+                    continue;
+                }
                 logger::info_file(
                     "LiveScraper",
                     format!("Item: {item} is not in all_interesting_items").as_str(),
                     Some(self.client.log_file.as_str()),
                 );
-                let item_info = wfm.items().get_item(item.to_string()).await?;
-
-                let item_id = item_info.id;
-                let item_rank = item_info.items_in_set.get(0).unwrap().mod_max_rank;
+                let (item_id, item_rank) = match self.resolve_item(&item)? {
+                    Some(item_info) => item_info,
+                    None => continue,
+                };
                 self.compare_live_orders_when_selling(
                     &item,
                     &item_id,
                     item_rank,
                     current_sell_orders_df.clone(),
-                    &item_live_orders_df,
+                    &item_all_orders_df,
+                    &item_sell_book,
                     &item_stats,
                     &stock_items_df,
                 )
@@ -231,69 +396,104 @@ impl<'a> ItemModule<'a> {
                 .collect()
                 .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
 
-            if order_mode == OrderMode::Buy || order_mode == OrderMode::Both {
-                self.compare_live_orders_when_buying(
-                    &item,
-                    &item_id,
-                    item_rank,
-                    current_buy_orders_df.clone(),
-                    &item_live_orders_df,
-                    &item_stats,
-                    &stock_items_df,
-                )
-                .await?;
+            if discover && (order_mode == OrderMode::Buy || order_mode == OrderMode::Both) {
+                // Keep the orders placed this pass so the exposure limits count them
+                if let Some(updated_buy_orders_df) = self
+                    .compare_live_orders_when_buying(
+                        &item,
+                        &item_id,
+                        item_rank,
+                        current_buy_orders_df.clone(),
+                        &item_live_orders_df,
+                        &item_stats,
+                        &stock_items_df,
+                    )
+                    .await?
+                {
+                    current_buy_orders_df = updated_buy_orders_df;
+                }
             }
 
-            if order_mode == OrderMode::Sell || order_mode == OrderMode::Both {
+            if (order_mode == OrderMode::Sell || order_mode == OrderMode::Both)
+                && !set_listings.contains(&item)
+            {
                 self.compare_live_orders_when_selling(
                     &item,
                     &item_id,
                     item_rank,
                     current_sell_orders_df.clone(),
-                    &item_live_orders_df,
+                    &item_all_orders_df,
+                    &item_sell_book,
                     &item_stats,
                     &stock_items_df,
                 )
                 .await?;
             }
         }
+        self.client.pending_items.lock()?.clear();
+        self.client.time_to_sell().emit().await?;
         Ok(())
     }
-    fn get_week_increase(&self, df: &DataFrame, row_name: &str) -> Result<f64, AppError> {
-        // Pre-filter DataFrame based on "order_type" == "closed"
-        let week_df = df
-            .clone()
-            .lazy()
-            .filter(
-                col("order_type")
-                    .eq(lit("closed"))
-                    .and(col("name").eq(lit(row_name))),
-            )
-            .collect()
-            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
-
-        // Sort the DataFrame by "datetime" column
-        let week_df = helper::sort_dataframe(week_df, "datetime", true)?;
-
-        // Assuming the filtered DataFrame has at least 7 rows
-        if week_df.height() >= 7 {
-            let avg_price_series = week_df
-                .column("median")
-                .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
-            let avg_price_array = avg_price_series
-                .f64()
-                .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
-            let first_avg_price = avg_price_array.get(0).unwrap(); // Now a f64
-            let last_avg_price = avg_price_array.get(6).unwrap(); // Now a f64
+    // Lists a single stock item right away, used when stock is added while the scraper is stopped
+    pub async fn price_item(&self, item: &str) -> Result<(), AppError> {
+        if self.client.is_running() || self.client.is_maintenance() {
+            return Ok(());
+        }
+        let settings = self.client.settings.lock()?.clone().live_scraper;
+        if settings.stock_item.order_mode == OrderMode::Buy {
+            return Ok(());
+        }
+        *self.client.request_budget.lock()? = settings.api_budget;
+        logger::info_con("LiveScraper", format!("Pricing new stock item: {item}").as_str());
 
-            let change = first_avg_price - last_avg_price;
-            Ok(change)
-        } else {
-            Ok(0.0)
+        let db = self.client.db.lock()?.clone();
+        let wfm = self.client.get_wfm()?;
+        let stock_items_df = db
+            .stock_item()
+            .convet_stock_item_to_datafream(db.stock_item().get_items().await?)?;
+        let (_, current_sell_orders_df) = wfm.orders().get_orders_as_dataframe().await?;
+        let filter = settings.stock_item.live_order_filter.clone();
+        let (item_all_orders_df, item_sell_book) = wfm
+            .orders()
+            .get_order_book_by_item(item, &filter.status, filter.min_reputation)
+            .await?;
+        if item_all_orders_df.height() == 0 {
+            return Ok(());
+        }
+        let (item_id, item_rank) = match self.resolve_item(item)? {
+            Some(item_info) => item_info,
+            None => return Ok(()),
+        };
+        self.compare_live_orders_when_selling(
+            item,
+            &item_id,
+            item_rank,
+            current_sell_orders_df,
+            &item_all_orders_df,
+            &item_sell_book,
+            &DataFrame::default(),
+            &stock_items_df,
+        )
+        .await?;
+        Ok(())
+    }
+    // Id and max rank from the cache, saves a request to Warframe Market per item
+    fn resolve_item(&self, url_name: &str) -> Result<Option<(String, Option<f64>)>, AppError> {
+        let db = self.client.db.lock()?.clone();
+        let cache = db.cache.lock()?.clone();
+        match cache.resolver().by_url_name(url_name)? {
+            Some(item) => Ok(Some((item.id, item.mod_max_rank.map(|rank| rank as f64)))),
+            None => {
+                logger::warning_con(
+                    "LiveScraper",
+                    format!("Item: {url_name} not found in the cache, skipping").as_str(),
+                );
+                Ok(None)
+            }
         }
     }
     pub async fn delete_all_orders(&self, mode: OrderMode) -> Result<(), AppError> {
-        let wfm = self.client.wfm.lock()?.clone();
+        let wfm = self.client.get_wfm()?;
         let settings = self.client.settings.lock()?.clone().live_scraper;
         let blacklist = settings.stock_item.blacklist.clone();
         self.client.send_message(
@@ -311,6 +511,19 @@ impl<'a> ItemModule<'a> {
             orders.append(&mut current_orders.sell_orders);
         }
 
+        // Check if item is in blacklist
+        orders.retain(|order| !blacklist.contains(&order.clone().item.unwrap().url_name));
+
+        // Wish list orders are kept, the wish list would take a missing order as filled
+        let db = self.client.db.lock()?.clone();
+        let wished = db.wish_list().get_order_ids().await?;
+        orders.retain(|order| !wished.contains(&order.id));
+
+        // Stopping halfway leaves the journal behind, to be resumed or rolled back later
+        let batch = db
+            .order_journal()
+            .begin("live_scraper.delete_all_orders", &orders)
+            .await?;
         let mut current_index = 0;
         let total = orders.len();
         self.client.send_message(
@@ -326,12 +539,12 @@ impl<'a> ItemModule<'a> {
             if self.client.is_running() == false {
                 return Ok(());
             }
-            // Check if item is in blacklist
-            if blacklist.contains(&order.clone().item.unwrap().url_name) {
-                continue;
-            }
             match wfm.orders().delete(&order.id).await {
-                Ok(_) => {}
+                Ok(_) => {
+                    db.order_journal()
+                        .set_status(&batch, &order.id, order_journal::DELETED)
+                        .await?;
+                }
                 Err(e) => {
                     error::create_log_file(self.client.log_file.to_owned(), &e);
                     logger::warning_con(
@@ -341,12 +554,13 @@ impl<'a> ItemModule<'a> {
                 }
             };
         }
+        db.order_journal().complete(&batch).await?;
         Ok(())
     }
     pub async fn get_buy_sell_overlap(&self) -> Result<DataFrame, AppError> {
         let settings = self.client.settings.lock()?.clone().live_scraper;
         let db = self.client.db.lock()?.clone();
-        let df = self.client.price_scraper.lock()?.get_price_historys()?;
+        let history = self.client.price_scraper.lock()?.get_price_history()?;
         let volume_threshold = settings.stock_item.volume_threshold;
         let range_threshold = settings.stock_item.range_threshold;
         let avg_price_cap = settings.stock_item.avg_price_cap;
@@ -354,48 +568,22 @@ impl<'a> ItemModule<'a> {
         let strict_whitelist = settings.stock_item.strict_whitelist;
         let whitelist = settings.stock_item.whitelist.clone();
 
-        // Group by the "name" and "order_type" columns, and compute the mean of the other columns
-        let averaged_df = df
-            .clone()
-            .lazy()
-            .groupby(&["name", "order_type"])
-            .agg(&[
-                // List the other columns you want to average
-                col("volume").mean().alias("volume"),
-                col("min_price").mean().alias("min_price"),
-                col("max_price").mean().alias("max_price"),
-                col("range").mean().alias("range"),
-                col("median").mean().alias("median"),
-                col("avg_price").mean().alias("avg_price"),
-                col("mod_rank").mean().alias("mod_rank"),
-                col("item_id").first().alias("item_id"),
-            ])
-            .collect()
-            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
+        // Price data generated before it was stored in the database is imported once
+        if db.item_price().is_empty().await? && history.df.height() > 0 {
+            db.item_price().import_dataframe(&history.df).await?;
+        }
+
         // Call the database to get the inventory names and DataFrame
         let inventory_names = db.stock_item().get_items_names().await?;
-        let inventory_names_s = Series::new("desired_column_name", inventory_names);
-
-        // Filters the DataFrame based on the given predicates and returns a new DataFrame.
-        // The `volume_threshold` and `range_threshold` arguments are used to filter by volume and range.
-        // The `inventory_names_s` argument is used to filter by name.
-        // The `closed` order type is used to filter by order type.
-        let filtered_df = averaged_df
-            .clone()
-            .lazy()
-            .filter(
-                col("order_type").eq(lit("closed")).and(
-                    col("volume")
-                        .gt(lit(volume_threshold))
-                        .and(col("range").gt(lit(range_threshold)))
-                        .or(col("name").is_in(lit(inventory_names_s.clone()))),
-                ),
-            )
-            .collect()
-            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
-
-        // Sort by "range" in descending order
-        let mut filtered_df = helper::sort_dataframe(filtered_df, "range", true)?;
+        let inventory_names_s = Series::new("desired_column_name", inventory_names.clone());
+
+        // Closed averages of the items above the volume and range thresholds and of the inventory,
+        // sorted by range in descending order
+        let mut filtered_df = item_price::convert_averages_to_dataframe(
+            db.item_price()
+                .get_interesting_items(volume_threshold, range_threshold, &inventory_names)
+                .await?,
+        )?;
 
         // If the DataFrame is empty, return an empty DataFrame
         if filtered_df.height() == 0 {
@@ -427,7 +615,7 @@ impl<'a> ItemModule<'a> {
             .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?
             .into_iter()
             .filter_map(|opt_name| {
-                opt_name.map(|name| self.get_week_increase(&df, name).unwrap_or(0.0))
+                opt_name.map(|name| history.get_week_increase(name))
             })
             .collect();
 
@@ -445,15 +633,16 @@ impl<'a> ItemModule<'a> {
                 .collect()
                 .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
         } else {
+            // Cheap enough and not falling in price, or kept no matter what
+            let tradable = col("avg_price")
+                .lt(lit(avg_price_cap))
+                .and(col("weekPriceShift").gt_eq(lit(price_shift_threshold)));
+            let listed = col("name")
+                .is_in(lit(inventory_names_s))
+                .or(col("name").is_in(lit(whitelist_s)));
             filtered_df = filtered_df
                 .lazy()
-                .filter(
-                    col("avg_price")
-                        .lt(lit(avg_price_cap))
-                        .and(col("weekPriceShift").gt_eq(lit(price_shift_threshold)))
-                        .or(col("name").is_in(lit(inventory_names_s)))
-                        .or(col("name").is_in(lit(whitelist_s))),
-                )
+                .filter(tradable.or(listed))
                 .collect()
                 .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
         }
@@ -469,12 +658,9 @@ impl<'a> ItemModule<'a> {
         let unique_names = name_set.into_iter().collect::<Vec<_>>();
 
         let unique_names_series = Series::new("name", unique_names.clone());
-        let df_filtered = averaged_df
-            .clone()
-            .lazy()
-            .filter(col("name").is_in(lit(unique_names_series.clone())))
-            .collect()
-            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
+        let df_filtered = item_price::convert_averages_to_dataframe(
+            db.item_price().get_averages(&unique_names).await?,
+        )?;
 
         // Start the creation of the buy_sell_overlap DataFrame
         let buy_sell_overlap = DataFrame::new(vec![unique_names_series])
@@ -536,9 +722,34 @@ impl<'a> ItemModule<'a> {
             .rename("weekPriceShift", "priceShift")
             .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
 
-        return Ok(buy_sell_overlap.clone());
+        // Replace the average price with the blend of the configured price providers
+        let providers =
+            price_provider::get_providers(&settings.stock_item, history.clone(), self.client.get_wfm()?);
+        let blended = price_provider::get_blended_prices(&providers, &unique_names).await;
+        let names = buy_sell_overlap
+            .column("name")
+            .and_then(|c| c.utf8())
+            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
+        let closed_avgs = buy_sell_overlap
+            .column("closedAvg")
+            .and_then(|c| c.f64())
+            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
+        // Items no provider knows keep the price from the dump
+        let closed_avgs: Vec<Option<f64>> = names
+            .into_iter()
+            .zip(closed_avgs.into_iter())
+            .map(|(name, closed_avg)| {
+                name.and_then(|name| blended.get(name).cloned()).or(closed_avg)
+            })
+            .collect();
+        let buy_sell_overlap = buy_sell_overlap
+            .with_column(Series::new("closedAvg", closed_avgs))
+            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
+
+        // Best scored items first
+        self.client.ranking().rank(buy_sell_overlap.clone())
     }
-    async fn get_my_order_information(
+    pub async fn get_my_order_information(
         &self,
         item_name: &str,
         df: &DataFrame,
@@ -583,7 +794,7 @@ impl<'a> ItemModule<'a> {
         };
         Ok((id.clone(), visibility, price, true))
     }
-    async fn restructure_live_order_df(
+    pub async fn restructure_live_order_df(
         &self,
         item_live_orders_df: &DataFrame,
     ) -> Result<(DataFrame, DataFrame, i64, i64, i64), AppError> {
@@ -644,6 +855,209 @@ impl<'a> ItemModule<'a> {
         Ok((buy_orders_df, sell_orders_df, buyers, sellers, range))
     }
 
+    fn get_depth_price(
+        &self,
+        item_name: &str,
+        sell_book: &[Order],
+        post_price: i64,
+        listed_price: Option<i64>,
+    ) -> Result<i64, AppError> {
+        let rule = self.client.settings.lock()?.live_scraper.stock_item.order_book.clone();
+        if !rule.enabled {
+            return Ok(post_price);
+        }
+        let in_game_name = self.client.auth.lock()?.ingame_name.clone();
+        let wfm = self.client.get_wfm()?;
+        let depth = wfm
+            .orders()
+            .get_sell_depth(sell_book, &in_game_name, rule.range.max(0));
+        if depth.orders == 0 {
+            return Ok(post_price);
+        }
+        if depth.online_sellers == 0 && rule.hold_against_offline {
+            logger::info_con(
+                "LiveScraper",
+                format!("Item {item_name} only has offline sellers near {} platinum, holding the price", depth.lowest_price).as_str(),
+            );
+            return Ok(post_price.max(listed_price.unwrap_or(depth.lowest_price)));
+        }
+        if depth.quantity <= rule.thin_quantity {
+            logger::info_con(
+                "LiveScraper",
+                format!("Item {item_name} has {} listed near {} platinum, undercutting harder", depth.quantity, depth.lowest_price).as_str(),
+            );
+            return Ok((post_price - rule.thin_undercut.max(0)).max(1));
+        }
+        Ok(post_price)
+    }
+
+    // Best price of a friend's order in the sorted live orders, None when friends are not protected
+    fn get_friend_price(&self, live_orders_df: &DataFrame) -> Result<Option<i64>, AppError> {
+        let friends = self.client.settings.lock()?.clone().friends;
+        if !friends.avoid_undercut || friends.names.is_empty() || live_orders_df.height() == 0 {
+            return Ok(None);
+        }
+        let usernames = match helper::get_column_values(
+            live_orders_df.clone(),
+            None,
+            "username",
+            ColumnType::String,
+        )? {
+            ColumnValues::String(values) => values,
+            _ => return Err(AppError::new("LiveScraper", eyre!("Expected string values"))),
+        };
+        let prices = match helper::get_column_values(
+            live_orders_df.clone(),
+            None,
+            "platinum",
+            ColumnType::I64,
+        )? {
+            ColumnValues::I64(values) => values,
+            _ => return Err(AppError::new("LiveScraper", eyre!("Expected i64 values"))),
+        };
+        Ok(usernames
+            .iter()
+            .zip(prices.iter())
+            .find(|(username, _)| friends.is_friend(username))
+            .map(|(_, price)| *price))
+    }
+
+    fn is_market_maker_order(&self, order: &Order) -> bool {
+        match &order.item {
+            Some(item) => matches!(self.get_market_maker_rule(&item.url_name), Ok(Some(_))),
+            None => false,
+        }
+    }
+
+    // Drops the blacklist and whitelist entries that expired before the interesting items are picked
+    fn prune_item_lists(&self) -> Result<(), AppError> {
+        let mut settings = self.client.settings.lock()?;
+        let expired = settings.live_scraper.stock_item.prune_expired_list_entries();
+        if expired.is_empty() {
+            return Ok(());
+        }
+        settings.save_to_file()?;
+        for entry in expired {
+            logger::info_con(
+                "LiveScraper",
+                format!("Removed {} from the {}, it expired", entry.url_name, entry.list)
+                    .as_str(),
+            );
+        }
+        helper::emit_update("settings", "SET", Some(json!(settings.clone())));
+        Ok(())
+    }
+
+    fn is_wish_list_order(&self, order: &Order, wish_items: &HashSet<String>) -> bool {
+        match &order.item {
+            Some(item) => wish_items.contains(&item.url_name),
+            None => false,
+        }
+    }
+
+    // Market maker rule for the item, only while the market maker feature is enabled
+    fn get_market_maker_rule(&self, item_name: &str) -> Result<Option<MarketMakerRule>, AppError> {
+        let settings = self.client.settings.lock()?.clone();
+        let cache = self.client.db.lock()?.cache.lock()?.clone();
+        if !cache.feature_flags().is_enabled(MARKET_MAKER, &settings)? {
+            return Ok(None);
+        }
+        Ok(settings
+            .live_scraper
+            .stock_item
+            .get_market_maker_rule(item_name))
+    }
+
+    // Which cap of the item or of its tags a new buy order would go over, None when it may be placed
+    fn get_exposure_limit(
+        &self,
+        item_name: &str,
+        current_orders: &DataFrame,
+        inventory_df: &DataFrame,
+    ) -> Result<Option<String>, AppError> {
+        let settings = self.client.settings.lock()?.clone().live_scraper.stock_item;
+        if settings.exposure_by_item.is_empty() && settings.exposure_by_category.is_empty() {
+            return Ok(None);
+        }
+        let order_urls = match helper::get_column_values(
+            current_orders.clone(),
+            None,
+            "url_name",
+            ColumnType::String,
+        )? {
+            ColumnValues::String(values) => values,
+            _ => return Err(AppError::new("LiveScraper", eyre!("Expected string values"))),
+        };
+        let stock_urls = match helper::get_column_values(
+            inventory_df.clone(),
+            None,
+            "item_url",
+            ColumnType::String,
+        )? {
+            ColumnValues::String(values) => values,
+            _ => return Err(AppError::new("LiveScraper", eyre!("Expected string values"))),
+        };
+        let stock_owned = match helper::get_column_values(
+            inventory_df.clone(),
+            None,
+            "owned",
+            ColumnType::I32,
+        )? {
+            ColumnValues::I32(values) => values,
+            _ => return Err(AppError::new("LiveScraper", eyre!("Expected i32 values"))),
+        };
+        let stock: Vec<(String, i64)> = stock_urls
+            .into_iter()
+            .zip(stock_owned.into_iter().map(|owned| owned as i64))
+            .collect();
+
+        if let Some(limit) = settings.exposure_by_item.get(item_name) {
+            let buy_orders = order_urls.iter().filter(|url| *url == item_name).count() as i64;
+            let owned = stock
+                .iter()
+                .filter(|(url, _)| url == item_name)
+                .map(|(_, owned)| owned)
+                .sum();
+            if let Some(reason) = limit.get_reason(buy_orders, owned) {
+                return Ok(Some(format!("{item_name} has {reason}")));
+            }
+        }
+
+        let cache = self.client.db.lock()?.cache.lock()?.clone();
+        let mut tags_by_url: HashMap<String, Vec<String>> = HashMap::new();
+        for url in order_urls.iter().chain(stock.iter().map(|(url, _)| url)) {
+            if !tags_by_url.contains_key(url) {
+                let tags = cache.items().find_type(url)?.and_then(|item| item.tags);
+                tags_by_url.insert(url.clone(), tags.unwrap_or_default());
+            }
+        }
+        let item_tags = cache
+            .items()
+            .find_type(item_name)?
+            .and_then(|item| item.tags)
+            .unwrap_or_default();
+        let has_tag = |url: &str, tag: &String| match tags_by_url.get(url) {
+            Some(tags) => tags.contains(tag),
+            None => false,
+        };
+        for tag in item_tags {
+            let limit = match settings.exposure_by_category.get(&tag) {
+                Some(limit) => limit,
+                None => continue,
+            };
+            let buy_orders = order_urls.iter().filter(|url| has_tag(url, &tag)).count() as i64;
+            let owned = stock
+                .iter()
+                .filter(|(url, _)| has_tag(url, &tag))
+                .map(|(_, owned)| owned)
+                .sum();
+            if let Some(reason) = limit.get_reason(buy_orders, owned) {
+                return Ok(Some(format!("{tag} items have {reason}")));
+            }
+        }
+        Ok(None)
+    }
+
     fn is_item_blacklisted(&self, item_name: &str) -> Result<bool, AppError> {
         let settings = self.client.settings.lock()?.clone().live_scraper;
         let blacklist = settings.stock_item.blacklist.clone();
@@ -711,15 +1125,18 @@ impl<'a> ItemModule<'a> {
         item_stats: &DataFrame,
         inventory_df: &DataFrame,
     ) -> Result<Option<DataFrame>, AppError> {
+        // Buy orders are placed for the base variant, e.g. intact relics
+        let sub_type = self.get_base_sub_type(item_name)?;
         // Check if item is blacklisted
         if self.is_item_blacklisted(item_name)? {
             return Ok(None);
         }
 
         let settings = self.client.settings.lock()?.clone().live_scraper;
-        let wfm = self.client.wfm.lock()?.clone();
+        let wfm = self.client.get_wfm()?;
         let mut current_orders = current_orders.clone();
-        let avg_price_cap = settings.stock_item.avg_price_cap;
+        let (_, profile) = self.get_pricing_profile(item_name)?;
+        let avg_price_cap = settings.stock_item.get_avg_price_cap(&profile);
         let max_total_price_cap = settings.stock_item.max_total_price_cap;
         // Get the current orders for the item from the Warframe Market API
         let (order_id, visibility, price, active) = self
@@ -727,7 +1144,7 @@ impl<'a> ItemModule<'a> {
             .await?;
 
         // Get all the live orders for the item from the Warframe Market API
-        let (live_buy_orders_df, _live_sell_orders_df, buyers, sellers, price_range) =
+        let (live_buy_orders_df, live_sell_orders_df, buyers, sellers, price_range) =
             self.restructure_live_order_df(item_live_orders_df).await?;
 
         // Probably don't want to be looking at this item right now if there's literally nobody interested in selling it.
@@ -745,8 +1162,9 @@ impl<'a> ItemModule<'a> {
 
         // If there are no buyers, and the average price is greater than 25p, then we should probably update our listing.
         if buyers == 0 && item_closed_avg > 25.0 {
-            // If the item is worth more than 40p, then we should probably update our listing.
-            let mut post_price = (price_range - 40).max((price_range / 3) - 1);
+            // Without buyers the range is the lowest sell price, open the bidding below it
+            let opening_price = settings.stock_item.opening_bid.get_price(price_range);
+            let mut post_price = self.get_rounded_price(item_name, opening_price)?;
 
             if post_price > avg_price_cap as i64 {
                 logger::info_con("LiveScraper",format!("Item {item_name} is higher than the price cap you set. cap: {avg_price_cap}, post_price: {post_price}").as_str());
@@ -772,12 +1190,27 @@ impl<'a> ItemModule<'a> {
                     .await?;
                 return Ok(None);
             } else {
+                if let Some(reason) = self.get_exposure_limit(item_name, &current_orders, inventory_df)? {
+                    logger::info_con("LiveScraper", format!("Not buying {item_name}, {reason}").as_str());
+                    return Ok(None);
+                }
+                if !self.client.order_budget().reserve(item_name)? {
+                    return Ok(None);
+                }
                 self.client.send_message(
                     "item.buy.creating",
                     Some(json!({ "name": item_name, "price": post_price})),
                 );
                 wfm.orders()
-                    .create(item_id, "buy", post_price, 1, true, item_rank)
+                    .create(
+                        item_id,
+                        "buy",
+                        post_price,
+                        1,
+                        true,
+                        item_rank,
+                        sub_type.as_deref(),
+                    )
                     .await?;
                 logger::info_con("LiveScraper",format!("Automatically Posted Visible Buy Order Item: {item_name}, ItemId: {item_id}, Price: {post_price}").as_str());
                 return Ok(None);
@@ -787,7 +1220,7 @@ impl<'a> ItemModule<'a> {
         }
 
         // Get highest buy order price
-        let post_price: i64 = match helper::get_column_value(
+        let highest_price: i64 = match helper::get_column_value(
             live_buy_orders_df.clone(),
             None,
             "platinum",
@@ -796,6 +1229,46 @@ impl<'a> ItemModule<'a> {
             ColumnValue::I64(values) => values.unwrap_or(0),
             _ => return Err(AppError::new("LiveScraper", eyre!("Expected i64 values"))),
         };
+        // Match or overbid the highest buy order depending on the profile
+        let post_price = profile.get_buy_price(highest_price);
+
+        // Bid below the top buy order when my past purchases show it still fills often enough
+        let fill_probability = self.client.fill_probability();
+        fill_probability
+            .record_snapshot(
+                item_name,
+                post_price,
+                buyers,
+                settings.stock_item.fill_probability_target,
+            )
+            .await?;
+        let post_price = fill_probability
+            .get_bid(
+                item_name,
+                post_price,
+                settings.stock_item.fill_probability_target,
+            )
+            .await?
+            .unwrap_or(post_price);
+        // Don't outbid a friend
+        let post_price = match self.get_friend_price(&live_buy_orders_df)? {
+            Some(friend_price) => post_price.min(friend_price),
+            None => post_price,
+        };
+        let post_price = self.get_rounded_price(item_name, post_price)?;
+        let bid_prices = match helper::get_column_values(
+            live_buy_orders_df.clone(),
+            None,
+            "platinum",
+            ColumnType::I64,
+        )? {
+            ColumnValues::I64(values) => values,
+            _ => return Err(AppError::new("LiveScraper", eyre!("Expected i64 values"))),
+        };
+        self.client
+            .shadow()
+            .compare(item_name, "buy", post_price, &bid_prices)
+            .await?;
 
         // Get the average price of the item from the Warframe Market API
         let closed_avg_metric: f64 =
@@ -839,11 +1312,23 @@ impl<'a> ItemModule<'a> {
                 wfm.orders()
                     .delete(order_id.clone().unwrap().as_str())
                     .await?;
+                self.client.order_budget().release(item_name)?;
             }
             return Ok(None);
         }
         if ((closed_avg_metric as i64) >= 30 && price_range >= 15) || price_range >= 21 {
+            // A seller below the average can be messaged directly once approved
+            self.client.action_queue().add_opportunity(
+                item_name,
+                item_rank,
+                sub_type.clone(),
+                &live_sell_orders_df,
+                item_closed_avg,
+            )?;
             if active {
+                if price != post_price && !self.client.reprice_throttle().allows(item_name, &order_id, price, post_price).await? {
+                    return Ok(None);
+                }
                 if price != post_price {
                     logger::info_con("LiveScraper", format!("Your current posting on this item {item_name} for {price} plat is not a good one. Updating to {post_price} plat.").as_str());
                     self.client.send_message(
@@ -858,14 +1343,22 @@ impl<'a> ItemModule<'a> {
                             visibility,
                         )
                         .await?;
-                    let df = DataFrame::new(vec![
-                        Series::new("url_name", vec![item_name]),
-                        Series::new("platinum", vec![post_price]),
-                        Series::new("potential_profit", vec![(post_price - price)]),
-                    ])
-                    .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
+                    self.client.reprice_throttle().record(&order_id, item_name).await?;
+                    // Only the repriced order changes, the other orders are kept as they are
+                    let is_item = col("url_name").eq(lit(item_name));
                     let updatede = current_orders
-                        .inner_join(&df, ["url_name"], ["url_name"])
+                        .lazy()
+                        .with_columns([
+                            when(is_item.clone())
+                                .then(lit(post_price))
+                                .otherwise(col("platinum"))
+                                .alias("platinum"),
+                            when(is_item)
+                                .then(lit(item_closed_avg - post_price as f64))
+                                .otherwise(col("potential_profit"))
+                                .alias("potential_profit"),
+                        ])
+                        .collect()
                         .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
                     return Ok(Some(updatede));
                 } else {
@@ -966,6 +1459,10 @@ impl<'a> ItemModule<'a> {
                     .collect();
 
                 if selected_item_names.contains(&item_name.to_string()) {
+                    if let Some(reason) = self.get_exposure_limit(item_name, &current_orders, inventory_df)? {
+                        logger::info_con("LiveScraper", format!("Not buying {item_name}, {reason}").as_str());
+                        return Ok(None);
+                    }
                     if !unselected_buy_orders.is_empty() {
                         let unselected_item_names: Vec<String> = unselected_buy_orders
                             .iter()
@@ -1002,6 +1499,7 @@ impl<'a> ItemModule<'a> {
                                 Some(json!({ "name": unselected_item.2})),
                             );
                             wfm.orders().delete(unselected_item.3.as_str()).await?;
+                            self.client.order_budget().release(&unselected_item.2)?;
                             logger::debug_con(
                                 "component",
                                 format!(
@@ -1012,13 +1510,24 @@ impl<'a> ItemModule<'a> {
                             );
                         }
                     }
+                    if !self.client.order_budget().reserve(item_name)? {
+                        return Ok(None);
+                    }
                     self.client.send_message(
                         "item.buy.creating",
                         Some(json!({ "name": item_name, "price": post_price})),
                     );
                     let new_order = wfm
                         .orders()
-                        .create(item_id, "buy", post_price, 1, true, item_rank)
+                        .create(
+                            item_id,
+                            "buy",
+                            post_price,
+                            1,
+                            true,
+                            item_rank,
+                            sub_type.as_deref(),
+                        )
                         .await?;
                     let current_orders =
                         self.get_new_buy_data(current_orders.clone(), new_order, item_closed_avg)?;
@@ -1034,10 +1543,12 @@ impl<'a> ItemModule<'a> {
             wfm.orders()
                 .delete(order_id.clone().unwrap().as_str())
                 .await?;
+            self.client.order_budget().release(item_name)?;
         }
 
         Ok(None)
     }
+    // Keeps one sell order per owned rank and sub type of the item, e.g. a rank 0 and a max rank copy of a mod
     async fn compare_live_orders_when_selling(
         &self,
         item_name: &str,
@@ -1045,80 +1556,312 @@ impl<'a> ItemModule<'a> {
         item_rank: Option<f64>,
         current_orders: DataFrame,
         item_live_orders_df: &DataFrame,
+        item_sell_book: &[Order],
         _item_stats: &DataFrame,
         _inventory_df: &DataFrame,
     ) -> Result<(), AppError> {
-        let wfm = self.client.wfm.lock()?.clone();
+        let wfm = self.client.get_wfm()?;
+        let db = self.client.db.lock()?.clone();
+        // Only items with a max rank have a rank on their orders
+        let is_ranked = item_rank.is_some();
+        let base_sub_type = self.get_base_sub_type(item_name)?;
+
+        // Copies kept for a listed set are not sold on their own
+        let set_pricing = self.client.settings.lock()?.live_scraper.stock_item.set_pricing.clone();
+        let mut set_reserved = if set_pricing.enabled && set_pricing.list_as_set {
+            db.stock_set().get_reserved_parts().await?.get(item_name).copied().unwrap_or(0)
+        } else {
+            0
+        };
+
+        let stock_items: Vec<StockItemStruct> = db
+            .stock_item()
+            .get_items_by_url_name(item_name)
+            .await?
+            .into_iter()
+            .filter(|t| t.hidden == false)
+            .map(|mut t| {
+                // Stock added before sub types were enforced is the base variant
+                if t.sub_type.is_none() {
+                    t.sub_type = base_sub_type.clone();
+                }
+                if t.rank == 0 && set_reserved > 0 {
+                    let taken = set_reserved.min(t.get_sellable() as i64);
+                    t.reserved += taken as i32;
+                    set_reserved -= taken;
+                }
+                t
+            })
+            .filter(|t| t.get_sellable() > 0)
+            .collect();
+
+        let my_orders = current_orders
+            .clone()
+            .lazy()
+            .filter(col("url_name").eq(lit(item_name)))
+            .collect()
+            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
+
+        // Delete the sell orders of ranks that are no longer in stock
+        let mut orphan_filter = lit(true);
+        for stock_item in stock_items.iter() {
+            orphan_filter = orphan_filter.and(self.get_variant_filter(stock_item, is_ranked).not());
+        }
+        let orphan_ids = match helper::get_column_values(
+            my_orders.clone(),
+            Some(orphan_filter),
+            "id",
+            ColumnType::String,
+        )? {
+            ColumnValues::String(values) => values,
+            _ => return Err(AppError::new("LiveScraper", eyre!("Expected string values"))),
+        };
+        for order_id in orphan_ids {
+            self.client
+                .send_message("item.sell.deleting", Some(json!({ "name": item_name})));
+            wfm.orders().delete(&order_id).await?;
+            self.client.order_budget().release(item_name)?;
+            logger::info_con(
+                "LiveScraper",
+                format!("Item {item_name} is not in your inventory. Deleted sell order {order_id}")
+                    .as_str(),
+            );
+        }
+
+        for stock_item in stock_items {
+            let variant_filter = self.get_variant_filter(&stock_item, is_ranked);
+            let variant_orders = my_orders
+                .clone()
+                .lazy()
+                .filter(variant_filter.clone())
+                .collect()
+                .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
+            let variant_live_orders = item_live_orders_df
+                .clone()
+                .lazy()
+                .filter(variant_filter)
+                .collect()
+                .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
+            let rank = if is_ranked {
+                Some(stock_item.rank as f64)
+            } else {
+                None
+            };
+            let variant_sell_book: Vec<Order> = item_sell_book
+                .iter()
+                .filter(|o| !is_ranked || o.mod_rank == Some(stock_item.rank as i64))
+                .filter(|o| stock_item.sub_type.is_none() || o.subtype == stock_item.sub_type)
+                .cloned()
+                .collect();
+            self.compare_variant_when_selling(
+                item_name,
+                item_id,
+                rank,
+                &stock_item,
+                variant_orders,
+                &variant_live_orders,
+                &variant_sell_book,
+            )
+            .await?;
+            self.client
+                .time_to_sell()
+                .record(stock_item.id, &variant_live_orders)
+                .await?;
+        }
+        Ok(())
+    }
+
+    // Matches the orders of the same rank and sub type as the stock item
+    fn get_variant_filter(&self, stock_item: &StockItemStruct, is_ranked: bool) -> Expr {
+        let mut filter = lit(true);
+        if is_ranked {
+            filter = filter.and(col("mod_rank").eq(lit(stock_item.rank as i64)));
+        }
+        // Orders without a sub type only match stock without one
+        filter = match stock_item.sub_type.clone() {
+            Some(sub_type) => filter.and(col("subtype").eq(lit(sub_type))),
+            None => filter.and(col("subtype").is_null()),
+        };
+        filter
+    }
+
+    // Only keep the orders of the highest rank, used when buying
+    fn get_highest_rank_orders(&self, df: &DataFrame) -> Result<DataFrame, AppError> {
+        let max_rank = df
+            .column("mod_rank")
+            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?
+            .i64()
+            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?
+            .max();
+        match max_rank {
+            Some(max_rank) => df
+                .clone()
+                .lazy()
+                .filter(col("mod_rank").eq(lit(max_rank)))
+                .collect()
+                .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string()))),
+            None => Ok(df.clone()),
+        }
+    }
+
+    // First sub type of the item from the cache, None for items without subtypes
+    fn get_base_sub_type(&self, url_name: &str) -> Result<Option<String>, AppError> {
+        let db = self.client.db.lock()?.clone();
+        let cache = db.cache.lock()?.clone();
+        Ok(cache.items().get_subtypes(url_name)?.first().cloned())
+    }
+
+    // Only keep the orders of the base variant so refinements are not priced together, used when buying
+    fn get_base_variant_orders(&self, url_name: &str, df: &DataFrame) -> Result<DataFrame, AppError> {
+        match self.get_base_sub_type(url_name)? {
+            Some(sub_type) => df
+                .clone()
+                .lazy()
+                .filter(col("subtype").eq(lit(sub_type)))
+                .collect()
+                .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string()))),
+            None => Ok(df.clone()),
+        }
+    }
+
+    async fn compare_variant_when_selling(
+        &self,
+        item_name: &str,
+        item_id: &str,
+        item_rank: Option<f64>,
+        stock_item: &StockItemStruct,
+        current_orders: DataFrame,
+        item_live_orders_df: &DataFrame,
+        sell_book: &[Order],
+    ) -> Result<(), AppError> {
+        let wfm = self.client.get_wfm()?;
         let db = self.client.db.lock()?.clone();
+        let sub_type = stock_item.sub_type.clone();
+        // Bulk items carry their bundle terms on the order
+        let note = stock_item.bulk.clone().map(|b| b.0.get_note());
 
         // Get the current orders for the item from the Warframe Market API
         let (order_id, visibility, price, active) = self
             .get_my_order_information(item_name, &current_orders)
             .await?;
 
-        let inventory_names = db.stock_item().get_items_names().await?;
+        // Get all the live orders for the item from the Warframe Market API
+        let (_live_buy_orders_df, live_sell_orders_df, _buyers, sellers, _price_range) =
+            self.restructure_live_order_df(item_live_orders_df).await?;
 
-        if !inventory_names.contains(&item_name.to_string()) && !active {
-            return Ok(());
-        } else if !inventory_names.contains(&item_name.to_string()) {
-            self.client
-                .send_message("item.sell.deleting", Some(json!({ "name": item_name})));
+        // Get the quantity of owned item, reserved copies are never listed.
+        let quantity = stock_item.get_sellable() as i64;
+
+        // Get the minimum price of the item.
+        let mut minimum_price = stock_item.minium_price;
+
+        // Items sold in bundles are only listed once there is a full bundle, and never below the tier price
+        let mut tier_price = None;
+        if let Some(bulk) = stock_item.bulk.clone().map(|b| b.0) {
+            if (quantity as i32) < bulk.min_quantity {
+                if active {
+                    self.client.send_message(
+                        "item.sell.deleting",
+                        Some(json!({ "name": item_name, "rank": stock_item.rank, "sub_type": sub_type})),
+                    );
+                    wfm.orders()
+                        .delete(order_id.clone().unwrap().as_str())
+                        .await?;
+                    self.client.order_budget().release(item_name)?;
+                }
+                if stock_item.status != "inactive" {
+                    db.stock_item()
+                        .update_by_id(
+                            stock_item.id,
+                            None,
+                            None,
+                            None,
+                            Some(-1),
+                            Some("inactive".to_string()),
+                            None,
+                        )
+                        .await?;
+                }
+                return Ok(());
+            }
+            tier_price = bulk.get_unit_price(quantity as i32);
+            if let Some(unit_price) = tier_price {
+                minimum_price = Some(minimum_price.unwrap_or(0).max(unit_price));
+            }
+        }
+
+        // A temporary override replaces the computed price until it expires
+        if let Some(post_price) = self.get_price_override(stock_item)? {
+            let post_price = post_price.max(tier_price.unwrap_or(0) as i64);
+            if active && price != post_price {
+                self.client.send_message(
+                    "item.sell.updating",
+                    Some(json!({ "name": item_name, "rank": stock_item.rank, "sub_type": sub_type, "price": post_price})),
+                );
+                wfm.orders()
+                    .update_with_note(
+                        order_id.clone().unwrap().as_str(),
+                        post_price as i32,
+                        stock_item.get_sellable(),
+                        visibility,
+                        note.as_deref(),
+                    )
+                    .await?;
+            } else if !active {
+                if !self.client.order_budget().reserve(item_name)? {
+                    return Ok(());
+                }
+                self.client.send_message(
+                    "item.sell.creating",
+                    Some(json!({ "name": item_name, "rank": stock_item.rank, "sub_type": sub_type, "price": post_price})),
+                );
+                wfm.orders()
+                    .create_with_note(
+                        item_id,
+                        "sell",
+                        post_price,
+                        stock_item.get_sellable() as i64,
+                        true,
+                        item_rank,
+                        sub_type.as_deref(),
+                        note.as_deref(),
+                    )
+                    .await?;
+            }
             db.stock_item()
-                .update_by_url(
-                    item_name,
+                .update_by_id(
+                    stock_item.id,
                     None,
                     None,
                     None,
-                    Some("to_low_profit".to_string()),
+                    Some(post_price as i32),
+                    Some("live".to_string()),
                     None,
                 )
                 .await?;
-            wfm.orders()
-                .delete(
-                    order_id.clone().unwrap().as_str()
-                )
-                .await?;
-            logger::info_con(
-                "LiveScraper",
-                format!(
-                    "Item {item_name} is not in your inventory. Deleted sell order for {price}"
-                )
-                .as_str(),
-            );
             return Ok(());
         }
 
-        // Get Invantory Data from the database
-        let stock_item = db
-            .stock_item()
-            .get_item_by_url_name(item_name)
-            .await?
-            .unwrap();
-
-        // Get all the live orders for the item from the Warframe Market API
-        let (_live_buy_orders_df, live_sell_orders_df, _buyers, sellers, _price_range) =
-            self.restructure_live_order_df(item_live_orders_df).await?;
+        let (profile_name, profile) = self.get_pricing_profile(item_name)?;
 
         // Get the average price of the item.
         let bought_avg_price =
             (stock_item.price * stock_item.owned as f64 / stock_item.owned as f64) as i64;
 
-        // Get the quantity of owned item.
-        let quantity = stock_item.owned as i64;
-
-        // Get the minimum price of the item.
-        let minimum_price = stock_item.minium_price;
-
-        // If there are no buyers, update order to be 30p above average price
+        // If there are no other sellers, list at the markup of the profile over the bought price
         if sellers == 0 {
-            let mut post_price = (bought_avg_price + 30) as i64;
+            let mut post_price = self.get_rounded_price(
+                item_name,
+                (bought_avg_price + profile.no_sellers_markup) as i64,
+            )?;
             if minimum_price.is_some() && post_price < minimum_price.unwrap() as i64 {
                 post_price = minimum_price.unwrap() as i64;
             }
 
             db.stock_item()
-                .update_by_url(
-                    item_name,
+                .update_by_id(
+                    stock_item.id,
+                    None,
                     None,
                     None,
                     Some(post_price as i32),
@@ -1127,21 +1870,28 @@ impl<'a> ItemModule<'a> {
                 )
                 .await?;
             if active {
-                self.client
-                    .send_message("item.sell.deleting", Some(json!({ "name": item_name})));
+                self.client.send_message(
+                    "item.sell.deleting",
+                    Some(json!({ "name": item_name, "rank": stock_item.rank, "sub_type": sub_type})),
+                );
                 wfm.orders()
-                    .update(
+                    .update_with_note(
                         order_id.clone().unwrap().as_str(),
                         post_price as i32,
                         quantity as i32,
-                        visibility
+                        visibility,
+                        note.as_deref(),
                     )
                     .await?;
                 return Ok(());
             } else {
+                if !self.client.order_budget().reserve(item_name)? {
+                    return Ok(());
+                }
                 wfm.orders()
-                    .create(
-                         item_id, "sell", post_price, quantity, true, item_rank,
+                    .create_with_note(
+                         item_id, "sell", post_price, quantity, true, item_rank, sub_type.as_deref(),
+                         note.as_deref(),
                     )
                     .await?;
                 return Ok(());
@@ -1159,8 +1909,17 @@ impl<'a> ItemModule<'a> {
             _ => return Err(AppError::new("LiveScraper", eyre!("Expected i64 values"))),
         };
 
-        // Get lowest buy order price from the DataFrame of live sell orders
-        let mut post_price = post_prices.get(0).unwrap_or(&0).clone();
+        // Undercut, match or hold above the lowest sell order depending on the profile
+        let mut post_price = profile.get_sell_price(post_prices.get(0).unwrap_or(&0).clone());
+
+        // Undercut harder on a thin book, hold the price when only offline players sell near it
+        let listed_price = if active { Some(price) } else { None };
+        post_price = self.get_depth_price(item_name, sell_book, post_price, listed_price)?;
+
+        // Don't list below a friend
+        if let Some(friend_price) = self.get_friend_price(&live_sell_orders_df)? {
+            post_price = post_price.max(friend_price);
+        }
 
         // Get the profit from the current order
         let profit = post_price - bought_avg_price as i64;
@@ -1169,8 +1928,9 @@ impl<'a> ItemModule<'a> {
             // Only update the database if the item is not already marked as to_low_profit
             if stock_item.status != "to_low_profit" {
                 db.stock_item()
-                    .update_by_url(
-                        item_name,
+                    .update_by_id(
+                        stock_item.id,
+                        None,
                         None,
                         None,
                         Some(-1),
@@ -1184,57 +1944,77 @@ impl<'a> ItemModule<'a> {
                 format!("Item {item_name} is too cheap. Not putting up a sell order.").as_str(),
             );
             if active {
-                self.client
-                    .send_message("item.sell.deleting", Some(json!({ "name": item_name})));
+                self.client.send_message(
+                    "item.sell.deleting",
+                    Some(json!({ "name": item_name, "rank": stock_item.rank, "sub_type": sub_type})),
+                );
                 wfm.orders()
                     .delete(
                         order_id.clone().unwrap().as_str()
                     )
                     .await?;
+                self.client.order_budget().release(item_name)?;
             }
             return Ok(());
         }
 
-        if post_price + 10 > post_price && sellers >= 2 {
-            post_price = (bought_avg_price + 10).max(post_prices.get(0).unwrap_or(&0).clone());
-        } else {
-            post_price = (bought_avg_price + 10).max(post_price);
-        }
+        post_price = (bought_avg_price + profile.min_profit).max(post_price);
+        // Sell ahead of the resupply, the minimum price is still kept
+        post_price = self.get_resupply_price(stock_item, post_price)?;
+        post_price = self.get_rounded_price(item_name, post_price)?;
         if minimum_price.is_some() && post_price < minimum_price.unwrap() as i64 {
             post_price = minimum_price.unwrap() as i64;
         }
+        self.client
+            .shadow()
+            .compare(item_name, "sell", post_price, &post_prices)
+            .await?;
         if active {
+            let (new_price, needs_review) =
+                self.check_listing_age(stock_item, price, post_price).await?;
+            if new_price != post_price {
+                post_price = self.get_rounded_price(item_name, new_price)?;
+            }
+            let status = if needs_review { "needs_review" } else { "live" };
+            if price != post_price && !self.client.reprice_throttle().allows(item_name, &order_id, price, post_price).await? {
+                return Ok(());
+            }
             if price != post_price {
                 self.client.send_message(
                     "item.sell.updating",
-                    Some(json!({ "name": item_name, "price": post_price})),
+                    Some(json!({ "name": item_name, "rank": stock_item.rank, "sub_type": sub_type, "price": post_price})),
                 );
                 wfm.orders()
-                    .update(
+                    .update_with_note(
                         order_id.clone().unwrap().as_str(),
                         post_price as i32,
                         quantity as i32,
-                        visibility
+                        visibility,
+                        note.as_deref(),
                     )
                     .await?;
+                self.client.reprice_throttle().record(&order_id, item_name).await?;
                 db.stock_item()
-                    .update_by_url(
-                        item_name,
+                    .update_by_id(
+                        stock_item.id,
+                        None,
                         None,
                         None,
                         Some(post_price as i32),
-                        Some("live".to_string()),
+                        Some(status.to_string()),
                         None,
                     )
                     .await?;
                 logger::info_con(
                     "LiveScraper",
                     format!(
-                        "Automatically updated order {} for {} from {} to {} plat",
+                        "Automatically updated order {} for {} rank {} from {} to {} plat ({} profile)",
                         order_id.unwrap_or("None".to_string()),
                         item_name,
+                        stock_item.rank,
                         price,
-                        post_price
+                        post_price,
+                        profile_name
                     )
                     .as_str(),
                 );
@@ -1244,18 +2024,23 @@ impl<'a> ItemModule<'a> {
                 return Ok(());
             }
         } else {
+            if !self.client.order_budget().reserve(item_name)? {
+                return Ok(());
+            }
             self.client.send_message(
                 "item.sell.creating",
-                Some(json!({ "name": item_name, "price": post_price})),
+                Some(json!({ "name": item_name, "rank": stock_item.rank, "sub_type": sub_type, "price": post_price})),
             );
             wfm.orders()
-                .create(
-                     item_id, "sell", post_price, quantity, true, item_rank,
+                .create_with_note(
+                     item_id, "sell", post_price, quantity, true, item_rank, sub_type.as_deref(),
+                     note.as_deref(),
                 )
                 .await?;
             db.stock_item()
-                .update_by_url(
-                    item_name,
+                .update_by_id(
+                    stock_item.id,
+                    None,
                     None,
                     None,
                     Some(post_price as i32),
@@ -1263,10 +2048,227 @@ impl<'a> ItemModule<'a> {
                     None,
                 )
                 .await?;
-            logger::info_con("LiveScraper",format!("Automatically Posted Visible Sell Order Item: {item_name}, ItemId: {item_id}, Price: {post_price}").as_str());
+            logger::info_con("LiveScraper",format!("Automatically Posted Visible Sell Order Item: {item_name}, ItemId: {item_id}, Rank: {}, Price: {post_price}", stock_item.rank).as_str());
+        }
+        Ok(())
+    }
+    // The override still follows the minimum price, the reserved copies and the bundle terms of the item
+    pub async fn set_price_override(
+        &self,
+        stock_id: i64,
+        price: i64,
+        hours: i64,
+    ) -> Result<PriceOverride, AppError> {
+        if price <= 0 || hours <= 0 {
+            return Err(AppError::new_with_level(
+                "LiveScraper",
+                eyre!("Price and hours must be greater than 0"),
+                LogLevel::Warning,
+            ));
+        }
+        let db = self.client.db.lock()?.clone();
+        let stock_item = db.stock_item().get_by_id(stock_id).await?.ok_or_else(|| {
+            AppError::new_with_level(
+                "LiveScraper",
+                eyre!("Stock item {} not found", stock_id),
+                LogLevel::Warning,
+            )
+            .with_kind(ErrorKind::NotFound)
+        })?;
+        let quantity = stock_item.get_sellable();
+        let bulk = stock_item.bulk.clone().map(|b| b.0);
+        let min_quantity = bulk.as_ref().map(|b| b.min_quantity).unwrap_or(1);
+        if quantity < min_quantity.max(1) {
+            return Err(AppError::new_with_level(
+                "LiveScraper",
+                eyre!(
+                    "{} has {} copies to sell, at least {} are needed to list it",
+                    stock_item.name,
+                    quantity,
+                    min_quantity.max(1)
+                ),
+                LogLevel::Warning,
+            )
+            .with_kind(ErrorKind::Validation));
+        }
+        let mut price = price;
+        if let Some(minimum_price) = stock_item.minium_price {
+            price = price.max(minimum_price as i64);
+        }
+        if let Some(unit_price) = bulk.and_then(|b| b.get_unit_price(quantity)) {
+            price = price.max(unit_price as i64);
         }
+        let price_override = PriceOverride {
+            price,
+            expires_at: (chrono::Utc::now() + chrono::Duration::hours(hours)).to_rfc3339(),
+        };
+        self.client
+            .price_overrides
+            .lock()?
+            .insert(stock_id, price_override.clone());
+        Ok(price_override)
+    }
+
+    pub fn clear_price_override(&self, stock_id: i64) -> Result<(), AppError> {
+        self.client.price_overrides.lock()?.remove(&stock_id);
         Ok(())
     }
+
+    // Named profile for the item, resolved by url name and then by tag
+    pub fn get_pricing_profile(&self, url_name: &str) -> Result<(String, PricingProfile), AppError> {
+        let settings = self.client.settings.lock()?.clone().live_scraper;
+        let cache = self.client.db.lock()?.cache.lock()?.clone();
+        let tags = cache
+            .items()
+            .find_type(url_name)?
+            .and_then(|item| item.tags)
+            .unwrap_or_default();
+        Ok(settings.stock_item.get_pricing_profile(url_name, &tags))
+    }
+
+    // Returns the override price, expired overrides are removed and the user is notified
+    // Applies the rounding rule of the item's category, the global rule when none matches
+    pub fn get_rounded_price(&self, url_name: &str, post_price: i64) -> Result<i64, AppError> {
+        let settings = self.client.settings.lock()?.clone().live_scraper;
+        let cache = self.client.db.lock()?.cache.lock()?.clone();
+        let tags = cache
+            .items()
+            .find_type(url_name)?
+            .and_then(|item| item.tags)
+            .unwrap_or_default();
+        let rounded = settings.stock_item.get_rounding_rule(&tags).apply(post_price);
+        Ok(rounded.max(1))
+    }
+
+    // Lowers the price linearly over the discount window before the resupply date of the item
+    fn get_resupply_price(&self, stock_item: &StockItemStruct, post_price: i64) -> Result<i64, AppError> {
+        let rule = self.client.settings.lock()?.live_scraper.stock_item.resupply_discount.clone();
+        if rule.days <= 0 || rule.max_discount_percent <= 0 {
+            return Ok(post_price);
+        }
+        let resupply_at = match stock_item.resupply_at.as_deref().and_then(helper::parse_timestamp) {
+            Some(resupply_at) => resupply_at,
+            None => return Ok(post_price),
+        };
+        let window = rule.days as f64 * 86400.0;
+        let seconds_left = (resupply_at - chrono::Utc::now()).num_seconds() as f64;
+        if seconds_left >= window {
+            return Ok(post_price);
+        }
+        let progress = ((window - seconds_left) / window).clamp(0.0, 1.0);
+        let discount = rule.max_discount_percent.min(100) as f64 / 100.0 * progress;
+        let discounted = ((post_price as f64) * (1.0 - discount)).round() as i64;
+        if discounted < post_price {
+            self.client.send_message(
+                "item.sell.resupply_discount",
+                Some(json!({ "name": stock_item.name, "price": discounted, "discount": (discount * 100.0).round() })),
+            );
+        }
+        Ok(discounted.max(1))
+    }
+
+    fn get_price_override(&self, stock_item: &StockItemStruct) -> Result<Option<i64>, AppError> {
+        let price_override = match self.client.price_overrides.lock()?.get(&stock_item.id) {
+            Some(price_override) => price_override.clone(),
+            None => return Ok(None),
+        };
+        let is_expired = match helper::parse_timestamp(&price_override.expires_at) {
+            Some(expires_at) => expires_at <= chrono::Utc::now(),
+            None => true,
+        };
+        if !is_expired {
+            return Ok(Some(price_override.price));
+        }
+        self.clear_price_override(stock_item.id)?;
+        self.client.send_message(
+            "item.sell.override_expired",
+            Some(json!({ "name": stock_item.name, "price": price_override.price})),
+        );
+        self.client.mh.lock()?.show_notification(
+            "Price Override Expired",
+            format!(
+                "{} is back to normal pricing, the override was {} plat",
+                stock_item.name, price_override.price
+            )
+            .as_str(),
+            None,
+            None,
+        );
+        Ok(None)
+    }
+
+    // Flags sell listings whose price has not changed for longer than the configured max age.
+    // Returns the price to post and whether the listing is waiting for a review.
+    async fn check_listing_age(
+        &self,
+        stock_item: &StockItemStruct,
+        price: i64,
+        post_price: i64,
+    ) -> Result<(i64, bool), AppError> {
+        let settings = self.client.settings.lock()?.clone().live_scraper;
+        let db = self.client.db.lock()?.clone();
+        let rule = settings.stock_item.get_listing_age_rule(&stock_item.tags);
+        let under_review = stock_item.status == "needs_review";
+
+        // Never raise a listing that is waiting for a review
+        let mut post_price = if under_review {
+            post_price.min(price)
+        } else {
+            post_price
+        };
+
+        if rule.max_days < 0 || post_price != price || stock_item.listed_at.is_none() {
+            return Ok((post_price, under_review));
+        }
+        let listed_at = match helper::parse_timestamp(&stock_item.listed_at.clone().unwrap()) {
+            Some(listed_at) => listed_at,
+            None => return Ok((post_price, under_review)),
+        };
+        let days = (chrono::Utc::now() - listed_at).num_days();
+        if days < rule.max_days {
+            return Ok((post_price, under_review));
+        }
+
+        if rule.action == ListingAgeAction::Drop {
+            post_price = price - (price * rule.drop_percent / 100).max(1);
+            if let Some(minimum_price) = stock_item.minium_price {
+                post_price = post_price.max(minimum_price as i64);
+            }
+            post_price = post_price.max(1);
+        } else if under_review {
+            return Ok((post_price, true));
+        }
+
+        if !under_review {
+            db.stock_item()
+                .update_by_id(
+                    stock_item.id,
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some("needs_review".to_string()),
+                    None,
+                )
+                .await?;
+        }
+        self.client.send_message(
+            "item.sell.needs_review",
+            Some(json!({ "name": stock_item.url, "days": days, "price": post_price})),
+        );
+        logger::info_con(
+            "LiveScraper",
+            format!(
+                "Item {} has been listed for {} plat for {} days, action: {}",
+                stock_item.url,
+                price,
+                days,
+                rule.action.as_str()
+            )
+            .as_str(),
+        );
+        Ok((post_price, true))
+    }
     fn get_new_buy_data(
         &self,
         mut current_orders: DataFrame,
@@ -0,0 +1,188 @@
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::modules::stock_item::StockItemStruct,
+    enums::{ErrorKind, LogLevel, OrderType},
+    error::AppError,
+    live_scraper::client::LiveScraperClient,
+    settings::WhisperTemplateSettings,
+    structs::Order,
+};
+
+// A buyer to whisper and how many units to sell them
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LiquidationBuyerStruct {
+    pub order_id: String,
+    pub ingame_name: String,
+    pub platinum: i64,
+    pub quantity: i64,
+    // Language of the whisper, from the buyer's locale or region
+    pub language: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LiquidationItemStruct {
+    pub stock_id: i64,
+    pub url: String,
+    pub name: String,
+    pub owned: i64,
+    // Units the buy orders at or above the minimum price can take right now
+    pub sellable: i64,
+    pub minimum_price: i64,
+    pub proceeds: i64,
+    pub buyers: Vec<LiquidationBuyerStruct>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LiquidationPlanStruct {
+    pub items: Vec<LiquidationItemStruct>,
+    pub total_proceeds: i64,
+}
+
+pub struct LiquidationModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> LiquidationModule<'a> {
+    // Highest buy orders for the selected stock items, filled until the owned quantity is sold.
+    // min_price is used for items without a minimum price of their own.
+    pub async fn get_suggestions(
+        &self,
+        stock_ids: Vec<i64>,
+        min_price: Option<i64>,
+    ) -> Result<LiquidationPlanStruct, AppError> {
+        let db = self.client.db.lock()?.clone();
+        // Asked for by the user, not limited by the live scraper's request budget
+        let wfm = self.client.wfm.lock()?.clone();
+        let ingame_name = self.client.auth.lock()?.ingame_name.clone();
+        let whisper_templates = self.client.settings.lock()?.whisper_templates.clone();
+
+        let mut plan = LiquidationPlanStruct {
+            items: vec![],
+            total_proceeds: 0,
+        };
+        for stock_id in stock_ids {
+            let stock_item = db.stock_item().get_by_id(stock_id).await?.ok_or_else(|| {
+                AppError::new_with_level(
+                    "Liquidation",
+                    eyre!("Stock item {} not found", stock_id),
+                    LogLevel::Warning,
+                )
+                .with_kind(ErrorKind::NotFound)
+            })?;
+            let orders = wfm
+                .orders()
+                .get_ingame_orders_by_item(&stock_item.url)
+                .await?;
+            let minimum_price = stock_item
+                .minium_price
+                .map(|price| price as i64)
+                .or(min_price)
+                .unwrap_or(0);
+            let item = Self::plan_item(
+                &stock_item,
+                orders,
+                minimum_price,
+                &ingame_name,
+                &whisper_templates,
+            );
+            plan.total_proceeds += item.proceeds;
+            plan.items.push(item);
+        }
+        Ok(plan)
+    }
+
+    fn plan_item(
+        stock_item: &StockItemStruct,
+        orders: Vec<Order>,
+        minimum_price: i64,
+        ingame_name: &str,
+        whisper_templates: &WhisperTemplateSettings,
+    ) -> LiquidationItemStruct {
+        let mut buy_orders: Vec<Order> = orders
+            .into_iter()
+            .filter(|order| order.order_type == OrderType::Buy)
+            .filter(|order| order.platinum >= minimum_price)
+            .filter(|order| order.mod_rank.unwrap_or(0) == stock_item.rank as i64)
+            .filter(|order| stock_item.sub_type.is_none() || order.subtype == stock_item.sub_type)
+            .filter(|order| {
+                order
+                    .user
+                    .as_ref()
+                    .map_or(false, |user| user.ingame_name != ingame_name)
+            })
+            .collect();
+        buy_orders.sort_by(|a, b| b.platinum.cmp(&a.platinum));
+
+        let owned = stock_item.owned as i64;
+        // Reserved copies are kept out of the liquidation
+        let available = stock_item.get_sellable() as i64;
+        let mut remaining = available;
+        let mut buyers = vec![];
+        for order in buy_orders {
+            if remaining <= 0 {
+                break;
+            }
+            let quantity = order.quantity.min(remaining);
+            remaining -= quantity;
+            let locale = order.user.as_ref().and_then(|user| user.locale.clone());
+            let language = whisper_templates.get_language(
+                &[locale.as_deref(), Some(order.region.as_str())]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<&str>>(),
+            );
+            let buyer = order.user.map(|user| user.ingame_name).unwrap_or_default();
+            buyers.push(LiquidationBuyerStruct {
+                message: Self::get_whisper(
+                    whisper_templates,
+                    &language,
+                    &buyer,
+                    stock_item,
+                    order.platinum,
+                    quantity,
+                ),
+                language,
+                order_id: order.id,
+                ingame_name: buyer,
+                platinum: order.platinum,
+                quantity,
+            });
+        }
+        LiquidationItemStruct {
+            stock_id: stock_item.id,
+            url: stock_item.url.clone(),
+            name: stock_item.name.clone(),
+            owned,
+            sellable: available - remaining,
+            minimum_price,
+            proceeds: buyers.iter().map(|b| b.platinum * b.quantity).sum(),
+            buyers,
+        }
+    }
+
+    fn get_whisper(
+        whisper_templates: &WhisperTemplateSettings,
+        language: &str,
+        buyer: &str,
+        stock_item: &StockItemStruct,
+        platinum: i64,
+        quantity: i64,
+    ) -> String {
+        let mut name = stock_item.name.clone();
+        if stock_item.rank > 0 {
+            name = format!("{} (rank {})", name, stock_item.rank);
+        }
+        if let Some(sub_type) = &stock_item.sub_type {
+            name = format!("{} ({})", name, sub_type);
+        }
+        let quantity = if quantity > 1 {
+            format!("{} x ", quantity)
+        } else {
+            "".to_string()
+        };
+        whisper_templates.render(language, buyer, &quantity, &name, platinum)
+    }
+}
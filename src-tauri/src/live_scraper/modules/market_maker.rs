@@ -0,0 +1,194 @@
+use crate::{
+    error::AppError,
+    helper::{self, ColumnType, ColumnValue},
+    live_scraper::client::LiveScraperClient,
+    logger,
+    settings::MarketMakerRule,
+};
+use eyre::eyre;
+use polars::prelude::*;
+use serde_json::json;
+
+pub struct MarketMakerModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> MarketMakerModule<'a> {
+    // Keeps a buy order below and a sell order above the mid price of the item.
+    // Fills are picked up through the stock, which the log parser and manual sales keep in sync.
+    pub async fn check_item(
+        &self,
+        item_name: &str,
+        item_id: &str,
+        item_rank: Option<f64>,
+        rule: &MarketMakerRule,
+        current_buy_orders: &DataFrame,
+        current_sell_orders: &DataFrame,
+        item_live_orders_df: &DataFrame,
+    ) -> Result<(), AppError> {
+        let db = self.client.db.lock()?.clone();
+
+        let (live_buy_orders_df, live_sell_orders_df, buyers, sellers, _price_range) = self
+            .client
+            .item()
+            .restructure_live_order_df(item_live_orders_df)
+            .await?;
+
+        // Without both sides of the market there is no mid price to quote around
+        if buyers == 0 || sellers == 0 {
+            logger::info_con(
+                "MarketMaker",
+                format!("Item {item_name} has no buyers or sellers, skipping").as_str(),
+            );
+            return Ok(());
+        }
+        let highest_buy = self.get_top_price(&live_buy_orders_df)?;
+        let lowest_sell = self.get_top_price(&live_sell_orders_df)?;
+        let mid_price = (highest_buy + lowest_sell) / 2;
+
+        let stock_item = db.stock_item().get_item_by_url_name(item_name).await?;
+        let owned = stock_item.clone().map(|s| s.owned as i64).unwrap_or(0);
+        let sellable = stock_item
+            .clone()
+            .map(|s| s.get_sellable() as i64)
+            .unwrap_or(0);
+
+        let buy_price = (mid_price - rule.buy_spread).max(1);
+        let mut sell_price = (mid_price + rule.sell_spread).max(buy_price + 1);
+        if let Some(stock_item) = stock_item.clone() {
+            // Never sell below what was paid or the minimum price
+            sell_price = sell_price.max(stock_item.price as i64 + 1);
+            if let Some(minimum_price) = stock_item.minium_price {
+                sell_price = sell_price.max(minimum_price as i64);
+            }
+        }
+
+        // Buy side, stop buying once the inventory limit is reached
+        let buy_quantity = if owned >= rule.max_owned { 0 } else { 1 };
+        self.sync_order(
+            item_name,
+            item_id,
+            item_rank,
+            "buy",
+            buy_price,
+            buy_quantity,
+            current_buy_orders,
+        )
+        .await?;
+
+        // Sell side, list everything that is owned and not reserved
+        self.sync_order(
+            item_name,
+            item_id,
+            item_rank,
+            "sell",
+            sell_price,
+            sellable,
+            current_sell_orders,
+        )
+        .await?;
+        if sellable > 0 {
+            db.stock_item()
+                .update_by_url(
+                    item_name,
+                    None,
+                    None,
+                    Some(sell_price as i32),
+                    Some("live".to_string()),
+                    None,
+                )
+                .await?;
+        }
+        logger::info_con(
+            "MarketMaker",
+            format!(
+                "Item {item_name} mid: {mid_price}, buy: {buy_price}, sell: {sell_price}, owned: {owned}"
+            )
+            .as_str(),
+        );
+        Ok(())
+    }
+
+    // Creates, updates or deletes my order so it matches the wanted price and quantity
+    async fn sync_order(
+        &self,
+        item_name: &str,
+        item_id: &str,
+        item_rank: Option<f64>,
+        order_type: &str,
+        price: i64,
+        quantity: i64,
+        current_orders: &DataFrame,
+    ) -> Result<(), AppError> {
+        let wfm = self.client.get_wfm()?;
+        let (order_id, visibility, current_price, active) = self
+            .client
+            .item()
+            .get_my_order_information(item_name, current_orders)
+            .await?;
+
+        if quantity <= 0 {
+            if active {
+                self.client.send_message(
+                    format!("item.{}.deleting", order_type).as_str(),
+                    Some(json!({ "name": item_name})),
+                );
+                wfm.orders()
+                    .delete(order_id.clone().unwrap().as_str())
+                    .await?;
+                self.client.order_budget().release(item_name)?;
+            }
+            return Ok(());
+        }
+
+        if !active {
+            if !self.client.order_budget().reserve(item_name)? {
+                return Ok(());
+            }
+            self.client.send_message(
+                format!("item.{}.creating", order_type).as_str(),
+                Some(json!({ "name": item_name, "price": price})),
+            );
+            wfm.orders()
+                .create(item_id, order_type, price, quantity, true, item_rank, None)
+                .await?;
+            return Ok(());
+        }
+
+        let current_quantity = self.get_order_quantity(item_name, current_orders)?;
+        if current_price != price || current_quantity != quantity {
+            self.client.send_message(
+                format!("item.{}.updating", order_type).as_str(),
+                Some(json!({ "name": item_name, "price": price})),
+            );
+            wfm.orders()
+                .update(
+                    order_id.clone().unwrap().as_str(),
+                    price as i32,
+                    quantity as i32,
+                    visibility,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    fn get_top_price(&self, df: &DataFrame) -> Result<i64, AppError> {
+        match helper::get_column_value(df.clone(), None, "platinum", ColumnType::I64)? {
+            ColumnValue::I64(values) => Ok(values.unwrap_or(0)),
+            _ => Err(AppError::new("MarketMaker", eyre!("Expected i64 values"))),
+        }
+    }
+
+    fn get_order_quantity(&self, item_name: &str, df: &DataFrame) -> Result<i64, AppError> {
+        match helper::get_column_value(
+            df.clone(),
+            Some(col("url_name").eq(lit(item_name))),
+            "quantity",
+            ColumnType::I64,
+        )? {
+            ColumnValue::I64(values) => Ok(values.unwrap_or(0)),
+            _ => Err(AppError::new("MarketMaker", eyre!("Expected i64 values"))),
+        }
+    }
+}
@@ -1,3 +1,19 @@
+pub mod action_queue;
+pub mod baro;
+pub mod fill_probability;
 pub mod helper;
 pub mod item;
-pub mod riven;
\ No newline at end of file
+pub mod liquidation;
+pub mod market_maker;
+pub mod order_budget;
+pub mod partial_sale;
+pub mod ranking;
+pub mod reprice_throttle;
+pub mod riven;
+pub mod schedule;
+pub mod set;
+pub mod shadow;
+pub mod supervisor;
+pub mod time_to_sell;
+pub mod volatility;
+pub mod wish_list;
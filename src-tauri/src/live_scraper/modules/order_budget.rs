@@ -0,0 +1,172 @@
+use serde_json::json;
+
+use crate::{error::AppError, live_scraper::client::LiveScraperClient, logger, structs::Order};
+
+// Riven auctions are counted under this category
+pub const RIVEN_CATEGORY: &str = "riven";
+
+pub struct OrderBudgetModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> OrderBudgetModule<'a> {
+    fn get_tags(&self, url_name: &str) -> Result<Vec<String>, AppError> {
+        let cache = self.client.db.lock()?.cache.lock()?.clone();
+        Ok(cache
+            .items()
+            .find_type(url_name)?
+            .and_then(|item| item.tags)
+            .unwrap_or_default())
+    }
+
+    fn get_order_tags(order: &Order) -> Vec<String> {
+        order
+            .item
+            .as_ref()
+            .map(|item| item.tags.clone())
+            .unwrap_or_default()
+    }
+
+    // Counts my item orders, the riven auctions are counted by the riven module
+    pub fn load_orders(&self, orders: &Vec<Order>) -> Result<(), AppError> {
+        let mut budget = self.client.order_budget.lock()?;
+        budget.orders.clear();
+        budget.total_orders = orders.len() as i64;
+        for order in orders {
+            for tag in Self::get_order_tags(order) {
+                *budget.orders.entry(tag).or_insert(0) += 1;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load_auctions(&self, count: i64) -> Result<(), AppError> {
+        self.client.order_budget.lock()?.auctions = count;
+        Ok(())
+    }
+
+    // Takes a slot for a new order of the item, false when the global cap or one of its categories is full
+    pub fn reserve(&self, url_name: &str) -> Result<bool, AppError> {
+        let settings = self.client.settings.lock()?.clone().live_scraper;
+        let tags = self.get_tags(url_name)?;
+        let mut budget = self.client.order_budget.lock()?;
+
+        let mut full: Option<(String, i64)> = None;
+        if settings.max_orders >= 0 && budget.total_orders >= settings.max_orders {
+            full = Some(("total".to_string(), settings.max_orders));
+        }
+        for tag in tags.iter() {
+            if full.is_some() {
+                break;
+            }
+            let cap = settings.get_category_order_cap(tag);
+            if cap >= 0 && budget.get_count(tag) >= cap {
+                full = Some((tag.clone(), cap));
+            }
+        }
+        if let Some((category, cap)) = full {
+            *budget.skipped.entry(category.clone()).or_insert(0) += 1;
+            drop(budget);
+            logger::info_file(
+                "LiveScraper",
+                format!(
+                    "Order for {} not created, the {} cap of {} orders is reached",
+                    url_name, category, cap
+                )
+                .as_str(),
+                Some(self.client.log_file.as_str()),
+            );
+            self.client.send_message(
+                "item.order_cap_reached",
+                Some(json!({ "name": url_name, "category": category, "cap": cap })),
+            );
+            return Ok(false);
+        }
+
+        budget.total_orders += 1;
+        for tag in tags {
+            *budget.orders.entry(tag).or_insert(0) += 1;
+        }
+        Ok(true)
+    }
+
+    // Frees the slot of a deleted order so it can be used in the same pass
+    pub fn release(&self, url_name: &str) -> Result<(), AppError> {
+        let tags = self.get_tags(url_name)?;
+        let mut budget = self.client.order_budget.lock()?;
+        budget.total_orders = (budget.total_orders - 1).max(0);
+        for tag in tags {
+            if let Some(count) = budget.orders.get_mut(&tag) {
+                *count = (*count - 1).max(0);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn release_order(&self, order: &Order) -> Result<(), AppError> {
+        let mut budget = self.client.order_budget.lock()?;
+        budget.total_orders = (budget.total_orders - 1).max(0);
+        for tag in Self::get_order_tags(order) {
+            if let Some(count) = budget.orders.get_mut(&tag) {
+                *count = (*count - 1).max(0);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn reserve_auction(&self, weapon_url: &str) -> Result<bool, AppError> {
+        let settings = self.client.settings.lock()?.clone().live_scraper;
+        let cap = settings.get_category_order_cap(RIVEN_CATEGORY);
+        let mut budget = self.client.order_budget.lock()?;
+        if cap >= 0 && budget.get_count(RIVEN_CATEGORY) >= cap {
+            *budget
+                .skipped
+                .entry(RIVEN_CATEGORY.to_string())
+                .or_insert(0) += 1;
+            drop(budget);
+            logger::info_file(
+                "LiveScraper",
+                format!(
+                    "Auction for {} not created, the {} cap of {} orders is reached",
+                    weapon_url, RIVEN_CATEGORY, cap
+                )
+                .as_str(),
+                Some(self.client.log_file.as_str()),
+            );
+            self.client.send_message(
+                "riven.order_cap_reached",
+                Some(json!({ "name": weapon_url, "cap": cap })),
+            );
+            return Ok(false);
+        }
+        budget.auctions += 1;
+        Ok(true)
+    }
+
+    pub fn release_auction(&self) -> Result<(), AppError> {
+        let mut budget = self.client.order_budget.lock()?;
+        budget.auctions = (budget.auctions - 1).max(0);
+        Ok(())
+    }
+
+    // Written to the run log at the end of every pass
+    pub fn report(&self) -> Result<(), AppError> {
+        let budget = self.client.order_budget.lock()?.clone();
+        logger::info_file(
+            "LiveScraper",
+            format!(
+                "Order budget: {} orders, {} auctions, by category {:?}, not created {:?}",
+                budget.total_orders, budget.auctions, budget.orders, budget.skipped
+            )
+            .as_str(),
+            Some(self.client.log_file.as_str()),
+        );
+        if !budget.skipped.is_empty() {
+            self.client.send_message(
+                "order_budget.skipped",
+                Some(json!({ "skipped": budget.skipped })),
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::{HashMap, HashSet};
+
+use eyre::eyre;
+use serde_json::json;
+
+use crate::{
+    database::client::DBClient,
+    enums::{LogLevel, PartialSaleMode},
+    error::AppError,
+    helper,
+    live_scraper::client::{LiveScraperClient, PartialSale},
+    logger,
+    structs::Order,
+};
+
+pub struct PartialSaleModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> PartialSaleModule<'a> {
+    // Compares my sell orders with the last pass and records units sold on Warframe Market.
+    // Sales already recorded by the log parser or by hand lower the stock first, so they are skipped.
+    pub async fn check_orders(&self, sell_orders: &Vec<Order>) -> Result<(), AppError> {
+        let settings = self.client.settings.lock()?.clone().live_scraper;
+        let db = self.client.db.lock()?.clone();
+        let mode = settings.stock_item.partial_sale_mode;
+        let closed_mode = settings.stock_item.closed_order_mode;
+        // A snapshot, the wish list checks the same set for its buy orders
+        let deleted_orders = self.client.wfm.lock()?.deleted_orders.clone();
+        let deleted: HashSet<String> = deleted_orders.lock()?.clone();
+
+        let previous = self.client.sell_orders.lock()?.clone();
+        // Only the sell orders of the last pass are handled here
+        deleted_orders
+            .lock()?
+            .retain(|id| !previous.contains_key(id));
+        let mut current: HashMap<String, Order> = HashMap::new();
+        for order in sell_orders {
+            current.insert(order.id.clone(), order.clone());
+        }
+        *self.client.sell_orders.lock()? = current.clone();
+
+        if mode != PartialSaleMode::Off {
+            for order in sell_orders {
+                let previous_quantity = match previous.get(&order.id) {
+                    Some(previous) => previous.quantity,
+                    None => continue,
+                };
+                if order.quantity >= previous_quantity {
+                    continue;
+                }
+                self.detect_sale(
+                    &db,
+                    order,
+                    previous_quantity - order.quantity,
+                    order.quantity,
+                    &mode,
+                    false,
+                )
+                .await?;
+            }
+        }
+
+        // Orders closed by hand after selling in game, the ones the app deleted itself are expected
+        if closed_mode != PartialSaleMode::Off {
+            for (order_id, order) in previous.iter() {
+                if current.contains_key(order_id) || deleted.contains(order_id) {
+                    continue;
+                }
+                self.detect_sale(&db, order, order.quantity, 0, &closed_mode, true)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    // Records or queues the units of an order that were sold, limited to what is still in stock
+    async fn detect_sale(
+        &self,
+        db: &DBClient,
+        order: &Order,
+        sold: i64,
+        remaining: i64,
+        mode: &PartialSaleMode,
+        order_closed: bool,
+    ) -> Result<(), AppError> {
+        let url_name = match order.item.as_ref() {
+            Some(item) => item.url_name.clone(),
+            None => return Ok(()),
+        };
+        let rank = order.mod_rank.unwrap_or(0) as i32;
+        let stock_item = match db
+            .stock_item()
+            .get_item_by_variant(&url_name, rank, order.subtype.as_deref())
+            .await?
+        {
+            Some(stock_item) => stock_item,
+            None => return Ok(()),
+        };
+        let quantity = sold.min(stock_item.get_sellable() as i64 - remaining);
+        if quantity <= 0 {
+            return Ok(());
+        }
+
+        let sale = PartialSale {
+            order_id: order.id.clone(),
+            stock_id: stock_item.id,
+            url_name: url_name.clone(),
+            rank,
+            sub_type: order.subtype.clone(),
+            quantity,
+            price: order.platinum,
+            detected_at: helper::get_utc_timestamp(),
+            order_closed,
+        };
+        if *mode == PartialSaleMode::Auto {
+            return self.record(&sale).await;
+        }
+
+        self.client
+            .partial_sales
+            .lock()?
+            .insert(sale.order_id.clone(), sale.clone());
+        helper::emit_update("PartialSales", "CREATE_OR_UPDATE", Some(json!(sale)));
+        let message = if order_closed {
+            format!(
+                "{} x{} is no longer listed on Warframe Market, confirm to record it as sold for {} plat each",
+                stock_item.name, quantity, order.platinum
+            )
+        } else {
+            format!(
+                "{} x{} sold on Warframe Market for {} plat each, confirm to record it",
+                stock_item.name, quantity, order.platinum
+            )
+        };
+        self.client
+            .mh
+            .lock()?
+            .show_notification("Partial Sale", message.as_str(), None, None);
+        Ok(())
+    }
+
+    // Lowers the stock and creates a sell transaction at the listed price
+    async fn record(&self, sale: &PartialSale) -> Result<(), AppError> {
+        let db = self.client.db.lock()?.clone();
+        let stock_item = db
+            .stock_item()
+            .sell_item(sale.stock_id, sale.quantity as i32)
+            .await?;
+        if stock_item.owned <= 0 {
+            db.stock_item().emit("DELETE", json!(stock_item.clone()));
+        } else {
+            db.stock_item()
+                .emit("CREATE_OR_UPDATE", json!(stock_item.clone()));
+        }
+        db.transaction()
+            .create(
+                &sale.url_name,
+                "item",
+                "sell",
+                sale.quantity as i32,
+                (sale.price * sale.quantity) as i32,
+                sale.rank,
+                Some(json!({
+                    "partial_sale": {
+                        "order_id": sale.order_id,
+                        "unit_price": sale.price,
+                        "order_closed": sale.order_closed,
+                    }
+                })),
+            )
+            .await?;
+        self.client.send_message(
+            "item.sell.partial_sale",
+            Some(json!({ "name": stock_item.name, "quantity": sale.quantity, "price": sale.price})),
+        );
+        logger::info_con(
+            "PartialSale",
+            format!(
+                "Recorded sale of {} x{} for {} plat each",
+                sale.url_name, sale.quantity, sale.price
+            )
+            .as_str(),
+        );
+        Ok(())
+    }
+
+    pub fn get_pending(&self) -> Result<Vec<PartialSale>, AppError> {
+        Ok(self
+            .client
+            .partial_sales
+            .lock()?
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    pub async fn confirm(&self, order_id: &str) -> Result<(), AppError> {
+        let sale = match self.client.partial_sales.lock()?.remove(order_id) {
+            Some(sale) => sale,
+            None => {
+                return Err(AppError::new_with_level(
+                    "PartialSale",
+                    eyre!("No partial sale found for order {}", order_id),
+                    LogLevel::Warning,
+                ))
+            }
+        };
+        helper::emit_update("PartialSales", "DELETE", Some(json!(sale)));
+        self.record(&sale).await
+    }
+
+    pub fn dismiss(&self, order_id: &str) -> Result<(), AppError> {
+        if let Some(sale) = self.client.partial_sales.lock()?.remove(order_id) {
+            helper::emit_update("PartialSales", "DELETE", Some(json!(sale)));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::{HashMap, HashSet};
+
+use eyre::eyre;
+use polars::prelude::*;
+
+use crate::{
+    error::AppError, helper, live_scraper::client::LiveScraperClient, logger, settings::RankingRule,
+};
+
+// The numbers of an interesting item the factors score on
+#[derive(Clone, Debug)]
+pub struct RankedItem {
+    pub name: String,
+    pub volume: f64,
+    pub range: f64,
+    pub price_shift: f64,
+    // Plat between the best buy and sell order, 0 when they overlap
+    pub spread: f64,
+}
+
+// One part of the score of an item, higher values rank the item higher
+pub trait ScoreFactor: Send + Sync {
+    fn name(&self) -> &str;
+    fn weight(&self, rule: &RankingRule) -> f64;
+    fn value(&self, item: &RankedItem) -> f64;
+}
+
+pub struct VolumeFactor;
+
+impl ScoreFactor for VolumeFactor {
+    fn name(&self) -> &str {
+        "volume"
+    }
+    fn weight(&self, rule: &RankingRule) -> f64 {
+        rule.volume_weight
+    }
+    fn value(&self, item: &RankedItem) -> f64 {
+        item.volume
+    }
+}
+
+pub struct RangeFactor;
+
+impl ScoreFactor for RangeFactor {
+    fn name(&self) -> &str {
+        "range"
+    }
+    fn weight(&self, rule: &RankingRule) -> f64 {
+        rule.range_weight
+    }
+    fn value(&self, item: &RankedItem) -> f64 {
+        item.range
+    }
+}
+
+pub struct PriceShiftFactor;
+
+impl ScoreFactor for PriceShiftFactor {
+    fn name(&self) -> &str {
+        "price_shift"
+    }
+    fn weight(&self, rule: &RankingRule) -> f64 {
+        rule.price_shift_weight
+    }
+    fn value(&self, item: &RankedItem) -> f64 {
+        item.price_shift
+    }
+}
+
+pub struct SpreadFactor;
+
+impl ScoreFactor for SpreadFactor {
+    fn name(&self) -> &str {
+        "spread"
+    }
+    fn weight(&self, rule: &RankingRule) -> f64 {
+        rule.spread_weight
+    }
+    fn value(&self, item: &RankedItem) -> f64 {
+        item.spread
+    }
+}
+
+pub fn get_factors() -> Vec<Box<dyn ScoreFactor>> {
+    vec![
+        Box::new(VolumeFactor),
+        Box::new(RangeFactor),
+        Box::new(PriceShiftFactor),
+        Box::new(SpreadFactor),
+    ]
+}
+
+// Scores keyed by name. Each factor is scaled by its highest value so the weights stay comparable.
+pub fn score_items(
+    items: &[RankedItem],
+    factors: &[Box<dyn ScoreFactor>],
+    rule: &RankingRule,
+) -> HashMap<String, f64> {
+    let mut scores: HashMap<String, f64> =
+        items.iter().map(|item| (item.name.clone(), 0.0)).collect();
+    for factor in factors {
+        let weight = factor.weight(rule);
+        let max = items
+            .iter()
+            .map(|item| factor.value(item).max(0.0))
+            .fold(0.0, f64::max);
+        if weight == 0.0 || max <= 0.0 {
+            continue;
+        }
+        for item in items {
+            if let Some(score) = scores.get_mut(&item.name) {
+                *score += weight * factor.value(item).max(0.0) / max;
+            }
+        }
+    }
+    scores
+}
+
+pub struct RankingModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> RankingModule<'a> {
+    fn get_f64_column(df: &DataFrame, column: &str) -> Result<Vec<f64>, AppError> {
+        Ok(df
+            .column(column)
+            .and_then(|c| c.cast(&DataType::Float64))
+            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?
+            .f64()
+            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?
+            .into_iter()
+            .map(|value| value.unwrap_or(0.0))
+            .collect())
+    }
+
+    // Adds a score column to the buy and sell overlap and sorts it by the score, best first
+    pub fn rank(&self, mut df: DataFrame) -> Result<DataFrame, AppError> {
+        let rule = self
+            .client
+            .settings
+            .lock()?
+            .live_scraper
+            .stock_item
+            .ranking
+            .clone();
+        let names: Vec<String> = df
+            .column("name")
+            .and_then(|c| c.utf8())
+            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?
+            .into_iter()
+            .map(|name| name.unwrap_or_default().to_string())
+            .collect();
+        let volumes = Self::get_f64_column(&df, "closedVol")?;
+        let mins = Self::get_f64_column(&df, "closedMin")?;
+        let maxs = Self::get_f64_column(&df, "closedMax")?;
+        let price_shifts = Self::get_f64_column(&df, "priceShift")?;
+        let overlaps = Self::get_f64_column(&df, "overlap")?;
+
+        let items: Vec<RankedItem> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| RankedItem {
+                name: name.clone(),
+                volume: volumes[i],
+                range: maxs[i] - mins[i],
+                price_shift: price_shifts[i],
+                spread: -overlaps[i],
+            })
+            .collect();
+        let factors = get_factors();
+        logger::debug_file(
+            "LiveScraper",
+            format!(
+                "Ranking weights: {}",
+                factors
+                    .iter()
+                    .map(|factor| format!("{}={}", factor.name(), factor.weight(&rule)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+            .as_str(),
+            Some(self.client.log_file.as_str()),
+        );
+        let scores = score_items(&items, &factors, &rule);
+        let score_column: Vec<f64> = names
+            .iter()
+            .map(|name| scores.get(name).cloned().unwrap_or(0.0))
+            .collect();
+
+        df.with_column(Series::new("score", score_column))
+            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?;
+        helper::sort_dataframe(df, "score", true)
+    }
+
+    // Items with a visible order of mine first, then the stock, then new opportunities. Within each
+    // phase the best scored items go first, then the items left over from the last pass, then the rest
+    // by score.
+    pub fn get_check_order(
+        &self,
+        items: Vec<String>,
+        ranked_df: &DataFrame,
+        pending_items: &[String],
+        active_items: &HashSet<String>,
+        stock_items: &HashSet<String>,
+    ) -> Result<Vec<String>, AppError> {
+        let top_items = self
+            .client
+            .settings
+            .lock()?
+            .live_scraper
+            .stock_item
+            .ranking
+            .top_items;
+        let positions: HashMap<String, usize> = ranked_df
+            .column("name")
+            .and_then(|c| c.utf8())
+            .map_err(|e| AppError::new("LiveScraper", eyre!(e.to_string())))?
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, name)| name.map(|name| (name.to_string(), i)))
+            .collect();
+        Ok(order_items(
+            items,
+            &positions,
+            pending_items,
+            active_items,
+            stock_items,
+            top_items.max(0) as usize,
+        ))
+    }
+}
+
+// The part of a pass an item is checked in, in the order they run
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckPhase {
+    ActiveOrders,
+    Stock,
+    Opportunities,
+}
+
+impl CheckPhase {
+    pub fn get(
+        item: &str,
+        active_items: &HashSet<String>,
+        stock_items: &HashSet<String>,
+    ) -> CheckPhase {
+        if active_items.contains(item) {
+            CheckPhase::ActiveOrders
+        } else if stock_items.contains(item) {
+            CheckPhase::Stock
+        } else {
+            CheckPhase::Opportunities
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckPhase::ActiveOrders => "active_orders",
+            CheckPhase::Stock => "stock",
+            CheckPhase::Opportunities => "opportunities",
+        }
+    }
+}
+
+// Sorts the items of a pass, positions are the ranks from the scored overlap
+pub fn order_items(
+    items: Vec<String>,
+    positions: &HashMap<String, usize>,
+    pending_items: &[String],
+    active_items: &HashSet<String>,
+    stock_items: &HashSet<String>,
+    top_items: usize,
+) -> Vec<String> {
+    let pending: HashSet<&String> = pending_items.iter().collect();
+    let mut items = items;
+    items.sort_by_key(|item| {
+        let position = positions.get(item).cloned().unwrap_or(usize::MAX);
+        (
+            CheckPhase::get(item, active_items, stock_items),
+            position >= top_items,
+            !pending.contains(item),
+            position,
+            item.clone(),
+        )
+    });
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    fn set(items: &[&str]) -> HashSet<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    fn positions(items: &[&str]) -> HashMap<String, usize> {
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (item.to_string(), i))
+            .collect()
+    }
+
+    #[test]
+    fn active_orders_go_before_stock_and_opportunities() {
+        let ordered = order_items(
+            names(&["new", "stocked", "listed"]),
+            &positions(&["new", "stocked", "listed"]),
+            &[],
+            &set(&["listed"]),
+            &set(&["stocked"]),
+            10,
+        );
+        assert_eq!(ordered, names(&["listed", "stocked", "new"]));
+    }
+
+    #[test]
+    fn top_items_go_before_pending_items_and_the_rest() {
+        let ordered = order_items(
+            names(&["d", "c", "b", "a"]),
+            &positions(&["a", "b", "c", "d"]),
+            &names(&["d"]),
+            &HashSet::new(),
+            &HashSet::new(),
+            2,
+        );
+        // a and b make the top two, d was left over from the last pass and goes before c
+        assert_eq!(ordered, names(&["a", "b", "d", "c"]));
+    }
+
+    #[test]
+    fn pending_items_do_not_jump_phases() {
+        let ordered = order_items(
+            names(&["pending", "listed"]),
+            &positions(&["listed", "pending"]),
+            &names(&["pending"]),
+            &set(&["listed"]),
+            &HashSet::new(),
+            0,
+        );
+        assert_eq!(ordered, names(&["listed", "pending"]));
+    }
+
+    #[test]
+    fn unranked_items_go_last_by_name() {
+        let ordered = order_items(
+            names(&["zeta", "alpha", "ranked"]),
+            &positions(&["ranked"]),
+            &[],
+            &HashSet::new(),
+            &HashSet::new(),
+            0,
+        );
+        assert_eq!(ordered, names(&["ranked", "alpha", "zeta"]));
+    }
+}
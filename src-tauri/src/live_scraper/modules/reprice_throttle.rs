@@ -0,0 +1,178 @@
+use chrono::{Duration, Utc};
+use serde_json::json;
+
+use crate::{
+    enums::OrderType, error::AppError, helper, live_scraper::client::LiveScraperClient, logger,
+};
+
+pub struct RepriceThrottleModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> RepriceThrottleModule<'a> {
+    // False when the order was repriced too recently and the new price is too close to the current one.
+    // The new price is kept and applied by apply_pending once the cooldown is over.
+    pub async fn allows(
+        &self,
+        item_name: &str,
+        order_id: &Option<String>,
+        current_price: i64,
+        new_price: i64,
+    ) -> Result<bool, AppError> {
+        let rule = self
+            .client
+            .settings
+            .lock()?
+            .clone()
+            .live_scraper
+            .reprice_throttle;
+        let order_id = match order_id {
+            Some(order_id) if rule.min_interval >= 0 => order_id,
+            _ => return Ok(true),
+        };
+        let db = self.client.db.lock()?.clone();
+        let last_reprice = match db.order_reprice().get_by_order(order_id).await? {
+            Some(entry) => helper::parse_timestamp(&entry.last_reprice),
+            None => None,
+        };
+        let last_reprice = match last_reprice {
+            Some(last_reprice) => last_reprice,
+            None => return Ok(true),
+        };
+        let minutes = (Utc::now() - last_reprice).num_minutes();
+        if minutes >= rule.min_interval {
+            return Ok(true);
+        }
+        let delta_percent = (new_price - current_price).abs() * 100 / current_price.max(1);
+        if rule.override_percent >= 0 && delta_percent >= rule.override_percent {
+            return Ok(true);
+        }
+        db.order_reprice().set_pending(order_id, new_price).await?;
+        self.client.send_message(
+            "item.reprice_throttled",
+            Some(json!({
+                "name": item_name,
+                "price": current_price,
+                "new_price": new_price,
+                "minutes": rule.min_interval - minutes,
+            })),
+        );
+        Ok(false)
+    }
+
+    pub async fn record(&self, order_id: &Option<String>, item_name: &str) -> Result<(), AppError> {
+        if let Some(order_id) = order_id {
+            let db = self.client.db.lock()?.clone();
+            db.order_reprice().record(order_id, item_name).await?;
+        }
+        Ok(())
+    }
+
+    // Moves the orders to the prices held back once their cooldown is over, run every pass
+    // so an item that is not checked again still gets its price
+    pub async fn apply_pending(&self) -> Result<(), AppError> {
+        let rule = self
+            .client
+            .settings
+            .lock()?
+            .clone()
+            .live_scraper
+            .reprice_throttle;
+        let db = self.client.db.lock()?.clone();
+        let cutoff = Utc::now() - Duration::minutes(rule.min_interval.max(0));
+        let mut due = vec![];
+        for entry in db.order_reprice().get_entries().await? {
+            match helper::parse_timestamp(&entry.last_reprice) {
+                Some(last_reprice) if last_reprice > cutoff => {}
+                // Entries without a pending price are only needed during the cooldown
+                _ if entry.pending_price.is_none() => {
+                    db.order_reprice().delete(&entry.order_id).await?
+                }
+                _ => due.push(entry),
+            }
+        }
+        if due.is_empty() {
+            return Ok(());
+        }
+
+        let wfm = self.client.get_wfm()?;
+        let my_orders = wfm.orders().get_my_orders().await?;
+        for entry in due {
+            let order = my_orders
+                .buy_orders
+                .iter()
+                .chain(my_orders.sell_orders.iter())
+                .find(|order| order.id == entry.order_id);
+            let (order, price) = match (order, entry.pending_price) {
+                (Some(order), Some(price)) if order.platinum != price => (order, price),
+                // The order is gone or already at the price
+                _ => {
+                    db.order_reprice().delete(&entry.order_id).await?;
+                    continue;
+                }
+            };
+            // Sell orders carry the listed price and the bulk note of the stock item
+            let stock_item = match order.order_type {
+                OrderType::Sell => db
+                    .stock_item()
+                    .get_items_by_url_name(&entry.item_name)
+                    .await?
+                    .into_iter()
+                    .find(|t| {
+                        let same_rank = match order.mod_rank {
+                            Some(rank) => rank == t.rank as i64,
+                            None => true,
+                        };
+                        same_rank && (order.subtype.is_none() || order.subtype == t.sub_type)
+                    }),
+                _ => None,
+            };
+            let note = stock_item
+                .as_ref()
+                .and_then(|t| t.bulk.clone())
+                .map(|b| b.0.get_note());
+            wfm.orders()
+                .update_with_note(
+                    &order.id,
+                    price as i32,
+                    order.quantity as i32,
+                    order.visible,
+                    note.as_deref(),
+                )
+                .await?;
+            db.order_reprice()
+                .record(&order.id, &entry.item_name)
+                .await?;
+            if let Some(stock_item) = stock_item {
+                db.stock_item()
+                    .update_by_id(
+                        stock_item.id,
+                        None,
+                        None,
+                        None,
+                        Some(price as i32),
+                        None,
+                        None,
+                    )
+                    .await?;
+            }
+            self.client.send_message(
+                "item.reprice_applied",
+                Some(json!({
+                    "name": entry.item_name,
+                    "price": order.platinum,
+                    "new_price": price,
+                })),
+            );
+            logger::info_con(
+                "LiveScraper",
+                format!(
+                    "Applied the held back price of {} from {} to {} plat",
+                    entry.item_name, order.platinum, price
+                )
+                .as_str(),
+            );
+        }
+        Ok(())
+    }
+}
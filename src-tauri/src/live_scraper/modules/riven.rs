@@ -2,17 +2,81 @@ use reqwest::header::HeaderMap;
 use serde_json::json;
 
 use crate::{
-    auth::AuthState, error::AppError, live_scraper::client::LiveScraperClient, logger,
-    structs::AuctionItem,
+    auth::AuthState,
+    error::AppError,
+    live_scraper::client::LiveScraperClient,
+    logger,
+    structs::{Auction, AuctionItem, AuctionOwner, RivenAttribute},
 };
+
+// Most similar auctions the suggested price is taken from
+const SIMILAR_AUCTIONS: usize = 10;
+
 pub struct RivenModule<'a> {
     pub client: &'a LiveScraperClient,
 }
 
 impl<'a> RivenModule<'a> {
+    // 0 to 1, how close the stats of two rivens are. A stat counts when both have it on the
+    // same side, weighted by how close the values are.
+    fn get_similarity(mine: &[RivenAttribute], theirs: &[RivenAttribute]) -> f64 {
+        let count = mine.len().max(theirs.len());
+        if count == 0 {
+            return 0.0;
+        }
+        let score: f64 = mine
+            .iter()
+            .filter_map(|attribute| {
+                let other = theirs.iter().find(|t| {
+                    t.url_name == attribute.url_name && t.positive == attribute.positive
+                })?;
+                let largest = attribute.value.abs().max(other.value.abs());
+                let closeness = if largest > 0.0 {
+                    1.0 - ((attribute.value - other.value).abs() / largest).min(1.0)
+                } else {
+                    1.0
+                };
+                Some(0.5 + 0.5 * closeness)
+            })
+            .sum();
+        score / count as f64
+    }
+
+    // Similarity weighted price of the auctions closest to the riven, None when none is similar enough
+    fn get_suggested_price(
+        attributes: &[RivenAttribute],
+        auctions: &[&Auction<AuctionOwner>],
+        min_similarity: f64,
+    ) -> Option<i64> {
+        let mut similar: Vec<(f64, i64)> = auctions
+            .iter()
+            .filter_map(|auction| {
+                let theirs = auction.item.attributes.as_deref().unwrap_or_default();
+                let similarity = Self::get_similarity(attributes, theirs);
+                if similarity >= min_similarity && similarity > 0.0 {
+                    Some((similarity, auction.starting_price))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        if similar.is_empty() {
+            return None;
+        }
+        similar.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let similar = &similar[..similar.len().min(SIMILAR_AUCTIONS)];
+        let weight: f64 = similar.iter().map(|(similarity, _)| similarity).sum();
+        let total: f64 = similar
+            .iter()
+            .map(|(similarity, price)| similarity * *price as f64)
+            .sum();
+        Some((total / weight).round() as i64)
+    }
+
     pub async fn check_stock(&self) -> Result<(), AppError> {
         let db = self.client.db.lock()?.clone();
-        let wfm = self.client.wfm.lock()?.clone();
+        let cache = db.cache.lock()?.clone();
+        let wfm = self.client.get_wfm()?;
         let auth = self.client.auth.lock()?.clone();
         let settings = self.client.settings.lock()?.clone().live_scraper;
         logger::info_con("RivenModule", "Run riven module");
@@ -20,8 +84,12 @@ impl<'a> RivenModule<'a> {
         let my_auctions = wfm.auction().get_my_auctions().await?;
         let my_rivens = my_auctions
             .iter()
-            .filter(|a| a.item.item_type == "riven".to_string())
+            // A closed auction no longer lists the riven, a new one is created for it
+            .filter(|a| a.item.item_type == "riven".to_string() && !a.closed)
             .collect::<Vec<_>>();
+        self.client
+            .order_budget()
+            .load_auctions(my_rivens.len() as i64)?;
         let mut current_index = stockrivens.len();
         let total = stockrivens.len();
         for riven in stockrivens {
@@ -45,6 +113,7 @@ impl<'a> RivenModule<'a> {
                     self.client
                         .send_message("riven.deleting", Some(json!({ "name": riven.weapon_url})));
                     wfm.auction().delete(auction.id.as_str()).await?;
+                    self.client.order_budget().release_auction()?;
                 }
 
                 // Update database status to inactive
@@ -123,45 +192,76 @@ impl<'a> RivenModule<'a> {
                 })
                 .collect::<Vec<_>>();
 
-            // Check if there are no live auctions continue to next riven if there is no live auctions
-            if live_auctions.len() == 0 {
-                logger::info_con(
-                    "RivenModule",
-                    format!("No live auctions for {}", riven.weapon_url).as_str(),
-                );
-                self.client
-                    .send_message("riven.no_offers", Some(json!({ "name": riven.weapon_url})));
-                db.stock_riven()
-                    .update_by_id(
-                        riven.id,
-                        None,
-                        None,
-                        None,
-                        None,
-                        None,
-                        None,
-                        None,
-                        Some("no_offers".to_string()),
-                        None,
-                    )
-                    .await?;
-                continue;
-            }
-
             // Get the minimum price of the riven.
             let minimum_price = riven.minium_price;
 
-            // Get the lowest price
-            let lowest_price = live_auctions.get(0).unwrap().starting_price;
+            // Without competing auctions the riven is listed at its minimum or stock price
+            let lowest_price = match live_auctions.get(0) {
+                Some(auction) => auction.starting_price,
+                None => {
+                    logger::info_con(
+                        "RivenModule",
+                        format!("No live auctions for {}", riven.weapon_url).as_str(),
+                    );
+                    self.client
+                        .send_message("riven.no_offers", Some(json!({ "name": riven.weapon_url})));
+                    minimum_price
+                        .or(riven.listed_price)
+                        .map(|price| price as i64)
+                        .unwrap_or(riven.price.round() as i64)
+                }
+            };
 
-            // The new price of the riven
+            // The new price of the riven, priced like the most similar rivens when enabled
             let mut post_price = lowest_price;
+            if settings.stock_riven.min_similarity >= 0 {
+                let min_similarity = settings.stock_riven.min_similarity.min(100) as f64 / 100.0;
+                let suggested_price =
+                    Self::get_suggested_price(&riven.attributes.0, &live_auctions, min_similarity);
+                if let Some(suggested_price) = suggested_price {
+                    logger::info_con(
+                        "RivenModule",
+                        format!(
+                            "Suggested price for {} from similar rivens is {}",
+                            riven.weapon_url, suggested_price
+                        )
+                        .as_str(),
+                    );
+                    self.client.send_message(
+                        "riven.suggested",
+                        Some(json!({ "name": riven.weapon_url, "price": suggested_price})),
+                    );
+                    post_price = suggested_price;
+                }
+            }
 
             // Check if the rivens price is lower than the minimum price
             if minimum_price.is_some() && post_price < minimum_price.unwrap() as i64 {
                 post_price = minimum_price.unwrap() as i64;
             }
 
+            // Don't follow the live asks below what the weapon's rivens have been selling for
+            if settings.stock_riven.sold_price_floor_percentile >= 0 {
+                let sold_stats = cache
+                    .riven()
+                    .get_sold_stats(&riven.weapon_url, Some(riven.re_rolls > 0))?;
+                if let Some(sold_stats) = sold_stats.first() {
+                    let floor = sold_stats
+                        .get_percentile(settings.stock_riven.sold_price_floor_percentile);
+                    if post_price < floor {
+                        logger::info_con(
+                            "RivenModule",
+                            format!(
+                                "Lowest price for {} is below the sold price floor {}",
+                                riven.weapon_url, floor
+                            )
+                            .as_str(),
+                        );
+                        post_price = floor;
+                    }
+                }
+            }
+
             // Calculate profit of the riven
             let profit = post_price as f64 - riven.price;
 
@@ -188,18 +288,18 @@ impl<'a> RivenModule<'a> {
                                 Some(json!({ "name": riven.weapon_url, "price": post_price})),
                             );
                             wfm.auction()
-                                .update(
-                                    auction.id.as_str(),
-                                    post_price as i32,
-                                    0,
-                                    "",
-                                    post_price as i32,
-                                    true,
-                                )
+                                .update_price(auction, post_price, true)
                                 .await?;
                         }
                     }
                     None => {
+                        if !self
+                            .client
+                            .order_budget()
+                            .reserve_auction(&riven.weapon_url)?
+                        {
+                            continue;
+                        }
                         // Post auction on warframe.market
                         self.client.send_message(
                             "riven.creating",
@@ -261,14 +361,7 @@ impl<'a> RivenModule<'a> {
                         if auction.visible {
                             // Update auction
                             wfm.auction()
-                                .update(
-                                    auction.id.as_str(),
-                                    (riven.price + 50.0) as i32,
-                                    0,
-                                    "",
-                                    (riven.price + 50.0) as i32,
-                                    false,
-                                )
+                                .update_price(auction, (riven.price + 50.0) as i64, false)
                                 .await?;
                         }
                     }
@@ -0,0 +1,82 @@
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone, Utc};
+
+use crate::{error::AppError, live_scraper::client::LiveScraperClient, settings::ScraperSchedule};
+
+pub struct ScheduleModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> ScheduleModule<'a> {
+    // When the next cycle may start, now when nothing holds it back.
+    // The reason is cycle_delay, quiet_hours or disabled_day.
+    pub fn next_run(
+        &self,
+        last_cycle: Option<DateTime<Utc>>,
+    ) -> Result<(DateTime<Local>, Option<String>), AppError> {
+        let schedule = self.client.settings.lock()?.live_scraper.schedule.clone();
+        let now = Local::now();
+        let mut next = now;
+        let mut reason = None;
+        if let Some(last_cycle) = last_cycle {
+            let earliest = last_cycle.with_timezone(&Local)
+                + Duration::seconds(schedule.min_cycle_delay.max(0));
+            if earliest > next {
+                next = earliest;
+                reason = Some("cycle_delay".to_string());
+            }
+        }
+        // A week of disabled days and quiet hours is all there is to skip
+        for _ in 0..16 {
+            if Self::is_disabled_day(&schedule, &next) {
+                next = Self::start_of_next_day(&next);
+                reason = Some("disabled_day".to_string());
+            } else if let Some(end) = Self::quiet_hours_end(&schedule, &next) {
+                next = end;
+                reason = Some("quiet_hours".to_string());
+            } else {
+                break;
+            }
+        }
+        Ok((next, reason))
+    }
+
+    fn is_disabled_day(schedule: &ScraperSchedule, time: &DateTime<Local>) -> bool {
+        let day = time.format("%a").to_string();
+        schedule
+            .disabled_days
+            .iter()
+            .any(|d| d.eq_ignore_ascii_case(&day))
+    }
+
+    // End of the quiet hours the time falls in
+    fn quiet_hours_end(
+        schedule: &ScraperSchedule,
+        time: &DateTime<Local>,
+    ) -> Option<DateTime<Local>> {
+        let start = NaiveTime::parse_from_str(&schedule.quiet_hours_start, "%H:%M").ok()?;
+        let end = NaiveTime::parse_from_str(&schedule.quiet_hours_end, "%H:%M").ok()?;
+        let current = time.time();
+        let end_date = if start <= end {
+            if current < start || current >= end {
+                return None;
+            }
+            time.date_naive()
+        } else if current >= start {
+            time.date_naive() + Duration::days(1)
+        } else if current < end {
+            time.date_naive()
+        } else {
+            return None;
+        };
+        Local
+            .from_local_datetime(&end_date.and_time(end))
+            .earliest()
+    }
+
+    fn start_of_next_day(time: &DateTime<Local>) -> DateTime<Local> {
+        let date = time.date_naive() + Duration::days(1);
+        date.and_hms_opt(0, 0, 0)
+            .and_then(|start| Local.from_local_datetime(&start).earliest())
+            .unwrap_or(*time + Duration::days(1))
+    }
+}
@@ -0,0 +1,373 @@
+use std::collections::{HashMap, HashSet};
+
+use eyre::eyre;
+use polars::prelude::*;
+use serde_json::json;
+
+use crate::{
+    database::modules::stock_set::{StockSetPart, StockSetStruct},
+    enums::{ErrorKind, OrderType},
+    error::AppError,
+    helper::{self, ColumnType, ColumnValue},
+    live_scraper::client::LiveScraperClient,
+    logger,
+    structs::Item,
+    wfm_client::client::WFMClient,
+};
+
+// Closed days the value of the parts is averaged over
+const AVERAGE_DAYS: usize = 7;
+
+pub struct SetModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> SetModule<'a> {
+    // Finds the sets the stock parts make up and prices them against the set's orders.
+    // Returns the listed sets, the parts they use are reserved from the part listings.
+    pub async fn check_sets(
+        &self,
+        current_sell_orders: &DataFrame,
+    ) -> Result<HashSet<String>, AppError> {
+        let mut handled = HashSet::new();
+        let rule = self
+            .client
+            .settings
+            .lock()?
+            .live_scraper
+            .stock_item
+            .set_pricing
+            .clone();
+        if !rule.enabled {
+            return Ok(handled);
+        }
+        let db = self.client.db.lock()?.clone();
+        let cache = db.cache.lock()?.clone();
+        let wfm = self.client.get_wfm()?;
+        let history = self.client.price_scraper.lock()?.get_price_history()?;
+
+        // Owned units per part, ranked copies, variants and reserved copies are never part of a set
+        let mut owned: HashMap<String, i64> = HashMap::new();
+        // What the owned units cost in total and the minimum price set on each part
+        let mut cost: HashMap<String, f64> = HashMap::new();
+        let mut minimum: HashMap<String, i64> = HashMap::new();
+        for stock_item in db.stock_item().get_items().await? {
+            if stock_item.hidden || stock_item.get_sellable() <= 0 || stock_item.rank != 0 {
+                continue;
+            }
+            let sellable = stock_item.get_sellable() as i64;
+            *owned.entry(stock_item.url.clone()).or_default() += sellable;
+            *cost.entry(stock_item.url.clone()).or_default() += stock_item.price * sellable as f64;
+            if let Some(minium_price) = stock_item.minium_price {
+                let entry = minimum.entry(stock_item.url.clone()).or_default();
+                *entry = (*entry).max(minium_price as i64);
+            }
+        }
+        // Owned units before any set takes them, the part orders are trimmed against it
+        let sellable = owned.clone();
+
+        let items = cache.items().get_types()?;
+        let by_id: HashMap<&str, &Item> = items.iter().map(|i| (i.id.as_str(), i)).collect();
+        let mut sets: HashMap<String, Item> = HashMap::new();
+        for item in items.iter().filter(|i| owned.contains_key(&i.url_name)) {
+            for id in item.set_items.iter().flatten() {
+                match by_id.get(id.as_str()) {
+                    Some(set)
+                        if set.url_name.ends_with("_set") && set.url_name != item.url_name =>
+                    {
+                        sets.insert(set.url_name.clone(), (*set).clone());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        for (set_url, set_item) in sets {
+            // The parts of a set never change, they are only fetched the first time
+            let parts = match db.stock_set().get_by_url(&set_url).await? {
+                Some(stored) => stored.parts.0,
+                None => match self.get_parts(&wfm, &set_url).await {
+                    Ok(parts) => parts,
+                    Err(e) if e.kind() == ErrorKind::BudgetExhausted => break,
+                    Err(e) => {
+                        logger::warning_con(
+                            "SetModule",
+                            format!("No parts for {}: {}", set_url, e.cause()).as_str(),
+                        );
+                        continue;
+                    }
+                },
+            };
+            if parts.is_empty() {
+                continue;
+            }
+            let complete = parts
+                .iter()
+                .map(|p| owned.get(&p.url).copied().unwrap_or(0) / p.quantity.max(1))
+                .min()
+                .unwrap_or(0);
+            let parts_price = parts
+                .iter()
+                .map(|p| {
+                    history
+                        .get_average_price(&p.url, AVERAGE_DAYS)
+                        .map(|price| price * p.quantity as f64)
+                })
+                .sum::<Option<f64>>()
+                .map(|total| total.round() as i64);
+
+            let mut set_price = None;
+            if complete > 0 {
+                let lowest_price = match self.get_lowest_sell_price(&wfm, &set_url).await {
+                    Ok(price) => price,
+                    Err(e) if e.kind() == ErrorKind::BudgetExhausted => break,
+                    Err(e) => return Err(e),
+                };
+                set_price = match lowest_price {
+                    Some(lowest_price) => Some(self.get_set_price(
+                        &set_url,
+                        lowest_price,
+                        &parts,
+                        &sellable,
+                        &cost,
+                        &minimum,
+                    )?),
+                    None => None,
+                };
+            }
+            // Without a part price the set is listed on the set's orders alone
+            let listed = rule.list_as_set
+                && match (set_price, parts_price) {
+                    (Some(set_price), Some(parts_price)) => set_price >= parts_price,
+                    (Some(_), None) => true,
+                    _ => false,
+                };
+
+            self.sync_order(
+                &set_item,
+                if listed { complete } else { 0 },
+                set_price.unwrap_or(0),
+                current_sell_orders,
+            )
+            .await?;
+            if listed {
+                handled.insert(set_url.clone());
+                // Only the copies the sets use are taken, spare copies are still sold as parts
+                for part in parts.iter() {
+                    if let Some(units) = owned.get_mut(&part.url) {
+                        *units -= complete * part.quantity.max(1);
+                    }
+                }
+                for part in parts.iter() {
+                    let remaining = owned.get(&part.url).copied().unwrap_or(0);
+                    if remaining < sellable.get(&part.url).copied().unwrap_or(0) {
+                        self.trim_part_order(&part.url, remaining, current_sell_orders)
+                            .await?;
+                    }
+                }
+                logger::info_con(
+                    "SetModule",
+                    format!(
+                        "Listing {} x{} for {}p instead of the parts",
+                        set_url,
+                        complete,
+                        set_price.unwrap_or(0)
+                    )
+                    .as_str(),
+                );
+            }
+
+            db.stock_set()
+                .save(StockSetStruct {
+                    id: 0,
+                    wfm_id: set_item.id.clone(),
+                    url: set_url.clone(),
+                    name: set_item.item_name.clone(),
+                    parts: sqlx::types::Json(parts),
+                    complete,
+                    set_price,
+                    parts_price,
+                    listed,
+                    updated: "".to_string(),
+                })
+                .await?;
+        }
+        Ok(handled)
+    }
+
+    // Prices the set like an item listing, the profile and rounding of the set, never below
+    // what the parts cost plus the minimum profit or the minimum prices of the parts
+    fn get_set_price(
+        &self,
+        set_url: &str,
+        lowest_price: i64,
+        parts: &[StockSetPart],
+        owned: &HashMap<String, i64>,
+        cost: &HashMap<String, f64>,
+        minimum: &HashMap<String, i64>,
+    ) -> Result<i64, AppError> {
+        let item = self.client.item();
+        let (_, profile) = item.get_pricing_profile(set_url)?;
+        let bought_price: f64 = parts
+            .iter()
+            .map(|p| {
+                let units = owned.get(&p.url).copied().unwrap_or(0).max(1) as f64;
+                cost.get(&p.url).copied().unwrap_or(0.0) / units * p.quantity as f64
+            })
+            .sum();
+        let minimum_price: i64 = parts
+            .iter()
+            .map(|p| minimum.get(&p.url).copied().unwrap_or(0) * p.quantity)
+            .sum();
+
+        let mut post_price = profile.get_sell_price(lowest_price);
+        post_price = (bought_price as i64 + profile.min_profit).max(post_price);
+        post_price = item.get_rounded_price(set_url, post_price)?;
+        Ok(post_price.max(minimum_price))
+    }
+
+    // Parts of the set and how many of each it needs
+    async fn get_parts(
+        &self,
+        wfm: &WFMClient,
+        set_url: &str,
+    ) -> Result<Vec<StockSetPart>, AppError> {
+        let details = wfm.items().get_item(set_url.to_string()).await?;
+        Ok(details
+            .items_in_set
+            .into_iter()
+            .filter(|i| i.set_root != Some(true))
+            .filter_map(|i| {
+                Some(StockSetPart {
+                    url: i.url_name?,
+                    quantity: i.quantity_for_set.unwrap_or(1),
+                })
+            })
+            .collect())
+    }
+
+    // Lowest sell order of another player in game, None without sellers
+    async fn get_lowest_sell_price(
+        &self,
+        wfm: &WFMClient,
+        url_name: &str,
+    ) -> Result<Option<i64>, AppError> {
+        let my_name = self.client.auth.lock()?.ingame_name.clone();
+        let orders = wfm.orders().get_ingame_orders_by_item(url_name).await?;
+        Ok(orders
+            .into_iter()
+            .filter(|o| o.order_type == OrderType::Sell)
+            .filter(|o| match &o.user {
+                Some(user) => !user.ingame_name.eq_ignore_ascii_case(&my_name),
+                None => false,
+            })
+            .map(|o| o.platinum)
+            .min())
+    }
+
+    // Creates, updates or deletes my sell order of the set
+    async fn sync_order(
+        &self,
+        set_item: &Item,
+        quantity: i64,
+        price: i64,
+        current_sell_orders: &DataFrame,
+    ) -> Result<(), AppError> {
+        let wfm = self.client.get_wfm()?;
+        let set_url = set_item.url_name.as_str();
+        let (order_id, visibility, current_price, active) = self
+            .client
+            .item()
+            .get_my_order_information(set_url, current_sell_orders)
+            .await?;
+
+        if quantity <= 0 || price <= 0 {
+            if active {
+                self.client
+                    .send_message("item.sell.deleting", Some(json!({ "name": set_url})));
+                wfm.orders().delete(&order_id.unwrap_or_default()).await?;
+                self.client.order_budget().release(set_url)?;
+            }
+            return Ok(());
+        }
+
+        if !active {
+            if !self.client.order_budget().reserve(set_url)? {
+                return Ok(());
+            }
+            self.client.send_message(
+                "item.sell.creating",
+                Some(json!({ "name": set_url, "price": price})),
+            );
+            wfm.orders()
+                .create(&set_item.id, "sell", price, quantity, true, None, None)
+                .await?;
+        } else if current_price != price
+            || self.get_order_quantity(set_url, current_sell_orders)? != quantity
+        {
+            self.client.send_message(
+                "item.sell.updating",
+                Some(json!({ "name": set_url, "price": price})),
+            );
+            wfm.orders()
+                .update(
+                    &order_id.unwrap_or_default(),
+                    price as i32,
+                    quantity as i32,
+                    visibility,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    fn get_order_quantity(&self, url_name: &str, df: &DataFrame) -> Result<i64, AppError> {
+        match helper::get_column_value(
+            df.clone(),
+            Some(col("url_name").eq(lit(url_name))),
+            "quantity",
+            ColumnType::I64,
+        )? {
+            ColumnValue::I64(values) => Ok(values.unwrap_or(0)),
+            _ => Err(AppError::new("SetModule", eyre!("Expected i64 values"))),
+        }
+    }
+
+    // Lowers the part's sell order to the copies the listed sets leave, deleting it when none are
+    async fn trim_part_order(
+        &self,
+        part_url: &str,
+        remaining: i64,
+        current_sell_orders: &DataFrame,
+    ) -> Result<(), AppError> {
+        let (order_id, visibility, price, active) = self
+            .client
+            .item()
+            .get_my_order_information(part_url, current_sell_orders)
+            .await?;
+        if !active {
+            return Ok(());
+        }
+        let wfm = self.client.get_wfm()?;
+        if remaining <= 0 {
+            self.client
+                .send_message("item.sell.deleting", Some(json!({ "name": part_url})));
+            wfm.orders().delete(&order_id.unwrap_or_default()).await?;
+            self.client.order_budget().release(part_url)?;
+        } else if self.get_order_quantity(part_url, current_sell_orders)? > remaining {
+            self.client.send_message(
+                "item.sell.updating",
+                Some(json!({ "name": part_url, "price": price})),
+            );
+            wfm.orders()
+                .update(
+                    &order_id.unwrap_or_default(),
+                    price as i32,
+                    remaining as i32,
+                    visibility,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
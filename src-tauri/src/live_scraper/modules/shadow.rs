@@ -0,0 +1,57 @@
+use crate::{enums::ShadowStrategy, error::AppError, live_scraper::client::LiveScraperClient};
+
+pub struct ShadowModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> ShadowModule<'a> {
+    // Candidate price from the competitor prices, best first: lowest sell or highest buy
+    fn get_candidate_price(strategy: &ShadowStrategy, prices: &Vec<i64>) -> Option<i64> {
+        match strategy {
+            ShadowStrategy::MedianTop => {
+                let mut top: Vec<i64> = prices.iter().take(5).cloned().collect();
+                if top.is_empty() {
+                    return None;
+                }
+                top.sort();
+                Some(top[top.len() / 2])
+            }
+            ShadowStrategy::SecondBest => prices.get(1).or(prices.first()).cloned(),
+            ShadowStrategy::Disabled | ShadowStrategy::Unknown(_) => None,
+        }
+    }
+
+    // Stores the candidate price when it differs from the price the live scraper acts on
+    pub async fn compare(
+        &self,
+        url_name: &str,
+        order_type: &str,
+        current_price: i64,
+        prices: &Vec<i64>,
+    ) -> Result<(), AppError> {
+        let strategy = self
+            .client
+            .settings
+            .lock()?
+            .clone()
+            .live_scraper
+            .shadow_strategy;
+        let candidate_price = match Self::get_candidate_price(&strategy, prices) {
+            Some(price) => price,
+            None => return Ok(()),
+        };
+        if candidate_price == current_price {
+            return Ok(());
+        }
+        let db = self.client.db.lock()?.clone();
+        db.shadow_decision()
+            .create(
+                url_name,
+                order_type,
+                strategy.as_str(),
+                current_price,
+                candidate_price,
+            )
+            .await
+    }
+}
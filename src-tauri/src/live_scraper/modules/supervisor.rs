@@ -0,0 +1,138 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    enums::ErrorKind, error::AppError, helper, live_scraper::client::LiveScraperClient, logger,
+};
+
+// Restarts kept for the UI, the oldest go first
+const MAX_HISTORY: usize = 100;
+
+// An error that ended a pass and what the supervisor did about it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RestartRecord {
+    pub created_at: String,
+    pub component: String,
+    pub cause: String,
+    // network, auth or logic
+    pub class: String,
+    // Failed passes in a row including this one
+    pub attempt: i64,
+    // Seconds until the next pass, None when the scraper was stopped
+    pub delay: Option<i64>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SupervisorState {
+    pub failed_passes: i64,
+    // Set when the current pass failed with an error worth retrying
+    pub retry: bool,
+    pub history: Vec<RestartRecord>,
+}
+
+pub struct SupervisorModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> SupervisorModule<'a> {
+    // Network errors go away on their own, auth and logic errors need the user
+    pub fn classify(error: &AppError) -> &'static str {
+        match error.kind() {
+            ErrorKind::Network
+            | ErrorKind::RateLimited
+            | ErrorKind::Unavailable
+            | ErrorKind::Maintenance => "network",
+            ErrorKind::AuthExpired | ErrorKind::Forbidden => "auth",
+            _ => "logic",
+        }
+    }
+
+    fn get_delay(&self, attempt: i64) -> Result<i64, AppError> {
+        let settings = self.client.settings.lock()?.live_scraper.supervisor.clone();
+        let factor = 2_i64.saturating_pow((attempt - 1).clamp(0, 30) as u32);
+        Ok(settings
+            .base_delay
+            .max(1)
+            .saturating_mul(factor)
+            .min(settings.max_delay.max(1)))
+    }
+
+    // True when the scraper keeps running and retries after a delay, false when it has to stop
+    pub fn handle(&self, error: &AppError) -> Result<bool, AppError> {
+        let settings = self.client.settings.lock()?.live_scraper.supervisor.clone();
+        let class = Self::classify(error);
+        let mut state = self.client.supervisor_state.lock()?;
+        // Only the first error of a pass counts as a failed pass
+        if !state.retry {
+            state.failed_passes += 1;
+        }
+        let attempt = state.failed_passes;
+        let retry =
+            class == "network" && (settings.max_retries < 0 || attempt <= settings.max_retries);
+        let delay = match retry {
+            true => Some(self.get_delay(attempt)?),
+            false => None,
+        };
+        state.retry = retry;
+        state.history.push(RestartRecord {
+            created_at: helper::get_utc_timestamp(),
+            component: error.component(),
+            cause: error.cause(),
+            class: class.to_string(),
+            attempt,
+            delay,
+        });
+        if state.history.len() > MAX_HISTORY {
+            state.history.remove(0);
+        }
+        drop(state);
+
+        if !retry {
+            self.client.mh.lock()?.show_notification(
+                "Live Scraper Stopped",
+                format!("{}: {}", class, error.cause().trim()).as_str(),
+                None,
+                None,
+            );
+        }
+        Ok(retry)
+    }
+
+    // Waits out the backoff of a failed pass, true when it had to wait. A pass without errors
+    // resets the backoff.
+    pub async fn wait_before_retry(&self) -> bool {
+        let (retry, attempt) = {
+            let mut state = self.client.supervisor_state.lock().unwrap();
+            if !state.retry {
+                state.failed_passes = 0;
+            }
+            let retry = state.retry;
+            state.retry = false;
+            (retry, state.failed_passes)
+        };
+        if !retry {
+            return false;
+        }
+        let delay = self.get_delay(attempt).unwrap_or(60);
+        logger::warning_con(
+            "LiveScraper",
+            format!("Pass {} failed, retrying in {} seconds", attempt, delay).as_str(),
+        );
+        self.client.send_message(
+            "supervisor.retrying",
+            Some(json!({ "attempt": attempt, "delay": delay })),
+        );
+        let mut elapsed = 0;
+        while self.client.is_running() && elapsed < delay {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            elapsed += 1;
+        }
+        true
+    }
+
+    pub fn get_history(&self) -> Result<Vec<RestartRecord>, AppError> {
+        Ok(self.client.supervisor_state.lock()?.history.clone())
+    }
+}
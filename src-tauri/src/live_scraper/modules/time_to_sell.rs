@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use eyre::eyre;
+use polars::prelude::*;
+use serde_json::json;
+
+use crate::{
+    error::AppError,
+    helper::{self, ColumnType, ColumnValues},
+    live_scraper::client::LiveScraperClient,
+};
+
+pub struct TimeToSellModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> TimeToSellModule<'a> {
+    // Average units sold per day of every item in the price history, loaded once per pass
+    pub fn load_daily_volumes(&self) -> Result<(), AppError> {
+        let history = self.client.price_scraper.lock()?.get_price_history()?;
+        *self.client.daily_volumes.lock()? = history
+            .iter_closed_days()
+            .filter(|(_, days)| !days.is_empty())
+            .map(|(name, days)| {
+                let volume = days.iter().map(|day| day.volume).sum::<f64>() / days.len() as f64;
+                (name.clone(), volume)
+            })
+            .collect();
+        Ok(())
+    }
+
+    // Days until the listing sells: the units listed cheaper than mine have to sell first.
+    // variant_orders are the live orders of the same rank and sub type as the stock item.
+    pub async fn record(
+        &self,
+        stock_id: i64,
+        variant_orders: &DataFrame,
+    ) -> Result<Option<f64>, AppError> {
+        let db = self.client.db.lock()?.clone();
+        let stock_item = match db.stock_item().get_by_id(stock_id).await? {
+            Some(stock_item) => stock_item,
+            None => return Ok(None),
+        };
+        let daily_volume = self
+            .client
+            .daily_volumes
+            .lock()?
+            .get(&stock_item.url)
+            .cloned()
+            .unwrap_or(0.0);
+        let listed_price = match stock_item.listed_price {
+            Some(listed_price) if daily_volume > 0.0 => listed_price as i64,
+            _ => {
+                self.client.time_to_sell.lock()?.remove(&stock_id);
+                return Ok(None);
+            }
+        };
+
+        let units_ahead = match helper::get_column_values(
+            variant_orders.clone(),
+            Some(
+                col("order_type")
+                    .eq(lit("sell"))
+                    .and(col("platinum").lt(lit(listed_price))),
+            ),
+            "quantity",
+            ColumnType::I64,
+        )? {
+            ColumnValues::I64(values) => values.iter().sum::<i64>(),
+            _ => return Err(AppError::new("TimeToSell", eyre!("Expected i64 values"))),
+        };
+        let days = (units_ahead + 1) as f64 / daily_volume;
+        self.client.time_to_sell.lock()?.insert(stock_id, days);
+        Ok(Some(days))
+    }
+
+    // Sends the estimates with the stock items after a pass
+    pub async fn emit(&self) -> Result<(), AppError> {
+        let db = self.client.db.lock()?.clone();
+        let estimates: HashMap<i64, f64> = self.client.time_to_sell.lock()?.clone();
+        for mut stock_item in db.stock_item().get_items().await? {
+            if let Some(days) = estimates.get(&stock_item.id) {
+                stock_item.time_to_sell = Some(*days);
+                db.stock_item().emit("CREATE_OR_UPDATE", json!(stock_item));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::{HashMap, VecDeque};
+
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    error::AppError,
+    helper::{self, ColumnType, ColumnValues},
+    live_scraper::client::LiveScraperClient,
+    logger,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VolatilityBucket {
+    Volatile,
+    Normal,
+    Stable,
+}
+
+// Price changes seen on the recent checks of an item
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ItemVolatility {
+    // Lowest sell and highest buy price of the last check
+    pub last_prices: Option<(i64, i64)>,
+    // True for every check where the prices changed, oldest first
+    pub changes: VecDeque<bool>,
+    pub last_checked_pass: u64,
+}
+
+pub struct VolatilityModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> VolatilityModule<'a> {
+    pub fn next_pass(&self) -> Result<u64, AppError> {
+        let mut pass = self.client.pass_count.lock()?;
+        *pass += 1;
+        Ok(*pass)
+    }
+
+    // My own orders are left out, they move with every update
+    fn get_top_prices(&self, orders: &DataFrame) -> Result<(i64, i64), AppError> {
+        let in_game_name = self.client.auth.lock()?.clone().ingame_name;
+        let get_prices = |order_type: &str| -> Result<Vec<i64>, AppError> {
+            match helper::get_column_values(
+                orders.clone(),
+                Some(
+                    col("order_type")
+                        .eq(lit(order_type))
+                        .and(col("username").neq(lit(in_game_name.clone()))),
+                ),
+                "platinum",
+                ColumnType::I64,
+            )? {
+                ColumnValues::I64(values) => Ok(values),
+                _ => Ok(vec![]),
+            }
+        };
+        let lowest_sell = get_prices("sell")?.into_iter().min().unwrap_or(0);
+        let highest_buy = get_prices("buy")?.into_iter().max().unwrap_or(0);
+        Ok((lowest_sell, highest_buy))
+    }
+
+    // Records if the top prices moved since the last check of the item
+    pub fn record(&self, url_name: &str, orders: &DataFrame) -> Result<(), AppError> {
+        let settings = self.client.settings.lock()?.clone().live_scraper;
+        let prices = self.get_top_prices(orders)?;
+        let pass = *self.client.pass_count.lock()?;
+        let mut volatility = self.client.item_volatility.lock()?;
+        let entry = volatility.entry(url_name.to_string()).or_default();
+        if let Some(last_prices) = entry.last_prices {
+            entry.changes.push_back(last_prices != prices);
+        }
+        while entry.changes.len() > settings.adaptive_frequency.window.max(1) as usize {
+            entry.changes.pop_front();
+        }
+        entry.last_prices = Some(prices);
+        entry.last_checked_pass = pass;
+        Ok(())
+    }
+
+    // Items with too few checks count as volatile until there is enough history
+    pub fn get_bucket(&self, url_name: &str) -> Result<VolatilityBucket, AppError> {
+        let settings = self.client.settings.lock()?.clone().live_scraper;
+        let rule = settings.adaptive_frequency;
+        let volatility = self.client.item_volatility.lock()?;
+        let changes = match volatility.get(url_name) {
+            Some(entry) if entry.changes.len() as i64 * 2 >= rule.window => &entry.changes,
+            _ => return Ok(VolatilityBucket::Volatile),
+        };
+        let change_percent =
+            changes.iter().filter(|changed| **changed).count() as i64 * 100 / changes.len() as i64;
+        Ok(if change_percent >= rule.volatile_percent {
+            VolatilityBucket::Volatile
+        } else if change_percent < rule.stable_percent {
+            VolatilityBucket::Stable
+        } else {
+            VolatilityBucket::Normal
+        })
+    }
+
+    // False when the item was checked too recently for its bucket
+    pub fn is_due(&self, url_name: &str) -> Result<bool, AppError> {
+        let rule = self
+            .client
+            .settings
+            .lock()?
+            .clone()
+            .live_scraper
+            .adaptive_frequency;
+        if !rule.enabled {
+            return Ok(true);
+        }
+        let interval = match self.get_bucket(url_name)? {
+            VolatilityBucket::Volatile => 1,
+            VolatilityBucket::Normal => rule.normal_interval.max(1),
+            VolatilityBucket::Stable => rule.stable_interval.max(1),
+        } as u64;
+        let pass = *self.client.pass_count.lock()?;
+        let last_checked_pass = match self.client.item_volatility.lock()?.get(url_name) {
+            Some(entry) => entry.last_checked_pass,
+            None => return Ok(true),
+        };
+        Ok(pass >= last_checked_pass + interval)
+    }
+
+    // Written to the run log at the end of every pass
+    pub fn report(&self) -> Result<(), AppError> {
+        let rule = self
+            .client
+            .settings
+            .lock()?
+            .clone()
+            .live_scraper
+            .adaptive_frequency;
+        if !rule.enabled {
+            return Ok(());
+        }
+        let pass = *self.client.pass_count.lock()?;
+        let volatility = self.client.item_volatility.lock()?.clone();
+        let mut buckets: HashMap<VolatilityBucket, i64> = HashMap::new();
+        for name in volatility.keys() {
+            *buckets.entry(self.get_bucket(name)?).or_insert(0) += 1;
+        }
+        let skipped = volatility
+            .values()
+            .filter(|entry| entry.last_checked_pass < pass)
+            .count();
+        let counts = json!({
+            "volatile": buckets.get(&VolatilityBucket::Volatile).unwrap_or(&0),
+            "normal": buckets.get(&VolatilityBucket::Normal).unwrap_or(&0),
+            "stable": buckets.get(&VolatilityBucket::Stable).unwrap_or(&0),
+            "skipped": skipped,
+        });
+        logger::info_file(
+            "LiveScraper",
+            format!("Adaptive frequency: {}", counts).as_str(),
+            Some(self.client.log_file.as_str()),
+        );
+        self.client
+            .send_message("item.adaptive_frequency", Some(counts));
+        Ok(())
+    }
+}
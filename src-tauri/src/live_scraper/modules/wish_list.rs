@@ -0,0 +1,173 @@
+use std::collections::HashSet;
+
+use eyre::eyre;
+use polars::prelude::*;
+use serde_json::json;
+
+use crate::{
+    database::modules::wish_list::WishListStruct,
+    error::AppError,
+    helper::{self, ColumnType, ColumnValue},
+    live_scraper::client::LiveScraperClient,
+    logger,
+};
+
+pub struct WishListModule<'a> {
+    pub client: &'a LiveScraperClient,
+}
+
+impl<'a> WishListModule<'a> {
+    // Keeps a buy order at the target price for every wish list item.
+    // Returns the items on the wish list, the profit driven buying leaves their orders alone.
+    pub async fn check_items(
+        &self,
+        current_buy_orders: &DataFrame,
+    ) -> Result<HashSet<String>, AppError> {
+        let db = self.client.db.lock()?.clone();
+        let mut wished = HashSet::new();
+        for wish in db.wish_list().get_items().await? {
+            wished.insert(wish.url.clone());
+            self.check_item(&wish, current_buy_orders).await?;
+        }
+        Ok(wished)
+    }
+
+    async fn check_item(
+        &self,
+        wish: &WishListStruct,
+        current_buy_orders: &DataFrame,
+    ) -> Result<(), AppError> {
+        let db = self.client.db.lock()?.clone();
+        let wfm = self.client.get_wfm()?;
+        let url_name = wish.url.as_str();
+        let (order_id, visibility, current_price, active) = self
+            .client
+            .item()
+            .get_my_order_information(url_name, current_buy_orders)
+            .await?;
+
+        // An order deleted by the app is placed again below, it was not filled
+        let deleted_by_app = match &wish.order_id {
+            Some(id) if !active => self.client.get_wfm()?.deleted_orders.lock()?.remove(id),
+            _ => false,
+        };
+        if deleted_by_app {
+            db.wish_list().update_order_id(wish.id, None).await?;
+        }
+
+        // Warframe Market removes a buy order once it is filled, a missing order is a finished wish
+        if !active && !deleted_by_app && wish.order_id.is_some() && wish.status == "live" {
+            logger::info_con(
+                "WishList",
+                format!("Buy order for {} is gone, marking it fulfilled", url_name).as_str(),
+            );
+            self.client.order_budget().release(url_name)?;
+            db.wish_list().update_order_id(wish.id, None).await?;
+            db.wish_list()
+                .update(wish.id, None, Some(0), Some("fulfilled".to_string()))
+                .await?;
+            return Ok(());
+        }
+
+        // The order quantity goes down as sellers fill it, what was bought is no longer wanted
+        let mut quantity = wish.quantity;
+        if active {
+            let order_quantity = self.get_order_quantity(url_name, current_buy_orders)?;
+            if order_quantity < quantity {
+                quantity = order_quantity;
+                db.wish_list()
+                    .update(wish.id, None, Some(quantity), None)
+                    .await?;
+            }
+        }
+
+        if quantity <= 0 {
+            if active {
+                self.client
+                    .send_message("wish_list.deleting", Some(json!({ "name": url_name})));
+                wfm.orders().delete(&order_id.unwrap_or_default()).await?;
+                self.client.order_budget().release(url_name)?;
+            }
+            if wish.order_id.is_some() {
+                db.wish_list().update_order_id(wish.id, None).await?;
+            }
+            if wish.status != "fulfilled" {
+                db.wish_list()
+                    .update(wish.id, None, None, Some("fulfilled".to_string()))
+                    .await?;
+            }
+            return Ok(());
+        }
+
+        if !active {
+            if !self.client.order_budget().reserve(url_name)? {
+                return Ok(());
+            }
+            let cache = db.cache.lock()?.clone();
+            let rank = match cache.items().find_type(url_name)? {
+                Some(item) if item.mod_max_rank.is_some() => Some(wish.rank as f64),
+                _ => None,
+            };
+            self.client.send_message(
+                "wish_list.creating",
+                Some(json!({ "name": url_name, "price": wish.max_price})),
+            );
+            let order = wfm
+                .orders()
+                .create(
+                    &wish.wfm_id,
+                    "buy",
+                    wish.max_price,
+                    quantity,
+                    true,
+                    rank,
+                    wish.sub_type.as_deref(),
+                )
+                .await?;
+            db.wish_list()
+                .update_order_id(wish.id, Some(order.id))
+                .await?;
+            logger::info_con(
+                "WishList",
+                format!("Buying {} x{} for {}p", url_name, quantity, wish.max_price).as_str(),
+            );
+        } else if current_price != wish.max_price
+            || self.get_order_quantity(url_name, current_buy_orders)? != quantity
+        {
+            self.client.send_message(
+                "wish_list.updating",
+                Some(json!({ "name": url_name, "price": wish.max_price})),
+            );
+            wfm.orders()
+                .update(
+                    &order_id.clone().unwrap_or_default(),
+                    wish.max_price as i32,
+                    quantity as i32,
+                    visibility,
+                )
+                .await?;
+        }
+        // Keep the id of an order placed by hand so its removal is noticed too
+        if active && order_id.is_some() && wish.order_id != order_id {
+            db.wish_list().update_order_id(wish.id, order_id).await?;
+        }
+        if wish.status != "live" {
+            db.wish_list()
+                .update(wish.id, None, None, Some("live".to_string()))
+                .await?;
+        }
+        Ok(())
+    }
+
+    fn get_order_quantity(&self, url_name: &str, df: &DataFrame) -> Result<i64, AppError> {
+        match helper::get_column_value(
+            df.clone(),
+            Some(col("url_name").eq(lit(url_name))),
+            "quantity",
+            ColumnType::I64,
+        )? {
+            ColumnValue::I64(values) => Ok(values.unwrap_or(0)),
+            _ => Err(AppError::new("WishList", eyre!("Expected i64 values"))),
+        }
+    }
+}
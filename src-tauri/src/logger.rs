@@ -1,12 +1,259 @@
+use once_cell::sync::Lazy;
 use polars::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     fs::{self, File, OpenOptions},
     io::BufWriter,
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::Mutex,
 };
 
-use crate::{enums::LogLevel, helper, PACKAGEINFO};
+use crate::{enums::LogLevel, helper, settings::LoggingSettings, PACKAGEINFO};
+
+// Every entry that passes the level filter is written here, read by the log viewer
+pub const APP_LOG_FILE: &str = "app.log";
+
+static CONFIG: Lazy<Mutex<Option<LoggingSettings>>> = Lazy::new(|| Mutex::new(None));
+
+// Bytes in each open log file, held while writing so threads never rotate under each other
+static FILE_SIZES: Lazy<Mutex<HashMap<PathBuf, u64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// One line of a log file
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LogEntry {
+    pub time: String,
+    pub level: LogLevel,
+    pub component: String,
+    pub message: String,
+}
+
+// Called with the settings on startup and whenever they are saved
+pub fn configure(settings: &LoggingSettings) {
+    *CONFIG.lock().unwrap() = Some(settings.clone());
+}
+
+// The level of the most specific component override, otherwise the global level
+fn is_enabled(level: &LogLevel, component: &str) -> bool {
+    let config = CONFIG.lock().unwrap();
+    let config = match config.as_ref() {
+        Some(config) => config,
+        None => return true,
+    };
+    let min_level = config
+        .component_levels
+        .iter()
+        .filter(|(name, _)| {
+            component == name.as_str() || component.starts_with(&format!("{}:", name))
+        })
+        .max_by_key(|(name, _)| name.len())
+        .map(|(_, level)| level)
+        .unwrap_or(&config.level);
+    level.severity() >= min_level.severity()
+}
+
+fn get_rotated_path(path: &Path, index: i64) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+// Moves a full log file to <file>.1, the older ones shift up and the last one is dropped.
+// Returns true when the file was rotated away.
+fn rotate(path: &Path, size: u64) -> bool {
+    let (max_size, max_files) = match CONFIG.lock().unwrap().as_ref() {
+        Some(config) => (config.max_file_size, config.max_files),
+        None => return false,
+    };
+    if max_size <= 0 || size < max_size as u64 * 1024 {
+        return false;
+    }
+    if max_files <= 0 {
+        let _ = fs::remove_file(path);
+        return true;
+    }
+    let _ = fs::remove_file(get_rotated_path(path, max_files));
+    for index in (1..max_files).rev() {
+        let _ = fs::rename(
+            get_rotated_path(path, index),
+            get_rotated_path(path, index + 1),
+        );
+    }
+    let _ = fs::rename(path, get_rotated_path(path, 1));
+    true
+}
+
+fn write_entry(file: &str, entry: &LogEntry) {
+    let mut log_path = get_log_forlder();
+    log_path.push(file);
+    let line = match serde_json::to_string(entry) {
+        Ok(line) => line,
+        Err(e) => {
+            eprintln!("Couldn't serialize log entry: {}", e);
+            return;
+        }
+    };
+    let mut sizes = match FILE_SIZES.lock() {
+        Ok(sizes) => sizes,
+        Err(e) => e.into_inner(),
+    };
+    // The size is only read from disk the first time, after that the written bytes are counted
+    let size = sizes
+        .entry(log_path.clone())
+        .or_insert_with(|| fs::metadata(&log_path).map(|m| m.len()).unwrap_or(0));
+    if rotate(&log_path, *size) {
+        *size = 0;
+    }
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    match result {
+        Ok(_) => *size += line.len() as u64 + 1,
+        Err(e) => eprintln!("Couldn't write to file: {}", e),
+    }
+}
+
+// What the log viewer asks for, every field is optional
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct LogFilter {
+    // Matches the component and its sub components, e.g. "LiveScraper" matches "LiveScraper:Item"
+    pub component: Option<String>,
+    // Lowest level returned
+    pub level: Option<LogLevel>,
+    // RFC3339 times, both inclusive
+    pub from: Option<String>,
+    pub to: Option<String>,
+    // Newest entries returned, 500 when not given
+    pub limit: Option<usize>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        if let Some(component) = &self.component {
+            if entry.component != *component
+                && !entry.component.starts_with(&format!("{}:", component))
+            {
+                return false;
+            }
+        }
+        if let Some(level) = &self.level {
+            if entry.level.severity() < level.severity() {
+                return false;
+            }
+        }
+        let time = helper::parse_timestamp(&entry.time);
+        if let (Some(from), Some(time)) = (self.from.as_deref(), time) {
+            match helper::parse_timestamp(from) {
+                Some(from) if time < from => return false,
+                _ => {}
+            }
+        }
+        if let (Some(to), Some(time)) = (self.to.as_deref(), time) {
+            match helper::parse_timestamp(to) {
+                Some(to) if time > to => return false,
+                _ => {}
+            }
+        }
+        true
+    }
+
+    // Whether the log folder of the day, named YYYY-MM-DD, can hold entries of the range
+    fn includes_day(&self, day: &str) -> bool {
+        let from = self.from.as_deref().and_then(helper::parse_timestamp);
+        let to = self.to.as_deref().and_then(helper::parse_timestamp);
+        let after_from = match from {
+            Some(from) => day >= from.format("%Y-%m-%d").to_string().as_str(),
+            None => true,
+        };
+        let before_to = match to {
+            Some(to) => day <= to.format("%Y-%m-%d").to_string().as_str(),
+            None => true,
+        };
+        after_from && before_to
+    }
+}
+
+static STREAM_FILTER: Lazy<Mutex<Option<LogFilter>>> = Lazy::new(|| Mutex::new(None));
+
+// New entries matching the filter are sent to the window as "Logs:Entry", None stops it
+pub fn set_stream_filter(filter: Option<LogFilter>) {
+    *STREAM_FILTER.lock().unwrap() = filter;
+}
+
+fn stream_entry(entry: &LogEntry) {
+    let matches = match STREAM_FILTER.lock().unwrap().as_ref() {
+        Some(filter) => filter.matches(entry),
+        None => false,
+    };
+    if !matches {
+        return;
+    }
+    // A window that is busy sending something else skips the entry instead of waiting for it
+    if let Ok(window) = helper::WINDOW.try_lock() {
+        if let Some(window) = window.as_ref() {
+            let _ = window.emit("Logs:Entry", entry);
+        }
+    }
+}
+
+// Entries of app.log and its rotated files in the folder, oldest first
+fn read_entries(folder: &Path) -> Vec<LogEntry> {
+    let path = folder.join(APP_LOG_FILE);
+    let max_files = match CONFIG.lock().unwrap().as_ref() {
+        Some(config) => config.max_files.max(0),
+        None => 0,
+    };
+    let mut entries: Vec<LogEntry> = vec![];
+    for index in (0..=max_files).rev() {
+        let file_path = match index {
+            0 => path.clone(),
+            _ => get_rotated_path(&path, index),
+        };
+        if let Ok(content) = fs::read_to_string(&file_path) {
+            entries.extend(
+                content
+                    .lines()
+                    .filter_map(|line| serde_json::from_str::<LogEntry>(line).ok()),
+            );
+        }
+    }
+    entries
+}
+
+// The newest entries matching the filter from every day in its range, oldest first
+pub fn query(filter: &LogFilter) -> Vec<LogEntry> {
+    let limit = filter.limit.unwrap_or(500);
+    let mut logs_path = get_log_forlder();
+    logs_path.pop();
+    let mut days: Vec<String> = fs::read_dir(&logs_path)
+        .map(|dirs| {
+            dirs.filter_map(|dir| dir.ok())
+                .filter(|dir| dir.path().is_dir())
+                .filter_map(|dir| dir.file_name().to_str().map(|name| name.to_string()))
+                .filter(|day| filter.includes_day(day))
+                .collect()
+        })
+        .unwrap_or_default();
+    days.sort();
+
+    let mut entries: Vec<LogEntry> = vec![];
+    for day in days.iter().rev() {
+        if entries.len() >= limit {
+            break;
+        }
+        let mut day_entries: Vec<LogEntry> = read_entries(&logs_path.join(day))
+            .into_iter()
+            .filter(|entry| filter.matches(entry))
+            .collect();
+        day_entries.append(&mut entries);
+        entries = day_entries;
+    }
+    let skip = entries.len().saturating_sub(limit);
+    entries.split_off(skip)
+}
 
 pub fn format_text(text: &str, color: &str, bold: bool) -> String {
     let color_code = match color {
@@ -41,6 +288,15 @@ fn format_square_bracket(msg: &str) -> String {
 }
 
 pub fn dolog(level: LogLevel, component: &str, msg: &str, console: bool, file: Option<&str>) {
+    if !is_enabled(&level, component) {
+        return;
+    }
+    let entry = LogEntry {
+        time: chrono::Utc::now().to_rfc3339(),
+        level: level.clone(),
+        component: component.to_string(),
+        message: remove_ansi_codes(msg),
+    };
     let time = format_square_bracket(
         chrono::Local::now()
             .naive_utc()
@@ -63,27 +319,12 @@ pub fn dolog(level: LogLevel, component: &str, msg: &str, console: bool, file: O
         println!("{} {} {} {}", time, log_prefix, component, msg);
     }
 
+    stream_entry(&entry);
+    // Console only entries are never written to disk
     if let Some(file) = file {
-        let mut log_path = get_log_forlder();
-        log_path.push(file);
-        if !log_path.exists() {
-            fs::File::create(&log_path).unwrap();
-        }
-        let mut file = OpenOptions::new()
-            .write(true)
-            .append(true)
-            .open(log_path)
-            .unwrap();
-
-        if let Err(e) = writeln!(
-            file,
-            "{} {} {} {}",
-            remove_ansi_codes(time.as_str()),
-            remove_ansi_codes(log_prefix.as_str()),
-            remove_ansi_codes(component.as_str()),
-            remove_ansi_codes(msg.as_str())
-        ) {
-            eprintln!("Couldn't write to file: {}", e);
+        write_entry(APP_LOG_FILE, &entry);
+        if file != APP_LOG_FILE {
+            write_entry(file, &entry);
         }
     }
 }
@@ -1,14 +1,20 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+use api_tokens::ApiTokenClient;
 use auth::AuthState;
+use backup::BackupClient;
 use cache::client::CacheClient;
 use database::client::DBClient;
+use deal_hunter::DealHunter;
 use debug::DebugClient;
 use error::AppError;
 use handler::MonitorHandler;
 use live_scraper::client::LiveScraperClient;
+use onboarding::OnboardingClient;
 use once_cell::sync::Lazy;
 use price_scraper::PriceScraper;
+use profile_sync::ProfileSyncClient;
+use scheduler::Scheduler;
 use settings::SettingsState;
 use std::path::{self, PathBuf};
 use std::sync::Arc;
@@ -23,18 +29,30 @@ mod handler;
 mod structs;
 use tauri::SystemTray;
 
+mod api_tokens;
 mod auth;
+mod backup;
 mod cache;
 mod commands;
+mod dashboard;
 mod database;
+mod deal_hunter;
 mod debug;
 mod error;
+mod export;
 mod helper;
 mod live_scraper;
 mod logger;
+mod market_report;
+mod onboarding;
 mod price_scraper;
+mod price_validation;
+mod profile_sync;
+mod projection;
 mod rate_limiter;
+mod scheduler;
 mod settings;
+mod support_bundle;
 mod system_tray;
 mod wf_ee_log_parser;
 mod wfm_client;
@@ -52,8 +70,12 @@ async fn setup_async(app: &mut App) -> Result<(), AppError> {
     ));
     app.manage(monitor_handler_arc.clone());
 
+    // Apply a backup restore that was staged in the last session
+    BackupClient::apply_pending_restore()?;
+
     // create and manage Settings state
     let settings_arc = Arc::new(Mutex::new(SettingsState::setup()?));
+    logger::configure(&settings_arc.lock()?.logging);
     app.manage(settings_arc.clone());
 
     // create and manage Auth state
@@ -71,6 +93,16 @@ async fn setup_async(app: &mut App) -> Result<(), AppError> {
     let cache_arc = Arc::new(Mutex::new(CacheClient::new(Arc::clone(&wfm_client))));
     app.manage(cache_arc.clone());
 
+    // create and manage Scheduler state, periodic work is registered here
+    let scheduler = Scheduler::new();
+    let cache = cache_arc.clone();
+    scheduler.add("cache_refresh", "*/10 * * * *", 60, move || {
+        let cache = cache.lock().unwrap().clone();
+        async move { cache.refresh_if_stale().await }
+    })?;
+    scheduler.start_loop();
+    app.manage(Arc::new(Mutex::new(scheduler.clone())));
+
     // create and manage DatabaseClient state
     let database_client = Arc::new(Mutex::new(
         DBClient::new(cache_arc.clone(), wfm_client.clone())
@@ -86,6 +118,56 @@ async fn setup_async(app: &mut App) -> Result<(), AppError> {
     )));
     app.manage(price_scraper.clone());
 
+    // Backfill the price history of the watched items a chunk of days at a time
+    let (backfill_scraper, backfill_db, backfill_settings) = (
+        price_scraper.clone(),
+        database_client.clone(),
+        settings_arc.clone(),
+    );
+    scheduler.add("price_backfill", "@every 30m", 120, move || {
+        let price_scraper = backfill_scraper.lock().unwrap().clone();
+        let db = backfill_db.lock().unwrap().clone();
+        let settings = backfill_settings.lock().unwrap().clone();
+        async move { price_scraper.backfill(&db, &settings).await.map(|_| ()) }
+    })?;
+
+    // Fill in the images and wiki links of the stock, a few Warframe Market requests per run
+    let enrichment_db = database_client.clone();
+    scheduler.add("stock_enrichment", "@every 15m", 60, move || {
+        let db = enrichment_db.lock().unwrap().clone();
+        async move { db.stock_item().enrich_media(10).await.map(|_| ()) }
+    })?;
+
+    // Drop the listed prices of the stock past the retention
+    let retention_db = database_client.clone();
+    let retention_settings = settings_arc.clone();
+    scheduler.add("price_history_retention", "@every 1h", 60, move || {
+        let db = retention_db.lock().unwrap().clone();
+        let retention = retention_settings.lock().unwrap().price_history.clone();
+        async move { db.stock_price_history().prune(&retention).await.map(|_| ()) }
+    })?;
+
+    // Drop the buy book snapshots the fill probability no longer looks at
+    let buy_book_db = database_client.clone();
+    scheduler.add("buy_book_retention", "@every 1h", 60, move || {
+        let db = buy_book_db.lock().unwrap().clone();
+        async move { db.buy_book().prune().await.map(|_| ()) }
+    })?;
+
+    // Alert on sell orders well below the closed average, a no-op while disabled
+    let deal_hunter = DealHunter::new(
+        Arc::clone(&settings_arc),
+        Arc::clone(&price_scraper),
+        Arc::clone(&wfm_client),
+        Arc::clone(&auth_arc),
+        Arc::clone(&cache_arc),
+        Arc::clone(&monitor_handler_arc),
+    );
+    scheduler.add("deal_hunter", "@every 5m", 30, move || {
+        let deal_hunter = deal_hunter.clone();
+        async move { deal_hunter.scan().await.map(|_| ()) }
+    })?;
+
     // create and manage LiveScraper state
     let live_scraper = LiveScraperClient::new(
         Arc::clone(&settings_arc),
@@ -102,6 +184,7 @@ async fn setup_async(app: &mut App) -> Result<(), AppError> {
         Arc::clone(&settings_arc),
         Arc::clone(&monitor_handler_arc),
         Arc::clone(&cache_arc),
+        Arc::clone(&database_client),
     );
     app.manage(Arc::new(Mutex::new(ee_log)));
     // create and manage WhisperScraper state
@@ -112,7 +195,40 @@ async fn setup_async(app: &mut App) -> Result<(), AppError> {
         Arc::clone(&database_client),
         Arc::clone(&settings_arc),
     );
-    app.manage(Arc::new(Mutex::new(debug_client)));
+    let debug_arc = Arc::new(Mutex::new(debug_client));
+    app.manage(debug_arc.clone());
+
+    // create and manage Onboarding state
+    let onboarding_client = OnboardingClient::new(
+        Arc::clone(&settings_arc),
+        Arc::clone(&auth_arc),
+        Arc::clone(&debug_arc),
+    );
+    app.manage(Arc::new(Mutex::new(onboarding_client)));
+
+    // create and manage Backup state
+    let backup_client =
+        BackupClient::new(Arc::clone(&settings_arc), Arc::clone(&database_client));
+    backup_client.start_loop();
+    app.manage(Arc::new(Mutex::new(backup_client)));
+
+    // create and manage Profile Sync state, pulls changes made on another device
+    let profile_sync = ProfileSyncClient::new(Arc::clone(&settings_arc), Arc::clone(&cache_arc));
+    if settings_arc.lock()?.profile_sync.folder != "" {
+        match profile_sync.sync() {
+            Ok(_) => {}
+            Err(e) => error::create_log_file("profile_sync.log".to_string(), &e),
+        }
+    }
+    app.manage(Arc::new(Mutex::new(profile_sync)));
+
+    // create and manage API token state
+    let api_tokens = ApiTokenClient::setup()?;
+    app.manage(Arc::new(Mutex::new(api_tokens.clone())));
+
+    // Start the remote dashboard stream, if enabled
+    let dashboard_settings = settings_arc.lock()?.dashboard.clone();
+    dashboard::start(&dashboard_settings, api_tokens);
 
     Ok(())
 }
@@ -129,9 +245,9 @@ fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_websocket::init())
         .system_tray(SystemTray::new().with_menu(system_tray::client::get_tray_menu()))
-        .on_system_tray_event(|_app, event| match event {
+        .on_system_tray_event(|app, event| match event {
             SystemTrayEvent::MenuItemClick { id, .. } => {
-                system_tray::client::get_tray_event(id);
+                system_tray::client::get_tray_event(app, id);
             }
             _ => {}
         })
@@ -169,8 +285,15 @@ fn main() {
             commands::base::update_settings,
             commands::base::open_logs_folder,
             commands::base::export_logs,
+            commands::base::get_logs,
+            commands::base::stream_logs,
+            commands::base::preview_support_bundle,
+            commands::base::generate_support_bundle,
             commands::base::show_notification,
             commands::base::on_new_wfm_message,
+            commands::base::flush_event_buffer,
+            commands::base::get_event_buffer_metrics,
+            commands::base::get_ee_log_candidates,
             commands::auth::login,
             commands::auth::logout,
             commands::base::log,
@@ -178,28 +301,139 @@ fn main() {
             commands::transaction::create_transaction_entry,
             commands::transaction::delete_transaction_entry,
             commands::transaction::update_transaction_entry,
+            commands::transaction::search_history,
+            commands::transaction::get_transaction_buckets,
+            commands::transaction::get_transactions_list,
+            commands::transaction::get_transaction_detail,
+            commands::transaction::import_transactions,
+            commands::undo::get_undo_status,
+            commands::undo::undo_last,
+            commands::undo::redo_last,
             commands::live_scraper::toggle_live_scraper,
+            commands::live_scraper::set_price_override,
+            commands::live_scraper::clear_price_override,
+            commands::live_scraper::get_price_overrides,
+            commands::live_scraper::get_partial_sales,
+            commands::live_scraper::confirm_partial_sale,
+            commands::live_scraper::dismiss_partial_sale,
+            commands::live_scraper::get_buy_actions,
+            commands::live_scraper::approve_buy_action,
+            commands::live_scraper::dismiss_buy_action,
+            commands::live_scraper::get_liquidation_suggestions,
+            commands::live_scraper::get_shadow_decisions,
+            commands::live_scraper::get_shadow_summary,
+            commands::live_scraper::clear_shadow_decisions,
+            commands::live_scraper::get_scraper_restarts,
             commands::price_scraper::generate_price_history,
+            commands::price_scraper::get_price_snapshots,
+            commands::price_scraper::generate_market_report,
+            commands::analytics::get_trade_analytics,
+            commands::analytics::get_item_turnover,
+            commands::analytics::get_ducat_analysis,
+            commands::export::export_table,
+            commands::friends::add_friend,
+            commands::friends::remove_friend,
+            commands::friends::import_friends,
+            commands::item_lists::get_item_list_entries,
+            commands::item_lists::add_item_list_entry,
+            commands::item_lists::remove_item_list_entry,
+            commands::whisper::get_whisper_templates,
+            commands::whisper::set_whisper_template,
+            commands::whisper::remove_whisper_template,
+            commands::whisper::set_whisper_fallback,
+            commands::whisper::set_trade_template,
+            commands::whisper::remove_trade_template,
+            commands::whisper::generate_trade_message,
             commands::debug::import_warframe_algo_trader_data,
             commands::debug::reset_data,
+            commands::debug::get_wfm_cache_stats,
+            commands::onboarding::detect_legacy_installs,
+            commands::onboarding::import_legacy_install,
             commands::auctions::refresh_auctions,
+            commands::auctions::update_auction_price,
+            commands::auctions::close_auction,
+            commands::auctions::delete_auction,
             commands::orders::refresh_orders,
             commands::orders::get_orders,
+            commands::orders::get_orders_list,
+            commands::orders::get_order_detail,
             commands::orders::delete_order,
             commands::orders::create_order,
-            commands::orders::update_order,
+            commands::orders::update_order_price,
+            commands::orders::toggle_order_visibility,
+            commands::orders::preview_delete_all_orders,
             commands::orders::delete_all_orders,
+            commands::orders::get_order_journal,
+            commands::orders::resume_order_journal,
+            commands::orders::rollback_order_journal,
+            commands::orders::compare_player_orders,
             commands::chat::get_chat,
             commands::chat::delete_chat,
             commands::chat::refresh_chats,
+            // Scheduler commands
+            commands::scheduler::get_scheduled_tasks,
+            commands::scheduler::pause_scheduled_task,
+            commands::scheduler::resume_scheduled_task,
+            commands::scheduler::run_scheduled_task,
+            // Backup commands
+            commands::api_tokens::get_api_tokens,
+            commands::api_tokens::create_api_token,
+            commands::api_tokens::update_api_token,
+            commands::api_tokens::revoke_api_token,
+            commands::backup::backup_now,
+            commands::backup::list_remote_backups,
+            commands::backup::restore_remote_backup,
+            commands::profile_sync::export_profile,
+            commands::profile_sync::import_profile,
+            commands::profile_sync::sync_profile,
+            commands::profile_sync::resolve_profile_conflict,
+            // Cache commands
+            commands::cache::get_item_aliases,
+            commands::cache::add_item_alias,
+            commands::cache::remove_item_alias,
+            commands::cache::get_riven_sold_stats,
+            commands::cache::get_feature_flags,
+            commands::cache::set_feature_flag,
+            commands::cache::resolve_item,
+            commands::cache::get_item_constraints,
+            commands::cache::reload_cache_file,
+            commands::cache::get_relic_values,
+            commands::tag::get_tags,
+            commands::tag::add_tag,
+            commands::tag::remove_tag,
+            commands::tag::delete_tag,
+            commands::tag::get_entity_tags,
+            commands::tag::get_tagged,
+            commands::tag::export_tag,
+            commands::wish_list::get_wish_list,
+            commands::wish_list::create_wish_list_item,
+            commands::wish_list::update_wish_list_item,
+            commands::wish_list::delete_wish_list_item,
             // Stock commands
+            commands::stock::get_stock_items_list,
+            commands::stock::get_stock_item_detail,
+            commands::stock::get_stock_sets,
+            commands::stock::get_price_history,
+            commands::stock::validate_item_price,
             commands::stock::create_item_stock,
+            commands::stock::import_stock_items,
             commands::stock::delete_item_stock,
             commands::stock::update_item_stock,
+            commands::stock::update_item_stock_bulk,
+            commands::stock::update_item_stock_reserved,
+            commands::stock::update_item_stock_resupply,
+            commands::stock::get_resupply_risk,
+            commands::stock::rebuild_cost_basis,
+            commands::stock::get_stock_item_conflicts,
+            commands::stock::remap_item_stock,
+            commands::stock::archive_item_stock,
+            commands::stock::set_hidden_by_filter,
             commands::stock::sell_item_stock,
+            commands::stock::gift_item_stock,
             commands::stock::sell_item_stock_by_url,
             commands::stock::create_riven_stock,
             commands::stock::import_auction,
+            commands::stock::query_riven_stock,
             commands::stock::delete_riven_stock,
             commands::stock::update_riven_stock,
             commands::stock::sell_riven_stock,
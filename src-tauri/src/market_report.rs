@@ -0,0 +1,469 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+};
+
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::{client::DBClient, modules::price_snapshot::PriceSnapshotStruct},
+    enums::{ErrorKind, LogLevel},
+    error::AppError,
+    helper, logger,
+    price_scraper::PriceScraper,
+    settings::SettingsState,
+};
+
+// Gainers and losers shown per report
+const TOP_MOVERS: usize = 5;
+const TOP_SALES: usize = 5;
+// Daily volume compared with the previous range before it counts as a spike
+const SPIKE_RATIO: f64 = 2.0;
+// Spikes of items that barely trade are left out
+const SPIKE_MIN_VOLUME: f64 = 5.0;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MarketMoverStruct {
+    pub url: String,
+    pub mod_rank: Option<i64>,
+    pub start_price: f64,
+    pub end_price: f64,
+    pub change: f64,
+    pub change_percent: f64,
+    pub volume: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct VolumeSpikeStruct {
+    pub url: String,
+    pub mod_rank: Option<i64>,
+    // Average volume per day in the range and in the range before it
+    pub volume: f64,
+    pub previous_volume: f64,
+    pub ratio: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ItemSalesStruct {
+    pub url: String,
+    pub name: String,
+    pub quantity: i64,
+    pub total: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SalesSummaryStruct {
+    pub sell_count: i64,
+    pub sell_total: i64,
+    pub buy_count: i64,
+    pub buy_total: i64,
+    pub profit: i64,
+    pub top_items: Vec<ItemSalesStruct>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PortfolioValuationStruct {
+    pub start_value: f64,
+    pub end_value: f64,
+    pub change: f64,
+    pub change_percent: f64,
+    // Owned items without any closed statistics in the range
+    pub unpriced_items: Vec<String>,
+}
+
+// One point of the report charts
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MarketReportDayStruct {
+    pub day: String,
+    pub portfolio_value: f64,
+    pub sell_total: i64,
+    pub buy_total: i64,
+    pub volume: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MarketReportStruct {
+    pub range: String,
+    pub from: String,
+    pub to: String,
+    pub generated_at: String,
+    pub gainers: Vec<MarketMoverStruct>,
+    pub losers: Vec<MarketMoverStruct>,
+    pub sales: SalesSummaryStruct,
+    pub portfolio: PortfolioValuationStruct,
+    pub volume_spikes: Vec<VolumeSpikeStruct>,
+    pub days: Vec<MarketReportDayStruct>,
+    pub html_path: Option<String>,
+}
+
+fn percent(change: f64, start: f64) -> f64 {
+    if start > 0.0 {
+        change / start * 100.0
+    } else {
+        0.0
+    }
+}
+
+fn get_range_days(range: &str) -> Result<i64, AppError> {
+    match range {
+        "week" => Ok(7),
+        "month" => Ok(30),
+        "quarter" => Ok(90),
+        _ => Err(AppError::new_with_level(
+            "MarketReport",
+            eyre!("Unknown report range {}, use week, month or quarter", range),
+            LogLevel::Warning,
+        )
+        .with_kind(ErrorKind::Validation)),
+    }
+}
+
+// Built from the stored price snapshots of the watched items and the transactions,
+// the snapshots are filled in by the price backfill.
+pub async fn generate(
+    db: &DBClient,
+    settings: &SettingsState,
+    range: &str,
+) -> Result<MarketReportStruct, AppError> {
+    let range_days = get_range_days(range)?;
+    // Newest first, the range and the one before it for the volume spikes
+    let all_days = helper::last_x_days(range_days * 2);
+    let (days, previous_days) = all_days.split_at(range_days as usize);
+    let (from, to) = (days[days.len() - 1].clone(), days[0].clone());
+
+    let watched = PriceScraper::get_watched_items(db, settings).await?;
+    let snapshots: Vec<PriceSnapshotStruct> = db
+        .price_snapshot()
+        .get_closed_between(&previous_days[previous_days.len() - 1], &to)
+        .await?
+        .into_iter()
+        .filter(|s| watched.contains(&s.url))
+        .collect();
+
+    // Oldest first per item and rank
+    let mut series: HashMap<(String, Option<i64>), Vec<&PriceSnapshotStruct>> = HashMap::new();
+    for snapshot in snapshots.iter() {
+        series
+            .entry((snapshot.url.clone(), snapshot.mod_rank))
+            .or_default()
+            .push(snapshot);
+    }
+
+    let mut movers = vec![];
+    let mut volume_spikes = vec![];
+    for ((url, mod_rank), snapshots) in series.iter() {
+        let (current, previous): (Vec<&PriceSnapshotStruct>, Vec<&PriceSnapshotStruct>) =
+            snapshots.iter().copied().partition(|s| s.day >= from);
+        let volume: i64 = current.iter().map(|s| s.volume).sum();
+        let priced: Vec<&&PriceSnapshotStruct> =
+            current.iter().filter(|s| s.median > 0.0).collect();
+        if priced.len() >= 2 {
+            let start_price = priced[0].median;
+            let end_price = priced[priced.len() - 1].median;
+            movers.push(MarketMoverStruct {
+                url: url.clone(),
+                mod_rank: *mod_rank,
+                start_price,
+                end_price,
+                change: end_price - start_price,
+                change_percent: percent(end_price - start_price, start_price),
+                volume,
+            });
+        }
+
+        let daily_volume = volume as f64 / range_days as f64;
+        let previous_volume =
+            previous.iter().map(|s| s.volume).sum::<i64>() as f64 / range_days as f64;
+        if daily_volume >= SPIKE_MIN_VOLUME
+            && previous_volume > 0.0
+            && daily_volume >= previous_volume * SPIKE_RATIO
+        {
+            volume_spikes.push(VolumeSpikeStruct {
+                url: url.clone(),
+                mod_rank: *mod_rank,
+                volume: daily_volume,
+                previous_volume,
+                ratio: daily_volume / previous_volume,
+            });
+        }
+    }
+    movers.sort_by(|a, b| b.change_percent.total_cmp(&a.change_percent));
+    let gainers: Vec<MarketMoverStruct> = movers
+        .iter()
+        .filter(|m| m.change > 0.0)
+        .take(TOP_MOVERS)
+        .cloned()
+        .collect();
+    let losers: Vec<MarketMoverStruct> = movers
+        .iter()
+        .rev()
+        .filter(|m| m.change < 0.0)
+        .take(TOP_MOVERS)
+        .cloned()
+        .collect();
+    volume_spikes.sort_by(|a, b| b.ratio.total_cmp(&a.ratio));
+
+    let mut chart: BTreeMap<String, MarketReportDayStruct> = days
+        .iter()
+        .map(|day| {
+            (
+                day.clone(),
+                MarketReportDayStruct {
+                    day: day.clone(),
+                    ..Default::default()
+                },
+            )
+        })
+        .collect();
+    for snapshot in snapshots.iter().filter(|s| s.day >= from) {
+        if let Some(day) = chart.get_mut(&snapshot.day) {
+            day.volume += snapshot.volume;
+        }
+    }
+
+    // Sales of the range, bucketed by their UTC day like the snapshots
+    let mut sales = SalesSummaryStruct::default();
+    let mut items: HashMap<String, ItemSalesStruct> = HashMap::new();
+    for transaction in db.transaction().get_items().await? {
+        let day = match helper::parse_timestamp(&transaction.created) {
+            Some(created) => created.format("%Y-%m-%d").to_string(),
+            None => continue,
+        };
+        let chart_day = match chart.get_mut(&day) {
+            Some(chart_day) => chart_day,
+            None => continue,
+        };
+        let total = transaction.price as i64;
+        match transaction.transaction_type.as_str() {
+            "sell" => {
+                sales.sell_count += 1;
+                sales.sell_total += total;
+                chart_day.sell_total += total;
+                let item =
+                    items
+                        .entry(transaction.url.clone())
+                        .or_insert_with(|| ItemSalesStruct {
+                            url: transaction.url.clone(),
+                            name: transaction.name.clone(),
+                            ..Default::default()
+                        });
+                item.quantity += transaction.quantity as i64;
+                item.total += total;
+            }
+            "buy" => {
+                sales.buy_count += 1;
+                sales.buy_total += total;
+                chart_day.buy_total += total;
+            }
+            // Gifts move stock but are left out of the profit
+            _ => {}
+        }
+    }
+    sales.profit = sales.sell_total - sales.buy_total;
+    let mut items: Vec<ItemSalesStruct> = items.into_values().collect();
+    items.sort_by(|a, b| b.total.cmp(&a.total));
+    sales.top_items = items.into_iter().take(TOP_SALES).collect();
+
+    // The current stock is valued over the range, past holdings are not stored
+    let mut portfolio = PortfolioValuationStruct::default();
+    for stock_item in db.stock_item().get_items().await? {
+        if stock_item.owned <= 0 || stock_item.hidden {
+            continue;
+        }
+        let rank = stock_item.rank as i64;
+        let prices = series
+            .get(&(stock_item.url.clone(), Some(rank)))
+            .or_else(|| series.get(&(stock_item.url.clone(), None)))
+            .map(|snapshots| {
+                snapshots
+                    .iter()
+                    .filter(|s| s.day >= from && s.median > 0.0)
+                    .map(|s| (s.day.clone(), s.median))
+                    .collect::<BTreeMap<String, f64>>()
+            })
+            .unwrap_or_default();
+        if prices.is_empty() {
+            portfolio.unpriced_items.push(stock_item.name.clone());
+            continue;
+        }
+        let owned = stock_item.owned as f64;
+        // Days without sales keep the last known price, the first known one before that
+        let mut last_price = *prices.values().next().unwrap();
+        for (day, chart_day) in chart.iter_mut() {
+            if let Some(price) = prices.get(day) {
+                last_price = *price;
+            }
+            chart_day.portfolio_value += last_price * owned;
+        }
+    }
+    let chart: Vec<MarketReportDayStruct> = chart.into_values().collect();
+    if let (Some(first), Some(last)) = (chart.first(), chart.last()) {
+        portfolio.start_value = first.portfolio_value;
+        portfolio.end_value = last.portfolio_value;
+        portfolio.change = portfolio.end_value - portfolio.start_value;
+        portfolio.change_percent = percent(portfolio.change, portfolio.start_value);
+    }
+
+    Ok(MarketReportStruct {
+        range: range.to_string(),
+        from,
+        to,
+        generated_at: helper::get_utc_timestamp(),
+        gainers,
+        losers,
+        sales,
+        portfolio,
+        volume_spikes,
+        days: chart,
+        html_path: None,
+    })
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_table(title: &str, headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut html = format!("<h2>{}</h2>", escape(title));
+    if rows.is_empty() {
+        return html + "<p>Nothing to show</p>";
+    }
+    html += "<table><tr>";
+    for header in headers {
+        html += &format!("<th>{}</th>", escape(header));
+    }
+    html += "</tr>";
+    for row in rows {
+        html += "<tr>";
+        for cell in row {
+            html += &format!("<td>{}</td>", escape(&cell));
+        }
+        html += "</tr>";
+    }
+    html + "</table>"
+}
+
+fn render_movers(title: &str, movers: &[MarketMoverStruct]) -> String {
+    render_table(
+        title,
+        &["Item", "Rank", "Start", "End", "Change", "Volume"],
+        movers
+            .iter()
+            .map(|m| {
+                vec![
+                    m.url.clone(),
+                    m.mod_rank.map(|r| r.to_string()).unwrap_or_default(),
+                    format!("{:.0}", m.start_price),
+                    format!("{:.0}", m.end_price),
+                    format!("{:+.1}%", m.change_percent),
+                    m.volume.to_string(),
+                ]
+            })
+            .collect(),
+    )
+}
+
+// A standalone page with the same data, for reading or sharing outside the app
+pub fn render_html(report: &MarketReportStruct) -> String {
+    let mut body = format!(
+        "<h1>Market report {} to {}</h1><p>Generated {}</p>",
+        escape(&report.from),
+        escape(&report.to),
+        escape(&report.generated_at)
+    );
+    body += &render_table(
+        "Portfolio",
+        &["Start value", "End value", "Change"],
+        vec![vec![
+            format!("{:.0}", report.portfolio.start_value),
+            format!("{:.0}", report.portfolio.end_value),
+            format!(
+                "{:+.0} ({:+.1}%)",
+                report.portfolio.change, report.portfolio.change_percent
+            ),
+        ]],
+    );
+    let sales = &report.sales;
+    body += &render_table(
+        "Sales",
+        &["Sold", "Sales", "Bought", "Purchases", "Profit"],
+        vec![vec![
+            sales.sell_count.to_string(),
+            sales.sell_total.to_string(),
+            sales.buy_count.to_string(),
+            sales.buy_total.to_string(),
+            sales.profit.to_string(),
+        ]],
+    );
+    body += &render_table(
+        "Best selling items",
+        &["Item", "Quantity", "Total"],
+        sales
+            .top_items
+            .iter()
+            .map(|i| vec![i.name.clone(), i.quantity.to_string(), i.total.to_string()])
+            .collect(),
+    );
+    body += &render_movers("Top gainers", &report.gainers);
+    body += &render_movers("Top losers", &report.losers);
+    body += &render_table(
+        "Volume spikes",
+        &["Item", "Rank", "Per day", "Before", "Ratio"],
+        report
+            .volume_spikes
+            .iter()
+            .map(|s| {
+                vec![
+                    s.url.clone(),
+                    s.mod_rank.map(|r| r.to_string()).unwrap_or_default(),
+                    format!("{:.1}", s.volume),
+                    format!("{:.1}", s.previous_volume),
+                    format!("{:.1}x", s.ratio),
+                ]
+            })
+            .collect(),
+    );
+    body += &render_table(
+        "Days",
+        &["Day", "Portfolio value", "Sales", "Purchases", "Volume"],
+        report
+            .days
+            .iter()
+            .map(|d| {
+                vec![
+                    d.day.clone(),
+                    format!("{:.0}", d.portfolio_value),
+                    d.sell_total.to_string(),
+                    d.buy_total.to_string(),
+                    d.volume.to_string(),
+                ]
+            })
+            .collect(),
+    );
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Quantframe market report</title>\
+<style>body{{font-family:sans-serif}}table{{border-collapse:collapse}}td,th{{border:1px solid #ccc;padding:4px 8px}}</style>\
+</head><body>{}</body></html>",
+        body
+    )
+}
+
+// Returns the path of the HTML file on the desktop
+pub fn write_html(report: &MarketReportStruct) -> Result<String, AppError> {
+    let path = helper::get_desktop_path().join(format!(
+        "quantframe_market_report_{}_{}.html",
+        report.range, report.to
+    ));
+    fs::write(&path, render_html(report))
+        .map_err(|e| AppError::new("MarketReport", eyre!(e.to_string())))?;
+    logger::info_con(
+        "MarketReport",
+        format!("Market report written to {}", path.display()).as_str(),
+    );
+    Ok(path.to_str().unwrap_or_default().to_string())
+}
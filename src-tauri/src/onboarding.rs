@@ -0,0 +1,252 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use directories::BaseDirs;
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    auth::AuthState,
+    debug::DebugClient,
+    enums::{ErrorKind, LogLevel},
+    error::AppError,
+    helper, logger,
+    settings::SettingsState,
+};
+
+// Folder names QuantframeV1 used under the user's cache and local data folders
+const LEGACY_FOLDERS: [&str; 3] = ["quantframe", "Quantframe", "dev.kenya.quantframe.v1"];
+// Database files of the old installs, in the format the debug importer reads
+const LEGACY_DATABASES: [&str; 2] = ["inventory.sqlite", "quantframe.sqlite"];
+const PROGRESS_ID: &str = "Onboarding:Progress";
+
+// What was found in an old install folder
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LegacyInstallStruct {
+    pub path: String,
+    pub settings: bool,
+    pub auth: bool,
+    pub cache: bool,
+    pub database: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct OnboardingClient {
+    log_file: String,
+    settings: Arc<Mutex<SettingsState>>,
+    auth: Arc<Mutex<AuthState>>,
+    debug: Arc<Mutex<DebugClient>>,
+}
+
+impl OnboardingClient {
+    pub fn new(
+        settings: Arc<Mutex<SettingsState>>,
+        auth: Arc<Mutex<AuthState>>,
+        debug: Arc<Mutex<DebugClient>>,
+    ) -> Self {
+        OnboardingClient {
+            log_file: "onboarding.log".to_string(),
+            settings,
+            auth,
+            debug,
+        }
+    }
+
+    fn get_candidate_paths() -> Vec<PathBuf> {
+        let base_dirs = match BaseDirs::new() {
+            Some(base_dirs) => base_dirs,
+            None => return vec![],
+        };
+        let mut paths = vec![];
+        for root in [
+            base_dirs.cache_dir(),
+            base_dirs.data_local_dir(),
+            base_dirs.data_dir(),
+        ] {
+            for folder in LEGACY_FOLDERS {
+                paths.push(root.join(folder));
+            }
+        }
+        // The current app folder is never a legacy install
+        let app_path = helper::get_app_roaming_path();
+        paths.retain(|path| *path != app_path);
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    fn inspect(path: &Path) -> Option<LegacyInstallStruct> {
+        if !path.is_dir() {
+            return None;
+        }
+        let database = LEGACY_DATABASES
+            .iter()
+            .map(|name| path.join(name))
+            .find(|db_path| db_path.is_file())
+            .map(|db_path| db_path.to_string_lossy().to_string());
+        let install = LegacyInstallStruct {
+            path: path.to_string_lossy().to_string(),
+            settings: path.join("settings.json").is_file(),
+            auth: path.join("auth.json").is_file(),
+            cache: path.join("cache.json").is_file(),
+            database,
+        };
+        if !install.settings && !install.auth && install.database.is_none() {
+            return None;
+        }
+        Some(install)
+    }
+
+    pub fn detect(&self) -> Vec<LegacyInstallStruct> {
+        Self::get_candidate_paths()
+            .iter()
+            .filter_map(|path| Self::inspect(path))
+            .collect()
+    }
+
+    fn read_json(path: &Path) -> Result<Value, AppError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| AppError::new("Onboarding", eyre!(e.to_string())))?;
+        serde_json::from_str(&content).map_err(|e| {
+            AppError::new_with_level(
+                "Onboarding",
+                eyre!("{} is not valid JSON: {}", path.display(), e),
+                LogLevel::Warning,
+            )
+        })
+    }
+
+    // Copies the legacy values whose key and type still exist in the new schema, the rest keeps its current value
+    fn overlay(current: &mut Value, legacy: &Value) {
+        let (current, legacy) = match (current.as_object_mut(), legacy.as_object()) {
+            (Some(current), Some(legacy)) => (current, legacy),
+            _ => return,
+        };
+        for (key, value) in current.iter_mut() {
+            let legacy_value = match legacy.get(key) {
+                Some(legacy_value) => legacy_value,
+                None => continue,
+            };
+            if value.is_object() {
+                Self::overlay(value, legacy_value);
+            } else if std::mem::discriminant(value) == std::mem::discriminant(legacy_value)
+                || value.is_null()
+            {
+                *value = legacy_value.clone();
+            }
+        }
+    }
+
+    fn import_settings(&self, path: &Path) -> Result<(), AppError> {
+        let legacy = Self::read_json(&path.join("settings.json"))?;
+        let mut settings = self.settings.lock()?;
+        let mut current = serde_json::to_value(settings.clone())
+            .map_err(|e| AppError::new("Onboarding", eyre!(e.to_string())))?;
+        Self::overlay(&mut current, &legacy);
+        let converted: SettingsState = serde_json::from_value(current).map_err(|e| {
+            AppError::new_with_level(
+                "Onboarding",
+                eyre!("Could not convert the old settings: {}", e),
+                LogLevel::Warning,
+            )
+            .with_kind(ErrorKind::Validation)
+        })?;
+        converted.save_to_file()?;
+        *settings = converted;
+        Ok(())
+    }
+
+    fn import_auth(&self, path: &Path) -> Result<(), AppError> {
+        let legacy = Self::read_json(&path.join("auth.json"))?;
+        let mut auth = self.auth.lock()?;
+        let mut current = serde_json::to_value(auth.clone())
+            .map_err(|e| AppError::new("Onboarding", eyre!(e.to_string())))?;
+        Self::overlay(&mut current, &legacy);
+        let converted: AuthState = serde_json::from_value(current).map_err(|e| {
+            AppError::new_with_level(
+                "Onboarding",
+                eyre!("Could not convert the old login: {}", e),
+                LogLevel::Warning,
+            )
+            .with_kind(ErrorKind::Validation)
+        })?;
+        converted.save_to_file()?;
+        *auth = converted;
+        Ok(())
+    }
+
+    // Renamed instead of deleted so nothing is lost if the import missed something
+    fn archive(path: &Path) -> Result<String, AppError> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let archived = path.with_file_name(format!(
+            "{}_archived_{}",
+            name,
+            chrono::Local::now().format("%Y%m%d%H%M%S")
+        ));
+        fs::rename(path, &archived)
+            .map_err(|e| AppError::new("Onboarding", eyre!(e.to_string())))?;
+        Ok(archived.to_string_lossy().to_string())
+    }
+
+    fn progress(step: &str, values: Option<Value>) {
+        helper::emit_progress(PROGRESS_ID, &format!("onboarding.{}", step), values, false);
+    }
+
+    // Settings and login first so the database import can resolve items with the user's cache.
+    // The old cache is not converted, it is downloaded again.
+    pub async fn import(&self, path: &str) -> Result<String, AppError> {
+        let install = Self::inspect(Path::new(path)).ok_or_else(|| {
+            AppError::new_with_level(
+                "Onboarding",
+                eyre!("No QuantframeV1 data found in {}", path),
+                LogLevel::Warning,
+            )
+            .with_kind(ErrorKind::NotFound)
+        })?;
+        let path = PathBuf::from(&install.path);
+        logger::info_con(
+            "Onboarding",
+            format!("Importing QuantframeV1 data from {}", install.path).as_str(),
+        );
+
+        if install.settings {
+            Self::progress("settings", None);
+            self.import_settings(&path)?;
+        }
+        if install.auth {
+            Self::progress("auth", None);
+            self.import_auth(&path)?;
+        }
+        if let Some(database) = install.database.clone() {
+            let debug = self.debug.lock()?.clone();
+            for import_type in ["inventory", "transactions"] {
+                Self::progress("database", Some(json!({ "type": import_type })));
+                debug
+                    .import_warframe_algo_trader_data(database.clone(), import_type.to_string())
+                    .await?;
+            }
+        }
+
+        Self::progress("archive", None);
+        let archived = Self::archive(&path)?;
+        logger::info_file(
+            "Onboarding",
+            format!("Archived {} to {}", install.path, archived).as_str(),
+            Some(self.log_file.as_str()),
+        );
+        helper::emit_progress(
+            PROGRESS_ID,
+            "onboarding.completed",
+            Some(json!({ "archived": archived })),
+            true,
+        );
+        Ok(archived)
+    }
+}
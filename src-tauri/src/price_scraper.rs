@@ -1,5 +1,6 @@
 use crate::enums::LogLevel;
 use crate::error::{ApiResult, AppError, ErrorApiResponse};
+use crate::helper::{ColumnType, ColumnValues};
 use crate::wfm_client::client::WFMClient;
 use crate::{helper, logger};
 use eyre::eyre;
@@ -10,22 +11,147 @@ use std::path::Path;
 use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::{self, File},
     io::BufWriter,
 };
 extern crate chrono;
 
 use crate::auth::AuthState;
+use crate::database::client::DBClient;
+use crate::database::modules::price_snapshot::PriceSnapshotStruct;
+use crate::settings::SettingsState;
+
+// Days downloaded per backfill run, the scheduler runs it until the history is complete
+const BACKFILL_CHUNK_DAYS: usize = 7;
 
 // Structs for the Warframe Market API
 
+// A closed day of an item in the price history
+#[derive(Clone, Debug)]
+pub struct PriceHistoryDay {
+    pub datetime: String,
+    pub volume: f64,
+    pub median: f64,
+    pub avg_price: f64,
+}
+
+// The price history parsed once per file change and shared read-only by every pass.
+// Cloning it only clones the Arc, the DataFrame columns are shared as well.
+pub struct PriceHistory {
+    pub df: DataFrame,
+    // Closed days keyed by url name, newest first
+    closed_days: HashMap<String, Arc<[PriceHistoryDay]>>,
+}
+
+impl PriceHistory {
+    fn new(df: DataFrame) -> Result<Self, AppError> {
+        let closed = df
+            .clone()
+            .lazy()
+            .filter(col("order_type").eq(lit("closed")))
+            .select([
+                col("name"),
+                col("datetime").cast(DataType::Utf8),
+                col("volume").cast(DataType::Float64).fill_null(lit(0.0)),
+                col("median").cast(DataType::Float64).fill_null(lit(0.0)),
+                col("avg_price").cast(DataType::Float64).fill_null(lit(0.0)),
+            ])
+            .collect()
+            .map_err(|e| AppError::new("PriceScraper", eyre!(e.to_string())))?;
+        let closed = helper::sort_dataframe(closed, "datetime", true)?;
+        let get_strings = |column: &str| -> Result<Vec<String>, AppError> {
+            match helper::get_column_values(closed.clone(), None, column, ColumnType::String)? {
+                ColumnValues::String(values) => Ok(values),
+                _ => Err(AppError::new(
+                    "PriceScraper",
+                    eyre!("Expected string values"),
+                )),
+            }
+        };
+        let get_floats = |column: &str| -> Result<Vec<f64>, AppError> {
+            match helper::get_column_values(closed.clone(), None, column, ColumnType::F64)? {
+                ColumnValues::F64(values) => Ok(values),
+                _ => Err(AppError::new("PriceScraper", eyre!("Expected f64 values"))),
+            }
+        };
+        let names = get_strings("name")?;
+        let datetimes = get_strings("datetime")?;
+        let volumes = get_floats("volume")?;
+        let medians = get_floats("median")?;
+        let avg_prices = get_floats("avg_price")?;
+
+        let mut days: HashMap<String, Vec<PriceHistoryDay>> = HashMap::new();
+        for ((((name, datetime), volume), median), avg_price) in names
+            .into_iter()
+            .zip(datetimes)
+            .zip(volumes)
+            .zip(medians)
+            .zip(avg_prices)
+        {
+            days.entry(name).or_default().push(PriceHistoryDay {
+                datetime,
+                volume,
+                median,
+                avg_price,
+            });
+        }
+        Ok(PriceHistory {
+            df,
+            closed_days: days
+                .into_iter()
+                .map(|(name, days)| (name, Arc::from(days)))
+                .collect(),
+        })
+    }
+
+    pub fn get_closed_days(&self, url_name: &str) -> &[PriceHistoryDay] {
+        self.closed_days
+            .get(url_name)
+            .map(|days| days.as_ref())
+            .unwrap_or(&[])
+    }
+
+    pub fn iter_closed_days(&self) -> impl Iterator<Item = (&String, &[PriceHistoryDay])> {
+        self.closed_days
+            .iter()
+            .map(|(name, days)| (name, days.as_ref()))
+    }
+
+    // Change of the median price over the last week, 0 without a week of history
+    pub fn get_week_increase(&self, url_name: &str) -> f64 {
+        let days = self.get_closed_days(url_name);
+        if days.len() >= 7 {
+            days[0].median - days[6].median
+        } else {
+            0.0
+        }
+    }
+
+    // Volume weighted median price of the last closed days, None without sales
+    pub fn get_average_price(&self, url_name: &str, days: usize) -> Option<f64> {
+        let days = self.get_closed_days(url_name).iter().take(days);
+        let (total, volume) = days
+            .filter(|day| day.median > 0.0)
+            .fold((0.0, 0.0), |(total, volume), day| {
+                (total + day.median * day.volume, volume + day.volume)
+            });
+        if volume > 0.0 {
+            Some(total / volume)
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct PriceScraper {
     csv_path: String,
     csv_backop_path: String,
     wfm: Arc<Mutex<WFMClient>>,
     auth: Arc<Mutex<AuthState>>,
+    // Parsed history and the modified time of the file it was read from
+    history: Arc<Mutex<Option<(u128, Arc<PriceHistory>)>>>,
 }
 
 impl PriceScraper {
@@ -43,6 +169,7 @@ impl PriceScraper {
                 .to_string(),
             wfm,
             auth,
+            history: Arc::new(Mutex::new(None)),
         }
     }
     /// Reads the price history data from a CSV file and returns it as a DataFrame.
@@ -61,6 +188,20 @@ impl PriceScraper {
             .map_err(|e| AppError::new("PriceScraper", eyre!(e.to_string())))
     }
 
+    /// Returns the shared price history, the CSV file is only parsed again after it changed.
+    pub fn get_price_history(&self) -> Result<Arc<PriceHistory>, AppError> {
+        let modified = self.get_status();
+        let mut history = self.history.lock()?;
+        if let Some((loaded, cached)) = history.as_ref() {
+            if Some(*loaded) == modified {
+                return Ok(cached.clone());
+            }
+        }
+        let loaded = Arc::new(PriceHistory::new(self.get_price_historys()?)?);
+        *history = Some((modified.unwrap_or(0), loaded.clone()));
+        Ok(loaded)
+    }
+
     pub fn get_status(&self) -> Option<u128> {
         // Try to read from "allItemDataBackup.csv", and if it fails, read from "allItemData.csv".
         let file = File::open(&self.csv_path).or_else(|_| File::open(&self.csv_backop_path));
@@ -182,7 +323,7 @@ impl PriceScraper {
             .collect();
         Ok((item_map_url, item_map_id))
     }
-    pub async fn generate(&self, days: i64) -> Result<i64, AppError> {
+    pub async fn generate(&self, db: &DBClient, days: i64) -> Result<i64, AppError> {
         let auth = self.auth.lock().unwrap().clone();
         // Should only get 7 days of data
         let valid_days = 7;
@@ -438,6 +579,9 @@ impl PriceScraper {
             .finish(&mut filtered_df)
             .map_err(|e| AppError::new("PriceScraper", eyre!(e.to_string())))?;
 
+        // The live scraper queries the averages from the database
+        db.item_price().import_dataframe(&filtered_df).await?;
+
         // Delete the backup file if it exists
         if csv_backop_path.exists() {
             fs::remove_file(csv_backop_path)
@@ -445,4 +589,135 @@ impl PriceScraper {
         }
         Ok(full_df.height() as i64)
     }
+
+    /// The url names of the stock, whitelist and market maker items.
+    pub async fn get_watched_items(
+        db: &DBClient,
+        settings: &SettingsState,
+    ) -> Result<HashSet<String>, AppError> {
+        let mut watched: HashSet<String> = db
+            .stock_item()
+            .get_items_names()
+            .await?
+            .into_iter()
+            .collect();
+        watched.extend(settings.live_scraper.stock_item.whitelist.iter().cloned());
+        watched.extend(
+            settings
+                .live_scraper
+                .stock_item
+                .market_maker
+                .keys()
+                .cloned(),
+        );
+        watched.retain(|item| item != "");
+        Ok(watched)
+    }
+
+    /// Stores the daily price history of the watched items in the database, newest days first.
+    /// Only a chunk of days is downloaded per call, days already looked at for an item are skipped.
+    pub async fn backfill(
+        &self,
+        db: &DBClient,
+        settings: &SettingsState,
+    ) -> Result<usize, AppError> {
+        if settings.price_backfill_days < 0 {
+            return Ok(0);
+        }
+        let auth = self.auth.lock()?.clone();
+        let cache = db.cache.lock()?.clone();
+
+        let watched = Self::get_watched_items(db, settings).await?;
+        if watched.is_empty() {
+            return Ok(0);
+        }
+        let url_map: HashMap<String, String> = cache
+            .items()
+            .get_types()?
+            .into_iter()
+            .map(|item| (item.item_name, item.url_name))
+            .collect();
+
+        let days = helper::last_x_days(settings.price_backfill_days);
+        let mut fetched = 0;
+        let mut added = 0;
+        for (index, day) in days.iter().enumerate() {
+            if fetched >= BACKFILL_CHUNK_DAYS {
+                break;
+            }
+            let backfilled = db.price_snapshot().get_backfilled_items(day).await?;
+            let missing: HashSet<String> = watched.difference(&backfilled).cloned().collect();
+            if missing.is_empty() {
+                continue;
+            }
+            fetched += 1;
+            helper::emit_progress(
+                "PriceBackfill:Progress",
+                "price_backfill.progress",
+                Some(json!({ "day": day, "current": index + 1, "total": days.len() })),
+                false,
+            );
+            let items = match self.get_price_by_day(auth.platform.as_str(), day).await? {
+                ApiResult::Success(items, _headers) => items,
+                // The server has no history for the day, nothing to look for later
+                ApiResult::Error(e, _headers) if e.status_code == 404 => json!({}),
+                ApiResult::Error(e, _headers) => {
+                    // Tried again on the next run
+                    logger::warning_file(
+                        "PriceScraper",
+                        format!("Backfill stopped at {}: {}", day, e.status_code).as_str(),
+                        Some("price_scraper.log"),
+                    );
+                    break;
+                }
+            };
+
+            let mut snapshots = vec![];
+            if let Value::Object(map) = &items {
+                for (item_name, item_data_list) in map {
+                    let url = match url_map.get(item_name) {
+                        Some(url) if missing.contains(url) => url,
+                        _ => continue,
+                    };
+                    let array = match item_data_list {
+                        Value::Array(array) => array,
+                        _ => continue,
+                    };
+                    for item_data in array {
+                        let get_f64 = |key: &str| item_data.get(key).and_then(|v| v.as_f64());
+                        snapshots.push(PriceSnapshotStruct {
+                            id: 0,
+                            url: url.clone(),
+                            day: day.clone(),
+                            order_type: item_data
+                                .get("order_type")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or("closed")
+                                .to_string(),
+                            mod_rank: item_data.get("mod_rank").and_then(|v| v.as_i64()),
+                            volume: item_data
+                                .get("volume")
+                                .and_then(|v| v.as_i64())
+                                .unwrap_or(0),
+                            min_price: get_f64("min_price").unwrap_or(0.0),
+                            max_price: get_f64("max_price").unwrap_or(0.0),
+                            avg_price: get_f64("avg_price").unwrap_or(0.0),
+                            median: get_f64("median").unwrap_or(0.0),
+                        });
+                    }
+                }
+            }
+            added += db
+                .price_snapshot()
+                .add_day(day, &missing, snapshots)
+                .await?;
+        }
+        helper::emit_progress(
+            "PriceBackfill:Progress",
+            "price_backfill.completed",
+            Some(json!({ "added": added })),
+            true,
+        );
+        Ok(added)
+    }
 }
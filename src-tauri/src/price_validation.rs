@@ -0,0 +1,92 @@
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    enums::{ErrorKind, LogLevel},
+    error::AppError,
+    price_scraper::PriceScraper,
+};
+
+// Days of closed statistics the entered price is compared against
+const AVERAGE_DAYS: usize = 7;
+// Prices outside of these multiples of the average are flagged, not rejected
+const HIGH_FACTOR: f64 = 3.0;
+const LOW_FACTOR: f64 = 0.3;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PriceWarning {
+    // above_average, below_average or no_market_data
+    pub code: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PriceValidationStruct {
+    // The entered price rounded to whole platinum
+    pub price: i64,
+    pub average: Option<f64>,
+    pub warnings: Vec<PriceWarning>,
+}
+
+impl PriceValidationStruct {
+    // Adds the warnings to a command result so the UI can show them next to the field
+    pub fn attach_to(&self, mut value: Value) -> Value {
+        if value.is_object() {
+            value["price_warnings"] = json!(self.warnings);
+        }
+        value
+    }
+}
+
+// Checks a price entered by the user for an item against the market statistics.
+// Non-positive prices are rejected, unusual ones come back as warnings.
+pub fn validate_price(
+    price_scraper: &PriceScraper,
+    url_name: &str,
+    price: f64,
+) -> Result<PriceValidationStruct, AppError> {
+    if !price.is_finite() || price.round() <= 0.0 {
+        return Err(AppError::new_with_level(
+            "PriceValidation",
+            eyre!("The price must be at least 1 platinum, got {}", price),
+            LogLevel::Warning,
+        )
+        .with_kind(ErrorKind::Validation));
+    }
+    let price = price.round() as i64;
+
+    // Without a price history there is nothing to compare with, the price is still accepted
+    let average = price_scraper
+        .get_price_history()
+        .ok()
+        .and_then(|history| history.get_average_price(url_name, AVERAGE_DAYS));
+
+    let mut warnings = vec![];
+    match average {
+        None => warnings.push(PriceWarning {
+            code: "no_market_data".to_string(),
+            message: format!("No recent sales of {} to compare the price with", url_name),
+        }),
+        Some(average) if price as f64 > average * HIGH_FACTOR => warnings.push(PriceWarning {
+            code: "above_average".to_string(),
+            message: format!(
+                "{} platinum is more than {}x the average of {:.0}",
+                price, HIGH_FACTOR, average
+            ),
+        }),
+        Some(average) if (price as f64) < average * LOW_FACTOR => warnings.push(PriceWarning {
+            code: "below_average".to_string(),
+            message: format!(
+                "{} platinum is less than {}x the average of {:.0}",
+                price, LOW_FACTOR, average
+            ),
+        }),
+        Some(_) => {}
+    }
+    Ok(PriceValidationStruct {
+        price,
+        average,
+        warnings,
+    })
+}
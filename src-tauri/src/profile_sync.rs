@@ -0,0 +1,309 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use eyre::eyre;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    cache::client::CacheClient,
+    enums::{ErrorKind, LogLevel},
+    error::AppError,
+    helper, logger,
+    settings::SettingsState,
+};
+
+const PROFILE_VERSION: i64 = 1;
+const PROFILE_FILE_NAME: &str = "quantframe_profile.qfp";
+
+// Settings that stay on this device: credentials, webhooks and the sync settings themselves
+const LOCAL_ONLY_SETTINGS: [&str; 8] = [
+    "/backup/url",
+    "/backup/username",
+    "/backup/password",
+    "/backup/passphrase",
+    "/live_scraper/webhook",
+    "/notifications/on_new_conversation/webhook",
+    "/notifications/on_wfm_chat_message/webhook",
+    "/profile_sync",
+];
+
+type HmacSha256 = Hmac<Sha256>;
+
+// What is shared between devices, maps are sorted so the signature does not depend on the order
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProfileContent {
+    pub settings: Value,
+    pub aliases: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProfileBundle {
+    pub version: i64,
+    pub device: String,
+    pub exported_at: String,
+    pub profile: ProfileContent,
+    // HMAC-SHA256 of the profile, keyed with the sync passphrase
+    pub signature: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProfileSyncResult {
+    // pushed, pulled, up_to_date or conflict
+    pub status: String,
+    pub remote_device: Option<String>,
+    pub remote_exported_at: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ProfileSyncClient {
+    settings: Arc<Mutex<SettingsState>>,
+    cache: Arc<Mutex<CacheClient>>,
+}
+
+impl ProfileSyncClient {
+    pub fn new(settings: Arc<Mutex<SettingsState>>, cache: Arc<Mutex<CacheClient>>) -> Self {
+        ProfileSyncClient { settings, cache }
+    }
+
+    fn validation_error(message: String) -> AppError {
+        AppError::new_with_level("ProfileSync", eyre!(message), LogLevel::Warning)
+            .with_kind(ErrorKind::Validation)
+    }
+
+    fn get_folder_path(&self) -> Result<PathBuf, AppError> {
+        let folder = self.settings.lock()?.profile_sync.folder.clone();
+        if folder == "" {
+            return Err(Self::validation_error("No sync folder is set".to_string()));
+        }
+        Ok(PathBuf::from(folder).join(PROFILE_FILE_NAME))
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn hash(content: &ProfileContent) -> Result<String, AppError> {
+        let json = serde_json::to_string(content)
+            .map_err(|e| AppError::new("ProfileSync", eyre!(e.to_string())))?;
+        Ok(Self::to_hex(&Sha256::digest(json.as_bytes())))
+    }
+
+    fn sign(&self, content: &ProfileContent) -> Result<String, AppError> {
+        let passphrase = self.settings.lock()?.profile_sync.passphrase.clone();
+        if passphrase == "" {
+            return Err(Self::validation_error(
+                "A passphrase is required to sign profiles".to_string(),
+            ));
+        }
+        let json = serde_json::to_string(content)
+            .map_err(|e| AppError::new("ProfileSync", eyre!(e.to_string())))?;
+        let mut mac = HmacSha256::new_from_slice(passphrase.as_bytes())
+            .map_err(|e| AppError::new("ProfileSync", eyre!(e.to_string())))?;
+        mac.update(json.as_bytes());
+        Ok(Self::to_hex(&mac.finalize().into_bytes()))
+    }
+
+    fn get_content(&self) -> Result<ProfileContent, AppError> {
+        let mut settings = serde_json::to_value(self.settings.lock()?.clone())
+            .map_err(|e| AppError::new("ProfileSync", eyre!(e.to_string())))?;
+        // Left out instead of blanked, importing fills them in from the local settings
+        for pointer in LOCAL_ONLY_SETTINGS {
+            let (parent, key) = pointer.rsplit_once('/').unwrap_or(("", pointer));
+            if let Some(parent) = settings.pointer_mut(parent).and_then(|p| p.as_object_mut()) {
+                parent.remove(key);
+            }
+        }
+        let aliases = self.cache.lock()?.alias().get_aliases()?;
+        Ok(ProfileContent {
+            settings,
+            aliases: aliases.into_iter().collect(),
+        })
+    }
+
+    fn create_bundle(&self) -> Result<ProfileBundle, AppError> {
+        let profile = self.get_content()?;
+        let device = self.settings.lock()?.profile_sync.device_name.clone();
+        Ok(ProfileBundle {
+            version: PROFILE_VERSION,
+            device,
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            signature: self.sign(&profile)?,
+            profile,
+        })
+    }
+
+    fn read_bundle(&self, path: &PathBuf) -> Result<ProfileBundle, AppError> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            AppError::new_with_level(
+                "ProfileSync",
+                eyre!("Could not read {}: {}", path.display(), e),
+                LogLevel::Warning,
+            )
+            .with_kind(ErrorKind::NotFound)
+        })?;
+        let bundle: ProfileBundle = serde_json::from_str(&content)
+            .map_err(|_| Self::validation_error("Not a valid profile file".to_string()))?;
+        if bundle.version > PROFILE_VERSION {
+            return Err(Self::validation_error(format!(
+                "The profile was written by a newer version (v{})",
+                bundle.version
+            )));
+        }
+        if self.sign(&bundle.profile)? != bundle.signature {
+            return Err(Self::validation_error(
+                "The profile signature does not match, was it signed with another passphrase?"
+                    .to_string(),
+            ));
+        }
+        Ok(bundle)
+    }
+
+    fn write_bundle(&self, path: &PathBuf, bundle: &ProfileBundle) -> Result<(), AppError> {
+        let json = serde_json::to_string_pretty(bundle)
+            .map_err(|e| AppError::new("ProfileSync", eyre!(e.to_string())))?;
+        fs::write(path, json).map_err(|e| AppError::new("ProfileSync", eyre!(e.to_string())))
+    }
+
+    // Replaces the settings and aliases, the local only settings are kept
+    fn apply(&self, content: &ProfileContent) -> Result<(), AppError> {
+        let mut settings_state = self.settings.lock()?;
+        let local = serde_json::to_value(settings_state.clone())
+            .map_err(|e| AppError::new("ProfileSync", eyre!(e.to_string())))?;
+        let required = serde_json::to_value(SettingsState::default())
+            .map_err(|e| AppError::new("ProfileSync", eyre!(e.to_string())))?;
+        if !content.settings.is_object() {
+            return Err(Self::validation_error(
+                "The profile has no settings".to_string(),
+            ));
+        }
+        // Fills in the local only settings and the ones missing from profiles of older versions
+        let (mut settings, _) = helper::validate_json(&content.settings, &required, "");
+        for pointer in LOCAL_ONLY_SETTINGS {
+            if let (Some(value), Some(local_value)) =
+                (settings.pointer_mut(pointer), local.pointer(pointer))
+            {
+                *value = local_value.clone();
+            }
+        }
+        let settings: SettingsState = serde_json::from_value(settings)
+            .map_err(|e| Self::validation_error(format!("Invalid settings: {}", e)))?;
+        *settings_state = settings;
+        settings_state.save_to_file()?;
+        helper::emit_update("settings", "SET", Some(json!(settings_state.clone())));
+        drop(settings_state);
+
+        self.cache
+            .lock()?
+            .alias()
+            .replace(content.aliases.clone().into_iter().collect())?;
+        Ok(())
+    }
+
+    fn set_last_synced_hash(&self, hash: String) -> Result<(), AppError> {
+        let mut settings = self.settings.lock()?;
+        settings.profile_sync.last_synced_hash = hash;
+        settings.save_to_file()
+    }
+
+    pub fn export_to(&self, path: &str) -> Result<(), AppError> {
+        let bundle = self.create_bundle()?;
+        self.write_bundle(&PathBuf::from(path), &bundle)?;
+        logger::info_con(
+            "ProfileSync",
+            format!("Exported profile to {}", path).as_str(),
+        );
+        Ok(())
+    }
+
+    pub fn import_from(&self, path: &str) -> Result<ProfileBundle, AppError> {
+        let bundle = self.read_bundle(&PathBuf::from(path))?;
+        self.apply(&bundle.profile)?;
+        logger::info_con(
+            "ProfileSync",
+            format!("Imported profile from {} ({})", path, bundle.device).as_str(),
+        );
+        Ok(bundle)
+    }
+
+    fn push(&self, path: &PathBuf) -> Result<(), AppError> {
+        let bundle = self.create_bundle()?;
+        self.write_bundle(path, &bundle)?;
+        self.set_last_synced_hash(Self::hash(&bundle.profile)?)
+    }
+
+    fn pull(&self, bundle: &ProfileBundle) -> Result<(), AppError> {
+        self.apply(&bundle.profile)?;
+        self.set_last_synced_hash(Self::hash(&bundle.profile)?)
+    }
+
+    // The side that changed since the last sync wins, when both changed the user has to pick one
+    pub fn sync(&self) -> Result<ProfileSyncResult, AppError> {
+        let path = self.get_folder_path()?;
+        let mut result = ProfileSyncResult {
+            status: "pushed".to_string(),
+            remote_device: None,
+            remote_exported_at: None,
+        };
+        if !path.exists() {
+            self.push(&path)?;
+            return Ok(result);
+        }
+        let remote = self.read_bundle(&path)?;
+        result.remote_device = Some(remote.device.clone());
+        result.remote_exported_at = Some(remote.exported_at.clone());
+
+        let last_synced_hash = self.settings.lock()?.profile_sync.last_synced_hash.clone();
+        let local_hash = Self::hash(&self.get_content()?)?;
+        let remote_hash = Self::hash(&remote.profile)?;
+        if local_hash == remote_hash {
+            result.status = "up_to_date".to_string();
+            self.set_last_synced_hash(local_hash)?;
+        } else if local_hash == last_synced_hash {
+            result.status = "pulled".to_string();
+            self.pull(&remote)?;
+        } else if remote_hash == last_synced_hash {
+            self.push(&path)?;
+        } else {
+            result.status = "conflict".to_string();
+        }
+        logger::info_con(
+            "ProfileSync",
+            format!("Profile sync: {}", result.status).as_str(),
+        );
+        Ok(result)
+    }
+
+    // keep is "local" or "remote"
+    pub fn resolve_conflict(&self, keep: &str) -> Result<ProfileSyncResult, AppError> {
+        let path = self.get_folder_path()?;
+        let remote = self.read_bundle(&path)?;
+        let status = match keep {
+            "local" => {
+                self.push(&path)?;
+                "pushed"
+            }
+            "remote" => {
+                self.pull(&remote)?;
+                "pulled"
+            }
+            _ => {
+                return Err(Self::validation_error(format!(
+                    "Unknown side {}, use local or remote",
+                    keep
+                )))
+            }
+        };
+        Ok(ProfileSyncResult {
+            status: status.to_string(),
+            remote_device: Some(remote.device),
+            remote_exported_at: Some(remote.exported_at),
+        })
+    }
+}
@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::modules::{stock_item::StockItemStruct, transaction::TransactionStruct},
+    enums::OrderType,
+    structs::Order,
+};
+
+// Lightweight rows for the GUI lists, the full entity is loaded on demand with the detail commands
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StockItemListItem {
+    pub id: i64,
+    pub url: String,
+    pub name: String,
+    pub rank: i32,
+    pub sub_type: Option<String>,
+    pub price: f64,
+    pub minium_price: Option<i32>,
+    pub listed_price: Option<i32>,
+    pub owned: i32,
+    pub reserved: i32,
+    pub hidden: bool,
+    pub status: String,
+    pub time_to_sell: Option<f64>,
+    pub thumb: Option<String>,
+}
+
+impl From<&StockItemStruct> for StockItemListItem {
+    fn from(item: &StockItemStruct) -> Self {
+        StockItemListItem {
+            id: item.id,
+            url: item.url.clone(),
+            name: item.name.clone(),
+            rank: item.rank,
+            sub_type: item.sub_type.clone(),
+            price: item.price,
+            minium_price: item.minium_price,
+            listed_price: item.listed_price,
+            owned: item.owned,
+            reserved: item.reserved,
+            hidden: item.hidden,
+            status: item.status.clone(),
+            time_to_sell: item.time_to_sell,
+            thumb: item.thumb.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TransactionListItem {
+    pub id: i64,
+    pub name: String,
+    pub item_type: String,
+    pub transaction_type: String,
+    pub quantity: i32,
+    pub rank: i32,
+    pub price: i32,
+    pub created: String,
+}
+
+impl From<&TransactionStruct> for TransactionListItem {
+    fn from(transaction: &TransactionStruct) -> Self {
+        TransactionListItem {
+            id: transaction.id,
+            name: transaction.name.clone(),
+            item_type: transaction.item_type.clone(),
+            transaction_type: transaction.transaction_type.clone(),
+            quantity: transaction.quantity,
+            rank: transaction.rank,
+            price: transaction.price,
+            created: transaction.created.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OrderListItem {
+    pub id: String,
+    pub url_name: Option<String>,
+    pub order_type: OrderType,
+    pub platinum: i64,
+    pub quantity: i64,
+    pub mod_rank: Option<i64>,
+    pub subtype: Option<String>,
+    pub visible: bool,
+    pub last_update: String,
+}
+
+impl From<&Order> for OrderListItem {
+    fn from(order: &Order) -> Self {
+        OrderListItem {
+            id: order.id.clone(),
+            url_name: order.item.as_ref().map(|item| item.url_name.clone()),
+            order_type: order.order_type.clone(),
+            platinum: order.platinum,
+            quantity: order.quantity,
+            mod_rank: order.mod_rank,
+            subtype: order.subtype.clone(),
+            visible: order.visible,
+            last_update: order.last_update.clone(),
+        }
+    }
+}
+
+pub fn project<'b, T, P>(entities: &'b [T]) -> Vec<P>
+where
+    P: From<&'b T>,
+{
+    entities.iter().map(P::from).collect()
+}
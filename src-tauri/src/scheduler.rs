@@ -0,0 +1,309 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Datelike, Local, TimeZone, Timelike, Utc};
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    enums::LogLevel,
+    error::{self, AppError},
+    helper, logger,
+};
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<(), AppError>> + Send>>;
+type Job = Arc<dyn Fn() -> JobFuture + Send + Sync>;
+
+// When a task runs, parsed from "@every 10m" or a cron expression in local time
+#[derive(Clone, Debug)]
+pub enum Schedule {
+    Every(chrono::Duration),
+    // minute hour day-of-month month day-of-week
+    Cron(Vec<Vec<u32>>),
+}
+
+const CRON_RANGES: [(u32, u32); 5] = [(0, 59), (0, 23), (1, 31), (1, 12), (0, 6)];
+
+impl Schedule {
+    pub fn parse(expression: &str) -> Result<Self, AppError> {
+        let invalid = |reason: &str| {
+            AppError::new_with_level(
+                "Scheduler",
+                eyre!("Invalid schedule {}: {}", expression, reason),
+                LogLevel::Warning,
+            )
+        };
+        let expression = expression.trim();
+
+        if let Some(interval) = expression.strip_prefix("@every ") {
+            let interval = interval.trim();
+            if interval.len() < 2 {
+                return Err(invalid("expected an interval like 30s, 10m or 24h"));
+            }
+            let (value, unit) = interval.split_at(interval.len() - 1);
+            let value: i64 = value
+                .parse()
+                .map_err(|_| invalid("expected an interval like 30s, 10m or 24h"))?;
+            let duration = match unit {
+                "s" => chrono::Duration::seconds(value),
+                "m" => chrono::Duration::minutes(value),
+                "h" => chrono::Duration::hours(value),
+                "d" => chrono::Duration::days(value),
+                _ => return Err(invalid("unknown interval unit")),
+            };
+            if value <= 0 {
+                return Err(invalid("the interval must be positive"));
+            }
+            return Ok(Schedule::Every(duration));
+        }
+
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(invalid("expected 5 cron fields"));
+        }
+        let mut parsed = vec![];
+        for (field, (min, max)) in fields.iter().zip(CRON_RANGES) {
+            parsed.push(Self::parse_field(field, min, max).ok_or_else(|| invalid(*field))?);
+        }
+        Ok(Schedule::Cron(parsed))
+    }
+
+    // Supports *, */n, a, a-b, a-b/n and comma separated lists of those
+    fn parse_field(field: &str, min: u32, max: u32) -> Option<Vec<u32>> {
+        let mut values = vec![];
+        for part in field.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, step.parse::<u32>().ok().filter(|s| *s > 0)?),
+                None => (part, 1),
+            };
+            let (start, end) = if range == "*" {
+                (min, max)
+            } else if let Some((start, end)) = range.split_once('-') {
+                (start.parse().ok()?, end.parse().ok()?)
+            } else {
+                let value = range.parse().ok()?;
+                (value, if step > 1 { max } else { value })
+            };
+            if start < min || end > max || start > end {
+                return None;
+            }
+            values.extend((start..=end).step_by(step as usize));
+        }
+        values.sort();
+        values.dedup();
+        Some(values)
+    }
+
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::Every(interval) => Some(after + *interval),
+            Schedule::Cron(fields) => {
+                let after = after.with_timezone(&Local);
+                let mut time = Local
+                    .with_ymd_and_hms(
+                        after.year(),
+                        after.month(),
+                        after.day(),
+                        after.hour(),
+                        after.minute(),
+                        0,
+                    )
+                    .earliest()?
+                    + chrono::Duration::minutes(1);
+                // A year of minutes covers every valid expression
+                for _ in 0..(366 * 24 * 60) {
+                    if fields[0].contains(&time.minute())
+                        && fields[1].contains(&time.hour())
+                        && fields[2].contains(&time.day())
+                        && fields[3].contains(&time.month())
+                        && fields[4].contains(&time.weekday().num_days_from_sunday())
+                    {
+                        return Some(time.with_timezone(&Utc));
+                    }
+                    time = time + chrono::Duration::minutes(1);
+                }
+                None
+            }
+        }
+    }
+}
+
+// State of a task as shown in the UI
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScheduledTaskInfo {
+    pub name: String,
+    pub expression: String,
+    // Up to this many seconds are added to every run so tasks do not fire together
+    pub jitter: u64,
+    pub paused: bool,
+    pub running: bool,
+    pub next_run: Option<String>,
+    pub last_run: Option<String>,
+}
+
+#[derive(Clone)]
+struct ScheduledTask {
+    info: ScheduledTaskInfo,
+    schedule: Schedule,
+    next_run: Option<DateTime<Utc>>,
+    job: Job,
+}
+
+impl ScheduledTask {
+    fn plan_next_run(&mut self, after: DateTime<Utc>) {
+        let jitter = chrono::Duration::seconds(helper::get_jitter(self.info.jitter) as i64);
+        self.next_run = self.schedule.next_after(after).map(|next| next + jitter);
+        self.info.next_run = self.next_run.map(|next| next.to_rfc3339());
+    }
+}
+
+#[derive(Clone)]
+pub struct Scheduler {
+    log_file: String,
+    is_running: Arc<AtomicBool>,
+    tasks: Arc<Mutex<HashMap<String, ScheduledTask>>>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Scheduler {
+            log_file: "scheduler.log".to_string(),
+            is_running: Arc::new(AtomicBool::new(false)),
+            tasks: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    // Adds or replaces a task, the first run is planned from now
+    pub fn add<F, Fut>(
+        &self,
+        name: &str,
+        expression: &str,
+        jitter: u64,
+        job: F,
+    ) -> Result<(), AppError>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), AppError>> + Send + 'static,
+    {
+        let schedule = Schedule::parse(expression)?;
+        let job: Job = Arc::new(move || Box::pin(job()) as JobFuture);
+        let mut task = ScheduledTask {
+            info: ScheduledTaskInfo {
+                name: name.to_string(),
+                expression: expression.to_string(),
+                jitter,
+                paused: false,
+                running: false,
+                next_run: None,
+                last_run: None,
+            },
+            schedule,
+            next_run: None,
+            job,
+        };
+        task.plan_next_run(Utc::now());
+        self.tasks.lock()?.insert(name.to_string(), task);
+        Ok(())
+    }
+
+    fn get_task_mut<'b>(
+        tasks: &'b mut HashMap<String, ScheduledTask>,
+        name: &str,
+    ) -> Result<&'b mut ScheduledTask, AppError> {
+        tasks.get_mut(name).ok_or_else(|| {
+            AppError::new_with_level(
+                "Scheduler",
+                eyre!("Scheduled task {} not found", name),
+                LogLevel::Warning,
+            )
+        })
+    }
+
+    pub fn pause(&self, name: &str) -> Result<(), AppError> {
+        let mut tasks = self.tasks.lock()?;
+        Self::get_task_mut(&mut tasks, name)?.info.paused = true;
+        Ok(())
+    }
+
+    // Resumed tasks plan their next run from now, missed runs are not made up
+    pub fn resume(&self, name: &str) -> Result<(), AppError> {
+        let mut tasks = self.tasks.lock()?;
+        let task = Self::get_task_mut(&mut tasks, name)?;
+        task.info.paused = false;
+        task.plan_next_run(Utc::now());
+        Ok(())
+    }
+
+    // Runs the task on the next tick
+    pub fn trigger(&self, name: &str) -> Result<(), AppError> {
+        let mut tasks = self.tasks.lock()?;
+        let task = Self::get_task_mut(&mut tasks, name)?;
+        task.next_run = Some(Utc::now());
+        task.info.next_run = task.next_run.map(|next| next.to_rfc3339());
+        Ok(())
+    }
+
+    pub fn get_tasks(&self) -> Result<Vec<ScheduledTaskInfo>, AppError> {
+        let mut tasks: Vec<ScheduledTaskInfo> = self
+            .tasks
+            .lock()?
+            .values()
+            .map(|task| task.info.clone())
+            .collect();
+        tasks.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tasks)
+    }
+
+    pub fn start_loop(&self) {
+        if self.is_running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let scheduler = self.clone();
+        tauri::async_runtime::spawn(async move {
+            logger::info_con("Scheduler", "Scheduler is started");
+            while scheduler.is_running.load(Ordering::SeqCst) {
+                for (name, job) in scheduler.take_due_tasks() {
+                    let scheduler = scheduler.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = job().await {
+                            error::create_log_file(scheduler.log_file.clone(), &e);
+                        }
+                        if let Some(task) = scheduler.tasks.lock().unwrap().get_mut(&name) {
+                            task.info.running = false;
+                        }
+                    });
+                }
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        });
+    }
+
+    pub fn stop_loop(&self) {
+        self.is_running.store(false, Ordering::SeqCst);
+    }
+
+    // Marks the due tasks as running and plans their next run
+    fn take_due_tasks(&self) -> Vec<(String, Job)> {
+        let now = Utc::now();
+        let mut due = vec![];
+        for task in self.tasks.lock().unwrap().values_mut() {
+            let is_due = task.next_run.map_or(false, |next_run| next_run <= now);
+            if task.info.paused || task.info.running || !is_due {
+                continue;
+            }
+            task.info.running = true;
+            task.info.last_run = Some(now.to_rfc3339());
+            task.plan_next_run(now);
+            due.push((task.info.name.clone(), task.job.clone()));
+        }
+        due
+    }
+}
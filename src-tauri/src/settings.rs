@@ -1,14 +1,25 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
+use std::env;
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::PathBuf;
 
-use crate::enums::{OrderMode, StockMode};
+use crate::enums::{
+    CostBasisMethod, ListingAgeAction, LogLevel, OnlineStatus, OrderMode, PartialSaleMode,
+    PriceStepMode, ShadowStrategy, StockMode,
+};
 use crate::error::AppError;
 use crate::{helper, logger};
 use eyre::eyre;
 
+// Same wording as the whispers Warframe Market copies for an order
+pub const DEFAULT_WHISPER_TEMPLATE: &str =
+    "/w <PLAYER_NAME> Hi! I want to sell: <QUANTITY>\"<ITEM_NAME>\" for <PRICE> platinum. (warframe.market)";
+pub const DEFAULT_BUY_WHISPER_TEMPLATE: &str =
+    "/w <PLAYER_NAME> Hi! I want to buy: <QUANTITY>\"<ITEM_NAME>\" for <PRICE> platinum. (warframe.market)";
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SettingsState {
     // Debug Mode
@@ -16,6 +27,126 @@ pub struct SettingsState {
     pub dev_mode: bool,
     pub live_scraper: LiveScraperSettings,
     pub notifications: Notifications,
+    // EE.log of the main install, empty to look for it in the Windows, Steam and Wine locations
+    pub ee_log_path: String,
+    // Extra EE.log files to watch, e.g. a second install or a cloud client
+    pub ee_log_paths: Vec<String>,
+    pub backup: BackupSettings,
+    // Opt in or out of experimental features, missing flags use the default from the cache feed
+    pub feature_flags: HashMap<String, bool>,
+    pub wfm_retry: WfmRetrySettings,
+    // Seconds item orders and statistics are reused for, 0 to always fetch them
+    pub wfm_cache_ttl: i64,
+    // Days of price history to download for watched items, -1 to disable
+    pub price_backfill_days: i64,
+    pub friends: FriendsSettings,
+    pub dashboard: DashboardSettings,
+    pub whisper_templates: WhisperTemplateSettings,
+    pub profile_sync: ProfileSyncSettings,
+    pub deal_hunter: DealHunterSettings,
+    pub price_history: PriceHistoryRetention,
+    pub trade_watcher: TradeWatcherSettings,
+    pub logging: LoggingSettings,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LoggingSettings {
+    // Lowest level that is logged
+    pub level: LogLevel,
+    // Levels keyed by component, "LiveScraper" also covers "LiveScraper:Item"
+    pub component_levels: HashMap<String, LogLevel>,
+    // Size in KB a log file is rotated at
+    pub max_file_size: i64,
+    // Rotated files kept next to each log file
+    pub max_files: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TradeWatcherSettings {
+    // Add bought items to the stock and sell them from it when a trade shows up in EE.log
+    pub auto_stock: bool,
+    // Ask before applying trades that can't be matched to one item and price, skipped otherwise
+    pub confirm_ambiguous: bool,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PriceHistoryRetention {
+    // Days the listed prices of the stock are kept, -1 to keep them forever
+    pub max_days: i64,
+    // Prices kept per stock item, the oldest go first. -1 to disable.
+    pub max_entries: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DealHunterSettings {
+    pub enabled: bool,
+    // How far below the closed average a sell order has to be
+    pub discount_percent: i64,
+    // Url names to watch, empty to watch the most traded items
+    pub items: Vec<String>,
+    // Items checked per scan, each one is a Warframe Market request
+    pub max_items: i64,
+    // Average daily volume an item needs to be watched when no items are given
+    pub min_volume: i64,
+    // <PLAYER_NAME>, <QUANTITY>, <ITEM_NAME> and <PRICE> are replaced
+    pub whisper_template: String,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProfileSyncSettings {
+    // Shared folder the profile is synced through, e.g. a Dropbox or OneDrive path, empty to disable
+    pub folder: String,
+    // Signs the profile, every device needs the same one
+    pub passphrase: String,
+    // Written into the profile so a conflict shows which device changed it
+    pub device_name: String,
+    // Hash of the profile after the last sync, tells which side changed since
+    pub last_synced_hash: String,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WhisperTemplateSettings {
+    // Keyed by language code, e.g. "de". <PLAYER_NAME>, <QUANTITY>, <ITEM_NAME> and <PRICE> are replaced
+    pub templates: HashMap<String, String>,
+    // Used when there is no template for the buyer's language
+    pub fallback: String,
+    // Keyed by name, "buy" and "sell" are used for orders. <RANK> is replaced as well.
+    pub trade_templates: HashMap<String, String>,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DashboardSettings {
+    // Websocket stream for a remote dashboard, applied on the next start
+    pub enabled: bool,
+    pub port: u16,
+    // Listen on all interfaces so a phone on the LAN can connect, otherwise only on this PC
+    pub allow_lan: bool,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FriendsSettings {
+    // In-game names, compared without case
+    pub names: Vec<String>,
+    // Never list below a friend's sell order or bid above a friend's buy order
+    pub avoid_undercut: bool,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WfmRetrySettings {
+    // Retries for deletes, updates and reads
+    pub idempotent_retries: i64,
+    // Retries for creates, only used when the request never reached Warframe Market
+    pub non_idempotent_retries: i64,
+    pub base_delay_ms: i64,
+    pub max_delay_ms: i64,
+    // Failed requests in a row before requests are paused, 0 to never pause
+    pub breaker_threshold: i64,
+    // Seconds requests are paused for
+    pub breaker_cooldown: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupSettings {
+    pub enabled: bool,
+    // WebDAV folder the backups are uploaded to
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    // Used to encrypt the backups before they are uploaded
+    pub passphrase: String,
+    pub interval_hours: i64,
+    // How many backups to keep on the remote, 0 keeps all
+    pub retention: i64,
 }
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LiveScraperSettings {
@@ -27,6 +158,75 @@ pub struct LiveScraperSettings {
     pub stock_item: StockItemSettings,
     // Stock Riven Settings
     pub stock_riven: StockRivenSettings,
+    // Max Warframe Market requests per pass, -1 to disable
+    pub api_budget: i64,
+    // Max active item orders, -1 to disable
+    pub max_orders: i64,
+    // Max active orders keyed by item tag, e.g. "mod", riven auctions count as "riven"
+    pub category_order_caps: HashMap<String, i64>,
+    // Check volatile items every pass and stable items less often
+    pub adaptive_frequency: AdaptiveFrequencySettings,
+    // Pricing compared against the live one, divergences are stored for review
+    pub shadow_strategy: ShadowStrategy,
+    // Minimum time between price updates of an order
+    pub reprice_throttle: RepriceThrottleRule,
+    // Items whose orders are fetched at the same time, 1 to check them one by one
+    pub concurrent_item_checks: i64,
+    // Show me as in game while the scraper runs and restore my status when it stops
+    pub sync_status: bool,
+    // Buy opportunities waiting for approval before the seller is messaged
+    pub action_queue: ActionQueueSettings,
+    // When the scraper cycles run, orders are left alone outside of it
+    pub schedule: ScraperSchedule,
+    // Restarts after network errors, other errors stop the scraper
+    pub supervisor: SupervisorSettings,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SupervisorSettings {
+    // Failed passes in a row before the scraper stops, -1 to retry forever
+    pub max_retries: i64,
+    // Seconds before the first retry, doubled after every failed pass
+    pub base_delay: i64,
+    pub max_delay: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScraperSchedule {
+    // Minimum seconds between the start of two cycles, 0 to run them back to back
+    pub min_cycle_delay: i64,
+    // Local time as HH:MM, the range may span midnight. Empty to disable.
+    pub quiet_hours_start: String,
+    pub quiet_hours_end: String,
+    // Days the scraper does not run, e.g. "Sat"
+    pub disabled_days: Vec<String>,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ActionQueueSettings {
+    pub enabled: bool,
+    // Plat below the closed average a sell order has to be
+    pub min_profit: i64,
+    // <PLAYER_NAME>, <QUANTITY>, <ITEM_NAME> and <PRICE> are replaced
+    pub whisper_template: String,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RepriceThrottleRule {
+    // Minutes an order keeps its price after an update, -1 to disable
+    pub min_interval: i64,
+    // Price change in percent that updates the order anyway, -1 to never override
+    pub override_percent: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AdaptiveFrequencySettings {
+    pub enabled: bool,
+    // Checks of an item the change rate is measured over
+    pub window: i64,
+    // Percent of checks with a price change to count as volatile, checked every pass
+    pub volatile_percent: i64,
+    // Percent of checks with a price change below which an item is stable
+    pub stable_percent: i64,
+    // Passes between checks of items that are neither volatile nor stable
+    pub normal_interval: i64,
+    // Passes between checks of stable items
+    pub stable_interval: i64,
 }
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StockItemSettings {
@@ -44,10 +244,198 @@ pub struct StockItemSettings {
     pub strict_whitelist: bool,
     // What to post sell, buy, or both
     pub order_mode: OrderMode,
+    // Flag sell listings whose price has not changed in a while
+    pub listing_age: ListingAgeRule,
+    // Overrides listing_age for items with the given tag, e.g. "mod" or "arcane_enhancement"
+    pub listing_age_by_category: HashMap<String, ListingAgeRule>,
+    // Items keyed by url name that keep both a buy and a sell order up around the mid price
+    pub market_maker: HashMap<String, MarketMakerRule>,
+    // What to do when a sell order quantity drops on Warframe Market without a local sale
+    pub partial_sale_mode: PartialSaleMode,
+    // What to do when a sell order disappears from Warframe Market without the app deleting it
+    pub closed_order_mode: PartialSaleMode,
+    // Bid the cheapest price that filled at least this often in percent, -1 to match the top bid
+    pub fill_probability_target: i64,
+    // List new stock right away when it is added while the live scraper is stopped
+    pub auto_price_new_items: bool,
+    // Used when the stock prices are rebuilt from the transactions
+    pub cost_basis: CostBasisMethod,
+    // Discount for stock with an upcoming resupply date
+    pub resupply_discount: ResupplyDiscountRule,
+    // Applied last to every buy and sell price
+    pub rounding: RoundingRule,
+    // Overrides rounding for items with the given tag
+    pub rounding_by_category: HashMap<String, RoundingRule>,
+    // Used for items without a profile assigned
+    pub pricing_profile: PricingProfile,
+    // Named pricing strategies, e.g. "aggressive_undercut", assigned below
+    pub pricing_profiles: HashMap<String, PricingProfile>,
+    // Profile names keyed by url name, checked before the ones keyed by tag
+    pub pricing_profile_by_item: HashMap<String, String>,
+    pub pricing_profile_by_category: HashMap<String, String>,
+    // Buy price of items with sellers but no buyers
+    pub opening_bid: OpeningBidRule,
+    // Sources of the average price of interesting items, keyed by qf_dump, wfm_statistics or csv
+    pub price_providers: HashMap<String, PriceProviderRule>,
+    // File read by the csv provider, with url_name and price columns
+    pub price_provider_csv_path: String,
+    // Recognize full sets among the stock parts
+    pub set_pricing: SetPricingRule,
+    // Reason, category and expiry of the blacklist and whitelist entries
+    pub list_entries: Vec<ItemListEntry>,
+    // Adjust sell prices to how many items are listed near the lowest price
+    pub order_book: OrderBookRule,
+    // Which players' orders the buy and sell prices are compared against
+    pub live_order_filter: LiveOrderFilter,
+    // Caps keyed by url name, checked before a new buy order is placed
+    pub exposure_by_item: HashMap<String, ExposureLimit>,
+    // Caps keyed by tag, counting every item with the tag, e.g. "relic" or "primed_mod"
+    pub exposure_by_category: HashMap<String, ExposureLimit>,
+    // Order the interesting items are checked in
+    pub ranking: RankingRule,
+    // Buy the best ducats per plat parts ahead of Baro Ki'Teer
+    pub baro_prep: BaroPrepRule,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BaroPrepRule {
+    pub enabled: bool,
+    // Plat the ducat parts in stock and the open buy orders may add up to
+    pub budget: i64,
+    // Parts worth fewer ducats per plat are not bought
+    pub min_ducats_per_plat: f64,
+    // Copies of each part to hold
+    pub quantity: i64,
+    // RFC3339 arrival of Baro, empty to buy whenever enabled
+    pub arrival: String,
+    // Days before the arrival the buying starts
+    pub days_before: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RankingRule {
+    // Weights of the scoring factors, each factor is scaled to 0-1 over the interesting items
+    pub volume_weight: f64,
+    pub range_weight: f64,
+    pub price_shift_weight: f64,
+    pub spread_weight: f64,
+    // The best scored items are checked before the items left over from the last pass
+    pub top_items: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExposureLimit {
+    // Buy orders up at the same time, -1 to disable
+    pub max_buy_orders: i64,
+    // Items in stock before no more are bought, -1 to disable
+    pub max_owned: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LiveOrderFilter {
+    // Least available status to count, ingame, online or offline
+    pub status: OnlineStatus,
+    pub min_reputation: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderBookRule {
+    pub enabled: bool,
+    // Plat above the lowest sell order that counts as the same price level
+    pub range: i64,
+    // At most this many items within the range is a thin book
+    pub thin_quantity: i64,
+    // Extra plat to undercut by when the book is thin
+    pub thin_undercut: i64,
+    // Keep the listed price when the sellers within the range are all offline
+    pub hold_against_offline: bool,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ItemListEntry {
+    pub url_name: String,
+    // blacklist or whitelist
+    pub list: String,
+    pub reason: String,
+    // Free text to group entries, e.g. "event" or "low_volume"
+    pub category: String,
+    // RFC3339, the entry is removed once it passed
+    pub expires_at: Option<String>,
+    pub created: String,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SetPricingRule {
+    pub enabled: bool,
+    // List complete sets instead of their parts when the set is worth more
+    pub list_as_set: bool,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PricingProfile {
+    // How far below the lowest sell order to list
+    pub undercut: PriceStepRule,
+    // How far above the highest buy order to bid
+    pub overbid: PriceStepRule,
+    // Percent above the lowest sell order to hold out for, 0 to disable
+    pub premium_percent: i64,
+    // Plat over the bought price the sell order never goes below
+    pub min_profit: i64,
+    // Plat over the bought price when nobody else is selling
+    pub no_sellers_markup: i64,
+    // Highest average price to buy at, -1 to use avg_price_cap
+    pub avg_price_cap: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PriceStepRule {
+    pub mode: PriceStepMode,
+    // Plat when fixed, percent of the competing price when percent
+    pub value: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OpeningBidRule {
+    // Plat below the lowest sell order to bid when nobody else is buying
+    pub below_lowest: i64,
+    // The bid never goes under this percent of the lowest sell order
+    pub min_percent: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PriceProviderRule {
+    pub enabled: bool,
+    // Share of the blended price relative to the other enabled providers
+    pub weight: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoundingRule {
+    // Round down to a multiple of this, -1 to disable
+    pub step: i64,
+    // Last digits a price should not end in, e.g. 9, lowered until it doesn't
+    pub avoid_endings: Vec<i64>,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResupplyDiscountRule {
+    // Days before the resupply date the discount starts, -1 to disable
+    pub days: i64,
+    // Discount reached on the resupply date, grows linearly until then
+    pub max_discount_percent: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MarketMakerRule {
+    pub enabled: bool,
+    // Plat below the mid price for the buy order
+    pub buy_spread: i64,
+    // Plat above the mid price for the sell order
+    pub sell_spread: i64,
+    // Stop buying once this many are owned
+    pub max_owned: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ListingAgeRule {
+    // Days a listing can stay unchanged before it needs review, -1 to disable
+    pub max_days: i64,
+    pub action: ListingAgeAction,
+    // Used when action is drop
+    pub drop_percent: i64,
 }
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StockRivenSettings {
     pub range_threshold: i64,
+    // Never list below this percentile of the weapon's weekly sold prices, -1 to disable
+    pub sold_price_floor_percentile: i64,
+    // Price like the live auctions whose stats are at least this percent similar, -1 to follow the lowest
+    pub min_similarity: i64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -87,9 +475,196 @@ impl Default for SettingsState {
                     report_to_wfm: true,
                     auto_trade: true,
                     order_mode: OrderMode::Both,
+                    listing_age: ListingAgeRule {
+                        max_days: -1,
+                        action: ListingAgeAction::Notify,
+                        drop_percent: 10,
+                    },
+                    listing_age_by_category: HashMap::new(),
+                    market_maker: HashMap::new(),
+                    partial_sale_mode: PartialSaleMode::Confirm,
+                    closed_order_mode: PartialSaleMode::Confirm,
+                    fill_probability_target: -1,
+                    auto_price_new_items: false,
+                    cost_basis: CostBasisMethod::WeightedAverage,
+                    resupply_discount: ResupplyDiscountRule {
+                        days: 14,
+                        max_discount_percent: 30,
+                    },
+                    rounding: RoundingRule {
+                        step: -1,
+                        avoid_endings: vec![],
+                    },
+                    rounding_by_category: HashMap::new(),
+                    pricing_profile: PricingProfile {
+                        undercut: PriceStepRule {
+                            mode: PriceStepMode::Match,
+                            value: 0,
+                        },
+                        overbid: PriceStepRule {
+                            mode: PriceStepMode::Match,
+                            value: 0,
+                        },
+                        premium_percent: 0,
+                        min_profit: 10,
+                        no_sellers_markup: 30,
+                        avg_price_cap: -1,
+                    },
+                    pricing_profiles: HashMap::from([
+                        (
+                            "aggressive_undercut".to_string(),
+                            PricingProfile {
+                                undercut: PriceStepRule {
+                                    mode: PriceStepMode::Fixed,
+                                    value: 1,
+                                },
+                                overbid: PriceStepRule {
+                                    mode: PriceStepMode::Fixed,
+                                    value: 1,
+                                },
+                                premium_percent: 0,
+                                min_profit: 5,
+                                no_sellers_markup: 20,
+                                avg_price_cap: -1,
+                            },
+                        ),
+                        (
+                            "match_lowest".to_string(),
+                            PricingProfile {
+                                undercut: PriceStepRule {
+                                    mode: PriceStepMode::Match,
+                                    value: 0,
+                                },
+                                overbid: PriceStepRule {
+                                    mode: PriceStepMode::Match,
+                                    value: 0,
+                                },
+                                premium_percent: 0,
+                                min_profit: 10,
+                                no_sellers_markup: 30,
+                                avg_price_cap: -1,
+                            },
+                        ),
+                        (
+                            "premium_hold".to_string(),
+                            PricingProfile {
+                                undercut: PriceStepRule {
+                                    mode: PriceStepMode::Match,
+                                    value: 0,
+                                },
+                                overbid: PriceStepRule {
+                                    mode: PriceStepMode::Match,
+                                    value: 0,
+                                },
+                                premium_percent: 15,
+                                min_profit: 25,
+                                no_sellers_markup: 50,
+                                avg_price_cap: -1,
+                            },
+                        ),
+                    ]),
+                    pricing_profile_by_item: HashMap::new(),
+                    pricing_profile_by_category: HashMap::new(),
+                    opening_bid: OpeningBidRule {
+                        below_lowest: 40,
+                        min_percent: 33,
+                    },
+                    price_providers: HashMap::from([
+                        (
+                            "qf_dump".to_string(),
+                            PriceProviderRule {
+                                enabled: true,
+                                weight: 1,
+                            },
+                        ),
+                        (
+                            "wfm_statistics".to_string(),
+                            PriceProviderRule {
+                                enabled: false,
+                                weight: 1,
+                            },
+                        ),
+                        (
+                            "csv".to_string(),
+                            PriceProviderRule {
+                                enabled: false,
+                                weight: 1,
+                            },
+                        ),
+                    ]),
+                    price_provider_csv_path: "".to_string(),
+                    set_pricing: SetPricingRule {
+                        enabled: false,
+                        list_as_set: false,
+                    },
+                    list_entries: vec![],
+                    order_book: OrderBookRule {
+                        enabled: false,
+                        range: 5,
+                        thin_quantity: 3,
+                        thin_undercut: 2,
+                        hold_against_offline: true,
+                    },
+                    live_order_filter: LiveOrderFilter {
+                        status: OnlineStatus::InGame,
+                        min_reputation: 0,
+                    },
+                    exposure_by_item: HashMap::new(),
+                    exposure_by_category: HashMap::new(),
+                    ranking: RankingRule {
+                        volume_weight: 1.0,
+                        range_weight: 1.0,
+                        price_shift_weight: 0.5,
+                        spread_weight: 1.0,
+                        top_items: 10,
+                    },
+                    baro_prep: BaroPrepRule {
+                        enabled: false,
+                        budget: 300,
+                        min_ducats_per_plat: 10.0,
+                        quantity: 3,
+                        arrival: "".to_string(),
+                        days_before: 3,
+                    },
                 },
                 stock_riven: StockRivenSettings {
                     range_threshold: 25,
+                    sold_price_floor_percentile: 25,
+                    min_similarity: 60,
+                },
+                api_budget: -1,
+                max_orders: -1,
+                category_order_caps: HashMap::new(),
+                adaptive_frequency: AdaptiveFrequencySettings {
+                    enabled: false,
+                    window: 10,
+                    volatile_percent: 50,
+                    stable_percent: 10,
+                    normal_interval: 2,
+                    stable_interval: 5,
+                },
+                shadow_strategy: ShadowStrategy::Disabled,
+                reprice_throttle: RepriceThrottleRule {
+                    min_interval: -1,
+                    override_percent: 20,
+                },
+                concurrent_item_checks: 3,
+                sync_status: false,
+                action_queue: ActionQueueSettings {
+                    enabled: false,
+                    min_profit: 15,
+                    whisper_template: DEFAULT_BUY_WHISPER_TEMPLATE.to_string(),
+                },
+                schedule: ScraperSchedule {
+                    min_cycle_delay: 0,
+                    quiet_hours_start: "".to_string(),
+                    quiet_hours_end: "".to_string(),
+                    disabled_days: vec![],
+                },
+                supervisor: SupervisorSettings {
+                    max_retries: 5,
+                    base_delay: 10,
+                    max_delay: 600,
                 },
             },
             notifications: Notifications {
@@ -110,7 +685,348 @@ impl Default for SettingsState {
                     user_ids: Some(vec![]),
                 },
             },
+            ee_log_path: "".to_string(),
+            ee_log_paths: vec![],
+            backup: BackupSettings {
+                enabled: false,
+                url: "".to_string(),
+                username: "".to_string(),
+                password: "".to_string(),
+                passphrase: "".to_string(),
+                interval_hours: 24,
+                retention: 7,
+            },
+            feature_flags: HashMap::new(),
+            wfm_retry: WfmRetrySettings {
+                idempotent_retries: 3,
+                non_idempotent_retries: 1,
+                base_delay_ms: 500,
+                max_delay_ms: 8000,
+                breaker_threshold: 5,
+                breaker_cooldown: 60,
+            },
+            wfm_cache_ttl: 60,
+            price_backfill_days: 90,
+            friends: FriendsSettings {
+                names: vec![],
+                avoid_undercut: true,
+            },
+            dashboard: DashboardSettings {
+                enabled: false,
+                port: 8727,
+                allow_lan: false,
+            },
+            whisper_templates: WhisperTemplateSettings {
+                templates: HashMap::from([
+                    ("en".to_string(), DEFAULT_WHISPER_TEMPLATE.to_string()),
+                    ("de".to_string(), "/w <PLAYER_NAME> Hi! Ich möchte verkaufen: <QUANTITY>\"<ITEM_NAME>\" für <PRICE> Platin. (warframe.market)".to_string()),
+                    ("fr".to_string(), "/w <PLAYER_NAME> Salut ! Je voudrais vendre : <QUANTITY>\"<ITEM_NAME>\" pour <PRICE> platine. (warframe.market)".to_string()),
+                    ("es".to_string(), "/w <PLAYER_NAME> ¡Hola! Quiero vender: <QUANTITY>\"<ITEM_NAME>\" por <PRICE> platino. (warframe.market)".to_string()),
+                    ("ru".to_string(), "/w <PLAYER_NAME> Привет! Хочу продать: <QUANTITY>\"<ITEM_NAME>\" за <PRICE> платины. (warframe.market)".to_string()),
+                ]),
+                fallback: "en".to_string(),
+                trade_templates: HashMap::from([
+                    ("buy".to_string(), DEFAULT_BUY_WHISPER_TEMPLATE.to_string()),
+                    ("sell".to_string(), DEFAULT_WHISPER_TEMPLATE.to_string()),
+                ]),
+            },
+            profile_sync: ProfileSyncSettings {
+                folder: "".to_string(),
+                passphrase: "".to_string(),
+                device_name: env::var("COMPUTERNAME")
+                    .or_else(|_| env::var("HOSTNAME"))
+                    .unwrap_or_default(),
+                last_synced_hash: "".to_string(),
+            },
+            deal_hunter: DealHunterSettings {
+                enabled: false,
+                discount_percent: 30,
+                items: vec![],
+                max_items: 20,
+                min_volume: 10,
+                whisper_template: DEFAULT_BUY_WHISPER_TEMPLATE.to_string(),
+            },
+            price_history: PriceHistoryRetention {
+                max_days: 90,
+                max_entries: 500,
+            },
+            trade_watcher: TradeWatcherSettings {
+                auto_stock: false,
+                confirm_ambiguous: true,
+            },
+            logging: LoggingSettings {
+                level: LogLevel::Debug,
+                component_levels: HashMap::new(),
+                max_file_size: 5120,
+                max_files: 3,
+            },
+        }
+    }
+}
+impl WhisperTemplateSettings {
+    // Language of the first template matching the given locales, e.g. "zh-hans" matches "zh"
+    pub fn get_language(&self, locales: &[&str]) -> String {
+        for locale in locales.iter().map(|l| l.trim().to_lowercase()) {
+            if self.templates.contains_key(&locale) {
+                return locale;
+            }
+            let base = locale.split('-').next().unwrap_or("").to_string();
+            if self.templates.contains_key(&base) {
+                return base;
+            }
+        }
+        self.fallback.clone()
+    }
+    pub fn render(
+        &self,
+        language: &str,
+        player_name: &str,
+        quantity: &str,
+        item_name: &str,
+        price: i64,
+    ) -> String {
+        self.templates
+            .get(language)
+            .or(self.templates.get(&self.fallback))
+            .map(|t| t.as_str())
+            .unwrap_or(DEFAULT_WHISPER_TEMPLATE)
+            .replace("<PLAYER_NAME>", player_name)
+            .replace("<QUANTITY>", quantity)
+            .replace("<ITEM_NAME>", item_name)
+            .replace("<PRICE>", &price.to_string())
+    }
+    // None when there is no trade template with the name
+    pub fn render_trade(
+        &self,
+        template: &str,
+        player_name: &str,
+        quantity: i64,
+        item_name: &str,
+        price: i64,
+        rank: Option<i64>,
+    ) -> Option<String> {
+        let quantity = if quantity > 1 {
+            format!("{} x ", quantity)
+        } else {
+            "".to_string()
+        };
+        let rank = rank.map(|rank| rank.to_string()).unwrap_or_default();
+        Some(
+            self.trade_templates
+                .get(template)?
+                .replace("<PLAYER_NAME>", player_name)
+                .replace("<QUANTITY>", &quantity)
+                .replace("<ITEM_NAME>", item_name)
+                .replace("<PRICE>", &price.to_string())
+                .replace("<RANK>", &rank),
+        )
+    }
+}
+impl ExposureLimit {
+    // What the limit is reached by, None while more may be bought
+    pub fn get_reason(&self, buy_orders: i64, owned: i64) -> Option<String> {
+        if self.max_buy_orders >= 0 && buy_orders >= self.max_buy_orders {
+            return Some(format!(
+                "{} of {} buy orders",
+                buy_orders, self.max_buy_orders
+            ));
+        }
+        if self.max_owned >= 0 && owned >= self.max_owned {
+            return Some(format!("{} of {} owned", owned, self.max_owned));
+        }
+        None
+    }
+}
+impl FriendsSettings {
+    pub fn is_friend(&self, ingame_name: &str) -> bool {
+        self.names
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(ingame_name.trim()))
+    }
+    // Adds the names that are not in the list yet, returns how many were added
+    pub fn add(&mut self, names: Vec<String>) -> usize {
+        let mut added = 0;
+        for name in names {
+            let name = name.trim().to_string();
+            if name.is_empty() || self.is_friend(&name) {
+                continue;
+            }
+            self.names.push(name);
+            added += 1;
+        }
+        added
+    }
+}
+impl LiveScraperSettings {
+    // -1 when the category has no cap
+    pub fn get_category_order_cap(&self, category: &str) -> i64 {
+        *self.category_order_caps.get(category).unwrap_or(&-1)
+    }
+}
+impl StockItemSettings {
+    fn get_list_mut(&mut self, list: &str) -> Option<&mut Vec<String>> {
+        match list {
+            "blacklist" => Some(&mut self.blacklist),
+            "whitelist" => Some(&mut self.whitelist),
+            _ => None,
+        }
+    }
+    // Adds the item to the list, replacing the entry it already had there
+    pub fn add_list_entry(&mut self, entry: ItemListEntry) -> Result<(), AppError> {
+        let items = match self.get_list_mut(&entry.list) {
+            Some(items) => items,
+            None => {
+                return Err(AppError::new(
+                    "Settings",
+                    eyre!("Unknown item list {}", entry.list),
+                ))
+            }
+        };
+        if !items.contains(&entry.url_name) {
+            items.push(entry.url_name.clone());
+        }
+        self.list_entries
+            .retain(|e| !(e.list == entry.list && e.url_name == entry.url_name));
+        self.list_entries.push(entry);
+        Ok(())
+    }
+    pub fn remove_list_entry(&mut self, list: &str, url_name: &str) {
+        if let Some(items) = self.get_list_mut(list) {
+            items.retain(|item| item != url_name);
+        }
+        self.list_entries
+            .retain(|e| !(e.list == list && e.url_name == url_name));
+    }
+    // Removes the entries past their expiry from the lists, returns them
+    pub fn prune_expired_list_entries(&mut self) -> Vec<ItemListEntry> {
+        let now = chrono::Utc::now();
+        let expired: Vec<ItemListEntry> = self
+            .list_entries
+            .iter()
+            .filter(
+                |e| match e.expires_at.as_deref().and_then(helper::parse_timestamp) {
+                    Some(expires_at) => expires_at <= now,
+                    None => false,
+                },
+            )
+            .cloned()
+            .collect();
+        for entry in expired.iter() {
+            self.remove_list_entry(&entry.list, &entry.url_name);
+        }
+        expired
+    }
+    pub fn get_market_maker_rule(&self, url_name: &str) -> Option<MarketMakerRule> {
+        self.market_maker
+            .get(url_name)
+            .filter(|rule| rule.enabled)
+            .cloned()
+    }
+    // Rule for the first matching tag, falls back to the global rule
+    pub fn get_listing_age_rule(&self, tags: &str) -> ListingAgeRule {
+        for tag in tags.split(',') {
+            if let Some(rule) = self.listing_age_by_category.get(tag) {
+                return rule.clone();
+            }
+        }
+        self.listing_age.clone()
+    }
+    // Rule for the first matching tag, falls back to the global rule
+    pub fn get_rounding_rule(&self, tags: &[String]) -> RoundingRule {
+        for tag in tags {
+            if let Some(rule) = self.rounding_by_category.get(tag) {
+                return rule.clone();
+            }
+        }
+        self.rounding.clone()
+    }
+    // Profile assigned to the item, then to its first matching tag, falls back to the global one.
+    // Assignments to a profile that no longer exists are skipped.
+    pub fn get_pricing_profile(&self, url_name: &str, tags: &[String]) -> (String, PricingProfile) {
+        let assigned = std::iter::once(self.pricing_profile_by_item.get(url_name))
+            .chain(
+                tags.iter()
+                    .map(|tag| self.pricing_profile_by_category.get(tag)),
+            )
+            .flatten();
+        for name in assigned {
+            if let Some(profile) = self.pricing_profiles.get(name) {
+                return (name.clone(), profile.clone());
+            }
+        }
+        ("default".to_string(), self.pricing_profile.clone())
+    }
+    pub fn get_avg_price_cap(&self, profile: &PricingProfile) -> i64 {
+        if profile.avg_price_cap >= 0 {
+            profile.avg_price_cap
+        } else {
+            self.avg_price_cap
+        }
+    }
+}
+impl PricingProfile {
+    // Where to list against the cheapest other seller, before the profit floor
+    pub fn get_sell_price(&self, lowest_price: i64) -> i64 {
+        if self.premium_percent > 0 {
+            lowest_price + (lowest_price * self.premium_percent + 99) / 100
+        } else {
+            (lowest_price - self.undercut.get_step(lowest_price)).max(1)
+        }
+    }
+    // Where to bid against the highest other buyer, before the price cap
+    pub fn get_buy_price(&self, highest_price: i64) -> i64 {
+        highest_price + self.overbid.get_step(highest_price)
+    }
+}
+impl PriceStepRule {
+    // Plat to move past the competing price, at least 1 unless matching
+    pub fn get_step(&self, price: i64) -> i64 {
+        match self.mode {
+            PriceStepMode::Fixed => self.value.max(1),
+            PriceStepMode::Percent => ((price * self.value.max(0) + 99) / 100).max(1),
+            PriceStepMode::Match | PriceStepMode::Unknown(_) => 0,
+        }
+    }
+}
+impl OpeningBidRule {
+    pub fn get_price(&self, lowest_price: i64) -> i64 {
+        (lowest_price - self.below_lowest).max(lowest_price * self.min_percent / 100)
+    }
+}
+impl BaroPrepRule {
+    // Enabled, and Baro arrives within days_before or no arrival is set
+    pub fn is_active(&self) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if self.arrival.is_empty() {
+            return true;
+        }
+        match helper::parse_timestamp(&self.arrival) {
+            Some(arrival) => {
+                let now = chrono::Utc::now();
+                now < arrival && arrival - now <= chrono::Duration::days(self.days_before)
+            }
+            None => false,
+        }
+    }
+    // Plat per unit the Baro buying pays at most for a part worth this many ducats
+    pub fn get_max_price(&self, ducats: i64) -> i64 {
+        (ducats as f64 / self.min_ducats_per_plat.max(0.01)).floor() as i64
+    }
+}
+impl RoundingRule {
+    pub fn apply(&self, price: i64) -> i64 {
+        let step = self.step.max(1);
+        let mut price = if step > 1 && price >= step {
+            price / step * step
+        } else {
+            price
+        };
+        while price > step && self.avoid_endings.contains(&(price % 10)) {
+            price -= step;
         }
+        price
     }
 }
 impl SettingsState {
@@ -16,6 +16,8 @@ pub enum TradeClassification {
     Sale,
     Purchase,
     Trade,
+    // Items given away without anything in return
+    Gift,
     Unknown,
 }
 
@@ -35,6 +37,17 @@ impl WarframeLanguage {
             _ => WarframeLanguage::Unknown,
         }
     }
+    // Language code Warframe Market uses for the names, None when unknown
+    pub fn as_code(&self) -> Option<&str> {
+        match self {
+            WarframeLanguage::English => Some("en"),
+            WarframeLanguage::French => Some("fr"),
+            WarframeLanguage::Spanish => Some("es"),
+            WarframeLanguage::German => Some("de"),
+            WarframeLanguage::Russian => Some("ru"),
+            WarframeLanguage::Unknown => None,
+        }
+    }
 }
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct RivenTypeInfo {
@@ -115,6 +128,8 @@ pub struct Item {
     pub tags: Option<Vec<String>>,
     pub mod_max_rank: Option<i64>,
     pub subtypes: Option<Vec<String>>,
+    // What Baro Ki'Teer pays for the item, None when it can not be traded for ducats
+    pub ducats: Option<i64>,
 }
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct ItemDetails {
@@ -126,8 +141,28 @@ pub struct ItemInfo {
     #[serde(rename = "id")]
     pub id: String,
 
+    #[serde(rename = "url_name")]
+    pub url_name: Option<String>,
+
+    #[serde(rename = "set_root")]
+    pub set_root: Option<bool>,
+
+    #[serde(rename = "quantity_for_set")]
+    pub quantity_for_set: Option<i64>,
+
     #[serde(rename = "mod_max_rank")]
     pub mod_max_rank: Option<f64>,
+
+    #[serde(rename = "thumb")]
+    pub thumb: Option<String>,
+
+    #[serde(rename = "en")]
+    pub en: Option<ItemInfoLocale>,
+}
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ItemInfoLocale {
+    #[serde(rename = "wiki_link")]
+    pub wiki_link: Option<String>,
 }
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Order {
@@ -224,6 +259,17 @@ pub struct Ordres {
     pub buy_orders: Vec<Order>,
 }
 
+// The cheap end of the sell side of an item's order book
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct OrderBookDepth {
+    pub lowest_price: i64,
+    // Items listed within the range of the lowest price
+    pub quantity: i64,
+    pub orders: i64,
+    // Distinct sellers within the range that are in game or on the site
+    pub online_sellers: i64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Transaction {
     pub id: i64,
@@ -297,8 +343,9 @@ pub struct User {
     #[serde(rename = "reputation")]
     pub reputation: f64,
 
-    // #[serde(rename = "locale")]
-    // pub locale: String,
+    // Used to pick the language of whispers to the player
+    #[serde(rename = "locale", default)]
+    pub locale: Option<String>,
 
     // #[serde(rename = "avatar")]
     // pub avatar: String,
@@ -0,0 +1,296 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    auth::AuthState,
+    cache::client::CacheClient,
+    database::client::DBClient,
+    enums::{ErrorKind, LogLevel},
+    error::AppError,
+    helper, logger,
+    settings::SettingsState,
+    PACKAGEINFO,
+};
+
+// The bundle never grows past this, the newest logs are kept first
+const SIZE_CAP: usize = 20 * 1024 * 1024;
+// Only the tail of a log file bigger than this is included
+const FILE_CAP: usize = 2 * 1024 * 1024;
+const LOG_DAYS: i64 = 7;
+const MAX_ERROR_LINES: usize = 500;
+// Settings keys that can hold credentials or personal data
+const SECRET_KEYS: [&str; 7] = [
+    "password",
+    "passphrase",
+    "token",
+    "secret",
+    "webhook",
+    "user_ids",
+    "username",
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SupportBundleEntry {
+    pub name: String,
+    pub size: usize,
+}
+
+// What will be in the bundle, shown to the user before they agree to create it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SupportBundleManifest {
+    pub entries: Vec<SupportBundleEntry>,
+    pub total_size: usize,
+    pub size_cap: usize,
+    // Files left out because of the size cap
+    pub skipped: Vec<String>,
+}
+
+pub struct SupportBundle {
+    files: Vec<(String, Vec<u8>)>,
+    skipped: Vec<String>,
+}
+
+impl SupportBundle {
+    pub async fn collect(
+        settings: &SettingsState,
+        auth: &AuthState,
+        db: &DBClient,
+        cache: &CacheClient,
+    ) -> Result<Self, AppError> {
+        let mut bundle = SupportBundle {
+            files: vec![],
+            skipped: vec![],
+        };
+        let system = Self::get_system_info(db, cache).await?;
+        bundle.add("system.json", Self::to_json(&system)?);
+
+        let mut settings = serde_json::to_value(settings)
+            .map_err(|e| AppError::new("SupportBundle", eyre!(e.to_string())))?;
+        Self::strip_secrets(&mut settings);
+        bundle.add("settings.json", Self::to_json(&settings)?);
+
+        let redactions = Self::get_redactions(auth);
+        let logs = Self::get_log_files();
+        bundle.add(
+            "recent_errors.log",
+            Self::get_recent_errors(&logs, &redactions).into_bytes(),
+        );
+        // The live_scraper.log files are the scraper run reports
+        for (name, path) in logs {
+            let content = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+            let mut content = Self::redact(&content, &redactions);
+            if content.len() > FILE_CAP {
+                let mut start = content.len() - FILE_CAP;
+                while !content.is_char_boundary(start) {
+                    start += 1;
+                }
+                content = content[start..].to_string();
+            }
+            bundle.add(&name, content.into_bytes());
+        }
+        Ok(bundle)
+    }
+
+    fn add(&mut self, name: &str, content: Vec<u8>) {
+        if self.get_total_size() + content.len() > SIZE_CAP {
+            self.skipped.push(name.to_string());
+            return;
+        }
+        self.files.push((name.to_string(), content));
+    }
+
+    fn get_total_size(&self) -> usize {
+        self.files.iter().map(|(_, content)| content.len()).sum()
+    }
+
+    fn to_json(value: &Value) -> Result<Vec<u8>, AppError> {
+        serde_json::to_vec_pretty(value)
+            .map_err(|e| AppError::new("SupportBundle", eyre!(e.to_string())))
+    }
+
+    async fn get_system_info(db: &DBClient, cache: &CacheClient) -> Result<Value, AppError> {
+        let connection = db.connection.lock().unwrap().clone();
+        let schema_version = sqlx::query_scalar::<_, i64>("PRAGMA user_version")
+            .fetch_one(&connection)
+            .await
+            .map_err(|e| AppError::new("SupportBundle", eyre!(e.to_string())))?;
+        let tables = sqlx::query_scalar::<_, String>(
+            "SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name",
+        )
+        .fetch_all(&connection)
+        .await
+        .map_err(|e| AppError::new("SupportBundle", eyre!(e.to_string())))?;
+        let version = PACKAGEINFO
+            .lock()?
+            .clone()
+            .map(|info| info.version.to_string());
+        Ok(json!({
+            "app_version": version,
+            "os": std::env::consts::OS,
+            "arch": std::env::consts::ARCH,
+            "created": helper::get_utc_timestamp(),
+            "schema_version": schema_version,
+            "tables": tables,
+            // The cache is identified by when it was downloaded
+            "cache_id": cache.cache_data.lock()?.last_refresh.clone(),
+        }))
+    }
+
+    fn strip_secrets(value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    let key = key.to_lowercase();
+                    if SECRET_KEYS.iter().any(|secret| key.contains(secret)) {
+                        *value = json!("<redacted>");
+                    } else {
+                        Self::strip_secrets(value);
+                    }
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(Self::strip_secrets),
+            _ => {}
+        }
+    }
+
+    fn get_redactions(auth: &AuthState) -> Vec<String> {
+        let mut redactions = vec![auth.ingame_name.clone(), auth.id.clone()];
+        if let Some(token) = &auth.access_token {
+            redactions.push(token.clone());
+        }
+        redactions.retain(|value| value.len() > 2);
+        redactions
+    }
+
+    fn redact(content: &str, redactions: &Vec<String>) -> String {
+        let mut content = content.to_string();
+        for value in redactions {
+            content = content.replace(value.as_str(), "<redacted>");
+        }
+        let patterns = [
+            r"JWT [A-Za-z0-9._\-]+",
+            r"https://(discord|discordapp)\.com/api/webhooks/\S+",
+        ];
+        for pattern in patterns {
+            let re = regex::Regex::new(pattern).unwrap();
+            content = re.replace_all(&content, "<redacted>").to_string();
+        }
+        content
+    }
+
+    // Log files of the last days keyed by their path in the bundle, newest day first
+    fn get_log_files() -> Vec<(String, PathBuf)> {
+        let mut logs_path = logger::get_log_forlder();
+        logs_path.pop();
+        let mut days: Vec<PathBuf> = match fs::read_dir(&logs_path) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_dir())
+                .collect(),
+            Err(_) => return vec![],
+        };
+        days.sort();
+        days.reverse();
+
+        let mut files = vec![];
+        for day in days.into_iter().take(LOG_DAYS as usize) {
+            let day_name = Self::get_file_name(&day);
+            let mut day_files: Vec<PathBuf> = match fs::read_dir(&day) {
+                Ok(entries) => entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.is_file())
+                    .collect(),
+                Err(_) => continue,
+            };
+            day_files.sort();
+            for file in day_files {
+                files.push((
+                    format!("logs/{}/{}", day_name, Self::get_file_name(&file)),
+                    file,
+                ));
+            }
+        }
+        files
+    }
+
+    fn get_file_name(path: &Path) -> String {
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    fn get_recent_errors(logs: &Vec<(String, PathBuf)>, redactions: &Vec<String>) -> String {
+        let mut lines = vec![];
+        for (name, path) in logs {
+            let content = match fs::read_to_string(path) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+            for line in content.lines() {
+                if line.contains("[ERROR]") || line.contains("[CRITICAL]") {
+                    lines.push(format!("{}: {}", name, Self::redact(line, redactions)));
+                }
+                if lines.len() >= MAX_ERROR_LINES {
+                    return lines.join("\n");
+                }
+            }
+        }
+        lines.join("\n")
+    }
+
+    pub fn get_manifest(&self) -> SupportBundleManifest {
+        SupportBundleManifest {
+            entries: self
+                .files
+                .iter()
+                .map(|(name, content)| SupportBundleEntry {
+                    name: name.clone(),
+                    size: content.len(),
+                })
+                .collect(),
+            total_size: self.get_total_size(),
+            size_cap: SIZE_CAP,
+            skipped: self.skipped.clone(),
+        }
+    }
+
+    // Writes the bundle to the desktop, only after the user agreed to what is in it
+    pub fn write(&self, consent: bool) -> Result<String, AppError> {
+        if !consent {
+            return Err(AppError::new_with_level(
+                "SupportBundle",
+                eyre!("The support bundle needs the user's consent"),
+                LogLevel::Warning,
+            )
+            .with_kind(ErrorKind::Validation));
+        }
+        let zip_path = helper::get_desktop_path().join(format!(
+            "quantframe_support_{}.zip",
+            chrono::Local::now().format("%Y%m%d%H%M%S")
+        ));
+        let file = File::create(&zip_path)
+            .map_err(|e| AppError::new("SupportBundle", eyre!(e.to_string())))?;
+        let mut zip = ZipWriter::new(file);
+        let options = FileOptions::default().compression_method(CompressionMethod::DEFLATE);
+        for (name, content) in self.files.iter() {
+            zip.start_file(name.as_str(), options)
+                .map_err(|e| AppError::new("SupportBundle", eyre!(e.to_string())))?;
+            zip.write_all(content)
+                .map_err(|e| AppError::new("SupportBundle", eyre!(e.to_string())))?;
+        }
+        zip.finish()
+            .map_err(|e| AppError::new("SupportBundle", eyre!(e.to_string())))?;
+        Ok(zip_path.to_string_lossy().to_string())
+    }
+}
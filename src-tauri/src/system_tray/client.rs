@@ -1,19 +1,65 @@
-use tauri::{CustomMenuItem, SystemTrayMenu};
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+use tauri::{AppHandle, CustomMenuItem, Manager, SystemTrayMenu, SystemTrayMenuItem};
+
+use crate::{error, helper, live_scraper::client::LiveScraperClient};
 
 pub fn get_tray_menu() -> SystemTrayMenu {
+    let status = CustomMenuItem::new("status".to_string(), "Live scraper stopped").disabled();
+    let last_error = CustomMenuItem::new("last_error".to_string(), "No errors").disabled();
+    let toggle = CustomMenuItem::new("toggle".to_string(), "Start live scraper");
+    let pause = CustomMenuItem::new("pause".to_string(), "Pause live scraper").disabled();
+    let show = CustomMenuItem::new("show".to_string(), "Show");
     let quit = CustomMenuItem::new("quit".to_string(), "Quit");
     let hide = CustomMenuItem::new("hide".to_string(), "Hide");
-    let tray_menu = SystemTrayMenu::new().add_item(quit).add_item(hide);
-    tray_menu
+    SystemTrayMenu::new()
+        .add_item(status)
+        .add_item(last_error)
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(toggle)
+        .add_item(pause)
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(show)
+        .add_item(hide)
+        .add_item(quit)
 }
 
-pub fn get_tray_event(event: String) -> String {
+pub fn get_tray_event(app: &AppHandle, event: String) -> String {
     match event.as_str() {
         "quit" => {
             std::process::exit(0);
         }
         "hide" => {
-            print!("hide");
+            if let Some(window) = app.get_window("main") {
+                let _ = window.hide();
+            }
+        }
+        "show" => {
+            if let Some(window) = app.get_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }
+        "toggle" => {
+            let live_scraper = app.state::<Arc<Mutex<LiveScraperClient>>>();
+            let mut live_scraper = live_scraper.lock().unwrap();
+            if live_scraper.is_running() {
+                live_scraper.stop_loop();
+            } else if let Err(e) = live_scraper.start_loop() {
+                error::create_log_file("system_tray.log".to_string(), &e);
+            }
+            update_status(&live_scraper, None);
+        }
+        "pause" => {
+            let live_scraper = app.state::<Arc<Mutex<LiveScraperClient>>>();
+            let live_scraper = live_scraper.lock().unwrap().clone();
+            if live_scraper.is_paused() {
+                live_scraper.resume();
+            } else {
+                live_scraper.pause();
+            }
+            update_status(&live_scraper, None);
         }
         _ => {
             print!("unknown");
@@ -21,3 +67,67 @@ pub fn get_tray_event(event: String) -> String {
     }
     event
 }
+
+// Short text for a progress message of the live scraper, e.g. "item.checking: Ash Prime Set (3/40)"
+fn format_progress(i18n_key: &str, values: &Option<Value>) -> String {
+    let values = match values {
+        Some(values) => values,
+        None => return i18n_key.to_string(),
+    };
+    let mut text = i18n_key.to_string();
+    if let Some(name) = values["name"].as_str() {
+        text = format!("{}: {}", text, name);
+    }
+    if let (Some(count), Some(total)) = (values["count"].as_i64(), values["total"].as_i64()) {
+        text = format!("{} ({}/{})", text, count, total);
+    }
+    text
+}
+
+// Keeps the tray menu and tooltip in line with the live scraper, the progress is left as is when None
+pub fn update_status(live_scraper: &LiveScraperClient, progress: Option<(&str, &Option<Value>)>) {
+    let window = helper::WINDOW.lock().unwrap().clone();
+    let tray = match window {
+        Some(window) => window.app_handle().tray_handle(),
+        None => return,
+    };
+    let state = if !live_scraper.is_running() {
+        "Live scraper stopped"
+    } else if live_scraper.is_paused() {
+        "Live scraper paused"
+    } else {
+        "Live scraper running"
+    };
+    let _ = tray.get_item("status").set_title(state);
+    let _ = tray
+        .get_item("toggle")
+        .set_title(match live_scraper.is_running() {
+            true => "Stop live scraper",
+            false => "Start live scraper",
+        });
+    let _ = tray
+        .get_item("pause")
+        .set_enabled(live_scraper.is_running());
+    let _ = tray
+        .get_item("pause")
+        .set_title(match live_scraper.is_paused() {
+            true => "Resume live scraper",
+            false => "Pause live scraper",
+        });
+    let last_error = live_scraper.last_error.lock().unwrap().clone();
+    let _ = tray
+        .get_item("last_error")
+        .set_title(last_error.unwrap_or_else(|| "No errors".to_string()));
+    match progress {
+        Some((i18n_key, values)) if live_scraper.is_running() && !i18n_key.is_empty() => {
+            let _ = tray.set_tooltip(&format!(
+                "Quantframe - {}",
+                format_progress(i18n_key, values)
+            ));
+        }
+        Some(_) => {
+            let _ = tray.set_tooltip(&format!("Quantframe - {}", state));
+        }
+        None => {}
+    }
+}
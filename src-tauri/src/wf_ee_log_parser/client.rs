@@ -1,11 +1,10 @@
 use crate::cache::client::CacheClient;
+use crate::database::client::DBClient;
 use crate::error::AppError;
 use crate::handler::MonitorHandler;
 use crate::settings::SettingsState;
 use crate::{helper, logger};
 use serde_json::json;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader, Seek, SeekFrom}; // Add Seek here
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
@@ -14,20 +13,34 @@ use std::time::Duration;
 
 use super::events::on_new_conversation::OnNewConversationEvent;
 use super::events::on_new_trading::OnTradingEvent;
+use super::paths;
+use super::watcher::{FileWatcher, LogWatcher};
+
+#[derive(Clone, Debug)]
+struct LogSource {
+    name: String,
+    watcher: Arc<dyn LogWatcher>,
+    cold_start: Arc<AtomicBool>,
+    event_trading: Arc<Mutex<OnTradingEvent>>,
+}
 
 #[derive(Clone, Debug)]
 pub struct EELogParser {
     is_running: Arc<AtomicBool>,
-    wf_ee_path: PathBuf,
     component: String,
-    last_file_size: Arc<Mutex<u64>>,
-    last_line_index: Arc<Mutex<usize>>,
     handle: Arc<Mutex<Option<JoinHandle<()>>>>,
-    cold_start: Arc<AtomicBool>,
     pub settings: Arc<Mutex<crate::settings::SettingsState>>,
+    mh: Arc<Mutex<MonitorHandler>>,
+    cache: Arc<Mutex<CacheClient>>,
+    db: Arc<Mutex<DBClient>>,
+    // Every EE.log we are watching, the default install is always the first one
+    sources: Arc<Mutex<Vec<LogSource>>>,
+    // The configured default path and the EE.log it resolved to, detected again when it changes
+    default_path: Arc<Mutex<Option<(String, PathBuf)>>>,
+    // Trades seen across all sources with the source they came from, used to drop duplicates
+    recent_trades: Arc<Mutex<Vec<(i64, String, String)>>>,
     // Events
     event_conversation: Arc<Mutex<OnNewConversationEvent>>,
-    event_trading: Arc<Mutex<OnTradingEvent>>,
 }
 
 impl EELogParser {
@@ -35,31 +48,98 @@ impl EELogParser {
         settings: Arc<Mutex<SettingsState>>,
         mh: Arc<Mutex<MonitorHandler>>,
         cache: Arc<Mutex<CacheClient>>,
+        db: Arc<Mutex<DBClient>>,
     ) -> Self {
-        let wf_ee_path = helper::get_app_local_path().join("Warframe").join("EE.log");
-        Self {
+        let wf_ee_path = paths::get_ee_log_path(&settings.lock().unwrap().ee_log_path);
+        let parser = Self {
             is_running: Arc::new(AtomicBool::new(false)),
             component: "EELogParser".to_string(),
-            wf_ee_path: wf_ee_path.clone(),
-            last_file_size: Arc::new(Mutex::new(0)),
-            last_line_index: Arc::new(Mutex::new(0)),
             handle: Arc::new(Mutex::new(None)),
-            cold_start: Arc::new(AtomicBool::new(true)),
             settings: Arc::clone(&settings),
+            mh: Arc::clone(&mh),
+            cache: Arc::clone(&cache),
+            db: Arc::clone(&db),
+            sources: Arc::new(Mutex::new(vec![])),
+            default_path: Arc::new(Mutex::new(None)),
+            recent_trades: Arc::new(Mutex::new(vec![])),
             event_conversation: Arc::new(Mutex::new(OnNewConversationEvent::new(
                 Arc::clone(&settings),
                 Arc::clone(&mh),
                 wf_ee_path.clone(),
             ))),
+        };
+        parser.sync_sources();
+        parser
+    }
+
+    fn get_default_path(&self) -> PathBuf {
+        let configured = self.settings.lock().unwrap().ee_log_path.clone();
+        let mut default_path = self.default_path.lock().unwrap();
+        match default_path.as_ref() {
+            Some((loaded, path)) if loaded == &configured => path.clone(),
+            _ => {
+                let path = paths::get_ee_log_path(&configured);
+                *default_path = Some((configured, path.clone()));
+                path
+            }
+        }
+    }
+
+    fn create_source(&self, name: &str, path: PathBuf) -> LogSource {
+        LogSource {
+            name: name.to_string(),
+            watcher: Arc::new(FileWatcher::new(path.clone())),
+            cold_start: Arc::new(AtomicBool::new(true)),
             event_trading: Arc::new(Mutex::new(OnTradingEvent::new(
-                Arc::clone(&settings),
-                Arc::clone(&mh),
-                Arc::clone(&cache),
-                wf_ee_path.clone(),
+                Arc::clone(&self.settings),
+                Arc::clone(&self.mh),
+                Arc::clone(&self.cache),
+                Arc::clone(&self.db),
+                path,
+                name.to_string(),
+                Arc::clone(&self.recent_trades),
             ))),
         }
     }
 
+    // Keep the watched sources in line with the settings, so paths can be added or removed at runtime
+    fn sync_sources(&self) {
+        let extra_paths = self.settings.lock().unwrap().ee_log_paths.clone();
+        let mut wanted: Vec<(String, PathBuf)> =
+            vec![("default".to_string(), self.get_default_path())];
+        for path in extra_paths {
+            let path = PathBuf::from(path.trim());
+            if path.as_os_str().is_empty() || wanted.iter().any(|(_, p)| Self::same_file(p, &path))
+            {
+                continue;
+            }
+            wanted.push((path.to_str().unwrap_or("").to_string(), path));
+        }
+
+        let mut sources = self.sources.lock().unwrap();
+        sources.retain(|s| {
+            wanted
+                .iter()
+                .any(|(name, path)| name == &s.name && path.as_path() == s.watcher.path())
+        });
+        for (name, path) in wanted {
+            if !sources.iter().any(|s| s.name == name) {
+                logger::info_con(
+                    self.component.as_str(),
+                    format!("Watching EE.log source: {}", path.to_str().unwrap_or("")).as_str(),
+                );
+                sources.push(self.create_source(&name, path));
+            }
+        }
+    }
+
+    fn same_file(a: &PathBuf, b: &PathBuf) -> bool {
+        match (a.canonicalize(), b.canonicalize()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => a == b,
+        }
+    }
+
     pub fn start_loop(&mut self) {
         logger::info_con(self.component.as_str(), "Starting EE Log Parser");
         let is_running = Arc::clone(&self.is_running);
@@ -69,11 +149,15 @@ impl EELogParser {
 
         let handle = thread::spawn(move || {
             while is_running.load(Ordering::SeqCst) {
-                match scraper.check() {
-                    Ok(_) => {
-                        scraper.cold_start.store(false, Ordering::SeqCst);
+                scraper.sync_sources();
+                let sources = scraper.sources.lock().unwrap().clone();
+                for source in sources {
+                    match scraper.check(&source) {
+                        Ok(_) => {
+                            source.cold_start.store(false, Ordering::SeqCst);
+                        }
+                        Err(_) => {}
                     }
-                    Err(_) => {}
                 }
                 thread::sleep(Duration::from_secs(1));
             }
@@ -92,12 +176,14 @@ impl EELogParser {
         self.is_running.load(Ordering::SeqCst)
     }
 
-    fn check(&self) -> Result<(), AppError> {
-        let new_lines_result = self.read_new_lines(self.cold_start.load(Ordering::SeqCst));
+    fn check(&self, source: &LogSource) -> Result<(), AppError> {
+        let new_lines_result = source
+            .watcher
+            .read_new_lines(source.cold_start.load(Ordering::SeqCst));
 
         // Events to check
         let event_conversation = self.event_conversation.lock()?.clone();
-        let mut event_trading = self.event_trading.lock()?;
+        let mut event_trading = source.event_trading.lock()?;
 
         match new_lines_result {
             Ok(new_lines) => {
@@ -111,7 +197,10 @@ impl EELogParser {
                 }
             }
             Err(err) => {
-                helper::send_message_to_window("EELogParser", Some(json!({ "error": "err" })));
+                helper::send_message_to_window(
+                    "EELogParser",
+                    Some(json!({ "error": "err", "source": source.name })),
+                );
                 Err(AppError::new(
                     self.component.as_str(),
                     eyre::eyre!(err.to_string()),
@@ -120,39 +209,4 @@ impl EELogParser {
         }
         Ok(())
     }
-
-    fn read_new_lines(&self, is_starting: bool) -> io::Result<Vec<(usize, String)>> {
-        let mut new_lines: Vec<(usize, String)> = Vec::new();
-        let mut file = File::open(&self.wf_ee_path)?;
-
-        let metadata = file.metadata()?;
-        let current_file_size = metadata.len();
-
-        if is_starting {
-            *self.last_file_size.lock().unwrap() = current_file_size;
-            return Ok(new_lines);
-        }
-
-        let mut last_file_size = self.last_file_size.lock().unwrap();
-        let mut last_line_index = self.last_line_index.lock().unwrap();
-        if *last_file_size > current_file_size || current_file_size < *last_file_size {
-            *last_file_size = 0;
-            *last_line_index = 0;
-        }
-
-        // Now we can call seek on the file because we have Seek in our scope
-        file.seek(SeekFrom::Start(*last_file_size))?;
-
-        let reader = BufReader::new(file);
-
-        for (_, line) in reader.lines().enumerate() {
-            *last_line_index += 1;
-            if let Ok(line) = line {
-                new_lines.push((last_line_index.clone(), line)); // Adding line index
-            }
-        }
-
-        *last_file_size = current_file_size;
-        Ok(new_lines)
-    }
 }
@@ -5,19 +5,29 @@ use std::{
 };
 
 use crate::{
-    cache::client::CacheClient,
-    database::client::DBClient,
+    cache::{client::CacheClient, modules::feature_flag::TRADE_OCR},
+    database::{
+        client::DBClient,
+        modules::{tag::ENTITY_TRANSACTION, undo::UndoChange},
+    },
+    enums::{ErrorKind, LogLevel},
     error::{self, AppError},
     handler::MonitorHandler,
-    helper, logger,
+    helper,
+    live_scraper::modules::{action_queue, baro},
+    logger,
     settings::SettingsState,
     structs::TradeClassification,
     structs::WarframeLanguage,
+    wf_ee_log_parser::ocr,
 };
 use eyre::eyre;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
+// Revealed rivens are named /AF_Special/Riven/<weapon>/<mod name> once the trade is parsed
+const RIVEN_PREFIX: &str = "/AF_Special/Riven/";
+
 #[derive(Debug)]
 struct TradeLogMessages {
     detect_line: &'static str,
@@ -36,6 +46,9 @@ pub struct PlayerTradeStruct {
     total_platinum: i32,
     offerings: Vec<TradeItemStruct>,
     receiving: Vec<TradeItemStruct>,
+    source: String,
+    // Trades with friends are kept apart in the trade analytics
+    friend: bool,
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeItemStruct {
@@ -52,6 +65,7 @@ pub struct OnTradingEvent {
     wf_ee_path: PathBuf,
     settings: Arc<Mutex<SettingsState>>,
     chche: Arc<Mutex<CacheClient>>,
+    db: Arc<Mutex<DBClient>>,
     helper: Arc<Mutex<MonitorHandler>>,
     // Current trade
     trade_log_messages_by_language: HashMap<WarframeLanguage, TradeLogMessages>,
@@ -59,6 +73,10 @@ pub struct OnTradingEvent {
     getting_trade_message_multiline: bool,
     waiting_for_trade_message_confirmation: bool,
     current_trade: Arc<Mutex<PlayerTradeStruct>>,
+    // Which EE.log this event is reading from
+    source: String,
+    // Shared between all sources so a trade seen in two logs is only handled once
+    recent_trades: Arc<Mutex<Vec<(i64, String, String)>>>,
 }
 
 impl OnTradingEvent {
@@ -66,13 +84,19 @@ impl OnTradingEvent {
         settings: Arc<Mutex<SettingsState>>,
         helper: Arc<Mutex<MonitorHandler>>,
         chche: Arc<Mutex<CacheClient>>,
+        db: Arc<Mutex<DBClient>>,
         wf_ee_path: PathBuf,
+        source: String,
+        recent_trades: Arc<Mutex<Vec<(i64, String, String)>>>,
     ) -> Self {
         Self {
             settings,
             helper,
             chche,
+            db,
             wf_ee_path,
+            source: source.clone(),
+            recent_trades,
             trade_log_messages_by_language: HashMap::from([(
                 WarframeLanguage::English,
                 TradeLogMessages {
@@ -91,12 +115,14 @@ impl OnTradingEvent {
             getting_trade_message_multiline: false,
             waiting_for_trade_message_confirmation: false,
             current_trade: Arc::new(Mutex::new(PlayerTradeStruct {
-                crated_at: chrono::Local::now().to_string(),
+                crated_at: helper::get_utc_timestamp(),
                 total_platinum: -1,
                 user_name: "".to_string(),
                 trade_type: TradeClassification::Unknown,
                 offerings: Vec::new(),
                 receiving: Vec::new(),
+                source,
+                friend: false,
             })),
         }
     }
@@ -106,6 +132,7 @@ impl OnTradingEvent {
             {
                 self.getting_trade_message_multiline = false;
                 self.trade_logs_finished()?;
+                self.capture_trade_window();
                 self.waiting_for_trade_message_confirmation = true;
             } else {
                 self.received_trade_log_message(input);
@@ -121,6 +148,7 @@ impl OnTradingEvent {
             if input
                 .contains(", leftItem=/Menu/Confirm_Item_Ok, rightItem=/Menu/Confirm_Item_Cancel)")
             {
+                self.capture_trade_window();
                 self.waiting_for_trade_message_confirmation = true;
             } else {
                 self.getting_trade_message_multiline = true;
@@ -275,7 +303,9 @@ impl OnTradingEvent {
 
         let receiving_plat = trade_struct.offerings.iter().any(|p| p.name == "plat");
 
-        if num3 == 0 || trade_struct.offerings.len() != 1 {
+        if trade_struct.receiving.is_empty() && !trade_struct.offerings.is_empty() && !receiving_plat {
+            trade_struct.trade_type = TradeClassification::Gift;
+        } else if num3 == 0 || trade_struct.offerings.len() != 1 {
             if !receiving_plat || trade_struct.receiving.len() != 1 {
                 trade_struct.trade_type = TradeClassification::Trade;
             } else {
@@ -284,17 +314,22 @@ impl OnTradingEvent {
         } else {
             trade_struct.trade_type = TradeClassification::Sale;
         }
+        trade_struct.friend = self
+            .settings
+            .lock()?
+            .friends
+            .is_friend(&trade_struct.user_name);
         Ok(())
     }
 
     fn convert_itemname_to_id(&self, item: &mut TradeItemStruct) -> Result<bool, AppError> {
         item.rank = -1;
-        let item_cache = self
-            .chche
-            .lock()?
-            .items()
-            .get_types()
-            .expect("No item cache found");
+        let cache = self.chche.lock()?.clone();
+        // Names in the trade log are in the language the game runs in
+        let language = helper::get_warframe_language()
+            .as_code()
+            .unwrap_or("en")
+            .to_string();
         // Find the item
 
         if item.name.contains("(") && item.name.ends_with(")") {
@@ -322,7 +357,7 @@ impl OnTradingEvent {
 
                     return Ok(true);
                 }
-                let ch_item = item_cache.iter().find(|p| p.item_name == name_part);
+                let ch_item = cache.resolver().by_name_in(&name_part, &language)?;
                 if ch_item.is_some() {
                     let ch_item = ch_item.unwrap();
                     item.wfm_id = Some(ch_item.id.clone());
@@ -340,6 +375,14 @@ impl OnTradingEvent {
             item.display_name = arcane_name_part;
             return Ok(true);
         }
+        if item.name != "plat" {
+            if let Some(ch_item) = cache.resolver().by_name_in(&item.name, &language)? {
+                item.wfm_id = Some(ch_item.id.clone());
+                item.wfm_url_name = Some(ch_item.url_name.clone());
+                item.display_name = ch_item.item_name.clone();
+                return Ok(true);
+            }
+        }
         Ok(false)
     }
 
@@ -348,6 +391,19 @@ impl OnTradingEvent {
         let mh = self.helper.lock()?.clone();
         let trade = self.current_trade.lock()?.clone();
 
+        if self.is_duplicate_trade(&trade)? {
+            logger::info_con(
+                "OnTradingEvent",
+                format!(
+                    "Trade with {} from source {} was already handled, skipping",
+                    trade.user_name, trade.source
+                )
+                .as_str(),
+            );
+            self.reset_trade();
+            return Ok(());
+        }
+
         // Send a notification to the user
         mh.show_notification(
             "Trade Accepted",
@@ -379,10 +435,471 @@ impl OnTradingEvent {
             }
         }
 
+        if let Err(err) = self.auto_stock(&trade) {
+            error::create_log_file("trade_watcher.log".to_string(), &err);
+        }
+
         self.reset_trade();
         Ok(())
     }
 
+    fn is_ocr_enabled(&self) -> Result<bool, AppError> {
+        if !ocr::is_available() {
+            return Ok(false);
+        }
+        let settings = self.settings.lock()?.clone();
+        let cache = self.chche.lock()?.clone();
+        cache.feature_flags().is_enabled(TRADE_OCR, &settings)
+    }
+
+    // Screenshot of the trade confirmation, read once the trade is accepted
+    fn capture_trade_window(&self) {
+        match self.is_ocr_enabled() {
+            Ok(true) => {}
+            _ => return,
+        }
+        let path = ocr::get_screenshot_path();
+        let _ = std::fs::remove_file(&path);
+        if let Err(err) = ocr::capture(&path) {
+            error::create_log_file("trade_ocr.log".to_string(), &err);
+        }
+    }
+
+    // What the log parser found that is not on the screenshot of the trade, empty when OCR is off
+    // or there is no screenshot to compare with
+    fn cross_validate(&self, trade: &PlayerTradeStruct) -> Result<Vec<String>, AppError> {
+        let path = ocr::get_screenshot_path();
+        if !self.is_ocr_enabled()? || !path.exists() {
+            return Ok(vec![]);
+        }
+        let text = ocr::read_text(&path);
+        let _ = std::fs::remove_file(&path);
+        let text = match text {
+            Ok(text) => text,
+            Err(err) => {
+                error::create_log_file("trade_ocr.log".to_string(), &err);
+                return Ok(vec![]);
+            }
+        };
+        let normalized = ocr::normalize(&text);
+        let mut mismatches: Vec<String> = trade
+            .offerings
+            .iter()
+            .chain(trade.receiving.iter())
+            .filter(|item| item.name != "plat")
+            .filter(|item| !normalized.contains(&ocr::normalize(&item.display_name)))
+            .map(|item| format!("{} x{}", item.display_name, item.quantity))
+            .collect();
+        let platinum = trade.total_platinum.to_string();
+        if trade.total_platinum > 0
+            && !text
+                .split(|c: char| !c.is_ascii_digit())
+                .any(|number| number == platinum)
+        {
+            mismatches.push(format!("{} platinum", trade.total_platinum));
+        }
+        Ok(mismatches)
+    }
+
+    // The item and transaction type of a trade that is one item or revealed riven for platinum
+    fn get_stock_change(trade: &PlayerTradeStruct) -> Option<(&'static str, TradeItemStruct)> {
+        let (transaction_type, items) = match trade.trade_type {
+            TradeClassification::Purchase => ("buy", &trade.receiving),
+            TradeClassification::Sale => ("sell", &trade.offerings),
+            _ => return None,
+        };
+        match items.as_slice() {
+            [item]
+                if (item.wfm_url_name.is_some() || item.name.starts_with(RIVEN_PREFIX))
+                    && trade.total_platinum > 0 =>
+            {
+                Some((transaction_type, item.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    // Weapon and mod name of a revealed riven in the trade log
+    fn get_riven_names(item: &TradeItemStruct) -> Option<(String, String)> {
+        let (weapon, mod_name) = item.name.strip_prefix(RIVEN_PREFIX)?.split_once('/')?;
+        Some((weapon.to_string(), mod_name.to_string()))
+    }
+
+    // Adds bought items and rivens to the stock and sells sold ones from it, trades that are
+    // not one known item for platinum (veiled rivens, item swaps) are left to the user
+    fn auto_stock(&self, trade: &PlayerTradeStruct) -> Result<(), AppError> {
+        let settings = self.settings.lock()?.trade_watcher.clone();
+        // The trade of an approved buy action is stocked even when auto stocking is off
+        let approved = match Self::get_stock_change(trade) {
+            Some(("buy", item)) => action_queue::take_approved(
+                &trade.user_name,
+                &item.wfm_url_name.unwrap_or_default(),
+            )?
+            .is_some(),
+            _ => false,
+        };
+        if !settings.auto_stock && !approved {
+            return Ok(());
+        }
+        let mismatches = self.cross_validate(trade)?;
+        if !mismatches.is_empty() {
+            logger::warning_con(
+                "OnTradingEvent",
+                format!(
+                    "Trade with {} does not match the screenshot: {}",
+                    trade.user_name,
+                    mismatches.join(", ")
+                )
+                .as_str(),
+            );
+            if settings.confirm_ambiguous {
+                let mut payload = json!(trade);
+                payload["ocr_mismatches"] = json!(mismatches);
+                helper::send_message_to_window("Client:Trade:Confirm", Some(payload));
+            }
+            return Ok(());
+        }
+        let (transaction_type, item) = match Self::get_stock_change(trade) {
+            Some(change) => change,
+            None => {
+                logger::info_con(
+                    "OnTradingEvent",
+                    format!(
+                        "Trade with {} could not be matched to the stock",
+                        trade.user_name
+                    )
+                    .as_str(),
+                );
+                if settings.confirm_ambiguous {
+                    helper::send_message_to_window("Client:Trade:Confirm", Some(json!(trade)));
+                }
+                return Ok(());
+            }
+        };
+        let db = self.db.lock()?.clone();
+        let language = helper::get_warframe_language()
+            .as_code()
+            .unwrap_or("en")
+            .to_string();
+        let trade = trade.clone();
+        tauri::async_runtime::spawn(async move {
+            let is_riven = item.name.starts_with(RIVEN_PREFIX);
+            let price = trade.total_platinum;
+            let result = match (transaction_type, is_riven) {
+                ("buy", false) => Self::stock_purchase(&db, &item, price).await,
+                ("buy", true) => Self::stock_riven_purchase(&db, &item, price, &language).await,
+                (_, false) => Self::stock_sale(&db, &item, price).await,
+                (_, true) => Self::stock_riven_sale(&db, &item, price, &language).await,
+            };
+            if let Err(err) = result {
+                error::create_log_file("trade_watcher.log".to_string(), &err);
+                if settings.confirm_ambiguous {
+                    helper::send_message_to_window("Client:Trade:Confirm", Some(json!(trade)));
+                }
+            }
+        });
+        Ok(())
+    }
+
+    async fn stock_purchase(
+        db: &DBClient,
+        item: &TradeItemStruct,
+        price: i32,
+    ) -> Result<(), AppError> {
+        let url_name = item.wfm_url_name.clone().unwrap_or_default();
+        // Wish list items are bought for my own account and never go into the stock
+        if let Some(wish) = db
+            .wish_list()
+            .record_purchase(&url_name, item.quantity as i64)
+            .await?
+        {
+            logger::info_con(
+                "OnTradingEvent",
+                format!(
+                    "Bought {} x{} for the wish list, {} still wanted",
+                    url_name, item.quantity, wish.quantity
+                )
+                .as_str(),
+            );
+            return Ok(());
+        }
+        let rank = item.rank.max(0);
+        let transaction = db
+            .transaction()
+            .create(&url_name, "item", "buy", item.quantity, price, rank, None)
+            .await?;
+        // Parts bought for Baro are traded for ducats and never go into the stock
+        if baro::is_baro_item(&url_name)? {
+            db.tag()
+                .add(ENTITY_TRANSACTION, transaction.id, baro::BARO_TAG)
+                .await?;
+            logger::info_con(
+                "OnTradingEvent",
+                format!("Bought {} x{} for Baro", url_name, item.quantity).as_str(),
+            );
+            db.undo().push(
+                &format!("Buy {} x{} for Baro", url_name, item.quantity),
+                vec![UndoChange::transaction(transaction.id, None, Some(&transaction))],
+            )?;
+            return Ok(());
+        }
+        let stock_before = db.stock_item().get_items().await?;
+        let stock_item = db
+            .stock_item()
+            .create(&url_name, item.quantity, price as f64, None, rank, None)
+            .await?;
+        db.undo().push(
+            &format!("Add {} x{}", stock_item.name, item.quantity),
+            vec![
+                UndoChange::stock_item(
+                    stock_item.id,
+                    stock_before.iter().find(|t| t.id == stock_item.id),
+                    Some(&stock_item),
+                ),
+                UndoChange::transaction(transaction.id, None, Some(&transaction)),
+            ],
+        )?;
+        Ok(())
+    }
+
+    async fn stock_sale(db: &DBClient, item: &TradeItemStruct, price: i32) -> Result<(), AppError> {
+        let url_name = item.wfm_url_name.clone().unwrap_or_default();
+        let stock_before = match db
+            .stock_item()
+            .get_item_by_variant(&url_name, item.rank.max(0), None)
+            .await?
+        {
+            Some(stock_item) => Some(stock_item),
+            None => db.stock_item().get_item_by_url_name(&url_name).await?,
+        };
+        let stock_before = match stock_before {
+            Some(stock_item) => stock_item,
+            None => {
+                return Err(AppError::new_with_level(
+                    "OnTradingEvent",
+                    eyre!("Sold item {} is not in the stock", url_name),
+                    LogLevel::Warning,
+                )
+                .with_kind(ErrorKind::NotFound))
+            }
+        };
+        let stock_item = db
+            .stock_item()
+            .sell_item(stock_before.id, item.quantity)
+            .await?;
+        // The trade already happened, sold reserved copies are no longer reserved
+        let reserved_sold = item.quantity - stock_before.get_sellable();
+        let stock_item = if reserved_sold > 0 && stock_item.owned > 0 {
+            logger::warning_con(
+                "OnTradingEvent",
+                format!(
+                    "Sold {} reserved copies of {}, owned {} reserved {}",
+                    reserved_sold, url_name, stock_before.owned, stock_before.reserved
+                )
+                .as_str(),
+            );
+            db.stock_item()
+                .update_reserved(stock_before.id, stock_before.reserved - reserved_sold)
+                .await?
+        } else {
+            stock_item
+        };
+        if stock_item.owned <= 0 {
+            db.stock_item().emit("DELETE", json!(stock_item));
+        } else {
+            db.stock_item().emit("CREATE_OR_UPDATE", json!(stock_item));
+        }
+        let transaction = db
+            .transaction()
+            .create(
+                &stock_item.url,
+                "item",
+                "sell",
+                item.quantity,
+                price,
+                stock_item.rank,
+                None,
+            )
+            .await?;
+        let stock_after = db.stock_item().get_by_id(stock_before.id).await?;
+        db.undo().push(
+            &format!("Sell {} x{}", stock_item.name, item.quantity),
+            vec![
+                UndoChange::stock_item(stock_before.id, Some(&stock_before), stock_after.as_ref()),
+                UndoChange::transaction(transaction.id, None, Some(&transaction)),
+            ],
+        )?;
+        Ok(())
+    }
+
+    // The trade log has no stats, the riven is kept private until they are filled in
+    async fn stock_riven_purchase(
+        db: &DBClient,
+        item: &TradeItemStruct,
+        price: i32,
+        language: &str,
+    ) -> Result<(), AppError> {
+        let (weapon, mod_name) = match Self::get_riven_names(item) {
+            Some(names) => names,
+            None => return Ok(()),
+        };
+        let cache = db.cache.lock()?.clone();
+        let weapon = match cache.riven().find_by_name(&weapon, language)? {
+            Some(weapon) => weapon,
+            None => {
+                return Err(AppError::new_with_level(
+                    "OnTradingEvent",
+                    eyre!("Riven weapon {} is not known", weapon),
+                    LogLevel::Warning,
+                )
+                .with_kind(ErrorKind::NotFound))
+            }
+        };
+        let riven = db
+            .stock_riven()
+            .create(
+                None,
+                &weapon.url_name,
+                &mod_name,
+                price as f64,
+                item.rank.max(0),
+                vec![],
+                None,
+                0,
+                0,
+                "",
+                None,
+            )
+            .await?;
+        let riven = db
+            .stock_riven()
+            .update_by_id(
+                riven.id,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some("inactive".to_string()),
+                Some(true),
+            )
+            .await?;
+        db.transaction()
+            .create(
+                &riven.weapon_url,
+                "riven",
+                "buy",
+                1,
+                price,
+                riven.rank,
+                Some(json!(riven)),
+            )
+            .await?;
+        logger::info_con(
+            "OnTradingEvent",
+            format!(
+                "Added riven {} {}, fill in its stats before it is listed",
+                riven.weapon_name, riven.mod_name
+            )
+            .as_str(),
+        );
+        Ok(())
+    }
+
+    async fn stock_riven_sale(
+        db: &DBClient,
+        item: &TradeItemStruct,
+        price: i32,
+        language: &str,
+    ) -> Result<(), AppError> {
+        let (weapon, mod_name) = match Self::get_riven_names(item) {
+            Some(names) => names,
+            None => return Ok(()),
+        };
+        let cache = db.cache.lock()?.clone();
+        let weapon_url = cache
+            .riven()
+            .find_by_name(&weapon, language)?
+            .map(|weapon| weapon.url_name);
+        let stock = db.stock_riven().get_rivens().await?.into_iter().find(|riven| {
+            Some(&riven.weapon_url) == weapon_url.as_ref()
+                && riven.mod_name.eq_ignore_ascii_case(&mod_name)
+        });
+        let stock = match stock {
+            Some(stock) => stock,
+            None => {
+                return Err(AppError::new_with_level(
+                    "OnTradingEvent",
+                    eyre!("Sold riven {} {} is not in the stock", weapon, mod_name),
+                    LogLevel::Warning,
+                )
+                .with_kind(ErrorKind::NotFound))
+            }
+        };
+        db.stock_riven().delete(stock.id).await?;
+        if let Some(order_id) = stock.order_id.clone().filter(|id| !id.is_empty()) {
+            let wfm = db.wfm.lock()?.clone();
+            if let Err(e) = wfm.auction().delete(&order_id).await {
+                error::create_log_file("trade_watcher.log".to_string(), &e);
+            }
+        }
+        db.transaction()
+            .create(
+                &stock.weapon_url,
+                "riven",
+                "sell",
+                1,
+                price,
+                stock.rank,
+                Some(json!({
+                    "type": "riven",
+                    "weapon_url_name": stock.weapon_url,
+                    "re_rolls": stock.re_rolls,
+                    "polarity": stock.polarity,
+                    "name": stock.mod_name,
+                    "mod_rank": stock.rank,
+                    "mastery_level": stock.mastery_rank,
+                    "attributes": stock.attributes,
+                })),
+            )
+            .await?;
+        Ok(())
+    }
+
+    // A trade with the same user and items within a minute from another source is the same trade,
+    // the same log can hold genuine back to back trades
+    fn is_duplicate_trade(&self, trade: &PlayerTradeStruct) -> Result<bool, AppError> {
+        let now = chrono::Utc::now().timestamp();
+        let mut items: Vec<String> = trade
+            .offerings
+            .iter()
+            .map(|i| format!("-{}x{}", i.name, i.quantity))
+            .chain(
+                trade
+                    .receiving
+                    .iter()
+                    .map(|i| format!("+{}x{}", i.name, i.quantity)),
+            )
+            .collect();
+        items.sort();
+        let signature = format!("{}|{}", trade.user_name, items.join(","));
+
+        let mut recent_trades = self.recent_trades.lock()?;
+        recent_trades.retain(|(created, _, _)| now - created < 60);
+        if let Some(index) = recent_trades
+            .iter()
+            .position(|(_, source, s)| source != &self.source && s == &signature)
+        {
+            // Each copy only cancels out one trade seen in the other source
+            recent_trades.remove(index);
+            return Ok(true);
+        }
+        recent_trades.push((now, self.source.clone(), signature));
+        Ok(false)
+    }
+
     fn trade_failed(&mut self) {
         self.reset_trade();
     }
@@ -394,6 +911,7 @@ impl OnTradingEvent {
         trade_struct.receiving.clear();
         trade_struct.user_name = "".to_string();
         trade_struct.total_platinum = 0;
+        trade_struct.friend = false;
         self.current_trade_logs = Vec::new();
         self.getting_trade_message_multiline = false;
         self.waiting_for_trade_message_confirmation = false;
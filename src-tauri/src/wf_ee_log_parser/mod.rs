@@ -1,2 +1,5 @@
 pub mod client;
 pub mod events;
+pub mod ocr;
+pub mod paths;
+pub mod watcher;
@@ -0,0 +1,64 @@
+use std::path::{Path, PathBuf};
+
+use eyre::eyre;
+
+use crate::{error::AppError, logger};
+
+// Where the trade confirmation window is saved while the trade is open
+pub fn get_screenshot_path() -> PathBuf {
+    logger::get_log_forlder().join("trade_screenshot.png")
+}
+
+// False when the app was built without the "ocr" feature
+pub fn is_available() -> bool {
+    cfg!(feature = "ocr")
+}
+
+// Saves the primary screen, taken while the trade confirmation is shown
+#[cfg(feature = "ocr")]
+pub fn capture(path: &Path) -> Result<(), AppError> {
+    let screen = screenshots::Screen::all()
+        .map_err(|e| AppError::new("TradeOcr", eyre!(e.to_string())))?
+        .into_iter()
+        .find(|screen| screen.display_info.is_primary)
+        .ok_or_else(|| AppError::new("TradeOcr", eyre!("No primary screen found")))?;
+    let image = screen
+        .capture()
+        .map_err(|e| AppError::new("TradeOcr", eyre!(e.to_string())))?;
+    image
+        .save(path)
+        .map_err(|e| AppError::new("TradeOcr", eyre!(e.to_string())))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "ocr"))]
+pub fn capture(_path: &Path) -> Result<(), AppError> {
+    Err(AppError::new(
+        "TradeOcr",
+        eyre!("Built without OCR support"),
+    ))
+}
+
+#[cfg(feature = "ocr")]
+pub fn read_text(path: &Path) -> Result<String, AppError> {
+    let image = rusty_tesseract::Image::from_path(path)
+        .map_err(|e| AppError::new("TradeOcr", eyre!(e.to_string())))?;
+    rusty_tesseract::image_to_string(&image, &rusty_tesseract::Args::default())
+        .map_err(|e| AppError::new("TradeOcr", eyre!(e.to_string())))
+}
+
+#[cfg(not(feature = "ocr"))]
+pub fn read_text(_path: &Path) -> Result<String, AppError> {
+    Err(AppError::new(
+        "TradeOcr",
+        eyre!("Built without OCR support"),
+    ))
+}
+
+// Lower case letters and digits only, recognition often gets spacing and punctuation wrong
+pub fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
@@ -0,0 +1,115 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use directories::BaseDirs;
+
+use crate::helper;
+
+// Steam app id of Warframe, the Proton prefix is named after it
+const WARFRAME_APP_ID: &str = "230410";
+
+// Where a Windows install writes EE.log, relative to the user folder of a Wine prefix
+fn get_prefix_log(users: &Path, user: &str) -> PathBuf {
+    users
+        .join(user)
+        .join("AppData")
+        .join("Local")
+        .join("Warframe")
+        .join("EE.log")
+}
+
+// Steam libraries listed in libraryfolders.vdf, the Steam root itself included
+fn get_steam_libraries(steam_root: &Path) -> Vec<PathBuf> {
+    let mut libraries = vec![steam_root.to_path_buf()];
+    let vdf = steam_root.join("steamapps").join("libraryfolders.vdf");
+    if let Ok(content) = fs::read_to_string(vdf) {
+        for line in content.lines() {
+            let mut parts = line.split('"').filter(|p| !p.trim().is_empty());
+            if parts.next() != Some("path") {
+                continue;
+            }
+            if let Some(path) = parts.next() {
+                libraries.push(PathBuf::from(path.replace("\\\\", "\\")));
+            }
+        }
+    }
+    libraries
+}
+
+// Every place EE.log may be written to on this machine, the native install first.
+// Consoles do not write a log, their trades are only picked up from Warframe Market.
+pub fn get_candidates() -> Vec<PathBuf> {
+    let mut candidates = vec![helper::get_app_local_path().join("Warframe").join("EE.log")];
+    let home = match BaseDirs::new() {
+        Some(base_dirs) => base_dirs.home_dir().to_path_buf(),
+        None => return candidates,
+    };
+
+    // Steam with Proton, including the Flatpak client and extra libraries
+    let steam_roots = [
+        home.join(".steam").join("steam"),
+        home.join(".local").join("share").join("Steam"),
+        home.join(".var")
+            .join("app")
+            .join("com.valvesoftware.Steam")
+            .join(".local")
+            .join("share")
+            .join("Steam"),
+    ];
+    for root in steam_roots.iter().filter(|r| r.exists()) {
+        for library in get_steam_libraries(root) {
+            let users = library
+                .join("steamapps")
+                .join("compatdata")
+                .join(WARFRAME_APP_ID)
+                .join("pfx")
+                .join("drive_c")
+                .join("users");
+            candidates.push(get_prefix_log(&users, "steamuser"));
+        }
+    }
+
+    // The standalone launcher in a Wine or Lutris prefix, any Windows user name
+    let prefixes = [
+        home.join(".wine"),
+        home.join("Games").join("warframe"),
+        home.join("Games").join("Warframe"),
+    ];
+    for prefix in prefixes.iter() {
+        let users = prefix.join("drive_c").join("users");
+        if let Ok(entries) = fs::read_dir(&users) {
+            for entry in entries.flatten() {
+                candidates.push(get_prefix_log(
+                    &users,
+                    entry.file_name().to_str().unwrap_or_default(),
+                ));
+            }
+        }
+    }
+
+    let mut unique: Vec<PathBuf> = vec![];
+    for candidate in candidates {
+        let canonical = candidate.canonicalize().unwrap_or(candidate.clone());
+        if !unique
+            .iter()
+            .any(|u| u.canonicalize().unwrap_or(u.clone()) == canonical)
+        {
+            unique.push(candidate);
+        }
+    }
+    unique
+}
+
+// The configured EE.log, otherwise the first install found, otherwise the Windows default
+pub fn get_ee_log_path(configured: &str) -> PathBuf {
+    let configured = configured.trim();
+    if !configured.is_empty() {
+        return PathBuf::from(configured);
+    }
+    let candidates = get_candidates();
+    candidates
+        .iter()
+        .find(|path| path.exists())
+        .cloned()
+        .unwrap_or_else(|| candidates[0].clone())
+}
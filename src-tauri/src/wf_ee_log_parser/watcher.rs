@@ -0,0 +1,69 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// A log the trade detection reads from, new sources only need to hand over new lines
+pub trait LogWatcher: Send + Sync + std::fmt::Debug {
+    fn path(&self) -> &Path;
+    // Lines added since the last call with their line index, the first call only remembers where the log ends
+    fn read_new_lines(&self, is_starting: bool) -> io::Result<Vec<(usize, String)>>;
+}
+
+// Follows a file on disk, starting over when it was truncated by a game restart
+#[derive(Debug)]
+pub struct FileWatcher {
+    path: PathBuf,
+    last_file_size: Mutex<u64>,
+    last_line_index: Mutex<usize>,
+}
+
+impl FileWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        FileWatcher {
+            path,
+            last_file_size: Mutex::new(0),
+            last_line_index: Mutex::new(0),
+        }
+    }
+}
+
+impl LogWatcher for FileWatcher {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn read_new_lines(&self, is_starting: bool) -> io::Result<Vec<(usize, String)>> {
+        let mut new_lines: Vec<(usize, String)> = Vec::new();
+        let mut file = File::open(&self.path)?;
+
+        let metadata = file.metadata()?;
+        let current_file_size = metadata.len();
+
+        if is_starting {
+            *self.last_file_size.lock().unwrap() = current_file_size;
+            return Ok(new_lines);
+        }
+
+        let mut last_file_size = self.last_file_size.lock().unwrap();
+        let mut last_line_index = self.last_line_index.lock().unwrap();
+        if *last_file_size > current_file_size {
+            *last_file_size = 0;
+            *last_line_index = 0;
+        }
+
+        file.seek(SeekFrom::Start(*last_file_size))?;
+
+        let reader = BufReader::new(file);
+
+        for line in reader.lines() {
+            *last_line_index += 1;
+            if let Ok(line) = line {
+                new_lines.push((*last_line_index, line));
+            }
+        }
+
+        *last_file_size = current_file_size;
+        Ok(new_lines)
+    }
+}
@@ -0,0 +1,63 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ResponseCacheStats {
+    pub hits: i64,
+    pub misses: i64,
+    pub entries: i64,
+}
+
+// Payloads of GET requests keyed by url and payload key, shared by the UI and the live scraper
+#[derive(Clone, Debug, Default)]
+pub struct ResponseCache {
+    entries: HashMap<String, (Instant, Value, HeaderMap)>,
+    hits: i64,
+    misses: i64,
+}
+
+impl ResponseCache {
+    pub fn get_key(url: &str, payload_key: Option<&str>) -> String {
+        format!("{}#{}", url, payload_key.unwrap_or(""))
+    }
+
+    // The cached payload when it is younger than ttl, counted as a hit or a miss
+    pub fn get(&mut self, key: &str, ttl: Duration) -> Option<(Value, HeaderMap)> {
+        match self.entries.get(key) {
+            Some((fetched_at, payload, headers)) if fetched_at.elapsed() < ttl => {
+                self.hits += 1;
+                Some((payload.clone(), headers.clone()))
+            }
+            _ => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, key: String, payload: Value, headers: HeaderMap, ttl: Duration) {
+        self.entries
+            .retain(|_, (fetched_at, _, _)| fetched_at.elapsed() < ttl);
+        self.entries.insert(key, (Instant::now(), payload, headers));
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    pub fn get_stats(&self) -> ResponseCacheStats {
+        ResponseCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len() as i64,
+        }
+    }
+}
@@ -1,10 +1,14 @@
 use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
     time::Duration,
 };
 
 use eyre::eyre;
+use futures_util::SinkExt;
 use polars::{
     prelude::{DataFrame, NamedFrom},
     series::Series,
@@ -12,21 +16,31 @@ use polars::{
 use reqwest::{header::HeaderMap, Client, Method, StatusCode, Url};
 use serde::de::DeserializeOwned;
 use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, http::HeaderValue, Message};
 
 use crate::{
     auth::AuthState,
-    enums::LogLevel,
+    cache::modules::resolver::ItemIndex,
+    enums::{ErrorKind, LogLevel},
     error::{ApiResult, AppError, ErrorApiResponse},
     helper,
     logger::{self},
     rate_limiter::RateLimiter,
+    structs::Ordres,
 };
 
-use super::modules::{
-    auction::AuctionModule, auth::AuthModule, chat::ChatModule, item::ItemModule,
-    order::OrderModule,
+use super::{
+    cache::{ResponseCache, ResponseCacheStats},
+    modules::{
+        auction::AuctionModule, auth::AuthModule, chat::ChatModule, item::ItemModule,
+        order::OrderModule,
+    },
+    retry::{CircuitBreaker, EndpointClass, FailureKind, RetryPolicy},
 };
 
+// Status changes and chat messages are only accepted over the websocket of the site
+const SOCKET_URL: &str = "wss://warframe.market/socket?platform=pc";
+
 #[derive(Clone, Debug)]
 pub struct WFMClient {
     endpoint: String,
@@ -35,6 +49,21 @@ pub struct WFMClient {
     pub log_file: String,
     pub auth: Arc<Mutex<AuthState>>,
     pub settings: Arc<Mutex<crate::settings::SettingsState>>,
+    // Orders of other players, keyed by ingame name
+    pub user_orders_cache: Arc<Mutex<HashMap<String, (std::time::Instant, Ordres)>>>,
+    // Requests left for the current live scraper pass, -1 for no limit
+    request_budget: Option<Arc<Mutex<i64>>>,
+    circuit_breaker: Arc<Mutex<CircuitBreaker>>,
+    // Set while Warframe Market answers with maintenance responses, cleared by the next successful request
+    maintenance: Arc<AtomicBool>,
+    // Orders deleted through the app since the live scraper last looked, others were closed by hand
+    pub deleted_orders: Arc<Mutex<HashSet<String>>>,
+    // Language of the names in the responses, the region of the account when None
+    language: Option<String>,
+    // Item orders and statistics fetched in the last wfm_cache_ttl seconds
+    response_cache: Arc<Mutex<ResponseCache>>,
+    // Item lookups of the cache client, shared so names and url names resolve the same way
+    pub item_index: Arc<Mutex<Option<Arc<ItemIndex>>>>,
 }
 
 impl WFMClient {
@@ -52,9 +81,65 @@ impl WFMClient {
             log_file: "wfmAPICalls.log".to_string(),
             auth,
             settings,
+            user_orders_cache: Arc::new(Mutex::new(HashMap::new())),
+            request_budget: None,
+            circuit_breaker: Arc::new(Mutex::new(CircuitBreaker::default())),
+            maintenance: Arc::new(AtomicBool::new(false)),
+            deleted_orders: Arc::new(Mutex::new(HashSet::new())),
+            language: None,
+            response_cache: Arc::new(Mutex::new(ResponseCache::default())),
+            item_index: Arc::new(Mutex::new(None)),
         }
     }
 
+    // Url name of an item given by id, url name or name. Unknown items are used as given
+    pub fn get_url_name(&self, item: &str) -> String {
+        let index = match self.item_index.lock() {
+            Ok(index) => index.clone(),
+            Err(_) => None,
+        };
+        index
+            .and_then(|index| index.find(item).map(|item| item.url_name.clone()))
+            .unwrap_or(item.to_string())
+    }
+
+    // A copy of the client that counts every request against the given budget
+    pub fn with_budget(&self, budget: Arc<Mutex<i64>>) -> Self {
+        let mut client = self.clone();
+        client.request_budget = Some(budget);
+        client
+    }
+
+    pub fn with_language(&self, language: &str) -> Self {
+        let mut client = self.clone();
+        client.language = Some(language.to_string());
+        client
+    }
+
+    fn use_budget(&self) -> Result<(), AppError> {
+        let budget = match &self.request_budget {
+            Some(budget) => budget,
+            None => return Ok(()),
+        };
+        let mut remaining = budget.lock()?;
+        if *remaining == 0 {
+            return Err(AppError::new_with_level(
+                "WarframeMarket:Budget",
+                eyre!("The request budget for this pass is exhausted"),
+                LogLevel::Warning,
+            )
+            .with_kind(ErrorKind::BudgetExhausted));
+        }
+        if *remaining > 0 {
+            *remaining -= 1;
+        }
+        Ok(())
+    }
+
+    pub fn is_maintenance(&self) -> bool {
+        self.maintenance.load(Ordering::SeqCst)
+    }
+
     pub fn debug(&self, id: &str, component: &str, msg: &str, file: Option<bool>) {
         let settings = self.settings.lock().unwrap().clone();
         if !settings.debug.contains(&"*".to_owned()) && !settings.debug.contains(&id.to_owned()) {
@@ -93,12 +178,102 @@ impl WFMClient {
         );
     }
 
+    // Sends the request with the retry policy of its endpoint class
     async fn send_request<T: DeserializeOwned>(
         &self,
         method: Method,
         url: &str,
         payload_key: Option<&str>,
         body: Option<Value>,
+    ) -> Result<ApiResult<T>, AppError> {
+        let policy = RetryPolicy {
+            settings: self.settings.lock()?.wfm_retry.clone(),
+        };
+        let class = EndpointClass::from_method(&method);
+        if let Some(remaining) = self.circuit_breaker.lock()?.get_remaining() {
+            return Err(AppError::new_with_level(
+                "WarframeMarket:Circuit",
+                eyre!(
+                    "Warframe Market keeps failing, requests are paused for {}s",
+                    remaining
+                ),
+                LogLevel::Warning,
+            )
+            .with_kind(ErrorKind::Unavailable));
+        }
+
+        let mut attempt = 0;
+        loop {
+            self.use_budget()?;
+            let mut failure = None;
+            let result = self
+                .send_once(method.clone(), url, payload_key, body.clone(), &mut failure)
+                .await;
+            let kind = match failure {
+                Some(kind) => kind,
+                None => {
+                    self.circuit_breaker.lock()?.record_success();
+                    self.maintenance.store(false, Ordering::SeqCst);
+                    return result;
+                }
+            };
+            if kind == FailureKind::Maintenance {
+                if !self.maintenance.swap(true, Ordering::SeqCst) {
+                    logger::warning_con(
+                        self.component.as_str(),
+                        "Warframe Market is down for maintenance",
+                    );
+                }
+                return result.map_err(|e| e.with_kind(ErrorKind::Maintenance));
+            }
+            if policy.should_retry(&class, &kind, attempt) {
+                let delay = policy.get_delay(attempt);
+                attempt += 1;
+                logger::warning_con(
+                    self.component.as_str(),
+                    format!(
+                        "Request {} {} failed: {:?}, retry {}/{} in {}ms",
+                        method,
+                        url,
+                        kind,
+                        attempt,
+                        policy.max_retries(&class),
+                        delay.as_millis()
+                    )
+                    .as_str(),
+                );
+                tokio::time::sleep(delay).await;
+                continue;
+            }
+            if !kind.is_outage() {
+                // Warframe Market answered, it is only this request that is wrong
+                self.circuit_breaker.lock()?.record_success();
+            } else if self
+                .circuit_breaker
+                .lock()?
+                .record_failure(&policy.settings)
+            {
+                logger::warning_con(
+                    self.component.as_str(),
+                    format!(
+                        "Too many failed requests, pausing requests for {}s",
+                        policy.settings.breaker_cooldown
+                    )
+                    .as_str(),
+                );
+            }
+            return result;
+        }
+    }
+
+    // A single attempt, failure is set when the request did not succeed
+    async fn send_once<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        url: &str,
+        payload_key: Option<&str>,
+        body: Option<Value>,
+        failure: &mut Option<FailureKind>,
     ) -> Result<ApiResult<T>, AppError> {
         let auth = self.auth.lock()?.clone();
         let mut rate_limiter = self.limiter.lock().await;
@@ -123,7 +298,7 @@ impl WFMClient {
                 "User-Agent",
                 format!("Quantframe {}", packageinfo.version.to_string()),
             )
-            .header("Language", auth.region);
+            .header("Language", self.language.clone().unwrap_or(auth.region));
 
         let request = match body.clone() {
             Some(content) => request.json(&content),
@@ -144,6 +319,7 @@ impl WFMClient {
         };
 
         if let Err(e) = response {
+            *failure = Some(FailureKind::from_reqwest(&e));
             error_def.messages.push(e.to_string());
             return Err(AppError::new_api(
                 "WarframeMarket",
@@ -156,9 +332,13 @@ impl WFMClient {
         // Get the response data from the response
         let response_data = response.unwrap();
         error_def.status_code = response_data.status().as_u16() as i64;
+        *failure = FailureKind::from_status(response_data.status().as_u16());
         let headers = response_data.headers().clone();
         let content = response_data.text().await.unwrap_or_default();
         error_def.raw_response = Some(content.clone());
+        if FailureKind::is_maintenance(error_def.status_code as u16, &content) {
+            *failure = Some(FailureKind::Maintenance);
+        }
 
         // Convert the response to a Value object
         let response: Value = serde_json::from_str(content.as_str()).map_err(|e| {
@@ -218,6 +398,7 @@ impl WFMClient {
         match serde_json::from_value(data.clone()) {
             Ok(payload) => Ok(ApiResult::Success(payload, headers)),
             Err(e) => {
+                *failure = Some(FailureKind::Permanent);
                 error_def.messages.push(e.to_string());
                 error_def.error = "ParseError".to_string();
                 return Err(AppError::new_api(
@@ -241,6 +422,53 @@ impl WFMClient {
         Ok(payload)
     }
 
+    // A GET answered from the response cache when the same request succeeded within the cache ttl
+    pub async fn get_cached<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        payload_key: Option<&str>,
+    ) -> Result<ApiResult<T>, AppError> {
+        let ttl = self.settings.lock()?.wfm_cache_ttl;
+        if ttl <= 0 {
+            return self.get(url, payload_key).await;
+        }
+        let ttl = Duration::from_secs(ttl as u64);
+        let key = format!(
+            "{}@{}",
+            ResponseCache::get_key(url, payload_key),
+            self.language.clone().unwrap_or_default()
+        );
+        let cached = self.response_cache.lock()?.get(&key, ttl);
+        let (payload, headers) = match cached {
+            Some(cached) => cached,
+            None => match self.get::<Value>(url, payload_key).await? {
+                ApiResult::Success(payload, headers) => {
+                    self.response_cache
+                        .lock()?
+                        .insert(key, payload.clone(), headers.clone(), ttl);
+                    (payload, headers)
+                }
+                ApiResult::Error(error, headers) => return Ok(ApiResult::Error(error, headers)),
+            },
+        };
+        let payload = serde_json::from_value(payload).map_err(|e| {
+            AppError::new(
+                self.component.as_str(),
+                eyre!(format!("Could not parse cached payload: {}", e)),
+            )
+        })?;
+        Ok(ApiResult::Success(payload, headers))
+    }
+
+    pub fn get_cache_stats(&self) -> Result<ResponseCacheStats, AppError> {
+        Ok(self.response_cache.lock()?.get_stats())
+    }
+
+    pub fn clear_cache(&self) -> Result<(), AppError> {
+        self.response_cache.lock()?.clear();
+        Ok(())
+    }
+
     pub async fn post<T: DeserializeOwned>(
         &self,
         url: &str,
@@ -275,6 +503,47 @@ impl WFMClient {
             .await?;
         Ok(payload)
     }
+    // Sends one message over a short lived websocket connection, logged in as me
+    pub async fn send_socket_message(
+        &self,
+        message_type: &str,
+        payload: Value,
+    ) -> Result<(), AppError> {
+        let component = format!("{}:Socket", self.component);
+        let token = match self.auth.lock()?.access_token.clone() {
+            Some(token) => token,
+            None => {
+                return Err(AppError::new_with_level(
+                    &component,
+                    eyre!("Not logged in"),
+                    LogLevel::Warning,
+                ))
+            }
+        };
+        let to_error = |e: tokio_tungstenite::tungstenite::Error| {
+            AppError::new_with_level(
+                &component,
+                eyre!("Could not send {}: {}", message_type, e),
+                LogLevel::Warning,
+            )
+        };
+        let mut request = SOCKET_URL.into_client_request().map_err(to_error)?;
+        let cookie = HeaderValue::from_str(format!("JWT={}", token).as_str())
+            .map_err(|e| AppError::new(&component, eyre!(e.to_string())))?;
+        request.headers_mut().insert("Cookie", cookie);
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(request)
+            .await
+            .map_err(to_error)?;
+        let message = json!({ "type": message_type, "payload": payload });
+        socket
+            .send(Message::Text(message.to_string()))
+            .await
+            .map_err(to_error)?;
+        let _ = socket.close(None).await;
+        Ok(())
+    }
+
     // Add an "add" method to WFMWFMClient
     pub fn auth(&self) -> AuthModule {
         AuthModule {
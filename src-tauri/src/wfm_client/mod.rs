@@ -1,2 +1,4 @@
+pub mod cache;
 pub mod client;
 pub mod modules;
+pub mod retry;
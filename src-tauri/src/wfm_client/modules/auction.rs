@@ -152,6 +152,17 @@ impl<'a> AuctionModule<'a> {
                 "mastery_level": item.mastery_level,
             });
             body["item"] = item_riven;
+        } else if auction_type == "lich" || auction_type == "sister" {
+            // Kuva Lich and Sisters of Parvos weapons
+            let item_lich = json!({
+                "type": auction_type,
+                "weapon_url_name": item.weapon_url_name,
+                "element": item.element,
+                "damage": item.damage,
+                "having_ephemera": item.having_ephemera,
+                "quirk": item.quirk,
+            });
+            body["item"] = item_lich;
         } else if auction_type == "item" {
             logger::warning_con(
                 "WarframeMarket:Auction:Create",
@@ -240,6 +251,24 @@ impl<'a> AuctionModule<'a> {
             }
         };
     }
+    // Only moves the price, the note, reputation and visibility of the auction are kept
+    pub async fn update_price(
+        &self,
+        auction: &Auction<String>,
+        price: i64,
+        visible: bool,
+    ) -> Result<Auction<String>, AppError> {
+        self.update(
+            auction.id.as_str(),
+            price as i32,
+            auction.minimal_reputation as i32,
+            auction.note.as_str(),
+            price as i32,
+            visible,
+        )
+        .await
+    }
+
     pub async fn search(
         &self,
         auction_type: &str,
@@ -333,11 +362,11 @@ impl<'a> AuctionModule<'a> {
         };
     }
     pub async fn delete(&self, auction_id: &str) -> Result<Option<String>, AppError> {
-        let url = format!("auctions/entry/{}/close", auction_id);
+        let url = format!("auctions/entry/{}", auction_id);
 
-        match self.client.put(&url, Some("auction_id"), None).await {
+        match self.client.delete(&url, Some("auction_id")).await {
             Ok(ApiResult::Success(payload, _headers)) => {
-                self.emit("CREATE_OR_UPDATE", serde_json::to_value(&payload).unwrap());
+                self.emit("DELETE", json!({ "id": auction_id }));
                 self.client.debug(
                     &self.debug_id,
                     "Auction:Delete",
@@ -368,6 +397,44 @@ impl<'a> AuctionModule<'a> {
             }
         };
     }
+
+    // Marks the auction as closed, e.g. once it is sold, it stays on the profile
+    pub async fn close(&self, auction_id: &str) -> Result<Option<String>, AppError> {
+        let url = format!("auctions/entry/{}/close", auction_id);
+
+        match self.client.put(&url, Some("auction_id"), None).await {
+            Ok(ApiResult::Success(payload, _headers)) => {
+                self.emit("CREATE_OR_UPDATE", serde_json::to_value(&payload).unwrap());
+                self.client.debug(
+                    &self.debug_id,
+                    "Auction:Close",
+                    format!("Closed auction: {}", auction_id).as_str(),
+                    None,
+                );
+                return Ok(payload);
+            }
+            Ok(ApiResult::Error(error, _headers)) => {
+                let log_level = match error.messages.get(0) {
+                    Some(message)
+                        if message.contains("app.form.not_exist")
+                            || message.contains("app.form.invalid") =>
+                    {
+                        crate::enums::LogLevel::Warning
+                    }
+                    _ => crate::enums::LogLevel::Error,
+                };
+                return Err(self.client.create_api_error(
+                    "Auction:Close",
+                    error,
+                    eyre!("There was an error closing the auction"),
+                    log_level,
+                ));
+            }
+            Err(err) => {
+                return Err(err);
+            }
+        };
+    }
     pub fn emit(&self, operation: &str, data: serde_json::Value) {
         helper::emit_update("auctions", operation, Some(data));
     }
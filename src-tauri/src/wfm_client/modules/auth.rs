@@ -1,9 +1,10 @@
 use eyre::eyre;
 use reqwest::header::HeaderMap;
-use serde_json::json;
+use serde_json::{json, Value};
 
 use crate::{
     auth::AuthState,
+    enums::LogLevel,
     error::{self, ApiResult, AppError},
     wfm_client::client::WFMClient,
 };
@@ -71,7 +72,7 @@ impl<'a> AuthModule<'a> {
         match self
             .client
             .orders()
-            .create("56783f24cbfa8f0432dd89a2", "buy", 1, 1, false, None)
+            .create("56783f24cbfa8f0432dd89a2", "buy", 1, 1, false, None, None)
             .await
         {
             Ok(order) => {
@@ -92,4 +93,52 @@ impl<'a> AuthModule<'a> {
             }
         }
     }
+
+    // My status as other players see it: online, ingame or offline
+    pub async fn get_status(&self) -> Result<String, AppError> {
+        let ingame_name = self.client.auth.lock()?.ingame_name.clone();
+        let url = format!("profile/{}", ingame_name);
+        match self.client.get::<Value>(&url, Some("profile")).await {
+            Ok(ApiResult::Success(profile, _headers)) => {
+                let status = profile["status"].as_str().unwrap_or("offline").to_string();
+                self.client.debug(
+                    &self.debug_id,
+                    "User:GetStatus",
+                    format!("Status of {} is {}", ingame_name, status).as_str(),
+                    None,
+                );
+                Ok(status)
+            }
+            // Only the ingame status is lost when this fails, the scraper keeps running
+            Ok(ApiResult::Error(e, _headers)) => Err(self.client.create_api_error(
+                "User:GetStatus",
+                e,
+                eyre!("There was an error fetching the status of {}", ingame_name),
+                LogLevel::Warning,
+            )),
+            Err(e) => Err(AppError::new_with_level(
+                "User:GetStatus",
+                eyre!(
+                    "There was an error fetching the status of {}: {}",
+                    ingame_name,
+                    e.cause()
+                ),
+                LogLevel::Warning,
+            )),
+        }
+    }
+
+    // Sets my status to online, ingame or invisible
+    pub async fn set_status(&self, status: &str) -> Result<(), AppError> {
+        self.client
+            .send_socket_message("@WS/USER/SET_STATUS", json!(status))
+            .await?;
+        self.client.debug(
+            &self.debug_id,
+            "User:SetStatus",
+            format!("Status set to {}", status).as_str(),
+            None,
+        );
+        Ok(())
+    }
 }
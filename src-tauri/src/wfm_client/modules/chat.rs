@@ -1,7 +1,9 @@
 use eyre::eyre;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 use crate::{
+    enums::{ErrorKind, LogLevel},
     error::{ApiResult, AppError},
     helper,
     wfm_client::client::WFMClient,
@@ -97,6 +99,42 @@ impl<'a> ChatModule<'a> {
             }
         };
     }
+    // Messages a player I already have a conversation with, new conversations can only be started in game
+    pub async fn send_message(&self, ingame_name: &str, message: &str) -> Result<(), AppError> {
+        let chat = self.get_chats().await?.into_iter().find(|chat| {
+            chat.chat_with
+                .iter()
+                .any(|user| user.ingame_name.eq_ignore_ascii_case(ingame_name))
+        });
+        let chat = match chat {
+            Some(chat) => chat,
+            None => {
+                return Err(AppError::new_with_level(
+                    "WarframeMarket:Chat:SendMessage",
+                    eyre!("No conversation with {} yet", ingame_name),
+                    LogLevel::Warning,
+                )
+                .with_kind(ErrorKind::NotFound))
+            }
+        };
+        self.client
+            .send_socket_message(
+                "@WS/chats/SEND_MESSAGE",
+                json!({
+                    "chat_id": chat.id,
+                    "message": message,
+                    "temp_id": helper::get_utc_timestamp(),
+                }),
+            )
+            .await?;
+        self.client.debug(
+            &self.debug_id,
+            "Chat:SendMessage",
+            format!("Message sent to {}", ingame_name).as_str(),
+            None,
+        );
+        Ok(())
+    }
     pub fn emit(&self, operation: &str, data: serde_json::Value) {
         helper::emit_update("ChatMessages", operation, Some(data));
     }
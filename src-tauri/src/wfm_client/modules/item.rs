@@ -5,6 +5,7 @@ use crate::{
 };
 
 use eyre::eyre;
+use serde_json::Value;
 pub struct ItemModule<'a> {
     pub client: &'a WFMClient,
     pub debug_id: String,
@@ -36,6 +37,7 @@ impl<'a> ItemModule<'a> {
         };
     }
     pub async fn get_item(&self, item: String) -> Result<ItemDetails, AppError> {
+        let item = self.client.get_url_name(&item);
         let url = format!("items/{}", item);
         match self.client.get(&url, Some("item")).await {
             Ok(ApiResult::Success(payload, _headers)) => {
@@ -60,4 +62,31 @@ impl<'a> ItemModule<'a> {
             }
         }
     }
+    // Closed statistics of the last 48 hours and 90 days, keyed by "48hours" and "90days"
+    pub async fn get_statistics(&self, item: &str) -> Result<Value, AppError> {
+        let item = self.client.get_url_name(item);
+        let url = format!("items/{}/statistics", item);
+        match self.client.get_cached(&url, Some("statistics_closed")).await {
+            Ok(ApiResult::Success(payload, _headers)) => {
+                self.client.debug(
+                    &self.debug_id,
+                    "Item:GetStatistics",
+                    format!("Gettting statistics: {}", item).as_str(),
+                    None,
+                );
+                return Ok(payload);
+            }
+            Ok(ApiResult::Error(error, _headers)) => {
+                return Err(self.client.create_api_error(
+                    "Item:GetStatistics",
+                    error,
+                    eyre!("There was an error fetching statistics of {}", item),
+                    crate::enums::LogLevel::Error,
+                ));
+            }
+            Err(err) => {
+                return Err(err);
+            }
+        }
+    }
 }
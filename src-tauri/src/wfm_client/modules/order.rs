@@ -5,10 +5,10 @@ use polars::{
 use serde_json::json;
 
 use crate::{
-    enums::OrderType,
+    enums::{ErrorKind, OnlineStatus, OrderType},
     error::{ApiResult, AppError},
     helper,
-    structs::{Order, Ordres},
+    structs::{Order, OrderBookDepth, Ordres},
     wfm_client::client::WFMClient,
 };
 
@@ -50,6 +50,43 @@ impl<'a> OrderModule<'a> {
         }
     }
 
+    // Orders of another player, fetched again once the cached copy is older than max_age.
+    // If the refresh fails, e.g. because of rate limiting, the stale copy is used instead.
+    pub async fn get_user_orders_cached(
+        &self,
+        ingame_name: &str,
+        max_age: std::time::Duration,
+    ) -> Result<Ordres, AppError> {
+        let key = ingame_name.to_lowercase();
+        let cached = self.client.user_orders_cache.lock()?.get(&key).cloned();
+        if let Some((fetched_at, orders)) = cached.clone() {
+            if fetched_at.elapsed() < max_age {
+                return Ok(orders);
+            }
+        }
+        match self.get_user_orders(ingame_name).await {
+            Ok(orders) => {
+                self.client
+                    .user_orders_cache
+                    .lock()?
+                    .insert(key, (std::time::Instant::now(), orders.clone()));
+                Ok(orders)
+            }
+            Err(e) => match cached {
+                Some((_, orders)) => {
+                    self.client.debug(
+                        &self.debug_id,
+                        "Order:GetUserOrdersCached",
+                        format!("Using cached orders for {}, refresh failed", ingame_name).as_str(),
+                        None,
+                    );
+                    Ok(orders)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
     pub async fn get_my_orders(&self) -> Result<Ordres, AppError> {
         let auth = self.client.auth.lock()?.clone();
         let orders = self.get_user_orders(auth.ingame_name.as_str()).await?;
@@ -64,6 +101,25 @@ impl<'a> OrderModule<'a> {
         quantity: i64,
         visible: bool,
         rank: Option<f64>,
+        sub_type: Option<&str>,
+    ) -> Result<Order, AppError> {
+        self.create_with_note(
+            item_id, order_type, platinum, quantity, visible, rank, sub_type, None,
+        )
+        .await
+    }
+
+    // Creates the order with a note shown to other players, e.g. the bundle terms of a bulk item
+    pub async fn create_with_note(
+        &self,
+        item_id: &str,
+        order_type: &str,
+        platinum: i64,
+        quantity: i64,
+        visible: bool,
+        rank: Option<f64>,
+        sub_type: Option<&str>,
+        note: Option<&str>,
     ) -> Result<Order, AppError> {
         // Construct any JSON body
         let mut body = json!({
@@ -77,6 +133,13 @@ impl<'a> OrderModule<'a> {
         if let Some(rank) = rank {
             body["rank"] = json!(rank);
         }
+        // Add subtype to body if it exists, e.g. the refinement of a relic
+        if let Some(sub_type) = sub_type {
+            body["subtype"] = json!(sub_type);
+        }
+        if let Some(note) = note {
+            body["note"] = json!(note);
+        }
 
         match self
             .client
@@ -131,22 +194,32 @@ impl<'a> OrderModule<'a> {
                     format!("Order {} was deleted.", order_id).as_str(),
                     None,
                 );
+                self.client
+                    .deleted_orders
+                    .lock()?
+                    .insert(order_id.to_string());
                 self.emit("DELETE", json!({ "id": &payload }));
                 return Ok(payload);
             }
             Ok(ApiResult::Error(error, _headers)) => {
-                let log_level = match error.messages.get(0) {
-                    Some(message) if message.contains("app.delete_order.order_not_exist") => {
-                        crate::enums::LogLevel::Warning
-                    }
-                    _ => crate::enums::LogLevel::Error,
+                let not_found = match error.messages.get(0) {
+                    Some(message) => message.contains("app.delete_order.order_not_exist"),
+                    None => false,
                 };
-                return Err(self.client.create_api_error(
+                let log_level = match not_found {
+                    true => crate::enums::LogLevel::Warning,
+                    false => crate::enums::LogLevel::Error,
+                };
+                let e = self.client.create_api_error(
                     "Order:Delete",
                     error,
                     eyre!("There was an error deleting order {}", order_id),
                     log_level,
-                ));
+                );
+                return Err(match not_found {
+                    true => e.with_kind(ErrorKind::NotFound),
+                    false => e,
+                });
             }
             Err(err) => {
                 return Err(err);
@@ -160,13 +233,29 @@ impl<'a> OrderModule<'a> {
         platinum: i32,
         quantity: i32,
         visible: bool,
+    ) -> Result<Order, AppError> {
+        self.update_with_note(order_id, platinum, quantity, visible, None)
+            .await
+    }
+
+    // Updates the order and its note, the note is left as it is when None
+    pub async fn update_with_note(
+        &self,
+        order_id: &str,
+        platinum: i32,
+        quantity: i32,
+        visible: bool,
+        note: Option<&str>,
     ) -> Result<Order, AppError> {
         // Construct any JSON body
-        let body = json!({
+        let mut body = json!({
             "platinum": platinum,
             "quantity": quantity,
             "visible": visible
         });
+        if let Some(note) = note {
+            body["note"] = json!(note);
+        }
         let url = format!("profile/orders/{}", order_id);
         match self
             .client
@@ -215,6 +304,7 @@ impl<'a> OrderModule<'a> {
         let mut ordres_vec = self.get_my_orders().await?;
         let mut ordres: Vec<Order> = ordres_vec.buy_orders;
         ordres.append(&mut ordres_vec.sell_orders);
+        let item = self.client.get_url_name(item);
         // Find Order by name.
         let order = ordres
             .iter()
@@ -294,10 +384,63 @@ impl<'a> OrderModule<'a> {
     // End Actions User Order
 
     // Methods
+    // Live orders of players with at least the status and reputation for every rank and subtype
+    // of the item, plus the sell orders whatever the status to measure the depth of the order book
+    pub async fn get_order_book_by_item(
+        &self,
+        item: &str,
+        status: &OnlineStatus,
+        min_reputation: i64,
+    ) -> Result<(DataFrame, Vec<Order>), AppError> {
+        let orders =
+            self.filter_by_min_reputation(self.get_orders_by_item(item).await?, min_reputation);
+        let sell_orders: Vec<Order> = orders
+            .iter()
+            .filter(|order| order.order_type == OrderType::Sell)
+            .cloned()
+            .collect();
+        let orders = self.filter_by_status(orders, status);
+        if orders.is_empty() {
+            return Ok((DataFrame::new_no_checks(vec![]), sell_orders));
+        }
+        Ok((self.convert_orders_to_dataframe(orders).await?, sell_orders))
+    }
+
+    // Live orders of players that are in game, only for the highest rank of the item
     pub async fn get_ordres_by_item(&self, item: &str) -> Result<DataFrame, AppError> {
+        let orders = self.get_ingame_orders_by_item(item).await?;
+        if orders.len() == 0 {
+            return Ok(DataFrame::new_no_checks(vec![]));
+        }
+        let mod_rank = orders
+            .iter()
+            .max_by(|a, b| a.mod_rank.cmp(&b.mod_rank))
+            .unwrap()
+            .mod_rank;
+
+        let orders: Vec<Order> = orders
+            .into_iter()
+            .filter(|order| order.mod_rank == mod_rank)
+            .collect();
+        Ok(self.convert_orders_to_dataframe(orders).await?)
+    }
+
+    // Buy and sell orders of players that are in game, for every rank of the item
+    pub async fn get_ingame_orders_by_item(&self, item: &str) -> Result<Vec<Order>, AppError> {
+        let orders = self.get_orders_by_item(item).await?;
+        Ok(self.filter_by_status(orders, &OnlineStatus::InGame))
+    }
+
+    // Buy and sell orders of every player, online or not, for every rank of the item
+    pub async fn get_orders_by_item(&self, item: &str) -> Result<Vec<Order>, AppError> {
+        let item = self.client.get_url_name(item);
         let url = format!("items/{}/orders", item);
 
-        let orders = match self.client.get::<Vec<Order>>(&url, Some("orders")).await {
+        let orders = match self
+            .client
+            .get_cached::<Vec<Order>>(&url, Some("orders"))
+            .await
+        {
             Ok(ApiResult::Success(payload, _headers)) => {
                 self.client.debug(
                     &self.debug_id,
@@ -320,30 +463,70 @@ impl<'a> OrderModule<'a> {
             }
         };
 
-        if orders.len() == 0 {
-            return Ok(DataFrame::new_no_checks(vec![]));
-        }
-        let mod_rank = orders
-            .iter()
-            .max_by(|a, b| a.mod_rank.cmp(&b.mod_rank))
-            .unwrap()
-            .mod_rank;
+        Ok(orders)
+    }
+    // End Methods
 
-        let orders: Vec<Order> = orders
+    // Helper
+    // Orders of players at least as available as the status, orders without a user are dropped
+    pub fn filter_by_status(&self, orders: Vec<Order>, status: &OnlineStatus) -> Vec<Order> {
+        let accepted = status.accepted();
+        orders
             .into_iter()
-            .filter(|order| {
-                if let Some(user) = &order.user {
-                    user.status == "ingame" && order.mod_rank == mod_rank
-                } else {
-                    false
-                }
+            .filter(|order| match &order.user {
+                Some(user) => accepted.contains(&user.status.as_str()),
+                None => false,
             })
-            .collect();
-        Ok(self.convert_orders_to_dataframe(orders).await?)
+            .collect()
     }
-    // End Methods
 
-    // Helper
+    pub fn filter_by_min_reputation(&self, orders: Vec<Order>, min_reputation: i64) -> Vec<Order> {
+        orders
+            .into_iter()
+            .filter(|order| match &order.user {
+                Some(user) => user.reputation >= min_reputation as f64,
+                None => false,
+            })
+            .collect()
+    }
+
+    // Sell orders of other players within range plat of the cheapest one
+    pub fn get_sell_depth(
+        &self,
+        sell_orders: &[Order],
+        ingame_name: &str,
+        range: i64,
+    ) -> OrderBookDepth {
+        let competing: Vec<&Order> = sell_orders
+            .iter()
+            .filter(|order| match &order.user {
+                Some(user) => user.ingame_name != ingame_name,
+                None => false,
+            })
+            .collect();
+        let lowest_price = match competing.iter().map(|order| order.platinum).min() {
+            Some(lowest_price) => lowest_price,
+            None => return OrderBookDepth::default(),
+        };
+        let within_range: Vec<&&Order> = competing
+            .iter()
+            .filter(|order| order.platinum <= lowest_price + range)
+            .collect();
+        let mut online_sellers: Vec<&str> = within_range
+            .iter()
+            .filter_map(|order| order.user.as_ref())
+            .filter(|user| user.status != "offline")
+            .map(|user| user.ingame_name.as_str())
+            .collect();
+        online_sellers.sort();
+        online_sellers.dedup();
+        OrderBookDepth {
+            lowest_price,
+            quantity: within_range.iter().map(|order| order.quantity).sum(),
+            orders: within_range.len() as i64,
+            online_sellers: online_sellers.len() as i64,
+        }
+    }
     pub fn convet_order_to_datafream(&self, order: Order) -> Result<DataFrame, AppError> {
         let orders_df = DataFrame::new_no_checks(vec![
             Series::new("id", vec![order.id.clone()]),
@@ -359,6 +542,8 @@ impl<'a> OrderModule<'a> {
             Series::new("platform", vec![order.platform.clone()]),
             Series::new("order_type", vec![order.order_type.as_str()]),
             Series::new("quantity", vec![order.quantity.clone()]),
+            Series::new("mod_rank", vec![order.mod_rank.clone()]),
+            Series::new("subtype", vec![order.subtype.clone()]),
             Series::new("last_update", vec![order.last_update.clone()]),
             Series::new("creation_date", vec![order.creation_date.clone()]),
         ]);
@@ -437,6 +622,20 @@ impl<'a> OrderModule<'a> {
                     .map(|order| order.quantity.clone())
                     .collect::<Vec<_>>(),
             ),
+            Series::new(
+                "mod_rank",
+                orders
+                    .iter()
+                    .map(|order| order.mod_rank.clone())
+                    .collect::<Vec<_>>(),
+            ),
+            Series::new(
+                "subtype",
+                orders
+                    .iter()
+                    .map(|order| order.subtype.clone())
+                    .collect::<Vec<_>>(),
+            ),
             Series::new(
                 "last_update",
                 orders
@@ -0,0 +1,138 @@
+use std::time::{Duration, Instant};
+
+use reqwest::Method;
+
+use crate::{helper, settings::WfmRetrySettings};
+
+// Idempotent requests can be sent again without side effects, e.g. a delete or an update.
+// A create sent twice lists the item twice, so it is only retried when it never reached the server.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EndpointClass {
+    Idempotent,
+    NonIdempotent,
+}
+
+impl EndpointClass {
+    pub fn from_method(method: &Method) -> Self {
+        if *method == Method::POST {
+            EndpointClass::NonIdempotent
+        } else {
+            EndpointClass::Idempotent
+        }
+    }
+}
+
+// Why a request failed, decides if it is sent again
+#[derive(Clone, Debug, PartialEq)]
+pub enum FailureKind {
+    // The connection was never made, nothing was processed
+    Connect,
+    // The request may have been processed, e.g. a timeout or a dropped connection
+    Transport,
+    RateLimited,
+    ServerError,
+    // Warframe Market is down for maintenance, every request fails until it is back
+    Maintenance,
+    // The same request fails the same way again, e.g. a validation error
+    Permanent,
+}
+
+impl FailureKind {
+    pub fn from_status(status: u16) -> Option<Self> {
+        match status {
+            429 => Some(FailureKind::RateLimited),
+            500..=599 => Some(FailureKind::ServerError),
+            200..=299 => None,
+            _ => Some(FailureKind::Permanent),
+        }
+    }
+    // Maintenance is announced with a 503 or an error page mentioning it
+    pub fn is_maintenance(status: u16, content: &str) -> bool {
+        if (200..=299).contains(&status) {
+            return false;
+        }
+        status == 503 || content.to_lowercase().contains("maintenance")
+    }
+    pub fn from_reqwest(error: &reqwest::Error) -> Self {
+        if error.is_connect() {
+            FailureKind::Connect
+        } else {
+            FailureKind::Transport
+        }
+    }
+    // Failures that say nothing about the health of Warframe Market are kept out of the circuit breaker
+    pub fn is_outage(&self) -> bool {
+        !matches!(self, FailureKind::Permanent | FailureKind::Maintenance)
+    }
+}
+
+pub struct RetryPolicy {
+    pub settings: WfmRetrySettings,
+}
+
+impl RetryPolicy {
+    pub fn max_retries(&self, class: &EndpointClass) -> i64 {
+        match class {
+            EndpointClass::Idempotent => self.settings.idempotent_retries,
+            EndpointClass::NonIdempotent => self.settings.non_idempotent_retries,
+        }
+    }
+
+    pub fn should_retry(&self, class: &EndpointClass, kind: &FailureKind, attempt: i64) -> bool {
+        if attempt >= self.max_retries(class) {
+            return false;
+        }
+        match kind {
+            FailureKind::Connect | FailureKind::RateLimited => true,
+            FailureKind::Transport | FailureKind::ServerError => {
+                *class == EndpointClass::Idempotent
+            }
+            FailureKind::Permanent | FailureKind::Maintenance => false,
+        }
+    }
+
+    // Exponential backoff capped at max_delay_ms, with up to half of the delay added as jitter
+    pub fn get_delay(&self, attempt: i64) -> Duration {
+        let base = self.settings.base_delay_ms.max(0) as u64;
+        let delay = base
+            .saturating_mul(2u64.saturating_pow(attempt as u32))
+            .min(self.settings.max_delay_ms.max(0) as u64);
+        Duration::from_millis(delay + helper::get_jitter(delay / 2))
+    }
+}
+
+// Stops sending requests for a while after too many outages in a row
+#[derive(Clone, Debug, Default)]
+pub struct CircuitBreaker {
+    failures: i64,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    // Seconds left until requests are allowed again, None when the circuit is closed
+    pub fn get_remaining(&self) -> Option<u64> {
+        match self.open_until {
+            Some(open_until) if open_until > Instant::now() => {
+                Some((open_until - Instant::now()).as_secs() + 1)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn record_success(&mut self) {
+        self.failures = 0;
+        self.open_until = None;
+    }
+
+    // Returns true when this failure opened the circuit
+    pub fn record_failure(&mut self, settings: &WfmRetrySettings) -> bool {
+        self.failures += 1;
+        if settings.breaker_threshold <= 0 || self.failures < settings.breaker_threshold {
+            return false;
+        }
+        self.failures = 0;
+        self.open_until =
+            Some(Instant::now() + Duration::from_secs(settings.breaker_cooldown.max(0) as u64));
+        true
+    }
+}
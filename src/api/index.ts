@@ -127,11 +127,14 @@ const api = {
       sell: async (id: number, price: number, quantity: number): Promise<StockItemDto> => {
         return await invoke("sell_item_stock", { id, price, quantity }) as StockItemDto;
       },
-      sell_by_name: async (name: string, price: number, quantity: number): Promise<StockItemDto> => {
-        return await invoke("sell_item_stock_by_url", { name, price, quantity }) as StockItemDto;
+      sell_by_name: async (name: string, price: number, quantity: number, rank?: number, sub_type?: string): Promise<StockItemDto> => {
+        return await invoke("sell_item_stock_by_url", { name, price, quantity, rank, subType: sub_type }) as StockItemDto;
       },
       update: async (id: number, item: Partial<StockItemDto>): Promise<StockItemDto> => {
         return await invoke("update_item_stock", { id, miniumPrice: item.minium_price, hidden: item.hidden }) as StockItemDto;
+      },
+      update_reserved: async (id: number, reserved: number): Promise<StockItemDto> => {
+        return await invoke("update_item_stock_reserved", { id, reserved }) as StockItemDto;
       }
     },
     riven: {
@@ -199,15 +202,24 @@ const api = {
     async deleteOrder(id: string): Promise<Wfm.OrderDto> {
       return await invoke("delete_order", { id }) as Wfm.OrderDto;
     },
-    async createOrder(id: string, quantity: number, price: number, rank: number, type: string): Promise<Wfm.OrderDto> {
-      return await invoke("create_order", { id, order_type: type, quantity, price, rank }) as Wfm.OrderDto;
+    async createOrder(id: string, quantity: number, price: number, rank: number, type: string, sub_type?: string, visible?: boolean): Promise<Wfm.OrderDto> {
+      return await invoke("create_order", { id, orderType: type, quantity, price, rank, subType: sub_type, visible }) as Wfm.OrderDto;
     },
-    async updateOrder(id: string, quantity: number, price: number, rank: number, type: string): Promise<Wfm.OrderDto> {
-      return await invoke("update_order", { id, order_type: type, quantity, price, rank }) as Wfm.OrderDto;
+    async updateOrderPrice(id: string, price: number): Promise<Wfm.OrderDto> {
+      return await invoke("update_order_price", { id, price }) as Wfm.OrderDto;
     },
-    async delete_all(): Promise<number> {
-      const rep = await invoke("delete_all_orders") as { count: number };
+    async toggleOrderVisibility(id: string): Promise<Wfm.OrderDto> {
+      return await invoke("toggle_order_visibility", { id }) as Wfm.OrderDto;
+    },
+    async preview_delete_all(): Promise<{ count: number, orders: Wfm.OrderDto[] }> {
+      return await invoke("preview_delete_all_orders") as { count: number, orders: Wfm.OrderDto[] };
+    },
+    async delete_all(ids?: string[]): Promise<number> {
+      const rep = await invoke("delete_all_orders", { ids }) as { count: number };
       return rep.count;
+    },
+    async compare_player_orders(ingame_name: string): Promise<{ ingame_name: string, orders: Wfm.OrderDto[], overlapping: any[] }> {
+      return await invoke("compare_player_orders", { ingameName: ingame_name }) as { ingame_name: string, orders: Wfm.OrderDto[], overlapping: any[] };
     }
   },
 }
@@ -1,4 +1,5 @@
 import { createContext, useContext, useEffect, useState } from "react";
+import i18next from "i18next";
 import { OnTauriEvent, SendNotificationToWindow } from "../utils";
 import { RustError, ScraperState, ScraperMessage } from "../types";
 import { useTranslateContext } from "../hooks";
@@ -28,6 +29,8 @@ export const LiveScraperContextProvider = ({ children }: LiveScraperContextProvi
     OnTauriEvent("LiveScraper:UpdateMessage", (e: ScraperMessage) => {
       if (e.i18n_key == "")
         setMessage(undefined);
+      else if (e.values?.phase)
+        setMessage({ ...e, i18n_key: `live_scraper.${e.i18n_key}`, values: { ...e.values, phase: i18next.t(`progress.live_scraper.item.phases.${e.values.phase}`) } })
       else
         setMessage({ ...e, i18n_key: `live_scraper.${e.i18n_key}` })
     });
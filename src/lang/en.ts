@@ -4,17 +4,42 @@ export const en = {
       starting: "Deleting all orders",
       loading: "Loading current orders",
       progress: "Deleting order {{current}}/{{total}}",
-      completed: "All orders deleted ({{total}})",
+      completed: "{{total}} orders deleted, {{failed}} failed",
+    },
+    onboarding: {
+      settings: "Converting old settings",
+      auth: "Converting old login",
+      database: "Importing old {{type}}",
+      archive: "Archiving old data folder",
+      completed: "Old data imported, the folder was moved to {{archived}}",
+    },
+    price_backfill: {
+      progress: "Downloading price history for {{day}} ({{current}}/{{total}})",
+      completed: "Price history updated, {{added}} new entries",
     },
     live_scraper: {
+      maintenance: "Warframe Market is in maintenance, waiting for it to come back",
       item: {
         starting: "Starting Items",
-        checking: "Checking: <blue>{{name}}</blue> <blue>{{count}}</blue>/<blue>{{total}}</blue>",
+        checking: "Checking {{phase}}: <blue>{{name}}</blue> <blue>{{count}}</blue>/<blue>{{total}}</blue>",
+        phases: {
+          active_orders: "my orders",
+          stock: "stock",
+          opportunities: "new items",
+        },
         deleting_orders: "Deleting Orders: <blue>{{count}}</blue>/<blue>{{total}}</blue>",
+        budget_exhausted: "Request budget exhausted, <blue>{{count}}</blue> items will be checked first next pass",
+        order_cap_reached: "Order for <blue>{{name}}</blue> not created, the <blue>{{category}}</blue> cap of <blue>{{cap}}</blue> orders is reached",
+        reprice_throttled: "Kept <blue>{{name}}</blue> at <blue>{{price}}</plat></blue> instead of <blue>{{new_price}}</plat></blue>, next update in <blue>{{minutes}}</blue> minutes",
+        adaptive_frequency: "Volatile: <blue>{{volatile}}</blue>, Normal: <blue>{{normal}}</blue>, Stable: <blue>{{stable}}</blue>, Not checked this pass: <blue>{{skipped}}</blue>",
         sell: {
           deleting: "Deleting Sell Order: <blue>{{name}}</blue>",
           updating: "Updating Sell Order: <blue>{{name}}</blue> for <blue>{{price}}</plat></blue>",
           creating: "Creating Sell Order: <blue>{{name}}</blue> for <blue>{{price}}</plat></blue>",
+          needs_review: "Sell Order: <blue>{{name}}</blue> unchanged for <blue>{{days}}</blue> days, needs review",
+          override_expired: "Price override for <blue>{{name}}</blue> of <blue>{{price}}</plat></blue> expired",
+          resupply_discount: "Selling <blue>{{name}}</blue> <blue>{{discount}}%</blue> lower for <blue>{{price}}</plat></blue> ahead of its resupply",
+          partial_sale: "Recorded sale of <blue>{{quantity}}</blue>x <blue>{{name}}</blue> for <blue>{{price}}</plat></blue> each",
         },
         buy: {
           deleting: "Deleting Buy Order: <blue>{{name}}</blue>",
@@ -27,8 +52,13 @@ export const en = {
         deleting: "Deleting Riven: <blue>{{name}}</blue>",
         searching: "Searching Riven: <blue>{{name}}</blue>",
         no_offers: "No offers found for: <blue>{{name}}</blue>",
+        suggested: "Similar rivens to <blue>{{name}}</blue> sell for <blue>{{price}}</plat></blue>",
         updating: "Updating Riven: <blue>{{name}}</blue> for <blue>{{price}}</plat></blue>",
         creating: "Creating Riven: <blue>{{name}}</blue> for <blue>{{price}}</plat></blue>",
+        order_cap_reached: "Auction for <blue>{{name}}</blue> not created, the riven cap of <blue>{{cap}}</blue> orders is reached",
+      },
+      order_budget: {
+        skipped: "Some orders were not created because their order cap was reached",
       }
     },
   },
@@ -38,7 +38,7 @@ const OrderItem = ({ item, ordre }: PurchaseNewItemProps) => {
     }
   })
 
-  const sellStockItemEntryMutation = useMutation((data: { url: string, price: number }) => api.stock.item.sell_by_name(data.url, data.price, 1), {
+  const sellStockItemEntryMutation = useMutation((data: { url: string, price: number, rank?: number, sub_type?: string }) => api.stock.item.sell_by_name(data.url, data.price, 1, data.rank, data.sub_type), {
     onSuccess: async (data) => {
       notifications.show({
         title: useTranslateNotifaications("sellStockItem.title"),
@@ -78,7 +78,9 @@ const OrderItem = ({ item, ordre }: PurchaseNewItemProps) => {
       case "sell":
         sellStockItemEntryMutation.mutate({
           url: ordre.item.url_name,
-          price: price || ordre.platinum
+          price: price || ordre.platinum,
+          rank: ordre.mod_rank,
+          sub_type: ordre.subtype
         });
         break;
       default:
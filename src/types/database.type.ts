@@ -27,6 +27,7 @@ export interface StockItemDto extends StockEntryDto {
   sub_type?: string;
   hidden: boolean;
   owned: number;
+  reserved: number;
 }
 
 export interface StockRivenDto extends StockEntryDto {
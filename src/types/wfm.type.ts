@@ -53,6 +53,7 @@ export namespace Wfm {
     tags: string[] | null,
     mod_max_rank: number | null,
     subtypes: string[] | null,
+    ducats?: number | null,
   }
   export interface ItemDetailsDto extends ItemDto {
     tags: string[];
@@ -232,6 +233,7 @@ export namespace Wfm {
     NoOffers = "no_offers",
     NoBuyers = "no_buyers",
     Inactive = "inactive",
+    NeedsReview = "needs_review",
   }
   export interface ChatData {
     id: string;
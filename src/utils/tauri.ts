@@ -22,7 +22,7 @@ export const OnTauriEvent = <T>(event: string, callback: (data: T) => void) => {
 
 // Handle events from rust side
 (async () => {
-  listen("message", (eventIn: { payload: { event: string, data: any } }) => {
+  await listen("message", (eventIn: { payload: { event: string, data: any } }) => {
     console.log("Message", eventIn.payload);
 
     const { event, data } = eventIn.payload;
@@ -34,6 +34,9 @@ export const OnTauriEvent = <T>(event: string, callback: (data: T) => void) => {
   OnTauriEvent<{ type: string, operation: string, data: any }>("Client:Update", ({ type, operation, data }) => {
     listener.fire(`Client:Update:${type}`, { operation, data });
   });
+
+  // Tell the backend we are listening so it can send any events it buffered while we were away
+  await invoke("flush_event_buffer");
 })();
 
 /**